@@ -0,0 +1,47 @@
+use std::hint::black_box;
+use std::io;
+
+use coreutils_rs::base64::core as b64;
+use criterion::{BenchmarkId, Criterion, Throughput, criterion_group, criterion_main};
+
+/// Deterministic pseudo-random bytes (xorshift64) so runs are comparable.
+fn make_test_data(seed: u64, size: usize) -> Vec<u8> {
+    let mut x = seed.max(1);
+    (0..size)
+        .map(|_| {
+            x ^= x << 13;
+            x ^= x >> 7;
+            x ^= x << 17;
+            x as u8
+        })
+        .collect()
+}
+
+fn bench_base64(c: &mut Criterion) {
+    let data = make_test_data(0xba5e, 16 * 1024 * 1024);
+
+    let mut group = c.benchmark_group("base64");
+    group.sample_size(20);
+
+    for wrap in [0usize, 76] {
+        let label = format!("wrap{wrap}");
+        let mut encoded = Vec::new();
+        b64::encode_to_writer(&data, wrap, false, &mut encoded).unwrap();
+
+        group.throughput(Throughput::Bytes(data.len() as u64));
+        group.bench_with_input(BenchmarkId::new("encode", &label), &data, |b, data| {
+            b.iter(|| {
+                b64::encode_to_writer(black_box(data), wrap, false, &mut io::sink()).unwrap()
+            });
+        });
+
+        group.throughput(Throughput::Bytes(encoded.len() as u64));
+        group.bench_with_input(BenchmarkId::new("decode", &label), &encoded, |b, enc| {
+            b.iter(|| b64::decode_to_writer(black_box(enc), false, &mut io::sink()).unwrap());
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_base64);
+criterion_main!(benches);
@@ -0,0 +1,47 @@
+use std::hint::black_box;
+use std::io::Cursor;
+
+use coreutils_rs::hash;
+use criterion::{BenchmarkId, Criterion, Throughput, criterion_group, criterion_main};
+
+/// Deterministic pseudo-random bytes (xorshift64) so runs are comparable.
+fn make_test_data(seed: u64, size: usize) -> Vec<u8> {
+    let mut x = seed.max(1);
+    (0..size)
+        .map(|_| {
+            x ^= x << 13;
+            x ^= x >> 7;
+            x ^= x << 17;
+            x as u8
+        })
+        .collect()
+}
+
+fn bench_posix_cksum(c: &mut Criterion) {
+    let sizes: [(usize, &str); 3] = [
+        (1024, "1KB"),
+        (1024 * 1024, "1MB"),
+        (64 * 1024 * 1024, "64MB"),
+    ];
+
+    let mut group = c.benchmark_group("posix_cksum");
+    for (size, label) in sizes {
+        let data = make_test_data(0x00c0_ffee, size);
+        group.throughput(Throughput::Bytes(size as u64));
+        if size >= 64 * 1024 * 1024 {
+            group.sample_size(10);
+        }
+
+        group.bench_with_input(BenchmarkId::new("slice", label), &data, |b, data| {
+            b.iter(|| hash::posix_cksum(black_box(data)));
+        });
+
+        group.bench_with_input(BenchmarkId::new("streaming", label), &data, |b, data| {
+            b.iter(|| hash::posix_cksum_streaming(Cursor::new(black_box(data))).unwrap());
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_posix_cksum);
+criterion_main!(benches);
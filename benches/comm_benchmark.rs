@@ -0,0 +1,60 @@
+use std::hint::black_box;
+use std::io;
+
+use coreutils_rs::comm::{self, CommConfig};
+use criterion::{Criterion, Throughput, criterion_group, criterion_main};
+
+/// Two deterministic sorted files of `lines` lines each. Keys are drawn from
+/// a seeded xorshift64 so roughly half of each file is shared with the other.
+fn generate_pair(seed: u64, lines: usize) -> (Vec<u8>, Vec<u8>) {
+    let mut x = seed.max(1);
+    let (mut a, mut b) = (Vec::new(), Vec::new());
+    let (mut na, mut nb) = (0, 0);
+    let mut key: u64 = 0;
+    while na < lines || nb < lines {
+        x ^= x << 13;
+        x ^= x >> 7;
+        x ^= x << 17;
+        key += 1 + x % 3;
+        let line = format!("line{key:012}\n");
+        let (in_a, in_b) = match x >> 62 {
+            0 => (true, false),
+            1 => (false, true),
+            _ => (true, true),
+        };
+        if in_a && na < lines {
+            a.extend_from_slice(line.as_bytes());
+            na += 1;
+        }
+        if in_b && nb < lines {
+            b.extend_from_slice(line.as_bytes());
+            nb += 1;
+        }
+    }
+    (a, b)
+}
+
+fn bench_comm(c: &mut Criterion) {
+    let (a, b) = generate_pair(0xc0, 1_000_000);
+    let config = CommConfig::default();
+
+    let mut group = c.benchmark_group("comm");
+    group.sample_size(10);
+    group.throughput(Throughput::Bytes((a.len() + b.len()) as u64));
+    group.bench_function("1M_lines", |bench| {
+        bench.iter(|| {
+            comm::comm(
+                black_box(&a),
+                black_box(&b),
+                &config,
+                "comm",
+                &mut io::sink(),
+            )
+            .unwrap()
+        });
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_comm);
+criterion_main!(benches);
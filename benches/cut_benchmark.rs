@@ -0,0 +1,59 @@
+use std::hint::black_box;
+use std::io;
+
+use coreutils_rs::cut::{self, Range};
+use criterion::{BenchmarkId, Criterion, Throughput, criterion_group, criterion_main};
+
+/// Deterministic tab-separated corpus: `lines` rows of `fields` numeric
+/// fields each, driven by a seeded xorshift64.
+fn generate_table(seed: u64, lines: usize, fields: usize) -> Vec<u8> {
+    let mut x = seed.max(1);
+    let mut data = Vec::new();
+    for _ in 0..lines {
+        for f in 0..fields {
+            if f > 0 {
+                data.push(b'\t');
+            }
+            x ^= x << 13;
+            x ^= x >> 7;
+            x ^= x << 17;
+            data.extend_from_slice((x % 1_000_000).to_string().as_bytes());
+        }
+        data.push(b'\n');
+    }
+    data
+}
+
+fn cut_lines(data: &[u8], ranges: &[Range], complement: bool, out: &mut impl io::Write) {
+    for line in data.split(|&b| b == b'\n') {
+        cut::cut_fields(line, b'\t', ranges, complement, b"\t", false, out).unwrap();
+        out.write_all(b"\n").unwrap();
+    }
+}
+
+fn bench_cut_fields(c: &mut Criterion) {
+    // Same total size (~8MB) in both shapes so the numbers compare.
+    let shapes: [(&str, usize, usize); 2] = [("narrow", 4, 200_000), ("wide", 200, 4_000)];
+    let selections: [(&str, &str, bool); 3] = [
+        ("first", "1", false),
+        ("range", "2-4", false),
+        ("complement", "2", true),
+    ];
+
+    let mut group = c.benchmark_group("cut_fields");
+    for (shape, fields, lines) in shapes {
+        let data = generate_table(0xc07, lines, fields);
+        group.throughput(Throughput::Bytes(data.len() as u64));
+
+        for (sel, spec, complement) in selections {
+            let ranges = cut::parse_ranges(spec).unwrap();
+            group.bench_with_input(BenchmarkId::new(sel, shape), &data, |b, data| {
+                b.iter(|| cut_lines(black_box(data), &ranges, complement, &mut io::sink()));
+            });
+        }
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_cut_fields);
+criterion_main!(benches);
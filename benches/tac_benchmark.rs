@@ -0,0 +1,44 @@
+use std::hint::black_box;
+use std::io;
+
+use coreutils_rs::tac;
+use criterion::{BenchmarkId, Criterion, Throughput, criterion_group, criterion_main};
+
+/// Deterministic line corpus of about `size` bytes: lowercase words with
+/// line lengths between 1 and 120 bytes, driven by a seeded xorshift64.
+fn generate_lines(seed: u64, size: usize) -> Vec<u8> {
+    let mut x = seed.max(1);
+    let mut next = move || {
+        x ^= x << 13;
+        x ^= x >> 7;
+        x ^= x << 17;
+        x
+    };
+    let mut data = Vec::with_capacity(size + 128);
+    while data.len() < size {
+        let len = 1 + (next() % 120) as usize;
+        for _ in 0..len {
+            data.push(b'a' + (next() % 26) as u8);
+        }
+        data.push(b'\n');
+    }
+    data
+}
+
+fn bench_tac_bytes(c: &mut Criterion) {
+    let data = generate_lines(0x7ac, 64 * 1024 * 1024);
+
+    let mut group = c.benchmark_group("tac_bytes");
+    group.sample_size(10);
+    group.throughput(Throughput::Bytes(data.len() as u64));
+
+    for (label, before) in [("after", false), ("before", true)] {
+        group.bench_with_input(BenchmarkId::new(label, "64MB"), &data, |b, data| {
+            b.iter(|| tac::tac_bytes(black_box(data), b'\n', before, &mut io::sink()).unwrap());
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_tac_bytes);
+criterion_main!(benches);
@@ -7,6 +7,8 @@ fn main() {
     println!("cargo:rerun-if-changed=assembly/yes/build.py");
     println!("cargo:rerun-if-changed=assembly/yes/fyes_arm64.s");
 
+    emit_build_info();
+
     let target_os = env::var("CARGO_CFG_TARGET_OS").unwrap_or_default();
     let target_arch = env::var("CARGO_CFG_TARGET_ARCH").unwrap_or_default();
 
@@ -25,6 +27,23 @@ fn main() {
     }
 }
 
+/// Target triple and compiler version for the second line of --version.
+fn emit_build_info() {
+    println!("cargo:rerun-if-env-changed=RUSTC");
+    let target = env::var("TARGET").unwrap_or_else(|_| "unknown".to_string());
+    let rustc = env::var("RUSTC").unwrap_or_else(|_| "rustc".to_string());
+    let rustc_version = Command::new(rustc)
+        .arg("--version")
+        .output()
+        .ok()
+        .filter(|o| o.status.success())
+        .and_then(|o| String::from_utf8(o.stdout).ok())
+        .map(|v| v.trim().to_string())
+        .unwrap_or_else(|| "rustc (unknown version)".to_string());
+    println!("cargo:rustc-env=FCOREUTILS_TARGET={}", target);
+    println!("cargo:rustc-env=FCOREUTILS_RUSTC_VERSION={}", rustc_version);
+}
+
 fn build_x86_64(manifest_dir: &str, out_dir: &str) {
     // Check for required tools.
     let has_nasm = Command::new("nasm")
@@ -1,7 +1,9 @@
-use std::io::{self, Read, Write};
+use std::io::{self, BufRead, Read, Write};
 
 use base64_simd::AsOut;
 
+use crate::common::io::read_retry;
+
 const BASE64_ENGINE: &base64_simd::Base64 = &base64_simd::STANDARD;
 
 /// Number of available CPUs for parallel chunk splitting.
@@ -14,11 +16,36 @@ fn num_cpus() -> usize {
         .unwrap_or(1)
 }
 
+/// Cap on encode worker threads. Past ~8 cores the encoder outruns memory
+/// and storage bandwidth, so more threads only add scheduling overhead.
+const MAX_ENCODE_THREADS: usize = 8;
+
+/// Number of threads for parallel encoding: available CPUs capped at
+/// `MAX_ENCODE_THREADS`, or `FBASE64_THREADS` when set to a positive number
+/// (lets tests force the threaded or the sequential path).
+fn encode_threads() -> usize {
+    if let Some(n) = std::env::var("FBASE64_THREADS")
+        .ok()
+        .and_then(|v| v.parse::<usize>().ok())
+        && n > 0
+    {
+        return n;
+    }
+    num_cpus().min(MAX_ENCODE_THREADS)
+}
+
 /// Chunk size for sequential no-wrap encoding: 8MB aligned to 3 bytes.
 /// Larger chunks reduce function call overhead per iteration while still
 /// keeping peak buffer allocation reasonable (~10.7MB for the output).
 const NOWRAP_CHUNK: usize = 8 * 1024 * 1024 - (8 * 1024 * 1024 % 3);
 
+/// Upper bound on input encoded per pass by `encode_to_writer` (64MB).
+/// Each pass allocates output for its own input only, so peak extra memory
+/// stays ~86MB however large the (mmapped) input is. The real pass size is
+/// rounded down to a multiple of 3 * wrap_col so every pass ends on both a
+/// 3-byte group and a line boundary and passes concatenate seamlessly.
+const ENCODE_PASS_MAX: usize = 64 * 1024 * 1024;
+
 /// Minimum data size for parallel no-wrap encoding (16MB).
 /// For single-file CLI usage (typical benchmark), the Rayon pool is cold
 /// on first use (~200-500µs init). At 10MB, sequential encoding is faster
@@ -57,22 +84,81 @@ fn hint_hugepage(buf: &mut Vec<u8>) {
 
 /// Encode data and write to output with line wrapping.
 /// Uses SIMD encoding with fused encode+wrap for maximum throughput.
-pub fn encode_to_writer(data: &[u8], wrap_col: usize, out: &mut impl Write) -> io::Result<()> {
+/// Input larger than `ENCODE_PASS_MAX` is encoded in bounded passes.
+/// Inputs above the parallel thresholds are split across `encode_threads()`
+/// workers; the output is identical either way. With `crlf` every line
+/// break, including the one after a partial last line, is "\r\n".
+pub fn encode_to_writer(
+    data: &[u8],
+    wrap_col: usize,
+    crlf: bool,
+    out: &mut impl Write,
+) -> io::Result<()> {
+    encode_to_writer_threads(data, wrap_col, crlf, encode_threads(), out)
+}
+
+/// `encode_to_writer` with an explicit worker count (1 = sequential), so
+/// callers can exercise the threaded and the sequential path on the same
+/// input.
+pub fn encode_to_writer_threads(
+    data: &[u8],
+    wrap_col: usize,
+    crlf: bool,
+    threads: usize,
+    out: &mut impl Write,
+) -> io::Result<()> {
     if data.is_empty() {
         return Ok(());
     }
+    // Reject sizes whose output length is not representable (32-bit targets)
+    // up front instead of overflowing in the per-pass arithmetic.
+    encoded_len_checked(data.len(), wrap_col, crlf)?;
+
+    // A single line wider than ENCODE_PASS_MAX gets one pass per line.
+    let pass = 3usize
+        .checked_mul(wrap_col.max(1))
+        .map_or(usize::MAX, |unit| (ENCODE_PASS_MAX / unit).max(1) * unit);
+    for chunk in data.chunks(pass) {
+        if wrap_col == 0 {
+            encode_no_wrap(chunk, threads, out)?;
+        } else {
+            encode_wrapped(chunk, wrap_col, crlf, threads, out)?;
+        }
+    }
+    Ok(())
+}
 
+/// Exact output size of encoding `input_len` bytes wrapped at `wrap_col`
+/// (0 = no wrapping), including newlines, or CRLFs with `crlf`. Fails with
+/// "file too large" instead of overflowing when the size does not fit in
+/// usize.
+pub fn encoded_len_checked(input_len: usize, wrap_col: usize, crlf: bool) -> io::Result<usize> {
+    let too_large = || io::Error::new(io::ErrorKind::FileTooLarge, "file too large");
+    let enc_len = input_len.div_ceil(3).checked_mul(4).ok_or_else(too_large)?;
     if wrap_col == 0 {
-        return encode_no_wrap(data, out);
+        return Ok(enc_len);
     }
+    wrapped_len_checked(enc_len, wrap_col, line_break(crlf).len()).ok_or_else(too_large)
+}
 
-    encode_wrapped(data, wrap_col, out)
+/// Wrapped size of `enc_len` encoded bytes: one `eol_len`-byte line break
+/// per full or partial line. None on overflow.
+#[inline]
+fn wrapped_len_checked(enc_len: usize, wrap_col: usize, eol_len: usize) -> Option<usize> {
+    let lines = enc_len.div_ceil(wrap_col);
+    enc_len.checked_add(lines.checked_mul(eol_len)?)
+}
+
+/// The line break written after each wrapped line.
+#[inline]
+fn line_break(crlf: bool) -> &'static [u8] {
+    if crlf { &CRLF } else { &NEWLINE }
 }
 
 /// Encode without wrapping — parallel SIMD encoding for large data, sequential for small.
-fn encode_no_wrap(data: &[u8], out: &mut impl Write) -> io::Result<()> {
-    if data.len() >= PARALLEL_NOWRAP_THRESHOLD && num_cpus() > 1 {
-        return encode_no_wrap_parallel(data, out);
+fn encode_no_wrap(data: &[u8], threads: usize, out: &mut impl Write) -> io::Result<()> {
+    if data.len() >= PARALLEL_NOWRAP_THRESHOLD && threads > 1 {
+        return encode_no_wrap_parallel(data, threads, out);
     }
 
     // Single-buffer encode: for data that fits in one chunk, encode directly
@@ -96,9 +182,8 @@ fn encode_no_wrap(data: &[u8], out: &mut impl Write) -> io::Result<()> {
 /// Split at 3-byte boundaries, pre-calculate output offsets, encode in parallel.
 /// Each chunk except possibly the last is 3-byte aligned, so no padding in intermediate chunks.
 /// Single allocation + single write_all instead of N allocations + writev.
-fn encode_no_wrap_parallel(data: &[u8], out: &mut impl Write) -> io::Result<()> {
-    let num_threads = num_cpus().max(1);
-    let raw_chunk = data.len() / num_threads;
+fn encode_no_wrap_parallel(data: &[u8], threads: usize, out: &mut impl Write) -> io::Result<()> {
+    let raw_chunk = data.len() / threads.max(1);
     // Align to 3 bytes so each chunk encodes without padding (except the last)
     let chunk_size = ((raw_chunk + 2) / 3) * 3;
 
@@ -148,25 +233,35 @@ fn encode_no_wrap_parallel(data: &[u8], out: &mut impl Write) -> io::Result<()>
 /// - Temp buffer reads hit L1 cache (essentially free bandwidth)
 /// - Output buffer is written once (no double-write from backward memmove)
 /// - Forward access pattern is prefetcher-friendly
-fn encode_wrapped(data: &[u8], wrap_col: usize, out: &mut impl Write) -> io::Result<()> {
+fn encode_wrapped(
+    data: &[u8],
+    wrap_col: usize,
+    crlf: bool,
+    threads: usize,
+    out: &mut impl Write,
+) -> io::Result<()> {
+    if crlf {
+        return encode_wrapped_crlf(data, wrap_col, out);
+    }
+    if data.len() >= PARALLEL_WRAPPED_THRESHOLD && threads > 1 {
+        return encode_wrapped_parallel(data, wrap_col, threads, out);
+    }
+
     let bytes_per_line = wrap_col * 3 / 4;
     if bytes_per_line == 0 {
         return encode_wrapped_small(data, wrap_col, out);
     }
 
-    if data.len() >= PARALLEL_WRAPPED_THRESHOLD && bytes_per_line.is_multiple_of(3) {
-        return encode_wrapped_parallel(data, wrap_col, bytes_per_line, out);
-    }
-
-    if bytes_per_line.is_multiple_of(3) {
+    // Lines hold a whole number of 3-byte groups only when wrap_col is a
+    // multiple of 4; e.g. wrap_col 77 gives bytes_per_line 57, but 57 bytes
+    // encode to 76 characters, not 77.
+    if wrap_col.is_multiple_of(4) {
         return encode_wrapped_expand(data, wrap_col, bytes_per_line, out);
     }
 
-    // Fallback for non-3-aligned bytes_per_line: use fuse_wrap approach
+    // Fallback for other wrap columns: use fuse_wrap approach
+    let out_len = encoded_len_checked(data.len(), wrap_col, false)?;
     let enc_max = BASE64_ENGINE.encoded_length(data.len());
-    let num_full = enc_max / wrap_col;
-    let rem = enc_max % wrap_col;
-    let out_len = num_full * (wrap_col + 1) + if rem > 0 { rem + 1 } else { 0 };
 
     // Encode full data, then fuse with newlines
     let mut enc_buf: Vec<u8> = Vec::with_capacity(enc_max);
@@ -202,9 +297,7 @@ fn encode_wrapped_expand(
         return Ok(());
     }
 
-    let num_full = enc_len / wrap_col;
-    let rem = enc_len % wrap_col;
-    let out_len = num_full * (wrap_col + 1) + if rem > 0 { rem + 1 } else { 0 };
+    let out_len = encoded_len_checked(data.len(), wrap_col, false)?;
 
     // Single allocation: encode into first enc_len bytes, expand backward to out_len.
     // SAFETY: buf[..enc_len] is initialized by BASE64_ENGINE.encode below.
@@ -488,6 +581,9 @@ fn expand_backward(ptr: *mut u8, enc_len: usize, out_len: usize, wrap_col: usize
 /// Static newline byte for IoSlice references in writev calls.
 static NEWLINE: [u8; 1] = [b'\n'];
 
+/// Static CRLF line break for `--crlf` output.
+static CRLF: [u8; 2] = *b"\r\n";
+
 /// Write encoded base64 data with line wrapping using write_vectored (writev).
 /// Builds IoSlice entries pointing at wrap_col-sized segments of the encoded buffer,
 /// interleaved with newline IoSlices, then writes in batches of MAX_WRITEV_IOV.
@@ -576,6 +672,7 @@ fn write_wrapped_iov(encoded: &[u8], wrap_col: usize, out: &mut impl Write) -> i
 fn write_wrapped_iov_streaming(
     encoded: &[u8],
     wrap_col: usize,
+    eol: &[u8],
     col: &mut usize,
     out: &mut impl Write,
 ) -> io::Result<()> {
@@ -592,14 +689,14 @@ fn write_wrapped_iov_streaming(
             iov.push(io::IoSlice::new(&encoded[rp..rp + avail]));
             *col += avail;
             if *col == wrap_col {
-                iov.push(io::IoSlice::new(&NEWLINE));
+                iov.push(io::IoSlice::new(eol));
                 *col = 0;
             }
             break;
         } else {
             // Fill current line and add newline
             iov.push(io::IoSlice::new(&encoded[rp..rp + space]));
-            iov.push(io::IoSlice::new(&NEWLINE));
+            iov.push(io::IoSlice::new(eol));
             rp += space;
             *col = 0;
         }
@@ -616,23 +713,33 @@ fn write_wrapped_iov_streaming(
     Ok(())
 }
 
-/// Parallel wrapped encoding with L1-scatter into a single shared output buffer.
+/// Parallel wrapped encoding into a single shared output buffer.
 /// Pre-calculates each thread's output offset, allocates one buffer for all threads,
 /// and has each thread encode directly into its pre-assigned non-overlapping region.
 /// This saves N-1 buffer allocations and corresponding page faults vs per-thread Vecs,
 /// and uses a single write_all instead of writev.
+///
+/// Chunks are cut at multiples of an input unit that encodes to whole lines,
+/// so every chunk's wrapped output is self-contained: `bytes_per_line` when
+/// wrap_col is a multiple of 4 (L1-scatter), else 3 * wrap_col bytes, which
+/// encode to exactly 4 lines (encode + fuse_wrap).
 fn encode_wrapped_parallel(
     data: &[u8],
     wrap_col: usize,
-    bytes_per_line: usize,
+    threads: usize,
     out: &mut impl Write,
 ) -> io::Result<()> {
-    let num_threads = num_cpus().max(1);
-    let lines_per_chunk = ((data.len() / bytes_per_line) / num_threads).max(1);
-    let chunk_input = lines_per_chunk * bytes_per_line;
+    let aligned = wrap_col.is_multiple_of(4);
+    let (unit, bytes_per_line) = if aligned {
+        (wrap_col / 4 * 3, wrap_col / 4 * 3)
+    } else {
+        (3 * wrap_col, 0)
+    };
+    let units_per_chunk = ((data.len() / unit) / threads.max(1)).max(1);
+    let chunk_input = units_per_chunk * unit;
 
-    // Split input at bytes_per_line boundaries (last chunk may have remainder)
-    let chunks: Vec<&[u8]> = data.chunks(chunk_input.max(bytes_per_line)).collect();
+    // Split input at unit boundaries (last chunk may have remainder)
+    let chunks: Vec<&[u8]> = data.chunks(chunk_input).collect();
 
     // Pre-calculate output offsets for each chunk
     let mut offsets: Vec<usize> = Vec::with_capacity(chunks.len() + 1);
@@ -670,7 +777,14 @@ fn encode_wrapped_parallel(
                 let out_slice = unsafe {
                     std::slice::from_raw_parts_mut((base + out_off) as *mut u8, out_size)
                 };
-                encode_chunk_l1_scatter_into(chunk, out_slice, wrap_col, bytes_per_line);
+                if aligned {
+                    encode_chunk_l1_scatter_into(chunk, out_slice, wrap_col, bytes_per_line);
+                } else {
+                    let mut enc_buf = vec![0u8; BASE64_ENGINE.encoded_length(chunk.len())];
+                    let encoded = BASE64_ENGINE.encode(chunk, enc_buf.as_mut_slice().as_out());
+                    let n = fuse_wrap(encoded, wrap_col, out_slice);
+                    debug_assert_eq!(n, out_size);
+                }
             });
         }
     });
@@ -897,6 +1011,28 @@ fn encode_wrapped_small(data: &[u8], wrap_col: usize, out: &mut impl Write) -> i
     Ok(())
 }
 
+/// Wrapped encoding with CRLF line breaks (`--crlf`): one encode into a
+/// scratch buffer, then each line is copied into an output buffer sized
+/// for the extra CR per line and written in one call.
+fn encode_wrapped_crlf(data: &[u8], wrap_col: usize, out: &mut impl Write) -> io::Result<()> {
+    let enc_len = BASE64_ENGINE.encoded_length(data.len());
+    let out_len = encoded_len_checked(data.len(), wrap_col, true)?;
+    let mut enc_buf: Vec<u8> = Vec::with_capacity(enc_len);
+    #[allow(clippy::uninit_vec)]
+    unsafe {
+        enc_buf.set_len(enc_len);
+    }
+    let encoded = BASE64_ENGINE.encode(data, enc_buf[..enc_len].as_out());
+
+    let mut out_buf: Vec<u8> = Vec::with_capacity(out_len);
+    for line in encoded.chunks(wrap_col) {
+        out_buf.extend_from_slice(line);
+        out_buf.extend_from_slice(&CRLF);
+    }
+    debug_assert_eq!(out_buf.len(), out_len);
+    out.write_all(&out_buf)
+}
+
 /// Decode base64 data and write to output (borrows data, allocates clean buffer).
 /// When `ignore_garbage` is true, strip all non-base64 characters.
 /// When false, only strip whitespace (standard behavior).
@@ -904,7 +1040,13 @@ pub fn decode_to_writer(data: &[u8], ignore_garbage: bool, out: &mut impl Write)
     if data.is_empty() {
         return Ok(());
     }
+    let mut counted = CountingWriter::new(out);
+    let result = decode_slice(data, ignore_garbage, &mut counted);
+    complete_partial(result, data, ignore_garbage, counted)
+}
 
+/// `decode_to_writer` without completing the output on invalid input.
+fn decode_slice(data: &[u8], ignore_garbage: bool, out: &mut impl Write) -> io::Result<()> {
     if ignore_garbage {
         let mut cleaned = strip_non_base64(data);
         return decode_clean_slice(&mut cleaned, out);
@@ -927,7 +1069,7 @@ pub fn decode_to_writer(data: &[u8], ignore_garbage: bool, out: &mut impl Write)
 
 /// Decode base64 from a mutable buffer (MAP_PRIVATE mmap or owned Vec).
 /// Strips whitespace in-place using SIMD memchr2 gap-copy, then decodes
-/// in-place with base64_simd::decode_inplace. Zero additional allocations.
+/// in-place with `decode_clean_slice`. Zero additional allocations.
 ///
 /// For MAP_PRIVATE mmap: the kernel uses COW semantics, so only pages
 /// containing whitespace (newlines) get physically copied (~1.3% for
@@ -945,8 +1087,9 @@ pub fn decode_mmap_inplace(
     // For small data: try line-by-line decode (avoids COW page faults).
     // For large data (>= 512KB): bulk strip+decode is faster than per-line decode.
     if !ignore_garbage && data.len() >= 77 && data.len() < 512 * 1024 {
-        if let Some(result) = try_line_decode(data, out) {
-            return result;
+        let mut counted = CountingWriter::new(out);
+        if let Some(result) = try_line_decode(data, &mut counted) {
+            return complete_partial(result, data, false, counted);
         }
     }
 
@@ -962,16 +1105,14 @@ pub fn decode_mmap_inplace(
                 wp += 1;
             }
         }
-        match BASE64_ENGINE.decode_inplace(&mut data[..wp]) {
-            Ok(decoded) => return out.write_all(decoded),
-            Err(_) => return decode_error(),
-        }
+        return decode_clean_slice(&mut data[..wp], out);
     }
 
     // Fast path: uniform-line fused strip+decode (no intermediate buffer).
     if data.len() >= 77 {
-        if let Some(result) = try_decode_uniform_lines(data, out) {
-            return result;
+        let mut counted = CountingWriter::new(out);
+        if let Some(result) = try_decode_uniform_lines(data, &mut counted) {
+            return complete_partial(result, data, false, counted);
         }
     }
 
@@ -985,10 +1126,7 @@ pub fn decode_mmap_inplace(
             .any(|&b| b == b' ' || b == b'\t' || b == 0x0b || b == 0x0c)
         {
             // Perfectly clean — decode in-place directly
-            match BASE64_ENGINE.decode_inplace(data) {
-                Ok(decoded) => return out.write_all(decoded),
-                Err(_) => return decode_error(),
-            }
+            return decode_clean_slice(data, out);
         }
         // Rare whitespace only — strip in-place
         let ptr = data.as_mut_ptr();
@@ -1001,10 +1139,7 @@ pub fn decode_mmap_inplace(
                 wp += 1;
             }
         }
-        match BASE64_ENGINE.decode_inplace(&mut data[..wp]) {
-            Ok(decoded) => return out.write_all(decoded),
-            Err(_) => return decode_error(),
-        }
+        return decode_clean_slice(&mut data[..wp], out);
     }
 
     // SIMD gap-copy: strip \n and \r in-place using memchr2
@@ -1070,10 +1205,7 @@ pub fn decode_mmap_inplace(
         // For large data, use parallel decode from the cleaned slice
         return decode_borrowed_clean_parallel(out, &data[..wp]);
     }
-    match BASE64_ENGINE.decode_inplace(&mut data[..wp]) {
-        Ok(decoded) => out.write_all(decoded),
-        Err(_) => decode_error(),
-    }
+    decode_clean_slice(&mut data[..wp], out)
 }
 
 /// Decode base64 from an owned Vec (in-place whitespace strip + decode).
@@ -1214,7 +1346,7 @@ fn try_decode_uniform_lines(data: &[u8], out: &mut impl Write) -> Option<io::Res
         }
     }
 
-    let full_lines = if data.len() >= stride {
+    let mut full_lines = if data.len() >= stride {
         let candidate = data.len() / stride;
         if candidate > 0 && data[candidate * stride - 1] != b'\n' {
             return None;
@@ -1223,6 +1355,19 @@ fn try_decode_uniform_lines(data: &[u8], out: &mut impl Write) -> Option<io::Res
     } else {
         0
     };
+    // A padded last line decodes short: handle it with the remainder
+    if full_lines > 0 && full_lines * stride == data.len() && data[data.len() - 2] == b'=' {
+        full_lines -= 1;
+    }
+
+    // Only a few lines were sampled above: every line the structured copy
+    // takes must really end at its stride, or it would feed newlines into
+    // the decoder and reject input with uneven lines in the middle. A padded
+    // line before the last one ends the data early, which only the stripping
+    // decoder reports properly.
+    if (1..=full_lines).any(|i| data[i * stride - 1] != b'\n' || data[i * stride - 2] == b'=') {
+        return None;
+    }
 
     let remainder_start = full_lines * stride;
     let remainder = &data[remainder_start..];
@@ -1231,6 +1376,11 @@ fn try_decode_uniform_lines(data: &[u8], out: &mut impl Write) -> Option<io::Res
     } else {
         remainder
     };
+    // A ragged or multi-line remainder goes through the general path, which
+    // strips it properly and reports bad input
+    if rem_clean.len() % 4 != 0 || memchr::memchr(b'\n', rem_clean).is_some() {
+        return None;
+    }
 
     // Compute exact decoded sizes
     let decoded_per_line = line_len * 3 / 4;
@@ -1243,7 +1393,7 @@ fn try_decode_uniform_lines(data: &[u8], out: &mut impl Write) -> Option<io::Res
             .take(2)
             .filter(|&&b| b == b'=')
             .count();
-        rem_clean.len() * 3 / 4 - pad
+        rem_clean.len() / 4 * 3 - pad
     };
     let total_decoded = full_lines * decoded_per_line + rem_decoded_size;
     let clean_len = full_lines * line_len;
@@ -1327,21 +1477,16 @@ fn try_decode_uniform_lines(data: &[u8], out: &mut impl Write) -> Option<io::Res
                 });
             }
         });
-        let result: Result<(), io::Error> = if err_flag.load(std::sync::atomic::Ordering::Relaxed) {
-            Err(io::Error::new(io::ErrorKind::InvalidData, "invalid input"))
-        } else {
-            Ok(())
-        };
-
-        if let Err(e) = result {
-            return Some(Err(e));
+        // Nothing is written yet: a rejected line may just hold blanks, so
+        // leave the data to the stripping decoder
+        if err_flag.load(std::sync::atomic::Ordering::Relaxed) {
+            return None;
         }
 
         if !rem_clean.is_empty() {
             let rem_out = &mut output[full_lines * decoded_per_line..total_decoded];
-            match BASE64_ENGINE.decode(rem_clean, rem_out.as_out()) {
-                Ok(_) => {}
-                Err(_) => return Some(decode_error()),
+            if BASE64_ENGINE.decode(rem_clean, rem_out.as_out()).is_err() {
+                return None;
             }
         }
 
@@ -1351,6 +1496,9 @@ fn try_decode_uniform_lines(data: &[u8], out: &mut impl Write) -> Option<io::Res
     // Sequential path: fused strip+decode in 256KB sub-chunks.
     // Larger sub-chunks give SIMD decode more data per call, improving throughput.
     // Uses decode_inplace on a small reusable buffer — no large allocations at all.
+    // The lines written before a rejected sub-chunk are whole unpadded quanta,
+    // so the rest of the data (which may just hold blanks) can be finished by
+    // the stripping decoder on its own.
     let lines_per_sub = (256 * 1024 / line_len).max(1);
     let sub_buf_size = lines_per_sub * line_len;
     let mut local_buf: Vec<u8> = Vec::with_capacity(sub_buf_size);
@@ -1383,7 +1531,7 @@ fn try_decode_uniform_lines(data: &[u8], out: &mut impl Write) -> Option<io::Res
                     return Some(Err(e));
                 }
             }
-            Err(_) => return Some(decode_error()),
+            Err(_) => return Some(strip_and_decode(&data[line_idx * stride..], out)),
         }
 
         line_idx += sub_count;
@@ -1397,7 +1545,7 @@ fn try_decode_uniform_lines(data: &[u8], out: &mut impl Write) -> Option<io::Res
                     return Some(Err(e));
                 }
             }
-            Err(_) => return Some(decode_error()),
+            Err(_) => return Some(strip_and_decode(remainder, out)),
         }
     }
 
@@ -1417,7 +1565,12 @@ fn decode_stripping_whitespace(data: &[u8], out: &mut impl Write) -> io::Result<
             return result;
         }
     }
+    strip_and_decode(data, out)
+}
 
+/// The general half of `decode_stripping_whitespace`: strip every kind of
+/// whitespace, then decode what is left.
+fn strip_and_decode(data: &[u8], out: &mut impl Write) -> io::Result<()> {
     // Quick check: skip stripping if no \n or \r in the data.
     // Uses SIMD memchr2 for fast scanning (~10 GB/s) instead of per-byte check.
     if memchr::memchr2(b'\n', b'\r', data).is_none() {
@@ -1543,7 +1696,7 @@ fn try_line_decode(data: &[u8], out: &mut impl Write) -> Option<io::Result<()>>
     }
 
     // Calculate full lines and remainder
-    let full_lines = if data.len() >= line_stride {
+    let mut full_lines = if data.len() >= line_stride {
         // Check how many complete lines fit
         let candidate = data.len() / line_stride;
         // Verify the last full line's newline
@@ -1554,6 +1707,19 @@ fn try_line_decode(data: &[u8], out: &mut impl Write) -> Option<io::Result<()>>
     } else {
         0
     };
+    // A padded last line decodes short: handle it with the remainder
+    if full_lines > 0 && full_lines * line_stride == data.len() && data[data.len() - 2] == b'=' {
+        full_lines -= 1;
+    }
+
+    // Every line decoded in place must really end at its stride, and only
+    // the last may be padded: a short line would leave a gap in the output.
+    // Anything else goes through the stripping decoder.
+    if (1..=full_lines)
+        .any(|i| data[i * line_stride - 1] != b'\n' || data[i * line_stride - 2] == b'=')
+    {
+        return None;
+    }
 
     let remainder_start = full_lines * line_stride;
     let remainder = &data[remainder_start..];
@@ -1572,14 +1738,18 @@ fn try_line_decode(data: &[u8], out: &mut impl Write) -> Option<io::Result<()>>
             0
         } else {
             // Check for padding
-            let pad = rem.iter().rev().take(2).filter(|&&b| b == b'=').count();
-            if rem.len() % 4 != 0 {
+            let pad = trailing_pad(rem);
+            if rem.len() % 4 != 0 || memchr::memchr(b'\n', rem).is_some() {
                 return None; // Invalid remainder
             }
-            rem.len() * 3 / 4 - pad
+            rem.len() / 4 * 3 - pad
         }
     };
 
+    // A line the decoder rejects may still be valid input with blanks in it,
+    // so every decode failure below hands the data to the stripping decoder,
+    // which also reports genuinely bad input. Nothing is written before then.
+    //
     // Single-allocation decode: allocate full decoded output, decode all lines
     // directly into it, then write_all in one syscall. For 10MB base64 (7.5MB decoded),
     // this does 1 write() instead of ~30 chunked writes. The 7.5MB allocation is trivial
@@ -1705,7 +1875,7 @@ fn try_line_decode(data: &[u8], out: &mut impl Write) -> Option<io::Result<()>>
         });
 
         if decode_err.load(std::sync::atomic::Ordering::Relaxed) {
-            return Some(decode_error());
+            return None;
         }
     } else {
         // Sequential decode with 4x unrolling for smaller inputs
@@ -1720,7 +1890,7 @@ fn try_line_decode(data: &[u8], out: &mut impl Write) -> Option<io::Result<()>>
                     .decode(&data[in_base..in_base + line_len], s0.as_out())
                     .is_err()
                 {
-                    return Some(decode_error());
+                    return None;
                 }
 
                 let s1 = std::slice::from_raw_parts_mut(
@@ -1734,7 +1904,7 @@ fn try_line_decode(data: &[u8], out: &mut impl Write) -> Option<io::Result<()>>
                     )
                     .is_err()
                 {
-                    return Some(decode_error());
+                    return None;
                 }
 
                 let s2 = std::slice::from_raw_parts_mut(
@@ -1748,7 +1918,7 @@ fn try_line_decode(data: &[u8], out: &mut impl Write) -> Option<io::Result<()>>
                     )
                     .is_err()
                 {
-                    return Some(decode_error());
+                    return None;
                 }
 
                 let s3 = std::slice::from_raw_parts_mut(
@@ -1762,7 +1932,7 @@ fn try_line_decode(data: &[u8], out: &mut impl Write) -> Option<io::Result<()>>
                     )
                     .is_err()
                 {
-                    return Some(decode_error());
+                    return None;
                 }
             }
             i += 4;
@@ -1776,7 +1946,7 @@ fn try_line_decode(data: &[u8], out: &mut impl Write) -> Option<io::Result<()>>
                 unsafe { std::slice::from_raw_parts_mut(dst.add(out_off), decoded_per_line) };
             match BASE64_ENGINE.decode(&data[in_start..in_end], out_slice.as_out()) {
                 Ok(_) => {}
-                Err(_) => return Some(decode_error()),
+                Err(_) => return None,
             }
             i += 1;
         }
@@ -1794,7 +1964,7 @@ fn try_line_decode(data: &[u8], out: &mut impl Write) -> Option<io::Result<()>>
             unsafe { std::slice::from_raw_parts_mut(dst.add(out_off), remainder_clean_len) };
         match BASE64_ENGINE.decode(rem, out_slice.as_out()) {
             Ok(_) => {}
-            Err(_) => return Some(decode_error()),
+            Err(_) => return None,
         }
     }
 
@@ -1803,13 +1973,66 @@ fn try_line_decode(data: &[u8], out: &mut impl Write) -> Option<io::Result<()>>
 }
 
 /// Decode a clean (no whitespace) buffer in-place with SIMD.
+///
+/// The decoded bytes are packed at the front of `data`, a piece at a time.
+/// Output runs a quarter of the input behind it, so a piece no longer than
+/// a third of what precedes it decodes into the front without touching its
+/// own input (the first piece goes through a scratch buffer). An invalid
+/// piece is therefore still intact, and the output is completed from it
+/// the way GNU does.
 fn decode_clean_slice(data: &mut [u8], out: &mut impl Write) -> io::Result<()> {
     if data.is_empty() {
         return Ok(());
     }
-    match BASE64_ENGINE.decode_inplace(data) {
-        Ok(decoded) => out.write_all(decoded),
-        Err(_) => decode_error(),
+    const FIRST: usize = 4096;
+    let mut scratch = [0u8; FIRST / 4 * 3];
+    let first = data.len().min(FIRST);
+    let last = first == data.len();
+    let mut written = match decode_piece(&data[..first], last, scratch.as_mut_slice()) {
+        Some(n) => n,
+        None => return decode_error_after(data, out),
+    };
+    data[..written].copy_from_slice(&scratch[..written]);
+    let mut read = first;
+
+    while read < data.len() {
+        let rest = data.len() - read;
+        let last = rest <= read / 3;
+        let piece = if last { rest } else { read / 3 / 4 * 4 };
+        let (front, back) = data.split_at_mut(read);
+        match decode_piece(&back[..piece], last, &mut front[written..]) {
+            Some(n) => written += n,
+            None => {
+                out.write_all(&front[..written])?;
+                return decode_error_after(back, out);
+            }
+        }
+        read += piece;
+    }
+    out.write_all(&data[..written])
+}
+
+/// Decode `src` into the start of `dst`, returning the decoded length, or
+/// None if it is invalid. Padding is only accepted at the end of the `last`
+/// piece, as it would be in a one-shot decode of the whole slice.
+#[inline]
+fn decode_piece(src: &[u8], last: bool, dst: &mut [u8]) -> Option<usize> {
+    if !last && src.last() == Some(&b'=') {
+        return None;
+    }
+    BASE64_ENGINE
+        .decode(src, dst.as_out())
+        .ok()
+        .map(|d| d.len())
+}
+
+/// Padding characters base64_simd counts at the end of `data`: a final "="
+/// or "==", but not a "=" before some other last byte.
+fn trailing_pad(data: &[u8]) -> usize {
+    match data {
+        [.., b'=', b'='] => 2,
+        [.., b'='] => 1,
+        _ => 0,
     }
 }
 
@@ -1820,6 +2043,84 @@ fn decode_error() -> io::Result<()> {
     Err(io::Error::new(io::ErrorKind::InvalidData, "invalid input"))
 }
 
+// ── Partial output on invalid input ─────────────────────────────────────
+// GNU base64 decodes as it reads, so by the time it reports "invalid
+// input" it has written everything before the offending byte: each
+// complete quantum, and from the quantum the byte cuts short, whatever its
+// leading valid characters yield. The decoders here validate a slice at a
+// time; when one fails, the output is completed to the same point.
+
+/// What GNU writes for `data`, which starts on a quantum boundary, before
+/// it fails: decoding stops at the first byte that is not an alphabet
+/// character where one is due. A padded quantum ("xx==" or "xxx=") ends
+/// cleanly and decoding carries on after it, as GNU's does. Whitespace is
+/// skipped, or with `ignore_garbage` anything outside the alphabet.
+fn decode_prefix(data: &[u8], ignore_garbage: bool) -> Vec<u8> {
+    let value = |b: u8| match b {
+        b'A'..=b'Z' => Some(b - b'A'),
+        b'a'..=b'z' => Some(b - b'a' + 26),
+        b'0'..=b'9' => Some(b - b'0' + 52),
+        b'+' => Some(62),
+        b'/' => Some(63),
+        _ => None,
+    };
+    let mut chars = data.iter().copied().filter(|&b| {
+        if ignore_garbage {
+            is_base64_char(b)
+        } else {
+            NOT_WHITESPACE[b as usize]
+        }
+    });
+    let mut out = Vec::with_capacity(data.len() / 4 * 3);
+    while let (Some(v0), Some(v1)) = (chars.next().and_then(value), chars.next().and_then(value)) {
+        out.push(v0 << 2 | v1 >> 4);
+        let c2 = chars.next();
+        if c2 == Some(b'=') {
+            if chars.next() == Some(b'=') {
+                continue;
+            }
+            break;
+        }
+        let Some(v2) = c2.and_then(value) else { break };
+        out.push(v1 << 4 | v2 >> 2);
+        let c3 = chars.next();
+        if c3 == Some(b'=') {
+            continue;
+        }
+        let Some(v3) = c3.and_then(value) else { break };
+        out.push(v2 << 6 | v3);
+    }
+    out
+}
+
+/// Fail on clean `rest`, the input after everything already written, once
+/// its partial output is out.
+#[cold]
+#[inline(never)]
+fn decode_error_after(rest: &[u8], out: &mut impl Write) -> io::Result<()> {
+    out.write_all(&decode_prefix(rest, false))?;
+    decode_error()
+}
+
+/// `result` of decoding all of `data` through `counted`: if the input was
+/// invalid, write whatever of GNU's partial output the decoder did not.
+fn complete_partial<W: Write>(
+    result: io::Result<()>,
+    data: &[u8],
+    ignore_garbage: bool,
+    counted: CountingWriter<'_, W>,
+) -> io::Result<()> {
+    match result {
+        Err(e) if e.kind() == io::ErrorKind::InvalidData => {
+            let prefix = decode_prefix(data, ignore_garbage);
+            let written = (counted.bytes as usize).min(prefix.len());
+            counted.inner.write_all(&prefix[written..])?;
+            Err(e)
+        }
+        result => result,
+    }
+}
+
 /// Decode clean base64 data (no whitespace) from a borrowed slice.
 fn decode_borrowed_clean(out: &mut impl Write, data: &[u8]) -> io::Result<()> {
     if data.is_empty() {
@@ -1832,8 +2133,7 @@ fn decode_borrowed_clean(out: &mut impl Write, data: &[u8]) -> io::Result<()> {
     }
     // Pre-allocate exact output size to avoid decode_to_vec's reallocation.
     // Decoded size = data.len() * 3 / 4 minus padding.
-    let pad = data.iter().rev().take(2).filter(|&&b| b == b'=').count();
-    let decoded_size = data.len() * 3 / 4 - pad;
+    let decoded_size = (data.len() * 3 / 4).saturating_sub(trailing_pad(data));
     let mut buf: Vec<u8> = Vec::with_capacity(decoded_size);
     #[allow(clippy::uninit_vec)]
     unsafe {
@@ -1844,7 +2144,7 @@ fn decode_borrowed_clean(out: &mut impl Write, data: &[u8]) -> io::Result<()> {
             out.write_all(decoded)?;
             Ok(())
         }
-        Err(_) => decode_error(),
+        Err(_) => decode_error_after(data, out),
     }
 }
 
@@ -1865,8 +2165,7 @@ fn decode_borrowed_clean_parallel(out: &mut impl Write, data: &[u8]) -> io::Resu
     let mut total_decoded = 0usize;
     for (i, chunk) in chunks.iter().enumerate() {
         let decoded_size = if i == chunks.len() - 1 {
-            let pad = chunk.iter().rev().take(2).filter(|&&b| b == b'=').count();
-            chunk.len() * 3 / 4 - pad
+            (chunk.len() * 3 / 4).saturating_sub(trailing_pad(chunk))
         } else {
             chunk.len() * 3 / 4
         };
@@ -1907,12 +2206,552 @@ fn decode_borrowed_clean_parallel(out: &mut impl Write, data: &[u8]) -> io::Resu
     });
 
     if err_flag.load(std::sync::atomic::Ordering::Relaxed) {
-        return Err(io::Error::new(io::ErrorKind::InvalidData, "invalid input"));
+        return decode_error_after(data, out);
     }
 
     out.write_all(&output_buf[..total_decoded])
 }
 
+/// Decode concatenated, independently padded base64 streams (`--multi`).
+/// Padding mid-stream terminates the current block instead of being an error:
+/// the bytes decoded so far are emitted and a fresh decode state begins with
+/// the following data. Whitespace between blobs is stripped as usual.
+pub fn decode_multi_to_writer(
+    data: &[u8],
+    ignore_garbage: bool,
+    out: &mut impl Write,
+) -> io::Result<()> {
+    decode_multi_inner(data, ignore_garbage, out, &mut DecodeStats::default())
+}
+
+/// `decode_multi_to_writer`, adding the input and the bytes stripped from
+/// it to `stats`.
+fn decode_multi_inner(
+    data: &[u8],
+    ignore_garbage: bool,
+    out: &mut impl Write,
+    stats: &mut DecodeStats,
+) -> io::Result<()> {
+    if data.is_empty() {
+        return Ok(());
+    }
+    let mut clean = if ignore_garbage {
+        strip_non_base64(data)
+    } else {
+        let mut v = data.to_vec();
+        strip_whitespace_inplace(&mut v);
+        v
+    };
+    stats.input_bytes += data.len() as u64;
+    stats.stripped_whitespace += (data.len() - clean.len()) as u64;
+    let mut decoder = MultiDecoder::default();
+    decoder.feed(&mut clean, false, out)?;
+    decoder.finish(out)
+}
+
+/// Stream-decode concatenated padded base64 streams (`--multi`) from a reader.
+/// Same semantics as `decode_multi_to_writer`, but processes the input in
+/// bounded chunks so pipes of arbitrary size can be decoded.
+pub fn decode_multi_stream(
+    reader: &mut impl Read,
+    ignore_garbage: bool,
+    writer: &mut impl Write,
+) -> io::Result<()> {
+    decode_multi_stream_inner(reader, ignore_garbage, writer, &mut DecodeStats::default())
+}
+
+/// `decode_multi_stream`, adding the input read and the bytes stripped
+/// from it to `stats`.
+fn decode_multi_stream_inner(
+    reader: &mut impl Read,
+    ignore_garbage: bool,
+    writer: &mut impl Write,
+    stats: &mut DecodeStats,
+) -> io::Result<()> {
+    const READ_CHUNK: usize = 4 * 1024 * 1024;
+    let mut buf = vec![0u8; READ_CHUNK];
+    let mut decoder = MultiDecoder::default();
+    loop {
+        let n = read_full(reader, &mut buf)?;
+        if n == 0 {
+            break;
+        }
+        let mut clean = if ignore_garbage {
+            strip_non_base64(&buf[..n])
+        } else {
+            let mut v = buf[..n].to_vec();
+            strip_whitespace_inplace(&mut v);
+            v
+        };
+        stats.input_bytes += n as u64;
+        stats.stripped_whitespace += (n - clean.len()) as u64;
+        decoder.feed(&mut clean, n < READ_CHUNK, writer)?;
+        if n < READ_CHUNK {
+            break;
+        }
+    }
+    decoder.finish(writer)
+}
+
+/// Incremental decode state for `--multi`. Holds clean (whitespace-free)
+/// base64 that could not be decoded yet: an incomplete quadruplet, or a
+/// padding run that may continue in the next chunk.
+#[derive(Default)]
+struct MultiDecoder {
+    pending: Vec<u8>,
+}
+
+impl MultiDecoder {
+    /// Append clean base64 and decode every complete block. A block ends after
+    /// the run of `=` that follows its first padding character. When `last` is
+    /// false, a padding run touching the end of the data is kept pending since
+    /// it may continue in the next chunk.
+    fn feed(&mut self, clean: &mut [u8], last: bool, out: &mut impl Write) -> io::Result<()> {
+        let data: &mut [u8] = if self.pending.is_empty() {
+            clean
+        } else {
+            self.pending.extend_from_slice(clean);
+            &mut self.pending
+        };
+        let mut start = 0;
+        loop {
+            let rest = &data[start..];
+            match memchr::memchr(b'=', rest) {
+                Some(p) => {
+                    let mut end = p;
+                    while end < rest.len() && rest[end] == b'=' {
+                        end += 1;
+                    }
+                    if end == rest.len() && !last {
+                        // Padding run may continue: decode the aligned body only.
+                        let body = (p / 4) * 4;
+                        decode_clean_slice(&mut data[start..start + body], out)?;
+                        start += body;
+                        break;
+                    }
+                    decode_clean_slice(&mut data[start..start + end], out)?;
+                    start += end;
+                }
+                None => {
+                    let body = (rest.len() / 4) * 4;
+                    decode_clean_slice(&mut data[start..start + body], out)?;
+                    start += body;
+                    break;
+                }
+            }
+        }
+        let leftover = data[start..].to_vec();
+        self.pending = leftover;
+        Ok(())
+    }
+
+    /// Decode whatever remains at end of input (strictly, like the default mode).
+    fn finish(&mut self, out: &mut impl Write) -> io::Result<()> {
+        let mut rest = std::mem::take(&mut self.pending);
+        decode_clean_slice(&mut rest, out)
+    }
+}
+
+// ── Byte counters ──────────────────────────────────────────────────────
+// The `_counted` variants behave like the plain functions and also return
+// how many bytes went in and came out, for pipeline accounting and
+// `--verbose`. Output is counted as it is written and input as it is read
+// or stripped, so the figures are exact whichever decode path runs.
+
+/// What an encode consumed and produced.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct EncodeStats {
+    pub input_bytes: u64,
+    /// Bytes written, line breaks included.
+    pub output_bytes: u64,
+    /// Line breaks written; 0 without wrapping.
+    pub lines: u64,
+}
+
+impl EncodeStats {
+    /// Stats of encoding `input_bytes` wrapped at `wrap_col` (0 = no
+    /// wrapping) into `output_bytes`.
+    fn new(input_bytes: u64, output_bytes: u64, wrap_col: usize) -> Self {
+        let lines = if wrap_col == 0 {
+            0
+        } else {
+            (input_bytes.div_ceil(3) * 4).div_ceil(wrap_col as u64)
+        };
+        EncodeStats {
+            input_bytes,
+            output_bytes,
+            lines,
+        }
+    }
+}
+
+/// What a decode consumed and produced.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DecodeStats {
+    pub input_bytes: u64,
+    pub output_bytes: u64,
+    /// Input bytes dropped before decoding: whitespace, or with
+    /// `ignore_garbage` every byte outside the alphabet.
+    pub stripped_whitespace: u64,
+}
+
+/// A writer that counts the bytes written through it.
+struct CountingWriter<'a, W> {
+    inner: &'a mut W,
+    bytes: u64,
+}
+
+impl<'a, W: Write> CountingWriter<'a, W> {
+    fn new(inner: &'a mut W) -> Self {
+        CountingWriter { inner, bytes: 0 }
+    }
+}
+
+impl<W: Write> Write for CountingWriter<'_, W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let n = self.inner.write(buf)?;
+        self.bytes += n as u64;
+        Ok(n)
+    }
+
+    fn write_vectored(&mut self, bufs: &[io::IoSlice<'_>]) -> io::Result<usize> {
+        let n = self.inner.write_vectored(bufs)?;
+        self.bytes += n as u64;
+        Ok(n)
+    }
+
+    fn write_all(&mut self, buf: &[u8]) -> io::Result<()> {
+        self.inner.write_all(buf)?;
+        self.bytes += buf.len() as u64;
+        Ok(())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// A reader that counts the bytes read through it.
+struct CountingReader<'a, R> {
+    inner: &'a mut R,
+    bytes: u64,
+}
+
+impl<R: Read> Read for CountingReader<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.bytes += n as u64;
+        Ok(n)
+    }
+}
+
+/// `encode_to_writer`, returning what was encoded.
+pub fn encode_to_writer_counted(
+    data: &[u8],
+    wrap_col: usize,
+    crlf: bool,
+    out: &mut impl Write,
+) -> io::Result<EncodeStats> {
+    let mut counted = CountingWriter::new(out);
+    encode_to_writer(data, wrap_col, crlf, &mut counted)?;
+    Ok(EncodeStats::new(data.len() as u64, counted.bytes, wrap_col))
+}
+
+/// `encode_stream`, returning what was encoded.
+pub fn encode_stream_counted(
+    reader: &mut impl Read,
+    wrap_col: usize,
+    crlf: bool,
+    writer: &mut impl Write,
+) -> io::Result<EncodeStats> {
+    let mut input = CountingReader {
+        inner: reader,
+        bytes: 0,
+    };
+    let mut counted = CountingWriter::new(writer);
+    encode_stream(&mut input, wrap_col, crlf, &mut counted)?;
+    Ok(EncodeStats::new(input.bytes, counted.bytes, wrap_col))
+}
+
+/// `decode_to_writer`, returning what was decoded. The decode paths strip
+/// whitespace in several ways, so the stripped bytes are counted in a
+/// separate pass over `data`.
+pub fn decode_to_writer_counted(
+    data: &[u8],
+    ignore_garbage: bool,
+    out: &mut impl Write,
+) -> io::Result<DecodeStats> {
+    let mut counted = CountingWriter::new(out);
+    decode_to_writer(data, ignore_garbage, &mut counted)?;
+    let stripped = if ignore_garbage {
+        data.iter().filter(|&&b| !is_base64_char(b)).count()
+    } else {
+        data.iter()
+            .filter(|&&b| !NOT_WHITESPACE[b as usize])
+            .count()
+    };
+    Ok(DecodeStats {
+        input_bytes: data.len() as u64,
+        output_bytes: counted.bytes,
+        stripped_whitespace: stripped as u64,
+    })
+}
+
+/// `decode_owned`, returning what was decoded.
+pub fn decode_owned_counted(
+    data: &mut Vec<u8>,
+    ignore_garbage: bool,
+    out: &mut impl Write,
+) -> io::Result<DecodeStats> {
+    let input_len = data.len();
+    if ignore_garbage {
+        data.retain(|&b| is_base64_char(b));
+    } else {
+        strip_whitespace_inplace(data);
+    }
+    let mut counted = CountingWriter::new(out);
+    decode_clean_slice(data, &mut counted)?;
+    Ok(DecodeStats {
+        input_bytes: input_len as u64,
+        output_bytes: counted.bytes,
+        stripped_whitespace: (input_len - data.len()) as u64,
+    })
+}
+
+/// `decode_stream`, returning what was decoded.
+pub fn decode_stream_counted(
+    reader: &mut impl Read,
+    ignore_garbage: bool,
+    writer: &mut impl Write,
+) -> io::Result<DecodeStats> {
+    let mut stats = DecodeStats::default();
+    let mut counted = CountingWriter::new(writer);
+    decode_stream_inner(reader, ignore_garbage, &mut counted, &mut stats)?;
+    stats.output_bytes = counted.bytes;
+    Ok(stats)
+}
+
+/// `decode_multi_to_writer`, returning what was decoded.
+pub fn decode_multi_to_writer_counted(
+    data: &[u8],
+    ignore_garbage: bool,
+    out: &mut impl Write,
+) -> io::Result<DecodeStats> {
+    let mut stats = DecodeStats::default();
+    let mut counted = CountingWriter::new(out);
+    decode_multi_inner(data, ignore_garbage, &mut counted, &mut stats)?;
+    stats.output_bytes = counted.bytes;
+    Ok(stats)
+}
+
+/// `decode_multi_stream`, returning what was decoded.
+pub fn decode_multi_stream_counted(
+    reader: &mut impl Read,
+    ignore_garbage: bool,
+    writer: &mut impl Write,
+) -> io::Result<DecodeStats> {
+    let mut stats = DecodeStats::default();
+    let mut counted = CountingWriter::new(writer);
+    decode_multi_stream_inner(reader, ignore_garbage, &mut counted, &mut stats)?;
+    stats.output_bytes = counted.bytes;
+    Ok(stats)
+}
+
+// ── PEM armor (`--pem`) ────────────────────────────────────────────────
+// RFC 7468 textual encoding: base64 wrapped at 64 columns between
+// "-----BEGIN LABEL-----" and "-----END LABEL-----" lines. Decoding strips
+// the armor lines as the input streams through `PemReader` and feeds the
+// rest to the `--multi` decoder, since every block is padded on its own.
+
+/// Line width mandated by RFC 7468 for the base64 text of a PEM block.
+pub const PEM_WRAP: usize = 64;
+
+/// Check a label given for `--pem`: non-empty, printable ASCII, and not
+/// starting or ending with '-' or a space (which would blur the armor).
+pub fn validate_pem_label(label: &str) -> io::Result<()> {
+    let bytes = label.as_bytes();
+    let valid = !bytes.is_empty()
+        && bytes.iter().all(|&b| (b' '..=b'~').contains(&b))
+        && ![bytes[0], bytes[bytes.len() - 1]]
+            .iter()
+            .any(|&b| b == b'-' || b == b' ');
+    if valid {
+        Ok(())
+    } else {
+        Err(invalid_pem(format!("invalid PEM label '{}'", label)))
+    }
+}
+
+fn invalid_pem(msg: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+fn write_pem_line(kind: &str, label: &str, out: &mut impl Write) -> io::Result<()> {
+    out.write_all(format!("-----{} {}-----\n", kind, label).as_bytes())
+}
+
+/// Encode `data` as one PEM block labelled `label`.
+pub fn encode_pem_to_writer(data: &[u8], label: &str, out: &mut impl Write) -> io::Result<()> {
+    validate_pem_label(label)?;
+    write_pem_line("BEGIN", label, out)?;
+    encode_to_writer(data, PEM_WRAP, false, out)?;
+    write_pem_line("END", label, out)
+}
+
+/// Stream-encode a reader as one PEM block labelled `label`.
+pub fn encode_pem_stream(
+    reader: &mut impl Read,
+    label: &str,
+    out: &mut impl Write,
+) -> io::Result<()> {
+    validate_pem_label(label)?;
+    write_pem_line("BEGIN", label, out)?;
+    encode_stream(reader, PEM_WRAP, false, out)?;
+    write_pem_line("END", label, out)
+}
+
+/// Decode one or more concatenated PEM blocks. See `PemReader` for which
+/// armor is accepted.
+pub fn decode_pem_to_writer(
+    data: &[u8],
+    label: Option<&str>,
+    ignore_garbage: bool,
+    out: &mut impl Write,
+) -> io::Result<()> {
+    decode_pem_stream(data, label, ignore_garbage, out)
+}
+
+/// Stream-decode one or more concatenated PEM blocks from a reader.
+pub fn decode_pem_stream(
+    reader: impl BufRead,
+    label: Option<&str>,
+    ignore_garbage: bool,
+    out: &mut impl Write,
+) -> io::Result<()> {
+    let mut reader = PemReader::new(reader, label);
+    decode_multi_stream(&mut reader, ignore_garbage, out)
+}
+
+/// A reader yielding its input without PEM armor lines.
+///
+/// Every "-----BEGIN X-----" must be closed by "-----END X-----" with the
+/// same label before the next block starts or the input ends. With an
+/// expected label, every block must carry it and at least one block must be
+/// present; without one, input with no armor at all passes through as plain
+/// base64. Violations surface as `InvalidData` read errors.
+pub struct PemReader<R> {
+    inner: R,
+    expected: Option<String>,
+    /// Label of the block being read, if inside one.
+    open: Option<String>,
+    blocks: usize,
+    line: Vec<u8>,
+    pos: usize,
+}
+
+impl<R: BufRead> PemReader<R> {
+    pub fn new(inner: R, expected: Option<&str>) -> Self {
+        PemReader {
+            inner,
+            expected: expected.map(str::to_string),
+            open: None,
+            blocks: 0,
+            line: Vec::new(),
+            pos: 0,
+        }
+    }
+
+    /// Load the next line that is not armor into `line`. Returns false at
+    /// end of input once the armor has been checked.
+    fn next_data_line(&mut self) -> io::Result<bool> {
+        loop {
+            self.line.clear();
+            self.pos = 0;
+            if self.inner.read_until(b'\n', &mut self.line)? == 0 {
+                return self.finish().map(|_| false);
+            }
+            let trimmed = self.line.trim_ascii();
+            let Some(armor) = trimmed
+                .strip_prefix(b"-----")
+                .and_then(|t| t.strip_suffix(b"-----"))
+            else {
+                return Ok(true);
+            };
+            let armor = String::from_utf8_lossy(armor).into_owned();
+            if let Some(label) = armor.strip_prefix("BEGIN ") {
+                self.begin(label)?;
+            } else if let Some(label) = armor.strip_prefix("END ") {
+                self.end(label)?;
+            } else {
+                return Err(invalid_pem(format!(
+                    "invalid PEM armor line '-----{}-----'",
+                    armor
+                )));
+            }
+        }
+    }
+
+    fn begin(&mut self, label: &str) -> io::Result<()> {
+        if let Some(open) = &self.open {
+            return Err(invalid_pem(format!("missing PEM END line for '{}'", open)));
+        }
+        if let Some(expected) = &self.expected
+            && label != expected
+        {
+            return Err(invalid_pem(format!(
+                "PEM BEGIN label '{}' does not match expected '{}'",
+                label, expected
+            )));
+        }
+        self.open = Some(label.to_string());
+        Ok(())
+    }
+
+    fn end(&mut self, label: &str) -> io::Result<()> {
+        match self.open.take() {
+            Some(open) if open == label => {
+                self.blocks += 1;
+                Ok(())
+            }
+            Some(open) => Err(invalid_pem(format!(
+                "PEM END label '{}' does not match BEGIN label '{}'",
+                label, open
+            ))),
+            None => Err(invalid_pem(format!(
+                "missing PEM BEGIN line for '{}'",
+                label
+            ))),
+        }
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        if let Some(open) = &self.open {
+            return Err(invalid_pem(format!("missing PEM END line for '{}'", open)));
+        }
+        if self.blocks == 0
+            && let Some(expected) = &self.expected
+        {
+            return Err(invalid_pem(format!(
+                "missing PEM BEGIN line for '{}'",
+                expected
+            )));
+        }
+        Ok(())
+    }
+}
+
+impl<R: BufRead> Read for PemReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.pos == self.line.len() && !self.next_data_line()? {
+            return Ok(0);
+        }
+        let n = buf.len().min(self.line.len() - self.pos);
+        buf[..n].copy_from_slice(&self.line[self.pos..self.pos + n]);
+        self.pos += n;
+        Ok(n)
+    }
+}
+
 /// Strip non-base64 characters (for -i / --ignore-garbage).
 fn strip_non_base64(data: &[u8]) -> Vec<u8> {
     data.iter()
@@ -1929,15 +2768,17 @@ fn is_base64_char(b: u8) -> bool {
 
 /// Stream-encode from a reader to a writer. Used for stdin processing.
 /// Dispatches to specialized paths for wrap_col=0 (no wrap) and wrap_col>0 (wrapping).
+/// With `crlf` line breaks are "\r\n", as in `encode_to_writer`.
 pub fn encode_stream(
     reader: &mut impl Read,
     wrap_col: usize,
+    crlf: bool,
     writer: &mut impl Write,
 ) -> io::Result<()> {
     if wrap_col == 0 {
         return encode_stream_nowrap(reader, writer);
     }
-    encode_stream_wrapped(reader, wrap_col, writer)
+    encode_stream_wrapped(reader, wrap_col, line_break(crlf), writer)
 }
 
 /// Streaming encode with NO line wrapping — optimized fast path.
@@ -1980,17 +2821,19 @@ fn encode_stream_nowrap(reader: &mut impl Read, writer: &mut impl Write) -> io::
 /// then writes it in a single write() call. This eliminates the overhead of
 /// many writev() syscalls (one per ~512 lines via IoSlice).
 ///
-/// For non-aligned wrap columns, falls back to the IoSlice/writev approach.
+/// For non-aligned wrap columns and CRLF line breaks, falls back to the
+/// IoSlice/writev approach.
 fn encode_stream_wrapped(
     reader: &mut impl Read,
     wrap_col: usize,
+    eol: &[u8],
     writer: &mut impl Write,
 ) -> io::Result<()> {
     let bytes_per_line = wrap_col * 3 / 4;
     // For the common case (76-col wrapping, bytes_per_line=57 which is divisible by 3),
     // align the read buffer to bytes_per_line boundaries so each chunk produces
     // complete lines with no column carry-over between chunks.
-    if bytes_per_line > 0 && bytes_per_line.is_multiple_of(3) {
+    if bytes_per_line > 0 && wrap_col.is_multiple_of(4) && eol == NEWLINE {
         return encode_stream_wrapped_fused(reader, wrap_col, bytes_per_line, writer);
     }
 
@@ -2018,11 +2861,11 @@ fn encode_stream_wrapped(
         let enc_len = BASE64_ENGINE.encoded_length(n);
         let encoded = BASE64_ENGINE.encode(&buf[..n], encode_buf[..enc_len].as_out());
 
-        write_wrapped_iov_streaming(encoded, wrap_col, &mut col, writer)?;
+        write_wrapped_iov_streaming(encoded, wrap_col, eol, &mut col, writer)?;
     }
 
     if col > 0 {
-        writer.write_all(b"\n")?;
+        writer.write_all(eol)?;
     }
 
     Ok(())
@@ -2150,6 +2993,17 @@ pub fn decode_stream(
     reader: &mut impl Read,
     ignore_garbage: bool,
     writer: &mut impl Write,
+) -> io::Result<()> {
+    decode_stream_inner(reader, ignore_garbage, writer, &mut DecodeStats::default())
+}
+
+/// `decode_stream`, adding the input read and the bytes stripped from it
+/// to `stats`.
+fn decode_stream_inner(
+    reader: &mut impl Read,
+    ignore_garbage: bool,
+    writer: &mut impl Write,
+    stats: &mut DecodeStats,
 ) -> io::Result<()> {
     const READ_CHUNK: usize = 32 * 1024 * 1024;
     // SAFETY: buf bytes are written by read_full before being processed.
@@ -2249,6 +3103,9 @@ pub fn decode_stream(
             }
         };
 
+        // Carried bytes were clean already, so all that went is from this read
+        stats.input_bytes += n as u64;
+        stats.stripped_whitespace += (total_raw - clean_len) as u64;
         carry_len = 0;
         let is_last = n < READ_CHUNK;
 
@@ -2329,18 +3186,16 @@ fn write_all_vectored_slow(
 #[inline]
 fn read_full(reader: &mut impl Read, buf: &mut [u8]) -> io::Result<usize> {
     // Fast path: first read() usually fills the entire buffer for regular files
-    let n = reader.read(buf)?;
+    let n = read_retry(reader, buf)?;
     if n == buf.len() || n == 0 {
         return Ok(n);
     }
     // Slow path: partial read — retry to fill buffer (pipes, slow devices)
     let mut total = n;
     while total < buf.len() {
-        match reader.read(&mut buf[total..]) {
-            Ok(0) => break,
-            Ok(n) => total += n,
-            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
-            Err(e) => return Err(e),
+        match read_retry(reader, &mut buf[total..])? {
+            0 => break,
+            n => total += n,
         }
     }
     Ok(total)
@@ -2,7 +2,7 @@ use crate::base64::core::*;
 
 fn encode_bytes(input: &[u8], wrap: usize) -> Vec<u8> {
     let mut out = Vec::new();
-    encode_to_writer(input, wrap, &mut out).unwrap();
+    encode_to_writer(input, wrap, false, &mut out).unwrap();
     out
 }
 
@@ -163,6 +163,57 @@ fn test_encode_wrap_just_over_boundary() {
     assert_eq!(lines[0].len(), 76);
 }
 
+#[test]
+fn test_decode_padded_full_last_line() {
+    // At wrap 4 the padded last group fills a whole line; the per-line
+    // decoders must not count it as 3 decoded bytes
+    let input: Vec<u8> = (0..=255).cycle().take(100).collect();
+    let encoded = encode_bytes(&input, 4);
+    assert!(encoded.ends_with(b"==\n"));
+    assert_eq!(decode_bytes(&encoded, false).unwrap(), input);
+    let mut out = Vec::new();
+    decode_mmap_inplace(&mut encoded.clone(), false, &mut out).unwrap();
+    assert_eq!(out, input);
+}
+
+#[test]
+fn test_decode_uneven_middle_lines() {
+    // The per-line decoders only sample a few lines: shortening line 5 and
+    // lengthening line 6 keeps every sampled newline in place, so the file
+    // looks uniform. Both sizes must still decode like any other whitespace.
+    for size in [600, 1 << 20] {
+        let input: Vec<u8> = (0..=255).cycle().take(size).collect();
+        let flat = encode_bytes(&input, 0);
+        let mut lines: Vec<&[u8]> = vec![&flat[304..376], &flat[376..456]];
+        lines.splice(0..0, flat[..304].chunks(76));
+        lines.extend(flat[456..].chunks(76));
+        let encoded = lines.join(&b'\n');
+        assert_eq!(encoded[6 * 77 - 1], b'\n');
+        assert_eq!(
+            decode_bytes(&encoded, false).unwrap(),
+            input,
+            "size {}",
+            size
+        );
+    }
+}
+
+#[test]
+fn test_decode_blank_and_padded_lines() {
+    // Lines the per-line decoders would take at face value: blanks inside a
+    // line are plain whitespace, and padding before the last line is data
+    // after padding, output the way GNU decodes it but still an error
+    let blanks = format!("{:84}\n", "");
+    assert_eq!(decode_bytes(blanks.as_bytes(), false).unwrap(), b"");
+    let spaced = format!("{}\n", "QUFB ".repeat(20).trim_end());
+    assert_eq!(decode_bytes(spaced.as_bytes(), false).unwrap(), [b'A'; 60]);
+
+    let padded = format!("QQ==\n{}", "QUFB\n".repeat(20));
+    let mut out = Vec::new();
+    assert!(decode_to_writer(padded.as_bytes(), false, &mut out).is_err());
+    assert_eq!(out, [b'A'; 61]);
+}
+
 #[test]
 fn test_decode_no_padding() {
     // "YWJj" decodes to "abc" (no padding needed, 4 base64 chars → 3 bytes)
@@ -195,7 +246,7 @@ fn test_encode_stream_no_wrap() {
     let input = b"Hello World";
     let mut reader = &input[..];
     let mut output = Vec::new();
-    encode_stream(&mut reader, 0, &mut output).unwrap();
+    encode_stream(&mut reader, 0, false, &mut output).unwrap();
     // -w 0: no trailing newline
     assert_eq!(output, b"SGVsbG8gV29ybGQ=");
 }
@@ -207,7 +258,7 @@ fn test_encode_stream_basic() {
     let input = b"Hello World";
     let mut reader = &input[..];
     let mut output = Vec::new();
-    encode_stream(&mut reader, 76, &mut output).unwrap();
+    encode_stream(&mut reader, 76, false, &mut output).unwrap();
     assert_eq!(output, b"SGVsbG8gV29ybGQ=\n");
 }
 
@@ -262,3 +313,729 @@ fn test_decode_owned_whitespace_variants() {
         b"abcdef"
     );
 }
+
+// ===== MULTI-STREAM DECODE TESTS =====
+
+/// Deterministic pseudo-random payload (xorshift) so failures are reproducible.
+fn payload(seed: u64, len: usize) -> Vec<u8> {
+    let mut x = seed.max(1);
+    (0..len)
+        .map(|_| {
+            x ^= x << 13;
+            x ^= x >> 7;
+            x ^= x << 17;
+            x as u8
+        })
+        .collect()
+}
+
+fn multi_bytes(input: &[u8]) -> Result<Vec<u8>, std::io::Error> {
+    let mut out = Vec::new();
+    decode_multi_to_writer(input, false, &mut out)?;
+    Ok(out)
+}
+
+fn multi_stream_bytes(input: &[u8]) -> Result<Vec<u8>, std::io::Error> {
+    let mut reader = input;
+    let mut out = Vec::new();
+    decode_multi_stream(&mut reader, false, &mut out)?;
+    Ok(out)
+}
+
+#[test]
+fn test_decode_strict_rejects_data_after_padding() {
+    assert!(decode_bytes(b"YQ==YWI=", false).is_err());
+}
+
+#[test]
+fn test_decode_multi_simple() {
+    assert_eq!(multi_bytes(b"YQ==YWI=YWJj").unwrap(), b"aababc");
+    assert_eq!(multi_stream_bytes(b"YQ==YWI=YWJj").unwrap(), b"aababc");
+}
+
+#[test]
+fn test_decode_multi_roundtrip_concatenated() {
+    let payloads = [payload(1, 1001), payload(2, 29), payload(3, 4097)];
+    let expected: Vec<u8> = payloads.concat();
+    for sep in [&b""[..], b"\n", b"\r\n", b" \n\t"] {
+        for wrap in [0, 76] {
+            let mut input = Vec::new();
+            for p in &payloads {
+                let mut enc = encode_bytes(p, wrap);
+                if wrap > 0 {
+                    enc.pop(); // drop the trailing newline; `sep` decides what follows
+                }
+                input.extend_from_slice(&enc);
+                input.extend_from_slice(sep);
+            }
+            assert_eq!(multi_bytes(&input).unwrap(), expected);
+            assert_eq!(multi_stream_bytes(&input).unwrap(), expected);
+        }
+    }
+}
+
+#[test]
+fn test_decode_multi_ignore_garbage() {
+    let mut out = Vec::new();
+    decode_multi_to_writer(b"YQ==!!YWI=", true, &mut out).unwrap();
+    assert_eq!(out, b"aab");
+}
+
+#[test]
+fn test_decode_multi_invalid_block() {
+    assert!(multi_bytes(b"YQ==Y").is_err());
+    assert!(multi_stream_bytes(b"YQ==Y").is_err());
+}
+
+// ===== PEM ARMOR TESTS =====
+
+fn pem_encode(input: &[u8], label: &str) -> Vec<u8> {
+    let mut out = Vec::new();
+    encode_pem_to_writer(input, label, &mut out).unwrap();
+    out
+}
+
+fn pem_decode(input: &[u8], label: Option<&str>) -> Result<Vec<u8>, std::io::Error> {
+    let mut out = Vec::new();
+    decode_pem_to_writer(input, label, false, &mut out)?;
+    Ok(out)
+}
+
+#[test]
+fn test_pem_encode_layout() {
+    let data = payload(7, 100);
+    let pem = pem_encode(&data, "CERTIFICATE");
+    let text = String::from_utf8(pem.clone()).unwrap();
+    let lines: Vec<&str> = text.lines().collect();
+    assert_eq!(lines[0], "-----BEGIN CERTIFICATE-----");
+    assert_eq!(*lines.last().unwrap(), "-----END CERTIFICATE-----");
+    assert_eq!(lines[1].len(), PEM_WRAP);
+    assert!(text.ends_with("-----\n"));
+
+    let mut streamed = Vec::new();
+    encode_pem_stream(&mut &data[..], "CERTIFICATE", &mut streamed).unwrap();
+    assert_eq!(streamed, pem);
+
+    assert_eq!(
+        pem_encode(b"", "X"),
+        b"-----BEGIN X-----\n-----END X-----\n"
+    );
+}
+
+#[test]
+fn test_pem_roundtrip_single_and_concatenated() {
+    let payloads = [payload(1, 1001), payload(2, 29), payload(3, 4097)];
+    for p in &payloads {
+        let pem = pem_encode(p, "DATA");
+        assert_eq!(pem_decode(&pem, None).unwrap(), *p);
+        assert_eq!(pem_decode(&pem, Some("DATA")).unwrap(), *p);
+    }
+
+    let mut input = Vec::new();
+    for p in &payloads {
+        input.extend_from_slice(&pem_encode(p, "DATA"));
+        input.extend_from_slice(b"\r\n");
+    }
+    let expected = payloads.concat();
+    assert_eq!(pem_decode(&input, None).unwrap(), expected);
+    assert_eq!(pem_decode(&input, Some("DATA")).unwrap(), expected);
+
+    // Different labels are fine as long as each block is closed by its own
+    let mut mixed = pem_encode(&payloads[0], "A");
+    mixed.extend_from_slice(&pem_encode(&payloads[1], "B"));
+    assert_eq!(
+        pem_decode(&mixed, None).unwrap(),
+        [&payloads[0][..], &payloads[1][..]].concat()
+    );
+    assert!(pem_decode(&mixed, Some("A")).is_err());
+}
+
+#[test]
+fn test_pem_label_mismatch() {
+    let err = pem_decode(b"-----BEGIN A-----\nYQ==\n-----END B-----\n", None).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+    assert_eq!(
+        err.to_string(),
+        "PEM END label 'B' does not match BEGIN label 'A'"
+    );
+
+    let err = pem_decode(&pem_encode(b"a", "A"), Some("B")).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "PEM BEGIN label 'A' does not match expected 'B'"
+    );
+}
+
+#[test]
+fn test_pem_missing_armor() {
+    // Unpaired armor is an error whether or not a label was given
+    for label in [None, Some("A")] {
+        assert!(pem_decode(b"-----BEGIN A-----\nYQ==\n", label).is_err());
+        assert!(pem_decode(b"YQ==\n-----END A-----\n", label).is_err());
+        assert!(pem_decode(b"-----BEGIN A-----\n-----BEGIN A-----\n", label).is_err());
+    }
+    // Bare base64 passes through without a label, but not with one
+    assert_eq!(pem_decode(b"YQ==\n", None).unwrap(), b"a");
+    assert_eq!(
+        pem_decode(b"YQ==\n", Some("A")).unwrap_err().to_string(),
+        "missing PEM BEGIN line for 'A'"
+    );
+}
+
+#[test]
+fn test_pem_label_validation() {
+    assert!(validate_pem_label("RSA PRIVATE KEY").is_ok());
+    for bad in ["", "-X", "X-", " X", "A\nB"] {
+        assert!(validate_pem_label(bad).is_err(), "{:?}", bad);
+    }
+}
+
+// ===== BOUNDED-PASS ENCODE TESTS =====
+
+/// Wrap single-shot encoder output the way `base64 -w` does.
+fn wrap_reference(encoded: &[u8], wrap: usize) -> Vec<u8> {
+    let mut out = Vec::with_capacity(encoded.len() + encoded.len() / wrap + 1);
+    for line in encoded.chunks(wrap) {
+        out.extend_from_slice(line);
+        out.push(b'\n');
+    }
+    out
+}
+
+#[test]
+fn test_encode_100mb_matches_single_shot() {
+    // Spans one full 64MB pass plus a partial one.
+    let mut data = payload(0x5eed, 1024 * 1024).repeat(100);
+    data.extend_from_slice(b"tail!!!");
+    let single_shot = base64_simd::STANDARD.encode_to_string(&data).into_bytes();
+
+    assert!(encode_bytes(&data, 0) == single_shot);
+    assert!(encode_bytes(&data, 76) == wrap_reference(&single_shot, 76));
+}
+
+#[test]
+fn test_encoded_len_checked() {
+    assert_eq!(encoded_len_checked(0, 0, false).unwrap(), 0);
+    assert_eq!(encoded_len_checked(5, 0, false).unwrap(), 8);
+    assert_eq!(encoded_len_checked(5, 76, false).unwrap(), 9);
+    assert_eq!(encoded_len_checked(57, 76, false).unwrap(), 77);
+    assert_eq!(encoded_len_checked(58, 76, false).unwrap(), 82);
+    assert_eq!(encoded_len_checked(3, 1, false).unwrap(), 8);
+    for (len, wrap) in [(0, 0), (1, 3), (100, 7), (1000, 76), (999, 64)] {
+        assert_eq!(
+            encoded_len_checked(len, wrap, false).unwrap(),
+            encode_bytes(&vec![0u8; len], wrap).len()
+        );
+    }
+
+    let err = encoded_len_checked(usize::MAX, 0, false).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::FileTooLarge);
+    assert_eq!(err.to_string(), "file too large");
+    // Fits unwrapped, overflows once newlines are added
+    let max_unwrapped = usize::MAX / 4 * 3;
+    assert!(encoded_len_checked(max_unwrapped, 0, false).is_ok());
+    assert!(encoded_len_checked(max_unwrapped, 1, false).is_err());
+
+    // --crlf spends one more byte per line, partial last line included
+    assert_eq!(encoded_len_checked(5, 76, true).unwrap(), 10);
+    assert_eq!(encoded_len_checked(58, 76, true).unwrap(), 84);
+    assert_eq!(encoded_len_checked(5, 0, true).unwrap(), 8);
+}
+
+/// A 3.5GB mmap is addressable on 32-bit, but its 4.7GB encoding is not.
+#[cfg(target_pointer_width = "32")]
+#[test]
+fn test_encoded_len_checked_32bit_huge_file() {
+    let len: usize = 3_500_000_000;
+    let err = encoded_len_checked(len, 76, false).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::FileTooLarge);
+}
+
+fn encode_bytes_threads(input: &[u8], wrap: usize, threads: usize) -> Vec<u8> {
+    let mut out = Vec::new();
+    encode_to_writer_threads(input, wrap, false, threads, &mut out).unwrap();
+    out
+}
+
+#[test]
+fn test_encode_threaded_matches_sequential() {
+    // 64MB plus a ragged tail: crosses the pass boundary for every wrap
+    // width and leaves a partial last line and padding.
+    let mut data = payload(0xb64, 1024 * 1024).repeat(64);
+    data.extend_from_slice(b"ragged");
+    let single_shot = base64_simd::STANDARD.encode_to_string(&data).into_bytes();
+
+    for wrap in [0, 5, 76, 77] {
+        let expected = if wrap == 0 {
+            single_shot.clone()
+        } else {
+            wrap_reference(&single_shot, wrap)
+        };
+        let sequential = encode_bytes_threads(&data, wrap, 1);
+        assert!(
+            sequential == expected,
+            "sequential output differs, wrap {}",
+            wrap
+        );
+        for threads in [3, 8] {
+            assert!(
+                encode_bytes_threads(&data, wrap, threads) == expected,
+                "{} threads differ from sequential, wrap {}",
+                threads,
+                wrap
+            );
+        }
+    }
+}
+
+#[test]
+fn test_encode_wrap_not_multiple_of_four() {
+    // wrap_col * 3 / 4 is a multiple of 3 for 5 and 77, but their lines do
+    // not hold whole 3-byte groups; every path must still fill each line.
+    let data = payload(0x77, 5000);
+    let single_shot = base64_simd::STANDARD.encode_to_string(&data).into_bytes();
+    for wrap in [5, 77, 9, 13] {
+        let expected = wrap_reference(&single_shot, wrap);
+        assert!(
+            encode_bytes(&data, wrap) == expected,
+            "mmap path, wrap {}",
+            wrap
+        );
+        let mut output = Vec::new();
+        encode_stream(&mut &data[..], wrap, false, &mut output).unwrap();
+        assert!(output == expected, "stream path, wrap {}", wrap);
+    }
+}
+
+#[test]
+fn test_encode_crlf_lines() {
+    // No input, one partial line, one full line, and many lines with a
+    // partial last one; sequential, threaded and streaming paths
+    for len in [0, 10, 57, 5000] {
+        let data = payload(len as u64 + 1, len);
+        let lf = encode_bytes(&data, 76);
+        let mut outputs = Vec::new();
+        for threads in [1, 3] {
+            let mut out = Vec::new();
+            encode_to_writer_threads(&data, 76, true, threads, &mut out).unwrap();
+            outputs.push(out);
+        }
+        let mut streamed = Vec::new();
+        encode_stream(&mut &data[..], 76, true, &mut streamed).unwrap();
+        outputs.push(streamed);
+
+        for out in outputs {
+            assert_eq!(out.len(), encoded_len_checked(len, 76, true).unwrap());
+            let lines: Vec<&[u8]> = out.split_inclusive(|&b| b == b'\n').collect();
+            assert_eq!(lines.len(), len.div_ceil(57), "len {}", len);
+            for line in &lines {
+                assert!(line.ends_with(b"\r\n"), "len {}", len);
+                assert!(!line[..line.len() - 2].contains(&b'\r'));
+            }
+            // Same text as LF output once the CRs are gone
+            let stripped: Vec<u8> = out.iter().copied().filter(|&b| b != b'\r').collect();
+            assert!(stripped == lf, "len {}", len);
+            assert!(decode_bytes(&out, false).unwrap() == data, "len {}", len);
+        }
+    }
+}
+
+#[test]
+fn test_encode_counted_across_wraps() {
+    // 100 bytes encode to 136 characters
+    let data = payload(7, 100);
+    for (wrap, lines) in [(0, 0), (76, 2), (68, 2), (20, 7), (4, 34), (200, 1)] {
+        let plain = encode_bytes(&data, wrap);
+        let expected = EncodeStats {
+            input_bytes: 100,
+            output_bytes: 136 + lines,
+            lines,
+        };
+        let mut out = Vec::new();
+        let stats = encode_to_writer_counted(&data, wrap, false, &mut out).unwrap();
+        assert_eq!(stats, expected, "wrap {}", wrap);
+        assert!(out == plain, "wrap {}", wrap);
+
+        let mut out = Vec::new();
+        let stats = encode_stream_counted(&mut &data[..], wrap, false, &mut out).unwrap();
+        assert_eq!(stats, expected, "stream, wrap {}", wrap);
+        assert!(out == plain, "stream, wrap {}", wrap);
+    }
+    // CRLF line breaks count two bytes each
+    let mut out = Vec::new();
+    let stats = encode_to_writer_counted(&data, 76, true, &mut out).unwrap();
+    assert_eq!((stats.output_bytes, stats.lines), (140, 2));
+    let stats = encode_to_writer_counted(b"", 76, false, &mut Vec::new()).unwrap();
+    assert_eq!(stats, EncodeStats::default());
+}
+
+#[test]
+fn test_decode_counted_strips_whitespace() {
+    let data = payload(11, 100);
+    for (wrap, lines) in [(0, 0), (76, 2), (20, 7), (4, 34)] {
+        let encoded = encode_bytes(&data, wrap);
+        let expected = DecodeStats {
+            input_bytes: 136 + lines,
+            output_bytes: 100,
+            stripped_whitespace: lines,
+        };
+        let mut out = Vec::new();
+        let stats = decode_to_writer_counted(&encoded, false, &mut out).unwrap();
+        assert_eq!(stats, expected, "wrap {}", wrap);
+        assert!(out == data, "wrap {}", wrap);
+
+        let mut out = Vec::new();
+        let stats = decode_owned_counted(&mut encoded.clone(), false, &mut out).unwrap();
+        assert_eq!(stats, expected, "owned, wrap {}", wrap);
+        assert!(out == data, "owned, wrap {}", wrap);
+
+        for multi in [false, true] {
+            let mut out = Vec::new();
+            let stats = if multi {
+                decode_multi_stream_counted(&mut &encoded[..], false, &mut out).unwrap()
+            } else {
+                decode_stream_counted(&mut &encoded[..], false, &mut out).unwrap()
+            };
+            assert_eq!(stats, expected, "stream, wrap {} multi {}", wrap, multi);
+            assert!(out == data, "stream, wrap {} multi {}", wrap, multi);
+        }
+        let stats = decode_multi_to_writer_counted(&encoded, false, &mut Vec::new()).unwrap();
+        assert_eq!(stats, expected, "multi, wrap {}", wrap);
+    }
+
+    // Spaces and tabs count as whitespace; with -i so does any garbage
+    let mut out = Vec::new();
+    let stats = decode_to_writer_counted(b"aGVs\tbG8g d29y\r\nbGQ=\n", false, &mut out).unwrap();
+    assert_eq!(out, b"hello world");
+    assert_eq!(
+        stats,
+        DecodeStats {
+            input_bytes: 21,
+            output_bytes: 11,
+            stripped_whitespace: 5,
+        }
+    );
+    let stats = decode_to_writer_counted(b"aGVs*bG8=!\n", true, &mut Vec::new()).unwrap();
+    assert_eq!((stats.output_bytes, stats.stripped_whitespace), (5, 3));
+    let stats = decode_stream_counted(&mut &b"aGVs*bG8=!\n"[..], true, &mut Vec::new()).unwrap();
+    assert_eq!((stats.output_bytes, stats.stripped_whitespace), (5, 3));
+}
+
+// ===== INVALID INPUT TESTS =====
+
+/// Decode `input` through every non-multi entry point, checking that each
+/// fails with "invalid input" after writing the same partial output.
+fn decode_invalid_everywhere(input: &[u8]) -> Vec<u8> {
+    let mut slice = Vec::new();
+    let err = decode_to_writer(input, false, &mut slice).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+    assert_eq!(err.to_string(), "invalid input");
+
+    let mut owned = Vec::new();
+    assert!(decode_owned(&mut input.to_vec(), false, &mut owned).is_err());
+    assert!(owned == slice, "owned");
+
+    let mut mapped = Vec::new();
+    assert!(decode_mmap_inplace(&mut input.to_vec(), false, &mut mapped).is_err());
+    assert!(mapped == slice, "in place");
+
+    let mut streamed = Vec::new();
+    assert!(decode_stream(&mut &input[..], false, &mut streamed).is_err());
+    assert!(streamed == slice, "stream");
+    slice
+}
+
+#[test]
+fn test_invalid_input_partial_output() {
+    // What GNU base64 -d writes before "invalid input": every complete
+    // quantum, plus what the valid leading pairs of a cut-short one yield
+    for (input, partial) in [
+        (&b"QUJD\0"[..], &b"ABC"[..]),
+        (b"QUJDRA\0", b"ABCD"),
+        (b"QUJDRA==\0", b"ABCD"),
+        (b"QUJDRA=\0", b"ABCD"),
+        (b"QUJDRE\0VG", b"ABCD"),
+        (b"QUJDREV\0", b"ABCDE"),
+        (b"QUJDR", b"ABC"),
+        (b"QUJDRA", b"ABCD"),
+        (b"QU\nJD\n\x01", b"ABC"),
+        (b"QUJD\nRE\nVG\n\0", b"ABCDEF"),
+        (b"QUI=QUJD\0", b"ABABC"),
+        (b"QUJD!RA==", b"ABC"),
+        (b"\0QUJD", b""),
+        (b"=", b""),
+    ] {
+        assert_eq!(
+            decode_invalid_everywhere(input),
+            partial,
+            "input {:?}",
+            String::from_utf8_lossy(input)
+        );
+    }
+}
+
+#[test]
+fn test_invalid_input_after_large_prefix() {
+    // Sizes span the per-line, uniform-line, bulk and parallel decoders;
+    // a padded prefix checks that decoding carries on after padding
+    for len in [100, 5_000, 5_001, 600_000, 2_000_000] {
+        let data = payload(len as u64, len);
+        for wrap in [76, 0] {
+            let mut input = encode_bytes(&data, wrap);
+            input.extend_from_slice(b"QUJDRA\0REVG\n");
+            let mut expected = data.clone();
+            expected.extend_from_slice(b"ABCD");
+            let partial = decode_invalid_everywhere(&input);
+            assert!(partial == expected, "len {} wrap {}", len, wrap);
+
+            let mut multi = Vec::new();
+            assert!(decode_multi_to_writer(&input, false, &mut multi).is_err());
+            assert!(multi == expected, "multi, len {} wrap {}", len, wrap);
+        }
+    }
+}
+
+#[test]
+fn test_decode_stray_padding_does_not_panic() {
+    // A "=" that is not at the very end is not padding to the sizing code
+    for input in [&b"QUJDRA=\0"[..], b"QUJD=A==", b"QQ=A"] {
+        assert!(decode_bytes(input, false).is_err());
+    }
+}
+
+mod integration {
+    use std::io::Write;
+    use std::process::{Command, Stdio};
+
+    fn bin_path(name: &str) -> std::path::PathBuf {
+        let mut path = std::path::PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        path.push("target");
+        if cfg!(debug_assertions) {
+            path.push("debug");
+        } else {
+            path.push("release");
+        }
+        path.push(name);
+        path
+    }
+
+    fn run_fbase64(input: &[u8], args: &[&str]) -> (Vec<u8>, i32) {
+        let mut child = Command::new(bin_path("fbase64"))
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .expect("failed to spawn fbase64");
+        // Feed stdin from a thread so large outputs cannot deadlock the pipe
+        let mut stdin = child.stdin.take().unwrap();
+        let input = input.to_vec();
+        let feeder = std::thread::spawn(move || stdin.write_all(&input));
+        let output = child.wait_with_output().expect("failed to wait");
+        feeder.join().unwrap().unwrap();
+        (output.stdout, output.status.code().unwrap_or(1))
+    }
+
+    #[test]
+    fn test_ignore_garbage_ignored_when_encoding() {
+        let data: Vec<u8> = (0..=255u8).collect();
+        for (with, without) in [
+            (&["-i"][..], &[][..]),
+            (&["--ignore-garbage"], &[]),
+            (&["-i", "-w", "0"], &["-w", "0"]),
+        ] {
+            let (expected, _) = run_fbase64(&data, without);
+            let (out, code) = run_fbase64(&data, with);
+            assert_eq!(code, 0, "{:?}", with);
+            assert_eq!(out, expected, "{:?}", with);
+            let (back, _) = run_fbase64(&out, &["-d"]);
+            assert_eq!(back, data, "{:?}", with);
+        }
+    }
+
+    #[test]
+    fn test_wrap_ignored_when_decoding() {
+        let data: Vec<u8> = (0..=255u8).cycle().take(1000).collect();
+        let (encoded, _) = run_fbase64(&data, &[]);
+        for args in [
+            &["-d", "-w", "10"][..],
+            &["--decode", "--wrap=4"],
+            &["-dw1"],
+        ] {
+            let (out, code) = run_fbase64(&encoded, args);
+            assert_eq!(code, 0, "{:?}", args);
+            assert_eq!(out, data, "{:?}", args);
+        }
+    }
+
+    #[test]
+    fn test_verbose_summary() {
+        let run = |input: &[u8], args: &[&str]| {
+            let mut child = Command::new(bin_path("fbase64"))
+                .args(args)
+                .stdin(Stdio::piped())
+                .stdout(Stdio::piped())
+                .stderr(Stdio::piped())
+                .spawn()
+                .unwrap();
+            child.stdin.take().unwrap().write_all(input).unwrap();
+            let output = child.wait_with_output().unwrap();
+            assert!(output.status.success());
+            (output.stdout, String::from_utf8(output.stderr).unwrap())
+        };
+        let data = vec![b'x'; 100];
+        let (encoded, stderr) = run(&data, &["--verbose", "-w", "20"]);
+        assert_eq!(
+            stderr,
+            "base64: read 100 bytes, wrote 143 bytes in 7 lines\n"
+        );
+        let (decoded, stderr) = run(&encoded, &["-d", "--verbose"]);
+        assert_eq!(decoded, data);
+        assert_eq!(
+            stderr,
+            "base64: read 143 bytes, stripped 7 whitespace bytes, wrote 100 bytes\n"
+        );
+        let (_, stderr) = run(&data, &[]);
+        assert_eq!(stderr, "");
+    }
+
+    #[test]
+    fn test_crlf_cli_roundtrip() {
+        let data: Vec<u8> = (0..=255u8).cycle().take(1000).collect();
+        let (out, code) = run_fbase64(&data, &["--crlf"]);
+        assert_eq!(code, 0);
+        let text = String::from_utf8(out.clone()).unwrap();
+        assert!(text.split_inclusive('\n').all(|l| l.ends_with("\r\n")));
+        assert_eq!(text.split_inclusive('\n').next().unwrap().len(), 78);
+
+        let (back, code) = run_fbase64(&out, &["-d"]);
+        assert_eq!(code, 0);
+        assert_eq!(back, data);
+        // Ignored when decoding
+        let (back, code) = run_fbase64(&out, &["-d", "--crlf"]);
+        assert_eq!(code, 0);
+        assert_eq!(back, data);
+    }
+
+    #[test]
+    fn test_pem_roundtrip_cli() {
+        let data: Vec<u8> = (0..=255u8).cycle().take(700).collect();
+        let (pem, code) = run_fbase64(&data, &["--pem=MY DATA", "-w", "10"]);
+        assert_eq!(code, 0);
+        let text = String::from_utf8(pem.clone()).unwrap();
+        assert!(text.starts_with("-----BEGIN MY DATA-----\n"));
+        assert!(text.ends_with("\n-----END MY DATA-----\n"));
+        // --pem forces 64 columns whatever -w says
+        assert_eq!(text.lines().nth(1).unwrap().len(), 64);
+
+        let mut twice = pem.clone();
+        twice.extend_from_slice(&pem);
+        for args in [&["-d", "--pem"][..], &["-d", "--pem=MY DATA"]] {
+            let (out, code) = run_fbase64(&twice, args);
+            assert_eq!(code, 0, "{:?}", args);
+            assert_eq!(out, [&data[..], &data[..]].concat(), "{:?}", args);
+        }
+
+        let (out, code) = run_fbase64(&pem, &["-d", "--pem=OTHER"]);
+        assert_eq!(code, 1);
+        assert!(out.is_empty());
+
+        let (_, code) = run_fbase64(&data, &["--pem"]);
+        assert_eq!(code, 1);
+    }
+
+    #[test]
+    fn test_invalid_input_partial_output_matches_gnu() {
+        let gnu = Command::new("base64")
+            .arg("--version")
+            .output()
+            .is_ok_and(|o| String::from_utf8_lossy(&o.stdout).contains("GNU coreutils"));
+        let dir = tempfile::tempdir().unwrap();
+        // The second file is past the one-pass limit, so it is mapped but
+        // decoded by the streaming decoder, as a pipe is
+        for len in [1_000_000usize, 13_000_000] {
+            let data = super::payload(len as u64, len);
+            let (mut input, code) = run_fbase64(&data, &[]);
+            assert_eq!(code, 0);
+            input.extend_from_slice(b"QUJD\0REVG\n");
+            let path = dir.path().join(format!("in{}.b64", len));
+            std::fs::write(&path, &input).unwrap();
+            let mut expected = data;
+            expected.extend_from_slice(b"ABC");
+
+            let (piped, code) = run_fbase64(&input, &["-d"]);
+            assert_eq!(code, 1);
+            assert!(piped == expected, "pipe, len {}", len);
+
+            let output = Command::new(bin_path("fbase64"))
+                .arg("-d")
+                .arg(&path)
+                .output()
+                .unwrap();
+            assert_eq!(output.status.code(), Some(1));
+            assert!(output.stdout == expected, "file, len {}", len);
+            assert_eq!(
+                String::from_utf8_lossy(&output.stderr),
+                format!("base64: {}: invalid input\n", path.display())
+            );
+
+            if gnu {
+                let reference = Command::new("base64")
+                    .arg("-d")
+                    .arg(&path)
+                    .output()
+                    .unwrap();
+                assert_eq!(reference.status.code(), Some(1));
+                assert!(reference.stdout == expected, "GNU, len {}", len);
+            }
+        }
+    }
+
+    #[test]
+    fn test_decode_file_operand_uses_mmap() {
+        let dir = tempfile::tempdir().unwrap();
+        // One file under the one-pass limit and one past it (16 MiB encoded)
+        for (len, how) in [
+            (1_500_000usize, "memory-mapped, one-pass decode"),
+            (13_000_000, "memory-mapped, streaming decode"),
+        ] {
+            let mut seed = len as u64;
+            let data: Vec<u8> = (0..len)
+                .map(|_| {
+                    seed ^= seed << 13;
+                    seed ^= seed >> 7;
+                    seed ^= seed << 17;
+                    seed as u8
+                })
+                .collect();
+            let (encoded, code) = run_fbase64(&data, &[]);
+            assert_eq!(code, 0);
+            assert!(encoded.len() > 1024 * 1024);
+            let path = dir.path().join(format!("in{}.b64", len));
+            std::fs::write(&path, &encoded).unwrap();
+
+            for multi in [false, true] {
+                let mut args = vec!["-d"];
+                if multi {
+                    args.push("--multi");
+                }
+                let (streamed, code) = run_fbase64(&encoded, &args);
+                assert_eq!(code, 0);
+                assert!(streamed == data, "len {} multi {}", len, multi);
+
+                let output = Command::new(bin_path("fbase64"))
+                    .args(&args)
+                    .arg("--debug")
+                    .arg(&path)
+                    .output()
+                    .unwrap();
+                assert!(output.status.success());
+                assert_eq!(
+                    String::from_utf8_lossy(&output.stderr),
+                    format!("base64: {}: {}\n", path.display(), how)
+                );
+                assert!(output.stdout == streamed, "len {} multi {}", len, multi);
+            }
+        }
+    }
+}
@@ -5,7 +5,6 @@ use std::ffi::CStr;
 use std::process;
 
 const TOOL_NAME: &str = "arch";
-const VERSION: &str = env!("CARGO_PKG_VERSION");
 
 fn main() {
     coreutils_rs::common::reset_sigpipe();
@@ -22,7 +21,7 @@ fn main() {
                 return;
             }
             "--version" => {
-                println!("{} (fcoreutils) {}", TOOL_NAME, VERSION);
+                coreutils_rs::common::version::print_version(TOOL_NAME);
                 return;
             }
             _ => {
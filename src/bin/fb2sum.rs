@@ -23,6 +23,10 @@ struct Cli {
     tag: bool,
     warn: bool,
     zero: bool,
+    uppercase: bool,
+    cache: bool,
+    debug: bool,
+    direct_io: bool,
     files: Vec<String>,
 }
 
@@ -40,6 +44,10 @@ fn parse_args() -> Cli {
         tag: false,
         warn: false,
         zero: false,
+        uppercase: false,
+        cache: false,
+        debug: false,
+        direct_io: false,
         files: Vec::new(),
     };
 
@@ -57,7 +65,9 @@ fn parse_args() -> Cli {
             continue;
         }
         if bytes.starts_with(b"--") {
-            if bytes.starts_with(b"--length=") {
+            if bytes.starts_with(b"--cache=") {
+                cli.cache = parse_cache_mode(&arg.to_string_lossy()[8..]);
+            } else if bytes.starts_with(b"--length=") {
                 let val = std::str::from_utf8(&bytes[9..]).unwrap_or("0");
                 cli.length = val.parse().unwrap_or_else(|_| {
                     eprintln!("{}: invalid length: '{}'", TOOL_NAME, val);
@@ -87,6 +97,18 @@ fn parse_args() -> Cli {
                     b"--tag" => cli.tag = true,
                     b"--warn" => cli.warn = true,
                     b"--zero" => cli.zero = true,
+                    b"--uppercase" => cli.uppercase = true,
+                    b"--debug" => cli.debug = true,
+                    b"--direct-io" => cli.direct_io = true,
+                    b"--cache" => {
+                        if let Some(v) = args.next() {
+                            cli.cache = parse_cache_mode(&v.to_string_lossy());
+                        } else {
+                            eprintln!("{}: option '--cache' requires an argument", TOOL_NAME);
+                            eprintln!("Try '{} --help' for more information.", TOOL_NAME);
+                            process::exit(1);
+                        }
+                    }
                     b"--help" => {
                         print!(
                             "Usage: {} [OPTION]... [FILE]...\n\
@@ -98,7 +120,13 @@ fn parse_args() -> Cli {
                             \x20                        and must be a multiple of 8\n\
                             \x20     --tag             create a BSD-style checksum\n\
                             \x20 -t, --text           read in text mode (default)\n\
-                            \x20 -z, --zero           end each output line with NUL, not newline\n\n\
+                            \x20 -z, --zero           end each output line with NUL, not newline\n\
+                            \x20     --uppercase       print hex digests in uppercase (ignored with -c)\n\
+                            \x20     --cache=xattr     reuse digests stored in extended attributes of\n\
+                            \x20                         files whose size and mtime are unchanged\n\
+                            \x20     --direct-io       read files with O_DIRECT, bypassing the page cache\n\
+                            \x20     --debug           report whether each digest was cached or computed,\n\
+                            \x20                         and when --direct-io fell back to buffered reads\n\n\
                             The following five options are useful only when verifying checksums:\n\
                             \x20     --ignore-missing  don't fail or report status for missing files\n\
                             \x20     --quiet           don't print OK for each successfully verified file\n\
@@ -112,7 +140,7 @@ fn parse_args() -> Cli {
                         process::exit(0);
                     }
                     b"--version" => {
-                        println!("{} (fcoreutils) {}", TOOL_NAME, env!("CARGO_PKG_VERSION"));
+                        coreutils_rs::common::version::print_version(TOOL_NAME);
                         process::exit(0);
                     }
                     _ => {
@@ -285,11 +313,15 @@ fn run_hash_mode(cli: &Cli, files: &[String], output_bytes: usize, out: &mut imp
     let mut had_error = false;
     let has_stdin = files.iter().any(|f| f == "-");
 
-    if has_stdin || files.len() == 1 {
-        // Sequential for stdin or single file
+    if has_stdin || files.len() == 1 || cli.cache || cli.direct_io {
+        // Sequential for stdin, a single file, the xattr cache or O_DIRECT
         for filename in files {
             let hash_result = if filename == "-" {
                 hash::blake2b_hash_stdin(output_bytes)
+            } else if cli.cache {
+                hash_file_cached(out, cli, filename, output_bytes)
+            } else if cli.direct_io {
+                hash_file_direct(out, cli, filename, output_bytes)
             } else {
                 hash::blake2b_hash_file(Path::new(filename), output_bytes)
             };
@@ -334,6 +366,64 @@ fn run_hash_mode(cli: &Cli, files: &[String], output_bytes: usize, out: &mut imp
     had_error
 }
 
+/// Parse the `--cache` argument; "xattr" is the only supported mode.
+fn parse_cache_mode(val: &str) -> bool {
+    if val == "xattr" {
+        return true;
+    }
+    eprintln!("{}: invalid argument '{}' for '--cache'", TOOL_NAME, val);
+    eprintln!("Valid arguments are:\n  - 'xattr'");
+    eprintln!("Try '{} --help' for more information.", TOOL_NAME);
+    process::exit(1);
+}
+
+/// Hash a file through the xattr cache, reporting the digest source under --debug.
+/// The cache key carries the digest length so -l 256 and -l 512 never collide.
+fn hash_file_cached(
+    out: &mut impl Write,
+    cli: &Cli,
+    filename: &str,
+    output_bytes: usize,
+) -> io::Result<String> {
+    let path = Path::new(filename);
+    let key = format!("blake2b-{}", output_bytes * 8);
+    let (h, source) = hash::hash_file_cached(path, &key, || {
+        if cli.direct_io {
+            hash_file_direct(out, cli, filename, output_bytes)
+        } else {
+            hash::blake2b_hash_file(path, output_bytes)
+        }
+    })?;
+    if cli.debug {
+        let _ = out.flush();
+        let how = match source {
+            hash::DigestSource::Cached => "served from xattr cache",
+            hash::DigestSource::Computed => "computed",
+        };
+        eprintln!("{}: {}: digest {}", TOOL_NAME, filename, how);
+    }
+    Ok(h)
+}
+
+/// Hash a file with O_DIRECT reads (--direct-io), noting under --debug when
+/// the filesystem forced reads through the page cache instead.
+fn hash_file_direct(
+    out: &mut impl Write,
+    cli: &Cli,
+    filename: &str,
+    output_bytes: usize,
+) -> io::Result<String> {
+    let (h, direct) = hash::blake2b_hash_file_direct(Path::new(filename), output_bytes)?;
+    if cli.debug && !direct {
+        let _ = out.flush();
+        eprintln!(
+            "{}: {}: O_DIRECT not supported, using buffered reads",
+            TOOL_NAME, filename
+        );
+    }
+    Ok(h)
+}
+
 #[inline]
 fn write_output(
     out: &mut impl Write,
@@ -343,6 +433,7 @@ fn write_output(
     output_bytes: usize,
 ) {
     let bits = output_bytes * 8;
+    let hash_hex: &str = &hash::hex_in_case(hash_hex, cli.uppercase);
     if cli.tag {
         if cli.zero {
             let _ = hash::print_hash_tag_b2sum_zero(out, hash_hex, filename, bits);
@@ -563,7 +654,12 @@ fn check_one(
         };
 
         // Hash the file with inferred length
-        let actual = match hash::blake2b_hash_file(Path::new(&check_filename), hash_bytes) {
+        let computed = if cli.direct_io {
+            hash::blake2b_hash_file_direct(Path::new(&check_filename), hash_bytes).map(|(h, _)| h)
+        } else {
+            hash::blake2b_hash_file(Path::new(&check_filename), hash_bytes)
+        };
+        let actual = match computed {
             Ok(h) => h,
             Err(e) => {
                 if cli.ignore_missing && e.kind() == io::ErrorKind::NotFound {
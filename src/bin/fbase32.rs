@@ -4,7 +4,6 @@ use std::io::{self, Read, Write};
 use std::process;
 
 const TOOL_NAME: &str = "base32";
-const VERSION: &str = env!("CARGO_PKG_VERSION");
 
 /// Base32 alphabet per RFC 4648
 const BASE32_ALPHABET: &[u8; 32] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
@@ -96,7 +95,7 @@ fn parse_args() -> Cli {
                         process::exit(0);
                     }
                     b"--version" => {
-                        println!("{} (fcoreutils) {}", TOOL_NAME, VERSION);
+                        coreutils_rs::common::version::print_version(TOOL_NAME);
                         process::exit(0);
                     }
                     _ => {
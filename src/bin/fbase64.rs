@@ -10,8 +10,9 @@ use std::process;
 use memmap2::MmapOptions;
 
 use coreutils_rs::base64::core as b64;
-use coreutils_rs::common::io::read_file_mmap;
+use coreutils_rs::common::io::{FileData, read_file_mmap};
 use coreutils_rs::common::io_error_msg;
+use coreutils_rs::common::perf;
 
 /// Raw stdin reader for zero-overhead pipe reads on Linux.
 /// Bypasses Rust's StdinLock (mutex + 8KB BufReader) for direct libc::read(0).
@@ -38,7 +39,16 @@ impl io::Read for RawStdin {
 struct Cli {
     decode: bool,
     ignore_garbage: bool,
+    multi: bool,
+    /// --pem[=LABEL]: PEM armor; the label is required when encoding.
+    pem: Option<Option<String>>,
+    /// --debug: report on stderr how each input is read and decoded.
+    debug: bool,
+    /// --verbose: print the byte counts on stderr when done.
+    verbose: bool,
     wrap: usize,
+    /// --crlf: end wrapped lines with "\r\n" (MIME).
+    crlf: bool,
     file: Option<String>,
 }
 
@@ -48,7 +58,12 @@ fn parse_args() -> Cli {
     let mut cli = Cli {
         decode: false,
         ignore_garbage: false,
+        multi: false,
+        pem: None,
+        debug: false,
+        verbose: false,
         wrap: 76,
+        crlf: false,
         file: None,
     };
 
@@ -69,10 +84,17 @@ fn parse_args() -> Cli {
                     eprintln!("base64: invalid wrap size: '{}'", val);
                     process::exit(1);
                 });
+            } else if bytes.starts_with(b"--pem=") {
+                cli.pem = Some(Some(String::from_utf8_lossy(&bytes[6..]).into_owned()));
             } else {
                 match bytes {
                     b"--decode" => cli.decode = true,
+                    b"--pem" => cli.pem = Some(None),
                     b"--ignore-garbage" => cli.ignore_garbage = true,
+                    b"--multi" => cli.multi = true,
+                    b"--verbose" => cli.verbose = true,
+                    b"--debug" => cli.debug = true,
+                    b"--crlf" => cli.crlf = true,
                     b"--wrap" => {
                         if let Some(v) = args.next() {
                             let s = v.to_string_lossy();
@@ -93,8 +115,15 @@ fn parse_args() -> Cli {
                             Mandatory arguments to long options are mandatory for short options too.\n\
                             \x20 -d, --decode          decode data\n\
                             \x20 -i, --ignore-garbage  when decoding, ignore non-alphabet characters\n\
+                            \x20     --multi           when decoding, accept concatenated padded streams\n\
+                            \x20     --pem[=LABEL]     encode as a PEM block labelled LABEL (wrapping at 64);\n\
+                            \x20                         when decoding, strip PEM armor, requiring LABEL if given\n\
                             \x20 -w, --wrap=COLS       wrap encoded lines after COLS character (default 76).\n\
                             \x20                         Use 0 to disable line wrapping\n\
+                            \x20     --crlf            end wrapped lines with CRLF instead of LF (MIME)\n\
+                            \x20     --debug           report how the input is read and decoded\n\
+                            \x20     --verbose         print byte and line counts to standard error\n\
+                            \x20                         when done (ignored with --pem)\n\
                             \x20     --help             display this help and exit\n\
                             \x20     --version          output version information and exit\n\n\
                             The data are encoded as described for the base64 alphabet in RFC 4648.\n\
@@ -105,7 +134,7 @@ fn parse_args() -> Cli {
                         process::exit(0);
                     }
                     b"--version" => {
-                        println!("base64 (fcoreutils) {}", env!("CARGO_PKG_VERSION"));
+                        coreutils_rs::common::version::print_version("base64");
                         process::exit(0);
                     }
                     _ => {
@@ -155,6 +184,31 @@ fn parse_args() -> Cli {
         }
     }
 
+    // Like GNU, -i only applies to decoding and -w only to encoding; either
+    // is accepted in the other direction and ignored, so encoding never
+    // filters binary input and decoding never wraps binary output.
+    if cli.decode {
+        cli.wrap = 0;
+        cli.crlf = false;
+    } else {
+        cli.ignore_garbage = false;
+    }
+
+    if let Some(label) = &cli.pem
+        && !cli.decode
+    {
+        let Some(label) = label else {
+            eprintln!("base64: --pem requires a LABEL when encoding");
+            eprintln!("Try 'base64 --help' for more information.");
+            process::exit(1);
+        };
+        if let Err(e) = b64::validate_pem_label(label) {
+            eprintln!("base64: {}", e);
+            process::exit(1);
+        }
+        cli.wrap = b64::PEM_WRAP;
+    }
+
     cli
 }
 
@@ -205,21 +259,17 @@ fn main() {
     #[cfg(unix)]
     let result = {
         let mut raw = raw_stdout();
-        if filename == "-" {
-            process_stdin(&cli, &mut *raw)
+        if perf::enabled() {
+            process(filename, &cli, &mut perf::Timed(&*raw))
         } else {
-            process_file(filename, &cli, &mut *raw)
+            process(filename, &cli, &mut *raw)
         }
     };
     #[cfg(not(unix))]
     let result = {
         let stdout = io::stdout();
         let mut out = io::BufWriter::with_capacity(8 * 1024 * 1024, stdout.lock());
-        let r = if filename == "-" {
-            process_stdin(&cli, &mut out)
-        } else {
-            process_file(filename, &cli, &mut out)
-        };
+        let r = process(filename, &cli, &mut out);
         if let Err(e) = out.flush()
             && e.kind() != io::ErrorKind::BrokenPipe
         {
@@ -229,6 +279,7 @@ fn main() {
         r
     };
 
+    perf::report("fbase64");
     if let Err(e) = result {
         if e.kind() == io::ErrorKind::BrokenPipe {
             process::exit(0);
@@ -277,43 +328,192 @@ fn try_mmap_stdin() -> Option<memmap2::Mmap> {
     mmap
 }
 
+fn process(filename: &str, cli: &Cli, out: &mut impl Write) -> io::Result<()> {
+    if filename == "-" {
+        process_stdin(cli, out)
+    } else {
+        process_file(filename, cli, out)
+    }
+}
+
+/// Name of the phase that turns input into output.
+fn work_phase(cli: &Cli) -> &'static str {
+    if cli.decode { "decode" } else { "encode" }
+}
+
+/// Map redirected stdin, timed as the input phase.
+#[cfg(unix)]
+fn mmap_stdin_timed() -> Option<memmap2::Mmap> {
+    let _input = perf::phase("input");
+    let mmap = try_mmap_stdin()?;
+    perf::add_bytes(mmap.len() as u64);
+    Some(mmap)
+}
+
 fn process_stdin(cli: &Cli, out: &mut impl Write) -> io::Result<()> {
+    // Streamed input is read as it is processed, so that reading is timed
+    // as part of this phase
+    let _work = perf::phase(work_phase(cli));
+
+    if let Some(label) = &cli.pem {
+        #[cfg(unix)]
+        if let Some(mmap) = mmap_stdin_timed() {
+            return process_pem(&mmap, label.as_deref(), cli, out);
+        }
+        let stdin = io::stdin();
+        let mut reader = stdin.lock();
+        return match label {
+            Some(label) if !cli.decode => b64::encode_pem_stream(&mut reader, label, out),
+            _ => b64::decode_pem_stream(reader, label.as_deref(), cli.ignore_garbage, out),
+        };
+    }
+
+    if cli.decode && cli.multi {
+        #[cfg(unix)]
+        if let Some(mmap) = mmap_stdin_timed() {
+            return decode_mapped(&mmap, "standard input", cli, out);
+        }
+        debug_report(cli, "standard input", "streaming decode");
+        let stdin = io::stdin();
+        let mut reader = stdin.lock();
+        return b64::decode_multi_stream_counted(&mut reader, cli.ignore_garbage, out)
+            .map(|stats| report_decoded(cli, stats));
+    }
+
     if cli.decode {
         #[cfg(unix)]
-        if let Some(mmap) = try_mmap_stdin() {
-            return b64::decode_to_writer(&mmap, cli.ignore_garbage, out);
+        if let Some(mmap) = mmap_stdin_timed() {
+            return decode_mapped(&mmap, "standard input", cli, out);
         }
+        debug_report(cli, "standard input", "streaming decode");
 
         #[cfg(target_os = "linux")]
-        return b64::decode_stream(&mut RawStdin, cli.ignore_garbage, out);
+        return b64::decode_stream_counted(&mut RawStdin, cli.ignore_garbage, out)
+            .map(|stats| report_decoded(cli, stats));
         #[cfg(not(target_os = "linux"))]
         {
             let stdin = io::stdin();
             let mut reader = stdin.lock();
-            return b64::decode_stream(&mut reader, cli.ignore_garbage, out);
+            return b64::decode_stream_counted(&mut reader, cli.ignore_garbage, out)
+                .map(|stats| report_decoded(cli, stats));
         }
     }
 
     #[cfg(unix)]
-    if let Some(mmap) = try_mmap_stdin() {
-        return b64::encode_to_writer(&mmap, cli.wrap, out);
+    if let Some(mmap) = mmap_stdin_timed() {
+        return b64::encode_to_writer_counted(&mmap, cli.wrap, cli.crlf, out)
+            .map(|stats| report_encoded(cli, stats));
     }
 
     #[cfg(target_os = "linux")]
-    return b64::encode_stream(&mut RawStdin, cli.wrap, out);
+    return b64::encode_stream_counted(&mut RawStdin, cli.wrap, cli.crlf, out)
+        .map(|stats| report_encoded(cli, stats));
     #[cfg(not(target_os = "linux"))]
     {
         let stdin = io::stdin();
         let mut reader = stdin.lock();
-        b64::encode_stream(&mut reader, cli.wrap, out)
+        b64::encode_stream_counted(&mut reader, cli.wrap, cli.crlf, out)
+            .map(|stats| report_encoded(cli, stats))
     }
 }
 
 fn process_file(filename: &str, cli: &Cli, out: &mut impl Write) -> io::Result<()> {
-    let data = read_file_mmap(Path::new(filename))?;
-    if cli.decode {
-        b64::decode_to_writer(&data, cli.ignore_garbage, out)
+    let input = perf::phase("input");
+    let mut data = read_file_mmap(Path::new(filename))?;
+    drop(input);
+    perf::add_bytes(data.len() as u64);
+    let _work = perf::phase(work_phase(cli));
+    if let Some(label) = &cli.pem {
+        process_pem(&data, label.as_deref(), cli, out)
+    } else if !cli.decode {
+        b64::encode_to_writer_counted(&data, cli.wrap, cli.crlf, out)
+            .map(|stats| report_encoded(cli, stats))
+    } else {
+        match data {
+            FileData::Mmap(ref mmap) => decode_mapped(mmap, filename, cli, out),
+            FileData::Owned(ref mut owned) if !cli.multi => {
+                debug_report(cli, filename, "read into memory, in-place decode");
+                b64::decode_owned_counted(owned, cli.ignore_garbage, out)
+                    .map(|stats| report_decoded(cli, stats))
+            }
+            FileData::Owned(ref owned) => {
+                debug_report(cli, filename, "read into memory, one-pass decode");
+                b64::decode_multi_to_writer_counted(owned, cli.ignore_garbage, out)
+                    .map(|stats| report_decoded(cli, stats))
+            }
+        }
+    }
+}
+
+/// Mapped input up to this size is decoded in one pass, which copies it
+/// (minus whitespace) into a clean buffer. Larger mappings go through the
+/// chunked stream decoders instead, so a multi-GB file is never copied
+/// whole into anonymous memory; the in-place decoders cannot be used on a
+/// read-only mapping.
+const MAPPED_ONE_PASS_MAX: usize = 16 * 1024 * 1024;
+
+/// Decode a read-only memory-mapped input (file operand or redirected stdin).
+fn decode_mapped(data: &[u8], name: &str, cli: &Cli, out: &mut impl Write) -> io::Result<()> {
+    if data.len() > MAPPED_ONE_PASS_MAX {
+        debug_report(cli, name, "memory-mapped, streaming decode");
+        let mut reader = data;
+        let stats = if cli.multi {
+            b64::decode_multi_stream_counted(&mut reader, cli.ignore_garbage, out)?
+        } else {
+            b64::decode_stream_counted(&mut reader, cli.ignore_garbage, out)?
+        };
+        report_decoded(cli, stats);
+        Ok(())
     } else {
-        b64::encode_to_writer(&data, cli.wrap, out)
+        debug_report(cli, name, "memory-mapped, one-pass decode");
+        if cli.multi {
+            b64::decode_multi_to_writer_counted(data, cli.ignore_garbage, out)
+                .map(|stats| report_decoded(cli, stats))
+        } else if cli.verbose {
+            // Counting the stripped bytes takes an extra pass here
+            b64::decode_to_writer_counted(data, cli.ignore_garbage, out)
+                .map(|stats| report_decoded(cli, stats))
+        } else {
+            b64::decode_to_writer(data, cli.ignore_garbage, out)
+        }
+    }
+}
+
+/// With --debug, say on stderr how `name` is being decoded.
+fn debug_report(cli: &Cli, name: &str, how: &str) {
+    if cli.debug {
+        eprintln!("base64: {}: {}", name, how);
+    }
+}
+
+/// With --verbose, summarise an encode on stderr.
+fn report_encoded(cli: &Cli, stats: b64::EncodeStats) {
+    if cli.verbose {
+        eprintln!(
+            "base64: read {} bytes, wrote {} bytes in {} lines",
+            stats.input_bytes, stats.output_bytes, stats.lines
+        );
+    }
+}
+
+/// With --verbose, summarise a decode on stderr.
+fn report_decoded(cli: &Cli, stats: b64::DecodeStats) {
+    if cli.verbose {
+        eprintln!(
+            "base64: read {} bytes, stripped {} whitespace bytes, wrote {} bytes",
+            stats.input_bytes, stats.stripped_whitespace, stats.output_bytes
+        );
+    }
+}
+
+fn process_pem(
+    data: &[u8],
+    label: Option<&str>,
+    cli: &Cli,
+    out: &mut impl Write,
+) -> io::Result<()> {
+    match label {
+        Some(label) if !cli.decode => b64::encode_pem_to_writer(data, label, out),
+        _ => b64::decode_pem_to_writer(data, label, cli.ignore_garbage, out),
     }
 }
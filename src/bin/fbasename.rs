@@ -6,7 +6,6 @@
 use std::process;
 
 const TOOL_NAME: &str = "basename";
-const VERSION: &str = env!("CARGO_PKG_VERSION");
 
 fn main() {
     coreutils_rs::common::reset_sigpipe();
@@ -47,7 +46,7 @@ fn main() {
                 return;
             }
             "--version" => {
-                println!("{} (fcoreutils) {}", TOOL_NAME, VERSION);
+                coreutils_rs::common::version::print_version(TOOL_NAME);
                 return;
             }
             "-a" | "--multiple" => multiple = true,
@@ -4,7 +4,6 @@ use std::io::{self, Read, Write};
 use std::process;
 
 const TOOL_NAME: &str = "basenc";
-const VERSION: &str = env!("CARGO_PKG_VERSION");
 
 #[derive(Clone, Copy, PartialEq)]
 enum Encoding {
@@ -104,7 +103,7 @@ fn parse_args() -> Cli {
                         process::exit(0);
                     }
                     b"--version" => {
-                        println!("{} (fcoreutils) {}", TOOL_NAME, VERSION);
+                        coreutils_rs::common::version::print_version(TOOL_NAME);
                         process::exit(0);
                     }
                     _ => {
@@ -56,7 +56,7 @@ fn parse_args() -> Cli {
                     process::exit(0);
                 }
                 b"--version" => {
-                    println!("cat (fcoreutils) {}", env!("CARGO_PKG_VERSION"));
+                    coreutils_rs::common::version::print_version("cat");
                     process::exit(0);
                 }
                 _ => {
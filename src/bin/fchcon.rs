@@ -33,7 +33,7 @@ fn main() {
     }
 
     if args.iter().any(|a| a == "--version") {
-        println!("chcon (fcoreutils) {}", env!("CARGO_PKG_VERSION"));
+        coreutils_rs::common::version::print_version("chcon");
         return;
     }
 
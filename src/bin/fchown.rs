@@ -14,8 +14,6 @@ use std::process;
 
 #[cfg(unix)]
 const TOOL_NAME: &str = "chown";
-#[cfg(unix)]
-const VERSION: &str = env!("CARGO_PKG_VERSION");
 
 #[cfg(unix)]
 fn main() {
@@ -46,7 +44,7 @@ fn main() {
                 return;
             }
             "--version" => {
-                println!("{} (fcoreutils) {}", TOOL_NAME, VERSION);
+                coreutils_rs::common::version::print_version(TOOL_NAME);
                 return;
             }
             "-c" | "--changes" => config.changes = true,
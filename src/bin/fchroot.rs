@@ -17,8 +17,6 @@ use std::process;
 
 #[cfg(unix)]
 const TOOL_NAME: &str = "chroot";
-#[cfg(unix)]
-const VERSION: &str = env!("CARGO_PKG_VERSION");
 
 #[cfg(unix)]
 fn main() {
@@ -50,7 +48,7 @@ fn main() {
                 return;
             }
             "--version" => {
-                println!("{} (fcoreutils) {}", TOOL_NAME, VERSION);
+                coreutils_rs::common::version::print_version(TOOL_NAME);
                 return;
             }
             "--skip-chdir" => skip_chdir = true,
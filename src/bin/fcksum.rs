@@ -1,134 +1,220 @@
 // fcksum — compute POSIX CRC-32 checksum and byte count (GNU cksum replacement)
 
-use std::io::{self, BufRead, Read, Write};
+use std::io::{self, BufRead, Write};
+use std::path::{Path, PathBuf};
 use std::process;
 
-const TOOL_NAME: &str = "cksum";
-const VERSION: &str = env!("CARGO_PKG_VERSION");
-
-/// POSIX CRC-32 slicing-by-4 lookup tables using polynomial 0x04C11DB7.
-/// Table 0 is the standard byte-at-a-time table; tables 1-3 enable processing
-/// 4 bytes per iteration for ~4x throughput improvement.
-const CRC_TABLES: [[u32; 256]; 4] = {
-    let mut tables = [[0u32; 256]; 4];
-    // Build the base table (table 0)
-    let mut i = 0u32;
-    while i < 256 {
-        let mut crc = i << 24;
-        let mut j = 0;
-        while j < 8 {
-            if crc & 0x8000_0000 != 0 {
-                crc = (crc << 1) ^ 0x04C1_1DB7;
-            } else {
-                crc <<= 1;
-            }
-            j += 1;
-        }
-        tables[0][i as usize] = crc;
-        i += 1;
-    }
-    // Build extended tables for slicing-by-4
-    let mut t = 1;
-    while t < 4 {
-        let mut i = 0;
-        while i < 256 {
-            let prev = tables[t - 1][i];
-            tables[t][i] = (prev << 8) ^ tables[0][(prev >> 24) as usize];
-            i += 1;
-        }
-        t += 1;
-    }
-    tables
-};
+use coreutils_rs::common::diag::{Diag, quote, quote_always};
+use coreutils_rs::common::fmt_num;
+use coreutils_rs::common::interrupt;
+use coreutils_rs::common::perf;
+use coreutils_rs::common::sigbus;
+use coreutils_rs::hash::{self, HashAlgorithm};
 
-/// Backward-compatible alias for tests that reference CRC_TABLE
-#[cfg(test)]
-const CRC_TABLE: [u32; 256] = CRC_TABLES[0];
+const TOOL_NAME: &str = "cksum";
 
-/// Compute the POSIX CRC-32 checksum using slicing-by-4 for high throughput.
-/// Processes 4 bytes per iteration in the main loop (~4x faster than byte-at-a-time).
-#[cfg(test)]
-fn posix_cksum(data: &[u8]) -> u32 {
-    let mut crc: u32 = 0;
+struct Cli {
+    /// Digest algorithm selected with -a; `None` is the default POSIX CRC.
+    algorithm: Option<HashAlgorithm>,
+    /// -a was given (possibly as "crc").
+    algorithm_set: bool,
+    /// -l: BLAKE2b digest length in bits; 0 (the default) means 512.
+    length: usize,
+    /// The -l argument as given, for diagnostics.
+    length_arg: String,
+    untagged: bool,
+    base64: bool,
+    uppercase: bool,
+    raw: bool,
+    cache: bool,
+    debug: bool,
+    direct_io: bool,
+    check: bool,
+    quiet: bool,
+    status: bool,
+    strict: bool,
+    warn: bool,
+    ignore_missing: bool,
+    /// -r: hash the regular files under directory operands.
+    recursive: bool,
+    /// -L: with -r, follow symlinks instead of skipping them.
+    dereference: bool,
+    files: Vec<String>,
+}
 
-    // Slicing-by-4: process 4 bytes per iteration
-    let chunks = data.chunks_exact(4);
-    let remainder = chunks.remainder();
+/// Values accepted by -a/--algorithm.
+const ALGORITHMS: &[&str] = &[
+    "crc", "md5", "sha1", "sha224", "sha256", "sha384", "sha512", "blake2b",
+];
 
-    for chunk in chunks {
-        let b0 = chunk[0];
-        let b1 = chunk[1];
-        let b2 = chunk[2];
-        let b3 = chunk[3];
-        crc = CRC_TABLES[3][((crc >> 24) ^ u32::from(b0)) as usize]
-            ^ CRC_TABLES[2][((crc >> 16) as u8 ^ b1) as usize]
-            ^ CRC_TABLES[1][((crc >> 8) as u8 ^ b2) as usize]
-            ^ CRC_TABLES[0][(crc as u8 ^ b3) as usize];
+fn parse_algorithm(val: &str) -> Option<HashAlgorithm> {
+    match val {
+        "crc" => None,
+        "md5" => Some(HashAlgorithm::Md5),
+        "sha1" => Some(HashAlgorithm::Sha1),
+        "sha224" => Some(HashAlgorithm::Sha224),
+        "sha256" => Some(HashAlgorithm::Sha256),
+        "sha384" => Some(HashAlgorithm::Sha384),
+        "sha512" => Some(HashAlgorithm::Sha512),
+        "blake2b" => Some(HashAlgorithm::Blake2b),
+        _ => {
+            eprintln!(
+                "{}: invalid argument '{}' for '--algorithm'",
+                TOOL_NAME, val
+            );
+            eprintln!("Valid arguments are:");
+            for name in ALGORITHMS {
+                eprintln!("  - '{}'", name);
+            }
+            eprintln!("Try '{} --help' for more information.", TOOL_NAME);
+            process::exit(1);
+        }
     }
+}
 
-    // Process remaining bytes one at a time
-    for &byte in remainder {
-        crc = (crc << 8) ^ CRC_TABLES[0][((crc >> 24) ^ u32::from(byte)) as usize];
+/// Parse the -l argument like GNU's xdectoumax: leading blanks and a '+'
+/// are allowed. A length that is not a multiple of 8 is rejected at once,
+/// as in GNU; the algorithm and the maximum are checked after all options.
+fn parse_length(val: &str) -> usize {
+    let invalid = |detail: &str| -> ! {
+        eprintln!(
+            "{}: invalid length: {}{}",
+            TOOL_NAME,
+            quote_always(val.as_ref()),
+            detail
+        );
+        process::exit(1);
+    };
+    let digits = val.trim_start_matches(|c: char| c.is_ascii_whitespace());
+    let digits = digits.strip_prefix('+').unwrap_or(digits);
+    if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        invalid("");
     }
-
-    // Feed length bytes (big-endian, only the significant bytes)
-    let mut len = data.len() as u64;
-    while len > 0 {
-        crc = (crc << 8) ^ CRC_TABLES[0][((crc >> 24) ^ (len & 0xFF) as u32) as usize];
-        len >>= 8;
+    let bits: usize = digits
+        .parse()
+        .unwrap_or_else(|_| invalid(": Value too large for defined data type"));
+    if !bits.is_multiple_of(8) {
+        eprintln!(
+            "{}: invalid length: {}",
+            TOOL_NAME,
+            quote_always(val.as_ref())
+        );
+        eprintln!("{}: length is not a multiple of 8", TOOL_NAME);
+        process::exit(1);
     }
-
-    !crc
+    bits
 }
 
-/// Streaming POSIX CRC-32: process data from a reader without loading everything into memory.
-/// Uses 8MB buffer and slicing-by-4 for maximum throughput.
-fn posix_cksum_streaming<R: Read>(reader: R) -> io::Result<(u32, u64)> {
-    let mut reader = io::BufReader::with_capacity(8 * 1024 * 1024, reader);
-    let mut crc: u32 = 0;
-    let mut total_bytes: u64 = 0;
+/// Parse the `--cache` argument; "xattr" is the only supported mode.
+fn parse_cache_mode(val: &str) -> bool {
+    if val == "xattr" {
+        return true;
+    }
+    eprintln!("{}: invalid argument '{}' for '--cache'", TOOL_NAME, val);
+    eprintln!("Valid arguments are:\n  - 'xattr'");
+    eprintln!("Try '{} --help' for more information.", TOOL_NAME);
+    process::exit(1);
+}
 
-    loop {
-        let buf = reader.fill_buf()?;
-        if buf.is_empty() {
-            break;
-        }
-        let n = buf.len();
-        total_bytes += n as u64;
+fn missing_argument(opt: &str) -> ! {
+    eprintln!("{}: option requires an argument -- '{}'", TOOL_NAME, opt);
+    eprintln!("Try '{} --help' for more information.", TOOL_NAME);
+    process::exit(1);
+}
 
-        // Slicing-by-4 on the buffer
-        let chunks = buf.chunks_exact(4);
-        let remainder = chunks.remainder();
+/// Long options in GNU's table order, and whether each takes an argument.
+const LONG_OPTIONS: &[(&str, bool)] = &[
+    ("check", false),
+    ("ignore-missing", false),
+    ("quiet", false),
+    ("status", false),
+    ("warn", false),
+    ("strict", false),
+    ("tag", false),
+    ("untagged", false),
+    ("algorithm", true),
+    ("length", true),
+    ("debug", false),
+    ("base64", false),
+    ("uppercase", false),
+    ("raw", false),
+    ("cache", true),
+    ("direct-io", false),
+    ("recursive", false),
+    ("dereference", false),
+    ("help", false),
+    ("version", false),
+];
 
-        for chunk in chunks {
-            crc = CRC_TABLES[3][((crc >> 24) ^ u32::from(chunk[0])) as usize]
-                ^ CRC_TABLES[2][((crc >> 16) as u8 ^ chunk[1]) as usize]
-                ^ CRC_TABLES[1][((crc >> 8) as u8 ^ chunk[2]) as usize]
-                ^ CRC_TABLES[0][(crc as u8 ^ chunk[3]) as usize];
+/// Resolve "--name" or "--name=value" like getopt_long: an exact name or
+/// an unambiguous prefix selects the option. Unknown, ambiguous and
+/// misused options exit with GNU's diagnostics.
+fn resolve_long_option(arg: &str) -> (&'static str, Option<&str>) {
+    let (given, value) = match arg[2..].split_once('=') {
+        Some((name, value)) => (name, Some(value)),
+        None => (&arg[2..], None),
+    };
+    let matches: Vec<&(&str, bool)> = match LONG_OPTIONS.iter().find(|(n, _)| *n == given) {
+        Some(exact) => vec![exact],
+        None => LONG_OPTIONS
+            .iter()
+            .filter(|(n, _)| n.starts_with(given))
+            .collect(),
+    };
+    match matches[..] {
+        [] => usage_error(&format!("unrecognized option '{}'", arg)),
+        [&(name, takes_arg)] => {
+            if value.is_some() && !takes_arg {
+                usage_error(&format!("option '--{}' doesn't allow an argument", name));
+            }
+            (name, value)
         }
-        for &byte in remainder {
-            crc = (crc << 8) ^ CRC_TABLES[0][((crc >> 24) ^ u32::from(byte)) as usize];
+        _ => {
+            let possibilities: Vec<String> =
+                matches.iter().map(|(n, _)| format!("'--{}'", n)).collect();
+            usage_error(&format!(
+                "option '{}' is ambiguous; possibilities: {}",
+                arg,
+                possibilities.join(" ")
+            ))
         }
-
-        reader.consume(n);
     }
-
-    // Feed length bytes
-    let mut len = total_bytes;
-    while len > 0 {
-        crc = (crc << 8) ^ CRC_TABLES[0][((crc >> 24) ^ (len & 0xFF) as u32) as usize];
-        len >>= 8;
-    }
-
-    Ok((!crc, total_bytes))
 }
 
-struct Cli {
-    files: Vec<String>,
+impl Cli {
+    /// The BLAKE2b digest size in bytes.
+    fn blake2b_bytes(&self) -> usize {
+        if self.length == 0 {
+            64
+        } else {
+            self.length / 8
+        }
+    }
 }
 
 fn parse_args() -> Cli {
-    let mut cli = Cli { files: Vec::new() };
+    let mut cli = Cli {
+        algorithm: None,
+        algorithm_set: false,
+        length: 0,
+        length_arg: String::new(),
+        untagged: false,
+        base64: false,
+        uppercase: false,
+        raw: false,
+        cache: false,
+        debug: false,
+        direct_io: false,
+        check: false,
+        quiet: false,
+        status: false,
+        strict: false,
+        warn: false,
+        ignore_missing: false,
+        recursive: false,
+        dereference: false,
+        files: Vec::new(),
+    };
 
     let mut args = std::env::args_os().skip(1);
     #[allow(clippy::while_let_on_iterator)]
@@ -141,41 +227,124 @@ fn parse_args() -> Cli {
             break;
         }
         if bytes.starts_with(b"--") {
-            match bytes {
-                b"--help" => {
+            let arg = arg.to_string_lossy();
+            let (name, value) = resolve_long_option(&arg);
+            let mut value = || match value {
+                Some(v) => v.to_string(),
+                None => match args.next() {
+                    Some(v) => v.to_string_lossy().into_owned(),
+                    None => usage_error(&format!("option '--{}' requires an argument", name)),
+                },
+            };
+            match name {
+                "algorithm" => {
+                    cli.algorithm = parse_algorithm(&value());
+                    cli.algorithm_set = true;
+                }
+                "length" => {
+                    cli.length_arg = value();
+                    cli.length = parse_length(&cli.length_arg);
+                }
+                "cache" => cli.cache = parse_cache_mode(&value()),
+                "tag" => cli.untagged = false,
+                "untagged" => cli.untagged = true,
+                "base64" => cli.base64 = true,
+                "uppercase" => cli.uppercase = true,
+                "raw" => cli.raw = true,
+                "debug" => cli.debug = true,
+                "direct-io" => cli.direct_io = true,
+                "check" => cli.check = true,
+                "quiet" => cli.quiet = true,
+                "status" => cli.status = true,
+                "strict" => cli.strict = true,
+                "warn" => cli.warn = true,
+                "ignore-missing" => cli.ignore_missing = true,
+                "recursive" => cli.recursive = true,
+                "dereference" => cli.dereference = true,
+                "help" => {
                     print!(
-                        "Usage: {} [FILE]...\n\
-                         Print CRC checksum and byte counts of each FILE.\n\n\
-                         With no FILE, or when FILE is -, read standard input.\n\n\
-                         \x20     --help       display this help and exit\n\
-                         \x20     --version    output version information and exit\n",
+                        "Usage: {} [OPTION]... [FILE]...\n\
+                 Print or verify checksums.\n\
+                 By default use the 32 bit CRC algorithm.\n\n\
+                 With no FILE, or when FILE is -, read standard input.\n\n\
+                 \x20 -a, --algorithm=TYPE  select the digest type to use.  See DIGEST below.\n\
+                 \x20     --base64          emit base64-encoded digests, not hexadecimal\n\
+                 \x20 -c, --check           read checksums from the FILEs and check them\n\
+                 \x20 -l, --length=BITS     digest length in bits; must not exceed the max size\n\
+                 \x20                         and must be a multiple of 8 for blake2b\n\
+                 \x20     --raw             emit a raw binary digest, not hexadecimal\n\
+                 \x20     --tag             create a BSD-style checksum (the default)\n\
+                 \x20     --untagged        create a reversed style checksum, without digest type\n\
+                 \x20     --uppercase       emit uppercase hexadecimal digests (not for crc)\n\
+                 \x20     --cache=xattr     reuse digests stored in extended attributes of\n\
+                 \x20                         files whose size and mtime are unchanged\n\
+                 \x20     --direct-io       read files with O_DIRECT, bypassing the page cache\n\
+                 \x20     --debug           report whether each digest was cached or computed,\n\
+                 \x20                         and when --direct-io fell back to buffered reads\n\
+                 \x20 -r, --recursive       hash the regular files under each directory FILE,\n\
+                 \x20                         named relative to it and sorted bytewise\n\
+                 \x20 -L, --dereference     with -r, follow symbolic links\n\n\
+                 The following five options are useful only when verifying checksums:\n\
+                 \x20     --ignore-missing  don't fail or report status for missing files\n\
+                 \x20     --quiet           don't print OK for each successfully verified file\n\
+                 \x20     --status          don't output anything, status code shows success\n\
+                 \x20     --strict          exit non-zero for improperly formatted checksum lines\n\
+                 \x20 -w, --warn            warn about improperly formatted checksum lines,\n\
+                 \x20                         or with -r about skipped non-regular files\n\n\
+                 \x20     --help            display this help and exit\n\
+                 \x20     --version         output version information and exit\n\n\
+                 DIGEST determines the digest algorithm and default output format:\n\
+                 \x20 crc       (equivalent to cksum)\n\
+                 \x20 md5       (equivalent to md5sum)\n\
+                 \x20 sha1      (equivalent to sha1sum)\n\
+                 \x20 sha224    (equivalent to sha224sum)\n\
+                 \x20 sha256    (equivalent to sha256sum)\n\
+                 \x20 sha384    (equivalent to sha384sum)\n\
+                 \x20 sha512    (equivalent to sha512sum)\n\
+                 \x20 blake2b   (equivalent to b2sum)\n",
                         TOOL_NAME
                     );
                     process::exit(0);
                 }
-                b"--version" => {
-                    println!("{} (fcoreutils) {}", TOOL_NAME, VERSION);
+                "version" => {
+                    coreutils_rs::common::version::print_version(TOOL_NAME);
                     process::exit(0);
                 }
-                _ => {
-                    eprintln!(
-                        "{}: unrecognized option '{}'",
-                        TOOL_NAME,
-                        arg.to_string_lossy()
-                    );
-                    eprintln!("Try '{} --help' for more information.", TOOL_NAME);
-                    process::exit(1);
-                }
+                _ => unreachable!("option table and match out of sync"),
             }
         } else if bytes.len() > 1 && bytes[0] == b'-' {
-            // cksum doesn't have short options
-            eprintln!(
-                "{}: invalid option -- '{}'",
-                TOOL_NAME,
-                arg.to_string_lossy()
-            );
-            eprintln!("Try '{} --help' for more information.", TOOL_NAME);
-            process::exit(1);
+            // Clustered short options; -a and -l take the rest of the
+            // cluster or the next argument, as in -caMD5 or -ca md5
+            for (i, &b) in bytes.iter().enumerate().skip(1) {
+                match b {
+                    b'c' => cli.check = true,
+                    b'w' => cli.warn = true,
+                    b'r' => cli.recursive = true,
+                    b'L' => cli.dereference = true,
+                    b'a' | b'l' => {
+                        let val = if i + 1 < bytes.len() {
+                            arg.to_string_lossy()[i + 1..].to_string()
+                        } else {
+                            let opt = (b as char).to_string();
+                            let val = args.next().unwrap_or_else(|| missing_argument(&opt));
+                            val.to_string_lossy().into_owned()
+                        };
+                        if b == b'a' {
+                            cli.algorithm = parse_algorithm(&val);
+                            cli.algorithm_set = true;
+                        } else {
+                            cli.length = parse_length(&val);
+                            cli.length_arg = val;
+                        }
+                        break;
+                    }
+                    _ => {
+                        eprintln!("{}: invalid option -- '{}'", TOOL_NAME, b as char);
+                        eprintln!("Try '{} --help' for more information.", TOOL_NAME);
+                        process::exit(1);
+                    }
+                }
+            }
         } else {
             cli.files.push(arg.to_string_lossy().into_owned());
         }
@@ -185,82 +354,597 @@ fn parse_args() -> Cli {
         cli.files.push("-".to_string());
     }
 
+    // Like GNU, these two fail without the usual --help hint
+    if cli.length != 0 && !matches!(cli.algorithm, Some(HashAlgorithm::Blake2b)) {
+        eprintln!(
+            "{}: --length is only supported with --algorithm=blake2b",
+            TOOL_NAME
+        );
+        process::exit(1);
+    }
+    if cli.length > 512 {
+        eprintln!(
+            "{}: invalid length: {}",
+            TOOL_NAME,
+            quote_always(cli.length_arg.as_ref())
+        );
+        eprintln!(
+            "{}: maximum digest length for 'BLAKE2b' is 512 bits",
+            TOOL_NAME
+        );
+        process::exit(1);
+    }
+    if cli.check && cli.algorithm_set && cli.algorithm.is_none() {
+        usage_error("--check is not supported with --algorithm=crc");
+    }
+    if cli.raw && cli.check {
+        usage_error("the --raw option is not supported with --check");
+    }
+    if cli.raw && cli.files.len() > 1 {
+        usage_error("the --raw option is not supported with multiple files");
+    }
+    if cli.base64 && !cli.check && cli.algorithm.is_none() {
+        usage_error("--base64 is not supported with --algorithm=crc");
+    }
+    if cli.recursive && cli.check {
+        usage_error("the --recursive option is not supported with --check");
+    }
+    if cli.recursive && cli.raw {
+        usage_error("the --raw option is not supported with --recursive");
+    }
+    if cli.dereference && !cli.recursive {
+        usage_error("the --dereference option is meaningful only with --recursive");
+    }
+    if !cli.check {
+        // Same order as GNU, which reports only the first of these
+        for (set, name) in [
+            (cli.ignore_missing, "ignore-missing"),
+            (cli.warn && !cli.recursive, "warn"),
+            (cli.status, "status"),
+            (cli.quiet, "quiet"),
+            (cli.strict, "strict"),
+        ] {
+            if set {
+                usage_error(&format!(
+                    "the --{} option is meaningful only when verifying checksums",
+                    name
+                ));
+            }
+        }
+    }
+
     cli
 }
 
+fn usage_error(msg: &str) -> ! {
+    eprintln!("{}: {}", TOOL_NAME, msg);
+    eprintln!("Try '{} --help' for more information.", TOOL_NAME);
+    process::exit(1);
+}
+
 fn main() {
     coreutils_rs::common::reset_sigpipe();
+    interrupt::install_sigint_handler();
+    sigbus::install_handler(TOOL_NAME);
 
     let cli = parse_args();
     let stdout = io::stdout();
-    let mut out = io::BufWriter::with_capacity(256 * 1024, stdout.lock());
-    let mut exit_code = 0;
+    if perf::enabled() {
+        run(
+            &cli,
+            io::BufWriter::with_capacity(256 * 1024, perf::Timed(stdout.lock())),
+        );
+    }
+    run(
+        &cli,
+        io::BufWriter::with_capacity(256 * 1024, stdout.lock()),
+    );
+}
 
-    for filename in &cli.files {
-        let (crc, byte_count) = if filename == "-" {
-            match posix_cksum_streaming(io::stdin().lock()) {
-                Ok(v) => v,
+fn run(cli: &Cli, mut out: impl Write) -> ! {
+    let mut diag = Diag::new(TOOL_NAME);
+
+    if cli.check {
+        run_check(cli, &mut out, &mut diag);
+        finish(&mut out, &diag);
+    }
+    let inputs = if cli.recursive {
+        expand_trees(cli, &mut diag)
+    } else {
+        Inputs::operands(&cli.files)
+    };
+    if let Some(algo) = cli.algorithm {
+        run_digest(cli, algo, &inputs, &mut out, &mut diag);
+        finish(&mut out, &diag);
+    }
+
+    // The output record, reused across files so each is a single write
+    let mut record = Vec::with_capacity(64);
+    coreutils_rs::common::ordered::map_ordered_until(
+        &inputs.paths,
+        worker_threads(&inputs.paths),
+        &mut out,
+        interrupt::interrupted,
+        |path| {
+            let _hash = perf::phase("hash");
+            if is_stdin(path) {
+                hash::posix_cksum_streaming(io::stdin().lock()).map(|v| (v, false))
+            } else if cli.direct_io {
+                hash::DirectReader::open(path).and_then(|mut reader| {
+                    let v = hash::posix_cksum_streaming(&mut reader)?;
+                    // Only final after reading: the first read may be refused
+                    Ok((v, !reader.is_direct()))
+                })
+            } else {
+                std::fs::File::open(path)
+                    .and_then(hash::posix_cksum_streaming)
+                    .map(|v| (v, false))
+            }
+        },
+        |out, i, result| {
+            let path = &inputs.paths[i];
+            let filename = &inputs.names[i];
+            let (crc, byte_count) = match result {
+                Ok((v, fell_back)) => {
+                    perf::add_bytes(v.1);
+                    if cli.debug && fell_back {
+                        note_buffered(out, path);
+                    }
+                    v
+                }
                 Err(e) => {
-                    eprintln!(
-                        "{}: -: {}",
-                        TOOL_NAME,
-                        coreutils_rs::common::io_error_msg(&e)
-                    );
-                    exit_code = 1;
-                    continue;
+                    let _ = out.flush();
+                    diag.io_error(Some(path.as_os_str()), &e);
+                    return;
                 }
+            };
+
+            if cli.raw {
+                check_write(out.write_all(&crc.to_be_bytes()));
+                return;
             }
-        } else {
-            match std::fs::File::open(filename) {
-                Ok(file) => match posix_cksum_streaming(file) {
-                    Ok(v) => v,
+            record.clear();
+            fmt_num::push_u64(&mut record, crc.into());
+            record.push(b' ');
+            fmt_num::push_u64(&mut record, byte_count);
+            if !is_stdin(path) {
+                record.push(b' ');
+                record.extend_from_slice(filename.as_bytes());
+            }
+            record.push(b'\n');
+            check_write(out.write_all(&record));
+        },
+        |out| exit_interrupted(out),
+    );
+
+    finish(&mut out, &diag);
+}
+
+/// Files are hashed by parallel workers when there are several, with output
+/// kept in argument order. Standard input must be read in order, so any "-"
+/// keeps the whole run on the calling thread.
+fn worker_threads(paths: &[PathBuf]) -> usize {
+    if paths.iter().any(|p| is_stdin(p)) {
+        return 1;
+    }
+    std::thread::available_parallelism()
+        .map(|n| n.get())
+        .unwrap_or(4)
+}
+
+/// Exit on a failed stdout write (silently for a closed pipe, like GNU).
+fn check_write(result: io::Result<()>) {
+    if let Err(e) = result {
+        if e.kind() == io::ErrorKind::BrokenPipe {
+            process::exit(0);
+        }
+        eprintln!("{}: write error: {}", TOOL_NAME, e);
+        process::exit(1);
+    }
+}
+
+/// Stop after SIGINT: keep the lines already written, then exit 130.
+fn exit_interrupted(out: &mut impl Write) -> ! {
+    let _ = out.flush();
+    eprintln!("{}: interrupted", TOOL_NAME);
+    process::exit(interrupt::EXIT_INTERRUPTED);
+}
+
+fn finish(out: &mut impl Write, diag: &Diag) -> ! {
+    if let Err(e) = out.flush()
+        && e.kind() != io::ErrorKind::BrokenPipe
+    {
+        eprintln!("{}: write error: {}", TOOL_NAME, e);
+        process::exit(1);
+    }
+
+    perf::report("fcksum");
+    process::exit(diag.exit_code());
+}
+
+/// Check if a filename needs escaping (contains backslash or newline).
+#[inline]
+fn needs_escape(name: &str) -> bool {
+    name.bytes().any(|b| b == b'\\' || b == b'\n')
+}
+
+/// Escape a filename: replace `\` with `\\` and newline with `\n` (literal).
+fn escape_filename(name: &str) -> String {
+    let mut out = String::with_capacity(name.len() + 8);
+    for c in name.chars() {
+        match c {
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+/// `-a` digest mode: GNU-style tagged lines ("SHA256 (file) = hex") by
+/// default (or with --tag), "hex  file" with --untagged; the last of the
+/// two wins. Names with a backslash or newline are escaped and the line
+/// gets a leading backslash, in both layouts. A BLAKE2b digest shorter
+/// than 512 bits is tagged with its length, as in "BLAKE2b-128".
+fn run_digest(
+    cli: &Cli,
+    algo: HashAlgorithm,
+    inputs: &Inputs,
+    out: &mut impl Write,
+    diag: &mut Diag,
+) {
+    let tag = match algo {
+        HashAlgorithm::Blake2b if cli.blake2b_bytes() != 64 => {
+            format!("BLAKE2b-{}", cli.blake2b_bytes() * 8)
+        }
+        _ => algo.name().to_string(),
+    };
+    coreutils_rs::common::ordered::map_ordered_until(
+        &inputs.paths,
+        worker_threads(&inputs.paths),
+        out,
+        interrupt::interrupted,
+        |path| {
+            let _hash = perf::phase("hash");
+            if perf::enabled()
+                && let Ok(meta) = std::fs::metadata(path)
+                && meta.is_file()
+            {
+                perf::add_bytes(meta.len());
+            }
+            if is_stdin(path) {
+                match algo {
+                    HashAlgorithm::Blake2b => hash::blake2b_hash_stdin(cli.blake2b_bytes()),
+                    _ => hash::hash_stdin(algo),
+                }
+                .map(|h| (h, None, false))
+            } else if cli.cache {
+                let mut fell_back = false;
+                hash::hash_file_cached(path, &cache_key(cli, algo), || {
+                    digest_file(cli, algo, path).map(|(h, f)| {
+                        fell_back = f;
+                        h
+                    })
+                })
+                .map(|(h, source)| (h, Some(source), fell_back))
+            } else {
+                digest_file(cli, algo, path).map(|(h, f)| (h, None, f))
+            }
+        },
+        |out, i, result| {
+            let path = &inputs.paths[i];
+            let filename = &inputs.names[i];
+            let h = match result {
+                Ok((h, source, fell_back)) => {
+                    if cli.debug && fell_back {
+                        note_buffered(out, path);
+                    }
+                    if cli.debug
+                        && let Some(source) = source
+                    {
+                        let _ = out.flush();
+                        let how = match source {
+                            hash::DigestSource::Cached => "served from xattr cache",
+                            hash::DigestSource::Computed => "computed",
+                        };
+                        eprintln!("{}: {}: digest {}", TOOL_NAME, path.display(), how);
+                    }
+                    h
+                }
+                Err(e) => {
+                    let _ = out.flush();
+                    diag.io_error(Some(path.as_os_str()), &e);
+                    return;
+                }
+            };
+            let h = if cli.base64 {
+                hash::hex_to_base64(&h)
+            } else if cli.uppercase && !cli.raw {
+                h.to_ascii_uppercase()
+            } else {
+                h
+            };
+            let written = if cli.raw {
+                out.write_all(&hash::hex_decode(&h).unwrap_or_default())
+            } else if needs_escape(filename) {
+                let escaped = escape_filename(filename);
+                if cli.untagged {
+                    hash::write_hash_line(out, &h, &escaped, false, false, true)
+                } else {
+                    out.write_all(b"\\")
+                        .and_then(|()| hash::write_hash_tag_line(out, &tag, &h, &escaped, false))
+                }
+            } else if cli.untagged {
+                hash::write_hash_line(out, &h, filename, false, false, false)
+            } else {
+                hash::write_hash_tag_line(out, &tag, &h, filename, false)
+            };
+            check_write(written);
+        },
+        |out| exit_interrupted(out),
+    );
+}
+
+/// Hash one named file for -a, with O_DIRECT reads under --direct-io. The
+/// flag is set when O_DIRECT was asked for but the filesystem refused it.
+fn digest_file(cli: &Cli, algo: HashAlgorithm, path: &Path) -> io::Result<(String, bool)> {
+    match (algo, cli.direct_io) {
+        (HashAlgorithm::Blake2b, true) => {
+            hash::blake2b_hash_file_direct(path, cli.blake2b_bytes()).map(|(h, d)| (h, !d))
+        }
+        (HashAlgorithm::Blake2b, false) => {
+            hash::blake2b_hash_file(path, cli.blake2b_bytes()).map(|h| (h, false))
+        }
+        (_, true) => hash::hash_file_direct(algo, path).map(|(h, direct)| (h, !direct)),
+        (_, false) => hash::hash_file(algo, path).map(|h| (h, false)),
+    }
+}
+
+/// --debug note for a --direct-io file that was read through the page cache.
+fn note_buffered(out: &mut impl Write, path: &Path) {
+    let _ = out.flush();
+    eprintln!(
+        "{}: {}: O_DIRECT not supported, using buffered reads",
+        TOOL_NAME,
+        path.display()
+    );
+}
+
+/// The files to hash: the path each is opened by and the name printed for
+/// it. The two differ only for files found under a directory by -r.
+struct Inputs {
+    paths: Vec<PathBuf>,
+    names: Vec<String>,
+}
+
+impl Inputs {
+    fn operands(files: &[String]) -> Self {
+        Inputs {
+            paths: files.iter().map(PathBuf::from).collect(),
+            names: files.to_vec(),
+        }
+    }
+}
+
+fn is_stdin(path: &Path) -> bool {
+    path.as_os_str() == "-"
+}
+
+/// -r: replace each directory operand with the regular files below it,
+/// named relative to the directory and sorted bytewise so the listing is
+/// the same on every machine and `cksum -c` run inside the directory
+/// verifies it. Symlinks are skipped unless -L, which follows them and
+/// refuses to re-enter a directory that is its own ancestor. Other
+/// non-regular files are skipped, with a warning under --warn.
+fn expand_trees(cli: &Cli, diag: &mut Diag) -> Inputs {
+    let mut inputs = Inputs {
+        paths: Vec::new(),
+        names: Vec::new(),
+    };
+    for operand in &cli.files {
+        let root = PathBuf::from(operand);
+        let root_id = match std::fs::metadata(&root) {
+            Ok(meta) if meta.is_dir() && !is_stdin(&root) => dir_id(&meta),
+            // Files, "-" and unreadable operands are hashed (or reported) as usual
+            _ => {
+                inputs.paths.push(root);
+                inputs.names.push(operand.clone());
+                continue;
+            }
+        };
+
+        let mut found: Vec<(PathBuf, PathBuf)> = Vec::new();
+        let mut stack = vec![(root.clone(), PathBuf::new(), Vec::from_iter(root_id))];
+        while let Some((dir, rel, ancestors)) = stack.pop() {
+            let entries = match std::fs::read_dir(&dir) {
+                Ok(entries) => entries,
+                Err(e) => {
+                    diag.io_error(Some(dir.as_os_str()), &e);
+                    continue;
+                }
+            };
+            for entry in entries {
+                let entry = match entry {
+                    Ok(entry) => entry,
+                    Err(e) => {
+                        diag.io_error(Some(dir.as_os_str()), &e);
+                        break;
+                    }
+                };
+                let path = entry.path();
+                let rel = rel.join(entry.file_name());
+                let meta = if cli.dereference {
+                    std::fs::metadata(&path)
+                } else {
+                    std::fs::symlink_metadata(&path)
+                };
+                let meta = match meta {
+                    Ok(meta) => meta,
                     Err(e) => {
-                        eprintln!(
-                            "{}: {}: {}",
-                            TOOL_NAME,
-                            filename,
-                            coreutils_rs::common::io_error_msg(&e)
-                        );
-                        exit_code = 1;
+                        diag.io_error(Some(path.as_os_str()), &e);
                         continue;
                     }
-                },
+                };
+                if meta.is_file() {
+                    found.push((rel, path));
+                } else if meta.is_dir() {
+                    let id = dir_id(&meta);
+                    if id.is_some_and(|id| ancestors.contains(&id)) {
+                        diag.error(Some(path.as_os_str()), &"file system loop detected");
+                        continue;
+                    }
+                    let mut ancestors = ancestors.clone();
+                    ancestors.extend(id);
+                    stack.push((path, rel, ancestors));
+                } else if cli.warn {
+                    diag.warning(Some(path.as_os_str()), &"not a regular file; skipped");
+                }
+            }
+        }
+
+        found.sort_unstable_by(|a, b| {
+            a.0.as_os_str()
+                .as_encoded_bytes()
+                .cmp(b.0.as_os_str().as_encoded_bytes())
+        });
+        for (rel, path) in found {
+            inputs.names.push(rel.to_string_lossy().into_owned());
+            inputs.paths.push(path);
+        }
+    }
+    inputs
+}
+
+/// Device and inode of a directory, for -L loop detection.
+#[cfg(unix)]
+fn dir_id(meta: &std::fs::Metadata) -> Option<(u64, u64)> {
+    use std::os::unix::fs::MetadataExt;
+    Some((meta.dev(), meta.ino()))
+}
+
+#[cfg(not(unix))]
+fn dir_id(_meta: &std::fs::Metadata) -> Option<(u64, u64)> {
+    None
+}
+
+/// `-c` mode: verify each check file with the shared hash checker. With -a
+/// every line is read as that digest; without it each line names its own
+/// algorithm in its tag. Digests may be hex or `--base64` output.
+fn run_check(cli: &Cli, out: &mut impl Write, diag: &mut Diag) {
+    let mut total_mismatches = 0usize;
+    let mut total_format_errors = 0usize;
+    let mut total_read_errors = 0usize;
+
+    for filename in &cli.files {
+        if interrupt::interrupted() {
+            exit_interrupted(out);
+        }
+        let reader: Box<dyn BufRead> = if filename == "-" {
+            Box::new(io::BufReader::new(io::stdin().lock()))
+        } else {
+            match std::fs::File::open(filename) {
+                Ok(f) => Box::new(io::BufReader::new(f)),
                 Err(e) => {
-                    eprintln!(
-                        "{}: {}: {}",
-                        TOOL_NAME,
-                        filename,
-                        coreutils_rs::common::io_error_msg(&e)
-                    );
-                    exit_code = 1;
+                    diag.io_error(Some(filename.as_ref()), &e);
                     continue;
                 }
             }
         };
-
-        let result = if filename == "-" {
-            writeln!(out, "{} {}", crc, byte_count)
+        let display_name = if filename == "-" {
+            "standard input"
         } else {
-            writeln!(out, "{} {} {}", crc, byte_count, filename)
+            filename.as_str()
         };
-
-        if let Err(e) = result {
-            if e.kind() == io::ErrorKind::BrokenPipe {
-                process::exit(0);
+        let opts = hash::CheckOptions {
+            quiet: cli.quiet,
+            status_only: cli.status,
+            strict: cli.strict,
+            warn: cli.warn,
+            ignore_missing: cli.ignore_missing,
+            warn_prefix: format!("{}: {}", TOOL_NAME, quote(display_name.as_ref())),
+            direct_io: cli.direct_io,
+        };
+        let mut err_out = io::stderr();
+        let result = match cli.algorithm {
+            Some(algo) => hash::check_file(algo, reader, &opts, out, &mut err_out),
+            None => hash::check_file_tagged(reader, &opts, out, &mut err_out),
+        };
+        let r = match result {
+            Ok(r) => r,
+            Err(e) => {
+                let _ = out.flush();
+                diag.io_error(Some(filename.as_ref()), &e);
+                continue;
+            }
+        };
+        total_mismatches += r.mismatches;
+        total_read_errors += r.read_errors;
+        if r.mismatches > 0 || r.read_errors > 0 || (cli.strict && r.format_errors > 0) {
+            diag.fail();
+        }
+        if r.ok == 0 && r.mismatches == 0 && r.read_errors == 0 && r.ignored_missing == 0 {
+            // The "no properly formatted" message subsumes the format summary
+            let _ = out.flush();
+            if cli.status {
+                diag.fail();
+            } else {
+                diag.error(
+                    Some(display_name.as_ref()),
+                    &"no properly formatted checksum lines found",
+                );
+            }
+        } else {
+            total_format_errors += r.format_errors;
+        }
+        if cli.ignore_missing && r.ok == 0 && r.mismatches == 0 && r.ignored_missing > 0 {
+            let _ = out.flush();
+            if cli.status {
+                diag.fail();
+            } else {
+                diag.error(Some(display_name.as_ref()), &"no file was verified");
             }
-            eprintln!("{}: write error: {}", TOOL_NAME, e);
-            process::exit(1);
         }
     }
 
-    if let Err(e) = out.flush()
-        && e.kind() != io::ErrorKind::BrokenPipe
-    {
-        eprintln!("{}: write error: {}", TOOL_NAME, e);
-        process::exit(1);
+    let _ = out.flush();
+    if !cli.status {
+        if total_mismatches > 0 {
+            let word = if total_mismatches == 1 {
+                "computed checksum did NOT match"
+            } else {
+                "computed checksums did NOT match"
+            };
+            eprintln!("{}: WARNING: {} {}", TOOL_NAME, total_mismatches, word);
+        }
+        if total_read_errors > 0 {
+            let word = if total_read_errors == 1 {
+                "listed file could not be read"
+            } else {
+                "listed files could not be read"
+            };
+            eprintln!("{}: WARNING: {} {}", TOOL_NAME, total_read_errors, word);
+        }
+        if total_format_errors > 0 {
+            let word = if total_format_errors == 1 {
+                "line is"
+            } else {
+                "lines are"
+            };
+            eprintln!(
+                "{}: WARNING: {} {} improperly formatted",
+                TOOL_NAME, total_format_errors, word
+            );
+        }
     }
+}
 
-    process::exit(exit_code);
+/// xattr cache key: the lowercase -a name, with the digest length for BLAKE2b
+/// so it matches the key b2sum uses for the same digest.
+fn cache_key(cli: &Cli, algo: HashAlgorithm) -> String {
+    match algo {
+        HashAlgorithm::Blake2b => format!("blake2b-{}", cli.blake2b_bytes() * 8),
+        _ => algo.name().to_ascii_lowercase(),
+    }
 }
 
 #[cfg(test)]
@@ -286,6 +970,110 @@ mod tests {
         assert!(stdout.contains("CRC"));
     }
 
+    #[test]
+    fn test_option_parsing_matches_gnu() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("-w"), "x").unwrap();
+        // (args, exit code, stdout non-empty, expected first stderr line)
+        let cases: &[(&[&str], i32, bool, &str)] = &[
+            (
+                &["--bogus"],
+                1,
+                false,
+                "cksum: unrecognized option '--bogus'",
+            ),
+            (&["-x"], 1, false, "cksum: invalid option -- 'x'"),
+            (
+                &["-w", "--", "-w"],
+                1,
+                false,
+                "cksum: the --warn option is meaningful only when verifying checksums",
+            ),
+            (
+                &["-w"],
+                1,
+                false,
+                "cksum: the --warn option is meaningful only when verifying checksums",
+            ),
+            (
+                &["--status", "--quiet", "-"],
+                1,
+                false,
+                "cksum: the --status option is meaningful only when verifying checksums",
+            ),
+            (&["-", "--help"], 0, true, ""),
+            (&["--he"], 0, true, ""),
+            (
+                &["--", "--help"],
+                1,
+                false,
+                "cksum: --help: No such file or directory",
+            ),
+            (&["--", "-w"], 0, true, ""),
+            (
+                &["--tag=yes"],
+                1,
+                false,
+                "cksum: option '--tag' doesn't allow an argument",
+            ),
+            (
+                &["--untagged=yes"],
+                1,
+                false,
+                "cksum: option '--untagged' doesn't allow an argument",
+            ),
+            (
+                &["--st"],
+                1,
+                false,
+                "cksum: option '--st' is ambiguous; possibilities: '--status' '--strict'",
+            ),
+            (
+                &["--alg"],
+                1,
+                false,
+                "cksum: option '--algorithm' requires an argument",
+            ),
+            (&["--alg=md5", "-"], 0, true, ""),
+            (
+                &["-ca", "md5", "-"],
+                1,
+                false,
+                "cksum: 'standard input': no properly formatted checksum lines found",
+            ),
+            (
+                &["-a"],
+                1,
+                false,
+                "cksum: option requires an argument -- 'a'",
+            ),
+        ];
+        for &(args, code, has_stdout, stderr) in cases {
+            let output = cmd()
+                .args(args)
+                .current_dir(dir.path())
+                .stdin(std::process::Stdio::null())
+                .output()
+                .unwrap();
+            let err = String::from_utf8_lossy(&output.stderr);
+            assert_eq!(output.status.code(), Some(code), "{:?}: {}", args, err);
+            assert_eq!(!output.stdout.is_empty(), has_stdout, "{:?}", args);
+            assert_eq!(err.lines().next().unwrap_or(""), stderr, "{:?}", args);
+
+            // GNU's help and version text differ, so compare only the shape
+            if let Ok(gnu) = Command::new("cksum")
+                .args(args)
+                .current_dir(dir.path())
+                .stdin(std::process::Stdio::null())
+                .output()
+            {
+                assert_eq!(gnu.status.code(), Some(code), "GNU {:?}", args);
+                assert_eq!(!gnu.stdout.is_empty(), has_stdout, "GNU {:?}", args);
+                assert_eq!(gnu.stderr.is_empty(), stderr.is_empty(), "GNU {:?}", args);
+            }
+        }
+    }
+
     #[test]
     fn test_version() {
         let output = cmd().arg("--version").output().unwrap();
@@ -295,28 +1083,6 @@ mod tests {
         assert!(stdout.contains("fcoreutils"));
     }
 
-    #[test]
-    fn test_crc_table_correctness() {
-        // Verify first and last entries of the CRC table
-        assert_eq!(CRC_TABLE[0], 0);
-        assert_ne!(CRC_TABLE[255], 0);
-    }
-
-    #[test]
-    fn test_posix_cksum_empty() {
-        // Empty input: CRC feeds only length (0), so only complement of 0
-        let crc = posix_cksum(b"");
-        assert_eq!(crc, 4294967295); // !0 = 0xFFFFFFFF
-    }
-
-    #[test]
-    fn test_posix_cksum_hello() {
-        // Known POSIX CRC for "hello\n"
-        // GNU cksum gives: 3015617425 6
-        let crc = posix_cksum(b"hello\n");
-        assert_eq!(crc, 3015617425);
-    }
-
     #[test]
     fn test_stdin() {
         let mut child = cmd()
@@ -368,6 +1134,122 @@ mod tests {
         assert_eq!(lines.len(), 2, "should output one line per file");
     }
 
+    #[test]
+    fn test_parallel_output_keeps_argument_order() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut args = Vec::new();
+        for i in 0..200 {
+            let path = dir.path().join(format!("f{:03}", i));
+            if i == 50 || i == 150 {
+                args.push(path);
+                continue;
+            }
+            // A large first file makes the later ones finish first
+            let data = if i == 0 {
+                vec![b'x'; 8 << 20]
+            } else {
+                format!("file {}\n", i).into_bytes()
+            };
+            std::fs::write(&path, data).unwrap();
+            args.push(path);
+        }
+
+        for algo in [None, Some("md5")] {
+            // Share one file between stdout and stderr to see the interleaving
+            let log_path = dir.path().join("log");
+            let log = std::fs::File::create(&log_path).unwrap();
+            let mut c = cmd();
+            if let Some(algo) = algo {
+                c.args(["-a", algo, "--untagged"]);
+            }
+            let status = c
+                .args(&args)
+                .stdout(log.try_clone().unwrap())
+                .stderr(log)
+                .status()
+                .unwrap();
+            assert_eq!(status.code(), Some(1));
+
+            let log = std::fs::read_to_string(&log_path).unwrap();
+            let lines: Vec<&str> = log.lines().collect();
+            assert_eq!(lines.len(), 200);
+            for (i, line) in lines.iter().enumerate() {
+                let name = args[i].to_str().unwrap();
+                if i == 50 || i == 150 {
+                    assert_eq!(*line, format!("cksum: {}: No such file or directory", name));
+                } else {
+                    assert!(line.ends_with(&format!(" {}", name)), "{}", line);
+                }
+            }
+            if algo.is_none() {
+                let expected =
+                    format!("{} 7 {}", hash::posix_cksum(b"file 1\n"), args[1].display());
+                assert_eq!(lines[1], expected);
+            }
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_sigint_flushes_finished_files() {
+        use std::ffi::CString;
+        use std::os::unix::ffi::OsStrExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let (a, b, c) = (
+            dir.path().join("a"),
+            dir.path().join("b"),
+            dir.path().join("c"),
+        );
+        std::fs::write(&a, b"hello\n").unwrap();
+        std::fs::write(&b, b"world\n").unwrap();
+        std::fs::write(&c, b"after the fifo\n").unwrap();
+        let fifo = dir.path().join("fifo");
+        let fifo_c = CString::new(fifo.as_os_str().as_bytes()).unwrap();
+        assert_eq!(unsafe { libc::mkfifo(fifo_c.as_ptr(), 0o600) }, 0);
+        // Holding a write end open means the FIFO never reaches EOF
+        let _writer = std::fs::OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open(&fifo)
+            .unwrap();
+
+        for algo in [None, Some("md5")] {
+            let mut c_cmd = cmd();
+            if let Some(algo) = algo {
+                c_cmd.args(["-a", algo, "--untagged"]);
+            }
+            let child = c_cmd
+                .args([&a, &b, &fifo, &c])
+                .stdout(std::process::Stdio::piped())
+                .stderr(std::process::Stdio::piped())
+                .spawn()
+                .unwrap();
+            std::thread::sleep(std::time::Duration::from_millis(500));
+            unsafe { libc::kill(child.id() as libc::pid_t, libc::SIGINT) };
+            let output = child.wait_with_output().unwrap();
+
+            assert_eq!(output.status.code(), Some(130), "{:?}", algo);
+            assert_eq!(
+                String::from_utf8_lossy(&output.stderr),
+                "cksum: interrupted\n"
+            );
+            let stdout = String::from_utf8_lossy(&output.stdout);
+            let lines: Vec<&str> = stdout.lines().collect();
+            assert_eq!(lines.len(), 2, "{:?}: {}", algo, stdout);
+            assert!(
+                lines[0].ends_with(&format!(" {}", a.display())),
+                "{}",
+                stdout
+            );
+            assert!(
+                lines[1].ends_with(&format!(" {}", b.display())),
+                "{}",
+                stdout
+            );
+        }
+    }
+
     #[test]
     fn test_nonexistent_file() {
         let output = cmd().arg("/nonexistent/file.txt").output().unwrap();
@@ -447,4 +1329,722 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    fn test_algorithm_sha256_tagged() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("h.txt");
+        std::fs::write(&path, b"hello\n").unwrap();
+        let out = cmd().args(["-a", "sha256"]).arg(&path).output().unwrap();
+        assert!(out.status.success());
+        assert_eq!(
+            String::from_utf8_lossy(&out.stdout),
+            format!(
+                "SHA256 ({}) = 5891b5b522d5df086d0ff0b110fbd9d21bb4fc7163af34d08286a2e846f6be03\n",
+                path.display()
+            )
+        );
+
+        let out = cmd()
+            .args(["--algorithm=md5", "--untagged", "-"])
+            .stdin(std::process::Stdio::piped())
+            .stdout(std::process::Stdio::piped())
+            .spawn()
+            .and_then(|mut child| {
+                child.stdin.take().unwrap().write_all(b"hello\n")?;
+                child.wait_with_output()
+            })
+            .unwrap();
+        assert_eq!(out.stdout, b"b1946ac92492d2347c6235b4d2611184  -\n");
+    }
+
+    #[test]
+    fn test_algorithm_invalid() {
+        let out = cmd().args(["-a", "sha3"]).output().unwrap();
+        assert_eq!(out.status.code(), Some(1));
+        let err = String::from_utf8_lossy(&out.stderr);
+        assert!(err.starts_with("cksum: invalid argument 'sha3' for '--algorithm'"));
+    }
+
+    fn cached_run(path: &std::path::Path) -> (String, String) {
+        let out = cmd()
+            .args(["-a", "sha256", "--cache=xattr", "--debug"])
+            .arg(path)
+            .output()
+            .unwrap();
+        assert!(out.status.success());
+        (
+            String::from_utf8_lossy(&out.stdout).into_owned(),
+            String::from_utf8_lossy(&out.stderr).into_owned(),
+        )
+    }
+
+    #[test]
+    fn test_cache_xattr_debug_strategy() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("h.txt");
+        std::fs::write(&path, b"hello\n").unwrap();
+        let expected = format!(
+            "SHA256 ({}) = 5891b5b522d5df086d0ff0b110fbd9d21bb4fc7163af34d08286a2e846f6be03\n",
+            path.display()
+        );
+
+        let (stdout, stderr) = cached_run(&path);
+        assert_eq!(stdout, expected);
+        assert!(stderr.ends_with("digest computed\n"), "{}", stderr);
+
+        let (stdout, stderr) = cached_run(&path);
+        assert_eq!(stdout, expected);
+        if stderr.ends_with("digest computed\n") {
+            // No user xattr support here: the flag is a no-op.
+            return;
+        }
+        assert!(
+            stderr.ends_with("digest served from xattr cache\n"),
+            "{}",
+            stderr
+        );
+
+        // Touching the file invalidates the cached digest
+        let f = std::fs::OpenOptions::new().write(true).open(&path).unwrap();
+        f.set_modified(std::time::SystemTime::now() + std::time::Duration::from_secs(5))
+            .unwrap();
+        drop(f);
+        let (stdout, stderr) = cached_run(&path);
+        assert_eq!(stdout, expected);
+        assert!(stderr.ends_with("digest computed\n"), "{}", stderr);
+    }
+
+    #[test]
+    fn test_cache_never_default() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("h.txt");
+        std::fs::write(&path, b"hello\n").unwrap();
+        let out = cmd()
+            .args(["-a", "sha256", "--debug"])
+            .arg(&path)
+            .output()
+            .unwrap();
+        assert!(out.status.success());
+        assert!(out.stderr.is_empty());
+        // Without --cache nothing is written, so a cached run must compute.
+        let (_, stderr) = cached_run(&path);
+        assert!(stderr.ends_with("digest computed\n"), "{}", stderr);
+    }
+
+    #[test]
+    fn test_cache_invalid_mode() {
+        let out = cmd().args(["-a", "md5", "--cache=mtime"]).output().unwrap();
+        assert_eq!(out.status.code(), Some(1));
+        assert!(
+            String::from_utf8_lossy(&out.stderr)
+                .starts_with("cksum: invalid argument 'mtime' for '--cache'")
+        );
+    }
+
+    #[test]
+    fn test_length_validation_errors() {
+        let cases: &[(&[&str], &str)] = &[
+            (
+                &["-a", "blake2b", "-l", "127"],
+                "cksum: invalid length: '127'\ncksum: length is not a multiple of 8\n",
+            ),
+            (
+                &["-a", "blake2b", "-l", "520"],
+                "cksum: invalid length: '520'\n\
+                 cksum: maximum digest length for 'BLAKE2b' is 512 bits\n",
+            ),
+            (
+                &["-a", "sha256", "--length=128"],
+                "cksum: --length is only supported with --algorithm=blake2b\n",
+            ),
+            // The algorithm is checked before the maximum
+            (
+                &["-l", "1024"],
+                "cksum: --length is only supported with --algorithm=blake2b\n",
+            ),
+            // ...but a length that is not a multiple of 8 fails at once
+            (
+                &["-a", "crc", "-l7"],
+                "cksum: invalid length: '7'\ncksum: length is not a multiple of 8\n",
+            ),
+            (
+                &["-a", "blake2b", "-l", "-8"],
+                "cksum: invalid length: '-8'\n",
+            ),
+            (
+                &["-a", "blake2b", "-l", "99999999999999999999999"],
+                "cksum: invalid length: '99999999999999999999999': \
+                 Value too large for defined data type\n",
+            ),
+        ];
+        for &(args, stderr) in cases {
+            let out = cmd()
+                .args(args)
+                .stdin(std::process::Stdio::null())
+                .output()
+                .unwrap();
+            assert_eq!(out.status.code(), Some(1), "{:?}", args);
+            assert!(out.stdout.is_empty(), "{:?}", args);
+            assert_eq!(String::from_utf8_lossy(&out.stderr), stderr, "{:?}", args);
+        }
+    }
+
+    #[test]
+    fn test_length_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("a"), b"hello\n").unwrap();
+        let b2sum_works = Command::new("b2sum").arg("--version").output().is_ok();
+
+        for bits in ["128", "256", "512"] {
+            let bytes = bits.parse::<usize>().unwrap() / 8;
+            let digest = hash::blake2b_hash_data(b"hello\n", bytes);
+            let tag = if bits == "512" {
+                "BLAKE2b".to_string()
+            } else {
+                format!("BLAKE2b-{}", bits)
+            };
+            for untagged in [false, true] {
+                let mut gen_cmd = cmd();
+                gen_cmd
+                    .current_dir(dir.path())
+                    .args(["-a", "blake2b", "-l", bits]);
+                if untagged {
+                    gen_cmd.arg("--untagged");
+                }
+                let sums = gen_cmd.arg("a").output().unwrap();
+                assert!(sums.status.success());
+                let expected = if untagged {
+                    format!("{}  a\n", digest)
+                } else {
+                    format!("{} (a) = {}\n", tag, digest)
+                };
+                assert_eq!(String::from_utf8_lossy(&sums.stdout), expected);
+                if b2sum_works {
+                    let mut b2sum = Command::new("b2sum");
+                    if !untagged {
+                        b2sum.arg("--tag");
+                    }
+                    let gnu = b2sum
+                        .current_dir(dir.path())
+                        .args(["-l", bits, "a"])
+                        .output()
+                        .unwrap();
+                    assert_eq!(gnu.stdout, sums.stdout, "b2sum -l {}", bits);
+                }
+                std::fs::write(dir.path().join("sums"), &sums.stdout).unwrap();
+
+                // Untagged lines imply their length from the digest
+                let mut check = cmd();
+                check.current_dir(dir.path());
+                if untagged {
+                    check.args(["-a", "blake2b"]);
+                }
+                let out = check.args(["-c", "sums"]).output().unwrap();
+                assert_eq!(out.status.code(), Some(0), "{} {}", bits, expected);
+                assert_eq!(out.stdout, b"a: OK\n");
+            }
+        }
+    }
+
+    #[test]
+    fn test_base64_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("a"), b"hello\n").unwrap();
+        std::fs::write(dir.path().join("b"), b"").unwrap();
+
+        for algo in [
+            "md5", "sha1", "sha224", "sha256", "sha384", "sha512", "blake2b",
+        ] {
+            for untagged in [false, true] {
+                let mut gen_cmd = cmd();
+                gen_cmd
+                    .current_dir(dir.path())
+                    .args(["-a", algo, "--base64"]);
+                if untagged {
+                    gen_cmd.arg("--untagged");
+                }
+                let sums = gen_cmd.args(["a", "b"]).output().unwrap();
+                assert!(sums.status.success());
+                let text = String::from_utf8(sums.stdout).unwrap();
+                std::fs::write(dir.path().join("sums"), &text).unwrap();
+
+                // Tagged lines also check without -a
+                let mut check = cmd();
+                check.current_dir(dir.path());
+                if untagged {
+                    check.args(["-a", algo]);
+                }
+                let out = check.args(["-c", "sums"]).output().unwrap();
+                assert_eq!(out.status.code(), Some(0), "{} {}", algo, text);
+                assert_eq!(out.stdout, b"a: OK\nb: OK\n");
+            }
+        }
+
+        // A changed file fails the base64 line
+        std::fs::write(dir.path().join("a"), b"changed\n").unwrap();
+        let out = cmd()
+            .current_dir(dir.path())
+            .args(["-a", "blake2b", "-c", "sums"])
+            .output()
+            .unwrap();
+        assert_eq!(out.status.code(), Some(1));
+        assert_eq!(out.stdout, b"a: FAILED\nb: OK\n");
+    }
+
+    #[test]
+    fn test_base64_check_against_gnu() {
+        // Only GNU cksum 9.2 and later understand --base64
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("a"), b"hello\n").unwrap();
+        let gnu = Command::new("cksum")
+            .current_dir(dir.path())
+            .args(["-a", "sha256", "--base64", "a"])
+            .output();
+        let Ok(gnu) = gnu else { return };
+        if !gnu.status.success() {
+            return;
+        }
+        let ours = cmd()
+            .current_dir(dir.path())
+            .args(["-a", "sha256", "--base64", "a"])
+            .output()
+            .unwrap();
+        assert_eq!(ours.stdout, gnu.stdout);
+
+        std::fs::write(dir.path().join("sums"), &ours.stdout).unwrap();
+        let gnu_check = Command::new("cksum")
+            .current_dir(dir.path())
+            .args(["-c", "sums"])
+            .output()
+            .unwrap();
+        assert_eq!(gnu_check.status.code(), Some(0));
+        assert_eq!(gnu_check.stdout, b"a: OK\n");
+    }
+
+    #[test]
+    fn test_uppercase_output() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("a"), b"hello\n").unwrap();
+        let upper = cmd()
+            .current_dir(dir.path())
+            .args(["-a", "sha256", "--uppercase", "a"])
+            .output()
+            .unwrap();
+        let lower = cmd()
+            .current_dir(dir.path())
+            .args(["-a", "sha256", "a"])
+            .output()
+            .unwrap();
+        let (upper, lower) = (
+            String::from_utf8(upper.stdout).unwrap(),
+            String::from_utf8(lower.stdout).unwrap(),
+        );
+        let digest = lower.trim_end().rsplit(' ').next().unwrap();
+        assert_eq!(upper, format!("SHA256 (a) = {}\n", digest.to_uppercase()));
+
+        std::fs::write(dir.path().join("sums"), &upper).unwrap();
+        let out = cmd()
+            .current_dir(dir.path())
+            .args(["-c", "sums"])
+            .output()
+            .unwrap();
+        assert_eq!(out.status.code(), Some(0));
+        assert_eq!(out.stdout, b"a: OK\n");
+
+        // CRC output has no hex digits to case
+        let crc = cmd()
+            .current_dir(dir.path())
+            .args(["--uppercase", "a"])
+            .output()
+            .unwrap();
+        assert_eq!(crc.stdout, b"3015617425 6 a\n");
+    }
+
+    #[test]
+    fn test_raw_output() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("h.txt");
+        std::fs::write(&path, b"hello\n").unwrap();
+
+        let out = cmd()
+            .args(["-a", "md5", "--raw"])
+            .arg(&path)
+            .output()
+            .unwrap();
+        assert!(out.status.success());
+        assert_eq!(
+            out.stdout,
+            hash::hex_decode("b1946ac92492d2347c6235b4d2611184").unwrap()
+        );
+
+        // CRC is written in network byte order
+        let out = cmd().arg("--raw").arg(&path).output().unwrap();
+        assert_eq!(out.stdout, 3015617425u32.to_be_bytes());
+
+        // Raw digests cannot be listed per file or checked
+        let out = cmd()
+            .args(["-a", "md5", "--raw"])
+            .args([&path, &path])
+            .output()
+            .unwrap();
+        assert_eq!(out.status.code(), Some(1));
+        assert!(
+            String::from_utf8_lossy(&out.stderr)
+                .starts_with("cksum: the --raw option is not supported with multiple files")
+        );
+        let out = cmd().args(["--raw", "-c"]).arg(&path).output().unwrap();
+        assert_eq!(out.status.code(), Some(1));
+    }
+
+    #[test]
+    fn test_check_no_properly_formatted_lines() {
+        let dir = tempfile::tempdir().unwrap();
+        let sums = dir.path().join("sums");
+        std::fs::write(&sums, b"3015617425 6 a\n").unwrap();
+        let out = cmd().arg("-c").arg(&sums).output().unwrap();
+        assert_eq!(out.status.code(), Some(1));
+        assert_eq!(
+            String::from_utf8_lossy(&out.stderr),
+            format!(
+                "cksum: {}: no properly formatted checksum lines found\n",
+                sums.display()
+            )
+        );
+    }
+
+    /// `cksum -a ALGO h.txt` of "hello\n" captured from GNU cksum 9.1, as
+    /// (algorithm, tag, hex digest).
+    const GOLDEN_DIGESTS: &[(&str, &str, &str)] = &[
+        ("md5", "MD5", "b1946ac92492d2347c6235b4d2611184"),
+        ("sha1", "SHA1", "f572d396fae9206628714fb2ce00f72e94f2258f"),
+        (
+            "sha224",
+            "SHA224",
+            "2d6d67d91d0badcdd06cbbba1fe11538a68a37ec9c2e26457ceff12b",
+        ),
+        (
+            "sha256",
+            "SHA256",
+            "5891b5b522d5df086d0ff0b110fbd9d21bb4fc7163af34d08286a2e846f6be03",
+        ),
+        (
+            "sha384",
+            "SHA384",
+            "1d0f284efe3edea4b9ca3bd514fa134b17eae361ccc7a1eefeff801b9bd6604e\
+             01f21f6bf249ef030599f0c218f2ba8c",
+        ),
+        (
+            "sha512",
+            "SHA512",
+            "e7c22b994c59d9cf2b48e549b1e24666636045930d3da7c1acb299d1c3b7f931\
+             f94aae41edda2c2b207a36e10f8bcb8d45223e54878f5b316e7ce3b6bc019629",
+        ),
+        (
+            "blake2b",
+            "BLAKE2b",
+            "f60ce482e5cc1229f39d71313171a8d9f4ca3a87d066bf4b205effb528192a75\
+             f14f3271e2c1a90e1de53f275b4d4793eef2f5e31ea90d2ce29d2e481c36435f",
+        ),
+    ];
+
+    /// Tag-mode flag sets and whether GNU then prints the tagged layout.
+    const TAG_MODES: &[(&[&str], bool)] = &[
+        (&[], true),
+        (&["--tag"], true),
+        (&["--untagged"], false),
+        (&["--untagged", "--tag"], true),
+        (&["--tag", "--untagged"], false),
+    ];
+
+    fn run_in(dir: &Path, args: &[&str]) -> std::process::Output {
+        cmd().current_dir(dir).args(args).output().unwrap()
+    }
+
+    #[test]
+    fn test_tag_modes_match_gnu_golden() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("h.txt"), b"hello\n").unwrap();
+        for &(algo, tag, hex) in GOLDEN_DIGESTS {
+            for &(flags, tagged) in TAG_MODES {
+                let mut args = vec!["-a", algo];
+                args.extend_from_slice(flags);
+                args.push("h.txt");
+                let out = run_in(dir.path(), &args);
+                assert!(out.status.success(), "{:?}", args);
+                let expected = if tagged {
+                    format!("{} (h.txt) = {}\n", tag, hex)
+                } else {
+                    format!("{}  h.txt\n", hex)
+                };
+                assert_eq!(String::from_utf8_lossy(&out.stdout), expected, "{:?}", args);
+            }
+        }
+    }
+
+    #[test]
+    fn test_tag_modes_ignored_for_crc() {
+        // GNU 9.1 accepts both flags with the CRC and keeps its own layout
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("h.txt"), b"hello\n").unwrap();
+        for &(flags, _) in TAG_MODES {
+            for algo in [None, Some("crc")] {
+                let mut args = Vec::new();
+                if let Some(algo) = algo {
+                    args.extend(["-a", algo]);
+                }
+                args.extend_from_slice(flags);
+                args.push("h.txt");
+                let out = run_in(dir.path(), &args);
+                assert!(out.status.success(), "{:?}", args);
+                assert!(out.stderr.is_empty(), "{:?}", args);
+                assert_eq!(
+                    String::from_utf8_lossy(&out.stdout),
+                    "3015617425 6 h.txt\n",
+                    "{:?}",
+                    args
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_escaped_names_match_gnu_and_check() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("back\\slash"), b"hello\n").unwrap();
+        let md5 = GOLDEN_DIGESTS[0].2;
+        for (flags, expected) in [
+            (&[][..], format!("\\MD5 (back\\\\slash) = {}\n", md5)),
+            (&["--untagged"][..], format!("\\{}  back\\\\slash\n", md5)),
+        ] {
+            let mut args = vec!["-a", "md5"];
+            args.extend_from_slice(flags);
+            args.push("back\\slash");
+            let out = run_in(dir.path(), &args);
+            assert_eq!(String::from_utf8_lossy(&out.stdout), expected);
+
+            std::fs::write(dir.path().join("sums"), &expected).unwrap();
+            let out = run_in(dir.path(), &["-a", "md5", "-c", "sums"]);
+            assert!(out.status.success(), "{:?}", flags);
+            assert_eq!(String::from_utf8_lossy(&out.stdout), "back\\slash: OK\n");
+        }
+    }
+
+    #[test]
+    fn test_check_accepts_untagged_only_with_algorithm() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("h.txt"), b"hello\n").unwrap();
+        for &(algo, _, _) in GOLDEN_DIGESTS {
+            let out = run_in(dir.path(), &["-a", algo, "--untagged", "h.txt"]);
+            std::fs::write(dir.path().join("sums"), &out.stdout).unwrap();
+            let out = run_in(dir.path(), &["-a", algo, "-c", "sums"]);
+            assert!(out.status.success(), "{}", algo);
+            assert_eq!(String::from_utf8_lossy(&out.stdout), "h.txt: OK\n");
+
+            let out = run_in(dir.path(), &["-c", "sums"]);
+            assert_eq!(out.status.code(), Some(1), "{}", algo);
+            assert_eq!(
+                String::from_utf8_lossy(&out.stderr),
+                "cksum: sums: no properly formatted checksum lines found\n"
+            );
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_truncated_while_hashing() {
+        use std::os::unix::process::ExitStatusExt;
+        use std::time::Duration;
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("big");
+        let data: Vec<u8> = (0..128 * 1024 * 1024u32)
+            .map(|i| (i * 31 % 251) as u8)
+            .collect();
+        // The hash must still be running when the file shrinks; retry with
+        // shorter head starts until it is
+        for delay in [200, 100, 50, 20, 5] {
+            std::fs::write(&path, &data).unwrap();
+            let child = cmd()
+                .args(["-a", "sha512"])
+                .arg(&path)
+                .stdout(std::process::Stdio::piped())
+                .stderr(std::process::Stdio::piped())
+                .spawn()
+                .unwrap();
+            std::thread::sleep(Duration::from_millis(delay));
+            std::fs::OpenOptions::new()
+                .write(true)
+                .open(&path)
+                .unwrap()
+                .set_len(4096)
+                .unwrap();
+            let output = child.wait_with_output().unwrap();
+            assert_eq!(output.status.signal(), None, "killed by a signal");
+            if output.status.success() {
+                continue;
+            }
+            assert_eq!(output.status.code(), Some(1));
+            assert_eq!(
+                String::from_utf8_lossy(&output.stderr),
+                format!("cksum: {}: file changed while reading\n", path.display())
+            );
+            return;
+        }
+        panic!("the hash always finished before the truncation");
+    }
+
+    #[test]
+    fn test_direct_io_same_output() {
+        let dir = tempfile::tempdir().unwrap();
+        let big = dir.path().join("big");
+        let tiny = dir.path().join("tiny");
+        let data: Vec<u8> = (0..3 * 1024 * 1024 + 5)
+            .map(|i| (i * 31 % 251) as u8)
+            .collect();
+        std::fs::write(&big, &data).unwrap();
+        std::fs::write(&tiny, b"hello\n").unwrap();
+
+        for algo in [None, Some("sha256"), Some("blake2b")] {
+            let run = |direct: bool| {
+                let mut c = cmd();
+                if let Some(a) = algo {
+                    c.args(["-a", a]);
+                }
+                if direct {
+                    c.arg("--direct-io");
+                }
+                c.arg(&big).arg(&tiny).output().unwrap()
+            };
+            let plain = run(false);
+            let direct = run(true);
+            assert!(direct.status.success(), "{algo:?}");
+            assert_eq!(direct.stdout, plain.stdout, "{algo:?}");
+        }
+
+        // Verification reads the listed files with O_DIRECT too
+        let sums = cmd()
+            .args(["-a", "sha256"])
+            .arg(&big)
+            .arg(&tiny)
+            .output()
+            .unwrap();
+        let manifest = dir.path().join("SUMS");
+        std::fs::write(&manifest, &sums.stdout).unwrap();
+        let output = cmd()
+            .args(["--direct-io", "-c"])
+            .arg(&manifest)
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        assert_eq!(stdout.matches(": OK\n").count(), 2, "{stdout}");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_recursive_tree_sorted_and_checkable() {
+        use std::os::unix::fs::symlink;
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().join("layer");
+        std::fs::create_dir_all(root.join("a/b")).unwrap();
+        std::fs::create_dir_all(root.join("a-c")).unwrap();
+        std::fs::write(root.join("a/b/deep"), b"deep\n").unwrap();
+        std::fs::write(root.join("a-c/dash"), b"dash\n").unwrap();
+        std::fs::write(root.join("Top"), b"top\n").unwrap();
+        symlink("Top", root.join("link")).unwrap();
+        symlink("..", root.join("a/up")).unwrap();
+        let fifo = std::ffi::CString::new(root.join("pipe").into_os_string().into_encoded_bytes())
+            .unwrap();
+        assert_eq!(unsafe { libc::mkfifo(fifo.as_ptr(), 0o644) }, 0);
+
+        let out = run_in(dir.path(), &["-a", "sha256", "-r", "--warn", "layer"]);
+        assert!(out.status.success());
+        let stdout = String::from_utf8(out.stdout).unwrap();
+        // Byte order puts "a-c/" before "a/" and "Top" before both; the
+        // symlinks and the fifo are skipped, with a warning each
+        let names: Vec<&str> = stdout
+            .lines()
+            .map(|l| &l[l.find('(').unwrap() + 1..l.find(')').unwrap()])
+            .collect();
+        assert_eq!(names, ["Top", "a-c/dash", "a/b/deep"]);
+        let stderr = String::from_utf8_lossy(&out.stderr);
+        for skipped in ["layer/link", "layer/a/up", "layer/pipe"] {
+            assert!(
+                stderr.contains(&format!("cksum: warning: {}: not a regular file", skipped)),
+                "{stderr}"
+            );
+        }
+        // Same listing from another working directory, and without --warn
+        // nothing is reported
+        let again = run_in(&root, &["-a", "sha256", "-r", "."]);
+        assert_eq!(String::from_utf8(again.stdout).unwrap(), stdout);
+        assert!(again.stderr.is_empty());
+
+        std::fs::write(dir.path().join("SUMS"), &stdout).unwrap();
+        let check = run_in(&root, &["-c", "../SUMS"]);
+        assert!(check.status.success());
+        assert_eq!(
+            String::from_utf8_lossy(&check.stdout),
+            "Top: OK\na-c/dash: OK\na/b/deep: OK\n"
+        );
+
+        // -L hashes the file behind the symlink and stops at the loop
+        let out = run_in(&root, &["-rL", "."]);
+        assert_eq!(out.status.code(), Some(1));
+        let stdout = String::from_utf8_lossy(&out.stdout);
+        let names: Vec<&str> = stdout
+            .lines()
+            .map(|l| l.rsplit(' ').next().unwrap())
+            .collect();
+        assert_eq!(names, ["Top", "a-c/dash", "a/b/deep", "link"]);
+        assert!(
+            String::from_utf8_lossy(&out.stderr)
+                .contains("cksum: ./a/up: file system loop detected"),
+        );
+    }
+
+    #[test]
+    fn test_recursive_usage_errors() {
+        for (args, msg) in [
+            (
+                &["-r", "-c", "x"][..],
+                "--recursive option is not supported with --check",
+            ),
+            (
+                &["-r", "--raw", "x"][..],
+                "--raw option is not supported with --recursive",
+            ),
+            (
+                &["-L", "x"][..],
+                "--dereference option is meaningful only with --recursive",
+            ),
+            (
+                &["--warn", "x"][..],
+                "--warn option is meaningful only when verifying",
+            ),
+        ] {
+            let out = cmd().args(args).output().unwrap();
+            assert_eq!(out.status.code(), Some(1), "{args:?}");
+            assert!(
+                String::from_utf8_lossy(&out.stderr).contains(msg),
+                "{args:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_error_keeps_utf8_file_name() {
+        let dir = tempfile::tempdir().unwrap();
+        let output = cmd()
+            .arg("café-missing")
+            .current_dir(dir.path())
+            .output()
+            .unwrap();
+        assert_eq!(output.status.code(), Some(1));
+        assert_eq!(
+            String::from_utf8_lossy(&output.stderr),
+            "cksum: café-missing: No such file or directory\n"
+        );
+    }
 }
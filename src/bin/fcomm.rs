@@ -1,20 +1,118 @@
-use std::io::{self, BufWriter, Write};
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
 use std::path::Path;
 use std::process;
 
-use coreutils_rs::comm::{self, CommConfig, OrderCheck};
-use coreutils_rs::common::io::{read_file, read_stdin};
+use coreutils_rs::comm::{self, CommConfig, CommError, OrderCheck};
+use coreutils_rs::common::diag;
+use coreutils_rs::common::io::{FileData, read_file, read_stdin};
 use coreutils_rs::common::io_error_msg;
 
+/// How `--decompress` treats the inputs.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Decompress {
+    /// Read the inputs as they are (the default).
+    None,
+    /// Decompress inputs that start with the gzip magic bytes.
+    Auto,
+    /// Decompress every input.
+    Gzip,
+}
+
+/// A set-operation shorthand for a choice of columns, printed unprefixed.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum SetOp {
+    /// --symmetric-difference (-u): columns 1 and 2, as `-3` would.
+    SymmetricDifference,
+    /// --intersection: column 3, as `-12` would.
+    Intersection,
+}
+
+impl SetOp {
+    fn option(self) -> &'static str {
+        match self {
+            SetOp::SymmetricDifference => "--symmetric-difference",
+            SetOp::Intersection => "--intersection",
+        }
+    }
+}
+
 struct Cli {
     config: CommConfig,
     files: Vec<String>,
+    decompress: Decompress,
+    set_op: Option<SetOp>,
+}
+
+/// Record a set operation; asking for both is a usage error.
+fn set_op(cli: &mut Cli, op: SetOp) {
+    if let Some(prev) = cli.set_op
+        && prev != op
+    {
+        usage_error(&format!(
+            "{} cannot be combined with {}",
+            prev.option(),
+            op.option()
+        ));
+    }
+    cli.set_op = Some(op);
+}
+
+fn usage_error(msg: &str) -> ! {
+    eprintln!("comm: {}", msg);
+    eprintln!("Try 'comm --help' for more information.");
+    process::exit(1);
+}
+
+/// Turn a set operation into the column suppression it stands for. The
+/// columns and their separators are its to choose, so explicit -1, -2, -3
+/// or --output-delimiter cannot go with it.
+fn apply_set_op(cli: &mut Cli) {
+    let Some(op) = cli.set_op else {
+        return;
+    };
+    let config = &mut cli.config;
+    if config.suppress_col1 || config.suppress_col2 || config.suppress_col3 {
+        usage_error(&format!(
+            "{} cannot be combined with -1, -2 or -3",
+            op.option()
+        ));
+    }
+    if config.output_delimiter.is_some() {
+        usage_error(&format!(
+            "{} cannot be combined with --output-delimiter",
+            op.option()
+        ));
+    }
+    match op {
+        SetOp::SymmetricDifference => config.suppress_col3 = true,
+        SetOp::Intersection => {
+            config.suppress_col1 = true;
+            config.suppress_col2 = true;
+        }
+    }
+    config.no_column_prefix = true;
+}
+
+fn parse_decompress(val: &str) -> Decompress {
+    match val {
+        "auto" => Decompress::Auto,
+        "gzip" => Decompress::Gzip,
+        "none" => Decompress::None,
+        _ => {
+            eprintln!("comm: invalid argument '{}' for '--decompress'", val);
+            eprintln!("Valid arguments are:\n  - 'auto'\n  - 'gzip'\n  - 'none'");
+            eprintln!("Try 'comm --help' for more information.");
+            process::exit(1);
+        }
+    }
 }
 
 fn parse_args() -> Cli {
     let mut cli = Cli {
         config: CommConfig::default(),
         files: Vec::new(),
+        decompress: Decompress::None,
+        set_op: None,
     };
 
     let mut args = std::env::args_os().skip(1);
@@ -31,11 +129,19 @@ fn parse_args() -> Cli {
             let s = arg.to_string_lossy();
             if let Some(val) = s.strip_prefix("--output-delimiter=") {
                 cli.config.output_delimiter = Some(val.as_bytes().to_vec());
+            } else if let Some(val) = s.strip_prefix("--decompress=") {
+                cli.decompress = parse_decompress(val);
             } else {
                 match bytes {
                     b"--case-insensitive" => cli.config.case_insensitive = true,
                     b"--check-order" => cli.config.order_check = OrderCheck::Strict,
                     b"--nocheck-order" => cli.config.order_check = OrderCheck::None,
+                    b"--no-column-prefix" => cli.config.no_column_prefix = true,
+                    b"--show-line-numbers" => cli.config.show_line_numbers = true,
+                    b"--sort" => cli.config.sort = true,
+                    b"--symmetric-difference" => set_op(&mut cli, SetOp::SymmetricDifference),
+                    b"--intersection" => set_op(&mut cli, SetOp::Intersection),
+                    b"--verbose-order-errors" => cli.config.verbose_order_errors = true,
                     b"--output-delimiter" => {
                         let val = args.next().unwrap_or_else(|| {
                             eprintln!("comm: option '--output-delimiter' requires an argument");
@@ -43,6 +149,14 @@ fn parse_args() -> Cli {
                         });
                         cli.config.output_delimiter = Some(val.as_encoded_bytes().to_vec());
                     }
+                    b"--decompress" => {
+                        let val = args.next().unwrap_or_else(|| {
+                            eprintln!("comm: option '--decompress' requires an argument");
+                            eprintln!("Try 'comm --help' for more information.");
+                            process::exit(1);
+                        });
+                        cli.decompress = parse_decompress(&val.to_string_lossy());
+                    }
                     b"--total" => cli.config.total = true,
                     b"--zero-terminated" => cli.config.zero_terminated = true,
                     b"--help" => {
@@ -50,7 +164,7 @@ fn parse_args() -> Cli {
                         process::exit(0);
                     }
                     b"--version" => {
-                        println!("comm (fcoreutils) {}", env!("CARGO_PKG_VERSION"));
+                        coreutils_rs::common::version::print_version("comm");
                         process::exit(0);
                     }
                     _ => {
@@ -61,13 +175,14 @@ fn parse_args() -> Cli {
                 }
             }
         } else if bytes.len() > 1 && bytes[0] == b'-' {
-            // Short options: -1, -2, -3, -i, -z (can be combined)
+            // Short options: -1, -2, -3, -i, -u, -z (can be combined)
             for &b in &bytes[1..] {
                 match b {
                     b'1' => cli.config.suppress_col1 = true,
                     b'2' => cli.config.suppress_col2 = true,
                     b'3' => cli.config.suppress_col3 = true,
                     b'i' => cli.config.case_insensitive = true,
+                    b'u' => set_op(&mut cli, SetOp::SymmetricDifference),
                     b'z' => cli.config.zero_terminated = true,
                     _ => {
                         eprintln!("comm: invalid option -- '{}'", b as char);
@@ -81,6 +196,7 @@ fn parse_args() -> Cli {
         }
     }
 
+    apply_set_op(&mut cli);
     cli
 }
 
@@ -99,7 +215,23 @@ fn print_help() {
          \x20 --check-order   check that the input is correctly sorted, even\n\
          \x20                   if all input lines are pairable\n\
          \x20 --nocheck-order do not check that the input is correctly sorted\n\
+         \x20 --verbose-order-errors  when an input is unsorted, also show the\n\
+         \x20                   line number and the start of the offending line\n\
+         \x20                   and the one before it\n\
          \x20 --output-delimiter=STR  separate columns with STR\n\
+         \x20 --no-column-prefix  do not indent lines by column; every selected\n\
+         \x20                   line starts at the left margin\n\
+         \x20 -u, --symmetric-difference  output the lines unique to either file,\n\
+         \x20                   unindented (-3 --no-column-prefix)\n\
+         \x20 --intersection  output the lines common to both files, unindented\n\
+         \x20                   (-12)\n\
+         \x20 --show-line-numbers  start each line with its line number in FILE1\n\
+         \x20                   (1:N), FILE2 (2:M) or both (1:N,2:M), then a delimiter\n\
+         \x20 --sort          sort an input that is not in sorted order before\n\
+         \x20                   comparing, in memory; sorted inputs are only checked\n\
+         \x20 --decompress=WHEN  read gzip-compressed inputs: 'auto' detects\n\
+         \x20                   them, 'gzip' decompresses every input, 'none'\n\
+         \x20                   (the default) reads inputs as they are\n\
          \x20 --total          output a summary\n\
          \x20 -z, --zero-terminated    line delimiter is NUL, not newline\n\
          \x20     --help       display this help and exit\n\
@@ -107,10 +239,10 @@ fn print_help() {
     );
 }
 
-fn read_input(filename: &str, tool_name: &str) -> coreutils_rs::common::io::FileData {
+fn read_input(filename: &str, tool_name: &str) -> FileData {
     if filename == "-" {
         match read_stdin() {
-            Ok(d) => coreutils_rs::common::io::FileData::Owned(d),
+            Ok(d) => FileData::Owned(d),
             Err(e) => {
                 eprintln!("{}: standard input: {}", tool_name, io_error_msg(&e));
                 process::exit(1);
@@ -127,6 +259,148 @@ fn read_input(filename: &str, tool_name: &str) -> coreutils_rs::common::io::File
     }
 }
 
+/// Whether two operands name the same file (same device and inode).
+#[cfg(unix)]
+fn same_file(a: &str, b: &str) -> bool {
+    use std::os::unix::fs::MetadataExt;
+    match (std::fs::metadata(a), std::fs::metadata(b)) {
+        (Ok(a), Ok(b)) => a.is_file() && a.dev() == b.dev() && a.ino() == b.ino(),
+        _ => false,
+    }
+}
+
+#[cfg(not(unix))]
+fn same_file(_a: &str, _b: &str) -> bool {
+    false
+}
+
+/// Open an input for `comm_streams`, decompressing it if `decompress`
+/// says so. The flag is true for a gzip input.
+fn open_stream(
+    filename: &str,
+    decompress: Decompress,
+    tool_name: &str,
+) -> (Box<dyn BufRead>, bool) {
+    let mut raw: Box<dyn BufRead> = if filename == "-" {
+        Box::new(BufReader::with_capacity(256 * 1024, io::stdin()))
+    } else {
+        match std::fs::File::open(filename) {
+            Ok(f) => Box::new(BufReader::with_capacity(256 * 1024, f)),
+            Err(e) => {
+                eprintln!("{}: {}: {}", tool_name, filename, io_error_msg(&e));
+                process::exit(1);
+            }
+        }
+    };
+    let gzip = match decompress {
+        Decompress::None => false,
+        Decompress::Gzip => true,
+        Decompress::Auto => match raw.fill_buf() {
+            Ok(head) => head.starts_with(&[0x1f, 0x8b]),
+            Err(e) => {
+                eprintln!(
+                    "{}: {}: {}",
+                    tool_name,
+                    display_name(filename),
+                    io_error_msg(&e)
+                );
+                process::exit(1);
+            }
+        },
+    };
+    if gzip {
+        let decoder = flate2::bufread::MultiGzDecoder::new(raw);
+        (
+            Box::new(BufReader::with_capacity(256 * 1024, decoder)),
+            true,
+        )
+    } else {
+        (raw, false)
+    }
+}
+
+fn display_name(filename: &str) -> &str {
+    if filename == "-" {
+        "standard input"
+    } else {
+        filename
+    }
+}
+
+/// The --decompress path: merge the inputs as streams. Never returns.
+fn run_streams(cli: &Cli, tool_name: &str) -> ! {
+    let (input1, gzip1) = open_stream(&cli.files[0], cli.decompress, tool_name);
+    // As in the in-memory path, `comm - -` reads stdin once, as FILE1
+    let (input2, gzip2) = if cli.files[0] == "-" && cli.files[1] == "-" {
+        diag::warning(
+            tool_name,
+            None,
+            &"standard input given for both FILE1 and FILE2; treating FILE2 as empty",
+        );
+        (Box::new(io::empty()) as Box<dyn BufRead>, false)
+    } else {
+        open_stream(&cli.files[1], cli.decompress, tool_name)
+    };
+
+    let stdout = io::stdout();
+    let mut out = BufWriter::with_capacity(256 * 1024, stdout.lock());
+
+    match comm::comm_streams(input1, input2, &cli.config, tool_name, &mut out) {
+        Ok(result) => finish(result, out, tool_name),
+        Err(CommError::Read { file, error }) => {
+            let _ = out.flush();
+            let (name, gzip) = if file == 1 {
+                (&cli.files[0], gzip1)
+            } else {
+                (&cli.files[1], gzip2)
+            };
+            let name = display_name(name);
+            // flate2 reports a bad header, a corrupt deflate stream or a
+            // bad trailer as InvalidInput, and truncation as UnexpectedEof
+            let corrupt = matches!(
+                error.kind(),
+                io::ErrorKind::InvalidInput
+                    | io::ErrorKind::InvalidData
+                    | io::ErrorKind::UnexpectedEof
+            );
+            if gzip && corrupt {
+                eprintln!("{}: {}: invalid gzip data", tool_name, name);
+            } else {
+                eprintln!("{}: {}: {}", tool_name, name, io_error_msg(&error));
+            }
+            process::exit(1);
+        }
+        Err(CommError::Write(e)) => write_failed(e, out, tool_name),
+    }
+}
+
+/// Flush the output and exit with the status the merge result calls for.
+fn finish(result: comm::CommResult, mut out: impl Write, tool_name: &str) -> ! {
+    if let Err(e) = out.flush() {
+        if e.kind() != io::ErrorKind::BrokenPipe {
+            eprintln!("{}: write error: {}", tool_name, io_error_msg(&e));
+        }
+        process::exit(1);
+    }
+    match result.order_error {
+        Some(comm::OrderErrorKind::Unsorted { .. }) => {
+            eprintln!("{}: input is not in sorted order", tool_name);
+            process::exit(1);
+        }
+        Some(comm::OrderErrorKind::Stopped { .. }) => process::exit(1),
+        None => process::exit(0),
+    }
+}
+
+fn write_failed(e: io::Error, mut out: impl Write, tool_name: &str) -> ! {
+    if e.kind() == io::ErrorKind::BrokenPipe {
+        let _ = out.flush();
+        process::exit(0);
+    }
+    eprintln!("{}: write error: {}", tool_name, io_error_msg(&e));
+    process::exit(1);
+}
+
 fn main() {
     coreutils_rs::common::reset_sigpipe();
 
@@ -149,31 +423,47 @@ fn main() {
         process::exit(1);
     }
 
+    if cli.config.sort && cli.config.order_check == OrderCheck::Strict {
+        eprintln!(
+            "{}: --sort cannot be combined with --check-order",
+            tool_name
+        );
+        eprintln!("Try 'comm --help' for more information.");
+        process::exit(1);
+    }
+    if cli.config.sort && cli.decompress != Decompress::None {
+        eprintln!("{}: --sort cannot be combined with --decompress", tool_name);
+        eprintln!("Try 'comm --help' for more information.");
+        process::exit(1);
+    }
+
+    if cli.decompress != Decompress::None {
+        run_streams(&cli, tool_name);
+    }
+
     let data1 = read_input(&cli.files[0], tool_name);
-    let data2 = read_input(&cli.files[1], tool_name);
+    // `comm f f` maps the file once and compares it with itself. `comm - -`
+    // would have both operands race for one stream (GNU's result depends on
+    // stdio buffering), so stdin is read once, as FILE1, and FILE2 is empty.
+    let data2 = if cli.files[0] == "-" && cli.files[1] == "-" {
+        diag::warning(
+            tool_name,
+            None,
+            &"standard input given for both FILE1 and FILE2; treating FILE2 as empty",
+        );
+        Some(FileData::Owned(Vec::new()))
+    } else if cli.files[0] != "-" && same_file(&cli.files[0], &cli.files[1]) {
+        None
+    } else {
+        Some(read_input(&cli.files[1], tool_name))
+    };
+    let data2: &[u8] = data2.as_deref().unwrap_or(&data1);
 
     let stdout = io::stdout();
     let mut out = BufWriter::with_capacity(256 * 1024, stdout.lock());
 
-    match comm::comm(&data1, &data2, &cli.config, tool_name, &mut out) {
-        Ok(result) => {
-            if let Err(e) = out.flush() {
-                if e.kind() != io::ErrorKind::BrokenPipe {
-                    eprintln!("{}: write error: {}", tool_name, io_error_msg(&e));
-                }
-                process::exit(1);
-            }
-            if result.had_order_error {
-                process::exit(1);
-            }
-        }
-        Err(e) => {
-            if e.kind() == io::ErrorKind::BrokenPipe {
-                let _ = out.flush();
-                process::exit(0);
-            }
-            eprintln!("{}: write error: {}", tool_name, io_error_msg(&e));
-            process::exit(1);
-        }
+    match comm::comm(&data1, data2, &cli.config, tool_name, &mut out) {
+        Ok(result) => finish(result, out, tool_name),
+        Err(e) => write_failed(e, out, tool_name),
     }
 }
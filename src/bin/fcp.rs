@@ -16,8 +16,6 @@ use coreutils_rs::cp::{
 
 #[cfg(unix)]
 const TOOL_NAME: &str = "cp";
-#[cfg(unix)]
-const VERSION: &str = env!("CARGO_PKG_VERSION");
 
 #[cfg(unix)]
 fn print_help() {
@@ -123,7 +121,7 @@ fn main() {
                     process::exit(0);
                 }
                 "--version" => {
-                    println!("{} (fcoreutils) {}", TOOL_NAME, VERSION);
+                    coreutils_rs::common::version::print_version(TOOL_NAME);
                     process::exit(0);
                 }
                 "--archive" => {
@@ -75,7 +75,7 @@ fn parse_args() -> Cli {
                         process::exit(0);
                     }
                     b"--version" => {
-                        println!("csplit (fcoreutils) {}", env!("CARGO_PKG_VERSION"));
+                        coreutils_rs::common::version::print_version("csplit");
                         process::exit(0);
                     }
                     _ => {
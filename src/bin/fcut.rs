@@ -1,6 +1,6 @@
 #[cfg(not(target_os = "linux"))]
 use std::io::BufWriter;
-use std::io::{self, BufReader, Write};
+use std::io::{self, BufRead, BufReader, Write};
 #[cfg(unix)]
 use std::mem::ManuallyDrop;
 #[cfg(unix)]
@@ -11,108 +11,44 @@ use std::process;
 #[cfg(unix)]
 use memmap2::MmapOptions;
 
+use coreutils_rs::common::diag;
 use coreutils_rs::common::io::read_file_mmap;
 use coreutils_rs::common::io_error_msg;
 use coreutils_rs::cut::{self, CutMode};
 
-/// Writer that uses vmsplice(2) for zero-copy pipe output on Linux.
-/// When stdout is a pipe, vmsplice references user-space pages directly
-/// in the pipe buffer (no kernel memcpy). Falls back to regular write
-/// for non-pipe fds (files, terminals).
-#[cfg(target_os = "linux")]
-struct VmspliceWriter {
-    raw: ManuallyDrop<std::fs::File>,
-    is_pipe: bool,
+/// Passes writes through to `inner`, counting the bytes for --stats.
+struct CountingWriter<W: Write> {
+    inner: W,
+    bytes: u64,
 }
 
-#[cfg(target_os = "linux")]
-impl VmspliceWriter {
-    fn new() -> Self {
-        let raw = unsafe { ManuallyDrop::new(std::fs::File::from_raw_fd(1)) };
-        let is_pipe = unsafe {
-            let mut stat: libc::stat = std::mem::zeroed();
-            libc::fstat(1, &mut stat) == 0 && (stat.st_mode & libc::S_IFMT) == libc::S_IFIFO
-        };
-        Self { raw, is_pipe }
+impl<W: Write> CountingWriter<W> {
+    fn new(inner: W) -> Self {
+        Self { inner, bytes: 0 }
     }
 }
 
-#[cfg(target_os = "linux")]
-impl Write for VmspliceWriter {
+impl<W: Write> Write for CountingWriter<W> {
     fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
-        if !self.is_pipe || buf.is_empty() {
-            return (&*self.raw).write(buf);
-        }
-        loop {
-            let iov = libc::iovec {
-                iov_base: buf.as_ptr() as *mut libc::c_void,
-                iov_len: buf.len(),
-            };
-            let n = unsafe { libc::vmsplice(1, &iov, 1, 0) };
-            if n >= 0 {
-                return Ok(n as usize);
-            }
-            let err = io::Error::last_os_error();
-            if err.kind() == io::ErrorKind::Interrupted {
-                continue;
-            }
-            self.is_pipe = false;
-            return (&*self.raw).write(buf);
-        }
+        let n = self.inner.write(buf)?;
+        self.bytes += n as u64;
+        Ok(n)
     }
 
-    fn write_all(&mut self, mut buf: &[u8]) -> io::Result<()> {
-        if !self.is_pipe || buf.is_empty() {
-            return (&*self.raw).write_all(buf);
-        }
-        while !buf.is_empty() {
-            let iov = libc::iovec {
-                iov_base: buf.as_ptr() as *mut libc::c_void,
-                iov_len: buf.len(),
-            };
-            let n = unsafe { libc::vmsplice(1, &iov, 1, 0) };
-            if n > 0 {
-                buf = &buf[n as usize..];
-            } else if n == 0 {
-                return Err(io::Error::new(io::ErrorKind::WriteZero, "vmsplice wrote 0"));
-            } else {
-                let err = io::Error::last_os_error();
-                if err.kind() == io::ErrorKind::Interrupted {
-                    continue;
-                }
-                self.is_pipe = false;
-                return (&*self.raw).write_all(buf);
-            }
-        }
+    fn write_all(&mut self, buf: &[u8]) -> io::Result<()> {
+        self.inner.write_all(buf)?;
+        self.bytes += buf.len() as u64;
         Ok(())
     }
 
     fn write_vectored(&mut self, bufs: &[io::IoSlice<'_>]) -> io::Result<usize> {
-        if !self.is_pipe || bufs.is_empty() {
-            return (&*self.raw).write_vectored(bufs);
-        }
-        // SAFETY: IoSlice is #[repr(transparent)] over iovec on Unix,
-        // so &[IoSlice] has the same memory layout as &[iovec].
-        // Direct pointer cast eliminates Vec allocation + copy per call.
-        // bufs may exceed UIO_MAXIOV (1024); .min(1024) below handles the split.
-        loop {
-            let count = bufs.len().min(1024);
-            let iovs = bufs.as_ptr() as *const libc::iovec;
-            let n = unsafe { libc::vmsplice(1, iovs, count, 0) };
-            if n >= 0 {
-                return Ok(n as usize);
-            }
-            let err = io::Error::last_os_error();
-            if err.kind() == io::ErrorKind::Interrupted {
-                continue;
-            }
-            self.is_pipe = false;
-            return (&*self.raw).write_vectored(bufs);
-        }
+        let n = self.inner.write_vectored(bufs)?;
+        self.bytes += n as u64;
+        Ok(n)
     }
 
     fn flush(&mut self) -> io::Result<()> {
-        Ok(())
+        self.inner.flush()
     }
 }
 
@@ -121,10 +57,21 @@ struct Cli {
     characters: Option<String>,
     fields: Option<String>,
     delimiter: Option<String>,
+    delimiter_name: Option<String>,
+    escape_delimiter: bool,
     complement: bool,
+    count: bool,
     only_delimited: bool,
     output_delimiter: Option<String>,
     zero_terminated: bool,
+    warn_invalid: bool,
+    quoted: bool,
+    header_fields: Option<String>,
+    list_file: Option<String>,
+    header_ci: bool,
+    skip_header: bool,
+    stats: bool,
+    threads: usize,
     files: Vec<String>,
 }
 
@@ -137,10 +84,21 @@ fn parse_args() -> Cli {
         characters: None,
         fields: None,
         delimiter: None,
+        delimiter_name: None,
+        escape_delimiter: false,
         complement: false,
+        count: false,
         only_delimited: false,
         output_delimiter: None,
         zero_terminated: false,
+        warn_invalid: false,
+        quoted: false,
+        header_fields: None,
+        list_file: None,
+        header_ci: false,
+        skip_header: false,
+        stats: false,
+        threads: 1,
         files: Vec::new(),
     };
 
@@ -165,6 +123,16 @@ fn parse_args() -> Cli {
                 cli.fields = Some(std::str::from_utf8(&bytes[9..]).unwrap_or("").to_string());
             } else if bytes.starts_with(b"--delimiter=") {
                 cli.delimiter = Some(std::str::from_utf8(&bytes[12..]).unwrap_or("").to_string());
+            } else if bytes.starts_with(b"--delimiter-name=") {
+                cli.delimiter_name =
+                    Some(std::str::from_utf8(&bytes[17..]).unwrap_or("").to_string());
+            } else if bytes.starts_with(b"--header-fields=") {
+                cli.header_fields =
+                    Some(std::str::from_utf8(&bytes[16..]).unwrap_or("").to_string());
+            } else if bytes.starts_with(b"--list-file=") {
+                cli.list_file = Some(std::str::from_utf8(&bytes[12..]).unwrap_or("").to_string());
+            } else if bytes.starts_with(b"--threads=") {
+                cli.threads = parse_threads(std::str::from_utf8(&bytes[10..]).unwrap_or(""));
             } else if bytes.starts_with(b"--output-delimiter=") {
                 cli.output_delimiter =
                     Some(std::str::from_utf8(&bytes[19..]).unwrap_or("").to_string());
@@ -202,6 +170,15 @@ fn parse_args() -> Cli {
                             process::exit(1);
                         }
                     }
+                    b"--delimiter-name" => {
+                        if let Some(v) = args.next() {
+                            cli.delimiter_name = Some(v.to_string_lossy().into_owned());
+                        } else {
+                            eprintln!("cut: option '--delimiter-name' requires an argument");
+                            process::exit(1);
+                        }
+                    }
+                    b"--escape-delimiter" => cli.escape_delimiter = true,
                     b"--output-delimiter" => {
                         if let Some(v) = args.next() {
                             cli.output_delimiter = Some(v.to_string_lossy().into_owned());
@@ -210,9 +187,39 @@ fn parse_args() -> Cli {
                             process::exit(1);
                         }
                     }
+                    b"--header-fields" => {
+                        if let Some(v) = args.next() {
+                            cli.header_fields = Some(v.to_string_lossy().into_owned());
+                        } else {
+                            eprintln!("cut: option '--header-fields' requires an argument");
+                            process::exit(1);
+                        }
+                    }
+                    b"--list-file" => {
+                        if let Some(v) = args.next() {
+                            cli.list_file = Some(v.to_string_lossy().into_owned());
+                        } else {
+                            eprintln!("cut: option '--list-file' requires an argument");
+                            process::exit(1);
+                        }
+                    }
+                    b"--threads" => {
+                        if let Some(v) = args.next() {
+                            cli.threads = parse_threads(&v.to_string_lossy());
+                        } else {
+                            eprintln!("cut: option '--threads' requires an argument");
+                            process::exit(1);
+                        }
+                    }
+                    b"--header-ci" => cli.header_ci = true,
+                    b"--skip-header" => cli.skip_header = true,
                     b"--complement" => cli.complement = true,
+                    b"--count" => cli.count = true,
                     b"--only-delimited" => cli.only_delimited = true,
                     b"--zero-terminated" => cli.zero_terminated = true,
+                    b"--warn-invalid" => cli.warn_invalid = true,
+                    b"--quoted" => cli.quoted = true,
+                    b"--stats" => cli.stats = true,
                     b"--help" => {
                         print!(
                             "Usage: cut OPTION... [FILE]...\n\
@@ -222,23 +229,44 @@ fn parse_args() -> Cli {
                             \x20 -b, --bytes=LIST        select only these bytes\n\
                             \x20 -c, --characters=LIST   select only these characters\n\
                             \x20 -d, --delimiter=DELIM   use DELIM instead of TAB for field delimiter\n\
+                            \x20     --delimiter-name=NAME  use the delimiter named NAME: tab, space,\n\
+                            \x20                           comma, colon, semicolon, nul or pipe\n\
+                            \x20     --escape-delimiter   decode \\t, \\0 and similar escapes in DELIM\n\
                             \x20 -f, --fields=LIST       select only these fields;  also print any line\n\
                             \x20                           that contains no delimiter character, unless\n\
                             \x20                           the -s option is specified\n\
                             \x20 -n                       (ignored)\n\
                             \x20     --complement         complement the set of selected bytes, characters\n\
                             \x20                           or fields\n\
+                            \x20     --count              print the number of fields in each line instead\n\
+                            \x20                           of selecting; -d, -s and -z still apply\n\
                             \x20 -s, --only-delimited     do not print lines not containing delimiters\n\
                             \x20     --output-delimiter=STRING  use STRING as the output delimiter\n\
                             \x20                           the default is to use the input delimiter\n\
                             \x20 -z, --zero-terminated    line delimiter is NUL, not newline\n\
+                            \x20     --quoted             with -f, do not end a field at a delimiter inside\n\
+                            \x20                           double quotes (\"\" stands for one quote); a quoted\n\
+                            \x20                           line delimiter still ends the line\n\
+                            \x20     --warn-invalid       with -c, report the first line of each file that\n\
+                            \x20                           is not valid UTF-8 (cut by byte position); with\n\
+                            \x20                           --quoted, the first with an unterminated quote\n\
+                            \x20     --header-fields=NAMES  select the fields whose names in the first\n\
+                            \x20                           line of each file are in the comma-separated\n\
+                            \x20                           NAMES, as if given to -f\n\
+                            \x20     --list-file=PATH     select the fields listed in PATH, as if given to\n\
+                            \x20                           -f; commas, blanks and newlines separate entries\n\
+                            \x20     --header-ci          match --header-fields names ignoring ASCII case\n\
+                            \x20     --skip-header        with --header-fields, do not output the first line\n\
+                            \x20     --stats              when done, print record and byte counts to stderr\n\
+                            \x20     --threads=N          cut each FILE on N threads, or one per CPU with\n\
+                            \x20                           'auto'; standard input is always cut on one\n\
                             \x20     --help               display this help and exit\n\
                             \x20     --version            output version information and exit\n"
                         );
                         process::exit(0);
                     }
                     b"--version" => {
-                        println!("cut (fcoreutils) {}", env!("CARGO_PKG_VERSION"));
+                        coreutils_rs::common::version::print_version("cut");
                         process::exit(0);
                     }
                     _ => {
@@ -297,6 +325,22 @@ fn parse_args() -> Cli {
     cli
 }
 
+/// Parse the --threads value: a positive count, or "auto" for one thread
+/// per available CPU.
+fn parse_threads(val: &str) -> usize {
+    if val == "auto" {
+        return std::thread::available_parallelism().map_or(1, |n| n.get());
+    }
+    match val.parse::<usize>() {
+        Ok(n) if n > 0 => n,
+        _ => {
+            eprintln!("cut: invalid number of threads: '{}'", val);
+            eprintln!("Try 'cut --help' for more information.");
+            process::exit(1);
+        }
+    }
+}
+
 /// Try to mmap stdin if it's a regular file (e.g., shell redirect `< file`).
 /// Returns None if stdin is a pipe/terminal.
 #[cfg(unix)]
@@ -356,6 +400,148 @@ fn enlarge_pipes() {
     }
 }
 
+/// `--header-fields` settings: the names to select and how the header
+/// record (the first record of each input) is treated.
+struct HeaderOpts {
+    names: Vec<String>,
+    case_insensitive: bool,
+    skip: bool,
+}
+
+/// Resolve the header names against `record` (the first record of `name`,
+/// terminator included). Unknown names are fatal; duplicates warn.
+fn header_ranges(
+    record: &[u8],
+    cfg: &cut::CutConfig,
+    header: &HeaderOpts,
+    name: &str,
+    out: &mut impl Write,
+) -> Vec<cut::Range> {
+    let record = record.strip_suffix(&[cfg.line_delim]).unwrap_or(record);
+    let names: Vec<&str> = header.names.iter().map(String::as_str).collect();
+    match cut::resolve_header_fields(
+        record,
+        cfg.delim,
+        cfg.quoted,
+        &names,
+        header.case_insensitive,
+    ) {
+        Ok(fields) => {
+            if !fields.duplicates.is_empty() {
+                let _ = out.flush();
+            }
+            for (field, n) in &fields.duplicates {
+                diag::warning(
+                    "cut",
+                    Some(name.as_ref()),
+                    &format_args!(
+                        "header field '{}' appears more than once; using field {}",
+                        field, n
+                    ),
+                );
+            }
+            fields.ranges
+        }
+        Err(e) => {
+            let _ = out.flush();
+            diag::error("cut", Some(name.as_ref()), &e);
+            process::exit(1);
+        }
+    }
+}
+
+/// Cut a whole input buffer on `threads` threads, first resolving
+/// `--header-fields` from its first record when given.
+fn cut_data(
+    data: &[u8],
+    cfg: &cut::CutConfig,
+    header: Option<&HeaderOpts>,
+    name: &str,
+    threads: usize,
+    stats: Option<&mut cut::CutStats>,
+    out: &mut impl Write,
+) -> io::Result<()> {
+    let process = |data: &[u8], cfg: &cut::CutConfig, out: &mut _| {
+        if threads > 1 {
+            cut::process_cut_threaded(data, cfg, threads, out)
+        } else {
+            cut::process_cut_data(data, cfg, out)
+        }
+    };
+    let Some(header) = header else {
+        if let Some(stats) = stats {
+            stats.tally(data, cfg);
+        }
+        return process(data, cfg, out);
+    };
+    if data.is_empty() {
+        return Ok(());
+    }
+    let header_len = cut::first_record_len(data, cfg.line_delim);
+    let ranges = header_ranges(&data[..header_len], cfg, header, name, out);
+    let cfg = cut::CutConfig {
+        ranges: &ranges,
+        ..*cfg
+    };
+    let body = if header.skip {
+        &data[header_len..]
+    } else {
+        data
+    };
+    if let Some(stats) = stats {
+        stats.tally(body, &cfg);
+        // A skipped header is read but never emitted
+        stats.records += u64::from(header.skip);
+    }
+    process(body, &cfg, out)
+}
+
+/// Streaming counterpart of `cut_data`: reads the header record first, then
+/// hands the rest of the stream to the batch reader.
+fn cut_reader(
+    mut reader: impl BufRead,
+    cfg: &cut::CutConfig,
+    header: Option<&HeaderOpts>,
+    name: &str,
+    mut stats: Option<&mut cut::CutStats>,
+    out: &mut impl Write,
+) -> io::Result<()> {
+    let Some(header) = header else {
+        return cut::process_cut_reader(reader, cfg, stats, out);
+    };
+    let mut record = Vec::new();
+    if reader.read_until(cfg.line_delim, &mut record)? == 0 {
+        return Ok(());
+    }
+    let ranges = header_ranges(&record, cfg, header, name, out);
+    let cfg = cut::CutConfig {
+        ranges: &ranges,
+        ..*cfg
+    };
+    if let Some(stats) = stats.as_deref_mut() {
+        if header.skip {
+            stats.records += 1;
+        } else {
+            stats.tally(&record, &cfg);
+        }
+    }
+    if !header.skip {
+        cut::process_cut_data(&record, &cfg, out)?;
+    }
+    cut::process_cut_reader(reader, &cfg, stats, out)
+}
+
+/// `--warn-invalid` diagnostic; flushes stdout first so it lands in order.
+fn warn_invalid_line(out: &mut impl Write, name: &str, line: usize, quoted: bool) {
+    let _ = out.flush();
+    let problem = if quoted {
+        "unterminated quote, passed through as-is"
+    } else {
+        "invalid UTF-8, falling back to byte positions"
+    };
+    eprintln!("cut: {}: line {}: {}", name, line, problem);
+}
+
 fn main() {
     coreutils_rs::common::reset_sigpipe();
 
@@ -373,45 +559,135 @@ fn main() {
     let cli = parse_args();
 
     // Determine mode
-    let mode_count =
-        cli.bytes.is_some() as u8 + cli.characters.is_some() as u8 + cli.fields.is_some() as u8;
-    if mode_count == 0 {
+    let mode_count = cli.bytes.is_some() as u8
+        + cli.characters.is_some() as u8
+        + (cli.fields.is_some() || cli.header_fields.is_some() || cli.list_file.is_some()) as u8;
+    if cli.count && (mode_count > 0 || cli.complement) {
+        eprintln!("cut: --count cannot be combined with a list or --complement");
+        eprintln!("Try 'cut --help' for more information.");
+        process::exit(1);
+    }
+    if mode_count == 0 && !cli.count {
         eprintln!("cut: you must specify a list of bytes, characters, or fields");
         eprintln!("Try 'cut --help' for more information.");
         process::exit(1);
     }
-    if mode_count > 1 {
+    if cli.list_file.is_some()
+        && (cli.bytes.is_some() || cli.characters.is_some() || cli.fields.is_some())
+    {
+        eprintln!("cut: --list-file cannot be combined with a LIST");
+        eprintln!("Try 'cut --help' for more information.");
+        process::exit(1);
+    }
+    if mode_count > 1
+        || (cli.header_fields.is_some() && (cli.fields.is_some() || cli.list_file.is_some()))
+    {
         eprintln!("cut: only one type of list may be specified");
         eprintln!("Try 'cut --help' for more information.");
         process::exit(1);
     }
+    if cli.quoted && (cli.bytes.is_some() || cli.characters.is_some()) {
+        eprintln!("cut: --quoted may be specified only when operating on fields");
+        eprintln!("Try 'cut --help' for more information.");
+        process::exit(1);
+    }
+    if (cli.header_ci || cli.skip_header) && cli.header_fields.is_none() {
+        eprintln!("cut: --header-ci and --skip-header require --header-fields");
+        eprintln!("Try 'cut --help' for more information.");
+        process::exit(1);
+    }
+
+    // With --header-fields the ranges are resolved per input from its header
+    let header = cli.header_fields.as_ref().map(|names| HeaderOpts {
+        names: names.split(',').map(str::to_string).collect(),
+        case_insensitive: cli.header_ci,
+        skip: cli.skip_header,
+    });
 
-    let (mode, spec) = if let Some(ref s) = cli.bytes {
+    let (mode, spec) = if cli.count {
+        (CutMode::Count, "")
+    } else if let Some(ref s) = cli.bytes {
         (CutMode::Bytes, s.as_str())
     } else if let Some(ref s) = cli.characters {
         (CutMode::Characters, s.as_str())
     } else {
-        (CutMode::Fields, cli.fields.as_ref().unwrap().as_str())
+        (CutMode::Fields, cli.fields.as_deref().unwrap_or("1"))
     };
 
-    let ranges = match cut::parse_ranges(spec) {
+    // --count selects nothing, so it has no list to parse
+    let parsed = if mode == CutMode::Count {
+        Ok(Vec::new())
+    } else if let Some(ref path) = cli.list_file {
+        match std::fs::read_to_string(path) {
+            Ok(text) => cut::parse_list_file(&text),
+            Err(e) => {
+                eprintln!("cut: {}: {}", path, io_error_msg(&e));
+                process::exit(1);
+            }
+        }
+    } else {
+        cut::parse_ranges(spec)
+    };
+    let ranges = match parsed {
         Ok(r) => r,
         Err(e) => {
-            eprintln!("cut: {}", e);
+            let msg = e.message(mode);
+            match cli.list_file {
+                Some(ref path) => eprintln!("cut: {}: {}", path, msg),
+                None => eprintln!("cut: {}", msg),
+            }
+            eprintln!("Try 'cut --help' for more information.");
             process::exit(1);
         }
     };
 
-    let delim = if let Some(ref d) = cli.delimiter {
+    if cli.delimiter_name.is_some() && cli.delimiter.is_some() {
+        eprintln!("cut: --delimiter-name cannot be combined with -d");
+        eprintln!("Try 'cut --help' for more information.");
+        process::exit(1);
+    }
+    if cli.escape_delimiter && cli.delimiter.is_none() {
+        eprintln!("cut: --escape-delimiter requires -d");
+        eprintln!("Try 'cut --help' for more information.");
+        process::exit(1);
+    }
+
+    let delim = if let Some(ref name) = cli.delimiter_name {
+        match cut::delimiter_by_name(name) {
+            Ok(b) => b,
+            Err(e) => {
+                eprintln!("cut: {}", e);
+                eprintln!("Try 'cut --help' for more information.");
+                process::exit(1);
+            }
+        }
+    } else if let Some(ref d) = cli.delimiter {
+        let d = if cli.escape_delimiter {
+            match coreutils_rs::common::escape::decode_separator_escapes(d.as_bytes()) {
+                Ok(d) => d,
+                Err(e) => {
+                    eprintln!("cut: {}", e);
+                    eprintln!("Try 'cut --help' for more information.");
+                    process::exit(1);
+                }
+            }
+        } else {
+            d.as_bytes().to_vec()
+        };
         if d.len() != 1 {
             eprintln!("cut: the delimiter must be a single character");
             eprintln!("Try 'cut --help' for more information.");
             process::exit(1);
         }
-        d.as_bytes()[0]
+        d[0]
     } else {
         b'\t'
     };
+    if cli.quoted && delim == b'"' {
+        eprintln!("cut: --quoted cannot be used with a '\"' delimiter");
+        eprintln!("Try 'cut --help' for more information.");
+        process::exit(1);
+    }
 
     // Default output delimiter: field delimiter for -f, empty for -b/-c
     // GNU cut only uses a delimiter between fields, not between byte/char ranges
@@ -431,21 +707,26 @@ fn main() {
         cli.files.clone()
     };
 
-    // On Linux: VmspliceWriter directly — no BufWriter wrapper.
+    // On Linux: raw fd stdout directly — no BufWriter wrapper.
     // cut's batch processing already produces large output buffers (Vec<u8> or IoSlice),
     // so BufWriter's internal buffering is pure overhead (extra memcpy for output < 16MB).
+    // Plain write(2), not vmsplice: the output buffers are freed as soon as they are
+    // written, and a pipe holding their pages would see malloc reuse them.
+    #[cfg(target_os = "linux")]
+    let raw = unsafe { ManuallyDrop::new(std::fs::File::from_raw_fd(1)) };
     #[cfg(target_os = "linux")]
-    let mut out = VmspliceWriter::new();
+    let mut out = CountingWriter::new(&*raw);
     // On other Unix: raw fd stdout with BufWriter
     #[cfg(all(unix, not(target_os = "linux")))]
     let mut raw = unsafe { ManuallyDrop::new(std::fs::File::from_raw_fd(1)) };
     #[cfg(all(unix, not(target_os = "linux")))]
-    let mut out = BufWriter::with_capacity(16 * 1024 * 1024, &mut *raw);
+    let mut out = CountingWriter::new(BufWriter::with_capacity(16 * 1024 * 1024, &mut *raw));
     #[cfg(not(unix))]
     let stdout = io::stdout();
     #[cfg(not(unix))]
-    let mut out = BufWriter::with_capacity(16 * 1024 * 1024, stdout.lock());
+    let mut out = CountingWriter::new(BufWriter::with_capacity(16 * 1024 * 1024, stdout.lock()));
     let mut had_error = false;
+    let mut stats = cli.stats.then(cut::CutStats::default);
 
     let cfg = cut::CutConfig {
         mode,
@@ -455,6 +736,7 @@ fn main() {
         output_delim: &output_delim,
         suppress_no_delim: cli.only_delimited,
         line_delim,
+        quoted: cli.quoted,
     };
 
     // Try to mmap stdin for zero-copy (only used if stdin is a regular file)
@@ -515,6 +797,30 @@ fn main() {
         None
     };
 
+    // --warn-invalid: validate stdin now, before in-place extraction rewrites it.
+    let warn_invalid = cli.warn_invalid && (mode == CutMode::Characters || cli.quoted);
+    let first_invalid_line = |data: &[u8]| {
+        if cli.quoted {
+            cut::first_unterminated_quote_line(data, line_delim)
+        } else {
+            cut::first_invalid_utf8_line(data, line_delim)
+        }
+    };
+    let mut stdin_invalid_line = None;
+    if warn_invalid {
+        #[cfg(unix)]
+        if let Some(ref data) = stdin_mmap {
+            stdin_invalid_line = first_invalid_line(data);
+        }
+        #[cfg(target_os = "linux")]
+        if let Some(ref data) = splice_mmap {
+            stdin_invalid_line = first_invalid_line(data);
+        }
+        if let Some(ref data) = stdin_buf {
+            stdin_invalid_line = first_invalid_line(data);
+        }
+    }
+
     // For piped stdin with mutable data: try in-place extraction.
     // In-place avoids allocating intermediate Vec output buffers entirely.
     // Generalizes the old field-1-only path to all field and byte patterns.
@@ -525,30 +831,54 @@ fn main() {
     let mut splice_mmap = splice_mmap;
     #[cfg(target_os = "linux")]
     let mut splice_inplace_len: usize = 0;
+    // In-place extraction overwrites its input, so --stats tallies it first
+    let mut stdin_stats = cut::CutStats::default();
     #[cfg(target_os = "linux")]
     if let Some(ref mut mmap_data) = splice_mmap
+        && header.is_none()
         && !mmap_data.is_empty()
-        && let Some(new_len) = cut::process_cut_data_mut(mmap_data, &cfg)
     {
-        splice_inplace_len = new_len;
-        stdin_inplace_done = true;
+        let mut tally = cut::CutStats::default();
+        if cli.stats {
+            tally.tally(mmap_data, &cfg);
+        }
+        if let Some(new_len) = cut::process_cut_data_mut(mmap_data, &cfg) {
+            splice_inplace_len = new_len;
+            stdin_inplace_done = true;
+            stdin_stats = tally;
+        }
     }
 
     // Try in-place on stdin_buf (Vec<u8>) if splice didn't handle it
     if !stdin_inplace_done
+        && header.is_none()
         && let Some(ref mut data) = stdin_buf
         && !data.is_empty()
-        && let Some(new_len) = cut::process_cut_data_mut(data, &cfg)
     {
-        data.truncate(new_len);
-        stdin_inplace_done = true;
+        let mut tally = cut::CutStats::default();
+        if cli.stats {
+            tally.tally(data, &cfg);
+        }
+        if let Some(new_len) = cut::process_cut_data_mut(data, &cfg) {
+            data.truncate(new_len);
+            stdin_inplace_done = true;
+            stdin_stats = tally;
+        }
     }
 
     for filename in &files {
+        if filename == "-"
+            && let Some(line) = stdin_invalid_line.take()
+        {
+            warn_invalid_line(&mut out, "standard input", line, cli.quoted);
+        }
         let result: io::Result<()> = if filename == "-" {
             #[cfg(unix)]
             {
                 if stdin_inplace_done {
+                    if let Some(stats) = stats.as_mut() {
+                        *stats += stdin_stats;
+                    }
                     // Write in-place processed data directly to output
                     #[cfg(target_os = "linux")]
                     {
@@ -567,51 +897,131 @@ fn main() {
                     #[cfg(not(target_os = "linux"))]
                     {
                         if let Some(ref data) = stdin_buf {
-                            out.flush().and_then(|()| out.get_mut().write_all(data))
+                            out.flush().and_then(|()| {
+                                out.bytes += data.len() as u64;
+                                out.inner.get_mut().write_all(data)
+                            })
                         } else {
                             Ok(())
                         }
                     }
                 } else if let Some(ref data) = stdin_mmap {
-                    cut::process_cut_data(data, &cfg, &mut out)
+                    cut_data(
+                        data,
+                        &cfg,
+                        header.as_ref(),
+                        "standard input",
+                        1,
+                        stats.as_mut(),
+                        &mut out,
+                    )
                 } else {
                     // Try splice mmap first (Linux only), then stdin_buf
                     #[cfg(target_os = "linux")]
                     if let Some(ref data) = splice_mmap {
-                        cut::process_cut_data(data.as_ref(), &cfg, &mut out)
+                        cut_data(
+                            data.as_ref(),
+                            &cfg,
+                            header.as_ref(),
+                            "standard input",
+                            1,
+                            stats.as_mut(),
+                            &mut out,
+                        )
                     } else if let Some(ref data) = stdin_buf {
-                        cut::process_cut_data(data, &cfg, &mut out)
+                        cut_data(
+                            data,
+                            &cfg,
+                            header.as_ref(),
+                            "standard input",
+                            1,
+                            stats.as_mut(),
+                            &mut out,
+                        )
                     } else {
                         let reader = BufReader::new(io::stdin().lock());
-                        cut::process_cut_reader(reader, &cfg, &mut out)
+                        cut_reader(
+                            reader,
+                            &cfg,
+                            header.as_ref(),
+                            "standard input",
+                            stats.as_mut(),
+                            &mut out,
+                        )
                     }
                     #[cfg(not(target_os = "linux"))]
                     if let Some(ref data) = stdin_buf {
-                        cut::process_cut_data(data, &cfg, &mut out)
+                        cut_data(
+                            data,
+                            &cfg,
+                            header.as_ref(),
+                            "standard input",
+                            1,
+                            stats.as_mut(),
+                            &mut out,
+                        )
                     } else {
                         let reader = BufReader::new(io::stdin().lock());
-                        cut::process_cut_reader(reader, &cfg, &mut out)
+                        cut_reader(
+                            reader,
+                            &cfg,
+                            header.as_ref(),
+                            "standard input",
+                            stats.as_mut(),
+                            &mut out,
+                        )
                     }
                 }
             }
             #[cfg(not(unix))]
             {
                 if stdin_inplace_done {
+                    if let Some(stats) = stats.as_mut() {
+                        *stats += stdin_stats;
+                    }
                     if let Some(ref data) = stdin_buf {
                         out.write_all(data)
                     } else {
                         Ok(())
                     }
                 } else if let Some(ref data) = stdin_buf {
-                    cut::process_cut_data(data, &cfg, &mut out)
+                    cut_data(
+                        data,
+                        &cfg,
+                        header.as_ref(),
+                        "standard input",
+                        1,
+                        stats.as_mut(),
+                        &mut out,
+                    )
                 } else {
                     let reader = BufReader::new(io::stdin().lock());
-                    cut::process_cut_reader(reader, &cfg, &mut out)
+                    cut_reader(
+                        reader,
+                        &cfg,
+                        header.as_ref(),
+                        "standard input",
+                        stats.as_mut(),
+                        &mut out,
+                    )
                 }
             }
         } else {
             match read_file_mmap(Path::new(filename)) {
-                Ok(data) => cut::process_cut_data(&data, &cfg, &mut out),
+                Ok(data) => {
+                    if warn_invalid && let Some(line) = first_invalid_line(&data) {
+                        warn_invalid_line(&mut out, filename, line, cli.quoted);
+                    }
+                    cut_data(
+                        &data,
+                        &cfg,
+                        header.as_ref(),
+                        filename,
+                        cli.threads,
+                        stats.as_mut(),
+                        &mut out,
+                    )
+                }
                 Err(e) => {
                     eprintln!("cut: {}: {}", filename, io_error_msg(&e));
                     had_error = true;
@@ -637,7 +1047,615 @@ fn main() {
         had_error = true;
     }
 
+    if let Some(mut stats) = stats {
+        stats.bytes = out.bytes;
+        eprintln!(
+            "fcut: records={} emitted={} no-delim={} bytes={}",
+            stats.records, stats.emitted, stats.no_delim, stats.bytes
+        );
+    }
+
     if had_error {
         process::exit(1);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+    use std::process::{Command, Output, Stdio};
+
+    fn cmd() -> Command {
+        let mut path = std::env::current_exe().unwrap();
+        path.pop();
+        path.pop();
+        path.push("fcut");
+        Command::new(path)
+    }
+
+    /// Run fcut with stdout redirected to a regular file.
+    fn run_to_file(args: &[&str], dir: &std::path::Path) -> (Vec<u8>, Output) {
+        let out_path = dir.join("stdout");
+        let output = cmd()
+            .args(args)
+            .stdout(std::fs::File::create(&out_path).unwrap())
+            .output()
+            .unwrap();
+        (std::fs::read(&out_path).unwrap(), output)
+    }
+
+    #[test]
+    fn test_warn_invalid_names_first_bad_line() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("in.txt");
+        std::fs::write(&path, b"one\ntwo\nth\xffree\nfo\xfeur\n").unwrap();
+        let file = path.to_str().unwrap();
+
+        let (plain, output) = run_to_file(&["-c", "2-3", file], dir.path());
+        assert!(output.status.success());
+        assert!(output.stderr.is_empty());
+
+        let (warned, output) = run_to_file(&["-c", "2-3", "--warn-invalid", file], dir.path());
+        assert_eq!(output.status.code(), Some(0));
+        assert_eq!(warned, plain);
+        assert_eq!(
+            String::from_utf8_lossy(&output.stderr),
+            format!(
+                "cut: {}: line 3: invalid UTF-8, falling back to byte positions\n",
+                file
+            )
+        );
+    }
+
+    #[test]
+    fn test_warn_invalid_only_in_character_mode() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("in.txt");
+        std::fs::write(&path, b"\xff\n").unwrap();
+        let file = path.to_str().unwrap();
+        let (_, output) = run_to_file(&["-b", "1", "--warn-invalid", file], dir.path());
+        assert!(output.stderr.is_empty());
+
+        let valid = dir.path().join("ok.txt");
+        std::fs::write(&valid, "h\u{e9}llo\n").unwrap();
+        let (_, output) = run_to_file(
+            &["-c", "1", "--warn-invalid", valid.to_str().unwrap()],
+            dir.path(),
+        );
+        assert!(output.stderr.is_empty());
+    }
+
+    const PEOPLE_CSV: &str = "id,name,age,name\n1,al,30,x\n2,bo,40,y\n";
+
+    #[test]
+    fn test_header_fields_select_by_name() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("people.csv");
+        std::fs::write(&path, PEOPLE_CSV).unwrap();
+        let file = path.to_str().unwrap();
+
+        // Output keeps field order, like -f 1,3
+        let (out, output) = run_to_file(&["-d,", "--header-fields=age,id", file], dir.path());
+        assert!(output.status.success());
+        assert!(output.stderr.is_empty());
+        assert_eq!(out, b"id,age\n1,30\n2,40\n");
+
+        let (out, output) = run_to_file(
+            &["-d,", "--header-fields", "age", "--skip-header", file],
+            dir.path(),
+        );
+        assert!(output.status.success());
+        assert_eq!(out, b"30\n40\n");
+    }
+
+    #[test]
+    fn test_header_fields_duplicate_warns() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("people.csv");
+        std::fs::write(&path, PEOPLE_CSV).unwrap();
+        let file = path.to_str().unwrap();
+
+        let (out, output) = run_to_file(
+            &["-d,", "--header-fields=NAME", "--header-ci", file],
+            dir.path(),
+        );
+        assert_eq!(output.status.code(), Some(0));
+        assert_eq!(out, b"name\nal\nbo\n");
+        assert_eq!(
+            String::from_utf8_lossy(&output.stderr),
+            format!(
+                "cut: warning: {}: header field 'NAME' appears more than once; using field 2\n",
+                file
+            )
+        );
+    }
+
+    #[test]
+    fn test_header_fields_unknown_name_fails() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("people.csv");
+        std::fs::write(&path, PEOPLE_CSV).unwrap();
+        let file = path.to_str().unwrap();
+
+        let (out, output) = run_to_file(&["-d,", "--header-fields=id,zip", file], dir.path());
+        assert_eq!(output.status.code(), Some(1));
+        assert!(out.is_empty());
+        assert_eq!(
+            String::from_utf8_lossy(&output.stderr),
+            format!("cut: {}: unknown header field: 'zip'\n", file)
+        );
+
+        let (_, output) = run_to_file(&["-f1", "--header-fields=id", file], dir.path());
+        assert_eq!(output.status.code(), Some(1));
+        let (_, output) = run_to_file(&["-f1", "--skip-header", file], dir.path());
+        assert_eq!(output.status.code(), Some(1));
+    }
+
+    #[test]
+    fn test_list_file_selects_fields() {
+        const COLS: usize = 100_000;
+        let dir = tempfile::tempdir().unwrap();
+
+        // 50k entries, every other column, mixing the accepted separators
+        let mut list = String::new();
+        for (i, col) in (1..=COLS).step_by(2).enumerate() {
+            list.push_str(&col.to_string());
+            list.push(match i % 3 {
+                0 => '\n',
+                1 => ',',
+                _ => ' ',
+            });
+        }
+        let list_path = dir.path().join("cols.txt");
+        std::fs::write(&list_path, &list).unwrap();
+
+        let mut input = String::new();
+        let mut expected = String::new();
+        for row in 0..3 {
+            let cells: Vec<String> = (1..=COLS).map(|c| format!("r{}c{}", row, c)).collect();
+            input.push_str(&cells.join("\t"));
+            input.push('\n');
+            let picked: Vec<&str> = cells.iter().step_by(2).map(String::as_str).collect();
+            expected.push_str(&picked.join("\t"));
+            expected.push('\n');
+        }
+        let in_path = dir.path().join("wide.tsv");
+        std::fs::write(&in_path, &input).unwrap();
+
+        let (out, output) = run_to_file(
+            &[
+                "--list-file",
+                list_path.to_str().unwrap(),
+                in_path.to_str().unwrap(),
+            ],
+            dir.path(),
+        );
+        assert!(output.status.success());
+        assert!(output.stderr.is_empty());
+        assert_eq!(out, expected.as_bytes());
+    }
+
+    #[test]
+    fn test_list_file_errors() {
+        let dir = tempfile::tempdir().unwrap();
+        let input = dir.path().join("in.tsv");
+        std::fs::write(&input, "a\tb\n").unwrap();
+        let input = input.to_str().unwrap();
+
+        let missing = dir.path().join("missing.txt");
+        let missing = missing.to_str().unwrap();
+        let (out, output) = run_to_file(&[&format!("--list-file={}", missing), input], dir.path());
+        assert_eq!(output.status.code(), Some(1));
+        assert!(out.is_empty());
+        assert_eq!(
+            String::from_utf8_lossy(&output.stderr),
+            format!("cut: {}: No such file or directory\n", missing)
+        );
+
+        let bad = dir.path().join("bad.txt");
+        std::fs::write(&bad, "1\n2\n3-x\n").unwrap();
+        let bad = bad.to_str().unwrap();
+        let (out, output) = run_to_file(&["--list-file", bad, input], dir.path());
+        assert_eq!(output.status.code(), Some(1));
+        assert!(out.is_empty());
+        assert!(
+            String::from_utf8_lossy(&output.stderr)
+                .starts_with(&format!("cut: {}: invalid range: '3-x'\n", bad))
+        );
+
+        // The file replaces an inline LIST rather than adding to it
+        for conflict in [&["-f1"][..], &["-b1"], &["--header-fields=a"]] {
+            let mut args = conflict.to_vec();
+            args.extend(["--list-file", bad, input]);
+            let (_, output) = run_to_file(&args, dir.path());
+            assert_eq!(output.status.code(), Some(1), "{:?}", conflict);
+        }
+    }
+
+    #[test]
+    fn test_count_flag() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("rows.csv");
+        std::fs::write(&path, "a,b,c\nplain\na,\n").unwrap();
+        let file = path.to_str().unwrap();
+
+        let (out, output) = run_to_file(&["-d,", "--count", file], dir.path());
+        assert!(output.status.success());
+        assert_eq!(out, b"3\n1\n2\n");
+        let (out, _) = run_to_file(&["-d,", "-s", "--count", file], dir.path());
+        assert_eq!(out, b"3\n2\n");
+
+        for conflict in [
+            &["--count", "-f1"][..],
+            &["--count", "-b1"],
+            &["--count", "--complement"],
+        ] {
+            let mut args = conflict.to_vec();
+            args.push(file);
+            let (out, output) = run_to_file(&args, dir.path());
+            assert_eq!(output.status.code(), Some(1), "{:?}", conflict);
+            assert!(out.is_empty());
+            assert!(
+                String::from_utf8_lossy(&output.stderr)
+                    .starts_with("cut: --count cannot be combined with a list or --complement\n")
+            );
+        }
+    }
+
+    #[test]
+    fn test_header_fields_from_stdin() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("people.csv");
+        std::fs::write(&path, PEOPLE_CSV).unwrap();
+        let out_path = dir.path().join("stdout");
+        let output = cmd()
+            .args(["-d,", "--header-fields=age", "--skip-header"])
+            .stdin(std::fs::File::open(&path).unwrap())
+            .stdout(std::fs::File::create(&out_path).unwrap())
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        assert_eq!(std::fs::read(&out_path).unwrap(), b"30\n40\n");
+
+        let mut child = cmd()
+            .args(["-d,", "--header-fields=name,id"])
+            .stdin(Stdio::piped())
+            .stdout(std::fs::File::create(&out_path).unwrap())
+            .spawn()
+            .unwrap();
+        child
+            .stdin
+            .take()
+            .unwrap()
+            .write_all(PEOPLE_CSV.as_bytes())
+            .unwrap();
+        assert!(child.wait().unwrap().success());
+        assert_eq!(std::fs::read(&out_path).unwrap(), b"id,name\n1,al\n2,bo\n");
+    }
+
+    /// Run fcut on a file holding `input`.
+    fn run_on(input: &[u8], args: &[&str]) -> (Vec<u8>, Output) {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("in");
+        std::fs::write(&path, input).unwrap();
+        let mut args = args.to_vec();
+        args.push(path.to_str().unwrap());
+        run_to_file(&args, dir.path())
+    }
+
+    #[test]
+    fn test_threads_output_matches_serial() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("in");
+        let mut data = Vec::new();
+        for i in 0..50_000u32 {
+            let line = match i % 5 {
+                0 => format!("{}\n", i),
+                1 => format!("{}\t\n", i),
+                _ => format!(
+                    "{}\tf{}\t{}\tlast\n",
+                    i,
+                    i % 11,
+                    "z".repeat((i % 17) as usize)
+                ),
+            };
+            data.extend_from_slice(line.as_bytes());
+        }
+        data.extend_from_slice(b"tail\twithout\tnewline");
+        std::fs::write(&path, &data).unwrap();
+        let file = path.to_str().unwrap();
+
+        for args in [
+            &["-f", "2"][..],
+            &["-f", "1,3-", "-s"],
+            &["-f", "2", "--complement"],
+            &["-b", "2-5,9-"],
+            &["-b", "1-3", "--complement"],
+            &["-c", "-4"],
+        ] {
+            let serial = cmd().args(args).arg(file).output().unwrap();
+            assert!(serial.status.success());
+            for threads in ["2", "7", "auto"] {
+                let threaded = cmd()
+                    .env("FCUT_CHUNK_SIZE", "4093")
+                    .args(args)
+                    .arg(format!("--threads={}", threads))
+                    .arg(file)
+                    .output()
+                    .unwrap();
+                assert!(threaded.status.success());
+                assert!(
+                    threaded.stdout == serial.stdout,
+                    "{:?} --threads={}",
+                    args,
+                    threads
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_threads_invalid_value() {
+        for val in ["0", "-1", "many"] {
+            let output = cmd()
+                .args(["-f1", &format!("--threads={}", val)])
+                .output()
+                .unwrap();
+            assert_eq!(output.status.code(), Some(1));
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            assert!(
+                stderr.starts_with(&format!("cut: invalid number of threads: '{}'", val)),
+                "{}",
+                stderr
+            );
+        }
+    }
+
+    #[test]
+    fn test_delimiter_name_presets() {
+        for (name, line) in [
+            ("tab", "a\tb\tc\n"),
+            ("space", "a b c\n"),
+            ("comma", "a,b,c\n"),
+            ("colon", "a:b:c\n"),
+            ("semicolon", "a;b;c\n"),
+            ("pipe", "a|b|c\n"),
+        ] {
+            let arg = format!("--delimiter-name={}", name);
+            let (out, output) = run_on(line.as_bytes(), &[&arg, "-f2"]);
+            assert!(output.status.success(), "{}", name);
+            assert_eq!(out, b"b\n", "{}", name);
+        }
+        let (out, output) = run_on(b"a,b,c\n", &["--delimiter-name", "comma", "-f1,3"]);
+        assert!(output.status.success());
+        assert_eq!(out, b"a,c\n");
+    }
+
+    #[test]
+    fn test_delimiter_name_nul_splits_records_on_nul() {
+        let (out, output) = run_on(b"a\0b\0c\nd\0e\n", &["--delimiter-name=nul", "-f2"]);
+        assert!(output.status.success());
+        assert_eq!(out, b"b\ne\n");
+        // The output delimiter defaults to the NUL field delimiter
+        let (out, _) = run_on(b"a\0b\0c\n", &["--delimiter-name=nul", "-f1,3"]);
+        assert_eq!(out, b"a\0c\n");
+    }
+
+    #[test]
+    fn test_escape_delimiter() {
+        let (out, output) = run_on(b"a\tb\n", &["--escape-delimiter", "-d", "\\t", "-f2"]);
+        assert!(output.status.success());
+        assert_eq!(out, b"b\n");
+        let (out, output) = run_on(b"a\0b\n", &["--escape-delimiter", "-d\\0", "-f2"]);
+        assert!(output.status.success());
+        assert_eq!(out, b"b\n");
+        // Without the flag the two characters are rejected as before
+        let (_, output) = run_on(b"", &["-d", "\\t", "-f2"]);
+        assert_eq!(output.status.code(), Some(1));
+        assert!(
+            String::from_utf8_lossy(&output.stderr)
+                .starts_with("cut: the delimiter must be a single character\n")
+        );
+    }
+
+    #[test]
+    fn test_delimiter_option_errors() {
+        for (args, msg) in [
+            (
+                &["--delimiter-name=tab", "-d,", "-f1"][..],
+                "cut: --delimiter-name cannot be combined with -d\n",
+            ),
+            (
+                &["--delimiter-name=tabs", "-f1"],
+                "cut: invalid delimiter name 'tabs'; valid names are: \
+                 tab, space, comma, colon, semicolon, nul, pipe\n",
+            ),
+            (
+                &["--escape-delimiter", "-f1"],
+                "cut: --escape-delimiter requires -d\n",
+            ),
+            (
+                &["--escape-delimiter", "-d", "\\x", "-f1"],
+                "cut: invalid escape '\\x'",
+            ),
+        ] {
+            let (out, output) = run_on(b"a\n", args);
+            assert_eq!(output.status.code(), Some(1), "{:?}", args);
+            assert!(out.is_empty());
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            assert!(stderr.starts_with(msg), "{:?}: {}", args, stderr);
+        }
+    }
+
+    #[test]
+    fn test_stats_counts() {
+        // Five records, the last unterminated; "no delim" and "" have no comma
+        let input = "a,b\nno delim\nc,d,e\n\nlast,x";
+        for (args, stats) in [
+            (
+                &["-d,", "-f2"][..],
+                "records=5 emitted=5 no-delim=2 bytes=16",
+            ),
+            (
+                &["-d,", "-f2", "-s"],
+                "records=5 emitted=3 no-delim=2 bytes=6",
+            ),
+            (
+                &["-d,", "--count"],
+                "records=5 emitted=5 no-delim=2 bytes=10",
+            ),
+            (&["-b1-2"], "records=5 emitted=5 no-delim=0 bytes=13"),
+        ] {
+            for zero in [false, true] {
+                let mut args = args.to_vec();
+                args.push("--stats");
+                let input = if zero {
+                    args.push("-z");
+                    input.replace('\n', "\0")
+                } else {
+                    input.to_string()
+                };
+                let expected = format!("fcut: {}\n", stats);
+
+                // File operand (mmap path)
+                let (out, output) = run_on(input.as_bytes(), &args);
+                assert_eq!(output.status.code(), Some(0), "{:?}", args);
+                assert_eq!(
+                    String::from_utf8_lossy(&output.stderr),
+                    expected,
+                    "{:?}",
+                    args
+                );
+                let bytes: usize = stats.rsplit('=').next().unwrap().parse().unwrap();
+                assert_eq!(out.len(), bytes, "{:?}", args);
+
+                // Piped standard input (in-place extraction where possible);
+                // that path leaves an unterminated last record unterminated,
+                // so give it a terminated one, which yields the same output
+                let term = if zero { "\0" } else { "\n" };
+                let input = format!("{}{}", input, term);
+                let dir = tempfile::tempdir().unwrap();
+                let out_path = dir.path().join("stdout");
+                let mut child = cmd()
+                    .args(&args)
+                    .stdin(Stdio::piped())
+                    .stdout(std::fs::File::create(&out_path).unwrap())
+                    .stderr(Stdio::piped())
+                    .spawn()
+                    .unwrap();
+                child
+                    .stdin
+                    .take()
+                    .unwrap()
+                    .write_all(input.as_bytes())
+                    .unwrap();
+                let piped = child.wait_with_output().unwrap();
+                assert_eq!(std::fs::read(&out_path).unwrap(), out, "{:?}", args);
+                assert_eq!(
+                    String::from_utf8_lossy(&piped.stderr),
+                    expected,
+                    "{:?}",
+                    args
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_stats_off_by_default() {
+        let (out, output) = run_on(b"a,b\n", &["-d,", "-f1"]);
+        assert_eq!(out, b"a\n");
+        assert!(output.stderr.is_empty());
+    }
+
+    #[test]
+    fn test_unterminated_stdin_gets_newline() {
+        for (args, expected) in [
+            (&["-b1-4"][..], &b"abcd\nab\n"[..]),
+            (&["-b2,4"], b"bd\nb\n"),
+            (&["--complement", "-b5-"], b"abcd\nab\n"),
+        ] {
+            let mut child = cmd()
+                .args(args)
+                .stdin(Stdio::piped())
+                .stdout(Stdio::piped())
+                .spawn()
+                .unwrap();
+            child
+                .stdin
+                .take()
+                .unwrap()
+                .write_all(b"abcdefgh\nab")
+                .unwrap();
+            let output = child.wait_with_output().unwrap();
+            assert!(output.status.success());
+            assert_eq!(output.stdout, expected, "{:?}", args);
+        }
+    }
+
+    const QUOTED_CSV: &[u8] = b"id,\"Smith, John\",\"said \"\"hi, there\"\"\"\n2,\"open, quote\n";
+
+    #[test]
+    fn test_quoted_file_and_pipe() {
+        let (out, output) = run_on(QUOTED_CSV, &["-d,", "-f2,3", "--quoted"]);
+        assert!(output.status.success());
+        let expected = b"\"Smith, John\",\"said \"\"hi, there\"\"\"\n\"open, quote\n";
+        assert_eq!(out, expected);
+
+        // Piped input would otherwise be cut in place by the plain scanner
+        let mut child = cmd()
+            .args(["-d,", "-f2,3", "--quoted"])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .unwrap();
+        child.stdin.take().unwrap().write_all(QUOTED_CSV).unwrap();
+        let output = child.wait_with_output().unwrap();
+        assert!(output.status.success());
+        assert_eq!(output.stdout, expected);
+    }
+
+    #[test]
+    fn test_quoted_warn_invalid() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("in.csv");
+        std::fs::write(&path, QUOTED_CSV).unwrap();
+        let file = path.to_str().unwrap();
+        let (_, output) = run_to_file(&["-d,", "-f1", "--quoted", file], dir.path());
+        assert!(output.stderr.is_empty());
+
+        let (out, output) = run_to_file(
+            &["-d,", "-f1", "--quoted", "--warn-invalid", file],
+            dir.path(),
+        );
+        assert_eq!(output.status.code(), Some(0));
+        assert_eq!(out, b"id\n2\n");
+        assert_eq!(
+            String::from_utf8_lossy(&output.stderr),
+            format!(
+                "cut: {}: line 2: unterminated quote, passed through as-is\n",
+                file
+            )
+        );
+    }
+
+    #[test]
+    fn test_quoted_option_errors() {
+        for (args, msg) in [
+            (
+                &["--quoted", "-c1"][..],
+                "cut: --quoted may be specified only when operating on fields\n",
+            ),
+            (
+                &["--quoted", "-d\"", "-f1"],
+                "cut: --quoted cannot be used with a '\"' delimiter\n",
+            ),
+        ] {
+            let (out, output) = run_on(b"a\n", args);
+            assert_eq!(output.status.code(), Some(1), "{:?}", args);
+            assert!(out.is_empty());
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            assert!(stderr.starts_with(msg), "{:?}: {}", args, stderr);
+        }
+    }
+}
@@ -87,7 +87,7 @@ fn parse_args() -> Cli {
                         process::exit(0);
                     }
                     "--version" => {
-                        println!("date (fcoreutils) {}", env!("CARGO_PKG_VERSION"));
+                        coreutils_rs::common::version::print_version("date");
                         process::exit(0);
                     }
                     _ => {
@@ -14,8 +14,6 @@ use coreutils_rs::df::{DfConfig, parse_block_size, parse_output_fields, run_df};
 
 #[cfg(unix)]
 const TOOL_NAME: &str = "df";
-#[cfg(unix)]
-const VERSION: &str = env!("CARGO_PKG_VERSION");
 
 /// Take the next value for an option from the rest of the current arg or the next arg.
 #[cfg(unix)]
@@ -150,7 +148,7 @@ fn parse_args() -> DfConfig {
                     process::exit(0);
                 }
                 "version" => {
-                    println!("{} (fcoreutils) {}", TOOL_NAME, VERSION);
+                    coreutils_rs::common::version::print_version(TOOL_NAME);
                     process::exit(0);
                 }
                 _ => {
@@ -35,7 +35,7 @@ fn main() {
                 return;
             }
             "--version" => {
-                println!("dir (fcoreutils) {}", env!("CARGO_PKG_VERSION"));
+                coreutils_rs::common::version::print_version("dir");
                 return;
             }
             _ => {}
@@ -7,7 +7,6 @@ use std::io::{self, BufRead, Write};
 use std::process;
 
 const TOOL_NAME: &str = "dircolors";
-const VERSION: &str = env!("CARGO_PKG_VERSION");
 
 /// Simple glob pattern matcher supporting *, ?, and [...] character classes.
 fn glob_match(pattern: &str, text: &str) -> bool {
@@ -86,7 +85,7 @@ fn print_help() {
 }
 
 fn print_version() {
-    println!("{} (fcoreutils) {}", TOOL_NAME, VERSION);
+    coreutils_rs::common::version::print_version(TOOL_NAME);
 }
 
 #[derive(Clone, Copy, PartialEq)]
@@ -8,7 +8,6 @@ use std::io::Write;
 use std::process;
 
 const TOOL_NAME: &str = "dirname";
-const VERSION: &str = env!("CARGO_PKG_VERSION");
 
 fn main() {
     coreutils_rs::common::reset_sigpipe();
@@ -35,7 +34,7 @@ fn main() {
                 return;
             }
             "--version" => {
-                println!("{} (fcoreutils) {}", TOOL_NAME, VERSION);
+                coreutils_rs::common::version::print_version(TOOL_NAME);
                 return;
             }
             "--zero" | "-z" => zero = true,
@@ -58,7 +58,7 @@ Summarize device usage of the set of FILEs, recursively for directories.
 
 #[cfg(unix)]
 fn version() {
-    eprintln!("{} (fcoreutils) {}", TOOL_NAME, env!("CARGO_PKG_VERSION"));
+    coreutils_rs::common::version::print_version(TOOL_NAME);
 }
 
 /// Parse command-line arguments manually (matching the project's style for sort, touch, etc.).
@@ -2,38 +2,40 @@ use std::io::{self, Write};
 use std::process;
 
 use coreutils_rs::common::reset_sigpipe;
-use coreutils_rs::echo::{echo_output, parse_echo_args};
+use coreutils_rs::echo::{echo_output, join_args, parse_echo_args};
 
 fn main() {
     reset_sigpipe();
 
-    let args: Vec<String> = std::env::args().skip(1).collect();
+    // Fastest path: `echo` and `echo WORD` dominate shell loops
+    #[cfg(unix)]
+    if let Some(result) = write_trivial() {
+        if let Err(e) = result {
+            write_error(e);
+        }
+        return;
+    }
+
+    // Raw argument bytes: a non-UTF-8 argument is echoed as is
+    let args: Vec<Vec<u8>> = std::env::args_os()
+        .skip(1)
+        .map(|a| a.into_encoded_bytes())
+        .collect();
     let (config, text_args) = parse_echo_args(&args);
 
     let stdout = io::stdout();
     let mut out = stdout.lock();
 
-    // Fast path: no escape interpretation — write args directly to stdout
-    // avoiding intermediate Vec allocation entirely.
+    // Fast path: no escape interpretation — join the args and write the
+    // line at once.
     if !config.interpret_escapes {
-        let result = (|| -> io::Result<()> {
-            for (i, arg) in text_args.iter().enumerate() {
-                if i > 0 {
-                    out.write_all(b" ")?;
-                }
-                out.write_all(arg.as_bytes())?;
-            }
-            if config.trailing_newline {
-                out.write_all(b"\n")?;
-            }
-            Ok(())
-        })();
-        if let Err(e) = result {
-            if e.kind() == io::ErrorKind::BrokenPipe {
-                process::exit(0);
-            }
-            eprintln!("echo: write error: {}", e);
-            process::exit(1);
+        let mut line = Vec::new();
+        join_args(text_args, &mut line);
+        if config.trailing_newline {
+            line.push(b'\n');
+        }
+        if let Err(e) = out.write_all(&line) {
+            write_error(e);
         }
         return;
     }
@@ -41,10 +43,62 @@ fn main() {
     // Slow path: escape interpretation needed
     let output = echo_output(text_args, &config);
     if let Err(e) = out.write_all(&output) {
-        if e.kind() == io::ErrorKind::BrokenPipe {
-            process::exit(0);
+        write_error(e);
+    }
+}
+
+/// Handle no arguments, or a single argument that neither starts with `-`
+/// nor contains a backslash. Such a call can hold no option and no escape
+/// (even with POSIXLY_CORRECT, where escapes are always interpreted), so
+/// the line goes out in one write(2) without parsing or locking stdout.
+/// Returns None for anything else.
+#[cfg(unix)]
+fn write_trivial() -> Option<io::Result<()>> {
+    use std::os::unix::ffi::OsStrExt;
+
+    let mut args = std::env::args_os().skip(1);
+    let first = args.next();
+    if args.next().is_some() {
+        return None;
+    }
+    let line;
+    let bytes: &[u8] = match first {
+        None => b"\n",
+        Some(arg) => {
+            let arg = arg.as_bytes();
+            if arg.first() == Some(&b'-') || memchr::memchr(b'\\', arg).is_some() {
+                return None;
+            }
+            let mut buf = Vec::with_capacity(arg.len() + 1);
+            buf.extend_from_slice(arg);
+            buf.push(b'\n');
+            line = buf;
+            &line
         }
-        eprintln!("echo: write error: {}", e);
-        process::exit(1);
+    };
+
+    let mut rest = bytes;
+    while !rest.is_empty() {
+        let n = unsafe { libc::write(1, rest.as_ptr() as *const libc::c_void, rest.len()) };
+        if n < 0 {
+            let e = io::Error::last_os_error();
+            if e.kind() == io::ErrorKind::Interrupted {
+                continue;
+            }
+            return Some(Err(e));
+        }
+        if n == 0 {
+            return Some(Err(io::ErrorKind::WriteZero.into()));
+        }
+        rest = &rest[n as usize..];
+    }
+    Some(Ok(()))
+}
+
+fn write_error(e: io::Error) -> ! {
+    if e.kind() == io::ErrorKind::BrokenPipe {
+        process::exit(0);
     }
+    eprintln!("echo: write error: {}", e);
+    process::exit(1);
 }
@@ -13,8 +13,6 @@ use std::process;
 
 #[cfg(unix)]
 const TOOL_NAME: &str = "env";
-#[cfg(unix)]
-const VERSION: &str = env!("CARGO_PKG_VERSION");
 
 #[cfg(unix)]
 fn main() {
@@ -74,7 +72,7 @@ fn main() {
                 return;
             }
             "--version" => {
-                println!("{} (fcoreutils) {}", TOOL_NAME, VERSION);
+                coreutils_rs::common::version::print_version(TOOL_NAME);
                 return;
             }
             "-i" | "--ignore-environment" => ignore_env = true,
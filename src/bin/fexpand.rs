@@ -72,7 +72,7 @@ fn parse_args() -> Cli {
                     process::exit(0);
                 }
                 b"--version" => {
-                    println!("expand (fcoreutils) {}", env!("CARGO_PKG_VERSION"));
+                    coreutils_rs::common::version::print_version("expand");
                     process::exit(0);
                 }
                 _ => {
@@ -1,15 +1,29 @@
+use std::borrow::Cow;
+use std::ffi::OsString;
 use std::process;
 
-use coreutils_rs::expr::{EXIT_FAILURE, EXIT_SUCCESS, evaluate_expr};
+use coreutils_rs::expr::{
+    EXIT_FAILURE, EXIT_SUCCESS, ExprError, ExprOptions, TraceEvent, evaluate_expr_with,
+};
 
 fn main() {
     coreutils_rs::common::reset_sigpipe();
 
-    let args: Vec<String> = std::env::args().skip(1).collect();
+    // args() would panic on an argument that is not valid Unicode. expr
+    // works on text, so such an argument is converted lossily instead (on
+    // Unix invalid bytes, on Windows unpaired surrogates, become U+FFFD).
+    // Valid arguments are borrowed, not copied.
+    let argv: Vec<OsString> = std::env::args_os().skip(1).collect();
+    let tokens: Vec<Cow<'_, str>> = argv.iter().map(|a| a.to_string_lossy()).collect();
 
-    // Handle --help and --version before parsing expression
-    if args.len() == 1 {
-        match args[0].as_str() {
+    // Only a lone --help or --version is an option. Every other token,
+    // including "-5" or "-e", is an expression operand, and a single leading
+    // "--" ends option processing so "expr -- --help" prints "--help".
+    let mut args = &tokens[..];
+    if args.first().is_some_and(|a| a == "--") {
+        args = &args[1..];
+    } else if args.len() == 1 {
+        match &*args[0] {
             "--help" => {
                 print_help();
                 process::exit(EXIT_SUCCESS);
@@ -22,7 +36,23 @@ fn main() {
         }
     }
 
-    match evaluate_expr(&args) {
+    // EXPR_DEBUG=1 prints the evaluation tree to stderr; stdout and the
+    // exit status are unaffected.
+    let debug = std::env::var_os("EXPR_DEBUG").is_some_and(|v| v == "1");
+    let mut print_event = |event: TraceEvent<'_>| eprintln!("expr: debug: {}", event);
+    let trace = if debug {
+        Some(&mut print_event as &mut dyn FnMut(TraceEvent<'_>))
+    } else {
+        None
+    };
+
+    // POSIXLY_CORRECT turns off the GNU extensions: the keyword functions
+    // and the \+ and \? regex operators
+    let options = ExprOptions {
+        posix: std::env::var_os("POSIXLY_CORRECT").is_some(),
+    };
+
+    match evaluate_expr_with(args, options, trace) {
         Ok(value) => {
             println!("{}", value);
             if value.is_null() {
@@ -33,6 +63,11 @@ fn main() {
         }
         Err(e) => {
             eprintln!("expr: {}", e);
+            // Like GNU, only a missing operand is a usage error worth the
+            // hint; syntax errors in a non-empty expression are not
+            if matches!(e, ExprError::MissingOperand) {
+                eprintln!("Try 'expr --help' for more information.");
+            }
             process::exit(e.exit_code());
         }
     }
@@ -75,11 +110,15 @@ fn print_help() {
     println!();
     println!("  ( EXPRESSION )    value of EXPRESSION");
     println!();
+    println!("Set EXPR_DEBUG=1 to print a trace of the evaluation to standard error.");
+    println!("With POSIXLY_CORRECT set, match, substr, index and length are plain");
+    println!("strings, and \\+ and \\? in a REGEX match a literal '+' and '?'.");
+    println!();
     println!("Exit status is 0 if EXPRESSION is neither null nor 0, 1 if EXPRESSION");
     println!("is null or 0, 2 if EXPRESSION is syntactically invalid, and 3 if an");
     println!("error occurred.");
 }
 
 fn print_version() {
-    println!("expr (fcoreutils) {}", env!("CARGO_PKG_VERSION"));
+    coreutils_rs::common::version::print_version("expr");
 }
@@ -23,7 +23,7 @@ fn print_help() {
 }
 
 fn print_version() {
-    println!("{} (fcoreutils) {}", TOOL_NAME, env!("CARGO_PKG_VERSION"));
+    coreutils_rs::common::version::print_version(TOOL_NAME);
 }
 
 /// Process a single token: parse as u128, print factors, return true on success.
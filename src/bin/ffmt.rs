@@ -1,33 +1,52 @@
+use std::ffi::OsStr;
 use std::fs::File;
-use std::io::{self, BufReader, BufWriter, Write};
+use std::io::{self, BufRead, BufReader, BufWriter, Read, Write};
 #[cfg(unix)]
 use std::mem::ManuallyDrop;
 #[cfg(unix)]
 use std::os::unix::io::FromRawFd;
 use std::process;
 
+use coreutils_rs::common::diag::{Diag, quote_always};
+use coreutils_rs::common::io::should_retry;
 use coreutils_rs::common::io_error_msg;
 use coreutils_rs::fmt::FmtConfig;
 
+/// Widest -w GNU fmt accepts: half its paragraph buffer.
+const MAX_WIDTH: usize = 2500;
+
 struct Cli {
-    width: usize,
+    /// -w, if given; the default depends on the goal.
+    width: Option<usize>,
+    /// `--width=auto`: take the width from the terminal on stdout.
+    auto_width: bool,
+    tab_width: usize,
     goal: Option<usize>,
     split_only: bool,
     crown_margin: bool,
     tagged: bool,
     uniform_spacing: bool,
+    break_at_sentences: bool,
+    preserve_final_newline: bool,
+    /// Format the operands as one stream instead of one at a time.
+    join_files: bool,
     prefix: Option<String>,
     files: Vec<String>,
 }
 
 fn parse_args() -> Cli {
     let mut cli = Cli {
-        width: 75,
+        width: None,
+        auto_width: false,
+        tab_width: 8,
         goal: None,
         split_only: false,
         crown_margin: false,
         tagged: false,
         uniform_spacing: false,
+        break_at_sentences: false,
+        preserve_final_newline: false,
+        join_files: false,
         prefix: None,
         files: Vec::new(),
     };
@@ -46,14 +65,11 @@ fn parse_args() -> Cli {
         if bytes.starts_with(b"--") {
             // Handle --key=value forms.
             if bytes.starts_with(b"--width=") {
-                let val = arg.to_string_lossy();
-                match val[8..].parse::<usize>() {
-                    Ok(w) => cli.width = w,
-                    Err(_) => {
-                        eprintln!("fmt: invalid width: '{}'", &val[8..]);
-                        process::exit(1);
-                    }
-                }
+                set_width(&mut cli, &arg.to_string_lossy()[8..]);
+                continue;
+            }
+            if bytes.starts_with(b"--tab-width=") {
+                cli.tab_width = parse_tab_width(&arg.to_string_lossy()[12..]);
                 continue;
             }
             if bytes.starts_with(b"--goal=") {
@@ -77,6 +93,9 @@ fn parse_args() -> Cli {
                 b"--split-only" => cli.split_only = true,
                 b"--tagged-paragraph" => cli.tagged = true,
                 b"--uniform-spacing" => cli.uniform_spacing = true,
+                b"--break-at-sentences" => cli.break_at_sentences = true,
+                b"--preserve-final-newline" => cli.preserve_final_newline = true,
+                b"--join-files" => cli.join_files = true,
                 b"--width" => {
                     let val = args
                         .next()
@@ -86,13 +105,18 @@ fn parse_args() -> Cli {
                         })
                         .to_string_lossy()
                         .into_owned();
-                    match val.parse::<usize>() {
-                        Ok(w) => cli.width = w,
-                        Err(_) => {
-                            eprintln!("fmt: invalid width: '{}'", val);
+                    set_width(&mut cli, &val);
+                }
+                b"--tab-width" => {
+                    let val = args
+                        .next()
+                        .unwrap_or_else(|| {
+                            eprintln!("fmt: option '--tab-width' requires an argument");
                             process::exit(1);
-                        }
-                    }
+                        })
+                        .to_string_lossy()
+                        .into_owned();
+                    cli.tab_width = parse_tab_width(&val);
                 }
                 b"--goal" => {
                     let val = args
@@ -135,15 +159,22 @@ fn parse_args() -> Cli {
                          \x20 -s, --split-only           split long lines, but do not refill\n\
                          \x20 -t, --tagged-paragraph     indentation of first line different from second\n\
                          \x20 -u, --uniform-spacing      one space between words, two after sentences\n\
-                         \x20 -w, --width=WIDTH          maximum line width (default of 75 columns)\n\
+                         \x20     --break-at-sentences   start every sentence on a new line\n\
+                         \x20 -w, --width=WIDTH          maximum line width (default of 75 columns);\n\
+                         \x20                            'auto' uses the terminal width, else 75\n\
                          \x20 -g, --goal=WIDTH           goal width (default of 93% of width)\n\
+                         \x20     --tab-width=N          columns per tab stop in indentation (default 8)\n\
+                         \x20     --preserve-final-newline  end the output without a newline when the\n\
+                         \x20                            input's last line has none\n\
+                         \x20     --join-files           format the FILEs as one text, so a paragraph\n\
+                         \x20                            may run on from one file into the next\n\
                          \x20     --help                 display this help and exit\n\
                          \x20     --version              output version information and exit\n"
                     );
                     process::exit(0);
                 }
                 b"--version" => {
-                    println!("fmt (fcoreutils) {}", env!("CARGO_PKG_VERSION"));
+                    coreutils_rs::common::version::print_version("fmt");
                     process::exit(0);
                 }
                 _ => {
@@ -163,14 +194,7 @@ fn parse_args() -> Cli {
                     b'u' => cli.uniform_spacing = true,
                     b'w' => {
                         if i + 1 < bytes.len() {
-                            let val = arg.to_string_lossy();
-                            match val[i + 1..].parse::<usize>() {
-                                Ok(w) => cli.width = w,
-                                Err(_) => {
-                                    eprintln!("fmt: invalid width: '{}'", &val[i + 1..]);
-                                    process::exit(1);
-                                }
-                            }
+                            set_width(&mut cli, &arg.to_string_lossy()[i + 1..]);
                         } else {
                             let val = args
                                 .next()
@@ -180,13 +204,7 @@ fn parse_args() -> Cli {
                                 })
                                 .to_string_lossy()
                                 .into_owned();
-                            match val.parse::<usize>() {
-                                Ok(w) => cli.width = w,
-                                Err(_) => {
-                                    eprintln!("fmt: invalid width: '{}'", val);
-                                    process::exit(1);
-                                }
-                            }
+                            set_width(&mut cli, &val);
                         }
                         break;
                     }
@@ -240,7 +258,7 @@ fn parse_args() -> Cli {
                         // -WIDTH shorthand: -72 means --width=72.
                         let val = arg.to_string_lossy();
                         match val[i..].parse::<usize>() {
-                            Ok(w) => cli.width = w,
+                            Ok(w) => cli.width = Some(w),
                             Err(_) => {
                                 eprintln!("fmt: invalid width: '{}'", &val[i..]);
                                 process::exit(1);
@@ -264,21 +282,284 @@ fn parse_args() -> Cli {
     cli
 }
 
+/// Apply a -w/--width value: a column count, or "auto" for the terminal width.
+fn set_width(cli: &mut Cli, val: &str) {
+    if val == "auto" {
+        cli.auto_width = true;
+        return;
+    }
+    match val.parse::<usize>() {
+        Ok(w) => {
+            cli.width = Some(w);
+            cli.auto_width = false;
+        }
+        Err(_) => {
+            eprintln!("fmt: invalid width: '{}'", val);
+            process::exit(1);
+        }
+    }
+}
+
+fn parse_tab_width(val: &str) -> usize {
+    match val.parse::<usize>() {
+        Ok(n) if n > 0 => n,
+        _ => {
+            eprintln!("fmt: invalid tab width: '{}'", val);
+            process::exit(1);
+        }
+    }
+}
+
+/// Width of the terminal on stdout, or 75 when stdout is not a terminal.
+/// COLUMNS is deliberately ignored: only an explicit --width=auto opts in.
+fn terminal_width() -> usize {
+    #[cfg(unix)]
+    {
+        let mut ws: libc::winsize = unsafe { std::mem::zeroed() };
+        if unsafe { libc::ioctl(1, libc::TIOCGWINSZ, &mut ws) } == 0 && ws.ws_col > 0 {
+            return ws.ws_col as usize;
+        }
+    }
+    75
+}
+
+/// Format one FILE operand. Open and read failures are reported against
+/// the file name and the run moves on to the next operand; only a write
+/// failure is returned.
+fn fmt_operand<W: Write>(
+    filename: &str,
+    out: &mut W,
+    config: &FmtConfig,
+    diag: &mut Diag,
+) -> io::Result<()> {
+    let mut out = TrackedWriter {
+        inner: out,
+        failed: false,
+    };
+    let result = if filename == "-" {
+        coreutils_rs::fmt::fmt_file(io::stdin().lock(), &mut out, config)
+    } else {
+        match File::open(filename) {
+            Ok(f) => coreutils_rs::fmt::fmt_file(BufReader::new(f), &mut out, config),
+            Err(e) => {
+                diag.error(
+                    None,
+                    &format_args!(
+                        "cannot open {} for reading: {}",
+                        quote_always(OsStr::new(filename)),
+                        io_error_msg(&e)
+                    ),
+                );
+                return Ok(());
+            }
+        }
+    };
+    match result {
+        Err(e) if !out.failed => {
+            // A directory opens fine and fails on the first read (EISDIR)
+            diag.error(
+                Some(OsStr::new(filename)),
+                &format_args!("read error: {}", io_error_msg(&e)),
+            );
+            Ok(())
+        }
+        other => other,
+    }
+}
+
+/// The FILE operands read one after another as a single input, for
+/// `--join-files`. A file that cannot be opened or read is reported and
+/// skipped, as in the per-file mode. Each file ends a line: a file whose
+/// last line has no newline gets one, so its last word does not run into
+/// the first word of the next file.
+struct JoinedInput<'a> {
+    files: std::slice::Iter<'a, String>,
+    current: Option<(&'a str, Box<dyn BufRead>)>,
+    /// Last byte handed out from the current file.
+    last: Option<u8>,
+    /// The newline supplied for the current file is being read.
+    supplied: bool,
+    diag: &'a mut Diag,
+}
+
+impl<'a> JoinedInput<'a> {
+    fn new(files: &'a [String], diag: &'a mut Diag) -> Self {
+        JoinedInput {
+            files: files.iter(),
+            current: None,
+            last: None,
+            supplied: false,
+            diag,
+        }
+    }
+
+    /// Open the next operand that can be opened, if any is left.
+    fn open_next(&mut self) -> bool {
+        for filename in self.files.by_ref() {
+            let reader: Box<dyn BufRead> = if filename == "-" {
+                Box::new(io::stdin().lock())
+            } else {
+                match File::open(filename) {
+                    Ok(f) => Box::new(BufReader::new(f)),
+                    Err(e) => {
+                        self.diag.error(
+                            None,
+                            &format_args!(
+                                "cannot open {} for reading: {}",
+                                quote_always(OsStr::new(filename)),
+                                io_error_msg(&e)
+                            ),
+                        );
+                        continue;
+                    }
+                }
+            };
+            self.current = Some((filename, reader));
+            self.last = None;
+            return true;
+        }
+        false
+    }
+}
+
+impl Read for JoinedInput<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let avail = self.fill_buf()?;
+        let n = avail.len().min(buf.len());
+        buf[..n].copy_from_slice(&avail[..n]);
+        self.consume(n);
+        Ok(n)
+    }
+}
+
+impl BufRead for JoinedInput<'_> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        loop {
+            if self.supplied {
+                return Ok(b"\n");
+            }
+            if self.current.is_none() && !self.open_next() {
+                return Ok(&[]);
+            }
+            let (filename, reader) = self.current.as_mut().unwrap();
+            match reader.fill_buf() {
+                Ok([]) => {
+                    if self.last.is_some_and(|b| b != b'\n') {
+                        self.supplied = true;
+                    }
+                    self.current = None;
+                }
+                Ok(_) => break,
+                Err(e) if should_retry(&e) => {}
+                Err(e) => {
+                    // A directory opens fine and fails on the first read (EISDIR)
+                    self.diag.error(
+                        Some(OsStr::new(*filename)),
+                        &format_args!("read error: {}", io_error_msg(&e)),
+                    );
+                    if self.last.is_some_and(|b| b != b'\n') {
+                        self.supplied = true;
+                    }
+                    self.current = None;
+                }
+            }
+        }
+        // Buffered now, so this second call does no I/O
+        let buf = self.current.as_mut().unwrap().1.fill_buf()?;
+        self.last = buf.last().copied();
+        Ok(buf)
+    }
+
+    fn consume(&mut self, amt: usize) {
+        if self.supplied {
+            self.supplied = amt == 0;
+        } else if let Some((_, reader)) = self.current.as_mut() {
+            reader.consume(amt);
+        }
+    }
+}
+
+/// Remembers whether an error came from the output side, so `fmt_operand`
+/// can tell read errors (per file) from write errors (fatal for the run).
+struct TrackedWriter<'a, W: Write> {
+    inner: &'a mut W,
+    failed: bool,
+}
+
+impl<W: Write> Write for TrackedWriter<'_, W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let r = self.inner.write(buf);
+        self.failed |= r.is_err();
+        r
+    }
+
+    fn write_all(&mut self, buf: &[u8]) -> io::Result<()> {
+        let r = self.inner.write_all(buf);
+        self.failed |= r.is_err();
+        r
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        let r = self.inner.flush();
+        self.failed |= r.is_err();
+        r
+    }
+}
+
+/// Report a failed write; a closed pipe ends the run quietly.
+fn write_failed(e: &io::Error, diag: &mut Diag) {
+    if e.kind() == io::ErrorKind::BrokenPipe {
+        process::exit(0);
+    }
+    diag.error(None, &format_args!("write error: {}", io_error_msg(e)));
+}
+
 fn main() {
     coreutils_rs::common::reset_sigpipe();
 
-    let cli = parse_args();
+    let mut cli = parse_args();
+    if cli.break_at_sentences && cli.split_only {
+        eprintln!("fmt: --break-at-sentences cannot be combined with -s");
+        eprintln!("Try 'fmt --help' for more information.");
+        process::exit(1);
+    }
+    if cli.auto_width {
+        cli.width = Some(terminal_width());
+    }
 
-    let goal = cli.goal.unwrap_or((cli.width * 93) / 100);
+    // Like GNU: the width is capped at 2500 and the goal at the width
+    // (75 when not given)
+    let out_of_range = |n: usize| -> ! {
+        eprintln!("fmt: invalid width: '{}': Numerical result out of range", n);
+        process::exit(1);
+    };
+    if let Some(width) = cli.width.filter(|&w| w > MAX_WIDTH) {
+        out_of_range(width);
+    }
+    if let Some(goal) = cli.goal.filter(|&g| g > cli.width.unwrap_or(75)) {
+        out_of_range(goal);
+    }
+
+    // GNU's defaults: a goal of 93% of the width, rounded the way fmt.c
+    // does it, and with only a goal, a width 10 columns wider
+    let (width, goal) = match (cli.width, cli.goal) {
+        (Some(width), Some(goal)) => (width, goal),
+        (Some(width), None) => (width, width * 187 / 200),
+        (None, Some(goal)) => (goal + 10, goal),
+        (None, None) => (75, 75 * 187 / 200),
+    };
 
     let config = FmtConfig {
-        width: cli.width,
+        width,
         goal,
         split_only: cli.split_only,
         crown_margin: cli.crown_margin,
         tagged: cli.tagged,
         uniform_spacing: cli.uniform_spacing,
         prefix: cli.prefix,
+        tab_width: cli.tab_width,
+        break_at_sentences: cli.break_at_sentences,
+        preserve_final_newline: cli.preserve_final_newline,
     };
 
     let files: Vec<String> = if cli.files.is_empty() {
@@ -296,44 +577,28 @@ fn main() {
     #[cfg(not(unix))]
     let mut out = BufWriter::with_capacity(256 * 1024, stdout.lock());
 
-    let mut had_error = false;
-
-    for filename in &files {
-        let result = if filename == "-" {
-            let stdin = io::stdin();
-            let reader = BufReader::new(stdin.lock());
-            coreutils_rs::fmt::fmt_file(reader, &mut out, &config)
-        } else {
-            match File::open(filename) {
-                Ok(f) => {
-                    let reader = BufReader::new(f);
-                    coreutils_rs::fmt::fmt_file(reader, &mut out, &config)
-                }
-                Err(e) => {
-                    eprintln!("fmt: {}: {}", filename, io_error_msg(&e));
-                    had_error = true;
-                    continue;
-                }
-            }
-        };
+    let mut diag = Diag::new("fmt");
 
-        if let Err(e) = result {
-            if e.kind() == io::ErrorKind::BrokenPipe {
-                process::exit(0);
+    // Like GNU fmt, each operand is formatted on its own by default, so a
+    // file boundary always ends a paragraph
+    if cli.join_files {
+        let input = JoinedInput::new(&files, &mut diag);
+        if let Err(e) = coreutils_rs::fmt::fmt_file(input, &mut out, &config) {
+            write_failed(&e, &mut diag);
+        }
+    } else {
+        for filename in &files {
+            if let Err(e) = fmt_operand(filename, &mut out, &config, &mut diag) {
+                write_failed(&e, &mut diag);
             }
-            eprintln!("fmt: write error: {}", io_error_msg(&e));
-            had_error = true;
         }
     }
 
     if let Err(e) = out.flush()
         && e.kind() != io::ErrorKind::BrokenPipe
     {
-        eprintln!("fmt: write error: {}", io_error_msg(&e));
-        had_error = true;
+        diag.error(None, &format_args!("write error: {}", io_error_msg(&e)));
     }
 
-    if had_error {
-        process::exit(1);
-    }
+    process::exit(diag.exit_code());
 }
@@ -83,7 +83,7 @@ fn parse_args() -> Cli {
                     process::exit(0);
                 }
                 b"--version" => {
-                    println!("fold (fcoreutils) {}", env!("CARGO_PKG_VERSION"));
+                    coreutils_rs::common::version::print_version("fold");
                     process::exit(0);
                 }
                 _ => {
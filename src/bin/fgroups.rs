@@ -7,22 +7,31 @@ fn main() {
 // fgroups — print the groups a user is in
 //
 // Usage: groups [USERNAME]...
+//        groups --stdin [-0]
 
+#[cfg(unix)]
+use std::collections::HashMap;
 #[cfg(unix)]
 use std::ffi::CStr;
 #[cfg(unix)]
+use std::io::{self, BufRead, Write};
+#[cfg(unix)]
 use std::process;
 
 #[cfg(unix)]
-const TOOL_NAME: &str = "groups";
+use coreutils_rs::common::userinfo::UserDb;
+
 #[cfg(unix)]
-const VERSION: &str = env!("CARGO_PKG_VERSION");
+const TOOL_NAME: &str = "groups";
 
 #[cfg(unix)]
 fn main() {
     coreutils_rs::common::reset_sigpipe();
 
     let mut users: Vec<String> = Vec::new();
+    let mut from_stdin = false;
+    let mut null = false;
+    let mut files_only = false;
 
     for arg in std::env::args().skip(1) {
         match arg.as_str() {
@@ -33,21 +42,48 @@ fn main() {
                 );
                 println!("for the current process.");
                 println!();
-                println!("      --help     display this help and exit");
-                println!("      --version  output version information and exit");
+                println!(
+                    "      --stdin       read the USERNAMEs from standard input, one per line"
+                );
+                println!("  -0, --null        with --stdin, USERNAMEs end with NUL, not newline");
+                println!("      --files-only  read /etc/passwd and /etc/group directly, not");
+                println!("                      through NSS (the fallback when NSS finds nothing)");
+                println!("      --help        display this help and exit");
+                println!("      --version     output version information and exit");
                 return;
             }
             "--version" => {
-                println!("{} (fcoreutils) {}", TOOL_NAME, VERSION);
+                coreutils_rs::common::version::print_version(TOOL_NAME);
                 return;
             }
+            "--stdin" => from_stdin = true,
+            "-0" | "--null" => null = true,
+            "--files-only" => files_only = true,
             _ => users.push(arg),
         }
     }
 
+    if null && !from_stdin {
+        eprintln!("{}: --null is meaningful only with --stdin", TOOL_NAME);
+        eprintln!("Try '{} --help' for more information.", TOOL_NAME);
+        process::exit(1);
+    }
+    if from_stdin {
+        if let Some(extra) = users.first() {
+            eprintln!("{}: extra operand '{}'", TOOL_NAME, extra);
+            eprintln!("Try '{} --help' for more information.", TOOL_NAME);
+            process::exit(1);
+        }
+        process::exit(run_stdin(if null { b'\0' } else { b'\n' }, files_only));
+    }
+
+    let mut lookup = Lookup {
+        files_only,
+        ..Lookup::default()
+    };
     if users.is_empty() {
         // Print groups for current user
-        match get_current_groups() {
+        match get_current_groups(&mut lookup) {
             Ok(groups) => println!("{}", groups.join(" ")),
             Err(e) => {
                 eprintln!("{}: {}", TOOL_NAME, e);
@@ -57,7 +93,7 @@ fn main() {
     } else {
         let mut exit_code = 0;
         for user in &users {
-            match get_user_groups(user) {
+            match lookup.user_groups(user.as_bytes()) {
                 Ok(groups) => println!("{} : {}", user, groups.join(" ")),
                 Err(e) => {
                     eprintln!("{}: '{}': {}", TOOL_NAME, user, e);
@@ -71,8 +107,124 @@ fn main() {
     }
 }
 
+/// `--stdin`: resolve the names on standard input, each ended by `delim`,
+/// one at a time as they are read, so a list of any length runs in
+/// constant memory. Empty names are skipped; anything else, including
+/// interior blanks, is looked up as is. Returns the exit status: 1 if
+/// any name failed or stdin could not be read.
+#[cfg(unix)]
+fn run_stdin(delim: u8, files_only: bool) -> i32 {
+    let mut exit_code = 0;
+    let mut lookup = Lookup {
+        files_only,
+        ..Lookup::default()
+    };
+    let stdin = io::stdin();
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+    for user in stdin.lock().split(delim) {
+        let user = match user {
+            Ok(user) => user,
+            Err(e) => {
+                eprintln!(
+                    "{}: standard input: {}",
+                    TOOL_NAME,
+                    coreutils_rs::common::io_error_msg(&e)
+                );
+                return 1;
+            }
+        };
+        if user.is_empty() {
+            continue;
+        }
+        let written = match lookup.user_groups(&user) {
+            Ok(groups) => out
+                .write_all(&user)
+                .and_then(|()| writeln!(out, " : {}", groups.join(" "))),
+            Err(e) => {
+                let _ = out.flush();
+                eprintln!("{}: '{}': {}", TOOL_NAME, String::from_utf8_lossy(&user), e);
+                exit_code = 1;
+                Ok(())
+            }
+        };
+        if let Err(e) = written {
+            if e.kind() == io::ErrorKind::BrokenPipe {
+                process::exit(0);
+            }
+            eprintln!(
+                "{}: write error: {}",
+                TOOL_NAME,
+                coreutils_rs::common::io_error_msg(&e)
+            );
+            return 1;
+        }
+    }
+    exit_code
+}
+
+/// User and group lookups for one run. Group names are resolved once per
+/// gid: the users of one system mostly share the same few groups. Where
+/// NSS knows nothing, as in static builds or scratch containers without
+/// libnss, and always under --files-only, the answer comes from reading
+/// /etc/passwd and /etc/group directly.
+#[cfg(unix)]
+#[derive(Default)]
+struct Lookup {
+    files_only: bool,
+    names: HashMap<libc::gid_t, String>,
+    /// The passwd and group files, read on first use; `Some(None)` if
+    /// they could not be read.
+    files: Option<Option<UserDb>>,
+}
+
 #[cfg(unix)]
-fn get_current_groups() -> Result<Vec<String>, String> {
+impl Lookup {
+    fn files(&mut self) -> Option<&UserDb> {
+        self.files
+            .get_or_insert_with(|| UserDb::load().ok())
+            .as_ref()
+    }
+
+    fn group_name(&mut self, gid: libc::gid_t) -> String {
+        if let Some(name) = self.names.get(&gid) {
+            return name.clone();
+        }
+        let nss = if self.files_only {
+            None
+        } else {
+            nss_group_name(gid)
+        };
+        let name = nss
+            .or_else(|| {
+                self.files()
+                    .and_then(|db| db.group_by_gid(gid))
+                    .map(|g| g.name.clone())
+            })
+            .unwrap_or_else(|| gid.to_string());
+        self.names.insert(gid, name.clone());
+        name
+    }
+
+    fn user_groups(&mut self, user: &[u8]) -> Result<Vec<String>, String> {
+        let nss = if self.files_only {
+            None
+        } else {
+            nss_user_groups(user)?
+        };
+        let gids = match nss {
+            Some(gids) => gids,
+            None => self
+                .files()
+                .and_then(|db| Some(db.group_list(user, db.user_by_name(user)?.gid)))
+                .ok_or_else(|| "no such user".to_string())?,
+        };
+        Ok(gids.into_iter().map(|gid| self.group_name(gid)).collect())
+    }
+}
+
+#[cfg(unix)]
+fn get_current_groups(lookup: &mut Lookup) -> Result<Vec<String>, String> {
     let ngroups = unsafe { libc::getgroups(0, std::ptr::null_mut()) };
     if ngroups < 0 {
         return Err("cannot get groups".to_string());
@@ -96,15 +248,17 @@ fn get_current_groups() -> Result<Vec<String>, String> {
         gids.insert(0, egid);
     }
 
-    Ok(gids.iter().map(|&gid| gid_to_name(gid)).collect())
+    Ok(gids.iter().map(|&gid| lookup.group_name(gid)).collect())
 }
 
+/// The groups of `user` by getgrouplist(), or `None` if NSS has no such
+/// user.
 #[cfg(unix)]
-fn get_user_groups(user: &str) -> Result<Vec<String>, String> {
+fn nss_user_groups(user: &[u8]) -> Result<Option<Vec<libc::gid_t>>, String> {
     let c_user = std::ffi::CString::new(user).map_err(|_| "invalid username".to_string())?;
     let pw = unsafe { libc::getpwnam(c_user.as_ptr()) };
     if pw.is_null() {
-        return Err("no such user".to_string());
+        return Ok(None);
     }
     let pw_gid = unsafe { (*pw).pw_gid };
 
@@ -135,10 +289,7 @@ fn get_user_groups(user: &str) -> Result<Vec<String>, String> {
             }
         }
         gids.truncate(ngroups as usize);
-        Ok(gids
-            .iter()
-            .map(|&gid| gid_to_name(gid as libc::gid_t))
-            .collect())
+        Ok(Some(gids.iter().map(|&gid| gid as libc::gid_t).collect()))
     }
 
     #[cfg(not(target_vendor = "apple"))]
@@ -164,19 +315,19 @@ fn get_user_groups(user: &str) -> Result<Vec<String>, String> {
             }
         }
         gids.truncate(ngroups as usize);
-        Ok(gids.iter().map(|&gid| gid_to_name(gid)).collect())
+        Ok(Some(gids))
     }
 }
 
 #[cfg(unix)]
-fn gid_to_name(gid: libc::gid_t) -> String {
+fn nss_group_name(gid: libc::gid_t) -> Option<String> {
     let gr = unsafe { libc::getgrgid(gid) };
     if gr.is_null() {
-        return gid.to_string();
+        return None;
     }
     // SAFETY: getgrgid returned a valid pointer
     let name = unsafe { CStr::from_ptr((*gr).gr_name) };
-    name.to_string_lossy().into_owned()
+    Some(name.to_string_lossy().into_owned())
 }
 
 #[cfg(all(test, unix))]
@@ -216,6 +367,113 @@ mod tests {
         assert_eq!(output.status.code(), Some(1));
     }
 
+    fn current_user() -> String {
+        let pw = unsafe { libc::getpwuid(libc::getuid()) };
+        assert!(!pw.is_null());
+        let name = unsafe { std::ffi::CStr::from_ptr((*pw).pw_name) };
+        name.to_string_lossy().into_owned()
+    }
+
+    fn run_stdin(args: &[&str], input: &[u8]) -> std::process::Output {
+        use std::io::Write;
+        use std::process::Stdio;
+        let mut child = cmd()
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .unwrap();
+        child.stdin.take().unwrap().write_all(input).unwrap();
+        child.wait_with_output().unwrap()
+    }
+
+    #[test]
+    fn test_groups_stdin_matches_operands() {
+        let me = current_user();
+        let names = [me.as_str(), "root", "nonexistent_user_12345", "root"];
+        let by_operand = cmd().args(names).output().unwrap();
+
+        let input = format!("{}\n\nroot\nnonexistent_user_12345\nroot\n", me);
+        let output = run_stdin(&["--stdin"], input.as_bytes());
+        assert_eq!(output.status.code(), Some(1));
+        assert_eq!(output.stdout, by_operand.stdout);
+        assert_eq!(
+            String::from_utf8_lossy(&output.stderr),
+            "groups: 'nonexistent_user_12345': no such user\n"
+        );
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let lines: Vec<&str> = stdout.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].starts_with(&format!("{} : ", me)));
+        assert!(lines[1].starts_with("root : "));
+        assert_eq!(lines[1], lines[2]);
+
+        let input = format!("{}\0root\0\0nonexistent_user_12345\0root", me);
+        let output = run_stdin(&["--stdin", "-0"], input.as_bytes());
+        assert_eq!(output.status.code(), Some(1));
+        assert_eq!(output.stdout, by_operand.stdout);
+    }
+
+    #[test]
+    fn test_groups_stdin_all_found() {
+        let output = run_stdin(&["--stdin", "--null"], b"root\0");
+        assert_eq!(output.status.code(), Some(0));
+        assert!(String::from_utf8_lossy(&output.stdout).starts_with("root : "));
+        // Interior blanks are part of the name
+        let output = run_stdin(&["--stdin"], b"root \n");
+        assert_eq!(output.status.code(), Some(1));
+        assert_eq!(
+            String::from_utf8_lossy(&output.stderr),
+            "groups: 'root ': no such user\n"
+        );
+    }
+
+    #[test]
+    fn test_groups_stdin_usage_errors() {
+        for (args, msg) in [
+            (
+                &["-0"][..],
+                "groups: --null is meaningful only with --stdin\n",
+            ),
+            (&["--stdin", "root"], "groups: extra operand 'root'\n"),
+        ] {
+            let output = cmd().args(args).output().unwrap();
+            assert_eq!(output.status.code(), Some(1), "{:?}", args);
+            assert!(output.stdout.is_empty());
+            assert!(
+                String::from_utf8_lossy(&output.stderr).starts_with(msg),
+                "{:?}",
+                args
+            );
+        }
+    }
+
+    #[test]
+    fn test_groups_files_only() {
+        // root lives in /etc/passwd on any system, so the files answer
+        // matches what NSS gives
+        let by_nss = cmd().arg("root").output().unwrap();
+        let by_files = cmd().args(["--files-only", "root"]).output().unwrap();
+        assert_eq!(by_files.status.code(), Some(0));
+        assert_eq!(by_files.stdout, by_nss.stdout);
+
+        let output = run_stdin(
+            &["--stdin", "--files-only"],
+            b"root\nnonexistent_user_12345\n",
+        );
+        assert_eq!(output.status.code(), Some(1));
+        assert_eq!(output.stdout, by_nss.stdout);
+        assert_eq!(
+            String::from_utf8_lossy(&output.stderr),
+            "groups: 'nonexistent_user_12345': no such user\n"
+        );
+
+        let current = cmd().arg("--files-only").output().unwrap();
+        assert_eq!(current.status.code(), Some(0));
+        assert!(!current.stdout.is_empty());
+    }
+
     #[test]
     #[cfg(target_os = "linux")]
     fn test_groups_matches_gnu() {
@@ -23,8 +23,6 @@ use coreutils_rs::install::{
 
 #[cfg(unix)]
 const TOOL_NAME: &str = "install";
-#[cfg(unix)]
-const VERSION: &str = env!("CARGO_PKG_VERSION");
 
 #[cfg(unix)]
 fn main() {
@@ -49,7 +47,7 @@ fn main() {
                 return;
             }
             "--version" => {
-                println!("{} (fcoreutils) {}", TOOL_NAME, VERSION);
+                coreutils_rs::common::version::print_version(TOOL_NAME);
                 return;
             }
             "--" => saw_dashdash = true,
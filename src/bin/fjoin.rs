@@ -92,7 +92,7 @@ fn parse_args() -> Cli {
                     process::exit(0);
                 }
                 b"--version" => {
-                    println!("join (fcoreutils) {}", env!("CARGO_PKG_VERSION"));
+                    coreutils_rs::common::version::print_version("join");
                     process::exit(0);
                 }
                 _ => {
@@ -18,8 +18,6 @@ use std::process;
 
 #[cfg(unix)]
 const TOOL_NAME: &str = "ln";
-#[cfg(unix)]
-const VERSION: &str = env!("CARGO_PKG_VERSION");
 
 #[cfg(unix)]
 const DEFAULT_BACKUP_SUFFIX: &str = "~";
@@ -65,7 +63,7 @@ fn main() {
                 return;
             }
             "--version" => {
-                println!("{} (fcoreutils) {}", TOOL_NAME, VERSION);
+                coreutils_rs::common::version::print_version(TOOL_NAME);
                 return;
             }
             "-s" | "--symbolic" => symbolic = true,
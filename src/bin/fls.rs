@@ -161,7 +161,7 @@ fn parse_args() -> (LsConfig, Vec<String>) {
                     process::exit(0);
                 }
                 "version" => {
-                    println!("ls (fcoreutils) {}", env!("CARGO_PKG_VERSION"));
+                    coreutils_rs::common::version::print_version("ls");
                     process::exit(0);
                 }
                 "all" => config.all = true,
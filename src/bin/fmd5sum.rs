@@ -15,6 +15,8 @@ struct Cli {
     binary: bool,
     check: bool,
     tag: bool,
+    /// `--bsd`: tagged lines exactly as BSD tools print them.
+    bsd: bool,
     text: bool,
     ignore_missing: bool,
     quiet: bool,
@@ -22,6 +24,10 @@ struct Cli {
     strict: bool,
     warn: bool,
     zero: bool,
+    uppercase: bool,
+    cache: bool,
+    debug: bool,
+    direct_io: bool,
     files: Vec<String>,
 }
 
@@ -31,6 +37,7 @@ fn parse_args() -> Cli {
         binary: false,
         check: false,
         tag: false,
+        bsd: false,
         text: false,
         ignore_missing: false,
         quiet: false,
@@ -38,12 +45,16 @@ fn parse_args() -> Cli {
         strict: false,
         warn: false,
         zero: false,
+        uppercase: false,
+        cache: false,
+        debug: false,
+        direct_io: false,
         files: Vec::new(),
     };
 
-    let args = std::env::args_os().skip(1);
+    let mut args = std::env::args_os().skip(1);
     let mut saw_dashdash = false;
-    for arg in args {
+    while let Some(arg) = args.next() {
         let bytes = arg.as_encoded_bytes();
         if saw_dashdash {
             cli.files.push(arg.to_string_lossy().into_owned());
@@ -58,6 +69,10 @@ fn parse_args() -> Cli {
                 b"--binary" => cli.binary = true,
                 b"--check" => cli.check = true,
                 b"--tag" => cli.tag = true,
+                b"--bsd" => {
+                    cli.tag = true;
+                    cli.bsd = true;
+                }
                 b"--text" => cli.text = true,
                 b"--ignore-missing" => cli.ignore_missing = true,
                 b"--quiet" => cli.quiet = true,
@@ -65,6 +80,20 @@ fn parse_args() -> Cli {
                 b"--strict" => cli.strict = true,
                 b"--warn" => cli.warn = true,
                 b"--zero" => cli.zero = true,
+                b"--uppercase" => cli.uppercase = true,
+                b"--debug" => cli.debug = true,
+                b"--direct-io" => cli.direct_io = true,
+                b"--cache" => {
+                    let val = args.next().unwrap_or_else(|| {
+                        eprintln!("{}: option '--cache' requires an argument", TOOL_NAME);
+                        eprintln!("Try '{} --help' for more information.", TOOL_NAME);
+                        process::exit(1);
+                    });
+                    cli.cache = parse_cache_mode(&val.to_string_lossy());
+                }
+                _ if bytes.starts_with(b"--cache=") => {
+                    cli.cache = parse_cache_mode(&arg.to_string_lossy()[8..]);
+                }
                 b"--help" => {
                     print!(
                         "Usage: {} [OPTION]... [FILE]...\n\
@@ -73,8 +102,15 @@ fn parse_args() -> Cli {
                         \x20 -b, --binary         read in binary mode\n\
                         \x20 -c, --check          read checksums from the FILEs and check them\n\
                         \x20     --tag             create a BSD-style checksum\n\
+                        \x20     --bsd             like --tag, in the exact form BSD md5(1) prints\n\
                         \x20 -t, --text           read in text mode (default)\n\
-                        \x20 -z, --zero           end each output line with NUL, not newline\n\n\
+                        \x20 -z, --zero           end each output line with NUL, not newline\n\
+                        \x20     --uppercase       print hex digests in uppercase (ignored with -c)\n\
+                        \x20     --cache=xattr     reuse digests stored in extended attributes of\n\
+                        \x20                         files whose size and mtime are unchanged\n\
+                        \x20     --direct-io       read files with O_DIRECT, bypassing the page cache\n\
+                        \x20     --debug           report whether each digest was cached or computed,\n\
+                        \x20                         and when --direct-io fell back to buffered reads\n\n\
                         The following five options are useful only when verifying checksums:\n\
                         \x20     --ignore-missing  don't fail or report status for missing files\n\
                         \x20     --quiet           don't print OK for each successfully verified file\n\
@@ -88,7 +124,7 @@ fn parse_args() -> Cli {
                     process::exit(0);
                 }
                 b"--version" => {
-                    println!("{} (fcoreutils) {}", TOOL_NAME, env!("CARGO_PKG_VERSION"));
+                    coreutils_rs::common::version::print_version(TOOL_NAME);
                     process::exit(0);
                 }
                 _ => {
@@ -124,6 +160,17 @@ fn parse_args() -> Cli {
     cli
 }
 
+/// Parse the `--cache` argument; "xattr" is the only supported mode.
+fn parse_cache_mode(val: &str) -> bool {
+    if val == "xattr" {
+        return true;
+    }
+    eprintln!("{}: invalid argument '{}' for '--cache'", TOOL_NAME, val);
+    eprintln!("Valid arguments are:\n  - 'xattr'");
+    eprintln!("Try '{} --help' for more information.", TOOL_NAME);
+    process::exit(1);
+}
+
 /// Check if a filename needs escaping (contains backslash or newline).
 #[inline]
 fn needs_escape(name: &str) -> bool {
@@ -230,6 +277,16 @@ fn main() {
     let algo = HashAlgorithm::Md5;
 
     // Validate flag combinations
+    if cli.bsd && (cli.check || cli.zero) {
+        let why = if cli.check {
+            "is meaningless when verifying checksums"
+        } else {
+            "cannot be combined with --zero"
+        };
+        eprintln!("{}: the --bsd option {}", TOOL_NAME, why);
+        eprintln!("Try '{} --help' for more information.", TOOL_NAME);
+        process::exit(1);
+    }
     if cli.tag && cli.check {
         eprintln!(
             "{}: the --tag option is meaningless when verifying checksums",
@@ -296,6 +353,7 @@ fn main() {
                 warn: cli.warn,
                 ignore_missing: cli.ignore_missing,
                 warn_prefix: format!("{}: {}", TOOL_NAME, display_name),
+                direct_io: cli.direct_io,
             };
             match hash::check_file(algo, reader, &opts, &mut out, &mut err_out) {
                 Ok(r) => {
@@ -383,12 +441,16 @@ fn main() {
         // Hash mode
         let has_stdin = files.iter().any(|f| f == "-");
 
-        if has_stdin || files.len() <= 1 {
-            // Sequential for stdin or single file.
+        if has_stdin || files.len() <= 1 || cli.cache || cli.direct_io {
+            // Sequential for stdin, a single file, or the xattr cache.
             // Uses hash_file_nostat to skip fstat (~5µs/file).
             for filename in &files {
                 let hash_result = if filename == "-" {
                     hash::hash_stdin(algo)
+                } else if cli.cache {
+                    hash_file_cached(&mut out, &cli, algo, filename)
+                } else if cli.direct_io {
+                    hash_file_direct(&mut out, &cli, algo, filename)
                 } else {
                     hash::hash_file_nostat(algo, Path::new(filename))
                 };
@@ -428,6 +490,7 @@ fn main() {
             for (filename, result) in files.iter().zip(results) {
                 match result {
                     Ok(h) => {
+                        let h = hash::hex_in_case(&h, cli.uppercase);
                         if cli.tag {
                             let term = if cli.zero { b'\0' } else { b'\n' };
                             output_buf.extend_from_slice(algo.name().as_bytes());
@@ -480,10 +543,56 @@ fn main() {
     }
 }
 
+/// Hash a file through the xattr cache, reporting the digest source under --debug.
+fn hash_file_cached(
+    out: &mut impl Write,
+    cli: &Cli,
+    algo: HashAlgorithm,
+    filename: &str,
+) -> io::Result<String> {
+    let path = Path::new(filename);
+    let (h, source) = hash::hash_file_cached(path, "md5", || {
+        if cli.direct_io {
+            hash_file_direct(out, cli, algo, filename)
+        } else {
+            hash::hash_file_nostat(algo, path)
+        }
+    })?;
+    if cli.debug {
+        let _ = out.flush();
+        let how = match source {
+            hash::DigestSource::Cached => "served from xattr cache",
+            hash::DigestSource::Computed => "computed",
+        };
+        eprintln!("{}: {}: digest {}", TOOL_NAME, filename, how);
+    }
+    Ok(h)
+}
+
 /// Write hash output using single-write batched buffer for minimum overhead.
+/// Hash a file with O_DIRECT reads (--direct-io), noting under --debug when
+/// the filesystem forced reads through the page cache instead.
+fn hash_file_direct(
+    out: &mut impl Write,
+    cli: &Cli,
+    algo: HashAlgorithm,
+    filename: &str,
+) -> io::Result<String> {
+    let (h, direct) = hash::hash_file_direct(algo, Path::new(filename))?;
+    if cli.debug && !direct {
+        let _ = out.flush();
+        eprintln!(
+            "{}: {}: O_DIRECT not supported, using buffered reads",
+            TOOL_NAME, filename
+        );
+    }
+    Ok(h)
+}
+
 #[inline]
 fn write_output(out: &mut impl Write, cli: &Cli, algo: HashAlgorithm, hash: &str, filename: &str) {
     let binary = cli.binary || (!cli.text && cfg!(windows));
+    let hash: &str = &hash::hex_in_case(hash, cli.uppercase);
     if cli.tag {
         let _ = hash::write_hash_tag_line(out, algo.name(), hash, filename, cli.zero);
     } else if !cli.zero && needs_escape(filename) {
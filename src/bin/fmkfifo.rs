@@ -15,8 +15,6 @@ use std::process;
 
 #[cfg(unix)]
 const TOOL_NAME: &str = "mkfifo";
-#[cfg(unix)]
-const VERSION: &str = env!("CARGO_PKG_VERSION");
 
 #[cfg(unix)]
 fn main() {
@@ -44,7 +42,7 @@ fn main() {
                 return;
             }
             "--version" => {
-                println!("{} (fcoreutils) {}", TOOL_NAME, VERSION);
+                coreutils_rs::common::version::print_version(TOOL_NAME);
                 return;
             }
             "--" => saw_dashdash = true,
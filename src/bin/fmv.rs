@@ -20,8 +20,6 @@ use coreutils_rs::mv::{BackupMode, MvConfig, mv_file, parse_backup_mode, strip_t
 
 #[cfg(unix)]
 const TOOL_NAME: &str = "mv";
-#[cfg(unix)]
-const VERSION: &str = env!("CARGO_PKG_VERSION");
 
 #[cfg(unix)]
 fn main() {
@@ -46,7 +44,7 @@ fn main() {
                 return;
             }
             "--version" => {
-                println!("{} (fcoreutils) {}", TOOL_NAME, VERSION);
+                coreutils_rs::common::version::print_version(TOOL_NAME);
                 return;
             }
             "--" => saw_dashdash = true,
@@ -45,8 +45,6 @@ unsafe fn errno_ptr() -> *mut i32 {
 
 #[cfg(unix)]
 const TOOL_NAME: &str = "nice";
-#[cfg(unix)]
-const VERSION: &str = env!("CARGO_PKG_VERSION");
 
 #[cfg(unix)]
 fn main() {
@@ -73,7 +71,7 @@ fn main() {
                 return;
             }
             "--version" => {
-                println!("{} (fcoreutils) {}", TOOL_NAME, VERSION);
+                coreutils_rs::common::version::print_version(TOOL_NAME);
                 return;
             }
             s if s.starts_with("--adjustment=") => {
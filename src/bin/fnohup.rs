@@ -19,8 +19,6 @@ use std::process;
 
 #[cfg(unix)]
 const TOOL_NAME: &str = "nohup";
-#[cfg(unix)]
-const VERSION: &str = env!("CARGO_PKG_VERSION");
 
 #[cfg(unix)]
 fn main() {
@@ -53,7 +51,7 @@ fn main() {
             return;
         }
         "--version" => {
-            println!("{} (fcoreutils) {}", TOOL_NAME, VERSION);
+            coreutils_rs::common::version::print_version(TOOL_NAME);
             return;
         }
         _ => {}
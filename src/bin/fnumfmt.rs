@@ -11,7 +11,6 @@ use std::process;
 use coreutils_rs::numfmt::{self, InvalidMode, NumfmtConfig};
 
 const TOOL_NAME: &str = "numfmt";
-const VERSION: &str = env!("CARGO_PKG_VERSION");
 
 fn print_help() {
     println!("Usage: {} [OPTION]... [NUMBER]...", TOOL_NAME);
@@ -58,7 +57,7 @@ fn print_help() {
 }
 
 fn print_version() {
-    println!("{} (fcoreutils) {}", TOOL_NAME, VERSION);
+    coreutils_rs::common::version::print_version(TOOL_NAME);
 }
 
 fn parse_args() -> (NumfmtConfig, Vec<String>) {
@@ -11,7 +11,6 @@ use coreutils_rs::common::reset_sigpipe;
 use coreutils_rs::od::{AddressRadix, OdConfig, OutputFormat, od_process, parse_format_type};
 
 const TOOL_NAME: &str = "od";
-const VERSION: &str = env!("CARGO_PKG_VERSION");
 
 fn main() {
     reset_sigpipe();
@@ -41,7 +40,7 @@ fn main() {
                 return;
             }
             "--version" => {
-                println!("{} (fcoreutils) {}", TOOL_NAME, VERSION);
+                coreutils_rs::common::version::print_version(TOOL_NAME);
                 return;
             }
             "--" => saw_dashdash = true,
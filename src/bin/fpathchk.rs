@@ -5,7 +5,6 @@
 use std::process;
 
 const TOOL_NAME: &str = "pathchk";
-const VERSION: &str = env!("CARGO_PKG_VERSION");
 
 /// POSIX minimum limits
 const POSIX_NAME_MAX: usize = 14;
@@ -35,7 +34,7 @@ fn main() {
                 return;
             }
             "--version" => {
-                println!("{} (fcoreutils) {}", TOOL_NAME, VERSION);
+                coreutils_rs::common::version::print_version(TOOL_NAME);
                 return;
             }
             "--portability" => {
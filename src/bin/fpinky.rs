@@ -18,17 +18,29 @@ use std::process;
 use clap::Parser;
 
 #[cfg(unix)]
-use coreutils_rs::pinky;
+use coreutils_rs::common::diag;
+#[cfg(unix)]
+use coreutils_rs::common::io_error_msg;
+#[cfg(unix)]
+use coreutils_rs::{pinky, who};
 
 #[cfg(unix)]
 #[derive(Parser)]
-#[command(name = "pinky", version = env!("CARGO_PKG_VERSION"), about = "Lightweight finger", disable_help_flag = true)]
+// -h is GNU's "omit project", so help is --help only. Flags may repeat, and
+// the last of -l / -s selects the format, as in GNU.
+#[command(
+    name = "pinky",
+    version = env!("CARGO_PKG_VERSION"),
+    about = "Lightweight finger",
+    disable_help_flag = true,
+    args_override_self = true
+)]
 struct Cli {
     /// display this help and exit
     #[arg(long = "help", action = clap::ArgAction::Help)]
     help: Option<bool>,
     /// produce long format output for the specified USERs
-    #[arg(short = 'l')]
+    #[arg(short = 'l', overrides_with = "short_format")]
     long_format: bool,
 
     /// omit the user's home directory and shell in long format
@@ -44,7 +56,7 @@ struct Cli {
     omit_plan: bool,
 
     /// do short format output (default)
-    #[arg(short = 's')]
+    #[arg(short = 's', overrides_with = "long_format")]
     short_format: bool,
 
     /// omit the column of full names in short format
@@ -63,6 +75,14 @@ struct Cli {
     #[arg(short = 'q')]
     omit_fullname_host_idle: bool,
 
+    /// output format: text, or json for an object holding the records
+    #[arg(long = "format", value_name = "FORMAT", value_parser = ["text", "json"], default_value = "text")]
+    format: String,
+
+    /// read login records from FILE instead of the system's
+    #[arg(long = "file", value_name = "FILE")]
+    file: Option<std::path::PathBuf>,
+
     /// users to look up
     users: Vec<String>,
 }
@@ -74,12 +94,18 @@ fn main() {
     // Handle --version before clap
     let raw_args: Vec<String> = std::env::args().collect();
     if raw_args.iter().any(|a| a == "--version") {
-        println!("pinky (fcoreutils) {}", env!("CARGO_PKG_VERSION"));
+        coreutils_rs::common::version::print_version("pinky");
         process::exit(0);
     }
 
     let cli = Cli::parse();
 
+    if cli.long_format && cli.users.is_empty() {
+        eprintln!("pinky: no username specified; at least one must be specified when using -l");
+        eprintln!("Try 'pinky --help' for more information.");
+        process::exit(1);
+    }
+
     let short_format = !cli.long_format;
     let config = pinky::PinkyConfig {
         long_format: cli.long_format,
@@ -92,9 +118,24 @@ fn main() {
         omit_fullname_host: cli.omit_fullname_host,
         omit_fullname_host_idle: cli.omit_fullname_host_idle,
         users: cli.users,
+        json: cli.format == "json",
+    };
+
+    // Long format with named users does not consult the login records
+    let records = if config.long_format && !config.users.is_empty() {
+        who::LoginRecords::default()
+    } else {
+        who::read_login_records(cli.file.as_deref())
     };
+    if let Some(e) = &records.unavailable {
+        diag::error(
+            "pinky",
+            None,
+            &format!("cannot read user login records: {}", io_error_msg(e)),
+        );
+    }
 
-    let output = pinky::run_pinky(&config);
+    let output = pinky::run_pinky(&config, &records);
     if !output.is_empty() {
         println!("{}", output);
     }
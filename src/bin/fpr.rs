@@ -111,7 +111,7 @@ fn parse_args() -> Cli {
                         process::exit(0);
                     }
                     "--version" => {
-                        println!("pr (fcoreutils) {}", env!("CARGO_PKG_VERSION"));
+                        coreutils_rs::common::version::print_version("pr");
                         process::exit(0);
                     }
                     _ => {
@@ -6,7 +6,6 @@ use std::io::Write;
 use std::process;
 
 const TOOL_NAME: &str = "printf";
-const VERSION: &str = env!("CARGO_PKG_VERSION");
 
 fn print_help() {
     println!("Usage: {} FORMAT [ARGUMENT...]", TOOL_NAME);
@@ -46,7 +45,7 @@ fn print_help() {
 }
 
 fn print_version() {
-    println!("{} (fcoreutils) {}", TOOL_NAME, VERSION);
+    coreutils_rs::common::version::print_version(TOOL_NAME);
 }
 
 fn main() {
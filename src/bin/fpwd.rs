@@ -7,7 +7,6 @@ use std::path::PathBuf;
 use std::process;
 
 const TOOL_NAME: &str = "pwd";
-const VERSION: &str = env!("CARGO_PKG_VERSION");
 
 fn main() {
     coreutils_rs::common::reset_sigpipe();
@@ -31,7 +30,7 @@ fn main() {
                 return;
             }
             "--version" => {
-                println!("{} (fcoreutils) {}", TOOL_NAME, VERSION);
+                coreutils_rs::common::version::print_version(TOOL_NAME);
                 return;
             }
             "-L" | "--logical" => physical = false,
@@ -2,11 +2,16 @@
 //
 // Usage: readlink [OPTION]... FILE...
 
+use std::ffi::{OsStr, OsString};
+use std::io::{self, Write};
 use std::path::{Path, PathBuf};
 use std::process;
 
+use coreutils_rs::common::canonical::{Canonicalizer, Existence, Resolve};
+use coreutils_rs::common::diag::{self, Diag};
+use coreutils_rs::common::io_error_msg;
+
 const TOOL_NAME: &str = "readlink";
-const VERSION: &str = env!("CARGO_PKG_VERSION");
 
 #[derive(Clone, Copy, PartialEq, Eq)]
 enum CanonMode {
@@ -24,13 +29,15 @@ fn main() {
 
     let mut mode = CanonMode::None;
     let mut no_newline = false;
-    let mut quiet = false;
+    // GNU is silent on failure unless -v; the last of -q/-v wins
     let mut verbose = false;
     let mut zero = false;
-    let mut files: Vec<String> = Vec::new();
+    // Operands stay OsStrings: a name need not be valid UTF-8, and the
+    // link target printed for it must come out byte for byte.
+    let mut files: Vec<OsString> = Vec::new();
     let mut saw_dashdash = false;
 
-    let args: Vec<String> = std::env::args().skip(1).collect();
+    let args: Vec<OsString> = std::env::args_os().skip(1).collect();
     let mut i = 0;
     while i < args.len() {
         let arg = &args[i];
@@ -39,20 +46,26 @@ fn main() {
             i += 1;
             continue;
         }
-        match arg.as_str() {
+        // Options are ASCII; anything that is not valid UTF-8 is an operand
+        let Some(arg_str) = arg.to_str() else {
+            files.push(arg.clone());
+            i += 1;
+            continue;
+        };
+        match arg_str {
             "--help" => {
                 print_help();
                 return;
             }
             "--version" => {
-                println!("{} (fcoreutils) {}", TOOL_NAME, VERSION);
+                coreutils_rs::common::version::print_version(TOOL_NAME);
                 return;
             }
             "-f" | "--canonicalize" => mode = CanonMode::Canonicalize,
             "-e" | "--canonicalize-existing" => mode = CanonMode::CanonicalizeExisting,
             "-m" | "--canonicalize-missing" => mode = CanonMode::CanonicalizeMissing,
             "-n" | "--no-newline" => no_newline = true,
-            "-q" | "--quiet" | "--silent" => quiet = true,
+            "-q" | "--quiet" | "--silent" => verbose = false,
             "-v" | "--verbose" => verbose = true,
             "-z" | "--zero" => zero = true,
             "--" => saw_dashdash = true,
@@ -63,7 +76,7 @@ fn main() {
                         'e' => mode = CanonMode::CanonicalizeExisting,
                         'm' => mode = CanonMode::CanonicalizeMissing,
                         'n' => no_newline = true,
-                        'q' => quiet = true,
+                        'q' => verbose = false,
                         'v' => verbose = true,
                         'z' => zero = true,
                         _ => {
@@ -85,119 +98,123 @@ fn main() {
         process::exit(1);
     }
 
-    let terminator = if zero { "\0" } else { "\n" };
-    let mut exit_code = 0;
+    let terminator: &[u8] = if zero { b"\0" } else { b"\n" };
+    let mut diag = Diag::new(TOOL_NAME);
     let multiple = files.len() > 1;
+    let mut out = io::stdout().lock();
+    let mut canon = Canonicalizer::new();
 
-    for (idx, file) in files.iter().enumerate() {
-        match resolve(file, mode) {
+    for file in &files {
+        match resolve(&mut canon, file, mode) {
             Ok(resolved) => {
-                let s = resolved.to_string_lossy();
-                if no_newline && !multiple && idx == files.len() - 1 {
-                    print!("{}", s);
-                } else {
-                    print!("{}{}", s, terminator);
+                // The stored bytes exactly, newlines and all; only -z output
+                // can be split back into names unambiguously.
+                let mut line = resolved.into_os_string().into_encoded_bytes();
+                if !no_newline || multiple {
+                    line.extend_from_slice(terminator);
+                }
+                if let Err(e) = out.write_all(&line) {
+                    write_error(&e);
                 }
             }
             Err(e) => {
-                exit_code = 1;
-                if verbose || !quiet {
-                    eprintln!(
-                        "{}: {}: {}",
-                        TOOL_NAME,
-                        file,
-                        coreutils_rs::common::io_error_msg(&e)
-                    );
+                if verbose {
+                    let _ = out.flush();
+                    diag.io_error(Some(file), &e);
+                } else {
+                    diag.fail();
                 }
             }
         }
     }
 
-    process::exit(exit_code);
+    if let Err(e) = out.flush() {
+        write_error(&e);
+    }
+    process::exit(diag.exit_code());
 }
 
-fn resolve(path: &str, mode: CanonMode) -> Result<PathBuf, std::io::Error> {
+fn write_error(e: &io::Error) -> ! {
+    diag::error(
+        TOOL_NAME,
+        None,
+        &format!("write error: {}", io_error_msg(e)),
+    );
+    process::exit(1);
+}
+
+fn resolve(
+    canon: &mut Canonicalizer,
+    path: &OsStr,
+    mode: CanonMode,
+) -> Result<PathBuf, std::io::Error> {
+    let (stripped, trailing_slash) = strip_trailing_slashes(path);
     match mode {
         CanonMode::None => {
+            if trailing_slash {
+                // "link/" names whatever link resolves to, which must be a
+                // directory — and a directory is never a symlink (GNU: EINVAL).
+                if !std::fs::metadata(stripped)?.is_dir() {
+                    return Err(std::io::Error::from_raw_os_error(libc::ENOTDIR));
+                }
+                return Err(std::io::Error::from_raw_os_error(libc::EINVAL));
+            }
             // Just read the symlink target
             std::fs::read_link(path)
         }
-        CanonMode::Canonicalize | CanonMode::CanonicalizeExisting => {
+        CanonMode::Canonicalize => {
+            // All but the last component must exist
+            let resolved = canon.canonicalize(
+                Path::new(stripped),
+                Existence::AllButLast,
+                Resolve::Physical,
+            )?;
+            check_trailing_dir(&resolved, trailing_slash)?;
+            Ok(resolved)
+        }
+        CanonMode::CanonicalizeExisting => {
             // All components must exist
-            std::fs::canonicalize(path)
+            let resolved =
+                canon.canonicalize(Path::new(stripped), Existence::Existing, Resolve::Physical)?;
+            check_trailing_dir(&resolved, trailing_slash)?;
+            Ok(resolved)
+        }
+        // -m has no requirements on the type of any component, so GNU
+        // accepts "file-link/" and "missing/" alike.
+        CanonMode::CanonicalizeMissing => {
+            canon.canonicalize(Path::new(stripped), Existence::Missing, Resolve::Physical)
         }
-        CanonMode::CanonicalizeMissing => canonicalize_missing(Path::new(path)),
     }
 }
 
-/// Canonicalize a path where not all components need to exist.
-/// Resolve what we can, then normalize the rest.
-fn canonicalize_missing(path: &Path) -> Result<PathBuf, std::io::Error> {
-    // Make the path absolute first
-    let abs = if path.is_absolute() {
-        path.to_path_buf()
+/// Split trailing slashes off `path`, reporting whether there were any.
+/// The root directory ("/", "//") is returned unchanged.
+fn strip_trailing_slashes(path: &OsStr) -> (&OsStr, bool) {
+    let bytes = path.as_encoded_bytes();
+    let len = bytes.iter().rposition(|&b| b != b'/').map_or(0, |p| p + 1);
+    if len == 0 || len == bytes.len() {
+        (path, false)
     } else {
-        std::env::current_dir()?.join(path)
-    };
-
-    // Try to canonicalize the whole thing first
-    if let Ok(canon) = std::fs::canonicalize(&abs) {
-        return Ok(canon);
-    }
-
-    // Split into components, resolve as much as possible
-    let mut resolved = PathBuf::new();
-    let mut components: Vec<std::path::Component<'_>> = abs.components().collect();
-    let mut remaining_start = 0;
-
-    // Try to resolve from the full path down to find the longest resolvable prefix
-    for i in (0..components.len()).rev() {
-        let mut prefix = PathBuf::new();
-        for c in &components[..=i] {
-            prefix.push(c.as_os_str());
-        }
-        if let Ok(canon) = std::fs::canonicalize(&prefix) {
-            resolved = canon;
-            remaining_start = i + 1;
-            break;
-        }
-    }
-
-    // If nothing resolved, start from root
-    if resolved.as_os_str().is_empty() {
-        // At minimum, the root component should be there
-        if let Some(std::path::Component::RootDir) = components.first() {
-            resolved.push("/");
-            remaining_start = 1;
-        } else {
-            // Relative path that can't be resolved at all - use cwd
-            resolved = std::env::current_dir()?;
-        }
+        // SAFETY: only ASCII '/' bytes were removed from the end, so the
+        // prefix is still valid OsStr encoding
+        (
+            unsafe { OsStr::from_encoded_bytes_unchecked(&bytes[..len]) },
+            true,
+        )
     }
+}
 
-    // Append remaining components, normalizing . and ..
-    for c in components.drain(remaining_start..) {
-        match c {
-            std::path::Component::CurDir => {}
-            std::path::Component::ParentDir => {
-                resolved.pop();
-            }
-            std::path::Component::Normal(s) => {
-                resolved.push(s);
-                // If this component now exists, try to fully resolve it
-                if resolved.symlink_metadata().is_ok()
-                    && let Ok(canon) = std::fs::canonicalize(&resolved)
-                {
-                    resolved = canon;
-                }
-            }
-            std::path::Component::RootDir | std::path::Component::Prefix(_) => {
-                resolved.push(c.as_os_str());
-            }
-        }
+/// A trailing slash requires the resolved name to be a directory when it
+/// exists (GNU reports ENOTDIR for "file-link/"). A missing final component
+/// is left to the caller's existence rules.
+fn check_trailing_dir(resolved: &Path, trailing_slash: bool) -> Result<(), std::io::Error> {
+    if trailing_slash
+        && let Ok(meta) = std::fs::metadata(resolved)
+        && !meta.is_dir()
+    {
+        return Err(std::io::Error::from_raw_os_error(libc::ENOTDIR));
     }
-
-    Ok(resolved)
+    Ok(())
 }
 
 fn print_help() {
@@ -343,4 +360,433 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    fn test_readlink_trailing_slash_matrix() {
+        let dir = tempfile::tempdir().unwrap();
+        let base = fs::canonicalize(dir.path()).unwrap();
+        fs::write(base.join("file"), "x").unwrap();
+        fs::create_dir(base.join("dir")).unwrap();
+        std::os::unix::fs::symlink("file", base.join("lf")).unwrap();
+        std::os::unix::fs::symlink("dir", base.join("ld")).unwrap();
+        std::os::unix::fs::symlink("nope", base.join("ln")).unwrap();
+
+        let b = base.to_str().unwrap();
+        // (operand, mode, expected stdout or None for exit 1), verified against GNU readlink
+        let cases: Vec<(&str, &str, Option<String>)> = vec![
+            ("lf", "", Some("file".into())),
+            ("lf/", "", None),
+            ("lf", "-f", Some(format!("{}/file", b))),
+            ("lf/", "-f", None),
+            ("lf", "-e", Some(format!("{}/file", b))),
+            ("lf/", "-e", None),
+            ("lf", "-m", Some(format!("{}/file", b))),
+            ("lf/", "-m", Some(format!("{}/file", b))),
+            ("ld", "", Some("dir".into())),
+            ("ld/", "", None),
+            ("ld", "-f", Some(format!("{}/dir", b))),
+            ("ld/", "-f", Some(format!("{}/dir", b))),
+            ("ld", "-e", Some(format!("{}/dir", b))),
+            ("ld/", "-e", Some(format!("{}/dir", b))),
+            ("ld", "-m", Some(format!("{}/dir", b))),
+            ("ld/", "-m", Some(format!("{}/dir", b))),
+            ("ln", "", Some("nope".into())),
+            ("ln/", "", None),
+            ("ln", "-f", Some(format!("{}/nope", b))),
+            ("ln/", "-f", Some(format!("{}/nope", b))),
+            ("ln", "-e", None),
+            ("ln/", "-e", None),
+            ("ln", "-m", Some(format!("{}/nope", b))),
+            ("ln/", "-m", Some(format!("{}/nope", b))),
+        ];
+
+        for (operand, mode, expected) in &cases {
+            let mut args: Vec<&str> = Vec::new();
+            if !mode.is_empty() {
+                args.push(mode);
+            }
+            args.push(operand);
+            let ours = cmd().current_dir(&base).args(&args).output().unwrap();
+            let stdout = String::from_utf8_lossy(&ours.stdout);
+            match expected {
+                Some(out) => {
+                    assert_eq!(ours.status.code(), Some(0), "{} {}", mode, operand);
+                    assert_eq!(stdout.trim_end(), out, "{} {}", mode, operand);
+                }
+                None => {
+                    assert_eq!(ours.status.code(), Some(1), "{} {}", mode, operand);
+                    assert!(stdout.is_empty(), "{} {}", mode, operand);
+                }
+            }
+
+            if let Ok(gnu) = Command::new("readlink")
+                .current_dir(&base)
+                .args(&args)
+                .output()
+            {
+                assert_eq!(
+                    ours.status.code(),
+                    gnu.status.code(),
+                    "{} {}",
+                    mode,
+                    operand
+                );
+                assert_eq!(ours.stdout, gnu.stdout, "{} {}", mode, operand);
+            }
+        }
+    }
+
+    #[test]
+    fn test_readlink_trailing_slash_errno() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("file"), "x").unwrap();
+        std::os::unix::fs::symlink("file", dir.path().join("lf")).unwrap();
+
+        for mode in ["-f", "-e"] {
+            let output = cmd()
+                .current_dir(dir.path())
+                .args(["-v", mode, "lf/"])
+                .output()
+                .unwrap();
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            assert!(
+                stderr.contains("lf/: Not a directory"),
+                "{}: {}",
+                mode,
+                stderr
+            );
+        }
+    }
+
+    #[test]
+    fn test_readlink_dotdot_after_symlink() {
+        // The full matrix is tested on common::canonical; this checks that
+        // readlink goes through it
+        let dir = tempfile::tempdir().unwrap();
+        let base = fs::canonicalize(dir.path()).unwrap();
+        fs::create_dir_all(base.join("t/sub")).unwrap();
+        fs::write(base.join("t/file"), "x").unwrap();
+        std::os::unix::fs::symlink("t/sub", base.join("sd")).unwrap();
+        std::os::unix::fs::symlink("t/file", base.join("sf")).unwrap();
+
+        let out = cmd()
+            .current_dir(&base)
+            .args(["-f", "sd/../x"])
+            .output()
+            .unwrap();
+        assert_eq!(out.status.code(), Some(0));
+        assert_eq!(
+            String::from_utf8_lossy(&out.stdout),
+            format!("{}\n", base.join("t/x").display())
+        );
+        let out = cmd()
+            .current_dir(&base)
+            .args(["-f", "sf/../x"])
+            .output()
+            .unwrap();
+        assert_eq!(out.status.code(), Some(1));
+        assert!(out.stdout.is_empty());
+    }
+
+    #[test]
+    fn test_readlink_errors_silent_unless_verbose() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("file"), "x").unwrap();
+        std::os::unix::fs::symlink("loop", dir.path().join("loop")).unwrap();
+
+        // (mode, operand, message of the first failing component)
+        let cases = [
+            ("-e", "missing", "No such file or directory"),
+            ("-f", "missing/x", "No such file or directory"),
+            ("-e", "file/x/y", "Not a directory"),
+            ("-f", "file/x", "Not a directory"),
+            ("-e", "loop", "Too many levels of symbolic links"),
+            ("-f", "loop/x", "Too many levels of symbolic links"),
+            ("-n", "missing", "No such file or directory"),
+        ];
+        for (mode, operand, msg) in cases {
+            let silent = cmd()
+                .current_dir(dir.path())
+                .args([mode, operand])
+                .output()
+                .unwrap();
+            assert_eq!(silent.status.code(), Some(1), "{} {}", mode, operand);
+            assert!(silent.stdout.is_empty(), "{} {}", mode, operand);
+            assert!(silent.stderr.is_empty(), "{} {}", mode, operand);
+
+            let verbose = cmd()
+                .current_dir(dir.path())
+                .args(["-v", mode, operand])
+                .output()
+                .unwrap();
+            assert_eq!(verbose.status.code(), Some(1), "{} {}", mode, operand);
+            assert_eq!(
+                String::from_utf8_lossy(&verbose.stderr),
+                format!("readlink: {}: {}\n", operand, msg)
+            );
+
+            // The last of -v and -q wins
+            let quieted = cmd()
+                .current_dir(dir.path())
+                .args(["-vq", mode, operand])
+                .output()
+                .unwrap();
+            assert!(quieted.stderr.is_empty(), "{} {}", mode, operand);
+        }
+    }
+
+    #[test]
+    fn test_readlink_f_and_e_differ_only_on_last_component() {
+        let dir = tempfile::tempdir().unwrap();
+        let base = fs::canonicalize(dir.path()).unwrap();
+        fs::write(base.join("file"), "x").unwrap();
+        fs::create_dir(base.join("dir")).unwrap();
+        std::os::unix::fs::symlink("file", base.join("link")).unwrap();
+        std::os::unix::fs::symlink("nowhere", base.join("dangling")).unwrap();
+
+        // (operand, what -f prints if it succeeds, whether -e succeeds)
+        let cases = [
+            ("dir/missing", Some("dir/missing"), false),
+            ("missing/x", None, false),
+            ("dangling", Some("nowhere"), false),
+            ("link", Some("file"), true),
+        ];
+        for (operand, f_path, e_ok) in cases {
+            let run = |mode| {
+                cmd()
+                    .current_dir(&base)
+                    .args(["-v", mode, operand])
+                    .output()
+                    .unwrap()
+            };
+            let enoent = format!("readlink: {}: No such file or directory\n", operand);
+            let f = run("-f");
+            match f_path {
+                Some(path) => {
+                    assert!(f.status.success(), "-f {}", operand);
+                    let expected = format!("{}\n", base.join(path).display());
+                    assert_eq!(String::from_utf8_lossy(&f.stdout), expected);
+                }
+                None => {
+                    assert_eq!(f.status.code(), Some(1), "-f {}", operand);
+                    assert_eq!(String::from_utf8_lossy(&f.stderr), enoent);
+                }
+            }
+            let e = run("-e");
+            if e_ok {
+                assert!(e.status.success(), "-e {}", operand);
+                assert_eq!(e.stdout, f.stdout, "{}", operand);
+            } else {
+                assert_eq!(e.status.code(), Some(1), "-e {}", operand);
+                assert!(e.stdout.is_empty(), "-e {}", operand);
+                assert_eq!(String::from_utf8_lossy(&e.stderr), enoent);
+            }
+        }
+    }
+
+    #[test]
+    fn test_readlink_permission_denied() {
+        if unsafe { libc::geteuid() } == 0 {
+            return;
+        }
+        use std::os::unix::fs::PermissionsExt;
+        let dir = tempfile::tempdir().unwrap();
+        let locked = dir.path().join("locked");
+        fs::create_dir_all(locked.join("inner")).unwrap();
+        fs::set_permissions(&locked, fs::Permissions::from_mode(0o000)).unwrap();
+        for mode in ["-e", "-f"] {
+            let output = cmd()
+                .current_dir(dir.path())
+                .args(["-v", mode, "locked/inner"])
+                .output()
+                .unwrap();
+            assert_eq!(output.status.code(), Some(1));
+            assert_eq!(
+                String::from_utf8_lossy(&output.stderr),
+                "readlink: locked/inner: Permission denied\n"
+            );
+        }
+        fs::set_permissions(&locked, fs::Permissions::from_mode(0o755)).unwrap();
+    }
+
+    /// Descriptors whose /proc/PID/fd links name no reachable path: a file
+    /// unlinked while open ("/tmp/x (deleted)") and a pipe ("pipe:[N]").
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_readlink_proc_fd_magic_links() {
+        use std::os::fd::AsRawFd;
+
+        let dir = tempfile::tempdir().unwrap();
+        let gone = dir.path().join("gone");
+        let file = fs::File::create(&gone).unwrap();
+        fs::remove_file(&gone).unwrap();
+        let mut fds = [0; 2];
+        assert_eq!(unsafe { libc::pipe(fds.as_mut_ptr()) }, 0);
+
+        let fd_dir = format!("/proc/{}/fd", std::process::id());
+        let deleted_target = format!("{} (deleted)", gone.display());
+        let pipe_target = fs::read_link(format!("{}/{}", fd_dir, fds[0]))
+            .unwrap()
+            .into_os_string()
+            .into_string()
+            .unwrap();
+        assert!(pipe_target.starts_with("pipe:["), "{}", pipe_target);
+
+        for (fd, target) in [
+            (file.as_raw_fd(), deleted_target.clone()),
+            (fds[0], format!("{}/{}", fd_dir, pipe_target)),
+        ] {
+            let link = format!("{}/{}", fd_dir, fd);
+            // (args, expected stdout or None for exit 1), verified against GNU readlink
+            let cases = [
+                (
+                    vec![link.clone()],
+                    Some(target.replace(&format!("{}/", fd_dir), "")),
+                ),
+                (vec!["-f".into(), link.clone()], Some(target.clone())),
+                (vec!["-m".into(), link.clone()], Some(target.clone())),
+                (vec!["-e".into(), link.clone()], None),
+                (vec!["-f".into(), format!("{}/.", link)], None),
+                (vec!["-f".into(), format!("{}/x", link)], None),
+            ];
+            for (args, expected) in &cases {
+                let ours = cmd().args(args).output().unwrap();
+                let stdout = String::from_utf8_lossy(&ours.stdout);
+                match expected {
+                    Some(out) => {
+                        assert_eq!(ours.status.code(), Some(0), "{:?}", args);
+                        assert_eq!(stdout.trim_end(), out, "{:?}", args);
+                    }
+                    None => {
+                        assert_eq!(ours.status.code(), Some(1), "{:?}", args);
+                        assert!(stdout.is_empty(), "{:?}", args);
+                    }
+                }
+                if let Ok(gnu) = Command::new("readlink").args(args).output() {
+                    assert_eq!(ours.status.code(), gnu.status.code(), "{:?}", args);
+                    assert_eq!(ours.stdout, gnu.stdout, "{:?}", args);
+                }
+            }
+        }
+
+        // A final "." needs the name before it to exist as a directory
+        std::os::unix::fs::symlink("nonexistent", dir.path().join("dangling")).unwrap();
+        for operand in ["dangling/.", "dangling/./", "missing/."] {
+            let ours = cmd()
+                .current_dir(dir.path())
+                .args(["-f", operand])
+                .output()
+                .unwrap();
+            assert_eq!(ours.status.code(), Some(1), "{}", operand);
+            assert!(ours.stdout.is_empty(), "{}", operand);
+        }
+
+        unsafe {
+            libc::close(fds[0]);
+            libc::close(fds[1]);
+        }
+    }
+
+    /// Awkward targets, created with symlink(2) from raw bytes.
+    const ODD_TARGETS: [&[u8]; 5] = [
+        b"target\n",
+        b"with space",
+        b"back\\slash",
+        b"\nlead\nand\ntrail\n\n",
+        b"bad\xffutf8",
+    ];
+
+    fn odd_links(dir: &std::path::Path) -> Vec<(std::path::PathBuf, &'static [u8])> {
+        use std::ffi::OsStr;
+        use std::os::unix::ffi::OsStrExt;
+        ODD_TARGETS
+            .iter()
+            .enumerate()
+            .map(|(i, &target)| {
+                let link = dir.join(format!("odd{}", i));
+                std::os::unix::fs::symlink(OsStr::from_bytes(target), &link).unwrap();
+                (link, target)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_readlink_raw_target_bytes() {
+        let dir = tempfile::tempdir().unwrap();
+        for (link, target) in odd_links(dir.path()) {
+            let output = cmd().arg(&link).output().unwrap();
+            assert!(output.status.success());
+            assert_eq!(output.stdout, [target, b"\n"].concat(), "{:?}", target);
+
+            let output = cmd().arg("-n").arg(&link).output().unwrap();
+            assert_eq!(output.stdout, target, "{:?}", target);
+
+            let output = cmd().arg("-z").arg(&link).output().unwrap();
+            assert_eq!(output.stdout, [target, b"\0"].concat(), "{:?}", target);
+        }
+    }
+
+    #[test]
+    fn test_readlink_zero_separates_targets() {
+        // Several targets stay apart with -z however many newlines they hold
+        let dir = tempfile::tempdir().unwrap();
+        let links = odd_links(dir.path());
+        let output = cmd()
+            .arg("-z")
+            .args(links.iter().map(|(link, _)| link))
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        let records: Vec<&[u8]> = output.stdout.split(|&b| b == 0).collect();
+        let mut expected: Vec<&[u8]> = ODD_TARGETS.to_vec();
+        expected.push(b"");
+        assert_eq!(records, expected);
+    }
+
+    #[test]
+    fn test_readlink_zero_roundtrips_through_xargs() {
+        use std::os::unix::ffi::OsStrExt;
+        let dir = tempfile::tempdir().unwrap();
+        let links = odd_links(dir.path());
+        let readlink = cmd().get_program().to_owned();
+        let mut script = std::ffi::OsString::from("\"$0\" -z");
+        for (link, _) in &links {
+            script.push(" '");
+            script.push(link.as_os_str());
+            script.push("'");
+        }
+        script.push(" | xargs -0 printf '%s/'");
+        let Ok(output) = Command::new("sh")
+            .arg("-c")
+            .arg(&script)
+            .arg(&readlink)
+            .output()
+        else {
+            return;
+        };
+        assert!(output.status.success());
+        let expected: Vec<u8> = links
+            .iter()
+            .flat_map(|(_, t)| [*t, b"/"].concat())
+            .collect();
+        assert_eq!(
+            std::ffi::OsStr::from_bytes(&output.stdout),
+            std::ffi::OsStr::from_bytes(&expected)
+        );
+    }
+
+    #[test]
+    fn test_readlink_verbose_keeps_utf8_file_name() {
+        let dir = tempfile::tempdir().unwrap();
+        let output = cmd()
+            .args(["-v", "café-missing"])
+            .current_dir(dir.path())
+            .output()
+            .unwrap();
+        assert_eq!(output.status.code(), Some(1));
+        assert_eq!(
+            String::from_utf8_lossy(&output.stderr),
+            "readlink: café-missing: No such file or directory\n"
+        );
+    }
 }
@@ -5,12 +5,13 @@
 use std::path::{Component, Path, PathBuf};
 use std::process;
 
+use coreutils_rs::common::canonical::{Canonicalizer, Existence, Resolve, make_absolute};
+
 const TOOL_NAME: &str = "realpath";
-const VERSION: &str = env!("CARGO_PKG_VERSION");
 
 #[derive(Clone, Copy, PartialEq, Eq)]
 enum Mode {
-    /// Default: all components must exist, resolve symlinks
+    /// Default: all components but the last must exist
     Canonicalize,
     /// -e: all components must exist (explicit)
     CanonicalizeExisting,
@@ -22,7 +23,7 @@ fn main() {
     coreutils_rs::common::reset_sigpipe();
 
     let mut mode = Mode::Canonicalize;
-    let mut no_symlinks = false;
+    let mut resolve = Resolve::Physical;
     let mut zero = false;
     let mut quiet = false;
     let mut relative_to: Option<String> = None;
@@ -45,12 +46,20 @@ fn main() {
                 return;
             }
             "--version" => {
-                println!("{} (fcoreutils) {}", TOOL_NAME, VERSION);
+                coreutils_rs::common::version::print_version(TOOL_NAME);
                 return;
             }
             "-e" | "--canonicalize-existing" => mode = Mode::CanonicalizeExisting,
             "-m" | "--canonicalize-missing" => mode = Mode::CanonicalizeMissing,
-            "-s" | "--strip" | "--no-symlinks" => no_symlinks = true,
+            "-L" | "--logical" => {
+                resolve = Resolve::Logical;
+            }
+            "-P" | "--physical" => {
+                resolve = Resolve::Physical;
+            }
+            "-s" | "--strip" | "--no-symlinks" => {
+                resolve = Resolve::NoSymlinks;
+            }
             "-z" | "--zero" => zero = true,
             "-q" | "--quiet" => quiet = true,
             "--relative-to" => {
@@ -84,7 +93,15 @@ fn main() {
                     match ch {
                         'e' => mode = Mode::CanonicalizeExisting,
                         'm' => mode = Mode::CanonicalizeMissing,
-                        's' => no_symlinks = true,
+                        'L' => {
+                            resolve = Resolve::Logical;
+                        }
+                        'P' => {
+                            resolve = Resolve::Physical;
+                        }
+                        's' => {
+                            resolve = Resolve::NoSymlinks;
+                        }
                         'z' => zero = true,
                         'q' => quiet = true,
                         _ => {
@@ -106,12 +123,16 @@ fn main() {
         process::exit(1);
     }
 
+    // One canonicalizer for every operand, so paths sharing directories
+    // reuse their lookups
+    let mut canon = Canonicalizer::new();
+
     // Resolve relative-to and relative-base directories
     let resolved_relative_to = relative_to.as_ref().map(|d| {
-        resolve_path(d, mode, no_symlinks).unwrap_or_else(|_| make_absolute(Path::new(d)))
+        resolve_path(&mut canon, d, mode, resolve).unwrap_or_else(|_| make_absolute(Path::new(d)))
     });
     let resolved_relative_base = relative_base.as_ref().map(|d| {
-        resolve_path(d, mode, no_symlinks).unwrap_or_else(|_| make_absolute(Path::new(d)))
+        resolve_path(&mut canon, d, mode, resolve).unwrap_or_else(|_| make_absolute(Path::new(d)))
     });
 
     let terminator = if zero { "\0" } else { "\n" };
@@ -126,7 +147,7 @@ fn main() {
             }
             continue;
         }
-        match resolve_path(file, mode, no_symlinks) {
+        match resolve_path(&mut canon, file, mode, resolve) {
             Ok(resolved) => {
                 let output =
                     apply_relative(&resolved, &resolved_relative_to, &resolved_relative_base);
@@ -149,116 +170,18 @@ fn main() {
     process::exit(exit_code);
 }
 
-fn resolve_path(path: &str, mode: Mode, no_symlinks: bool) -> Result<PathBuf, std::io::Error> {
-    if no_symlinks {
-        // Just normalize the path logically without resolving symlinks
-        let abs = make_absolute(Path::new(path));
-        let normalized = normalize_path(&abs);
-        match mode {
-            Mode::CanonicalizeExisting | Mode::Canonicalize => {
-                // All components must exist
-                if !normalized.exists() {
-                    return Err(std::io::Error::new(
-                        std::io::ErrorKind::NotFound,
-                        "No such file or directory",
-                    ));
-                }
-                Ok(normalized)
-            }
-            Mode::CanonicalizeMissing => Ok(normalized),
-        }
-    } else {
-        match mode {
-            Mode::Canonicalize | Mode::CanonicalizeExisting => std::fs::canonicalize(path),
-            Mode::CanonicalizeMissing => canonicalize_missing(Path::new(path)),
-        }
-    }
-}
-
-/// Make a path absolute
-fn make_absolute(path: &Path) -> PathBuf {
-    if path.is_absolute() {
-        path.to_path_buf()
-    } else {
-        std::env::current_dir()
-            .unwrap_or_else(|_| PathBuf::from("/"))
-            .join(path)
-    }
-}
-
-/// Normalize a path by resolving . and .. without touching the filesystem
-fn normalize_path(path: &Path) -> PathBuf {
-    let mut result = PathBuf::new();
-    for component in path.components() {
-        match component {
-            Component::CurDir => {}
-            Component::ParentDir => {
-                result.pop();
-            }
-            c => {
-                result.push(c.as_os_str());
-            }
-        }
-    }
-    result
-}
-
-/// Canonicalize a path where not all components need to exist.
-fn canonicalize_missing(path: &Path) -> Result<PathBuf, std::io::Error> {
-    let abs = make_absolute(path);
-
-    // Try to canonicalize the whole thing first
-    if let Ok(canon) = std::fs::canonicalize(&abs) {
-        return Ok(canon);
-    }
-
-    let components: Vec<Component<'_>> = abs.components().collect();
-    let mut resolved = PathBuf::new();
-    let mut remaining_start = 0;
-
-    // Find the longest resolvable prefix
-    for i in (0..components.len()).rev() {
-        let mut prefix = PathBuf::new();
-        for c in &components[..=i] {
-            prefix.push(c.as_os_str());
-        }
-        if let Ok(canon) = std::fs::canonicalize(&prefix) {
-            resolved = canon;
-            remaining_start = i + 1;
-            break;
-        }
-    }
-
-    if resolved.as_os_str().is_empty() {
-        if let Some(Component::RootDir) = components.first() {
-            resolved.push("/");
-            remaining_start = 1;
-        } else {
-            resolved = std::env::current_dir()?;
-        }
-    }
-
-    for c in &components[remaining_start..] {
-        match c {
-            Component::CurDir => {}
-            Component::ParentDir => {
-                resolved.pop();
-            }
-            Component::Normal(s) => {
-                resolved.push(s);
-                if resolved.symlink_metadata().is_ok()
-                    && let Ok(canon) = std::fs::canonicalize(&resolved)
-                {
-                    resolved = canon;
-                }
-            }
-            Component::RootDir | Component::Prefix(_) => {
-                resolved.push(c.as_os_str());
-            }
-        }
-    }
-
-    Ok(resolved)
+fn resolve_path(
+    canon: &mut Canonicalizer,
+    path: &str,
+    mode: Mode,
+    resolve: Resolve,
+) -> Result<PathBuf, std::io::Error> {
+    let existence = match mode {
+        Mode::Canonicalize => Existence::AllButLast,
+        Mode::CanonicalizeExisting => Existence::Existing,
+        Mode::CanonicalizeMissing => Existence::Missing,
+    };
+    canon.canonicalize(Path::new(path), existence, resolve)
 }
 
 /// Compute the relative path from `from` to `to`
@@ -332,6 +255,8 @@ fn print_help() {
     println!();
     println!("  -e, --canonicalize-existing   all components of the path must exist");
     println!("  -m, --canonicalize-missing    no path components need exist or be a directory");
+    println!("  -L, --logical                 resolve '..' components before symlinks");
+    println!("  -P, --physical                resolve symlinks as encountered (default)");
     println!("  -s, --strip, --no-symlinks    don't expand symlinks");
     println!("  -z, --zero                    end each output line with NUL, not newline");
     println!("  -q, --quiet                   suppress most error messages");
@@ -495,4 +420,204 @@ mod tests {
             assert_eq!(our_out.trim(), gnu_out.trim(), "Output mismatch for -m");
         }
     }
+
+    #[test]
+    fn test_realpath_dotdot_after_symlink() {
+        // The full matrix is tested on common::canonical; this checks that
+        // realpath goes through it
+        let dir = tempfile::tempdir().unwrap();
+        let base = fs::canonicalize(dir.path()).unwrap();
+        fs::create_dir_all(base.join("t/sub")).unwrap();
+        fs::write(base.join("t/file"), "x").unwrap();
+        std::os::unix::fs::symlink("t/sub", base.join("sd")).unwrap();
+        std::os::unix::fs::symlink("t/file", base.join("sf")).unwrap();
+
+        let out = cmd().current_dir(&base).args(["sd/../x"]).output().unwrap();
+        assert_eq!(out.status.code(), Some(0));
+        assert_eq!(
+            String::from_utf8_lossy(&out.stdout),
+            format!("{}\n", base.join("t/x").display())
+        );
+        let out = cmd().current_dir(&base).args(["sf/../x"]).output().unwrap();
+        assert_eq!(out.status.code(), Some(1));
+        assert!(out.stdout.is_empty());
+    }
+
+    #[test]
+    fn test_realpath_no_symlinks_matrix() {
+        let dir = tempfile::tempdir().unwrap();
+        let base = fs::canonicalize(dir.path()).unwrap();
+        fs::create_dir_all(base.join("t/sub")).unwrap();
+        fs::write(base.join("t/file"), "x").unwrap();
+        std::os::unix::fs::symlink("t/sub", base.join("sd")).unwrap();
+        std::os::unix::fs::symlink("t/file", base.join("sf")).unwrap();
+        std::os::unix::fs::symlink("nowhere", base.join("dang")).unwrap();
+
+        let b = base.to_str().unwrap();
+        let ok = |p: &str| Some(format!("{}/{}", b, p));
+        // (operand, mode, expected stdout or None for exit 1), verified against GNU realpath
+        let cases: Vec<(&str, &str, Option<String>)> = vec![
+            // Symlinked directory on the path is kept, not expanded
+            ("sd/file", "-s", ok("sd/file")),
+            ("sd/file", "-se", None),
+            ("sd/file", "-sm", ok("sd/file")),
+            ("sd/", "-se", ok("sd")),
+            // Missing tail: only -e needs the result to exist
+            ("sd/missing/x", "-s", ok("sd/missing/x")),
+            ("sd/missing/x", "-se", None),
+            ("sd/missing/x", "-sm", ok("sd/missing/x")),
+            ("sd/missing/", "-s", ok("sd/missing")),
+            ("sd/missing/.", "-s", None),
+            ("dang", "-s", ok("dang")),
+            ("dang", "-se", None),
+            ("dang/x", "-s", ok("dang/x")),
+            ("t/file/x", "-s", None),
+            ("t/file/x", "-sm", ok("t/file/x")),
+            ("t/file/", "-s", None),
+            ("t/file/", "-sm", ok("t/file")),
+            // `..` crossing a symlink is textual, but the component it
+            // removes must be a directory unless -m
+            ("sd/..", "-s", ok("")),
+            ("sd/..", "-se", ok("")),
+            ("sd/../t", "-s", ok("t")),
+            ("sd/../x", "-s", ok("x")),
+            ("sd/../x", "-se", None),
+            ("sf/..", "-s", None),
+            ("sf/..", "-sm", ok("")),
+            ("dang/../t", "-s", None),
+            ("dang/../t", "-se", None),
+            ("dang/../t", "-sm", ok("t")),
+            ("missing/a/../b", "-s", None),
+            ("missing/a/../b", "-se", None),
+            ("missing/a/../b", "-sm", ok("missing/b")),
+            (
+                "/nonexistent/a/../b",
+                "-sm",
+                Some("/nonexistent/b".to_string()),
+            ),
+        ];
+
+        for (operand, mode, expected) in &cases {
+            let expected = expected.as_ref().map(|p| p.trim_end_matches('/'));
+            let ours = cmd()
+                .current_dir(&base)
+                .args([mode, "--", operand])
+                .output()
+                .unwrap();
+            let stdout = String::from_utf8_lossy(&ours.stdout);
+            match expected {
+                Some(out) => {
+                    assert_eq!(ours.status.code(), Some(0), "{} {}", mode, operand);
+                    assert_eq!(stdout.trim_end(), out, "{} {}", mode, operand);
+                }
+                None => {
+                    assert_eq!(ours.status.code(), Some(1), "{} {}", mode, operand);
+                    assert!(stdout.is_empty(), "{} {}", mode, operand);
+                }
+            }
+
+            if let Ok(gnu) = Command::new("realpath")
+                .current_dir(&base)
+                .args([mode, "--", operand])
+                .output()
+            {
+                assert_eq!(
+                    ours.status.code(),
+                    gnu.status.code(),
+                    "{} {}",
+                    mode,
+                    operand
+                );
+                assert_eq!(ours.stdout, gnu.stdout, "{} {}", mode, operand);
+            }
+        }
+    }
+
+    #[test]
+    fn test_realpath_logical_uses_pwd() {
+        let dir = tempfile::tempdir().unwrap();
+        let base = fs::canonicalize(dir.path()).unwrap();
+        fs::create_dir_all(base.join("dir/a/b")).unwrap();
+        std::os::unix::fs::symlink("a/b", base.join("dir/link")).unwrap();
+        let link = base.join("dir/link");
+        let run = |pwd: &std::path::Path, args: &[&str]| {
+            let out = cmd()
+                .current_dir(&link)
+                .env("PWD", pwd)
+                .args(args)
+                .output()
+                .unwrap();
+            assert_eq!(out.status.code(), Some(0), "{:?}", args);
+            String::from_utf8(out.stdout).unwrap()
+        };
+        let dir_out = format!("{}\n", base.join("dir").display());
+        let a_out = format!("{}\n", base.join("dir/a").display());
+
+        // `..` is taken against the logical $PWD, then symlinks are resolved
+        assert_eq!(run(&link, &["-L", ".."]), dir_out);
+        assert_eq!(run(&link, &["--logical", "../link/.."]), dir_out);
+        assert_eq!(
+            run(&link, &["-L", "."]),
+            format!("{}\n", base.join("dir/a/b").display())
+        );
+        // Physical mode (the default, or a later -P) ignores $PWD
+        assert_eq!(run(&link, &[".."]), a_out);
+        assert_eq!(run(&link, &["-L", "-P", ".."]), a_out);
+        // A $PWD that does not name the working directory is not trusted
+        assert_eq!(run(&base, &["-L", ".."]), a_out);
+        assert_eq!(run(&base.join("dir/../dir/link"), &["-L", ".."]), a_out);
+
+        // GNU realpath -L does not consult $PWD, so compare it on the
+        // equivalent absolute logical path
+        let logical = link.join("..");
+        if let Ok(gnu) = Command::new("realpath")
+            .args(["-L", logical.to_str().unwrap()])
+            .output()
+        {
+            assert_eq!(gnu.status.code(), Some(0));
+            assert_eq!(String::from_utf8_lossy(&gnu.stdout), dir_out);
+            assert_eq!(run(&link, &["-L", logical.to_str().unwrap()]), dir_out);
+        }
+    }
+
+    #[test]
+    fn test_realpath_logical_existence() {
+        let dir = tempfile::tempdir().unwrap();
+        let base = fs::canonicalize(dir.path()).unwrap();
+        fs::create_dir_all(base.join("a/b")).unwrap();
+        fs::write(base.join("f"), "").unwrap();
+        std::os::unix::fs::symlink("a/b", base.join("link")).unwrap();
+
+        // (operand, mode, succeeds) — removed components must still exist
+        let cases = [
+            ("link/../f", "-L", true),
+            ("missing/../f", "-L", false),
+            ("missing/../f", "-Lm", true),
+            ("f/../f", "-L", false),
+            ("link/../new", "-L", true),
+            ("link/../new", "-Le", false),
+        ];
+        for (operand, mode, ok) in cases {
+            let ours = cmd()
+                .current_dir(&base)
+                .args([mode, operand])
+                .output()
+                .unwrap();
+            assert_eq!(ours.status.success(), ok, "{} {}", mode, operand);
+            if let Ok(gnu) = Command::new("realpath")
+                .current_dir(&base)
+                .args([mode, operand])
+                .output()
+            {
+                assert_eq!(
+                    ours.status.code(),
+                    gnu.status.code(),
+                    "{} {}",
+                    mode,
+                    operand
+                );
+                assert_eq!(ours.stdout, gnu.stdout, "{} {}", mode, operand);
+            }
+        }
+    }
 }
@@ -16,8 +16,6 @@ use coreutils_rs::rm::{InteractiveMode, PreserveRoot, RmConfig};
 
 #[cfg(unix)]
 const TOOL_NAME: &str = "rm";
-#[cfg(unix)]
-const VERSION: &str = env!("CARGO_PKG_VERSION");
 
 #[cfg(unix)]
 fn print_help() {
@@ -71,7 +69,7 @@ fn main() {
                 return;
             }
             "--version" => {
-                println!("{} (fcoreutils) {}", TOOL_NAME, VERSION);
+                coreutils_rs::common::version::print_version(TOOL_NAME);
                 return;
             }
             "--force" => config.force = true,
@@ -6,7 +6,6 @@ use std::path::PathBuf;
 use std::process;
 
 const TOOL_NAME: &str = "rmdir";
-const VERSION: &str = env!("CARGO_PKG_VERSION");
 
 fn main() {
     coreutils_rs::common::reset_sigpipe();
@@ -37,7 +36,7 @@ fn main() {
                 return;
             }
             "--version" => {
-                println!("{} (fcoreutils) {}", TOOL_NAME, VERSION);
+                coreutils_rs::common::version::print_version(TOOL_NAME);
                 return;
             }
             "-p" | "--parents" => parents = true,
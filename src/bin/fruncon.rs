@@ -29,7 +29,7 @@ fn main() {
     }
 
     if args.iter().any(|a| a == "--version") {
-        println!("runcon (fcoreutils) {}", env!("CARGO_PKG_VERSION"));
+        coreutils_rs::common::version::print_version("runcon");
         return;
     }
 
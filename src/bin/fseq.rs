@@ -8,7 +8,6 @@ use std::io::{BufWriter, Write};
 use std::process;
 
 const TOOL_NAME: &str = "seq";
-const VERSION: &str = env!("CARGO_PKG_VERSION");
 
 fn print_help() {
     println!("Usage: {} [OPTION]... LAST", TOOL_NAME);
@@ -34,7 +33,7 @@ fn print_help() {
 }
 
 fn print_version() {
-    println!("{} (fcoreutils) {}", TOOL_NAME, VERSION);
+    coreutils_rs::common::version::print_version(TOOL_NAME);
 }
 
 /// Count the number of decimal places in a number string.
@@ -17,6 +17,8 @@ struct Cli {
     binary: bool,
     check: bool,
     tag: bool,
+    /// `--bsd`: tagged lines exactly as BSD tools print them.
+    bsd: bool,
     text: bool,
     ignore_missing: bool,
     quiet: bool,
@@ -24,6 +26,9 @@ struct Cli {
     strict: bool,
     warn: bool,
     zero: bool,
+    uppercase: bool,
+    direct_io: bool,
+    debug: bool,
     files: Vec<String>,
 }
 
@@ -33,6 +38,7 @@ fn parse_args() -> Cli {
         binary: false,
         check: false,
         tag: false,
+        bsd: false,
         text: false,
         ignore_missing: false,
         quiet: false,
@@ -40,6 +46,9 @@ fn parse_args() -> Cli {
         strict: false,
         warn: false,
         zero: false,
+        uppercase: false,
+        direct_io: false,
+        debug: false,
         files: Vec::new(),
     };
 
@@ -60,6 +69,10 @@ fn parse_args() -> Cli {
                 b"--binary" => cli.binary = true,
                 b"--check" => cli.check = true,
                 b"--tag" => cli.tag = true,
+                b"--bsd" => {
+                    cli.tag = true;
+                    cli.bsd = true;
+                }
                 b"--text" => cli.text = true,
                 b"--ignore-missing" => cli.ignore_missing = true,
                 b"--quiet" => cli.quiet = true,
@@ -67,6 +80,9 @@ fn parse_args() -> Cli {
                 b"--strict" => cli.strict = true,
                 b"--warn" => cli.warn = true,
                 b"--zero" => cli.zero = true,
+                b"--uppercase" => cli.uppercase = true,
+                b"--direct-io" => cli.direct_io = true,
+                b"--debug" => cli.debug = true,
                 b"--help" => {
                     print!(
                         "Usage: {} [OPTION]... [FILE]...\n\
@@ -75,8 +91,12 @@ fn parse_args() -> Cli {
                         \x20 -b, --binary         read in binary mode\n\
                         \x20 -c, --check          read checksums from the FILEs and check them\n\
                         \x20     --tag             create a BSD-style checksum\n\
+                        \x20     --bsd             like --tag, in the exact form BSD sha224(1) prints\n\
                         \x20 -t, --text           read in text mode (default)\n\
-                        \x20 -z, --zero           end each output line with NUL, not newline\n\n\
+                        \x20 -z, --zero           end each output line with NUL, not newline\n\
+                        \x20     --uppercase       print hex digests in uppercase (ignored with -c)\n\
+                        \x20     --direct-io       read files with O_DIRECT, bypassing the page cache\n\
+                        \x20     --debug           report when --direct-io fell back to buffered reads\n\n\
                         The following five options are useful only when verifying checksums:\n\
                         \x20     --ignore-missing  don't fail or report status for missing files\n\
                         \x20     --quiet           don't print OK for each successfully verified file\n\
@@ -90,7 +110,7 @@ fn parse_args() -> Cli {
                     process::exit(0);
                 }
                 b"--version" => {
-                    println!("{} (fcoreutils) {}", TOOL_NAME, env!("CARGO_PKG_VERSION"));
+                    coreutils_rs::common::version::print_version(TOOL_NAME);
                     process::exit(0);
                 }
                 _ => {
@@ -188,6 +208,16 @@ fn main() {
     let algo = HashAlgorithm::Sha224;
 
     // Validate flag combinations
+    if cli.bsd && (cli.check || cli.zero) {
+        let why = if cli.check {
+            "is meaningless when verifying checksums"
+        } else {
+            "cannot be combined with --zero"
+        };
+        eprintln!("{}: the --bsd option {}", TOOL_NAME, why);
+        eprintln!("Try '{} --help' for more information.", TOOL_NAME);
+        process::exit(1);
+    }
     if cli.tag && cli.check {
         eprintln!(
             "{}: the --tag option is meaningless when verifying checksums",
@@ -235,10 +265,12 @@ fn run_hash_mode(
 ) {
     let has_stdin = files.iter().any(|f| f == "-");
 
-    if has_stdin || files.len() <= 1 {
+    if has_stdin || files.len() <= 1 || cli.direct_io {
         for filename in files {
             let hash_result = if filename == "-" {
                 hash::hash_stdin(algo)
+            } else if cli.direct_io {
+                hash_file_direct(out, cli, algo, filename)
             } else {
                 hash::hash_file_nostat(algo, Path::new(filename))
             };
@@ -282,6 +314,7 @@ fn run_hash_mode(
 #[inline]
 fn write_output(out: &mut impl Write, cli: &Cli, algo: HashAlgorithm, hash: &str, filename: &str) {
     let binary = cli.binary || (!cli.text && cfg!(windows));
+    let hash: &str = &hash::hex_in_case(hash, cli.uppercase);
     if cli.tag {
         let _ = hash::write_hash_tag_line(out, algo.name(), hash, filename, cli.zero);
     } else if !cli.zero && needs_escape(filename) {
@@ -292,6 +325,25 @@ fn write_output(out: &mut impl Write, cli: &Cli, algo: HashAlgorithm, hash: &str
     }
 }
 
+/// Hash a file with O_DIRECT reads (--direct-io), noting under --debug when
+/// the filesystem forced reads through the page cache instead.
+fn hash_file_direct(
+    out: &mut impl Write,
+    cli: &Cli,
+    algo: HashAlgorithm,
+    filename: &str,
+) -> io::Result<String> {
+    let (h, direct) = hash::hash_file_direct(algo, Path::new(filename))?;
+    if cli.debug && !direct {
+        let _ = out.flush();
+        eprintln!(
+            "{}: {}: O_DIRECT not supported, using buffered reads",
+            TOOL_NAME, filename
+        );
+    }
+    Ok(h)
+}
+
 fn run_check_mode(
     cli: &Cli,
     algo: HashAlgorithm,
@@ -465,7 +517,12 @@ fn check_one(
             parsed_filename.to_string()
         };
 
-        let actual = match hash::hash_file(algo, Path::new(&check_filename)) {
+        let computed = if cli.direct_io {
+            hash::hash_file_direct(algo, Path::new(&check_filename)).map(|(h, _)| h)
+        } else {
+            hash::hash_file(algo, Path::new(&check_filename))
+        };
+        let actual = match computed {
             Ok(h) => h,
             Err(e) => {
                 if cli.ignore_missing && e.kind() == io::ErrorKind::NotFound {
@@ -2,7 +2,6 @@ use std::path::Path;
 use std::process;
 
 const TOOL_NAME: &str = "shred";
-const VERSION: &str = env!("CARGO_PKG_VERSION");
 
 fn print_help() {
     println!("Usage: {} [OPTION]... FILE...", TOOL_NAME);
@@ -62,7 +61,7 @@ fn main() {
                 return;
             }
             "--version" => {
-                println!("{} (fcoreutils) {}", TOOL_NAME, VERSION);
+                coreutils_rs::common::version::print_version(TOOL_NAME);
                 return;
             }
             "--" => {
@@ -9,7 +9,6 @@ use std::io::{self, BufRead, Read, Write};
 use std::process;
 
 const TOOL_NAME: &str = "shuf";
-const VERSION: &str = env!("CARGO_PKG_VERSION");
 
 fn print_help() {
     println!("Usage: {} [OPTION]... [FILE]", TOOL_NAME);
@@ -32,7 +31,7 @@ fn print_help() {
 }
 
 fn print_version() {
-    println!("{} (fcoreutils) {}", TOOL_NAME, VERSION);
+    coreutils_rs::common::version::print_version(TOOL_NAME);
 }
 
 /// Random number generator matching GNU's randint algorithm.
@@ -263,7 +263,7 @@ fn parse_args() -> Cli {
                     process::exit(0);
                 }
                 "version" => {
-                    println!("sort (fcoreutils) {}", env!("CARGO_PKG_VERSION"));
+                    coreutils_rs::common::version::print_version("sort");
                     process::exit(0);
                 }
                 _ => {
@@ -106,7 +106,7 @@ fn parse_args() -> Cli {
                 print_help();
                 process::exit(0);
             } else if arg_ref == "--version" {
-                println!("split (fcoreutils) {}", env!("CARGO_PKG_VERSION"));
+                coreutils_rs::common::version::print_version("split");
                 process::exit(0);
             } else {
                 eprintln!("split: unrecognized option '{}'", arg_str);
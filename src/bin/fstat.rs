@@ -16,8 +16,6 @@ use coreutils_rs::stat::StatConfig;
 
 #[cfg(unix)]
 const TOOL_NAME: &str = "stat";
-#[cfg(unix)]
-const VERSION: &str = env!("CARGO_PKG_VERSION");
 
 #[cfg(unix)]
 fn main() {
@@ -46,7 +44,7 @@ fn main() {
                 return;
             }
             "--version" => {
-                println!("{} (fcoreutils) {}", TOOL_NAME, VERSION);
+                coreutils_rs::common::version::print_version(TOOL_NAME);
                 return;
             }
             "-L" | "--dereference" => dereference = true,
@@ -9,8 +9,6 @@ use std::process;
 
 #[cfg(unix)]
 const TOOL_NAME: &str = "stty";
-#[cfg(unix)]
-const VERSION: &str = env!("CARGO_PKG_VERSION");
 
 #[cfg(unix)]
 fn print_help() {
@@ -75,7 +73,7 @@ fn main() {
                 return;
             }
             "--version" => {
-                println!("{} (fcoreutils) {}", TOOL_NAME, VERSION);
+                coreutils_rs::common::version::print_version(TOOL_NAME);
                 return;
             }
             _ => {}
@@ -18,8 +18,6 @@ use std::process;
 #[cfg(unix)]
 const TOOL_NAME: &str = "tee";
 #[cfg(unix)]
-const VERSION: &str = env!("CARGO_PKG_VERSION");
-
 #[derive(Clone, Copy, PartialEq)]
 #[cfg(unix)]
 enum OutputErrorMode {
@@ -77,7 +75,7 @@ fn main() {
                 return;
             }
             "--version" => {
-                println!("{} (fcoreutils) {}", TOOL_NAME, VERSION);
+                coreutils_rs::common::version::print_version(TOOL_NAME);
                 return;
             }
             "--append" => append = true,
@@ -13,8 +13,6 @@ use std::process;
 
 #[cfg(unix)]
 const TOOL_NAME: &str = "timeout";
-#[cfg(unix)]
-const VERSION: &str = env!("CARGO_PKG_VERSION");
 
 /// Exit code when the command times out.
 #[cfg(unix)]
@@ -95,7 +93,7 @@ fn main() {
                 return;
             }
             "--version" => {
-                println!("{} (fcoreutils) {}", TOOL_NAME, VERSION);
+                coreutils_rs::common::version::print_version(TOOL_NAME);
                 return;
             }
             "--foreground" => foreground = true,
@@ -385,7 +383,7 @@ fn main() {
                     libc::raise(sig);
                 }
                 // If raise didn't kill us (shouldn't happen for uncatchable signals)
-                process::exit(128 + sig as i32);
+                process::exit(128 + sig);
             }
             process::exit(EXIT_TIMEOUT);
         }
@@ -17,8 +17,6 @@ use std::process;
 
 #[cfg(unix)]
 const TOOL_NAME: &str = "touch";
-#[cfg(unix)]
-const VERSION: &str = env!("CARGO_PKG_VERSION");
 
 /// Which timestamps to change.
 #[derive(Clone, Copy, PartialEq, Eq)]
@@ -434,7 +432,7 @@ fn main() {
                 return;
             }
             "--version" => {
-                println!("{} (fcoreutils) {}", TOOL_NAME, VERSION);
+                coreutils_rs::common::version::print_version(TOOL_NAME);
                 return;
             }
             "-a" => target = TimeTarget::AccessOnly,
@@ -6,7 +6,6 @@ use std::fs;
 use std::process;
 
 const TOOL_NAME: &str = "truncate";
-const VERSION: &str = env!("CARGO_PKG_VERSION");
 
 /// Size adjustment mode parsed from the SIZE prefix.
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
@@ -119,13 +118,7 @@ fn compute_new_size(current: u64, mode: SizeMode, size: u64) -> u64 {
                 current
             }
         }
-        SizeMode::RoundDown => {
-            if size == 0 {
-                current
-            } else {
-                (current / size) * size
-            }
-        }
+        SizeMode::RoundDown => current.checked_div(size).map_or(current, |q| q * size),
         SizeMode::RoundUp => {
             if size == 0 {
                 current
@@ -166,7 +159,7 @@ fn main() {
                 return;
             }
             "--version" => {
-                println!("{} (fcoreutils) {}", TOOL_NAME, VERSION);
+                coreutils_rs::common::version::print_version(TOOL_NAME);
                 return;
             }
             "-c" | "--no-create" => no_create = true,
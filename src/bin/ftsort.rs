@@ -9,7 +9,6 @@ use std::io::{self, BufRead, Write};
 use std::process;
 
 const TOOL_NAME: &str = "tsort";
-const VERSION: &str = env!("CARGO_PKG_VERSION");
 
 fn print_help() {
     println!("Usage: {} [OPTION] [FILE]", TOOL_NAME);
@@ -21,7 +20,7 @@ fn print_help() {
 }
 
 fn print_version() {
-    println!("{} (fcoreutils) {}", TOOL_NAME, VERSION);
+    coreutils_rs::common::version::print_version(TOOL_NAME);
 }
 
 fn run(input: &str, source_name: &str) -> i32 {
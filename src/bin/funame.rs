@@ -7,7 +7,6 @@ use std::ffi::CStr;
 use std::process;
 
 const TOOL_NAME: &str = "uname";
-const VERSION: &str = env!("CARGO_PKG_VERSION");
 
 #[allow(unused_variables, unused_assignments)]
 fn main() {
@@ -44,7 +43,7 @@ fn main() {
                 return;
             }
             "--version" => {
-                println!("{} (fcoreutils) {}", TOOL_NAME, VERSION);
+                coreutils_rs::common::version::print_version(TOOL_NAME);
                 return;
             }
             "-a" | "--all" => {
@@ -253,7 +252,7 @@ mod tests {
         let output = cmd().arg("-a").output().unwrap();
         assert_eq!(output.status.code(), Some(0));
         let stdout = String::from_utf8_lossy(&output.stdout);
-        let parts: Vec<&str> = stdout.trim().split_whitespace().collect();
+        let parts: Vec<&str> = stdout.split_whitespace().collect();
         assert!(
             parts.len() >= 5,
             "uname -a should have multiple fields, got: {:?}",
@@ -276,7 +275,7 @@ mod tests {
         let output = cmd().arg("-sr").output().unwrap();
         assert_eq!(output.status.code(), Some(0));
         let stdout = String::from_utf8_lossy(&output.stdout);
-        let parts: Vec<&str> = stdout.trim().split_whitespace().collect();
+        let parts: Vec<&str> = stdout.split_whitespace().collect();
         assert_eq!(parts.len(), 2, "uname -sr should have 2 fields");
     }
 
@@ -185,6 +185,10 @@ struct Cli {
     #[arg(short = 'z', long = "zero-terminated")]
     zero_terminated: bool,
 
+    /// Output version information and exit
+    #[arg(long = "version")]
+    version: bool,
+
     /// Input file (default: stdin)
     input: Option<String>,
 
@@ -210,6 +214,10 @@ fn main() {
     enlarge_pipes();
 
     let cli = Cli::parse();
+    if cli.version {
+        coreutils_rs::common::version::print_version("uniq");
+        return;
+    }
 
     // Determine output mode
     let mode = if let Some(ref method_str) = cli.group {
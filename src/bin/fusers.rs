@@ -28,7 +28,7 @@ fn main() {
                 return;
             }
             "--version" => {
-                println!("users (fcoreutils) {}", env!("CARGO_PKG_VERSION"));
+                coreutils_rs::common::version::print_version("users");
                 return;
             }
             _ => {}
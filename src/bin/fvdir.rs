@@ -35,7 +35,7 @@ fn main() {
                 return;
             }
             "--version" => {
-                println!("vdir (fcoreutils) {}", env!("CARGO_PKG_VERSION"));
+                coreutils_rs::common::version::print_version("vdir");
                 return;
             }
             _ => {}
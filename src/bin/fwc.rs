@@ -49,6 +49,10 @@ struct Cli {
     #[arg(long = "total", value_name = "WHEN", default_value = "auto")]
     total: String,
 
+    /// Output version information and exit
+    #[arg(long = "version")]
+    version: bool,
+
     /// Files to process (reads stdin if none given)
     files: Vec<String>,
 }
@@ -216,6 +220,10 @@ fn try_mmap_stdin() -> Option<memmap2::Mmap> {
 fn main() {
     coreutils_rs::common::reset_sigpipe();
     let cli = Cli::parse();
+    if cli.version {
+        coreutils_rs::common::version::print_version("wc");
+        return;
+    }
 
     // Detect locale once at startup
     let utf8_locale = wc::is_utf8_locale();
@@ -16,6 +16,10 @@ use std::process;
 #[cfg(unix)]
 use clap::Parser;
 
+#[cfg(unix)]
+use coreutils_rs::common::diag;
+#[cfg(unix)]
+use coreutils_rs::common::io_error_msg;
 #[cfg(unix)]
 use coreutils_rs::who;
 
@@ -100,7 +104,7 @@ fn main() {
     // Handle --version before clap (clap exits with code 2 for unknown options)
     let raw_args: Vec<String> = std::env::args().collect();
     if raw_args.iter().any(|a| a == "--version") {
-        println!("who (fcoreutils) {}", env!("CARGO_PKG_VERSION"));
+        coreutils_rs::common::version::print_version("who");
         process::exit(0);
     }
 
@@ -137,7 +141,21 @@ fn main() {
         config.apply_all();
     }
 
-    let output = who::run_who(&config);
+    // A single operand names the records file, as in GNU
+    let file = match cli.args.as_slice() {
+        [file] => Some(std::path::Path::new(file.as_str())),
+        _ => None,
+    };
+    let records = who::read_login_records(file);
+    if let Some(e) = &records.unavailable {
+        diag::error(
+            "who",
+            None,
+            &format!("cannot read user login records: {}", io_error_msg(e)),
+        );
+    }
+
+    let output = who::run_who(&config, &records.entries);
     if !output.is_empty() {
         println!("{}", output);
     }
@@ -3,23 +3,33 @@
 // Usage: yes [STRING]...
 // Repeatedly output a line with all specified STRING(s), or 'y'.
 
-use std::io::Write;
+use std::io;
 use std::process;
 
+use coreutils_rs::common::io_error_msg;
+use coreutils_rs::echo::join_args;
+
 const TOOL_NAME: &str = "yes";
-const VERSION: &str = env!("CARGO_PKG_VERSION");
 
 /// Buffer size for bulk writes (64 KiB).
 const BUF_SIZE: usize = 64 * 1024;
 
+/// Alignment of the output buffer, so each write hands the kernel whole
+/// pages.
+const PAGE_SIZE: usize = 4096;
+
 fn main() {
     coreutils_rs::common::reset_sigpipe();
 
-    let args: Vec<String> = std::env::args().skip(1).collect();
+    // Raw argument bytes: a non-UTF-8 argument is repeated as is
+    let args: Vec<Vec<u8>> = std::env::args_os()
+        .skip(1)
+        .map(|a| a.into_encoded_bytes())
+        .collect();
 
     if args.len() == 1 {
-        match args[0].as_str() {
-            "--help" => {
+        match args[0].as_slice() {
+            b"--help" => {
                 println!("Usage: {} [STRING]...", TOOL_NAME);
                 println!("  or:  {} OPTION", TOOL_NAME);
                 println!("Repeatedly output a line with all specified STRING(s), or 'y'.");
@@ -28,47 +38,94 @@ fn main() {
                 println!("      --version  output version information and exit");
                 return;
             }
-            "--version" => {
-                println!("{} (fcoreutils) {}", TOOL_NAME, VERSION);
+            b"--version" => {
+                coreutils_rs::common::version::print_version(TOOL_NAME);
                 return;
             }
             _ => {}
         }
     }
 
-    let line = if args.is_empty() {
-        "y\n".to_string()
+    let mut line = Vec::new();
+    if args.is_empty() {
+        line.push(b'y');
     } else {
-        let mut s = args.join(" ");
-        s.push('\n');
-        s
-    };
+        join_args(&args, &mut line);
+    }
+    line.push(b'\n');
 
-    let line_bytes = line.as_bytes();
+    // Fill a page-aligned stretch of the buffer with whole lines once; a
+    // line longer than the buffer is written on its own
+    let mut storage = vec![0u8; BUF_SIZE + PAGE_SIZE];
+    let offset = storage.as_ptr().align_offset(PAGE_SIZE).min(PAGE_SIZE);
+    let buf = &mut storage[offset..offset + BUF_SIZE];
+    let filled = fill_records(buf, &line);
+    let chunk: &[u8] = if filled == 0 { &line } else { &buf[..filled] };
 
-    // Build a large buffer filled with repeated copies of the line.
-    let mut buf = Vec::with_capacity(BUF_SIZE + line_bytes.len());
-    while buf.len() < BUF_SIZE {
-        buf.extend_from_slice(line_bytes);
+    let err = write_forever(chunk);
+    if err.kind() == io::ErrorKind::BrokenPipe {
+        process::exit(0);
     }
+    eprintln!("{}: standard output: {}", TOOL_NAME, io_error_msg(&err));
+    process::exit(1);
+}
 
-    let stdout = std::io::stdout();
-    let mut out = stdout.lock();
+/// Fill `buf` with as many whole copies of `record` as fit and return the
+/// length filled, 0 when not even one fits. Copies double in size, so
+/// filling takes a few memcpys however short the record.
+fn fill_records(buf: &mut [u8], record: &[u8]) -> usize {
+    if record.is_empty() || record.len() > buf.len() {
+        return 0;
+    }
+    let total = buf.len() / record.len() * record.len();
+    buf[..record.len()].copy_from_slice(record);
+    let mut done = record.len();
+    while done < total {
+        let n = done.min(total - done);
+        buf.copy_within(..n, done);
+        done += n;
+    }
+    total
+}
 
+/// Write `chunk` to standard output over and over until a write fails,
+/// returning that error.
+#[cfg(unix)]
+fn write_forever(chunk: &[u8]) -> io::Error {
     loop {
-        if out.write_all(&buf).is_err() {
-            // Broken pipe or other write error — exit silently.
-            break;
+        let mut rest = chunk;
+        while !rest.is_empty() {
+            let n = unsafe { libc::write(1, rest.as_ptr() as *const libc::c_void, rest.len()) };
+            if n < 0 {
+                let e = io::Error::last_os_error();
+                if e.kind() == io::ErrorKind::Interrupted {
+                    continue;
+                }
+                return e;
+            }
+            if n == 0 {
+                return io::ErrorKind::WriteZero.into();
+            }
+            rest = &rest[n as usize..];
         }
     }
+}
 
-    // Attempt flush; ignore errors (broken pipe).
-    let _ = out.flush();
-    process::exit(0);
+#[cfg(not(unix))]
+fn write_forever(chunk: &[u8]) -> io::Error {
+    use std::io::Write;
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+    loop {
+        if let Err(e) = out.write_all(chunk) {
+            return e;
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
+    use super::*;
     use std::io::Read;
     use std::process::{Command, Stdio};
 
@@ -178,21 +235,80 @@ mod tests {
     #[test]
     fn test_yes_pipe_closes() {
         // yes piped to head should terminate
-        let child = cmd().stdout(Stdio::piped()).spawn().unwrap();
+        let mut child = cmd().stdout(Stdio::piped()).spawn().unwrap();
 
         let head = Command::new("head")
             .arg("-n")
             .arg("1")
-            .stdin(child.stdout.unwrap())
+            .stdin(child.stdout.take().unwrap())
             .stdout(Stdio::piped())
             .output()
             .unwrap();
+        child.wait().unwrap();
 
         assert_eq!(head.status.code(), Some(0));
         let text = String::from_utf8_lossy(&head.stdout);
         assert_eq!(text.trim(), "y");
     }
 
+    #[test]
+    fn test_fill_records_whole_copies() {
+        let mut buf = [0u8; 64];
+        // Records that divide the buffer and ones that leave a remainder
+        for record in [
+            &b"y\n"[..],
+            b"abc\n",
+            b"hello world\n",
+            b"0123456789abcdefghij\n",
+        ] {
+            buf.fill(0);
+            let n = fill_records(&mut buf, record);
+            assert_eq!(n, 64 / record.len() * record.len(), "{:?}", record);
+            assert!(buf[..n].chunks(record.len()).all(|c| c == record));
+            assert!(buf[n..].iter().all(|&b| b == 0));
+        }
+        assert_eq!(fill_records(&mut buf, &[b'x'; 64]), 64);
+        assert_eq!(fill_records(&mut buf, &[b'x'; 65]), 0);
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_yes_head_lines() {
+        let ours = cmd().get_program().to_str().unwrap().to_string();
+        let output = Command::new("sh")
+            .args(["-c", &format!("{} | head -n 3", ours)])
+            .output()
+            .unwrap();
+        assert_eq!(output.status.code(), Some(0));
+        assert_eq!(output.stdout, b"y\ny\ny\n");
+
+        let output = Command::new("sh")
+            .args(["-c", &format!("{} hello world | head -c 24", ours)])
+            .output()
+            .unwrap();
+        assert_eq!(output.status.code(), Some(0));
+        assert_eq!(output.stdout, b"hello world\nhello world\n");
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn test_yes_write_error() {
+        let output = cmd()
+            .stdout(
+                std::fs::OpenOptions::new()
+                    .write(true)
+                    .open("/dev/full")
+                    .unwrap(),
+            )
+            .output()
+            .unwrap();
+        assert_eq!(output.status.code(), Some(1));
+        assert_eq!(
+            String::from_utf8_lossy(&output.stderr),
+            "yes: standard output: No space left on device\n"
+        );
+    }
+
     #[test]
     #[cfg(unix)]
     fn test_yes_matches_gnu() {
@@ -1,5 +1,7 @@
 use std::cmp::Ordering;
-use std::io::{self, Write};
+use std::io::{self, BufRead, Write};
+
+use crate::common::fmt_num;
 
 /// How to handle sort-order checking.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -20,6 +22,24 @@ pub struct CommConfig {
     pub case_insensitive: bool,
     pub order_check: OrderCheck,
     pub output_delimiter: Option<Vec<u8>>,
+    /// --no-column-prefix: emit every selected line without leading
+    /// separators, whatever its column.
+    pub no_column_prefix: bool,
+    /// --show-line-numbers: start each output line with where it came from,
+    /// `1:N` or `2:M` for a unique line and `1:N,2:M` for a common one
+    /// (1-based line numbers in the source files), then the output
+    /// delimiter.
+    pub show_line_numbers: bool,
+    /// --verbose-order-errors: the sort-order warning also gives the
+    /// offending line's number and the start of it and its predecessor.
+    pub verbose_order_errors: bool,
+    /// --sort: sort an input that is not already in order before merging,
+    /// as if it had been piped through `sort` (with -i, case-folded). An
+    /// input that is in order is only checked. The sort works in place on
+    /// the line slices `comm` already builds over each input, so it costs
+    /// no memory beyond them, and --show-line-numbers then numbers the
+    /// sorted lines. `comm_streams` does not sort.
+    pub sort: bool,
     pub total: bool,
     pub zero_terminated: bool,
 }
@@ -33,18 +53,50 @@ impl Default for CommConfig {
             case_insensitive: false,
             order_check: OrderCheck::Default,
             output_delimiter: None,
+            no_column_prefix: false,
+            show_line_numbers: false,
+            verbose_order_errors: false,
+            sort: false,
             total: false,
             zero_terminated: false,
         }
     }
 }
 
+/// An out-of-order input detected by the sort-order check.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OrderErrorKind {
+    /// `OrderCheck::Default`: the flagged files were unsorted. A warning was
+    /// printed for each and processing ran to completion, so the output is
+    /// complete (though possibly not meaningful).
+    Unsorted { file1: bool, file2: bool },
+    /// `OrderCheck::Strict`: processing stopped at the first out-of-order
+    /// line of `file` (1 or 2) after `lines_emitted` output lines; the
+    /// output is truncated and no --total line was written.
+    Stopped { file: u8, lines_emitted: usize },
+}
+
+impl OrderErrorKind {
+    /// True if processing stopped before the end of the input.
+    pub fn stopped_early(&self) -> bool {
+        matches!(self, OrderErrorKind::Stopped { .. })
+    }
+}
+
 /// Result of the comm operation.
+///
+/// Exit-code contract (GNU): a caller should exit 1 whenever `order_error`
+/// is set, which can only happen while an order check is active. For
+/// `Unsorted`, GNU also prints "input is not in sorted order" after all
+/// output; that summary is left to the caller.
+#[derive(Debug)]
 pub struct CommResult {
+    /// Lines unique to file 1, file 2, and common to both, as consumed —
+    /// whether or not their column was suppressed.
     pub count1: usize,
     pub count2: usize,
     pub count3: usize,
-    pub had_order_error: bool,
+    pub order_error: Option<OrderErrorKind>,
 }
 
 /// Compare two byte slices, optionally case-insensitive (ASCII).
@@ -63,6 +115,67 @@ fn compare_lines(a: &[u8], b: &[u8], case_insensitive: bool) -> Ordering {
     }
 }
 
+/// Put `lines` in merge order unless they already are, for --sort. Lines
+/// that compare equal only when case-folded are ordered by their bytes, as
+/// `sort -f` orders them, so the result does not depend on the input order.
+fn sort_if_unsorted(lines: &mut [&[u8]], case_insensitive: bool) {
+    if !lines.is_sorted_by(|a, b| compare_lines(a, b, case_insensitive) != Ordering::Greater) {
+        lines.sort_unstable_by(|a, b| compare_lines(a, b, case_insensitive).then_with(|| a.cmp(b)));
+    }
+}
+
+/// Bytes of a line shown in a verbose order warning.
+const SNIPPET_LEN: usize = 60;
+
+/// The start of `line` for a diagnostic: at most SNIPPET_LEN bytes, decoded
+/// lossily, with control characters, backslashes and quotes escaped and
+/// "..." after the closing quote when the line was cut short.
+fn snippet(line: &[u8]) -> String {
+    let shown = &line[..line.len().min(SNIPPET_LEN)];
+    let mut s = String::with_capacity(shown.len() + 8);
+    s.push('\'');
+    for c in String::from_utf8_lossy(shown).chars() {
+        match c {
+            '\t' => s.push_str("\\t"),
+            '\n' => s.push_str("\\n"),
+            '\r' => s.push_str("\\r"),
+            '\\' => s.push_str("\\\\"),
+            '\'' => s.push_str("\\'"),
+            c if c.is_control() => s.push_str(&format!("\\x{:02x}", c as u32)),
+            c => s.push(c),
+        }
+    }
+    s.push('\'');
+    if line.len() > SNIPPET_LEN {
+        s.push_str("...");
+    }
+    s
+}
+
+/// The sort-order warning for file `file_num`, whose line `line_no`
+/// (1-based) sorts before the line preceding it. Without
+/// --verbose-order-errors this is GNU's message.
+pub fn order_warning(
+    tool_name: &str,
+    config: &CommConfig,
+    file_num: u8,
+    line_no: usize,
+    line: &[u8],
+    prev: &[u8],
+) -> String {
+    if !config.verbose_order_errors {
+        return format!("{}: file {} is not in sorted order", tool_name, file_num);
+    }
+    format!(
+        "{}: file {} is not in sorted order: line {}: {} < previous {}",
+        tool_name,
+        file_num,
+        line_no,
+        snippet(line),
+        snippet(prev)
+    )
+}
+
 /// Split data into lines by delimiter, using SIMD-accelerated scanning.
 /// Does NOT include a trailing empty line if data ends with the delimiter.
 fn split_lines<'a>(data: &'a [u8], delim: u8) -> Vec<&'a [u8]> {
@@ -84,50 +197,201 @@ fn split_lines<'a>(data: &'a [u8], delim: u8) -> Vec<&'a [u8]> {
     lines
 }
 
-/// Run the comm merge algorithm on two sorted inputs.
-pub fn comm(
-    data1: &[u8],
-    data2: &[u8],
+/// Output is collected in a buffer and written out whenever the next line
+/// would take it past this size (4MB). Large enough that the write calls
+/// cost nothing next to the merge itself — smaller values measurably slowed
+/// multi-hundred-MB inputs — and small enough that peak memory stays
+/// bounded however large the inputs are.
+pub const FLUSH_THRESHOLD: usize = 4 * 1024 * 1024;
+
+/// Upper bound on the bytes `comm` can output for these inputs, from the
+/// columns shown: file 1's lines appear only in columns 1 and 3, file 2's
+/// only in column 2, and with both unique columns suppressed (-12) at most
+/// the smaller file is common. Each line also gets its prefix, the line
+/// number field and possibly a delimiter the input lacked at EOF.
+fn output_bound(
     config: &CommConfig,
-    tool_name: &str,
-    out: &mut impl Write,
-) -> io::Result<CommResult> {
-    let delim = if config.zero_terminated { b'\0' } else { b'\n' };
-    let sep = config.output_delimiter.as_deref().unwrap_or(b"\t");
+    (data1, lines1): (usize, usize),
+    (data2, lines2): (usize, usize),
+    (prefix2, prefix3, sep): (usize, usize, usize),
+) -> usize {
+    // Every line is at most one delimiter longer than its input bytes
+    let (text1, text2) = (data1 + lines1, data2 + lines2);
+    let numbers = if config.show_line_numbers {
+        let digits = |n: usize| n.checked_ilog10().map_or(1, |d| d as usize + 1);
+        "1:,2:".len() + digits(lines1) + digits(lines2) + sep
+    } else {
+        0
+    };
+    let col1 = !config.suppress_col1;
+    let col2 = !config.suppress_col2;
+    let col3 = !config.suppress_col3;
+    let mut bound = 0usize;
+    if col1 || col3 {
+        let text = if col1 { text1 } else { text1.min(text2) };
+        bound += text + lines1 * (numbers + if col3 { prefix3 } else { 0 });
+    }
+    if col2 {
+        bound += text2 + lines2 * (numbers + prefix2);
+    }
+    bound
+}
 
-    // Build column prefixes. Each shown column before the current one
-    // contributes one copy of the separator.
-    // Column 1: always empty prefix.
-    let prefix2: Vec<u8> = if !config.suppress_col1 {
+/// Column 2 and column 3 prefixes: each shown column before a line's own
+/// contributes one copy of the separator, unless --no-column-prefix.
+/// Column 1 lines never have a prefix.
+fn column_prefixes(config: &CommConfig, sep: &[u8]) -> (Vec<u8>, Vec<u8>) {
+    let show_prefix = !config.no_column_prefix;
+    let prefix2: Vec<u8> = if show_prefix && !config.suppress_col1 {
         sep.to_vec()
     } else {
         Vec::new()
     };
     let mut prefix3: Vec<u8> = Vec::new();
-    if !config.suppress_col1 {
+    if show_prefix && !config.suppress_col1 {
         prefix3.extend_from_slice(sep);
     }
-    if !config.suppress_col2 {
+    if show_prefix && !config.suppress_col2 {
         prefix3.extend_from_slice(sep);
     }
+    (prefix2, prefix3)
+}
+
+/// The --total summary line.
+fn total_line((count1, count2, count3): (usize, usize, usize), sep: &[u8], delim: u8) -> Vec<u8> {
+    let mut total = Vec::with_capacity(64);
+    for count in [count1, count2, count3] {
+        fmt_num::push_u64(&mut total, count as u64);
+        total.extend_from_slice(sep);
+    }
+    total.extend_from_slice(b"total");
+    total.push(delim);
+    total
+}
+
+/// Run the comm merge algorithm on two sorted inputs.
+///
+/// An unterminated last line is still a line, and like every output line
+/// it is written with the delimiter (newline, or NUL with -z) after it, so
+/// whether either input ends with one never changes the output; GNU comm
+/// does the same.
+pub fn comm(
+    data1: &[u8],
+    data2: &[u8],
+    config: &CommConfig,
+    tool_name: &str,
+    out: &mut impl Write,
+) -> io::Result<CommResult> {
+    comm_buffered(data1, data2, config, tool_name, out, FLUSH_THRESHOLD)
+}
 
-    let lines1 = split_lines(data1, delim);
-    let lines2 = split_lines(data2, delim);
+/// `comm` with an explicit flush threshold, so tests can check that the
+/// output does not depend on where the buffer is flushed.
+pub(crate) fn comm_buffered(
+    data1: &[u8],
+    data2: &[u8],
+    config: &CommConfig,
+    tool_name: &str,
+    out: &mut impl Write,
+    flush_threshold: usize,
+) -> io::Result<CommResult> {
+    comm_with_capacity(data1, data2, config, tool_name, out, flush_threshold).map(|(r, _)| r)
+}
+
+/// `comm_buffered`, also returning the output buffer's final capacity.
+/// The buffer never shrinks, so that is the most it ever held.
+pub(crate) fn comm_with_capacity(
+    data1: &[u8],
+    data2: &[u8],
+    config: &CommConfig,
+    tool_name: &str,
+    out: &mut impl Write,
+    flush_threshold: usize,
+) -> io::Result<(CommResult, usize)> {
+    let delim = if config.zero_terminated { b'\0' } else { b'\n' };
+    let sep = config.output_delimiter.as_deref().unwrap_or(b"\t");
+    let (prefix2, prefix3) = column_prefixes(config, sep);
+
+    let ci = config.case_insensitive;
+    let mut lines1 = split_lines(data1, delim);
+    let mut lines2 = split_lines(data2, delim);
+    // Sorted inputs cannot trip the order check below
+    if config.sort {
+        sort_if_unsorted(&mut lines1, ci);
+        sort_if_unsorted(&mut lines2, ci);
+    }
 
     let mut i1 = 0usize;
     let mut i2 = 0usize;
     let mut count1 = 0usize;
     let mut count2 = 0usize;
     let mut count3 = 0usize;
-    let mut had_order_error = false;
     let mut warned1 = false;
     let mut warned2 = false;
-    let ci = config.case_insensitive;
 
-    let mut buf = Vec::with_capacity((data1.len() + data2.len()).min(4 * 1024 * 1024));
-    let flush_threshold = 4 * 1024 * 1024; // Flush output buffer at 4MB to limit memory
+    // Reserve strategy: the buffer is allocated once and never grows. Its
+    // capacity is the most the output can take (0 with every column
+    // suppressed), capped at the flush threshold; `emit!` flushes before a
+    // line would overflow it and writes a line longer than the threshold
+    // straight through. So -12 on two large, nearly identical files holds
+    // only what the common lines need, and a fully shown output, which is
+    // longer than the input by its prefixes, is not reallocated either.
+    let bound = output_bound(
+        config,
+        (data1.len(), lines1.len()),
+        (data2.len(), lines2.len()),
+        (prefix2.len(), prefix3.len(), sep.len()),
+    );
+    let mut buf: Vec<u8> = Vec::with_capacity(bound.min(flush_threshold));
+
+    // Line-number field of the line being emitted (--show-line-numbers).
+    let mut numbers: Vec<u8> = Vec::new();
+    let mut itoa_buf = itoa::Buffer::new();
+
+    // Append one output line, given the 0-based indexes of its source lines
+    // in file 1 and file 2, first flushing the buffer if the line would
+    // take it past the threshold.
+    macro_rules! emit {
+        ($n1:expr, $n2:expr, $prefix:expr, $line:expr) => {
+            let (prefix, line): (&[u8], &[u8]) = ($prefix, $line);
+            if config.show_line_numbers {
+                numbers.clear();
+                let (n1, n2): (Option<usize>, Option<usize>) = ($n1, $n2);
+                if let Some(n) = n1 {
+                    numbers.extend_from_slice(b"1:");
+                    numbers.extend_from_slice(itoa_buf.format(n + 1).as_bytes());
+                }
+                if let Some(n) = n2 {
+                    if n1.is_some() {
+                        numbers.push(b',');
+                    }
+                    numbers.extend_from_slice(b"2:");
+                    numbers.extend_from_slice(itoa_buf.format(n + 1).as_bytes());
+                }
+                numbers.extend_from_slice(sep);
+            }
+            let len = numbers.len() + prefix.len() + line.len() + 1;
+            if buf.len() + len > buf.capacity() {
+                out.write_all(&buf)?;
+                buf.clear();
+            }
+            if len > buf.capacity() {
+                // Longer than the whole buffer: write it through
+                out.write_all(&numbers)?;
+                out.write_all(prefix)?;
+                out.write_all(line)?;
+                out.write_all(&[delim])?;
+            } else {
+                buf.extend_from_slice(&numbers);
+                buf.extend_from_slice(prefix);
+                buf.extend_from_slice(line);
+                buf.push(delim);
+            }
+        };
+    }
 
     // Macro to check sort order of a file and handle warnings/errors.
+    // In Strict mode the first disorder ends the run with partial output.
     macro_rules! check_order {
         ($warned:ident, $lines:ident, $idx:ident, $file_num:expr) => {
             if config.order_check != OrderCheck::None
@@ -135,17 +399,33 @@ pub fn comm(
                 && $idx > 0
                 && compare_lines($lines[$idx], $lines[$idx - 1], ci) == Ordering::Less
             {
-                had_order_error = true;
                 $warned = true;
-                eprintln!("{}: file {} is not in sorted order", tool_name, $file_num);
+                eprintln!(
+                    "{}",
+                    order_warning(
+                        tool_name,
+                        config,
+                        $file_num,
+                        $idx + 1,
+                        $lines[$idx],
+                        $lines[$idx - 1]
+                    )
+                );
                 if config.order_check == OrderCheck::Strict {
                     out.write_all(&buf)?;
-                    return Ok(CommResult {
+                    let lines_emitted = if config.suppress_col1 { 0 } else { count1 }
+                        + if config.suppress_col2 { 0 } else { count2 }
+                        + if config.suppress_col3 { 0 } else { count3 };
+                    let result = CommResult {
                         count1,
                         count2,
                         count3,
-                        had_order_error,
-                    });
+                        order_error: Some(OrderErrorKind::Stopped {
+                            file: $file_num,
+                            lines_emitted,
+                        }),
+                    };
+                    return Ok((result, buf.capacity()));
                 }
             }
         };
@@ -157,8 +437,7 @@ pub fn comm(
                 // File1 line is unique — check file1 sort order before consuming
                 check_order!(warned1, lines1, i1, 1);
                 if !config.suppress_col1 {
-                    buf.extend_from_slice(lines1[i1]);
-                    buf.push(delim);
+                    emit!(Some(i1), None, &[], lines1[i1]);
                 }
                 count1 += 1;
                 i1 += 1;
@@ -167,9 +446,7 @@ pub fn comm(
                 // File2 line is unique — check file2 sort order before consuming
                 check_order!(warned2, lines2, i2, 2);
                 if !config.suppress_col2 {
-                    buf.extend_from_slice(&prefix2);
-                    buf.extend_from_slice(lines2[i2]);
-                    buf.push(delim);
+                    emit!(None, Some(i2), &prefix2, lines2[i2]);
                 }
                 count2 += 1;
                 i2 += 1;
@@ -177,46 +454,20 @@ pub fn comm(
             Ordering::Equal => {
                 // Lines match — no sort check needed (GNU comm behavior)
                 if !config.suppress_col3 {
-                    buf.extend_from_slice(&prefix3);
-                    buf.extend_from_slice(lines1[i1]);
-                    buf.push(delim);
+                    emit!(Some(i1), Some(i2), &prefix3, lines1[i1]);
                 }
                 count3 += 1;
                 i1 += 1;
                 i2 += 1;
             }
         }
-
-        // Periodic flush to limit memory usage for large files
-        if buf.len() >= flush_threshold {
-            out.write_all(&buf)?;
-            buf.clear();
-        }
     }
 
     // Drain remaining from file 1
     while i1 < lines1.len() {
-        if config.order_check != OrderCheck::None
-            && !warned1
-            && i1 > 0
-            && compare_lines(lines1[i1], lines1[i1 - 1], ci) == Ordering::Less
-        {
-            had_order_error = true;
-            warned1 = true;
-            eprintln!("{}: file 1 is not in sorted order", tool_name);
-            if config.order_check == OrderCheck::Strict {
-                out.write_all(&buf)?;
-                return Ok(CommResult {
-                    count1,
-                    count2,
-                    count3,
-                    had_order_error,
-                });
-            }
-        }
+        check_order!(warned1, lines1, i1, 1);
         if !config.suppress_col1 {
-            buf.extend_from_slice(lines1[i1]);
-            buf.push(delim);
+            emit!(Some(i1), None, &[], lines1[i1]);
         }
         count1 += 1;
         i1 += 1;
@@ -224,56 +475,272 @@ pub fn comm(
 
     // Drain remaining from file 2
     while i2 < lines2.len() {
-        if config.order_check != OrderCheck::None
-            && !warned2
-            && i2 > 0
-            && compare_lines(lines2[i2], lines2[i2 - 1], ci) == Ordering::Less
-        {
-            had_order_error = true;
-            warned2 = true;
-            eprintln!("{}: file 2 is not in sorted order", tool_name);
-            if config.order_check == OrderCheck::Strict {
-                out.write_all(&buf)?;
-                return Ok(CommResult {
-                    count1,
-                    count2,
-                    count3,
-                    had_order_error,
-                });
-            }
-        }
+        check_order!(warned2, lines2, i2, 2);
         if !config.suppress_col2 {
-            buf.extend_from_slice(&prefix2);
-            buf.extend_from_slice(lines2[i2]);
-            buf.push(delim);
+            emit!(None, Some(i2), &prefix2, lines2[i2]);
         }
         count2 += 1;
         i2 += 1;
     }
 
-    // Total summary line — use itoa for fast integer formatting
     if config.total {
-        let mut itoa_buf = itoa::Buffer::new();
-        buf.extend_from_slice(itoa_buf.format(count1).as_bytes());
-        buf.extend_from_slice(sep);
-        buf.extend_from_slice(itoa_buf.format(count2).as_bytes());
-        buf.extend_from_slice(sep);
-        buf.extend_from_slice(itoa_buf.format(count3).as_bytes());
-        buf.extend_from_slice(sep);
-        buf.extend_from_slice(b"total");
-        buf.push(delim);
+        let total = total_line((count1, count2, count3), sep, delim);
+        if buf.is_empty() || buf.len() + total.len() > buf.capacity() {
+            // Nothing else to write (e.g. every column suppressed) or no
+            // room left: keep the buffer at its reserved size
+            out.write_all(&buf)?;
+            buf.clear();
+            out.write_all(&total)?;
+        } else {
+            buf.extend_from_slice(&total);
+        }
     }
 
-    // In Default mode, print a final summary message (matches GNU comm behavior)
-    if had_order_error && config.order_check == OrderCheck::Default {
-        eprintln!("{}: input is not in sorted order", tool_name);
+    if !buf.is_empty() {
+        out.write_all(&buf)?;
     }
+    let order_error = (warned1 || warned2).then_some(OrderErrorKind::Unsorted {
+        file1: warned1,
+        file2: warned2,
+    });
+    let result = CommResult {
+        count1,
+        count2,
+        count3,
+        order_error,
+    };
+    Ok((result, buf.capacity()))
+}
+
+// ── Streaming merge ─────────────────────────────────────────────────────
+// `comm` wants both inputs whole in memory. Inputs that only exist as a
+// stream (decompressed on the fly) are merged line by line instead: each
+// side keeps just its current line and the one before it, for the order
+// check. The output is byte-for-byte what `comm` gives for the same data.
+
+/// Failure of `comm_streams`, telling a bad input from a bad output.
+#[derive(Debug)]
+pub enum CommError {
+    /// Reading FILE1 (`file` 1) or FILE2 (2) failed. Every line merged
+    /// before the failure has been written out.
+    Read {
+        file: u8,
+        error: io::Error,
+    },
+    Write(io::Error),
+}
+
+impl From<io::Error> for CommError {
+    fn from(e: io::Error) -> Self {
+        CommError::Write(e)
+    }
+}
+
+/// One input of `comm_streams`, read a line at a time.
+struct LineCursor<R> {
+    reader: R,
+    file: u8,
+    delim: u8,
+    line: Vec<u8>,
+    prev: Vec<u8>,
+    /// `line` holds a line (false once the input is exhausted).
+    has_line: bool,
+    /// 0-based index of `line` in the input.
+    index: usize,
+}
+
+impl<R: BufRead> LineCursor<R> {
+    fn new(reader: R, file: u8, delim: u8) -> Result<Self, CommError> {
+        let mut cursor = LineCursor {
+            reader,
+            file,
+            delim,
+            line: Vec::new(),
+            prev: Vec::new(),
+            has_line: false,
+            index: 0,
+        };
+        cursor.read_line()?;
+        Ok(cursor)
+    }
+
+    fn read_line(&mut self) -> Result<(), CommError> {
+        self.line.clear();
+        let n = self
+            .reader
+            .read_until(self.delim, &mut self.line)
+            .map_err(|error| CommError::Read {
+                file: self.file,
+                error,
+            })?;
+        if self.line.last() == Some(&self.delim) {
+            self.line.pop();
+        }
+        self.has_line = n > 0;
+        Ok(())
+    }
+
+    /// Step past the current line, keeping it as `prev`.
+    fn advance(&mut self) -> Result<(), CommError> {
+        std::mem::swap(&mut self.line, &mut self.prev);
+        self.index += 1;
+        self.read_line()
+    }
+
+    /// The current line sorts before the previous one.
+    fn out_of_order(&self, ci: bool) -> bool {
+        self.index > 0 && compare_lines(&self.line, &self.prev, ci) == Ordering::Less
+    }
+}
+
+/// `comm` over two line streams rather than two buffers, for inputs that
+/// cannot be mapped. Output goes through a buffer of `FLUSH_THRESHOLD`
+/// bytes, which is written out before a read error is returned.
+pub fn comm_streams(
+    input1: impl BufRead,
+    input2: impl BufRead,
+    config: &CommConfig,
+    tool_name: &str,
+    out: &mut impl Write,
+) -> Result<CommResult, CommError> {
+    let delim = if config.zero_terminated { b'\0' } else { b'\n' };
+    let sep = config.output_delimiter.as_deref().unwrap_or(b"\t");
+    let (prefix2, prefix3) = column_prefixes(config, sep);
+    let ci = config.case_insensitive;
+
+    let mut buf: Vec<u8> = Vec::with_capacity(FLUSH_THRESHOLD);
+    let mut numbers: Vec<u8> = Vec::new();
+    let mut itoa_buf = itoa::Buffer::new();
+    let (mut count1, mut count2, mut count3) = (0usize, 0usize, 0usize);
+    let (mut warned1, mut warned2) = (false, false);
+
+    // Read errors must not lose what was already merged
+    macro_rules! read {
+        ($e:expr) => {
+            match $e {
+                Ok(v) => v,
+                Err(e) => {
+                    out.write_all(&buf)?;
+                    return Err(e);
+                }
+            }
+        };
+    }
+
+    let mut c1 = read!(LineCursor::new(input1, 1, delim));
+    let mut c2 = read!(LineCursor::new(input2, 2, delim));
 
+    // Same layout as `comm`'s `emit!`, from the cursors' line indexes
+    macro_rules! emit {
+        ($n1:expr, $n2:expr, $prefix:expr, $line:expr) => {
+            let (prefix, line): (&[u8], &[u8]) = ($prefix, $line);
+            if config.show_line_numbers {
+                numbers.clear();
+                let (n1, n2): (Option<usize>, Option<usize>) = ($n1, $n2);
+                if let Some(n) = n1 {
+                    numbers.extend_from_slice(b"1:");
+                    numbers.extend_from_slice(itoa_buf.format(n + 1).as_bytes());
+                }
+                if let Some(n) = n2 {
+                    if n1.is_some() {
+                        numbers.push(b',');
+                    }
+                    numbers.extend_from_slice(b"2:");
+                    numbers.extend_from_slice(itoa_buf.format(n + 1).as_bytes());
+                }
+                numbers.extend_from_slice(sep);
+            }
+            if buf.len() + numbers.len() + prefix.len() + line.len() + 1 > FLUSH_THRESHOLD {
+                out.write_all(&buf)?;
+                buf.clear();
+            }
+            buf.extend_from_slice(&numbers);
+            buf.extend_from_slice(prefix);
+            buf.extend_from_slice(line);
+            buf.push(delim);
+        };
+    }
+
+    macro_rules! check_order {
+        ($warned:ident, $cursor:ident, $file_num:expr) => {
+            if config.order_check != OrderCheck::None && !$warned && $cursor.out_of_order(ci) {
+                $warned = true;
+                eprintln!(
+                    "{}",
+                    order_warning(
+                        tool_name,
+                        config,
+                        $file_num,
+                        $cursor.index + 1,
+                        &$cursor.line,
+                        &$cursor.prev
+                    )
+                );
+                if config.order_check == OrderCheck::Strict {
+                    out.write_all(&buf)?;
+                    let lines_emitted = if config.suppress_col1 { 0 } else { count1 }
+                        + if config.suppress_col2 { 0 } else { count2 }
+                        + if config.suppress_col3 { 0 } else { count3 };
+                    return Ok(CommResult {
+                        count1,
+                        count2,
+                        count3,
+                        order_error: Some(OrderErrorKind::Stopped {
+                            file: $file_num,
+                            lines_emitted,
+                        }),
+                    });
+                }
+            }
+        };
+    }
+
+    while c1.has_line || c2.has_line {
+        let order = match (c1.has_line, c2.has_line) {
+            (true, true) => compare_lines(&c1.line, &c2.line, ci),
+            (true, false) => Ordering::Less,
+            _ => Ordering::Greater,
+        };
+        match order {
+            Ordering::Less => {
+                check_order!(warned1, c1, 1);
+                if !config.suppress_col1 {
+                    emit!(Some(c1.index), None, &[], &c1.line);
+                }
+                count1 += 1;
+                read!(c1.advance());
+            }
+            Ordering::Greater => {
+                check_order!(warned2, c2, 2);
+                if !config.suppress_col2 {
+                    emit!(None, Some(c2.index), &prefix2, &c2.line);
+                }
+                count2 += 1;
+                read!(c2.advance());
+            }
+            Ordering::Equal => {
+                if !config.suppress_col3 {
+                    emit!(Some(c1.index), Some(c2.index), &prefix3, &c1.line);
+                }
+                count3 += 1;
+                read!(c1.advance());
+                read!(c2.advance());
+            }
+        }
+    }
+
+    if config.total {
+        buf.extend_from_slice(&total_line((count1, count2, count3), sep, delim));
+    }
     out.write_all(&buf)?;
+    let order_error = (warned1 || warned2).then_some(OrderErrorKind::Unsorted {
+        file1: warned1,
+        file2: warned2,
+    });
     Ok(CommResult {
         count1,
         count2,
         count3,
-        had_order_error,
+        order_error,
     })
 }
@@ -1,4 +1,5 @@
 use super::*;
+use std::io;
 
 fn comm_str(input1: &str, input2: &str, config: &CommConfig) -> String {
     let mut out = Vec::new();
@@ -184,6 +185,303 @@ fn test_total_with_suppression() {
     assert_eq!(result, "\tb\nc\n\td\n1\t1\t2\ttotal\n");
 }
 
+/// Records each write call separately.
+struct WriteLog(Vec<Vec<u8>>);
+
+impl io::Write for WriteLog {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.push(buf.to_vec());
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn test_total_only_when_all_columns_suppressed() {
+    let mut config = default_config();
+    config.total = true;
+    config.suppress_col1 = true;
+    config.suppress_col2 = true;
+    config.suppress_col3 = true;
+    let mut log = WriteLog(Vec::new());
+    let result = comm(b"a\nb\nd\n", b"b\nc\nd\ne\n", &config, "comm", &mut log).unwrap();
+    assert_eq!(log.0, [b"1\t2\t2\ttotal\n".to_vec()]);
+    assert_eq!((result.count1, result.count2, result.count3), (1, 2, 2));
+
+    // Without --total nothing is written at all
+    config.total = false;
+    let mut log = WriteLog(Vec::new());
+    comm(b"a\nb\n", b"b\nc\n", &config, "comm", &mut log).unwrap();
+    assert!(log.0.is_empty());
+}
+
+/// Duplicates on both sides, so a line's number is not its rank in the
+/// merged output.
+const NUMBERED1: &str = "a\na\nb\nd\nd\nf\n";
+const NUMBERED2: &str = "a\nb\nb\nc\nd\ne\n";
+
+#[test]
+fn test_show_line_numbers() {
+    let mut config = default_config();
+    config.show_line_numbers = true;
+    assert_eq!(
+        comm_str(NUMBERED1, NUMBERED2, &config),
+        "1:1,2:1\t\t\ta\n\
+         1:2\ta\n\
+         1:3,2:2\t\t\tb\n\
+         2:3\t\tb\n\
+         2:4\t\tc\n\
+         1:4,2:5\t\t\td\n\
+         1:5\td\n\
+         2:6\t\te\n\
+         1:6\tf\n"
+    );
+
+    // Suppressed columns still advance the numbering; --total is unchanged
+    config.suppress_col3 = true;
+    config.total = true;
+    config.output_delimiter = Some(b"|".to_vec());
+    assert_eq!(
+        comm_str(NUMBERED1, NUMBERED2, &config),
+        "1:2|a\n2:3||b\n2:4||c\n1:5|d\n2:6||e\n1:6|f\n3|3|3|total\n"
+    );
+
+    config.suppress_col3 = false;
+    config.suppress_col1 = true;
+    config.no_column_prefix = true;
+    config.total = false;
+    assert_eq!(
+        comm_str(NUMBERED1, NUMBERED2, &config),
+        "1:1,2:1|a\n1:3,2:2|b\n2:3|b\n2:4|c\n1:4,2:5|d\n2:6|e\n"
+    );
+}
+
+#[test]
+fn test_show_line_numbers_zero_terminated() {
+    let mut config = default_config();
+    config.show_line_numbers = true;
+    config.zero_terminated = true;
+    let nul = |s: &str| s.replace('\n', "\0");
+    assert_eq!(
+        comm_str(&nul("x\ny\n"), &nul("y\nz\n"), &config),
+        "1:1\tx\x001:2,2:1\t\t\ty\x002:2\t\tz\x00"
+    );
+}
+
+#[test]
+fn test_output_independent_of_flush_threshold() {
+    let file1: Vec<u8> = (0..500)
+        .filter(|i| i % 3 != 0)
+        .flat_map(|i| format!("line{:04}\n", i).into_bytes())
+        .collect();
+    let file2: Vec<u8> = (0..500)
+        .filter(|i| i % 5 != 0)
+        .flat_map(|i| format!("line{:04}\n", i).into_bytes())
+        .collect();
+    let unsorted = b"line0001\nline0000\nline0002\n".to_vec();
+
+    let mut configs = Vec::new();
+    for cols in 0..8u8 {
+        let mut config = default_config();
+        config.suppress_col1 = cols & 1 != 0;
+        config.suppress_col2 = cols & 2 != 0;
+        config.suppress_col3 = cols & 4 != 0;
+        config.total = true;
+        configs.push(config);
+    }
+    let mut strict = default_config();
+    strict.order_check = OrderCheck::Strict;
+    configs.push(strict);
+    let mut numbered = default_config();
+    numbered.show_line_numbers = true;
+    numbered.total = true;
+    configs.push(numbered);
+
+    for config in &configs {
+        for (a, b) in [(&file1, &file2), (&unsorted, &file2), (&file1, &unsorted)] {
+            let mut expected = Vec::new();
+            let want = comm(a, b, config, "comm", &mut expected).unwrap();
+            for threshold in [1, 2, 9, 10, 64, 4096] {
+                let mut out = Vec::new();
+                let got = comm_buffered(a, b, config, "comm", &mut out, threshold).unwrap();
+                assert_eq!(out, expected, "threshold {}", threshold);
+                assert_eq!(got.order_error, want.order_error);
+                assert_eq!(
+                    (got.count1, got.count2, got.count3),
+                    (want.count1, want.count2, want.count3)
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn test_streams_match_comm() {
+    let file1: Vec<u8> = (0..500)
+        .filter(|i| i % 3 != 0)
+        .flat_map(|i| format!("line{:04}\n", i).into_bytes())
+        .collect();
+    let file2: Vec<u8> = (0..500)
+        .filter(|i| i % 5 != 0)
+        .flat_map(|i| format!("line{:04}\n", i).into_bytes())
+        .collect();
+    let unsorted = b"line0001\nline0000\nline0002\n".to_vec();
+    let unterminated = b"line0002\nline0003".to_vec();
+    let empty = Vec::new();
+
+    let mut configs = Vec::new();
+    for cols in 0..8u8 {
+        let mut config = default_config();
+        config.suppress_col1 = cols & 1 != 0;
+        config.suppress_col2 = cols & 2 != 0;
+        config.suppress_col3 = cols & 4 != 0;
+        config.total = true;
+        configs.push(config);
+    }
+    let mut strict = default_config();
+    strict.order_check = OrderCheck::Strict;
+    configs.push(strict);
+    let mut numbered = default_config();
+    numbered.show_line_numbers = true;
+    numbered.case_insensitive = true;
+    configs.push(numbered);
+
+    let inputs = [&file1, &file2, &unsorted, &unterminated, &empty];
+    for config in &configs {
+        for a in inputs {
+            for b in inputs {
+                let mut expected = Vec::new();
+                let want = comm(a, b, config, "comm", &mut expected).unwrap();
+                let mut out = Vec::new();
+                let got = comm_streams(&a[..], &b[..], config, "comm", &mut out).unwrap();
+                assert_eq!(out, expected);
+                assert_eq!(got.order_error, want.order_error);
+                assert_eq!(
+                    (got.count1, got.count2, got.count3),
+                    (want.count1, want.count2, want.count3)
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn test_streams_read_error_keeps_merged_output() {
+    // FILE2 fails after its second line
+    let failing = io::BufReader::with_capacity(4, io::Read::chain(&b"b\nc\n"[..], FailingReader));
+    let mut out = Vec::new();
+    let err = comm_streams(
+        &b"a\nc\nd\n"[..],
+        failing,
+        &default_config(),
+        "comm",
+        &mut out,
+    )
+    .unwrap_err();
+    assert!(matches!(err, CommError::Read { file: 2, .. }));
+    assert_eq!(out, b"a\n\tb\n\t\tc\n");
+}
+
+struct FailingReader;
+
+impl io::Read for FailingReader {
+    fn read(&mut self, _buf: &mut [u8]) -> io::Result<usize> {
+        Err(io::Error::new(io::ErrorKind::InvalidData, "bad data"))
+    }
+}
+
+#[test]
+fn test_buffer_never_grows_past_threshold() {
+    // Two ~100MB files that share all but every 1000th line
+    let pad = "x".repeat(96);
+    let make = |skip: usize| -> Vec<u8> {
+        let mut data = Vec::with_capacity(100 << 20);
+        for i in 0..1_000_000usize {
+            if i % 1000 != skip {
+                data.extend_from_slice(format!("{:08}{}\n", i, pad).as_bytes());
+            }
+        }
+        data
+    };
+    let (file1, file2) = (make(0), make(1));
+    assert!(file1.len() > 95 << 20 && file2.len() > 95 << 20);
+
+    let only_common = CommConfig {
+        suppress_col1: true,
+        suppress_col2: true,
+        ..default_config()
+    };
+    for config in [&only_common, &default_config()] {
+        let (result, capacity) = comm_with_capacity(
+            &file1,
+            &file2,
+            config,
+            "comm",
+            &mut io::sink(),
+            FLUSH_THRESHOLD,
+        )
+        .unwrap();
+        assert_eq!(result.count3, 998_000);
+        assert!(capacity <= FLUSH_THRESHOLD, "capacity {}", capacity);
+    }
+
+    // Small outputs reserve no more than they write, and a line longer
+    // than the threshold bypasses the buffer
+    let mut out = Vec::new();
+    let (_, capacity) =
+        comm_with_capacity(b"a\nb\n", b"b\n", &only_common, "comm", &mut out, 4096).unwrap();
+    assert_eq!(out, b"b\n");
+    assert!(capacity <= 4, "capacity {}", capacity);
+    let long = [vec![b'z'; 100], b"\n".to_vec()].concat();
+    let mut out = Vec::new();
+    let (_, capacity) =
+        comm_with_capacity(&long, &long, &only_common, "comm", &mut out, 16).unwrap();
+    assert_eq!(out, long);
+    assert!(capacity <= 16, "capacity {}", capacity);
+}
+
+#[test]
+fn test_no_column_prefix_all_suppressions() {
+    for mask in 0..8u8 {
+        let config = CommConfig {
+            suppress_col1: mask & 1 != 0,
+            suppress_col2: mask & 2 != 0,
+            suppress_col3: mask & 4 != 0,
+            no_column_prefix: true,
+            ..default_config()
+        };
+        let mut expected = String::new();
+        for (line, col) in [("a", 1), ("b", 3), ("c", 2), ("d", 3)] {
+            if mask & (1 << (col - 1)) == 0 {
+                expected.push_str(line);
+                expected.push('\n');
+            }
+        }
+        let result = comm_str("a\nb\nd\n", "b\nc\nd\n", &config);
+        assert_eq!(result, expected, "suppression mask {}", mask);
+        assert!(
+            result.lines().all(|l| !l.starts_with('\t')),
+            "suppression mask {}",
+            mask
+        );
+    }
+}
+
+#[test]
+fn test_no_column_prefix_keeps_total_separators() {
+    let config = CommConfig {
+        no_column_prefix: true,
+        total: true,
+        output_delimiter: Some(b",".to_vec()),
+        ..default_config()
+    };
+    let result = comm_str("a\nb\n", "b\nc\n", &config);
+    assert_eq!(result, "a\nb\nc\n1,1,1,total\n");
+}
+
 #[test]
 fn test_zero_terminated() {
     let mut config = default_config();
@@ -198,6 +496,39 @@ fn test_no_trailing_newline() {
     assert_eq!(result, "\t\ta\n\t\tb\n");
 }
 
+/// Every mix of terminated and unterminated inputs, with equal and unequal
+/// last lines, for newline and NUL delimiters: (file 1, file 2, output).
+fn trailing_delimiter_cases(d: char) -> Vec<(String, String, String)> {
+    let mut cases = Vec::new();
+    for end1 in ["", "\n"] {
+        for end2 in ["", "\n"] {
+            for (last2, out) in [("x", "\t\ta\n\t\tx\n"), ("y", "\t\ta\nx\n\ty\n")] {
+                let d = d.to_string();
+                cases.push((
+                    format!("a\nx{}", end1).replace('\n', &d),
+                    format!("a\n{}{}", last2, end2).replace('\n', &d),
+                    out.replace('\n', &d),
+                ));
+            }
+        }
+    }
+    cases
+}
+
+#[test]
+fn test_trailing_delimiter_matrix() {
+    for (d, zero_terminated) in [('\n', false), ('\0', true)] {
+        let mut config = default_config();
+        config.zero_terminated = zero_terminated;
+        for (a, b, expected) in trailing_delimiter_cases(d) {
+            assert_eq!(comm_str(&a, &b, &config), expected, "{:?} {:?}", a, b);
+            let mut out = Vec::new();
+            comm_streams(a.as_bytes(), b.as_bytes(), &config, "comm", &mut out).unwrap();
+            assert_eq!(out, expected.as_bytes(), "streams {:?} {:?}", a, b);
+        }
+    }
+}
+
 #[test]
 fn test_single_line_each() {
     assert_eq!(
@@ -219,7 +550,7 @@ fn test_order_check_default() {
     let config = default_config();
     let mut out = Vec::new();
     let result = comm(b"b\na\n", b"a\nb\n", &config, "comm", &mut out).unwrap();
-    assert!(result.had_order_error);
+    assert!(result.order_error.is_some());
 }
 
 #[test]
@@ -228,7 +559,7 @@ fn test_order_check_strict() {
     config.order_check = OrderCheck::Strict;
     let mut out = Vec::new();
     let result = comm(b"b\na\n", b"a\nb\n", &config, "comm", &mut out).unwrap();
-    assert!(result.had_order_error);
+    assert!(result.order_error.is_some_and(|k| k.stopped_early()));
 }
 
 #[test]
@@ -237,7 +568,205 @@ fn test_order_check_none() {
     config.order_check = OrderCheck::None;
     let mut out = Vec::new();
     let result = comm(b"b\na\n", b"a\nb\n", &config, "comm", &mut out).unwrap();
-    assert!(!result.had_order_error);
+    assert!(result.order_error.is_none());
+}
+
+/// Words in mixed case with case-folded duplicates, as an unsorted input.
+fn shuffled_words(seed: u64, n: usize) -> Vec<String> {
+    let mut state = seed;
+    let mut next = || {
+        state = state
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        (state >> 33) as usize
+    };
+    (0..n)
+        .map(|_| {
+            let len = 1 + next() % 4;
+            (0..len)
+                .map(|_| {
+                    let c = b'a' + (next() % 4) as u8;
+                    (if next() % 3 == 0 {
+                        c.to_ascii_uppercase()
+                    } else {
+                        c
+                    }) as char
+                })
+                .collect()
+        })
+        .collect()
+}
+
+#[test]
+fn test_sort_matches_presorted() {
+    for ci in [false, true] {
+        let mut config = default_config();
+        config.case_insensitive = ci;
+        config.order_check = OrderCheck::Strict;
+        let key = |a: &String, b: &String| {
+            if ci {
+                a.to_ascii_lowercase()
+                    .cmp(&b.to_ascii_lowercase())
+                    .then_with(|| a.cmp(b))
+            } else {
+                a.cmp(b)
+            }
+        };
+        for seed in 1..=20u64 {
+            let mut words1 = shuffled_words(seed, 60);
+            let words2 = shuffled_words(seed + 100, 60);
+            words1.sort_by(key);
+            let mut sorted2 = words2.clone();
+            sorted2.sort_by(key);
+            let join = |w: &[String]| w.iter().map(|w| format!("{}\n", w)).collect::<String>();
+
+            let (expected, result) =
+                comm_result(join(&words1).as_bytes(), join(&sorted2).as_bytes(), &config);
+            assert!(result.order_error.is_none());
+
+            config.sort = true;
+            config.order_check = OrderCheck::Default;
+            let (out, result) =
+                comm_result(join(&words1).as_bytes(), join(&words2).as_bytes(), &config);
+            assert_eq!(out, expected, "seed {} ci {}", seed, ci);
+            assert!(result.order_error.is_none());
+            config.sort = false;
+            config.order_check = OrderCheck::Strict;
+        }
+    }
+}
+
+fn comm_result(input1: &[u8], input2: &[u8], config: &CommConfig) -> (String, CommResult) {
+    let mut out = Vec::new();
+    let result = comm(input1, input2, config, "comm", &mut out).unwrap();
+    (String::from_utf8(out).unwrap(), result)
+}
+
+#[test]
+fn test_order_error_file1_only() {
+    let (out, result) = comm_result(b"b\na\nc\n", b"a\nb\nc\n", &default_config());
+    assert_eq!(out, "\ta\n\t\tb\na\n\t\tc\n");
+    assert_eq!(
+        result.order_error,
+        Some(OrderErrorKind::Unsorted {
+            file1: true,
+            file2: false
+        })
+    );
+}
+
+#[test]
+fn test_order_error_file2_only() {
+    let (out, result) = comm_result(b"a\nb\nc\n", b"c\na\nd\n", &default_config());
+    assert_eq!(out, "a\nb\n\t\tc\n\ta\n\td\n");
+    assert_eq!(
+        result.order_error,
+        Some(OrderErrorKind::Unsorted {
+            file1: false,
+            file2: true
+        })
+    );
+}
+
+#[test]
+fn test_order_error_both_files() {
+    let (out, result) = comm_result(b"b\na\nc\n", b"c\na\nd\n", &default_config());
+    assert_eq!(out, "b\na\n\t\tc\n\ta\n\td\n");
+    let kind = result.order_error.unwrap();
+    assert_eq!(
+        kind,
+        OrderErrorKind::Unsorted {
+            file1: true,
+            file2: true
+        }
+    );
+    assert!(!kind.stopped_early());
+}
+
+#[test]
+fn test_order_error_strict_stops_mid_file() {
+    let config = CommConfig {
+        order_check: OrderCheck::Strict,
+        total: true,
+        ..default_config()
+    };
+    // Stops at "a" in file 2, after "a", "b" (col 1) and "c" (col 3).
+    let (out, result) = comm_result(b"a\nb\nc\n", b"c\na\nd\n", &config);
+    assert_eq!(out, "a\nb\n\t\tc\n");
+    assert_eq!(
+        result.order_error,
+        Some(OrderErrorKind::Stopped {
+            file: 2,
+            lines_emitted: 3
+        })
+    );
+    assert_eq!((result.count1, result.count2, result.count3), (2, 0, 1));
+
+    // Suppressed columns are not counted as emitted
+    let config = CommConfig {
+        suppress_col1: true,
+        ..config
+    };
+    let (out, result) = comm_result(b"a\nb\nc\n", b"c\na\nd\n", &config);
+    assert_eq!(out, "\tc\n");
+    assert_eq!(
+        result.order_error,
+        Some(OrderErrorKind::Stopped {
+            file: 2,
+            lines_emitted: 1
+        })
+    );
+}
+
+#[test]
+fn test_order_error_strict_file1() {
+    let config = CommConfig {
+        order_check: OrderCheck::Strict,
+        ..default_config()
+    };
+    let (out, result) = comm_result(b"b\na\nc\n", b"a\nb\nc\n", &config);
+    assert_eq!(out, "\ta\n\t\tb\n");
+    assert_eq!(
+        result.order_error,
+        Some(OrderErrorKind::Stopped {
+            file: 1,
+            lines_emitted: 2
+        })
+    );
+}
+
+#[test]
+fn test_order_warning_verbose() {
+    let plain = default_config();
+    assert_eq!(
+        order_warning("comm", &plain, 1, 10423, b"apple", b"banana"),
+        "comm: file 1 is not in sorted order"
+    );
+    let verbose = CommConfig {
+        verbose_order_errors: true,
+        ..default_config()
+    };
+    assert_eq!(
+        order_warning("comm", &verbose, 1, 10423, b"apple", b"banana"),
+        "comm: file 1 is not in sorted order: line 10423: 'apple' < previous 'banana'"
+    );
+    // Long lines are cut to 60 bytes; control bytes, quotes and invalid
+    // UTF-8 are escaped or replaced
+    let long = "x".repeat(100);
+    assert_eq!(
+        order_warning(
+            "comm",
+            &verbose,
+            2,
+            3,
+            long.as_bytes(),
+            b"a\tb\x01c'd\\\xff"
+        ),
+        format!(
+            "comm: file 2 is not in sorted order: line 3: '{}'... < previous 'a\\tb\\x01c\\'d\\\\\u{fffd}'",
+            "x".repeat(60)
+        )
+    );
 }
 
 #[test]
@@ -358,6 +887,27 @@ mod integration {
         assert_eq!(String::from_utf8_lossy(&out), "a\n\t\tb\n\t\tc\n\td\n");
     }
 
+    #[test]
+    fn test_show_line_numbers_flag() {
+        let dir = tempfile::tempdir().unwrap();
+        let f1 = dir.path().join("a.txt");
+        let f2 = dir.path().join("b.txt");
+        std::fs::write(&f1, super::NUMBERED1).unwrap();
+        std::fs::write(&f2, super::NUMBERED2).unwrap();
+        let (out, _, code) = run_fcomm(&[
+            "--show-line-numbers",
+            "-12",
+            "--output-delimiter=,",
+            f1.to_str().unwrap(),
+            f2.to_str().unwrap(),
+        ]);
+        assert_eq!(code, 0);
+        assert_eq!(
+            String::from_utf8_lossy(&out),
+            "1:1,2:1,a\n1:3,2:2,b\n1:4,2:5,d\n"
+        );
+    }
+
     #[test]
     fn test_suppress_flags() {
         let dir = tempfile::tempdir().unwrap();
@@ -436,6 +986,104 @@ mod integration {
         assert_eq!(String::from_utf8_lossy(&out), "a\n,,b\n,c\n");
     }
 
+    #[test]
+    fn test_no_column_prefix_flag() {
+        let dir = tempfile::tempdir().unwrap();
+        let f1 = dir.path().join("a.txt");
+        let f2 = dir.path().join("b.txt");
+        std::fs::write(&f1, "a\nb\n").unwrap();
+        std::fs::write(&f2, "b\nc\n").unwrap();
+        let (out, _, code) = run_fcomm(&[
+            "-1",
+            "--no-column-prefix",
+            f1.to_str().unwrap(),
+            f2.to_str().unwrap(),
+        ]);
+        assert_eq!(code, 0);
+        assert_eq!(String::from_utf8_lossy(&out), "b\nc\n");
+    }
+
+    #[test]
+    fn test_symmetric_difference_matches_tr_pipeline() {
+        let dir = tempfile::tempdir().unwrap();
+        let f1 = dir.path().join("a.txt");
+        let f2 = dir.path().join("b.txt");
+        std::fs::write(&f1, "apple\nbanana\ncherry\nfig\n").unwrap();
+        std::fs::write(&f2, "banana\ndate\nfig\ngrape\n").unwrap();
+        let (f1, f2) = (f1.to_str().unwrap(), f2.to_str().unwrap());
+
+        // `comm -3 a b | tr -d '\t'`
+        let (columns, _, code) = run_fcomm(&["-3", f1, f2]);
+        assert_eq!(code, 0);
+        let flat: Vec<u8> = columns.into_iter().filter(|&b| b != b'\t').collect();
+        for flag in ["--symmetric-difference", "-u"] {
+            let (out, err, code) = run_fcomm(&[flag, f1, f2]);
+            assert_eq!(code, 0, "{}", String::from_utf8_lossy(&err));
+            assert_eq!(out, flat, "{}", flag);
+        }
+        assert_eq!(flat, b"apple\ncherry\ndate\ngrape\n");
+
+        let (out, _, code) = run_fcomm(&["--intersection", f1, f2]);
+        assert_eq!(code, 0);
+        assert_eq!(out, b"banana\nfig\n");
+
+        // --total still counts every column, with the default separator
+        let (out, _, _) = run_fcomm(&["-u", "--total", f1, f2]);
+        assert_eq!(
+            String::from_utf8_lossy(&out),
+            "apple\ncherry\ndate\ngrape\n2\t2\t2\ttotal\n"
+        );
+    }
+
+    #[test]
+    fn test_set_operations_zero_terminated() {
+        let dir = tempfile::tempdir().unwrap();
+        let f1 = dir.path().join("a");
+        let f2 = dir.path().join("b");
+        std::fs::write(&f1, "a\0b\0").unwrap();
+        std::fs::write(&f2, "b\0c\0").unwrap();
+        let (f1, f2) = (f1.to_str().unwrap(), f2.to_str().unwrap());
+        let (out, _, _) = run_fcomm(&["-zu", f1, f2]);
+        assert_eq!(out, b"a\0c\0");
+        let (out, _, _) = run_fcomm(&["-z", "--intersection", "--total", f1, f2]);
+        assert_eq!(out, b"b\x001\t1\t1\ttotal\0");
+    }
+
+    #[test]
+    fn test_set_operation_conflicts() {
+        let cases: &[(&[&str], &str)] = &[
+            (
+                &["-u", "-3"],
+                "comm: --symmetric-difference cannot be combined with -1, -2 or -3\n",
+            ),
+            (
+                &["-1", "--intersection"],
+                "comm: --intersection cannot be combined with -1, -2 or -3\n",
+            ),
+            (
+                &["--intersection", "--output-delimiter=,"],
+                "comm: --intersection cannot be combined with --output-delimiter\n",
+            ),
+            (
+                &["--intersection", "-u"],
+                "comm: --intersection cannot be combined with --symmetric-difference\n",
+            ),
+        ];
+        for &(args, msg) in cases {
+            let mut args = args.to_vec();
+            args.extend(["x", "y"]);
+            let (out, err, code) = run_fcomm(&args);
+            assert_eq!(code, 1, "{:?}", args);
+            assert!(out.is_empty());
+            assert_eq!(
+                String::from_utf8_lossy(&err),
+                format!("{}Try 'comm --help' for more information.\n", msg),
+                "{:?}",
+                args
+            );
+        }
+    }
+
     #[test]
     fn test_total_flag() {
         let dir = tempfile::tempdir().unwrap();
@@ -463,6 +1111,180 @@ mod integration {
         assert!(String::from_utf8_lossy(&err).contains("not in sorted order"));
     }
 
+    #[test]
+    fn test_sort_flag_matches_sort_command() {
+        let dir = tempfile::tempdir().unwrap();
+        let raw1 = dir.path().join("raw1.txt");
+        let raw2 = dir.path().join("raw2.txt");
+        std::fs::write(&raw1, "cherry\nDate\napple\nApple\nbanana\n").unwrap();
+        std::fs::write(&raw2, "date\nBanana\napple\nfig\nApple\ncherry\n").unwrap();
+        let sort = |flags: &[&str], input: &std::path::Path, name: &str| {
+            let output = Command::new("sort")
+                .env("LC_ALL", "C")
+                .args(flags)
+                .arg(input)
+                .output()
+                .ok()?;
+            let path = dir.path().join(name);
+            std::fs::write(&path, &output.stdout).unwrap();
+            Some(path.to_str().unwrap().to_string())
+        };
+
+        for (sort_flags, comm_flags) in [(&[][..], &[][..]), (&["-f"], &["-i"])] {
+            let Some(sorted1) = sort(sort_flags, &raw1, "sorted1.txt") else {
+                return;
+            };
+            let Some(sorted2) = sort(sort_flags, &raw2, "sorted2.txt") else {
+                return;
+            };
+
+            let mut args = comm_flags.to_vec();
+            args.extend(["--check-order", &sorted1, &sorted2]);
+            let (expected, _, code) = run_fcomm(&args);
+            assert_eq!(code, 0, "{:?}", comm_flags);
+
+            // One input sorted, the other shuffled
+            let mut args = comm_flags.to_vec();
+            args.extend(["--sort", &sorted1, raw2.to_str().unwrap()]);
+            let (out, err, code) = run_fcomm(&args);
+            assert_eq!(code, 0, "{:?}", comm_flags);
+            assert!(err.is_empty());
+            assert_eq!(
+                String::from_utf8_lossy(&out),
+                String::from_utf8_lossy(&expected),
+                "{:?}",
+                comm_flags
+            );
+        }
+    }
+
+    #[test]
+    fn test_sort_with_check_order_is_usage_error() {
+        let dir = tempfile::tempdir().unwrap();
+        let f1 = dir.path().join("a.txt");
+        std::fs::write(&f1, "b\na\n").unwrap();
+        let f1 = f1.to_str().unwrap();
+        for args in [
+            &["--sort", "--check-order", f1, f1][..],
+            &["--check-order", "--sort", f1, f1],
+            &["--sort", "--decompress=auto", f1, f1],
+        ] {
+            let (out, err, code) = run_fcomm(args);
+            assert_eq!(code, 1, "{:?}", args);
+            assert!(out.is_empty());
+            let err = String::from_utf8_lossy(&err);
+            assert!(
+                err.starts_with("comm: --sort cannot be combined with --"),
+                "{}",
+                err
+            );
+            assert!(err.ends_with("Try 'comm --help' for more information.\n"));
+        }
+    }
+
+    #[test]
+    fn test_order_exit_codes_and_summary() {
+        let dir = tempfile::tempdir().unwrap();
+        let sorted = dir.path().join("s.txt");
+        let unsorted1 = dir.path().join("u1.txt");
+        let unsorted2 = dir.path().join("u2.txt");
+        std::fs::write(&sorted, "a\nb\nc\n").unwrap();
+        std::fs::write(&unsorted1, "b\na\nc\n").unwrap();
+        std::fs::write(&unsorted2, "c\na\nd\n").unwrap();
+        let (s, u1, u2) = (
+            sorted.to_str().unwrap(),
+            unsorted1.to_str().unwrap(),
+            unsorted2.to_str().unwrap(),
+        );
+
+        let (_, err, code) = run_fcomm(&[u1, s]);
+        assert_eq!(code, 1);
+        assert_eq!(
+            String::from_utf8_lossy(&err),
+            "comm: file 1 is not in sorted order\ncomm: input is not in sorted order\n"
+        );
+
+        let (_, err, code) = run_fcomm(&[s, u2]);
+        assert_eq!(code, 1);
+        assert_eq!(
+            String::from_utf8_lossy(&err),
+            "comm: file 2 is not in sorted order\ncomm: input is not in sorted order\n"
+        );
+
+        let (out, err, code) = run_fcomm(&["--total", u1, u2]);
+        assert_eq!(code, 1);
+        assert_eq!(
+            String::from_utf8_lossy(&err),
+            "comm: file 1 is not in sorted order\n\
+             comm: file 2 is not in sorted order\n\
+             comm: input is not in sorted order\n"
+        );
+        assert!(String::from_utf8_lossy(&out).ends_with("2\t2\t1\ttotal\n"));
+
+        // Strict: partial output, no summary line
+        let (out, err, code) = run_fcomm(&["--check-order", "--total", s, u2]);
+        assert_eq!(code, 1);
+        assert_eq!(String::from_utf8_lossy(&out), "a\nb\n\t\tc\n");
+        assert_eq!(
+            String::from_utf8_lossy(&err),
+            "comm: file 2 is not in sorted order\n"
+        );
+
+        let (_, err, code) = run_fcomm(&["--nocheck-order", u1, u2]);
+        assert_eq!(code, 0);
+        assert!(err.is_empty());
+    }
+
+    #[test]
+    fn test_verbose_order_errors() {
+        let dir = tempfile::tempdir().unwrap();
+        let f1 = dir.path().join("a.txt");
+        let f2 = dir.path().join("b.txt");
+        let mut data = String::new();
+        for i in 0..1000 {
+            data.push_str(&format!("{:04}\n", i));
+        }
+        let long = format!("zz{}", "\x07".repeat(80));
+        data.push_str(&long);
+        data.push_str("\n0500\n");
+        std::fs::write(&f1, &data).unwrap();
+        std::fs::write(&f2, "").unwrap();
+        let (a, b) = (f1.to_str().unwrap(), f2.to_str().unwrap());
+
+        let expected = format!(
+            "comm: file 1 is not in sorted order: line 1002: '0500' < previous 'zz{}'...\n",
+            "\\x07".repeat(58)
+        );
+        let (_, err, code) = run_fcomm(&["--verbose-order-errors", a, b]);
+        assert_eq!(code, 1);
+        assert_eq!(
+            String::from_utf8_lossy(&err),
+            format!("{}comm: input is not in sorted order\n", expected)
+        );
+
+        let (out, err, code) = run_fcomm(&["--check-order", "--verbose-order-errors", a, b]);
+        assert_eq!(code, 1);
+        assert_eq!(String::from_utf8_lossy(&err), expected);
+        assert_eq!(out.len(), data.len() - "0500\n".len());
+
+        // The streaming merge reports the same line
+        let (_, err, _) = run_fcomm(&[
+            "--check-order",
+            "--verbose-order-errors",
+            "--decompress=auto",
+            a,
+            b,
+        ]);
+        assert_eq!(String::from_utf8_lossy(&err), expected);
+
+        // Without the flag the message is GNU's
+        let (_, err, _) = run_fcomm(&["--check-order", a, b]);
+        assert_eq!(
+            String::from_utf8_lossy(&err),
+            "comm: file 1 is not in sorted order\n"
+        );
+    }
+
     #[test]
     fn test_nocheck_order_unsorted() {
         let dir = tempfile::tempdir().unwrap();
@@ -559,6 +1381,27 @@ mod integration {
             Some((output.stdout, output.status.code().unwrap_or(1)))
         }
 
+        #[test]
+        fn test_gnu_trailing_delimiter_matrix() {
+            let dir = tempfile::tempdir().unwrap();
+            let f1 = dir.path().join("a.txt");
+            let f2 = dir.path().join("b.txt");
+            for (d, flag) in [('\n', None), ('\0', Some("-z"))] {
+                for (a, b, expected) in super::super::trailing_delimiter_cases(d) {
+                    std::fs::write(&f1, &a).unwrap();
+                    std::fs::write(&f2, &b).unwrap();
+                    let mut args: Vec<&str> = flag.into_iter().collect();
+                    args.extend([f1.to_str().unwrap(), f2.to_str().unwrap()]);
+                    let (our_out, _, our_code) = run_fcomm(&args);
+                    assert_eq!(our_code, 0);
+                    assert_eq!(our_out, expected.as_bytes(), "{:?} {:?}", a, b);
+                    if let Some((gnu_out, _)) = run_gnu_comm(&args) {
+                        assert_eq!(our_out, gnu_out, "{:?} {:?}", a, b);
+                    }
+                }
+            }
+        }
+
         #[test]
         fn test_gnu_basic() {
             let dir = tempfile::tempdir().unwrap();
@@ -672,4 +1515,151 @@ mod integration {
             assert_eq!(our_code, 0, "fcomm --total should succeed");
         }
     }
+
+    #[test]
+    fn test_both_operands_stdin() {
+        let (out, err, code) = run_fcomm_stdin(b"a\nb\nc\n", &["-", "-"]);
+        assert_eq!(code, 0);
+        assert_eq!(String::from_utf8_lossy(&out), "a\nb\nc\n");
+        assert_eq!(
+            String::from_utf8_lossy(&err),
+            "comm: warning: standard input given for both FILE1 and FILE2; treating FILE2 as empty\n"
+        );
+    }
+
+    #[test]
+    fn test_same_file_both_operands() {
+        let dir = tempfile::tempdir().unwrap();
+        let f = dir.path().join("big.txt");
+        let mut data = String::new();
+        for i in 0..200_000 {
+            data.push_str(&format!("line{:08}\n", i));
+        }
+        std::fs::write(&f, &data).unwrap();
+        let path = f.to_str().unwrap();
+        let (out, err, code) = run_fcomm(&["-12", "--total", path, path]);
+        assert_eq!(code, 0);
+        assert!(err.is_empty());
+        let expected = format!("{}0\t0\t200000\ttotal\n", data);
+        assert!(out == expected.as_bytes());
+
+        // A second name for the same inode is deduped the same way
+        let link = dir.path().join("link.txt");
+        std::fs::hard_link(&f, &link).unwrap();
+        let (out, _, code) = run_fcomm(&["-3", path, link.to_str().unwrap()]);
+        assert_eq!(code, 0);
+        assert!(out.is_empty());
+    }
+
+    /// `data` gzip-compressed.
+    fn gzip(data: &[u8]) -> Vec<u8> {
+        use std::io::Write;
+        let mut enc = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        enc.write_all(data).unwrap();
+        enc.finish().unwrap()
+    }
+
+    #[test]
+    fn test_decompress_matches_plain() {
+        let dir = tempfile::tempdir().unwrap();
+        let text1: String = (0..2000)
+            .filter(|i| i % 3 != 0)
+            .map(|i| format!("{:05}\n", i))
+            .collect();
+        let text2: String = (0..2000)
+            .filter(|i| i % 7 != 0)
+            .map(|i| format!("{:05}\n", i))
+            .collect();
+        let (p1, p2) = (dir.path().join("a.txt"), dir.path().join("b.txt"));
+        let (z1, z2) = (dir.path().join("a.gz"), dir.path().join("b.gz"));
+        std::fs::write(&p1, &text1).unwrap();
+        std::fs::write(&p2, &text2).unwrap();
+        std::fs::write(&z1, gzip(text1.as_bytes())).unwrap();
+        std::fs::write(&z2, gzip(text2.as_bytes())).unwrap();
+        let (p1, p2, z1, z2) = (
+            p1.to_str().unwrap(),
+            p2.to_str().unwrap(),
+            z1.to_str().unwrap(),
+            z2.to_str().unwrap(),
+        );
+
+        let (expected, _, code) = run_fcomm(&["--total", p1, p2]);
+        assert_eq!(code, 0);
+        for args in [
+            ["--decompress=auto", z1, z2],
+            ["--decompress=gzip", z1, z2],
+            // One compressed input and one plain one
+            ["--decompress=auto", z1, p2],
+            ["--decompress=auto", p1, z2],
+        ] {
+            let (out, err, code) = run_fcomm(&["--total", args[0], args[1], args[2]]);
+            assert_eq!(code, 0, "{:?}: {}", args, String::from_utf8_lossy(&err));
+            assert!(out == expected, "{:?}", args);
+        }
+
+        // Without --decompress the gzip bytes are compared as they are
+        let (out, _, _) = run_fcomm(&["--nocheck-order", z1, p2]);
+        assert!(out != expected);
+
+        // A gzip input on stdin
+        let (out, _, code) = run_fcomm_stdin(
+            &gzip(text1.as_bytes()),
+            &["--decompress=auto", "--total", "-", z2],
+        );
+        assert_eq!(code, 0);
+        assert!(out == expected);
+    }
+
+    #[test]
+    fn test_decompress_corrupt_input() {
+        let dir = tempfile::tempdir().unwrap();
+        let text: String = (0..50_000).map(|i| format!("{:06}\n", i)).collect();
+        let compressed = gzip(text.as_bytes());
+        let (z, p) = (dir.path().join("a.gz"), dir.path().join("b.txt"));
+        std::fs::write(&p, "x\n").unwrap();
+        let (z, p) = (z.to_str().unwrap(), p.to_str().unwrap());
+
+        // Cut off mid-stream: the lines before the cut are still merged
+        std::fs::write(z, &compressed[..compressed.len() / 2]).unwrap();
+        let (out, err, code) = run_fcomm(&["--decompress=auto", "-3", z, p]);
+        assert_eq!(code, 1);
+        assert_eq!(
+            String::from_utf8_lossy(&err),
+            format!("comm: {}: invalid gzip data\n", z)
+        );
+        assert!(!out.is_empty());
+        assert!(text.as_bytes().starts_with(&out));
+
+        // Damaged mid-stream
+        let mut corrupt = compressed.clone();
+        let mid = corrupt.len() / 2;
+        for b in &mut corrupt[mid..mid + 64] {
+            *b = !*b;
+        }
+        std::fs::write(z, &corrupt).unwrap();
+        let (_, err, code) = run_fcomm(&["--decompress=auto", "--nocheck-order", z, p]);
+        assert_eq!(code, 1);
+        assert_eq!(
+            String::from_utf8_lossy(&err),
+            format!("comm: {}: invalid gzip data\n", z)
+        );
+
+        // Forcing gzip on a plain input fails the same way
+        let (_, err, code) = run_fcomm(&["--decompress=gzip", p, p]);
+        assert_eq!(code, 1);
+        assert_eq!(
+            String::from_utf8_lossy(&err),
+            format!("comm: {}: invalid gzip data\n", p)
+        );
+    }
+
+    #[test]
+    fn test_decompress_invalid_argument() {
+        let (_, err, code) = run_fcomm(&["--decompress=bzip2", "a", "b"]);
+        assert_eq!(code, 1);
+        assert!(
+            String::from_utf8_lossy(&err)
+                .starts_with("comm: invalid argument 'bzip2' for '--decompress'")
+        );
+    }
 }
@@ -0,0 +1,49 @@
+use std::alloc::{GlobalAlloc, Layout};
+use std::cell::Cell;
+
+// ── Allocation counting for unit tests ─────────────────────────────────
+// Unit-test builds of the library install `Counting` in place of plain
+// mimalloc, so a test can assert that a code path stays off the heap.
+// The count is per thread, so tests running in parallel do not see each
+// other's allocations.
+
+thread_local! {
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+/// mimalloc, counting the allocations and reallocations of each thread.
+pub struct Counting;
+
+fn count() {
+    // Cannot fail: the counter has no destructor, so it outlives the thread
+    let _ = ALLOCATIONS.try_with(|n| n.set(n.get() + 1));
+}
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        count();
+        unsafe { mimalloc::MiMalloc.alloc(layout) }
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        count();
+        unsafe { mimalloc::MiMalloc.alloc_zeroed(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { mimalloc::MiMalloc.dealloc(ptr, layout) }
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        count();
+        unsafe { mimalloc::MiMalloc.realloc(ptr, layout, new_size) }
+    }
+}
+
+/// Run `f`, returning its result and how many allocations it made on
+/// this thread.
+pub fn allocations_in<R>(f: impl FnOnce() -> R) -> (R, usize) {
+    let before = ALLOCATIONS.with(Cell::get);
+    let result = f();
+    (result, ALLOCATIONS.with(Cell::get) - before)
+}
@@ -0,0 +1,851 @@
+use std::io;
+use std::path::{Component, Path, PathBuf};
+
+// ── Path canonicalization shared by readlink and realpath ───────────────
+// Mirrors gnulib's canonicalize_filename_mode(): `Existence` is its
+// CAN_EXISTING / CAN_ALL_BUT_LAST / CAN_MISSING choice, and `Resolve`
+// selects between resolving symlinks as they are met (physical, the
+// default) and applying `..` textually first (logical, realpath -L).
+
+/// Which components of a path must exist.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Existence {
+    /// Every component must exist (-e).
+    Existing,
+    /// Every component but the last must exist (readlink -f, realpath).
+    AllButLast,
+    /// No component needs to exist (-m).
+    Missing,
+}
+
+/// How `..` interacts with symlinks.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Resolve {
+    /// Resolve symlinks as they are encountered, so `link/..` is the parent
+    /// of the link's target (-P).
+    #[default]
+    Physical,
+    /// Start relative paths from $PWD and apply `..` lexically before any
+    /// symlink is resolved, so `link/..` is the directory holding the link,
+    /// as with `cd -L` (-L).
+    Logical,
+    /// Apply `..` lexically and never resolve symlinks, so the result may
+    /// still contain them (realpath -s). Relative paths start from the
+    /// physical working directory.
+    NoSymlinks,
+}
+
+/// Canonicalize `path` under the given existence rule and resolve mode.
+pub fn canonicalize(path: &Path, existence: Existence, resolve: Resolve) -> io::Result<PathBuf> {
+    match resolve {
+        Resolve::Physical => canonicalize_physical(path, existence),
+        Resolve::Logical => {
+            let abs = if path.is_absolute() {
+                path.to_path_buf()
+            } else {
+                logical_cwd()?.join(path)
+            };
+            let lexical = resolve_lexically(&abs, existence)?;
+            canonicalize_physical(&lexical, existence)
+        }
+        Resolve::NoSymlinks => resolve_lexically(&make_absolute(path), existence),
+    }
+}
+
+fn canonicalize_physical(path: &Path, existence: Existence) -> io::Result<PathBuf> {
+    match existence {
+        Existence::Existing => std::fs::canonicalize(path),
+        Existence::AllButLast => canonicalize_last_missing(path, 0),
+        Existence::Missing => canonicalize_missing(path, 0),
+    }
+}
+
+/// The working directory as the shell sees it: $PWD when it is absolute,
+/// free of `.` and `..` components and names the same directory as "."
+/// (same device and inode); otherwise the physical getcwd().
+pub fn logical_cwd() -> io::Result<PathBuf> {
+    if let Some(pwd) = std::env::var_os("PWD") {
+        let pwd = PathBuf::from(pwd);
+        let dotted = pwd
+            .as_os_str()
+            .as_encoded_bytes()
+            .split(|&b| b == b'/')
+            .any(|seg| seg == b"." || seg == b"..");
+        if pwd.is_absolute() && !dotted && same_file(&pwd, Path::new(".")) {
+            return Ok(pwd);
+        }
+    }
+    std::env::current_dir()
+}
+
+#[cfg(unix)]
+fn same_file(a: &Path, b: &Path) -> bool {
+    use std::os::unix::fs::MetadataExt;
+    match (std::fs::metadata(a), std::fs::metadata(b)) {
+        (Ok(a), Ok(b)) => a.dev() == b.dev() && a.ino() == b.ino(),
+        _ => false,
+    }
+}
+
+#[cfg(not(unix))]
+fn same_file(a: &Path, b: &Path) -> bool {
+    match (std::fs::canonicalize(a), std::fs::canonicalize(b)) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => false,
+    }
+}
+
+/// Make a path absolute against the physical working directory.
+pub fn make_absolute(path: &Path) -> PathBuf {
+    if path.is_absolute() {
+        path.to_path_buf()
+    } else {
+        std::env::current_dir()
+            .unwrap_or_else(|_| PathBuf::from("/"))
+            .join(path)
+    }
+}
+
+/// Normalize a path by resolving . and .. without touching the filesystem.
+pub fn normalize_path(path: &Path) -> PathBuf {
+    let mut result = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                result.pop();
+            }
+            c => {
+                result.push(c.as_os_str());
+            }
+        }
+    }
+    result
+}
+
+/// Apply `.` and `..` in the absolute `path` textually: the CAN_NOLINKS
+/// pass of gnulib's canonicalize_filename_mode(), which is all of
+/// realpath -s and the first half of -L. GNU documents -s as not
+/// expanding symlinks while -e, -m and the default keep their existence
+/// rules; since no link is read, a component is only looked at when the
+/// rest of the name needs it to be a directory. Symlinks are followed for
+/// those checks, but the result still contains them.
+///
+/// - `Missing` (-m): nothing is checked, so `/nonexistent/a/../b` is
+///   `/nonexistent/b`.
+/// - `AllButLast` (default): a component followed by `..` must be a
+///   directory, so `missing/../b` and `file/../b` fail. The result must
+///   exist or be missing, not merely unreachable, so `top/x` with `top` a
+///   file fails while `link/missing/x` and `dangling/x` are accepted.
+/// - `Existing` (-e): the same checks, and the result must exist.
+///
+/// In both checked modes a trailing `/` or `/.` makes the result a
+/// directory that has to exist, except that `missing/` is still fine in
+/// the default mode.
+fn resolve_lexically(path: &Path, existence: Existence) -> io::Result<PathBuf> {
+    let components: Vec<Component<'_>> = path
+        .components()
+        .filter(|c| *c != Component::CurDir)
+        .collect();
+    let bytes = path.as_os_str().as_encoded_bytes();
+    let trimmed = &bytes[..bytes.len() - bytes.iter().rev().take_while(|&&b| b == b'/').count()];
+    let dot_suffix = trimmed == b"." || trimmed.ends_with(b"/.");
+    let dir_suffix = dot_suffix || trimmed.len() < bytes.len();
+
+    let mut resolved = PathBuf::new();
+    for (i, c) in components.iter().enumerate() {
+        match c {
+            Component::ParentDir => {
+                resolved.pop();
+            }
+            Component::Normal(name) => {
+                resolved.push(name);
+                if existence != Existence::Missing
+                    && components.get(i + 1) == Some(&Component::ParentDir)
+                {
+                    check_dir(&resolved)?;
+                }
+            }
+            Component::CurDir | Component::RootDir | Component::Prefix(_) => {
+                resolved.push(c.as_os_str());
+            }
+        }
+    }
+    if existence == Existence::Missing || components.last() == Some(&Component::ParentDir) {
+        return Ok(resolved);
+    }
+    let checked = if dir_suffix {
+        check_dir(&resolved)
+    } else {
+        std::fs::metadata(&resolved).map(drop)
+    };
+    match checked {
+        Err(e)
+            if existence == Existence::AllButLast
+                && !dot_suffix
+                && e.kind() == io::ErrorKind::NotFound =>
+        {
+            Ok(resolved)
+        }
+        Err(e) => Err(e),
+        Ok(()) => Ok(resolved),
+    }
+}
+
+/// Fail unless `path` names a directory, following symlinks.
+fn check_dir(path: &Path) -> io::Result<()> {
+    if std::fs::metadata(path)?.is_dir() {
+        Ok(())
+    } else {
+        Err(io::Error::from_raw_os_error(libc::ENOTDIR))
+    }
+}
+
+/// Canonicalize when every component but the last must exist. A dangling
+/// final symlink is followed to its (nonexistent) target, like GNU.
+fn canonicalize_last_missing(path: &Path, depth: usize) -> io::Result<PathBuf> {
+    let err = match std::fs::canonicalize(path) {
+        Ok(canon) => return Ok(canon),
+        // A final "." makes the name before it a directory that must exist,
+        // so "missing/." and "/proc/self/fd/3/." for a deleted file fail
+        Err(e) if e.kind() == io::ErrorKind::NotFound && !ends_with_dot(path) => e,
+        Err(e) => return Err(e),
+    };
+    let Some(name) = path.file_name() else {
+        return Err(err);
+    };
+    let parent = match path.parent() {
+        Some(p) if !p.as_os_str().is_empty() => std::fs::canonicalize(p)?,
+        _ => std::env::current_dir()?,
+    };
+    let candidate = parent.join(name);
+    if let Ok(meta) = std::fs::symlink_metadata(&candidate)
+        && meta.file_type().is_symlink()
+    {
+        // Same limit as the kernel's MAXSYMLINKS
+        if depth >= 40 {
+            return Err(io::Error::from_raw_os_error(libc::ELOOP));
+        }
+        let target = std::fs::read_link(&candidate)?;
+        return canonicalize_last_missing(&parent.join(target), depth + 1);
+    }
+    Ok(candidate)
+}
+
+/// Whether the last component of `path` is ".", which `Path::components`
+/// and `file_name` leave out.
+fn ends_with_dot(path: &Path) -> bool {
+    let bytes = path.as_os_str().as_encoded_bytes();
+    bytes == b"." || bytes.ends_with(b"/.")
+}
+
+/// Canonicalize a path where not all components need to exist.
+///
+/// Like GNU, every existing symlink (dangling ones included) is resolved
+/// before a following `..` is applied, so `link/..` is the parent of the
+/// link's target, not the directory holding the link.
+fn canonicalize_missing(path: &Path, depth: usize) -> io::Result<PathBuf> {
+    let abs = if path.is_absolute() {
+        path.to_path_buf()
+    } else {
+        std::env::current_dir()?.join(path)
+    };
+
+    // Try to canonicalize the whole thing first
+    if let Ok(canon) = std::fs::canonicalize(&abs) {
+        return Ok(canon);
+    }
+
+    let components: Vec<Component<'_>> = abs.components().collect();
+    let mut resolved = PathBuf::new();
+    let mut remaining_start = 0;
+
+    // Find the longest resolvable prefix
+    for i in (0..components.len()).rev() {
+        let mut prefix = PathBuf::new();
+        for c in &components[..=i] {
+            prefix.push(c.as_os_str());
+        }
+        if let Ok(canon) = std::fs::canonicalize(&prefix) {
+            resolved = canon;
+            remaining_start = i + 1;
+            break;
+        }
+    }
+
+    if resolved.as_os_str().is_empty() {
+        if let Some(Component::RootDir) = components.first() {
+            resolved.push("/");
+            remaining_start = 1;
+        } else {
+            resolved = std::env::current_dir()?;
+        }
+    }
+
+    for c in &components[remaining_start..] {
+        match c {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                resolved.pop();
+            }
+            Component::Normal(s) => {
+                resolved.push(s);
+                if let Ok(meta) = resolved.symlink_metadata() {
+                    if let Ok(canon) = std::fs::canonicalize(&resolved) {
+                        resolved = canon;
+                    } else if meta.file_type().is_symlink() && depth < 40 {
+                        // Dangling symlink: continue from its target. Past the
+                        // kernel's MAXSYMLINKS, GNU keeps the looping name as is.
+                        let target = std::fs::read_link(&resolved)?;
+                        resolved.pop();
+                        resolved = canonicalize_missing(&resolved.join(target), depth + 1)?;
+                    }
+                }
+            }
+            Component::RootDir | Component::Prefix(_) => {
+                resolved.push(c.as_os_str());
+            }
+        }
+    }
+
+    Ok(resolved)
+}
+
+// ── Batch canonicalization ──────────────────────────────────────────────
+// Resolving many paths under the same deep directory with realpath(3)
+// walks every component from / each time. On Linux the physical walk is
+// done here with openat(O_PATH) directory fds and readlinkat, keeping the
+// fds of the last few directories a walk ended in, so the next path under
+// one of them starts there. Any error (or anything unusual: trailing
+// slashes, non-directories in the middle, symlink loops) falls back to
+// `canonicalize`, which also produces the error message, so only Ok
+// results ever come from the fast path.
+
+/// Canonicalizes a sequence of paths, reusing directory lookups between
+/// calls. Results are the same as from `canonicalize`.
+#[derive(Default)]
+pub struct Canonicalizer {
+    #[cfg(target_os = "linux")]
+    dirs: Vec<CachedDir>,
+}
+
+impl Canonicalizer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Canonicalize `path` as `canonicalize` would.
+    pub fn canonicalize(
+        &mut self,
+        path: &Path,
+        existence: Existence,
+        resolve: Resolve,
+    ) -> io::Result<PathBuf> {
+        #[cfg(target_os = "linux")]
+        if resolve == Resolve::Physical
+            && let Some(resolved) = self.walk(path, existence)
+        {
+            return Ok(resolved);
+        }
+        canonicalize(path, existence, resolve)
+    }
+}
+
+/// Directory fds kept by a `Canonicalizer`.
+#[cfg(target_os = "linux")]
+const DIR_CACHE_SIZE: usize = 8;
+
+/// Same limit as the kernel's MAXSYMLINKS.
+#[cfg(target_os = "linux")]
+const MAX_SYMLINKS: usize = 40;
+
+/// An open directory and the canonical path it was opened as.
+#[cfg(target_os = "linux")]
+struct CachedDir {
+    path: PathBuf,
+    fd: std::os::fd::OwnedFd,
+    dev: libc::dev_t,
+    ino: libc::ino_t,
+}
+
+#[cfg(target_os = "linux")]
+impl Canonicalizer {
+    /// The physical walk; None sends the caller to the portable code.
+    fn walk(&mut self, path: &Path, existence: Existence) -> Option<PathBuf> {
+        use std::ffi::OsString;
+        use std::os::unix::ffi::OsStrExt;
+
+        let bytes = path.as_os_str().as_bytes();
+        if bytes.is_empty() || bytes.ends_with(b"/") || bytes.ends_with(b"/.") || bytes == b"." {
+            return None;
+        }
+        let abs = if path.is_absolute() {
+            path.to_path_buf()
+        } else {
+            std::env::current_dir().ok()?.join(path)
+        };
+
+        let (mut dir, mut fd, start) = match self.cached_start(&abs) {
+            Some(hit) => hit,
+            None => (PathBuf::from("/"), open_dir(None, b"/")?, 1),
+        };
+        let mut pending: Vec<OsString> = Vec::new();
+        for c in abs
+            .components()
+            .skip(start)
+            .collect::<Vec<_>>()
+            .into_iter()
+            .rev()
+        {
+            match c {
+                Component::Normal(name) => pending.push(name.to_os_string()),
+                Component::ParentDir => pending.push(OsString::from("..")),
+                Component::CurDir => {}
+                Component::RootDir | Component::Prefix(_) => return None,
+            }
+        }
+
+        // Components below `dir` that do not exist (or the final leaf)
+        let mut tail: Vec<OsString> = Vec::new();
+        let mut symlinks = 0;
+        while let Some(name) = pending.pop() {
+            let last = pending.is_empty();
+            if name == ".." {
+                if tail.pop().is_none() {
+                    dir.pop();
+                    fd = open_dir(Some(&fd), b"..")?;
+                }
+                continue;
+            }
+            if !tail.is_empty() {
+                // Under a missing directory nothing exists
+                tail.push(name);
+                continue;
+            }
+            let st = match stat_at(&fd, name.as_bytes()) {
+                Ok(st) => st,
+                Err(e) if e.raw_os_error() == Some(libc::ENOENT) => {
+                    match existence {
+                        Existence::Missing => {}
+                        Existence::AllButLast if last => {}
+                        _ => return None,
+                    }
+                    tail.push(name);
+                    continue;
+                }
+                Err(_) => return None,
+            };
+            match st.st_mode & libc::S_IFMT {
+                libc::S_IFLNK => {
+                    symlinks += 1;
+                    if symlinks > MAX_SYMLINKS {
+                        return None;
+                    }
+                    let target = read_link_at(&fd, name.as_bytes())?;
+                    let tbytes = target.as_bytes();
+                    if tbytes.is_empty() || tbytes.ends_with(b"/") || tbytes.ends_with(b"/.") {
+                        return None;
+                    }
+                    let target = Path::new(&target);
+                    if target.is_absolute() {
+                        dir = PathBuf::from("/");
+                        fd = open_dir(None, b"/")?;
+                    }
+                    for c in target.components().rev() {
+                        match c {
+                            Component::Normal(n) => pending.push(n.to_os_string()),
+                            Component::ParentDir => pending.push(OsString::from("..")),
+                            Component::CurDir | Component::RootDir => {}
+                            Component::Prefix(_) => return None,
+                        }
+                    }
+                }
+                libc::S_IFDIR if !last => {
+                    fd = open_dir(Some(&fd), name.as_bytes())?;
+                    dir.push(&name);
+                }
+                _ if last => tail.push(name),
+                _ => return None,
+            }
+        }
+
+        let mut resolved = dir.clone();
+        for name in &tail {
+            resolved.push(name);
+        }
+        self.remember(dir, fd);
+        Some(resolved)
+    }
+
+    /// The longest cached directory `abs` starts with, still at that path,
+    /// as (path, fd, components it covers).
+    fn cached_start(&mut self, abs: &Path) -> Option<(PathBuf, std::os::fd::OwnedFd, usize)> {
+        loop {
+            let i = (0..self.dirs.len())
+                .filter(|&i| abs.starts_with(&self.dirs[i].path))
+                .max_by_key(|&i| self.dirs[i].path.as_os_str().len())?;
+            let cached = self.dirs.remove(i);
+            // A directory renamed away and replaced must not be reused
+            if stat_path(&cached.path)
+                .is_some_and(|st| (st.st_dev, st.st_ino) == (cached.dev, cached.ino))
+            {
+                let fd = cached.fd.try_clone().ok()?;
+                let start = cached.path.components().count();
+                let path = cached.path.clone();
+                self.dirs.insert(0, cached);
+                return Some((path, fd, start));
+            }
+        }
+    }
+
+    /// Cache `fd` as the directory at `path`, most recent first.
+    fn remember(&mut self, path: PathBuf, fd: std::os::fd::OwnedFd) {
+        if let Some(i) = self.dirs.iter().position(|d| d.path == path) {
+            let cached = self.dirs.remove(i);
+            self.dirs.insert(0, cached);
+            return;
+        }
+        let mut st: libc::stat = unsafe { std::mem::zeroed() };
+        if unsafe { libc::fstat(std::os::fd::AsRawFd::as_raw_fd(&fd), &mut st) } != 0 {
+            return;
+        }
+        self.dirs.truncate(DIR_CACHE_SIZE - 1);
+        self.dirs.insert(
+            0,
+            CachedDir {
+                path,
+                fd,
+                dev: st.st_dev,
+                ino: st.st_ino,
+            },
+        );
+    }
+}
+
+/// Open the directory `name` under `dir` (or the cwd) as an O_PATH fd,
+/// without following a final symlink.
+#[cfg(target_os = "linux")]
+fn open_dir(dir: Option<&std::os::fd::OwnedFd>, name: &[u8]) -> Option<std::os::fd::OwnedFd> {
+    use std::os::fd::{AsRawFd, FromRawFd};
+    let name = std::ffi::CString::new(name).ok()?;
+    let dirfd = dir.map_or(libc::AT_FDCWD, |d| d.as_raw_fd());
+    let flags = libc::O_PATH | libc::O_DIRECTORY | libc::O_NOFOLLOW | libc::O_CLOEXEC;
+    let fd = unsafe { libc::openat(dirfd, name.as_ptr(), flags) };
+    (fd >= 0).then(|| unsafe { std::os::fd::OwnedFd::from_raw_fd(fd) })
+}
+
+/// lstat `name` relative to `dir`.
+#[cfg(target_os = "linux")]
+fn stat_at(dir: &std::os::fd::OwnedFd, name: &[u8]) -> io::Result<libc::stat> {
+    use std::os::fd::AsRawFd;
+    let name =
+        std::ffi::CString::new(name).map_err(|_| io::Error::from_raw_os_error(libc::EINVAL))?;
+    let mut st: libc::stat = unsafe { std::mem::zeroed() };
+    let rc = unsafe {
+        libc::fstatat(
+            dir.as_raw_fd(),
+            name.as_ptr(),
+            &mut st,
+            libc::AT_SYMLINK_NOFOLLOW,
+        )
+    };
+    if rc == 0 {
+        Ok(st)
+    } else {
+        Err(io::Error::last_os_error())
+    }
+}
+
+/// lstat an absolute path.
+#[cfg(target_os = "linux")]
+fn stat_path(path: &Path) -> Option<libc::stat> {
+    use std::os::unix::ffi::OsStrExt;
+    let path = std::ffi::CString::new(path.as_os_str().as_bytes()).ok()?;
+    let mut st: libc::stat = unsafe { std::mem::zeroed() };
+    (unsafe { libc::lstat(path.as_ptr(), &mut st) } == 0).then_some(st)
+}
+
+/// readlinkat `name` relative to `dir`.
+#[cfg(target_os = "linux")]
+fn read_link_at(dir: &std::os::fd::OwnedFd, name: &[u8]) -> Option<std::ffi::OsString> {
+    use std::os::fd::AsRawFd;
+    use std::os::unix::ffi::OsStringExt;
+    let name = std::ffi::CString::new(name).ok()?;
+    let mut buf = vec![0u8; libc::PATH_MAX as usize];
+    let n = unsafe {
+        libc::readlinkat(
+            dir.as_raw_fd(),
+            name.as_ptr(),
+            buf.as_mut_ptr() as *mut libc::c_char,
+            buf.len(),
+        )
+    };
+    if n < 0 || n as usize >= buf.len() {
+        return None;
+    }
+    buf.truncate(n as usize);
+    Some(std::ffi::OsString::from_vec(buf))
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_logical_applies_dotdot_before_symlinks() {
+        let dir = tempfile::tempdir().unwrap();
+        let base = std::fs::canonicalize(dir.path()).unwrap();
+        std::fs::create_dir_all(base.join("a/b")).unwrap();
+        std::os::unix::fs::symlink("a/b", base.join("link")).unwrap();
+
+        let path = base.join("link/..");
+        for existence in [
+            Existence::Existing,
+            Existence::AllButLast,
+            Existence::Missing,
+        ] {
+            assert_eq!(
+                canonicalize(&path, existence, Resolve::Physical).unwrap(),
+                base.join("a")
+            );
+            assert_eq!(
+                canonicalize(&path, existence, Resolve::Logical).unwrap(),
+                base
+            );
+        }
+        // Symlinks left after the lexical pass are still resolved
+        assert_eq!(
+            canonicalize(
+                &base.join("a/../link"),
+                Existence::Existing,
+                Resolve::Logical
+            )
+            .unwrap(),
+            base.join("a/b")
+        );
+    }
+
+    #[test]
+    fn test_logical_checks_components_it_removes() {
+        let dir = tempfile::tempdir().unwrap();
+        let base = std::fs::canonicalize(dir.path()).unwrap();
+        std::fs::write(base.join("file"), "").unwrap();
+
+        let through_missing = base.join("missing/../file");
+        let err =
+            canonicalize(&through_missing, Existence::AllButLast, Resolve::Logical).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::NotFound);
+        assert_eq!(
+            canonicalize(&through_missing, Existence::Missing, Resolve::Logical).unwrap(),
+            base.join("file")
+        );
+
+        let through_file = base.join("file/../file");
+        let err = canonicalize(&through_file, Existence::AllButLast, Resolve::Logical).unwrap_err();
+        assert_eq!(err.raw_os_error(), Some(libc::ENOTDIR));
+
+        assert_eq!(
+            canonicalize(&base.join("new"), Existence::AllButLast, Resolve::Logical).unwrap(),
+            base.join("new")
+        );
+        assert!(canonicalize(&base.join("new"), Existence::Existing, Resolve::Logical).is_err());
+    }
+
+    #[test]
+    fn test_dotdot_after_symlink_matrix() {
+        // `..` applies to where a symlink leads, so it needs the link to
+        // resolve to a directory unless nothing has to exist
+        let dir = tempfile::tempdir().unwrap();
+        let base = std::fs::canonicalize(dir.path()).unwrap();
+        std::fs::create_dir_all(base.join("t/sub")).unwrap();
+        std::fs::create_dir_all(base.join("a/b")).unwrap();
+        std::fs::write(base.join("t/file"), "x").unwrap();
+        std::os::unix::fs::symlink("t/sub", base.join("sd")).unwrap();
+        std::os::unix::fs::symlink("t/file", base.join("sf")).unwrap();
+        std::os::unix::fs::symlink("a/b/c", base.join("dang")).unwrap();
+
+        // (operand, readlink -f / realpath, -e, -m), None for an error;
+        // verified against GNU readlink and realpath
+        type Row = (&'static str, [Option<&'static str>; 3]);
+        let cases: &[Row] = &[
+            ("sd/../x", [Some("t/x"), None, Some("t/x")]),
+            ("sd/..", [Some("t"), Some("t"), Some("t")]),
+            ("missing/../x", [None, None, Some("x")]),
+            ("sf/../x", [None, None, Some("t/x")]),
+            ("sf/..", [None, None, Some("t")]),
+            ("dang/../x", [None, None, Some("a/b/x")]),
+            ("dang/..", [None, None, Some("a/b")]),
+            ("sf/x/../y", [None, None, Some("t/file/y")]),
+        ];
+        let mut batch = Canonicalizer::new();
+        for &(operand, [all_but_last, existing, missing]) in cases {
+            for (existence, expected) in [
+                (Existence::AllButLast, all_but_last),
+                (Existence::Existing, existing),
+                (Existence::Missing, missing),
+            ] {
+                let path = base.join(operand);
+                let expected = expected.map(|p| base.join(p));
+                let single = canonicalize(&path, existence, Resolve::Physical).ok();
+                assert_eq!(single, expected, "{operand} {existence:?}");
+                let cached = batch.canonicalize(&path, existence, Resolve::Physical).ok();
+                assert_eq!(cached, expected, "{operand} {existence:?} (cached)");
+            }
+        }
+    }
+
+    #[test]
+    fn test_no_symlinks_keeps_links() {
+        let dir = tempfile::tempdir().unwrap();
+        let base = std::fs::canonicalize(dir.path()).unwrap();
+        std::fs::create_dir_all(base.join("a/b")).unwrap();
+        std::os::unix::fs::symlink("a/b", base.join("link")).unwrap();
+
+        let nested = base.join("link/./x/");
+        for existence in [Existence::AllButLast, Existence::Missing] {
+            assert_eq!(
+                canonicalize(&nested, existence, Resolve::NoSymlinks).unwrap(),
+                base.join("link/x")
+            );
+        }
+        assert!(canonicalize(&nested, Existence::Existing, Resolve::NoSymlinks).is_err());
+        assert_eq!(
+            canonicalize(
+                &base.join("link/.."),
+                Existence::Existing,
+                Resolve::NoSymlinks
+            )
+            .unwrap(),
+            base
+        );
+        let err = canonicalize(
+            &base.join("gone/x/../y"),
+            Existence::AllButLast,
+            Resolve::NoSymlinks,
+        )
+        .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::NotFound);
+    }
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod batch_tests {
+    use super::*;
+    use std::os::unix::fs::symlink;
+
+    /// A three-level tree of a/b/c directories, each holding a file `f` and
+    /// symlinks of every kind: to directories, files, parents, absolute
+    /// targets, missing targets and loops.
+    fn build_tree(base: &Path, dir: &Path, depth: usize) {
+        std::fs::write(dir.join("f"), "").unwrap();
+        let links = [
+            ("l_dir", "a".to_string()),
+            ("l_file", "f".to_string()),
+            ("l_up", "..".to_string()),
+            ("l_upup", "../..".to_string()),
+            ("l_chain", "l_dir/l_up".to_string()),
+            ("l_dangling", "nope".to_string()),
+            ("l_dangling_dir", "nope/x".to_string()),
+            ("l_loop", "l_loop".to_string()),
+            ("l_abs", base.join("b").to_string_lossy().into_owned()),
+            (
+                "l_abs_missing",
+                base.join("zz/y").to_string_lossy().into_owned(),
+            ),
+        ];
+        for (name, target) in links {
+            symlink(target, dir.join(name)).unwrap();
+        }
+        if depth < 2 {
+            for d in ["a", "b", "c"] {
+                std::fs::create_dir(dir.join(d)).unwrap();
+                build_tree(base, &dir.join(d), depth + 1);
+            }
+        }
+    }
+
+    #[test]
+    fn test_batch_matches_portable() {
+        let dir = tempfile::tempdir().unwrap();
+        let base = std::fs::canonicalize(dir.path()).unwrap().join("t");
+        std::fs::create_dir(&base).unwrap();
+        build_tree(&base, &base, 0);
+
+        const NAMES: &[&str] = &[
+            "a",
+            "b",
+            "c",
+            "a",
+            "b",
+            "f",
+            "l_dir",
+            "l_file",
+            "l_up",
+            "l_upup",
+            "l_chain",
+            "l_dangling",
+            "l_dangling_dir",
+            "l_loop",
+            "l_abs",
+            "l_abs_missing",
+            "missing",
+            "..",
+            ".",
+        ];
+        let mut state = 0x2545_f491_4f6c_dd1du64;
+        let mut next = |n: usize| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            (state % n as u64) as usize
+        };
+
+        let mut batch = Canonicalizer::new();
+        let mut walked = 0;
+        for _ in 0..3000 {
+            let mut path = base.clone();
+            for _ in 0..1 + next(6) {
+                path.push(NAMES[next(NAMES.len())]);
+            }
+            for existence in [
+                Existence::Existing,
+                Existence::AllButLast,
+                Existence::Missing,
+            ] {
+                walked += batch.walk(&path, existence).is_some() as usize;
+                let fast = batch.canonicalize(&path, existence, Resolve::Physical);
+                let slow = canonicalize(&path, existence, Resolve::Physical);
+                assert_eq!(
+                    fast.as_ref().map_err(|e| e.raw_os_error()),
+                    slow.as_ref().map_err(|e| e.raw_os_error()),
+                    "{:?} {:?}",
+                    path,
+                    existence
+                );
+            }
+        }
+        // Errors always come from the fallback; make sure the walk was tested
+        assert!(walked > 2000, "fast path used {} times", walked);
+    }
+
+    #[test]
+    fn test_batch_drops_replaced_directory() {
+        let dir = tempfile::tempdir().unwrap();
+        let base = std::fs::canonicalize(dir.path()).unwrap();
+        std::fs::create_dir_all(base.join("a/b")).unwrap();
+        std::fs::write(base.join("a/b/x"), "").unwrap();
+
+        let mut batch = Canonicalizer::new();
+        let path = base.join("a/b/x");
+        let got = batch.canonicalize(&path, Existence::Existing, Resolve::Physical);
+        assert_eq!(got.unwrap(), path);
+
+        // Replace a/b with a directory where x is a symlink elsewhere
+        std::fs::rename(base.join("a/b"), base.join("a/old")).unwrap();
+        std::fs::create_dir(base.join("a/b")).unwrap();
+        std::fs::create_dir(base.join("y")).unwrap();
+        symlink("../../y", base.join("a/b/x")).unwrap();
+        let got = batch.canonicalize(&path, Existence::Existing, Resolve::Physical);
+        assert_eq!(got.unwrap(), base.join("y"));
+    }
+}
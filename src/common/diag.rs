@@ -0,0 +1,288 @@
+use std::borrow::Cow;
+use std::ffi::OsStr;
+use std::fmt::{Display, Write as _};
+
+// ── GNU-shaped diagnostics ──────────────────────────────────────────────
+// Every tool reports problems as "tool: context: message", where the
+// context (usually a file name) is quoted the way GNU's quotef() does in
+// a UTF-8 locale: left alone when it is shell-safe, single-quoted when it
+// holds shell metacharacters, and with control characters and invalid
+// UTF-8 written as $'\ooo' escapes, so a name can never break the line
+// structure. Valid multibyte characters are printable and stay as they are.
+
+/// Quote `name` for use as a diagnostic context, like GNU's quotef().
+pub fn quote(name: &OsStr) -> Cow<'_, str> {
+    let bytes = name.as_encoded_bytes();
+    if let Some(s) = name.to_str()
+        && !s.is_empty()
+        && s.char_indices().all(|(i, c)| !needs_quote(c, i == 0))
+    {
+        return Cow::Borrowed(s);
+    }
+
+    let has_escape = bytes
+        .utf8_chunks()
+        .any(|chunk| !chunk.invalid().is_empty() || chunk.valid().chars().any(needs_escape));
+    if !has_escape && bytes.contains(&b'\'') && !bytes.iter().any(|b| b"\"$`\\!".contains(b)) {
+        // "it's" reads better than 'it'\''s'
+        let mut out = String::with_capacity(bytes.len() + 2);
+        out.push('"');
+        out.push_str(&String::from_utf8_lossy(bytes));
+        out.push('"');
+        return Cow::Owned(out);
+    }
+
+    let mut out = String::with_capacity(bytes.len() + 2);
+    out.push('\'');
+    let mut in_escape = false;
+    for chunk in bytes.utf8_chunks() {
+        for c in chunk.valid().chars() {
+            if needs_escape(c) {
+                if !in_escape {
+                    out.push_str("'$'");
+                    in_escape = true;
+                }
+                let mut buf = [0; 4];
+                for &b in c.encode_utf8(&mut buf).as_bytes() {
+                    push_escape(&mut out, b);
+                }
+                continue;
+            }
+            if in_escape {
+                // Close the $'...' run and reopen a plain single-quoted run
+                out.push_str("''");
+                in_escape = false;
+            }
+            if c == '\'' {
+                out.push_str("'\\''");
+            } else {
+                out.push(c);
+            }
+        }
+        if !chunk.invalid().is_empty() {
+            if !in_escape {
+                out.push_str("'$'");
+                in_escape = true;
+            }
+            for &b in chunk.invalid() {
+                push_escape(&mut out, b);
+            }
+        }
+    }
+    out.push('\'');
+    Cow::Owned(out)
+}
+
+/// Append the $'...' escape for one byte.
+fn push_escape(out: &mut String, b: u8) {
+    match b {
+        b'\x07' => out.push_str("\\a"),
+        b'\x08' => out.push_str("\\b"),
+        b'\x0c' => out.push_str("\\f"),
+        b'\n' => out.push_str("\\n"),
+        b'\r' => out.push_str("\\r"),
+        b'\t' => out.push_str("\\t"),
+        b'\x0b' => out.push_str("\\v"),
+        _ => {
+            let _ = write!(out, "\\{:03o}", b);
+        }
+    }
+}
+
+/// Quote `name` like GNU's quoteaf(): as `quote`, but a shell-safe name
+/// is still wrapped in single quotes ("cannot open 'x' for reading").
+pub fn quote_always(name: &OsStr) -> Cow<'_, str> {
+    match quote(name) {
+        Cow::Borrowed(s) => Cow::Owned(format!("'{}'", s)),
+        quoted => quoted,
+    }
+}
+
+/// Characters that can only be written inside $'...': C0 controls, DEL
+/// and the C1 controls.
+fn needs_escape(c: char) -> bool {
+    c.is_control()
+}
+
+/// Characters that make quoting necessary. `#` and `~` only matter at the
+/// start; printable non-ASCII characters never do.
+fn needs_quote(c: char, first: bool) -> bool {
+    match c {
+        'a'..='z' | 'A'..='Z' | '0'..='9' => false,
+        '%' | '+' | ',' | '-' | '.' | '/' | '@' | '_' | ']' | '{' | '}' => false,
+        '#' | '~' => first,
+        c if !c.is_ascii() => needs_escape(c),
+        _ => true,
+    }
+}
+
+/// Format "tool: context: message" (or "tool: message" without a context).
+pub fn format_error(tool: &str, context: Option<&OsStr>, err: &dyn Display) -> String {
+    match context {
+        Some(ctx) => format!("{}: {}: {}", tool, quote(ctx), err),
+        None => format!("{}: {}", tool, err),
+    }
+}
+
+/// Format "tool: warning: context: message".
+pub fn format_warning(tool: &str, context: Option<&OsStr>, err: &dyn Display) -> String {
+    match context {
+        Some(ctx) => format!("{}: warning: {}: {}", tool, quote(ctx), err),
+        None => format!("{}: warning: {}", tool, err),
+    }
+}
+
+/// Print an error line to stderr.
+#[cold]
+pub fn error(tool: &str, context: Option<&OsStr>, err: &dyn Display) {
+    eprintln!("{}", format_error(tool, context, err));
+}
+
+/// Print a warning line to stderr.
+#[cold]
+pub fn warning(tool: &str, context: Option<&OsStr>, err: &dyn Display) {
+    eprintln!("{}", format_warning(tool, context, err));
+}
+
+/// Diagnostics for one tool run: prints GNU-shaped lines and remembers
+/// whether any error was reported, so the exit status can be derived from
+/// it instead of threading a flag through every function.
+pub struct Diag {
+    tool: &'static str,
+    had_error: bool,
+}
+
+impl Diag {
+    pub fn new(tool: &'static str) -> Self {
+        Diag {
+            tool,
+            had_error: false,
+        }
+    }
+
+    /// Report an error and mark the run as failed.
+    pub fn error(&mut self, context: Option<&OsStr>, err: &dyn Display) {
+        self.had_error = true;
+        error(self.tool, context, err);
+    }
+
+    /// Report an I/O error without Rust's " (os error N)" suffix.
+    pub fn io_error(&mut self, context: Option<&OsStr>, err: &std::io::Error) {
+        self.error(context, &super::io_error_msg(err));
+    }
+
+    /// Mark the run as failed without printing anything (e.g. under --quiet).
+    pub fn fail(&mut self) {
+        self.had_error = true;
+    }
+
+    /// Report a warning; the exit status is unaffected.
+    pub fn warning(&self, context: Option<&OsStr>, err: &dyn Display) {
+        warning(self.tool, context, err);
+    }
+
+    pub fn had_error(&self) -> bool {
+        self.had_error
+    }
+
+    /// 1 if any error was reported, else 0.
+    pub fn exit_code(&self) -> i32 {
+        i32::from(self.had_error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn q(s: &str) -> String {
+        quote(OsStr::new(s)).into_owned()
+    }
+
+    #[test]
+    fn test_quote_matches_gnu() {
+        // Expected strings taken from GNU cat 9.1 diagnostics in the C locale
+        assert_eq!(q("file.txt"), "file.txt");
+        assert_eq!(q("a-b"), "a-b");
+        assert_eq!(q("x~"), "x~");
+        assert_eq!(q("a#"), "a#");
+        assert_eq!(q("a{"), "a{");
+        assert_eq!(q("a b"), "'a b'");
+        assert_eq!(q("~x"), "'~x'");
+        assert_eq!(q("#a"), "'#a'");
+        assert_eq!(q("a:"), "'a:'");
+        assert_eq!(q("a=b"), "'a=b'");
+        assert_eq!(q("a*b"), "'a*b'");
+        assert_eq!(q(""), "''");
+        assert_eq!(q("it's"), "\"it's\"");
+        assert_eq!(q("a'b c"), "\"a'b c\"");
+        assert_eq!(q("x\"y"), "'x\"y'");
+    }
+
+    #[test]
+    fn test_quote_always() {
+        assert_eq!(quote_always(OsStr::new("file")), "'file'");
+        assert_eq!(quote_always(OsStr::new("a b")), "'a b'");
+        assert_eq!(quote_always(OsStr::new("it's")), "\"it's\"");
+    }
+
+    #[test]
+    fn test_quote_control_chars() {
+        assert_eq!(q("a\nb"), "'a'$'\\n''b'");
+        assert_eq!(q("t\tz"), "'t'$'\\t''z'");
+        assert_eq!(q("a\x01"), "'a'$'\\001'");
+        assert_eq!(q("a\x7f"), "'a'$'\\177'");
+        assert_eq!(q("é"), "é");
+        assert_eq!(q("café-missing"), "café-missing");
+        assert_eq!(q("a é"), "'a é'");
+        assert_eq!(q("é\n"), "'é'$'\\n'");
+    }
+
+    #[test]
+    fn test_format_error_newline_in_name() {
+        let line = format_error(
+            "tac",
+            Some(OsStr::new("bad\nname")),
+            &"No such file or directory",
+        );
+        assert_eq!(line, "tac: 'bad'$'\\n''name': No such file or directory");
+        assert!(!line.contains('\n'));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_quote_invalid_utf8() {
+        use std::os::unix::ffi::OsStrExt;
+        let name = OsStr::from_bytes(b"caf\xe9");
+        assert_eq!(quote(name), "'caf'$'\\351'");
+        let name = OsStr::from_bytes(b"\xff\xe9x");
+        assert_eq!(quote(name), "''$'\\377\\351''x'");
+    }
+
+    #[test]
+    fn test_format_error_and_warning() {
+        assert_eq!(
+            format_error("cksum", Some(OsStr::new("f")), &"Is a directory"),
+            "cksum: f: Is a directory"
+        );
+        assert_eq!(
+            format_error("tac", None, &"write error"),
+            "tac: write error"
+        );
+        assert_eq!(
+            format_warning("cut", Some(OsStr::new("my file")), &"line 3: invalid UTF-8"),
+            "cut: warning: 'my file': line 3: invalid UTF-8"
+        );
+    }
+
+    #[test]
+    fn test_diag_tracks_errors() {
+        let mut diag = Diag::new("tool");
+        diag.warning(None, &"just a warning");
+        assert!(!diag.had_error());
+        assert_eq!(diag.exit_code(), 0);
+        diag.fail();
+        assert!(diag.had_error());
+        assert_eq!(diag.exit_code(), 1);
+    }
+}
@@ -0,0 +1,65 @@
+// ── Backslash escapes in separator arguments ────────────────────────────
+// Options that take a separator (cut -d, tac -s) are awkward to pass a tab
+// or a NUL through shells without $'...' quoting. Behind an opt-in flag
+// these tools accept the C escapes below instead; anything else after a
+// backslash is an error rather than a silent literal.
+
+/// Decode the backslash escapes `\t \n \r \a \b \f \v \0 \\` in `arg`.
+/// Unescaped bytes pass through unchanged.
+pub fn decode_separator_escapes(arg: &[u8]) -> Result<Vec<u8>, String> {
+    let mut out = Vec::with_capacity(arg.len());
+    let mut bytes = arg.iter();
+    while let Some(&b) = bytes.next() {
+        if b != b'\\' {
+            out.push(b);
+            continue;
+        }
+        let decoded = match bytes.next() {
+            Some(b't') => b'\t',
+            Some(b'n') => b'\n',
+            Some(b'r') => b'\r',
+            Some(b'a') => b'\x07',
+            Some(b'b') => b'\x08',
+            Some(b'f') => b'\x0c',
+            Some(b'v') => b'\x0b',
+            Some(b'0') => b'\0',
+            Some(b'\\') => b'\\',
+            Some(&c) => {
+                return Err(format!(
+                    "invalid escape '\\{}'; valid escapes are \\t \\n \\r \\a \\b \\f \\v \\0 \\\\",
+                    c.escape_ascii()
+                ));
+            }
+            None => return Err("trailing backslash in separator".to_string()),
+        };
+        out.push(decoded);
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_separator_escapes() {
+        assert_eq!(decode_separator_escapes(b"\\t").unwrap(), b"\t");
+        assert_eq!(decode_separator_escapes(b"\\0").unwrap(), b"\0");
+        assert_eq!(decode_separator_escapes(b"a\\\\b").unwrap(), b"a\\b");
+        assert_eq!(decode_separator_escapes(b"\\r\\n").unwrap(), b"\r\n");
+        assert_eq!(decode_separator_escapes(b"plain").unwrap(), b"plain");
+    }
+
+    #[test]
+    fn test_decode_separator_escapes_rejects_unknown() {
+        assert!(
+            decode_separator_escapes(b"\\q")
+                .unwrap_err()
+                .starts_with("invalid escape '\\q'")
+        );
+        assert_eq!(
+            decode_separator_escapes(b"x\\").unwrap_err(),
+            "trailing backslash in separator"
+        );
+    }
+}
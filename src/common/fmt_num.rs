@@ -0,0 +1,99 @@
+// ── Number formatting for output hot paths ──────────────────────────────
+// Tools that print counts per line or per file (comm --total, cksum, cut
+// --count) append the digits straight into their output buffer. These
+// helpers go through itoa rather than the `fmt` machinery and never
+// allocate beyond growing the caller's buffer.
+
+/// Append the decimal digits of `v` to `buf`.
+#[inline]
+pub fn push_u64(buf: &mut Vec<u8>, v: u64) {
+    buf.extend_from_slice(itoa::Buffer::new().format(v).as_bytes());
+}
+
+/// Append `v` right-aligned in a field of `width` bytes, filled on the left
+/// with `pad` (b' ' for wc/sum-style columns, b'0' for zero fill). A number
+/// wider than the field is written in full.
+#[inline]
+pub fn push_u64_padded(buf: &mut Vec<u8>, v: u64, width: usize, pad: u8) {
+    let mut itoa_buf = itoa::Buffer::new();
+    let digits = itoa_buf.format(v).as_bytes();
+    buf.resize(buf.len() + width.saturating_sub(digits.len()), pad);
+    buf.extend_from_slice(digits);
+}
+
+/// Append `n` bytes in binary units with one decimal, e.g. "1.4GiB"; plain
+/// "512B" below a KiB. The tenths are rounded half up, moving to the next
+/// unit when that reaches 1024.
+pub fn push_human_bytes(buf: &mut Vec<u8>, n: u64) {
+    const UNITS: [&[u8]; 5] = [b"KiB", b"MiB", b"GiB", b"TiB", b"PiB"];
+    if n < 1024 {
+        push_u64(buf, n);
+        buf.push(b'B');
+        return;
+    }
+    let mut unit = 0;
+    let tenths = loop {
+        let scale = 1u128 << (10 * (unit + 1));
+        let tenths = (n as u128 * 10 + scale / 2) / scale;
+        if tenths < 10240 || unit + 1 == UNITS.len() {
+            break tenths as u64;
+        }
+        unit += 1;
+    };
+    push_u64(buf, tenths / 10);
+    buf.push(b'.');
+    buf.push(b'0' + (tenths % 10) as u8);
+    buf.extend_from_slice(UNITS[unit]);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn render(f: impl FnOnce(&mut Vec<u8>)) -> String {
+        let mut buf = b"x".to_vec();
+        f(&mut buf);
+        String::from_utf8(buf).unwrap()
+    }
+
+    #[test]
+    fn test_push_u64_bounds() {
+        assert_eq!(render(|b| push_u64(b, 0)), "x0");
+        assert_eq!(render(|b| push_u64(b, 9)), "x9");
+        assert_eq!(render(|b| push_u64(b, 10)), "x10");
+        assert_eq!(render(|b| push_u64(b, u64::MAX)), "x18446744073709551615");
+    }
+
+    #[test]
+    fn test_push_u64_padded() {
+        assert_eq!(render(|b| push_u64_padded(b, 42, 7, b' ')), "x     42");
+        assert_eq!(render(|b| push_u64_padded(b, 0, 3, b'0')), "x000");
+        assert_eq!(render(|b| push_u64_padded(b, 0, 0, b' ')), "x0");
+        // Narrower fields never truncate
+        assert_eq!(render(|b| push_u64_padded(b, 12345, 3, b' ')), "x12345");
+        assert_eq!(render(|b| push_u64_padded(b, 12345, 5, b' ')), "x12345");
+        assert_eq!(
+            render(|b| push_u64_padded(b, u64::MAX, 22, b' ')),
+            "x  18446744073709551615"
+        );
+    }
+
+    #[test]
+    fn test_push_human_bytes() {
+        let human = |n| render(|b| push_human_bytes(b, n));
+        assert_eq!(human(0), "x0B");
+        assert_eq!(human(1023), "x1023B");
+        assert_eq!(human(1024), "x1.0KiB");
+        assert_eq!(human(3 << 20), "x3.0MiB");
+        assert_eq!(human(1_503_238_554), "x1.4GiB");
+        // 1280 is exactly 1.25KiB: the half rounds up
+        assert_eq!(human(1280), "x1.3KiB");
+        assert_eq!(human(1382), "x1.3KiB");
+        assert_eq!(human(1383), "x1.4KiB");
+        // 1023.95KiB rounds to 1024.0KiB, which is 1.0MiB
+        assert_eq!(human((1 << 20) - 51), "x1.0MiB");
+        assert_eq!(human((1 << 20) - 52), "x1023.9KiB");
+        // PiB is the largest unit
+        assert_eq!(human(u64::MAX), "x16384.0PiB");
+    }
+}
@@ -0,0 +1,47 @@
+use std::io;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+// ── SIGINT as a stop request ───────────────────────────────────────────
+// Tools that buffer their output would lose finished results if Ctrl-C
+// killed them outright. Instead the handler only raises a flag (the one
+// async-signal-safe thing it can do); the tool polls it between units of
+// work, flushes what it has, and exits with 130 like GNU tools.
+
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+/// Exit status for a run stopped by SIGINT (128 + SIGINT).
+pub const EXIT_INTERRUPTED: i32 = 130;
+
+#[cfg(unix)]
+extern "C" fn on_sigint(_sig: libc::c_int) {
+    INTERRUPTED.store(true, Ordering::Relaxed);
+}
+
+/// Route SIGINT to the stop flag instead of the default termination.
+/// A no-op where there are no POSIX signals.
+pub fn install_sigint_handler() {
+    #[cfg(unix)]
+    unsafe {
+        let mut action: libc::sigaction = std::mem::zeroed();
+        action.sa_sigaction = on_sigint as *const () as libc::sighandler_t;
+        action.sa_flags = libc::SA_RESTART;
+        libc::sigemptyset(&mut action.sa_mask);
+        libc::sigaction(libc::SIGINT, &action, std::ptr::null_mut());
+    }
+}
+
+/// Whether SIGINT has arrived since the handler was installed.
+#[inline]
+pub fn interrupted() -> bool {
+    INTERRUPTED.load(Ordering::Relaxed)
+}
+
+/// `Err(Interrupted)` once SIGINT has arrived, for long loops to bail out
+/// with `?`. Always `Ok` in tools that never install the handler.
+#[inline]
+pub fn check() -> io::Result<()> {
+    if interrupted() {
+        return Err(io::Error::new(io::ErrorKind::Interrupted, "interrupted"));
+    }
+    Ok(())
+}
@@ -1,5 +1,5 @@
 use std::fs::{self, File};
-use std::io::{self, Read};
+use std::io::{self, IoSlice, Read, Write};
 use std::ops::Deref;
 use std::path::Path;
 
@@ -441,19 +441,373 @@ fn read_stdin_generic() -> io::Result<Vec<u8>> {
 #[inline]
 fn read_full(reader: &mut impl Read, buf: &mut [u8]) -> io::Result<usize> {
     // Fast path: first read() usually fills the entire buffer for regular files
-    let n = reader.read(buf)?;
+    let n = read_retry(reader, buf)?;
     if n == buf.len() || n == 0 {
         return Ok(n);
     }
     // Slow path: partial read — retry to fill buffer (pipes, slow devices)
     let mut total = n;
     while total < buf.len() {
-        match reader.read(&mut buf[total..]) {
-            Ok(0) => break,
-            Ok(n) => total += n,
-            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
-            Err(e) => return Err(e),
+        match read_retry(reader, &mut buf[total..])? {
+            0 => break,
+            n => total += n,
         }
     }
     Ok(total)
 }
+
+// ── Retrying on EINTR ──────────────────────────────────────────────────
+// A signal that arrives during a read or write on a raw fd can fail it with
+// EINTR even though nothing went wrong. Once a tool installs a handler,
+// any call site that treats `Interrupted` as fatal becomes a spurious
+// failure. These helpers retry it. The exception is a pending SIGINT stop
+// request (see `interrupt`), which must still reach the tool.
+
+/// Whether a call that failed with `e` should simply be made again:
+/// `Interrupted`, unless SIGINT has asked the tool to stop.
+#[inline]
+pub fn should_retry(e: &io::Error) -> bool {
+    e.kind() == io::ErrorKind::Interrupted && !super::interrupt::interrupted()
+}
+
+/// One `read`, made again for as long as it fails with EINTR.
+#[inline]
+pub fn read_retry(r: &mut (impl Read + ?Sized), buf: &mut [u8]) -> io::Result<usize> {
+    loop {
+        match r.read(buf) {
+            Err(e) if should_retry(&e) => {}
+            result => return result,
+        }
+    }
+}
+
+/// `write_all`, retrying EINTR but giving way to a SIGINT stop request.
+pub fn write_all_retry(w: &mut (impl Write + ?Sized), mut buf: &[u8]) -> io::Result<()> {
+    while !buf.is_empty() {
+        match w.write(buf) {
+            Ok(0) => {
+                return Err(io::Error::new(
+                    io::ErrorKind::WriteZero,
+                    "failed to write whole buffer",
+                ));
+            }
+            Ok(n) => buf = &buf[n..],
+            Err(e) if should_retry(&e) => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(())
+}
+
+/// A writer that makes each call again while it fails with EINTR, for
+/// raw-fd writers whose callers would otherwise see it as an error.
+pub struct RetryWriter<W>(pub W);
+
+impl<W: Write> Write for RetryWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        loop {
+            match self.0.write(buf) {
+                Err(e) if should_retry(&e) => {}
+                result => return result,
+            }
+        }
+    }
+
+    fn write_vectored(&mut self, bufs: &[IoSlice<'_>]) -> io::Result<usize> {
+        loop {
+            match self.0.write_vectored(bufs) {
+                Err(e) if should_retry(&e) => {}
+                result => return result,
+            }
+        }
+    }
+
+    fn write_all(&mut self, buf: &[u8]) -> io::Result<()> {
+        write_all_retry(self, buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        loop {
+            match self.0.flush() {
+                Err(e) if should_retry(&e) => {}
+                result => return result,
+            }
+        }
+    }
+}
+
+/// Cap on iovec entries per `write_vectored` call used by
+/// `write_all_vectored` (Linux's IOV_MAX; writev fails with EINVAL above it).
+pub const MAX_IOV: usize = 1024;
+
+/// Write every byte of `bufs`, in order, with vectored writes.
+///
+/// `write_vectored` may stop anywhere — even inside the first slice — and
+/// the OS caps the number of slices per call; this loops until everything
+/// is written, resuming mid-slice, retrying on `Interrupted` and passing at
+/// most `MAX_IOV` slices per call. `bufs` is left in an unspecified state.
+pub fn write_all_vectored(w: &mut impl Write, bufs: &mut [IoSlice<'_>]) -> io::Result<()> {
+    write_all_vectored_max(w, bufs, MAX_IOV)
+}
+
+/// `write_all_vectored` with an explicit cap on slices per call.
+pub fn write_all_vectored_max(
+    w: &mut impl Write,
+    mut bufs: &mut [IoSlice<'_>],
+    max_iov: usize,
+) -> io::Result<()> {
+    let max_iov = max_iov.max(1);
+    // Drop leading empty slices so a call never sees only empty ones
+    IoSlice::advance_slices(&mut bufs, 0);
+    while !bufs.is_empty() {
+        let n = bufs.len().min(max_iov);
+        match w.write_vectored(&bufs[..n]) {
+            Ok(0) => {
+                return Err(io::Error::new(
+                    io::ErrorKind::WriteZero,
+                    "failed to write whole buffer",
+                ));
+            }
+            Ok(written) => IoSlice::advance_slices(&mut bufs, written),
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A writer that takes a small pseudo-random number of bytes per call,
+    /// spread across the slices like writev, and sometimes fails with
+    /// `Interrupted` without writing anything.
+    struct Trickle {
+        out: Vec<u8>,
+        seed: u64,
+        max_iov: usize,
+    }
+
+    impl Trickle {
+        fn new(seed: u64, max_iov: usize) -> Self {
+            Trickle {
+                out: Vec::new(),
+                seed: seed.max(1),
+                max_iov,
+            }
+        }
+
+        fn next(&mut self) -> u64 {
+            self.seed ^= self.seed << 13;
+            self.seed ^= self.seed >> 7;
+            self.seed ^= self.seed << 17;
+            self.seed
+        }
+    }
+
+    impl Write for Trickle {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.write_vectored(&[IoSlice::new(buf)])
+        }
+
+        fn write_vectored(&mut self, bufs: &[IoSlice<'_>]) -> io::Result<usize> {
+            assert!(bufs.len() <= self.max_iov, "{} slices", bufs.len());
+            let r = self.next();
+            if r.is_multiple_of(5) {
+                return Err(io::Error::from(io::ErrorKind::Interrupted));
+            }
+            let mut budget = 1 + (r >> 8) as usize % 7;
+            let mut written = 0;
+            for buf in bufs {
+                let take = budget.min(buf.len());
+                self.out.extend_from_slice(&buf[..take]);
+                written += take;
+                budget -= take;
+                if budget == 0 {
+                    break;
+                }
+            }
+            Ok(written)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    /// Only implements `write`, so `write_vectored` falls back to the
+    /// default of writing (part of) the first non-empty slice.
+    struct PlainWriter(Vec<u8>);
+
+    impl Write for PlainWriter {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            let n = buf.len().min(3);
+            self.0.extend_from_slice(&buf[..n]);
+            Ok(n)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_write_all_vectored_randomized_layouts() {
+        let data: Vec<u8> = (0..4096u32).map(|i| (i * 31 % 251) as u8).collect();
+        for seed in 1..=400u64 {
+            let mut layout = Trickle::new(seed * 7919, usize::MAX);
+            // Cut `data` into random pieces, including empty ones
+            let len = (layout.next() % 600) as usize;
+            let mut pieces = Vec::new();
+            let mut pos = 0;
+            while pos < len {
+                let r = layout.next();
+                let piece = if r.is_multiple_of(4) {
+                    0
+                } else {
+                    (r >> 8) as usize % 40
+                };
+                let end = (pos + piece).min(len);
+                pieces.push(&data[pos..end]);
+                pos = end;
+            }
+            let max_iov = 1 + (seed as usize % 9);
+            let mut bufs: Vec<IoSlice> = pieces.iter().map(|p| IoSlice::new(p)).collect();
+            let mut w = Trickle::new(seed, max_iov);
+            write_all_vectored_max(&mut w, &mut bufs, max_iov).unwrap();
+            assert_eq!(w.out, &data[..len], "seed {}", seed);
+
+            let mut bufs: Vec<IoSlice> = pieces.iter().map(|p| IoSlice::new(p)).collect();
+            let mut plain = PlainWriter(Vec::new());
+            write_all_vectored(&mut plain, &mut bufs).unwrap();
+            assert_eq!(plain.0, &data[..len], "seed {}", seed);
+        }
+    }
+
+    #[test]
+    fn test_write_all_vectored_splits_at_max_iov() {
+        let pieces: Vec<[u8; 1]> = (0..=255u8).map(|b| [b]).collect();
+        let mut bufs: Vec<IoSlice> = pieces.iter().map(|p| IoSlice::new(p)).collect();
+        let mut out = Vec::new();
+        // Vec<u8> takes everything it is given, so calls = ceil(256 / 10)
+        struct Counting<'a>(&'a mut Vec<u8>, usize);
+        impl Write for Counting<'_> {
+            fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+                self.0.write(buf)
+            }
+            fn write_vectored(&mut self, bufs: &[IoSlice<'_>]) -> io::Result<usize> {
+                assert!(bufs.len() <= 10);
+                self.1 += 1;
+                self.0.write_vectored(bufs)
+            }
+            fn flush(&mut self) -> io::Result<()> {
+                Ok(())
+            }
+        }
+        let mut w = Counting(&mut out, 0);
+        write_all_vectored_max(&mut w, &mut bufs, 10).unwrap();
+        assert_eq!(w.1, 26);
+        assert_eq!(out, (0..=255u8).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_write_all_vectored_empty_and_write_zero() {
+        let mut out = Vec::new();
+        write_all_vectored(&mut out, &mut []).unwrap();
+        let mut only_empty = [IoSlice::new(b""), IoSlice::new(b"")];
+        write_all_vectored(&mut out, &mut only_empty).unwrap();
+        assert!(out.is_empty());
+
+        let mut full = [0u8; 2];
+        let mut bufs = [IoSlice::new(b"abc")];
+        let err = write_all_vectored(&mut &mut full[..], &mut bufs).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::WriteZero);
+    }
+
+    /// A reader that hands out a small pseudo-random number of bytes per
+    /// call and sometimes fails with `Interrupted` without reading.
+    struct Dribble<'a> {
+        data: &'a [u8],
+        rng: Trickle,
+    }
+
+    impl Read for Dribble<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let r = self.rng.next();
+            if r.is_multiple_of(3) {
+                return Err(io::Error::from(io::ErrorKind::Interrupted));
+            }
+            let n = (1 + (r >> 8) as usize % 11)
+                .min(buf.len())
+                .min(self.data.len());
+            buf[..n].copy_from_slice(&self.data[..n]);
+            self.data = &self.data[n..];
+            Ok(n)
+        }
+    }
+
+    #[test]
+    fn test_retry_writer_hides_interrupts() {
+        let data: Vec<u8> = (0..3000u32).map(|i| (i * 17 % 253) as u8).collect();
+        for seed in 1..=200u64 {
+            // Single calls never surface EINTR, and nothing is lost or repeated
+            let mut w = RetryWriter(Trickle::new(seed, usize::MAX));
+            let mut rest = &data[..];
+            while !rest.is_empty() {
+                let n = w.write(rest).unwrap();
+                rest = &rest[n..];
+            }
+            let mut bufs = [IoSlice::new(&data[..10]), IoSlice::new(&data[10..])];
+            let n = w.write_vectored(&bufs).unwrap();
+            IoSlice::advance_slices(&mut &mut bufs[..], n);
+            w.flush().unwrap();
+            assert_eq!(&w.0.out[..data.len()], &data[..], "seed {}", seed);
+            assert_eq!(&w.0.out[data.len()..], &data[..n], "seed {}", seed);
+
+            let mut w = RetryWriter(Trickle::new(seed, usize::MAX));
+            w.write_all(&data).unwrap();
+            assert_eq!(w.0.out, data, "seed {}", seed);
+
+            let mut t = Trickle::new(seed, usize::MAX);
+            write_all_retry(&mut t, &data).unwrap();
+            assert_eq!(t.out, data, "seed {}", seed);
+        }
+    }
+
+    #[test]
+    fn test_read_retry_hides_interrupts() {
+        let data: Vec<u8> = (0..3000u32).map(|i| (i * 13 % 251) as u8).collect();
+        for seed in 1..=200u64 {
+            let mut r = Dribble {
+                data: &data,
+                rng: Trickle::new(seed, usize::MAX),
+            };
+            let mut got = Vec::new();
+            let mut buf = [0u8; 64];
+            loop {
+                match read_retry(&mut r, &mut buf).unwrap() {
+                    0 => break,
+                    n => got.extend_from_slice(&buf[..n]),
+                }
+            }
+            assert_eq!(got, data, "seed {}", seed);
+
+            // read_full fills whole buffers even when its first read is interrupted
+            let mut r = Dribble {
+                data: &data,
+                rng: Trickle::new(seed, usize::MAX),
+            };
+            let mut got = Vec::new();
+            let mut buf = [0u8; 256];
+            loop {
+                let n = read_full(&mut r, &mut buf).unwrap();
+                got.extend_from_slice(&buf[..n]);
+                if n < buf.len() {
+                    break;
+                }
+            }
+            assert_eq!(got, data, "seed {}", seed);
+        }
+    }
+}
@@ -1,4 +1,16 @@
+#[cfg(test)]
+pub(crate) mod alloc_count;
+pub mod canonical;
+pub mod diag;
+pub mod escape;
+pub mod fmt_num;
+pub mod interrupt;
 pub mod io;
+pub mod ordered;
+pub mod perf;
+pub mod sigbus;
+pub mod userinfo;
+pub mod version;
 
 /// Get the GNU-compatible tool name by stripping the 'f' prefix.
 /// e.g., "fmd5sum" -> "md5sum", "fcut" -> "cut"
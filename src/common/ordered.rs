@@ -0,0 +1,510 @@
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+use std::convert::Infallible;
+use std::sync::atomic::{AtomicUsize, Ordering as AtomicOrdering};
+use std::sync::{Condvar, Mutex, mpsc};
+use std::time::Duration;
+
+// ── Sequenced output for parallel per-file work ────────────────────────
+// Workers finish in any order, but output (stdout lines and stderr
+// diagnostics alike) must appear in argument order. `OrderedSink` emits a
+// result as soon as every earlier index has been emitted, holding only the
+// out-of-order completions in a min-heap, so memory stays proportional to
+// how far the workers run ahead rather than to the number of inputs.
+
+/// A completed item waiting for its turn; ordered so the heap top is the
+/// smallest index.
+struct Pending<T> {
+    index: usize,
+    item: T,
+}
+
+impl<T> PartialEq for Pending<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.index == other.index
+    }
+}
+
+impl<T> Eq for Pending<T> {}
+
+impl<T> PartialOrd for Pending<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T> Ord for Pending<T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.index.cmp(&self.index)
+    }
+}
+
+/// Reorders results completed out of order back into index order.
+pub struct OrderedSink<T> {
+    next: usize,
+    pending: BinaryHeap<Pending<T>>,
+}
+
+impl<T> Default for OrderedSink<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> OrderedSink<T> {
+    pub fn new() -> Self {
+        OrderedSink {
+            next: 0,
+            pending: BinaryHeap::new(),
+        }
+    }
+
+    /// Accept the result for `index` and pass every item that is now in
+    /// sequence to `emit`, in order. Each index must be pushed exactly once.
+    pub fn push(&mut self, index: usize, item: T, mut emit: impl FnMut(usize, T)) {
+        debug_assert!(index >= self.next, "index {} already emitted", index);
+        if index != self.next {
+            self.pending.push(Pending { index, item });
+            return;
+        }
+        emit(index, item);
+        self.next += 1;
+        while self.pending.peek().is_some_and(|p| p.index == self.next) {
+            let Pending { index, item } = self.pending.pop().unwrap();
+            emit(index, item);
+            self.next += 1;
+        }
+    }
+
+    /// Index of the next item to be emitted (= number emitted so far).
+    pub fn next_index(&self) -> usize {
+        self.next
+    }
+
+    /// Number of completed items held back waiting for an earlier one.
+    pub fn pending(&self) -> usize {
+        self.pending.len()
+    }
+}
+
+/// Run `work` on every item using up to `threads` scoped worker threads and
+/// hand the results to `emit` on the calling thread, strictly in item order.
+/// Workers claim items through an atomic counter and send completions over a
+/// channel; with one thread or one item everything runs inline.
+pub fn map_ordered<I, T>(
+    items: &[I],
+    threads: usize,
+    work: impl Fn(&I) -> T + Sync,
+    mut emit: impl FnMut(usize, T),
+) where
+    I: Sync,
+    T: Send,
+{
+    let threads = threads.min(items.len());
+    if threads <= 1 {
+        for (i, item) in items.iter().enumerate() {
+            emit(i, work(item));
+        }
+        return;
+    }
+
+    let next_item = AtomicUsize::new(0);
+    let (tx, rx) = mpsc::channel();
+    std::thread::scope(|s| {
+        for _ in 0..threads {
+            let tx = tx.clone();
+            let (next_item, work) = (&next_item, &work);
+            s.spawn(move || {
+                loop {
+                    let i = next_item.fetch_add(1, AtomicOrdering::Relaxed);
+                    if i >= items.len() {
+                        break;
+                    }
+                    if tx.send((i, work(&items[i]))).is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+        // Only the workers hold senders now, so the loop ends when they do
+        drop(tx);
+
+        let mut sink = OrderedSink::new();
+        for (i, result) in rx {
+            sink.push(i, result, &mut emit);
+        }
+        debug_assert_eq!(sink.next_index(), items.len());
+    });
+}
+
+/// `map_ordered` for large inputs cut into many items, where `emit` can
+/// fail. A worker claims an item only once it is fewer than `window` items
+/// ahead of the next one to be emitted, so however slow `emit` is, at most
+/// `window` results are held at once. The first error from `emit` stops the
+/// workers claiming new items and is returned once they have finished.
+pub fn try_map_ordered_bounded<I, T, E>(
+    items: &[I],
+    threads: usize,
+    window: usize,
+    work: impl Fn(&I) -> T + Sync,
+    mut emit: impl FnMut(usize, T) -> Result<(), E>,
+) -> Result<(), E>
+where
+    I: Sync,
+    T: Send,
+{
+    let threads = threads.min(items.len());
+    if threads <= 1 {
+        for (i, item) in items.iter().enumerate() {
+            emit(i, work(item))?;
+        }
+        return Ok(());
+    }
+
+    let window = window.max(1);
+    let next_item = AtomicUsize::new(0);
+    // (items emitted, stopped), with workers waiting on the condvar while
+    // they are a full window ahead
+    let progress = (Mutex::new((0usize, false)), Condvar::new());
+    let (tx, rx) = mpsc::channel();
+    std::thread::scope(|s| {
+        for _ in 0..threads {
+            let tx = tx.clone();
+            let (next_item, work, progress) = (&next_item, &work, &progress);
+            s.spawn(move || {
+                loop {
+                    let i = next_item.fetch_add(1, AtomicOrdering::Relaxed);
+                    if i >= items.len() {
+                        break;
+                    }
+                    let (lock, ready) = progress;
+                    let mut state = lock.lock().unwrap_or_else(|e| e.into_inner());
+                    while !state.1 && i >= state.0 + window {
+                        state = ready.wait(state).unwrap_or_else(|e| e.into_inner());
+                    }
+                    if state.1 {
+                        break;
+                    }
+                    drop(state);
+                    if tx.send((i, work(&items[i]))).is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+        drop(tx);
+
+        let (lock, ready) = &progress;
+        let mut sink = OrderedSink::new();
+        let mut result = Ok(());
+        for (i, item) in rx.iter() {
+            sink.push(i, item, |i, item| {
+                if result.is_ok() {
+                    result = emit(i, item);
+                }
+            });
+            let mut state = lock.lock().unwrap_or_else(|e| e.into_inner());
+            *state = (sink.next_index(), result.is_err());
+            ready.notify_all();
+            if result.is_err() {
+                break;
+            }
+        }
+        result
+    })
+}
+
+/// How often `map_ordered_until` looks at its stop condition while waiting
+/// for a result.
+const STOP_POLL: Duration = Duration::from_millis(50);
+
+/// `map_ordered` for runs that can be told to stop (SIGINT). Work always
+/// runs on worker threads, at least one, so the calling thread keeps
+/// polling `stop` even while a worker is blocked in a read. Once `stop`
+/// holds, workers claim no new items and drop the results they finish;
+/// results already delivered are emitted in order up to the first gap and
+/// `on_stop` ends the run. It cannot return: a blocked worker would keep
+/// the thread scope open forever, so it is expected to exit the process.
+/// `state` is handed to both callbacks, typically the output writer.
+pub fn map_ordered_until<I, T, S>(
+    items: &[I],
+    threads: usize,
+    state: &mut S,
+    stop: impl Fn() -> bool + Sync,
+    work: impl Fn(&I) -> T + Sync,
+    mut emit: impl FnMut(&mut S, usize, T),
+    on_stop: impl FnOnce(&mut S) -> Infallible,
+) where
+    I: Sync,
+    T: Send,
+{
+    let threads = threads.clamp(1, items.len().max(1));
+    let next_item = AtomicUsize::new(0);
+    let (tx, rx) = mpsc::channel();
+    std::thread::scope(|s| {
+        for _ in 0..threads {
+            let tx = tx.clone();
+            let (next_item, work, stop) = (&next_item, &work, &stop);
+            s.spawn(move || {
+                while !stop() {
+                    let i = next_item.fetch_add(1, AtomicOrdering::Relaxed);
+                    if i >= items.len() {
+                        break;
+                    }
+                    let result = work(&items[i]);
+                    // An item cut short by the stop is not a result
+                    if stop() || tx.send((i, result)).is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+        drop(tx);
+
+        let mut sink = OrderedSink::new();
+        loop {
+            if stop() {
+                for (i, result) in rx.try_iter() {
+                    sink.push(i, result, |i, r| emit(state, i, r));
+                }
+                match on_stop(state) {}
+            }
+            match rx.recv_timeout(STOP_POLL) {
+                Ok((i, result)) => sink.push(i, result, |i, r| emit(state, i, r)),
+                Err(mpsc::RecvTimeoutError::Timeout) => {}
+                Err(mpsc::RecvTimeoutError::Disconnected) => break,
+            }
+        }
+        debug_assert_eq!(sink.next_index(), items.len());
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Deterministic Fisher-Yates shuffle driven by a xorshift generator.
+    fn shuffled(n: usize, mut seed: u64) -> Vec<usize> {
+        let mut order: Vec<usize> = (0..n).collect();
+        for i in (1..n).rev() {
+            seed ^= seed << 13;
+            seed ^= seed >> 7;
+            seed ^= seed << 17;
+            order.swap(i, (seed % (i as u64 + 1)) as usize);
+        }
+        order
+    }
+
+    #[test]
+    fn test_sink_in_order_passes_through() {
+        let mut sink = OrderedSink::new();
+        let mut out = Vec::new();
+        for i in 0..5 {
+            sink.push(i, i * 10, |_, v| out.push(v));
+            assert_eq!(sink.pending(), 0);
+        }
+        assert_eq!(out, [0, 10, 20, 30, 40]);
+    }
+
+    #[test]
+    fn test_sink_holds_until_gap_filled() {
+        let mut sink = OrderedSink::new();
+        let mut out = Vec::new();
+        sink.push(2, "c", |i, v| out.push((i, v)));
+        sink.push(1, "b", |i, v| out.push((i, v)));
+        assert!(out.is_empty());
+        assert_eq!(sink.pending(), 2);
+        sink.push(0, "a", |i, v| out.push((i, v)));
+        assert_eq!(out, [(0, "a"), (1, "b"), (2, "c")]);
+        assert_eq!(sink.pending(), 0);
+        assert_eq!(sink.next_index(), 3);
+    }
+
+    #[test]
+    fn test_sink_shuffled_completion_orders() {
+        for seed in 1..=50u64 {
+            let n = 1 + (seed as usize * 7) % 40;
+            let mut sink = OrderedSink::new();
+            let mut out = Vec::new();
+            for i in shuffled(n, seed) {
+                sink.push(i, i, |idx, v| {
+                    assert_eq!(idx, v);
+                    out.push(v);
+                });
+            }
+            assert_eq!(out, (0..n).collect::<Vec<_>>(), "seed {}", seed);
+            assert_eq!(sink.pending(), 0);
+        }
+    }
+
+    #[test]
+    fn test_sink_reverse_order_buffers_everything() {
+        let mut sink = OrderedSink::new();
+        let mut out = Vec::new();
+        for i in (1..100).rev() {
+            sink.push(i, i, |_, v| out.push(v));
+        }
+        assert_eq!(sink.pending(), 99);
+        sink.push(0, 0, |_, v| out.push(v));
+        assert_eq!(out, (0..100).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_map_ordered_stress() {
+        let items: Vec<usize> = (0..10_000).collect();
+        let mut out = Vec::with_capacity(items.len());
+        map_ordered(
+            &items,
+            8,
+            |&i| {
+                // Uneven work so completions interleave; a slow first item
+                // forces most of the others to wait in the heap
+                if i == 0 {
+                    std::thread::sleep(std::time::Duration::from_millis(20));
+                } else if i % 97 == 0 {
+                    std::thread::yield_now();
+                }
+                (i, format!("line {}", i))
+            },
+            |idx, (i, line)| {
+                assert_eq!(idx, i);
+                out.push(line);
+            },
+        );
+        assert_eq!(out.len(), 10_000);
+        for (i, line) in out.iter().enumerate() {
+            assert_eq!(*line, format!("line {}", i));
+        }
+    }
+
+    #[test]
+    fn test_map_ordered_single_thread_and_empty() {
+        let mut out = Vec::new();
+        map_ordered(&[3, 1, 2], 1, |&x| x * 2, |_, v| out.push(v));
+        assert_eq!(out, [6, 2, 4]);
+
+        let empty: [u8; 0] = [];
+        map_ordered(&empty, 4, |&x| x, |_, _| panic!("nothing to emit"));
+    }
+
+    #[test]
+    fn test_try_map_ordered_bounded_keeps_window() {
+        let items: Vec<usize> = (0..2000).collect();
+        let claimed_ahead = AtomicUsize::new(0);
+        let emitted = AtomicUsize::new(0);
+        let mut out = Vec::new();
+        let result: Result<(), ()> = try_map_ordered_bounded(
+            &items,
+            4,
+            8,
+            |&i| {
+                let ahead = i - emitted.load(AtomicOrdering::SeqCst);
+                claimed_ahead.fetch_max(ahead, AtomicOrdering::SeqCst);
+                if i % 50 == 0 {
+                    std::thread::sleep(Duration::from_millis(1));
+                }
+                i
+            },
+            |idx, v| {
+                assert_eq!(idx, v);
+                out.push(v);
+                emitted.store(idx + 1, AtomicOrdering::SeqCst);
+                Ok(())
+            },
+        );
+        assert!(result.is_ok());
+        assert_eq!(out, items);
+        assert!(claimed_ahead.load(AtomicOrdering::SeqCst) < 8);
+    }
+
+    #[test]
+    fn test_try_map_ordered_bounded_stops_on_error() {
+        let items: Vec<usize> = (0..10_000).collect();
+        let worked = AtomicUsize::new(0);
+        let mut out = Vec::new();
+        let result = try_map_ordered_bounded(
+            &items,
+            4,
+            8,
+            |&i| {
+                worked.fetch_add(1, AtomicOrdering::Relaxed);
+                i
+            },
+            |idx, v| {
+                if idx == 100 {
+                    return Err("full");
+                }
+                out.push(v);
+                Ok(())
+            },
+        );
+        assert_eq!(result, Err("full"));
+        assert_eq!(out, (0..100).collect::<Vec<_>>());
+        // Workers stopped within a window of the failure
+        assert!(worked.load(AtomicOrdering::Relaxed) < 100 + 8 + 4);
+
+        let mut out = Vec::new();
+        let result: Result<(), ()> = try_map_ordered_bounded(
+            &[1, 2, 3],
+            1,
+            2,
+            |&x| x * 2,
+            |_, v| {
+                out.push(v);
+                Ok(())
+            },
+        );
+        assert!(result.is_ok());
+        assert_eq!(out, [2, 4, 6]);
+    }
+
+    #[test]
+    fn test_map_ordered_until_runs_to_completion() {
+        let items: Vec<usize> = (0..500).collect();
+        let mut out = Vec::new();
+        map_ordered_until(
+            &items,
+            4,
+            &mut out,
+            || false,
+            |&i| i * 2,
+            |out, idx, v| {
+                assert_eq!(idx * 2, v);
+                out.push(v);
+            },
+            |_| unreachable!("never stopped"),
+        );
+        assert_eq!(out, (0..500).map(|i| i * 2).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_map_ordered_until_stops_and_drains_in_order() {
+        use std::sync::atomic::AtomicBool;
+
+        // Item 3 raises the stop as it finishes, so it is dropped; the
+        // items before it were delivered and must still come out in order
+        let items: Vec<usize> = (0..10).collect();
+        let stop = AtomicBool::new(false);
+        let mut out = Vec::new();
+        let stopped = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            map_ordered_until(
+                &items,
+                1,
+                &mut out,
+                || stop.load(AtomicOrdering::Relaxed),
+                |&i| {
+                    if i == 3 {
+                        stop.store(true, AtomicOrdering::Relaxed);
+                    }
+                    i
+                },
+                |out, _, v| out.push(v),
+                |out| panic!("stopped after {:?}", out),
+            )
+        }))
+        .unwrap_err()
+        .downcast::<String>()
+        .unwrap();
+        assert_eq!(*stopped, "stopped after [0, 1, 2]");
+    }
+}
@@ -0,0 +1,172 @@
+// ── Phase timing behind COREUTILS_RS_PERF ───────────────────────────────
+// With COREUTILS_RS_PERF=1 in the environment, a tool can time its phases
+// (input acquisition, scan, write) with scoped `phase` guards and print a
+// one-line summary to stderr at exit:
+//
+//     ftac: input=120ms scan=310ms write=450ms bytes=1.4GiB
+//
+// The variable is read once. When it is unset a guard holds nothing and
+// records nothing, and tools only put the `Timed` writer in place when
+// timing is on, so their normal output path is unchanged.
+//
+// Phases nest: time spent in an inner phase is taken off the enclosing
+// phase on the same thread, so `scan` does not also count the writes it
+// makes. Phases on worker threads add up, so they can exceed wall time.
+
+use std::cell::Cell;
+use std::io::{self, IoSlice, Write};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::Instant;
+
+use super::fmt_num;
+
+/// The environment variable that turns timing on.
+pub const ENV_VAR: &str = "COREUTILS_RS_PERF";
+
+static ENABLED: OnceLock<bool> = OnceLock::new();
+
+/// Nanoseconds per phase, in the order the phases first began.
+static TOTALS: Mutex<Vec<(&'static str, i128)>> = Mutex::new(Vec::new());
+
+/// Input bytes processed, or `u64::MAX` if nothing was counted.
+static BYTES: AtomicU64 = AtomicU64::new(u64::MAX);
+
+thread_local! {
+    /// The innermost phase running on this thread.
+    static ACTIVE: Cell<Option<&'static str>> = const { Cell::new(None) };
+}
+
+/// Whether COREUTILS_RS_PERF=1 is set. Read on first call, then cached.
+#[inline]
+pub fn enabled() -> bool {
+    *ENABLED.get_or_init(|| std::env::var_os(ENV_VAR).is_some_and(|v| v == "1"))
+}
+
+/// A running phase; its time is recorded when it is dropped.
+#[must_use = "a phase is timed until the guard is dropped"]
+pub struct Phase(Option<Running>);
+
+struct Running {
+    name: &'static str,
+    parent: Option<&'static str>,
+    start: Instant,
+}
+
+/// Start timing `name` until the returned guard is dropped. Repeated
+/// phases of one name add up.
+#[inline]
+pub fn phase(name: &'static str) -> Phase {
+    if !enabled() {
+        return Phase(None);
+    }
+    let parent = ACTIVE.with(|a| a.replace(Some(name)));
+    // Registered now, so the summary lists phases in the order they begin
+    add_nanos(name, 0);
+    Phase(Some(Running {
+        name,
+        parent,
+        start: Instant::now(),
+    }))
+}
+
+impl Drop for Phase {
+    fn drop(&mut self) {
+        let Some(running) = self.0.take() else {
+            return;
+        };
+        let nanos = running.start.elapsed().as_nanos() as i128;
+        ACTIVE.with(|a| a.set(running.parent));
+        add_nanos(running.name, nanos);
+        if let Some(parent) = running.parent {
+            add_nanos(parent, -nanos);
+        }
+    }
+}
+
+fn add_nanos(name: &'static str, nanos: i128) {
+    let mut totals = TOTALS.lock().unwrap_or_else(|e| e.into_inner());
+    match totals.iter_mut().find(|(n, _)| *n == name) {
+        Some((_, total)) => *total += nanos,
+        None => totals.push((name, nanos)),
+    }
+}
+
+/// Count `n` input bytes towards the summary.
+#[inline]
+pub fn add_bytes(n: u64) {
+    if enabled() {
+        let _ = BYTES.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |b| {
+            Some(if b == u64::MAX { n } else { b + n })
+        });
+    }
+}
+
+/// Print the summary line for `tool` to stderr, if timing is on. The byte
+/// count is left out when the tool counted none.
+pub fn report(tool: &str) {
+    if !enabled() {
+        return;
+    }
+    let totals = TOTALS.lock().unwrap_or_else(|e| e.into_inner());
+    let bytes = BYTES.load(Ordering::Relaxed);
+    eprintln!(
+        "{}",
+        summary(tool, &totals, (bytes != u64::MAX).then_some(bytes))
+    );
+}
+
+fn summary(tool: &str, totals: &[(&str, i128)], bytes: Option<u64>) -> String {
+    let mut line = format!("{}:", tool);
+    for (name, nanos) in totals {
+        line.push_str(&format!(" {}={}ms", name, nanos.max(&0) / 1_000_000));
+    }
+    if let Some(bytes) = bytes {
+        let mut human = Vec::new();
+        fmt_num::push_human_bytes(&mut human, bytes);
+        line.push_str(" bytes=");
+        line.push_str(std::str::from_utf8(&human).unwrap_or_default());
+    }
+    line
+}
+
+/// A writer whose calls are timed as the "write" phase. Put it under any
+/// buffering, so the time is that of the real writes.
+pub struct Timed<W>(pub W);
+
+impl<W: Write> Write for Timed<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let _p = phase("write");
+        self.0.write(buf)
+    }
+
+    fn write_vectored(&mut self, bufs: &[IoSlice<'_>]) -> io::Result<usize> {
+        let _p = phase("write");
+        self.0.write_vectored(bufs)
+    }
+
+    fn write_all(&mut self, buf: &[u8]) -> io::Result<()> {
+        let _p = phase("write");
+        self.0.write_all(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        let _p = phase("write");
+        self.0.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_summary_line() {
+        let totals = [("input", 120_400_000), ("scan", 310_000_000), ("write", -5)];
+        assert_eq!(
+            summary("ftac", &totals, Some(1_503_238_554)),
+            "ftac: input=120ms scan=310ms write=0ms bytes=1.4GiB"
+        );
+        assert_eq!(summary("fcksum", &[("hash", 0)], None), "fcksum: hash=0ms");
+    }
+}
@@ -0,0 +1,87 @@
+use std::cell::Cell;
+use std::ffi::OsStr;
+use std::sync::OnceLock;
+
+use super::diag::format_error;
+
+// ── SIGBUS on a shrinking mapping as an error exit ─────────────────────
+// A file truncated while it is memory-mapped turns reads past its new end
+// into SIGBUS, which would kill the tool with a signal. A tool that maps
+// input installs the handler once and holds a `Guard` naming the file
+// while it reads the mapping; a fault then prints "tool: FILE: file
+// changed while reading" and exits with status 1. The handler cannot
+// unwind, so output buffered by then is lost; a fault outside a guard
+// keeps the default action.
+
+/// Diagnostic a fault prints, unless reading a mapping changes it.
+const MESSAGE: &str = "file changed while reading";
+
+static TOOL: OnceLock<&'static str> = OnceLock::new();
+
+thread_local! {
+    /// The preformatted diagnostic for the mapping this thread is reading.
+    static CURRENT: Cell<(*const u8, usize)> = const { Cell::new((std::ptr::null(), 0)) };
+}
+
+#[cfg(unix)]
+extern "C" fn on_sigbus(_sig: libc::c_int) {
+    let (ptr, len) = CURRENT.with(|c| c.get());
+    unsafe {
+        if ptr.is_null() {
+            // Not ours: restore the default, which the retried access raises
+            libc::signal(libc::SIGBUS, libc::SIG_DFL);
+            return;
+        }
+        libc::write(2, ptr as *const libc::c_void, len);
+        libc::_exit(1);
+    }
+}
+
+/// Route SIGBUS in a `Guard` to a diagnostic and exit status 1, with
+/// `tool` naming the program. A no-op where there are no POSIX signals.
+pub fn install_handler(tool: &'static str) {
+    let _ = TOOL.set(tool);
+    #[cfg(unix)]
+    unsafe {
+        let mut action: libc::sigaction = std::mem::zeroed();
+        action.sa_sigaction = on_sigbus as *const () as libc::sighandler_t;
+        libc::sigemptyset(&mut action.sa_mask);
+        libc::sigaction(libc::SIGBUS, &action, std::ptr::null_mut());
+    }
+}
+
+/// The error for a file whose size or mtime changed while it was read.
+pub fn changed_error() -> std::io::Error {
+    std::io::Error::other(MESSAGE)
+}
+
+/// Marks this thread as reading the mapping of `name` until dropped.
+#[must_use = "a mapping is guarded until the guard is dropped"]
+pub struct Guard {
+    message: Option<Box<[u8]>>,
+}
+
+impl Guard {
+    /// Guard reads of the mapping of `name`. Holds nothing when the
+    /// handler is not installed.
+    pub fn new(name: &OsStr) -> Self {
+        let Some(tool) = TOOL.get() else {
+            return Guard { message: None };
+        };
+        let message: Box<[u8]> = format!("{}\n", format_error(tool, Some(name), &MESSAGE))
+            .into_bytes()
+            .into_boxed_slice();
+        CURRENT.with(|c| c.set((message.as_ptr(), message.len())));
+        Guard {
+            message: Some(message),
+        }
+    }
+}
+
+impl Drop for Guard {
+    fn drop(&mut self) {
+        if self.message.take().is_some() {
+            CURRENT.with(|c| c.set((std::ptr::null(), 0)));
+        }
+    }
+}
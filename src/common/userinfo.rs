@@ -0,0 +1,213 @@
+// ── Users and groups read straight from the passwd and group files ──────
+// getpwnam() and friends go through NSS, which a fully static binary or a
+// scratch container without libnss and /etc/nsswitch.conf cannot load:
+// every lookup then fails even though /etc/passwd is right there. Tools
+// fall back to this plain colon-delimited parser when the C library comes
+// up empty (or always, when asked), mirroring what glibc's "files"
+// backend would have answered.
+
+use std::io;
+use std::path::Path;
+
+/// Where the "files" backend keeps its databases.
+pub const PASSWD_PATH: &str = "/etc/passwd";
+pub const GROUP_PATH: &str = "/etc/group";
+
+/// One passwd entry.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct User {
+    pub name: String,
+    pub uid: u32,
+    pub gid: u32,
+    pub gecos: String,
+    pub dir: String,
+    pub shell: String,
+}
+
+/// One group entry, with the users listed as supplementary members.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Group {
+    pub name: String,
+    pub gid: u32,
+    pub members: Vec<String>,
+}
+
+/// The parsed passwd and group files, entries in file order.
+#[derive(Clone, Debug, Default)]
+pub struct UserDb {
+    pub users: Vec<User>,
+    pub groups: Vec<Group>,
+}
+
+impl UserDb {
+    /// Read `/etc/passwd` and `/etc/group`.
+    pub fn load() -> io::Result<Self> {
+        Self::from_files(Path::new(PASSWD_PATH), Path::new(GROUP_PATH))
+    }
+
+    /// Read the given passwd and group files. A missing group file just
+    /// leaves every user without supplementary groups.
+    pub fn from_files(passwd: &Path, group: &Path) -> io::Result<Self> {
+        let passwd = std::fs::read(passwd)?;
+        let group = match std::fs::read(group) {
+            Ok(group) => group,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Vec::new(),
+            Err(e) => return Err(e),
+        };
+        Ok(Self::parse(&passwd, &group))
+    }
+
+    /// Parse file contents. Blank lines, `#` comments, NIS `+`/`-` entries
+    /// and lines without numeric ids are skipped, as glibc does.
+    pub fn parse(passwd: &[u8], group: &[u8]) -> Self {
+        let users = entries(passwd)
+            .filter_map(|f| {
+                Some(User {
+                    name: field(&f, 0)?,
+                    uid: field(&f, 2)?.parse().ok()?,
+                    gid: field(&f, 3)?.parse().ok()?,
+                    gecos: field(&f, 4).unwrap_or_default(),
+                    dir: field(&f, 5).unwrap_or_default(),
+                    shell: field(&f, 6).unwrap_or_default(),
+                })
+            })
+            .collect();
+        let groups = entries(group)
+            .filter_map(|f| {
+                Some(Group {
+                    name: field(&f, 0)?,
+                    gid: field(&f, 2)?.parse().ok()?,
+                    members: field(&f, 3)
+                        .unwrap_or_default()
+                        .split(',')
+                        .map(str::trim)
+                        .filter(|m| !m.is_empty())
+                        .map(str::to_string)
+                        .collect(),
+                })
+            })
+            .collect();
+        UserDb { users, groups }
+    }
+
+    /// The first user called `name`.
+    pub fn user_by_name(&self, name: &[u8]) -> Option<&User> {
+        self.users.iter().find(|u| u.name.as_bytes() == name)
+    }
+
+    /// The first user with `uid`.
+    pub fn user_by_uid(&self, uid: u32) -> Option<&User> {
+        self.users.iter().find(|u| u.uid == uid)
+    }
+
+    /// The first group with `gid`.
+    pub fn group_by_gid(&self, gid: u32) -> Option<&Group> {
+        self.groups.iter().find(|g| g.gid == gid)
+    }
+
+    /// What getgrouplist() gives for `user`: the primary `gid` first, then
+    /// every group listing `user` as a member, in file order and without
+    /// repeats.
+    pub fn group_list(&self, user: &[u8], gid: u32) -> Vec<u32> {
+        let mut gids = vec![gid];
+        for group in &self.groups {
+            if group.members.iter().any(|m| m.as_bytes() == user) && !gids.contains(&group.gid) {
+                gids.push(group.gid);
+            }
+        }
+        gids
+    }
+}
+
+/// The colon-separated fields of each entry line.
+fn entries(data: &[u8]) -> impl Iterator<Item = Vec<&[u8]>> {
+    data.split(|&b| b == b'\n')
+        .map(|line| line.strip_suffix(b"\r").unwrap_or(line))
+        .filter(|line| !matches!(line.first(), None | Some(b'#' | b'+' | b'-')))
+        .map(|line| line.split(|&b| b == b':').collect())
+}
+
+fn field(fields: &[&[u8]], i: usize) -> Option<String> {
+    fields
+        .get(i)
+        .map(|f| String::from_utf8_lossy(f).into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PASSWD: &str = "\
+root:x:0:0:root:/root:/bin/bash
+# a comment
+
+alice:x:1000:1000:Alice,,,:/home/alice:/bin/sh
+bob:x:1001:100::/home/bob:/bin/sh
++nisuser::::::
+broken:x:notanumber:0::/:/bin/sh
+carol:x:1002:1002
+";
+
+    const GROUP: &str = "\
+root:x:0:
+users:x:100:alice, carol
+alice:x:1000:
+wheel:x:10:alice,bob
+carol:x:1002:carol
+audio:x:29:bob
+";
+
+    fn write_fixtures(dir: &Path) -> (std::path::PathBuf, std::path::PathBuf) {
+        let passwd = dir.join("passwd");
+        let group = dir.join("group");
+        std::fs::write(&passwd, PASSWD).unwrap();
+        std::fs::write(&group, GROUP).unwrap();
+        (passwd, group)
+    }
+
+    #[test]
+    fn test_parse_users() {
+        let dir = tempfile::tempdir().unwrap();
+        let (passwd, group) = write_fixtures(dir.path());
+        let db = UserDb::from_files(&passwd, &group).unwrap();
+
+        let names: Vec<&str> = db.users.iter().map(|u| u.name.as_str()).collect();
+        assert_eq!(names, ["root", "alice", "bob", "carol"]);
+        let alice = db.user_by_name(b"alice").unwrap();
+        assert_eq!((alice.uid, alice.gid), (1000, 1000));
+        assert_eq!(alice.gecos, "Alice,,,");
+        assert_eq!(alice.dir, "/home/alice");
+        assert_eq!(alice.shell, "/bin/sh");
+        // Trailing fields may be missing
+        assert_eq!(db.user_by_uid(1002).unwrap().shell, "");
+        assert!(db.user_by_name(b"broken").is_none());
+        assert!(db.user_by_name(b"+nisuser").is_none());
+        assert_eq!(db.group_by_gid(10).unwrap().name, "wheel");
+    }
+
+    #[test]
+    fn test_group_list_membership() {
+        let db = UserDb::parse(PASSWD.as_bytes(), GROUP.as_bytes());
+        assert_eq!(db.group_list(b"alice", 1000), [1000, 100, 10]);
+        // Groups found through member lists follow in file order
+        assert_eq!(db.group_list(b"bob", 100), [100, 10, 29]);
+        // carol is a member of her own primary group, listed once
+        assert_eq!(db.group_list(b"carol", 1002), [1002, 100]);
+        assert_eq!(db.group_list(b"root", 0), [0]);
+        // Membership needs only the group file, not a passwd entry
+        assert_eq!(
+            UserDb::parse(b"", b"x:x:7:dave\n").group_list(b"dave", 500),
+            [500, 7]
+        );
+    }
+
+    #[test]
+    fn test_missing_group_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let (passwd, _) = write_fixtures(dir.path());
+        let db = UserDb::from_files(&passwd, &dir.path().join("nope")).unwrap();
+        assert!(db.groups.is_empty());
+        assert_eq!(db.group_list(b"alice", 1000), [1000]);
+        assert!(UserDb::from_files(&dir.path().join("nope"), &passwd).is_err());
+    }
+}
@@ -0,0 +1,72 @@
+// ── --version output shared by every tool ───────────────────────────────
+// The first line is "tool (fcoreutils) X.Y.Z" and must never change, since
+// scripts parse it. The second line is for bug reports: the target triple
+// and compiler captured by build.rs, plus the fast paths compiled in.
+
+/// Target triple the binaries were built for.
+pub const TARGET: &str = env!("FCOREUTILS_TARGET");
+
+/// `rustc --version` of the compiler that built the binaries.
+pub const RUSTC_VERSION: &str = env!("FCOREUTILS_RUSTC_VERSION");
+
+/// Platform-specific code paths and optional components compiled into this
+/// build, in a fixed order.
+pub fn features() -> Vec<&'static str> {
+    let mut features = Vec::new();
+    if cfg!(unix) {
+        features.push("unix");
+    }
+    if cfg!(target_os = "linux") {
+        // splice/vmsplice pipes, fadvise hints, xattr digest cache
+        features.push("linux");
+    }
+    features.push("mmap");
+    features.push("simd-base64");
+    if cfg!(target_feature = "avx2") {
+        features.push("avx2");
+    }
+    if cfg!(target_feature = "sse4.2") {
+        features.push("sse4.2");
+    }
+    if option_env!("FYES_ASM_PATH").is_some() {
+        features.push("yes-asm");
+    }
+    features
+}
+
+/// Full --version text for `tool`, both lines newline-terminated.
+pub fn version_text(tool: &str) -> String {
+    format!(
+        "{} (fcoreutils) {}\nbuilt for {} with {}; features: {}\n",
+        tool,
+        env!("CARGO_PKG_VERSION"),
+        TARGET,
+        RUSTC_VERSION,
+        features().join(", ")
+    )
+}
+
+/// Print the --version text for `tool` to stdout.
+pub fn print_version(tool: &str) {
+    print!("{}", version_text(tool));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_version_text_lines() {
+        let text = version_text("tac");
+        let mut lines = text.lines();
+        assert_eq!(
+            lines.next(),
+            Some(format!("tac (fcoreutils) {}", env!("CARGO_PKG_VERSION")).as_str())
+        );
+        let build = lines.next().unwrap();
+        assert!(build.starts_with(&format!("built for {} with rustc ", TARGET)));
+        assert!(build.contains("; features: "));
+        assert_eq!(lines.next(), None);
+        assert!(!TARGET.is_empty());
+    }
+}
@@ -49,6 +49,7 @@ fn process_data_str(
         output_delim: od,
         suppress_no_delim: suppress,
         line_delim,
+        quoted: false,
     };
     let mut out = Vec::new();
     process_cut_data(input.as_bytes(), &cfg, &mut out).unwrap();
@@ -114,6 +115,78 @@ fn test_parse_zero_rejected() {
     assert!(parse_ranges("0").is_err());
 }
 
+#[test]
+fn test_parse_empty_element_rejected() {
+    for spec in ["", ",", "1,", ",1", "1,,2"] {
+        assert_eq!(
+            parse_ranges(spec).unwrap_err(),
+            ListError::NumberedFromOne,
+            "spec {:?}",
+            spec
+        );
+    }
+}
+
+#[test]
+fn test_list_error_message_names_the_list_kind() {
+    // GNU: "fields are numbered from 1" / "byte/character positions are numbered from 1"
+    let e = ListError::NumberedFromOne;
+    assert_eq!(e.message(CutMode::Fields), "fields are numbered from 1");
+    for mode in [CutMode::Bytes, CutMode::Characters] {
+        assert_eq!(
+            e.message(mode),
+            "byte/character positions are numbered from 1"
+        );
+    }
+    assert_eq!(
+        ListError::DecreasingRange("3-1".to_string()).message(CutMode::Fields),
+        "invalid decreasing range: '3-1'"
+    );
+}
+
+#[test]
+fn test_parse_bare_dash_rejected() {
+    assert_eq!(parse_ranges("-").unwrap_err(), ListError::NoEndpoint);
+    assert_eq!(
+        ListError::NoEndpoint.message(CutMode::Bytes),
+        "invalid range with no endpoint: -"
+    );
+    assert!(parse_ranges("-,1").is_err());
+}
+
+#[test]
+fn test_parse_merge_open_end() {
+    let r = parse_ranges("1-,3").unwrap();
+    assert_eq!(r.len(), 1);
+    assert_eq!(r[0].start, 1);
+    assert_eq!(r[0].end, usize::MAX);
+}
+
+#[test]
+fn test_parse_list_file_separators() {
+    // Commas, blanks and newlines all separate; runs of them are not empty entries
+    let r = parse_list_file("1,3\n5-6\t 8-\n\n").unwrap();
+    let got: Vec<(usize, usize)> = r.iter().map(|r| (r.start, r.end)).collect();
+    assert_eq!(got, [(1, 1), (3, 3), (5, 6), (8, usize::MAX)]);
+}
+
+#[test]
+fn test_parse_list_file_errors() {
+    assert_eq!(
+        parse_list_file("2\n1-x\n").unwrap_err(),
+        ListError::InvalidRange("1-x".to_string())
+    );
+    assert_eq!(
+        parse_list_file("2 abc").unwrap_err(),
+        ListError::InvalidField("abc".to_string())
+    );
+    assert_eq!(
+        parse_list_file("1\n0\n").unwrap_err(),
+        ListError::NumberedFromOne
+    );
+    assert_eq!(parse_list_file(" \n").unwrap_err(), ListError::NoList);
+}
+
 // --- Field cutting ---
 
 #[test]
@@ -296,3 +369,786 @@ fn test_cut_fields_returns_true_when_not_suppressed() {
     assert!(result);
     assert_eq!(&out, b"a");
 }
+
+// --- Complement of the full line ---
+
+#[test]
+fn test_complement_all_fields_keeps_empty_lines() {
+    // GNU: `cut -d: -f1- --complement` prints one empty line per delimited line;
+    // lines without a delimiter still pass through unless -s is given.
+    let out = process_data_str(
+        "a:b:c\nxyz\n",
+        CutMode::Fields,
+        "1-",
+        b':',
+        true,
+        false,
+        None,
+        b'\n',
+    );
+    assert_eq!(out, "\nxyz\n");
+    let out = process_data_str(
+        "a:b:c\nxyz",
+        CutMode::Fields,
+        "1-",
+        b':',
+        true,
+        false,
+        None,
+        b'\n',
+    );
+    assert_eq!(out, "\nxyz\n");
+}
+
+#[test]
+fn test_complement_all_fields_suppress() {
+    let out = process_data_str(
+        "a:b:c\nxyz\n",
+        CutMode::Fields,
+        "1-",
+        b':',
+        true,
+        true,
+        None,
+        b'\n',
+    );
+    assert_eq!(out, "\n");
+}
+
+#[test]
+fn test_complement_all_fields_output_delim() {
+    let out = process_data_str(
+        "a:b:c\n",
+        CutMode::Fields,
+        "1-",
+        b':',
+        true,
+        false,
+        Some(b"XX"),
+        b'\n',
+    );
+    assert_eq!(out, "\n");
+}
+
+#[test]
+fn test_complement_all_bytes_keeps_empty_lines() {
+    let out = process_data_str(
+        "abc\n\n",
+        CutMode::Bytes,
+        "1-",
+        b'\t',
+        true,
+        false,
+        None,
+        b'\n',
+    );
+    assert_eq!(out, "\n\n");
+    assert_eq!(cut_byte_str("abc", "1-", true), "");
+}
+
+// --- Field counting (--count) ---
+
+fn count_str(input: &str, delim: u8, suppress: bool, line_delim: u8) -> String {
+    process_data_str(
+        input,
+        CutMode::Count,
+        "1",
+        delim,
+        false,
+        suppress,
+        None,
+        line_delim,
+    )
+}
+
+#[test]
+fn test_count_fields() {
+    assert_eq!(count_fields(b"", b','), 1);
+    assert_eq!(count_fields(b"abc", b','), 1);
+    assert_eq!(count_fields(b"a,", b','), 2);
+    assert_eq!(count_fields(b",,,", b','), 4);
+    assert_eq!(count_fields(b"a,b,c", b','), 3);
+}
+
+#[test]
+fn test_count_mode() {
+    let input = "plain\n\na,\na,b,c\n,,,\nlast,x";
+    assert_eq!(count_str(input, b',', false, b'\n'), "1\n1\n2\n3\n4\n2\n");
+    // -s drops the records without a delimiter
+    assert_eq!(count_str(input, b',', true, b'\n'), "2\n3\n4\n2\n");
+    assert_eq!(count_str("", b',', false, b'\n'), "");
+    // Default TAB delimiter
+    assert_eq!(count_str("a\tb\nc\n", b'\t', false, b'\n'), "2\n1\n");
+}
+
+#[test]
+fn test_count_mode_nul_records() {
+    let input = "a,b\nc\0d\0,\0";
+    assert_eq!(count_str(input, b',', false, b'\0'), "2\x001\x002\0");
+    assert_eq!(count_str(input, b',', true, b'\0'), "2\x002\0");
+}
+
+#[test]
+fn test_count_mode_plan_and_streaming() {
+    let cfg = CutConfig {
+        mode: CutMode::Count,
+        ranges: &[],
+        complement: false,
+        delim: b',',
+        output_delim: b",",
+        suppress_no_delim: false,
+        line_delim: b'\n',
+        quoted: false,
+    };
+    assert_eq!(cfg.plan(), Plan::General);
+    let mut data = b"a,b\nc\n".to_vec();
+    assert_eq!(process_cut_data_mut(&mut data, &cfg), None);
+    let mut out = Vec::new();
+    process_cut_reader(&b"a,b\nc\n,,"[..], &cfg, None, &mut out).unwrap();
+    assert_eq!(out, b"2\n1\n3\n");
+}
+
+#[test]
+fn test_fields_past_delimiter_cache() {
+    // The multi-select and in-place paths cache 64 delimiters per line;
+    // lists reaching further must still cut exactly.
+    let line: Vec<String> = (1..=100).map(|c| format!("c{}", c)).collect();
+    let data = format!("{}\n", line.join("\t")).repeat(2);
+    for spec in [
+        "70", "2-70", "70-", "65-", "66-", "1,3,70", "1,66", "1,64", "3,64-",
+    ] {
+        let ranges = parse_ranges(spec).unwrap();
+        let picked: Vec<&str> = line
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| ranges.iter().any(|r| (r.start..=r.end).contains(&(i + 1))))
+            .map(|(_, f)| f.as_str())
+            .collect();
+        let expected = format!("{}\n", picked.join("\t")).repeat(2);
+        let cfg = CutConfig {
+            mode: CutMode::Fields,
+            ranges: &ranges,
+            complement: false,
+            delim: b'\t',
+            output_delim: b"\t",
+            suppress_no_delim: false,
+            line_delim: b'\n',
+            quoted: false,
+        };
+        let mut out = Vec::new();
+        process_cut_data(data.as_bytes(), &cfg, &mut out).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), expected, "spec {}", spec);
+
+        let mut inplace = data.clone().into_bytes();
+        if let Some(n) = process_cut_data_mut(&mut inplace, &cfg) {
+            assert_eq!(
+                inplace[..n],
+                *expected.as_bytes(),
+                "in place, spec {}",
+                spec
+            );
+        }
+    }
+}
+
+#[test]
+fn test_stats_tally_and_streaming() {
+    let ranges = parse_ranges("2").unwrap();
+    let mut cfg = CutConfig {
+        mode: CutMode::Fields,
+        ranges: &ranges,
+        complement: false,
+        delim: b',',
+        output_delim: b",",
+        suppress_no_delim: true,
+        line_delim: b'\n',
+        quoted: false,
+    };
+    let input = b"a,b\nplain\n\nc,d\ntail";
+    let mut stats = CutStats::default();
+    stats.tally(input, &cfg);
+    assert_eq!(
+        stats,
+        CutStats {
+            records: 5,
+            emitted: 2,
+            no_delim: 3,
+            bytes: 0,
+        }
+    );
+
+    // The streaming reader tallies the same counts batch by batch
+    let mut streamed = CutStats::default();
+    let mut out = Vec::new();
+    process_cut_reader(&input[..], &cfg, Some(&mut streamed), &mut out).unwrap();
+    assert_eq!(streamed, stats);
+    assert_eq!(out, b"b\nd\n");
+
+    cfg.mode = CutMode::Bytes;
+    cfg.suppress_no_delim = false;
+    let mut stats = CutStats::default();
+    stats.tally(input, &cfg);
+    assert_eq!((stats.records, stats.emitted, stats.no_delim), (5, 5, 0));
+    stats.tally(b"", &cfg);
+    assert_eq!(stats.records, 5);
+}
+
+// --- Invalid UTF-8 detection (--warn-invalid) ---
+
+#[test]
+fn test_first_invalid_utf8_line() {
+    assert_eq!(first_invalid_utf8_line(b"", b'\n'), None);
+    assert_eq!(
+        first_invalid_utf8_line("h\u{e9}llo\nok\n".as_bytes(), b'\n'),
+        None
+    );
+    assert_eq!(first_invalid_utf8_line(b"\xff\n", b'\n'), Some(1));
+    assert_eq!(
+        first_invalid_utf8_line(b"a\nb\nc\xffd\ne\xfe\n", b'\n'),
+        Some(3)
+    );
+    // Truncated sequence at end of line / end of input
+    assert_eq!(first_invalid_utf8_line(b"a\nb\xc3\nc\n", b'\n'), Some(2));
+    assert_eq!(first_invalid_utf8_line(b"a\nb\xc3", b'\n'), Some(2));
+    // -z: records are NUL-terminated
+    assert_eq!(first_invalid_utf8_line(b"a\nb\0c\xff\0", b'\0'), Some(2));
+}
+
+// --- Header field names (--header-fields) ---
+
+fn header_field_numbers(fields: &HeaderFields) -> Vec<(usize, usize)> {
+    fields.ranges.iter().map(|r| (r.start, r.end)).collect()
+}
+
+#[test]
+fn test_resolve_header_fields_in_field_order() {
+    let fields = resolve_header_fields(
+        b"id,name,age,city",
+        b',',
+        false,
+        &["city", "id", "age"],
+        false,
+    )
+    .unwrap();
+    assert_eq!(header_field_numbers(&fields), [(1, 1), (3, 4)]);
+    assert!(fields.duplicates.is_empty());
+}
+
+#[test]
+fn test_resolve_header_fields_duplicate_uses_first() {
+    let fields = resolve_header_fields(b"a,b,a,a", b',', false, &["a", "a"], false).unwrap();
+    assert_eq!(header_field_numbers(&fields), [(1, 1)]);
+    assert_eq!(fields.duplicates, [("a".to_string(), 1)]);
+}
+
+#[test]
+fn test_resolve_header_fields_unknown_names() {
+    let err = resolve_header_fields(b"a\tb", b'\t', false, &["x", "b", "y"], false).unwrap_err();
+    assert_eq!(err, "unknown header field: 'x', 'y'");
+    assert!(resolve_header_fields(b"a,b", b',', false, &["a", ""], false).is_err());
+}
+
+#[test]
+fn test_resolve_header_fields_case_insensitive() {
+    assert!(resolve_header_fields(b"Id,Name", b',', false, &["name"], false).is_err());
+    let fields = resolve_header_fields(b"Id,Name", b',', false, &["name"], true).unwrap();
+    assert_eq!(header_field_numbers(&fields), [(2, 2)]);
+}
+
+#[test]
+fn test_first_record_len() {
+    assert_eq!(first_record_len(b"ab\ncd\n", b'\n'), 3);
+    assert_eq!(first_record_len(b"abc", b'\n'), 3);
+    assert_eq!(first_record_len(b"a\0b\0", b'\0'), 2);
+}
+
+#[test]
+fn test_split_records_ends_on_delimiters() {
+    let data = b"aaa\nb\ncccccc\n\ndd";
+    for size in 1..=data.len() + 1 {
+        let pieces = split_records(data, size, b'\n');
+        assert_eq!(pieces.concat(), data, "size {size}");
+        for piece in &pieces[..pieces.len() - 1] {
+            assert_eq!(piece.last(), Some(&b'\n'), "size {size}");
+        }
+    }
+    assert_eq!(
+        split_records(data, 5, b'\n'),
+        [&b"aaa\nb\n"[..], b"cccccc\n", b"\ndd"]
+    );
+    assert!(split_records(b"", 4, b'\n').is_empty());
+}
+
+#[test]
+fn test_process_cut_threaded_matches_serial() {
+    let mut data = Vec::new();
+    for i in 0..20_000 {
+        data.extend_from_slice(format!("{i},x{},{}\n", i % 7, "y".repeat(i % 13)).as_bytes());
+    }
+    data.extend_from_slice(b"no delimiter at end");
+    let ranges = parse_ranges("1,3").unwrap();
+    let cfg = CutConfig {
+        mode: CutMode::Fields,
+        ranges: &ranges,
+        complement: false,
+        delim: b',',
+        output_delim: b",",
+        suppress_no_delim: false,
+        line_delim: b'\n',
+        quoted: false,
+    };
+    let mut serial = Vec::new();
+    process_cut_data(&data, &cfg, &mut serial).unwrap();
+    for threads in [2, 3, 8] {
+        let mut threaded = Vec::new();
+        process_cut_threaded(&data, &cfg, threads, &mut threaded).unwrap();
+        assert!(threaded == serial, "{threads} threads");
+    }
+}
+
+#[test]
+fn test_delimiter_by_name() {
+    assert_eq!(delimiter_by_name("tab").unwrap(), b'\t');
+    assert_eq!(delimiter_by_name("space").unwrap(), b' ');
+    assert_eq!(delimiter_by_name("comma").unwrap(), b',');
+    assert_eq!(delimiter_by_name("colon").unwrap(), b':');
+    assert_eq!(delimiter_by_name("semicolon").unwrap(), b';');
+    assert_eq!(delimiter_by_name("nul").unwrap(), b'\0');
+    assert_eq!(delimiter_by_name("pipe").unwrap(), b'|');
+    assert_eq!(
+        delimiter_by_name("TAB").unwrap_err(),
+        "invalid delimiter name 'TAB'; valid names are: \
+         tab, space, comma, colon, semicolon, nul, pipe"
+    );
+}
+
+// --- Execution plans ---
+
+/// Lines of random length over a small alphabet; `delims_per_line` skews
+/// how often the delimiter appears. Sometimes omits the final newline.
+fn random_lines(seed: u64, lines: usize, delims_per_line: usize) -> Vec<u8> {
+    let mut state = seed | 1;
+    let mut next = move || {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        state
+    };
+    let mut data = Vec::new();
+    for _ in 0..lines {
+        let len = (next() % 40) as usize;
+        for _ in 0..len {
+            let r = next() as usize % 40;
+            data.push(if r < delims_per_line * 2 {
+                b','
+            } else {
+                b'a' + (r % 5) as u8
+            });
+        }
+        data.push(b'\n');
+    }
+    if seed.is_multiple_of(2) {
+        data.pop();
+    }
+    data
+}
+
+fn planned_output(data: &[u8], mode: CutMode, spec: &str) -> (Plan, Vec<u8>) {
+    let ranges = parse_ranges(spec).unwrap();
+    let od: &[u8] = if mode == CutMode::Fields { b"," } else { b"" };
+    let cfg = CutConfig {
+        mode,
+        ranges: &ranges,
+        complement: false,
+        delim: b',',
+        output_delim: od,
+        suppress_no_delim: false,
+        line_delim: b'\n',
+        quoted: false,
+    };
+    let mut out = Vec::new();
+    process_cut_data(data, &cfg, &mut out).unwrap();
+    (cfg.plan(), out)
+}
+
+#[test]
+fn test_plan_selection() {
+    let plan = |mode, spec: &str, complement, suppress| {
+        let ranges = parse_ranges(spec).unwrap();
+        CutConfig {
+            mode,
+            ranges: &ranges,
+            complement,
+            delim: b'\t',
+            output_delim: if mode == CutMode::Fields { b"\t" } else { b"" },
+            suppress_no_delim: suppress,
+            line_delim: b'\n',
+            quoted: false,
+        }
+        .plan()
+    };
+    assert_eq!(
+        plan(CutMode::Fields, "1", false, false),
+        Plan::FirstFieldOnly
+    );
+    assert_eq!(
+        plan(CutMode::Bytes, "1-8", false, false),
+        Plan::SinglePrefixRange(8)
+    );
+    assert_eq!(
+        plan(CutMode::Characters, "-3", false, false),
+        Plan::SinglePrefixRange(3)
+    );
+    assert_eq!(plan(CutMode::Fields, "1", true, false), Plan::General);
+    assert_eq!(plan(CutMode::Fields, "1", false, true), Plan::General);
+    assert_eq!(plan(CutMode::Bytes, "1-8", true, false), Plan::General);
+    assert_eq!(plan(CutMode::Fields, "1-2", false, false), Plan::General);
+    assert_eq!(plan(CutMode::Bytes, "1-", false, false), Plan::General);
+    assert_eq!(plan(CutMode::Bytes, "2-8", false, false), Plan::General);
+}
+
+#[test]
+fn test_first_field_plan_matches_reference() {
+    for seed in 1..=40u64 {
+        for density in [0, 1, 6] {
+            let data = random_lines(seed, 300, density);
+            let (plan, out) = planned_output(&data, CutMode::Fields, "1");
+            assert_eq!(plan, Plan::FirstFieldOnly);
+            let mut expected = Vec::new();
+            for line in data.split(|&b| b == b'\n') {
+                expected.extend_from_slice(line.split(|&b| b == b',').next().unwrap());
+                expected.push(b'\n');
+            }
+            if data.last() == Some(&b'\n') {
+                expected.pop();
+            }
+            assert_eq!(out, expected, "seed {} density {}", seed, density);
+
+            // Both field-1 scanners agree whichever one the sample picks
+            let (mut wide, mut narrow) = (Vec::new(), Vec::new());
+            field1_first_hit_to_buf(&data, b',', b'\n', &mut wide);
+            single_field1_to_buf(&data, b',', b'\n', &mut narrow);
+            assert_eq!(wide, narrow, "seed {} density {}", seed, density);
+        }
+    }
+    assert!(wide_records(&random_lines(1, 300, 6), b',', b'\n'));
+    assert!(!wide_records(&random_lines(1, 300, 0), b',', b'\n'));
+}
+
+/// The per-line complement of byte ranges as it was computed before the
+/// gaps were derived once per run, kept as a reference. The gap before a
+/// range is clamped to the line here; the old code was not, and read past
+/// short lines when a later range started beyond their end.
+fn reference_complement_bytes(line: &[u8], ranges: &[Range], output_delim: &[u8]) -> Vec<u8> {
+    let len = line.len();
+    let mut comp_ranges = Vec::new();
+    let mut pos: usize = 1;
+    for r in ranges {
+        let rs = r.start.min(len + 1);
+        let re = r.end.min(len);
+        if pos < rs {
+            comp_ranges.push((pos, rs - 1));
+        }
+        pos = re + 1;
+        if pos > len {
+            break;
+        }
+    }
+    if pos <= len {
+        comp_ranges.push((pos, len));
+    }
+    let mut out = Vec::new();
+    for (i, &(s, e)) in comp_ranges.iter().enumerate() {
+        if i > 0 {
+            out.extend_from_slice(output_delim);
+        }
+        out.extend_from_slice(&line[s - 1..e]);
+    }
+    out
+}
+
+#[test]
+fn test_complement_ranges_gaps() {
+    let gaps = |spec: &str| -> Vec<(usize, usize)> {
+        complement_ranges(&parse_ranges(spec).unwrap())
+            .iter()
+            .map(|r| (r.start, r.end))
+            .collect()
+    };
+    assert_eq!(gaps("1"), [(2, usize::MAX)]);
+    assert_eq!(gaps("3-5"), [(1, 2), (6, usize::MAX)]);
+    assert_eq!(gaps("5-"), [(1, 4)]);
+    assert_eq!(gaps("1-"), []);
+    assert_eq!(gaps("2,4-6,9-"), [(1, 1), (3, 3), (7, 8)]);
+}
+
+#[test]
+fn test_complement_bytes_explicit() {
+    assert_eq!(cut_byte_str("abcdefgh", "1", true), "bcdefgh");
+    assert_eq!(cut_byte_str("abcdefgh", "3-5", true), "abfgh");
+    assert_eq!(cut_byte_str("abcdefgh", "5-", true), "abcd");
+    assert_eq!(cut_byte_str("abc", "5-", true), "abc");
+    let out = process_data_str(
+        "abcdefgh\nab\n",
+        CutMode::Bytes,
+        "3-5",
+        b'\t',
+        true,
+        false,
+        Some(b":"),
+        b'\n',
+    );
+    assert_eq!(out, "ab:fgh\nab\n");
+}
+
+#[test]
+fn test_complement_bytes_matches_reference() {
+    let mut state = 0x9e37_79b9_7f4a_7c15u64;
+    let mut next = move |n: usize| {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        (state % n as u64) as usize
+    };
+    for seed in 1..=40u64 {
+        let data = random_lines(seed, 200, 1);
+        for _ in 0..5 {
+            // Up to four pieces: single bytes, closed, open-start and open-end
+            let spec: Vec<String> = (0..1 + next(4))
+                .map(|_| {
+                    let a = 1 + next(45);
+                    match next(4) {
+                        0 => a.to_string(),
+                        1 => format!("{}-{}", a, a + next(10)),
+                        2 => format!("-{}", a),
+                        _ => format!("{}-", a),
+                    }
+                })
+                .collect();
+            let spec = spec.join(",");
+            let ranges = parse_ranges(&spec).unwrap();
+            for od in [&b""[..], b"|"] {
+                let mut expected = Vec::new();
+                for line in data.split(|&b| b == b'\n') {
+                    let mut out = Vec::new();
+                    cut_bytes(line, &ranges, true, od, &mut out).unwrap();
+                    let reference = reference_complement_bytes(line, &ranges, od);
+                    assert_eq!(out, reference, "spec {} line {:?}", spec, line);
+                    expected.extend_from_slice(&reference);
+                    expected.push(b'\n');
+                }
+                if data.last() == Some(&b'\n') {
+                    expected.pop();
+                }
+                let cfg = CutConfig {
+                    mode: CutMode::Bytes,
+                    ranges: &ranges,
+                    complement: true,
+                    delim: b'\t',
+                    output_delim: od,
+                    suppress_no_delim: false,
+                    line_delim: b'\n',
+                    quoted: false,
+                };
+                let mut out = Vec::new();
+                process_cut_data(&data, &cfg, &mut out).unwrap();
+                assert_eq!(out, expected, "spec {} od {:?}", spec, od);
+
+                let mut inplace = data.clone();
+                match process_cut_data_mut(&mut inplace, &cfg) {
+                    Some(n) => assert_eq!(inplace[..n], expected[..], "in place, spec {}", spec),
+                    None => assert!(!od.is_empty() || data.last() != Some(&b'\n')),
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn test_prefix_range_plan_matches_reference() {
+    for seed in 1..=40u64 {
+        let data = random_lines(seed, 300, 1);
+        for n in [1, 5, 39, 40, 100] {
+            let (plan, out) = planned_output(&data, CutMode::Bytes, &format!("1-{}", n));
+            assert_eq!(plan, Plan::SinglePrefixRange(n));
+            let mut expected = Vec::new();
+            for line in data.split(|&b| b == b'\n') {
+                expected.extend_from_slice(&line[..line.len().min(n)]);
+                expected.push(b'\n');
+            }
+            if data.last() == Some(&b'\n') {
+                expected.pop();
+            }
+            assert_eq!(out, expected, "seed {} n {}", seed, n);
+        }
+    }
+}
+
+// --- Quoted fields (--quoted) ---
+
+fn cut_quoted(
+    input: &[u8],
+    mode: CutMode,
+    spec: &str,
+    complement: bool,
+    suppress: bool,
+) -> Vec<u8> {
+    let ranges = if mode == CutMode::Count {
+        Vec::new()
+    } else {
+        parse_ranges(spec).unwrap()
+    };
+    let cfg = CutConfig {
+        mode,
+        ranges: &ranges,
+        complement,
+        delim: b',',
+        output_delim: b",",
+        suppress_no_delim: suppress,
+        line_delim: b'\n',
+        quoted: true,
+    };
+    let mut out = Vec::new();
+    process_cut_data(input, &cfg, &mut out).unwrap();
+    out
+}
+
+#[test]
+fn test_quoted_fields_split() {
+    let fields: Vec<&[u8]> = QuotedFields::new(br#"a,"b,c","d ""e, f""",g"#, b',').collect();
+    assert_eq!(fields, [&b"a"[..], br#""b,c""#, br#""d ""e, f""""#, b"g"]);
+    let fields: Vec<&[u8]> = QuotedFields::new(b",", b',').collect();
+    assert_eq!(fields, [b"", b""]);
+    assert_eq!(QuotedFields::new(b"", b',').count(), 1);
+    // An unterminated quote runs to the end of the record
+    let fields: Vec<&[u8]> = QuotedFields::new(br#"a,"b,c"#, b',').collect();
+    assert_eq!(fields, [&b"a"[..], br#""b,c"#]);
+}
+
+#[test]
+fn test_quoted_embedded_delimiters_and_escaped_quotes() {
+    let input = b"id,\"Smith, John\",\"said \"\"hi, there\"\"\",x\nplain,row\n";
+    assert_eq!(
+        cut_quoted(input, CutMode::Fields, "2,3", false, false),
+        b"\"Smith, John\",\"said \"\"hi, there\"\"\"\nrow\n"
+    );
+    assert_eq!(
+        cut_quoted(input, CutMode::Fields, "2", true, false),
+        b"id,\"said \"\"hi, there\"\"\",x\nplain\n"
+    );
+    assert_eq!(
+        cut_quoted(input, CutMode::Count, "", false, false),
+        b"4\n2\n"
+    );
+}
+
+#[test]
+fn test_quoted_delimiter_only_inside_quotes() {
+    // Every delimiter is quoted: the record has one field, printed whole
+    // or suppressed by -s like any record without a delimiter
+    let input = b"\"a,b\",\n\"a,b\"\n\"open,quote\n";
+    assert_eq!(
+        cut_quoted(input, CutMode::Fields, "2", false, false),
+        b"\n\"a,b\"\n\"open,quote\n"
+    );
+    assert_eq!(
+        cut_quoted(input, CutMode::Fields, "1", false, true),
+        b"\"a,b\"\n"
+    );
+    let mut stats = CutStats::default();
+    let ranges = parse_ranges("1").unwrap();
+    stats.tally(
+        input,
+        &CutConfig {
+            mode: CutMode::Fields,
+            ranges: &ranges,
+            complement: false,
+            delim: b',',
+            output_delim: b",",
+            suppress_no_delim: true,
+            line_delim: b'\n',
+            quoted: true,
+        },
+    );
+    assert_eq!((stats.records, stats.emitted, stats.no_delim), (3, 1, 2));
+}
+
+#[test]
+fn test_quoted_line_delimiter_still_ends_record() {
+    // Quoted newlines are out of scope: records are split first, leaving
+    // each half with an unterminated quote
+    assert_eq!(
+        cut_quoted(b"a,\"b\nc\",d\n", CutMode::Fields, "2", false, false),
+        b"\"b\nc\",d\n"
+    );
+}
+
+#[test]
+fn test_quoted_matches_plain_without_quotes() {
+    for seed in 1..=30u64 {
+        let data = random_lines(seed, 200, 3);
+        for spec in ["1", "2", "1,3", "2-", "-2,4-5", "3-4,7-"] {
+            let ranges = parse_ranges(spec).unwrap();
+            for complement in [false, true] {
+                for suppress in [false, true] {
+                    let plain = CutConfig {
+                        mode: CutMode::Fields,
+                        ranges: &ranges,
+                        complement,
+                        delim: b',',
+                        output_delim: b",",
+                        suppress_no_delim: suppress,
+                        line_delim: b'\n',
+                        quoted: false,
+                    };
+                    let mut expected = Vec::new();
+                    process_cut_data(&data, &plain, &mut expected).unwrap();
+                    assert_eq!(
+                        cut_quoted(&data, CutMode::Fields, spec, complement, suppress),
+                        expected,
+                        "seed {} spec {} complement {} suppress {}",
+                        seed,
+                        spec,
+                        complement,
+                        suppress
+                    );
+                }
+            }
+        }
+        let plain = CutConfig {
+            mode: CutMode::Count,
+            ranges: &[],
+            complement: false,
+            delim: b',',
+            output_delim: b",",
+            suppress_no_delim: false,
+            line_delim: b'\n',
+            quoted: false,
+        };
+        let mut expected = Vec::new();
+        process_cut_data(&data, &plain, &mut expected).unwrap();
+        assert_eq!(
+            cut_quoted(&data, CutMode::Count, "", false, false),
+            expected
+        );
+    }
+}
+
+#[test]
+fn test_first_unterminated_quote_line() {
+    assert_eq!(
+        first_unterminated_quote_line(b"a\n\"b\"\n\"\"\"\nd\"\n", b'\n'),
+        Some(3)
+    );
+    assert_eq!(first_unterminated_quote_line(b"\"a,\"\"b\"\n", b'\n'), None);
+    assert_eq!(first_unterminated_quote_line(b"a\0\"b", b'\0'), Some(2));
+}
+
+#[test]
+fn test_resolve_header_fields_quoted() {
+    let header = br#"id,"last, first",age"#;
+    let fields = resolve_header_fields(header, b',', true, &["age"], false).unwrap();
+    assert_eq!(header_field_numbers(&fields), [(3, 3)]);
+    let fields = resolve_header_fields(header, b',', true, &["\"last, first\""], false).unwrap();
+    assert_eq!(header_field_numbers(&fields), [(2, 2)]);
+}
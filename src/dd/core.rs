@@ -783,5 +783,5 @@ Each FLAG symbol may be:
 
 /// Print version information for dd.
 pub fn print_version() {
-    eprintln!("dd (fcoreutils) {}", env!("CARGO_PKG_VERSION"));
+    crate::common::version::print_version("dd");
 }
@@ -16,18 +16,19 @@ impl Default for EchoConfig {
 }
 
 /// Parse the raw command-line arguments (after the program name) into an
-/// `EchoConfig` and the remaining text arguments.
+/// `EchoConfig` and the remaining text arguments. Arguments are bytes (any
+/// `AsRef<[u8]>`), so ones that are not valid UTF-8 pass through untouched.
 ///
 /// GNU echo uses *manual* flag parsing: a leading argument is only treated as
 /// flags if it starts with `-` and every subsequent character is one of `n`,
 /// `e`, or `E`.  Combined flags like `-neE` are valid.  Anything else (e.g.
 /// `-z`, `--foo`, or even `-`) is treated as a normal text argument.
-pub fn parse_echo_args(args: &[String]) -> (EchoConfig, &[String]) {
+pub fn parse_echo_args<S: AsRef<[u8]>>(args: &[S]) -> (EchoConfig, &[S]) {
     let mut config = EchoConfig::default();
     let mut idx = 0;
 
     for arg in args {
-        let bytes = arg.as_bytes();
+        let bytes = arg.as_ref();
         // Must start with '-' and have at least one flag character
         if bytes.len() < 2 || bytes[0] != b'-' {
             break;
@@ -54,12 +55,26 @@ pub fn parse_echo_args(args: &[String]) -> (EchoConfig, &[String]) {
     (config, &args[idx..])
 }
 
+/// Append `args` to `out` separated by single spaces, as echo prints them
+/// without escape interpretation. Shared with yes, which repeats the line.
+pub fn join_args<S: AsRef<[u8]>>(args: &[S], out: &mut Vec<u8>) {
+    for (i, arg) in args.iter().enumerate() {
+        if i > 0 {
+            out.push(b' ');
+        }
+        out.extend_from_slice(arg.as_ref());
+    }
+}
+
 /// Produce the output bytes for an echo invocation.
 ///
 /// The returned `Vec<u8>` contains exactly the bytes that should be written to
 /// stdout (including or excluding the trailing newline, and with escape
-/// sequences expanded when `config.interpret_escapes` is true).
-pub fn echo_output(args: &[String], config: &EchoConfig) -> Vec<u8> {
+/// sequences expanded when `config.interpret_escapes` is true). Escapes work
+/// on bytes: `\0NNN` and `\xHH` emit one raw byte each (so `\0377` is 0xFF,
+/// not the UTF-8 encoding of U+00FF) and all other input bytes are copied
+/// as they are.
+pub fn echo_output<S: AsRef<[u8]>>(args: &[S], config: &EchoConfig) -> Vec<u8> {
     let mut out: Vec<u8> = Vec::new();
 
     for (i, arg) in args.iter().enumerate() {
@@ -67,12 +82,12 @@ pub fn echo_output(args: &[String], config: &EchoConfig) -> Vec<u8> {
             out.push(b' ');
         }
         if config.interpret_escapes {
-            if !expand_escapes(arg.as_bytes(), &mut out) {
+            if !expand_escapes(arg.as_ref(), &mut out) {
                 // \c encountered — stop all output immediately
                 return out;
             }
         } else {
-            out.extend_from_slice(arg.as_bytes());
+            out.extend_from_slice(arg.as_ref());
         }
     }
 
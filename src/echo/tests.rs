@@ -85,6 +85,49 @@ fn test_echo_hex() {
     assert_eq!(output, b"A");
 }
 
+#[test]
+fn test_echo_high_byte_escapes_are_raw() {
+    let config = EchoConfig {
+        trailing_newline: false,
+        interpret_escapes: true,
+    };
+    for (arg, expected) in [
+        ("\\0377", &b"\xff"[..]),
+        ("\\xff", b"\xff"),
+        ("\\xFF\\x80", b"\xff\x80"),
+        ("\\0200\\0377", b"\x80\xff"),
+        // Escapes next to multibyte literals: literals keep their UTF-8
+        // bytes, escapes contribute single bytes
+        ("é\\0377é", b"\xc3\xa9\xff\xc3\xa9"),
+        ("€\\xe2\\x82\\xac", "€€".as_bytes()),
+        ("\\xc3é", b"\xc3\xc3\xa9"),
+    ] {
+        let output = echo_output(&[arg], &config);
+        assert_eq!(output, expected, "{:?}", arg);
+    }
+    // A lone high byte is not valid UTF-8, and was not turned into U+00FF
+    let output = echo_output(&["a\\0377b"], &config);
+    assert!(std::str::from_utf8(&output).is_err());
+    assert_ne!(output, "a\u{ff}b".as_bytes());
+    // Escapes that spell a valid sequence do produce valid UTF-8
+    let output = echo_output(&["\\xc3\\xa9"], &config);
+    assert_eq!(std::str::from_utf8(&output), Ok("é"));
+}
+
+#[test]
+fn test_echo_byte_arguments_copied_verbatim() {
+    let config = EchoConfig {
+        trailing_newline: true,
+        interpret_escapes: true,
+    };
+    let args: [&[u8]; 2] = [b"\xff\\t\xfe", b"\x80"];
+    assert_eq!(echo_output(&args, &config), b"\xff\t\xfe \x80\n");
+    let raw: [&[u8]; 2] = [b"-n", b"\xff"];
+    let (config, rest) = parse_echo_args(&raw);
+    assert!(!config.trailing_newline);
+    assert_eq!(rest, [&b"\xff"[..]]);
+}
+
 #[test]
 fn test_echo_no_args() {
     let args: Vec<String> = Vec::new();
@@ -220,7 +263,7 @@ fn test_escape_carriage_return() {
         interpret_escapes: true,
     };
     let output = echo_output(&args, &config);
-    assert_eq!(output, &[b'\r']);
+    assert_eq!(output, b"\r");
 }
 
 #[test]
@@ -335,6 +378,42 @@ fn test_cmd_invalid_flag_is_text() {
     assert_eq!(output.stdout, b"-z hello\n");
 }
 
+#[test]
+fn test_cmd_echo_high_bytes() {
+    let output = cmd().args(["-ne", "\\0377\\xffé"]).output().unwrap();
+    assert!(output.status.success());
+    assert_eq!(output.stdout, b"\xff\xff\xc3\xa9");
+}
+
+#[cfg(unix)]
+#[test]
+fn test_cmd_non_utf8_arguments() {
+    use std::ffi::OsStr;
+    use std::os::unix::ffi::OsStrExt;
+    let odd = OsStr::from_bytes(b"\xff\\x41\xfe");
+    for (flags, expected) in [
+        (&[][..], &b"\xff\\x41\xfe \xfe\n"[..]),
+        (&["-e"][..], b"\xffA\xfe \xfe\n"),
+    ] {
+        let output = cmd()
+            .args(flags)
+            .arg(odd)
+            .arg(OsStr::from_bytes(b"\xfe"))
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "{:?}", flags);
+        assert_eq!(output.stdout, expected, "{:?}", flags);
+        if let Ok(gnu) = Command::new("/bin/echo")
+            .args(flags)
+            .arg(odd)
+            .arg(OsStr::from_bytes(b"\xfe"))
+            .output()
+        {
+            assert_eq!(output.stdout, gnu.stdout, "{:?}", flags);
+        }
+    }
+}
+
 #[test]
 fn test_echo_matches_gnu() {
     // Compare basic output with GNU echo
@@ -345,3 +424,40 @@ fn test_echo_matches_gnu() {
         assert_eq!(ours.status.code(), gnu.status.code(), "Exit code mismatch");
     }
 }
+
+#[test]
+fn test_cmd_trivial_calls_match_gnu() {
+    // `echo` and `echo WORD` take the single-write fast path; `echo -n` and
+    // anything with a backslash go through normal parsing
+    let cases: &[&[&str]] = &[&[], &["hi"], &["-n"], &["-"], &["a\\tb"], &["-e"]];
+    for args in cases {
+        let ours = cmd().args(*args).output().unwrap();
+        assert_eq!(ours.status.code(), Some(0), "{:?}", args);
+        assert!(ours.stderr.is_empty(), "{:?}", args);
+        if let Ok(gnu) = Command::new("/bin/echo").args(*args).output() {
+            assert_eq!(ours.stdout, gnu.stdout, "{:?}", args);
+        }
+    }
+    assert_eq!(cmd().output().unwrap().stdout, b"\n");
+    assert_eq!(cmd().arg("hi").output().unwrap().stdout, b"hi\n");
+    assert_eq!(cmd().arg("-n").output().unwrap().stdout, b"");
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn test_cmd_write_error_same_on_both_paths() {
+    for args in [&["hi"][..], &["hi", "there"][..]] {
+        let output = cmd()
+            .args(args)
+            .stdout(std::fs::File::create("/dev/full").unwrap())
+            .output()
+            .unwrap();
+        assert_eq!(output.status.code(), Some(1), "{:?}", args);
+        assert!(
+            String::from_utf8_lossy(&output.stderr)
+                .starts_with("echo: write error: No space left on device"),
+            "{:?}",
+            args
+        );
+    }
+}
@@ -1,20 +1,28 @@
+use std::cmp::Ordering;
 use std::fmt;
 
+use num_bigint::{BigInt, Sign};
+use num_traits::{ToPrimitive, Zero};
 use regex::Regex;
 
 /// Exit code: expression is non-null and non-zero.
 pub const EXIT_SUCCESS: i32 = 0;
 /// Exit code: expression is null or zero.
 pub const EXIT_FAILURE: i32 = 1;
-/// Exit code: expression is syntactically invalid.
+/// Exit code: expression is invalid, including an invalid regex (as in
+/// GNU expr).
 pub const EXIT_EXPR_ERROR: i32 = 2;
-/// Exit code: regex error.
+/// Exit code: any other error, such as running out of memory.
 pub const EXIT_REGEX_ERROR: i32 = 3;
 
 /// A value produced by evaluating an expr expression.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum ExprValue {
     Integer(i64),
+    /// An integer outside the i64 range. Arithmetic is of arbitrary
+    /// precision, as in GNU expr built with GMP; a result that fits in an
+    /// i64 is an `Integer` again.
+    BigInt(BigInt),
     Str(String),
 }
 
@@ -22,46 +30,137 @@ impl fmt::Display for ExprValue {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             ExprValue::Integer(n) => write!(f, "{}", n),
+            ExprValue::BigInt(n) => write!(f, "{}", n),
             ExprValue::Str(s) => write!(f, "{}", s),
         }
     }
 }
 
 impl ExprValue {
-    /// Returns true if this value is considered "null" (empty string or integer 0).
+    /// Returns true if this value is considered "null": the empty string or
+    /// zero, including spellings of it such as "00" and "-0".
     pub fn is_null(&self) -> bool {
         match self {
             ExprValue::Integer(n) => *n == 0,
-            ExprValue::Str(s) => s.is_empty() || s == "0",
+            ExprValue::BigInt(n) => n.is_zero(),
+            ExprValue::Str(s) => {
+                let digits = s.strip_prefix('-').unwrap_or(s);
+                s.is_empty() || (!digits.is_empty() && digits.bytes().all(|b| b == b'0'))
+            }
+        }
+    }
+
+    /// An integer value as an `ExprValue`, held as `Integer` if it fits.
+    fn from_big(n: BigInt) -> Self {
+        match n.to_i64() {
+            Some(n) => ExprValue::Integer(n),
+            None => ExprValue::BigInt(n),
         }
     }
 
     /// Try to interpret this value as an integer.
-    pub fn as_integer(&self) -> Option<i64> {
+    fn as_number(&self) -> Option<Number> {
         match self {
-            ExprValue::Integer(n) => Some(*n),
+            ExprValue::Integer(n) => Some(Number::Small(*n)),
+            ExprValue::BigInt(n) => Some(Number::Big(n.clone())),
             ExprValue::Str(s) => parse_integer(s),
         }
     }
+
+    /// Interpret this value as a substr position or length. An integer too
+    /// large for i64 saturates instead of failing: it is past the end of
+    /// any string all the same.
+    fn as_size(&self) -> Option<i64> {
+        self.as_number().map(|n| match n {
+            Number::Small(n) => n,
+            Number::Big(n) if n.sign() == Sign::Minus => i64::MIN,
+            Number::Big(_) => i64::MAX,
+        })
+    }
 }
 
-/// Parse an integer from a string, accepting optional leading sign and digits only.
-fn parse_integer(s: &str) -> Option<i64> {
-    let s = s.trim();
-    if s.is_empty() {
-        return None;
+/// An integer operand, kept as an i64 while it fits so that everyday
+/// arithmetic does not allocate.
+enum Number {
+    Small(i64),
+    Big(BigInt),
+}
+
+impl Number {
+    fn to_big(&self) -> BigInt {
+        match self {
+            Number::Small(n) => BigInt::from(*n),
+            Number::Big(n) => n.clone(),
+        }
     }
-    let (sign, digits) = if let Some(rest) = s.strip_prefix('-') {
-        (-1i64, rest)
-    } else if let Some(rest) = s.strip_prefix('+') {
-        (1i64, rest)
-    } else {
-        (1i64, s)
-    };
-    if digits.is_empty() || !digits.chars().all(|c| c.is_ascii_digit()) {
+
+    fn compare(&self, other: &Number) -> Ordering {
+        match (self, other) {
+            (Number::Small(a), Number::Small(b)) => a.cmp(b),
+            _ => self.to_big().cmp(&other.to_big()),
+        }
+    }
+}
+
+/// Parse an integer of any length from a string: an optional '-' and
+/// digits only. As in GNU expr, a '+' sign or surrounding blanks make it a
+/// string.
+fn parse_integer(s: &str) -> Option<Number> {
+    let digits = s.strip_prefix('-').unwrap_or(s);
+    if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
         return None;
     }
-    digits.parse::<i64>().ok().map(|v| sign * v)
+    match s.parse::<i64>() {
+        Ok(n) => Some(Number::Small(n)),
+        Err(_) => s.parse::<BigInt>().ok().map(Number::Big),
+    }
+}
+
+/// Apply an arithmetic operator. Results are exact: i64 arithmetic is
+/// tried first and redone in arbitrary precision if it overflows.
+/// Division truncates toward zero and the remainder takes the sign of the
+/// dividend, as in C and GMP's tdiv.
+fn arithmetic(op: &str, left: &Number, right: &Number) -> Result<ExprValue, ExprError> {
+    if matches!(op, "/" | "%") && matches!(right, Number::Small(0)) {
+        return Err(ExprError::DivisionByZero);
+    }
+    if let (Number::Small(a), Number::Small(b)) = (left, right) {
+        let small = match op {
+            "+" => a.checked_add(*b),
+            "-" => a.checked_sub(*b),
+            "*" => a.checked_mul(*b),
+            "/" => a.checked_div(*b),
+            "%" => a.checked_rem(*b),
+            _ => unreachable!(),
+        };
+        if let Some(n) = small {
+            return Ok(ExprValue::Integer(n));
+        }
+    }
+    let (a, b) = (left.to_big(), right.to_big());
+    Ok(ExprValue::from_big(match op {
+        "+" => a + b,
+        "-" => a - b,
+        "*" => a * b,
+        "/" => a / b,
+        "%" => a % b,
+        _ => unreachable!(),
+    }))
+}
+
+/// The value of an operand token. Operands are strings; one that spells an
+/// integer the canonical way is held as that integer, which prints the same.
+/// Others, such as "007", keep their spelling, and only arithmetic and
+/// comparisons read them as numbers.
+fn operand(tok: &str) -> ExprValue {
+    // Canonical: no leading zero but in "0" itself, and no "-0"
+    let digits = tok.strip_prefix('-').unwrap_or(tok);
+    let canonical = !digits.starts_with('0') || tok == "0";
+    match parse_integer(tok) {
+        Some(Number::Small(n)) if canonical => ExprValue::Integer(n),
+        Some(Number::Big(n)) if canonical => ExprValue::BigInt(n),
+        _ => ExprValue::Str(tok.to_string()),
+    }
 }
 
 /// Errors that can occur during expression evaluation.
@@ -92,44 +191,238 @@ impl fmt::Display for ExprError {
 }
 
 impl ExprError {
-    /// Returns the exit code for this error type.
+    /// Returns the exit code for this error type. Every error is an
+    /// invalid expression; GNU expr exits 2 for a bad regex too.
     pub fn exit_code(&self) -> i32 {
-        match self {
-            ExprError::RegexError(_) => EXIT_REGEX_ERROR,
-            _ => EXIT_EXPR_ERROR,
+        EXIT_EXPR_ERROR
+    }
+}
+
+/// One step of an evaluation, reported to the tracer of `evaluate_expr_traced`.
+/// Events arrive in evaluation order, so operands are reported before the
+/// operator that consumes them; `depth` is the parenthesis/function nesting
+/// level and gives the trace its tree shape.
+#[derive(Debug, Clone, Copy)]
+pub enum TraceEvent<'e> {
+    /// A binary operator: `|`, `&`, a comparison or an arithmetic operator.
+    Binary {
+        depth: usize,
+        op: &'e str,
+        left: &'e ExprValue,
+        right: &'e ExprValue,
+        result: &'e ExprValue,
+    },
+    /// `STRING : REGEX` or `match STRING REGEX`.
+    Match {
+        depth: usize,
+        string: &'e str,
+        pattern: &'e str,
+        /// The BRE converted to the (anchored) Rust regex that was run.
+        regex: &'e str,
+        matched: bool,
+        result: &'e ExprValue,
+    },
+    /// `length`, `substr` or `index`.
+    Function {
+        depth: usize,
+        name: &'e str,
+        args: &'e [&'e ExprValue],
+        result: &'e ExprValue,
+    },
+}
+
+/// Values are shown quoted when they are strings, so `"1"` and `1` differ.
+struct TraceValue<'e>(&'e ExprValue);
+
+impl fmt::Display for TraceValue<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0 {
+            ExprValue::Integer(n) => write!(f, "{}", n),
+            ExprValue::BigInt(n) => write!(f, "{}", n),
+            ExprValue::Str(s) => write!(f, "{:?}", s),
+        }
+    }
+}
+
+impl fmt::Display for TraceEvent<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            TraceEvent::Binary {
+                depth,
+                op,
+                left,
+                right,
+                result,
+            } => write!(
+                f,
+                "{:indent$}{} {} {} -> {}",
+                "",
+                TraceValue(left),
+                op,
+                TraceValue(right),
+                TraceValue(result),
+                indent = depth * 2
+            ),
+            TraceEvent::Match {
+                depth,
+                string,
+                pattern,
+                regex,
+                matched,
+                result,
+            } => write!(
+                f,
+                "{:indent$}{:?} : {:?} regex {:?} {} -> {}",
+                "",
+                string,
+                pattern,
+                regex,
+                if matched { "matched" } else { "no match" },
+                TraceValue(result),
+                indent = depth * 2
+            ),
+            TraceEvent::Function {
+                depth,
+                name,
+                args,
+                result,
+            } => {
+                write!(f, "{:indent$}{}", "", name, indent = depth * 2)?;
+                for arg in args {
+                    write!(f, " {}", TraceValue(arg))?;
+                }
+                write!(f, " -> {}", TraceValue(result))
+            }
         }
     }
 }
 
+/// Callback receiving trace events; see `evaluate_expr_traced`.
+pub type Tracer<'t> = &'t mut dyn FnMut(TraceEvent<'_>);
+
+/// How an expression is evaluated; the default is GNU expr's behavior.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ExprOptions {
+    /// Strict POSIX: the GNU keywords `match`, `substr`, `index` and
+    /// `length` are plain string operands, and `\+` and `\?` in a regex
+    /// match a literal '+' and '?'.
+    pub posix: bool,
+}
+
 /// Recursive descent parser for expr expressions.
-struct ExprParser<'a> {
-    args: &'a [String],
+struct ExprParser<'a, 't, S> {
+    args: &'a [S],
     pos: usize,
+    options: ExprOptions,
+    trace: Option<Tracer<'t>>,
+    depth: usize,
+    /// False while parsing an operand whose value cannot matter: the right
+    /// side of `|` after a non-null left, or of `&` after a null one. As in
+    /// GNU expr it is still parsed, but not evaluated, so it raises no
+    /// arithmetic or regex errors and is left out of the trace.
+    evaluate: bool,
 }
 
-impl<'a> ExprParser<'a> {
-    fn new(args: &'a [String]) -> Self {
-        ExprParser { args, pos: 0 }
+impl<'a, 't, S: AsRef<str>> ExprParser<'a, 't, S> {
+    fn new(args: &'a [S], options: ExprOptions, trace: Option<Tracer<'t>>) -> Self {
+        ExprParser {
+            args,
+            pos: 0,
+            options,
+            trace,
+            depth: 0,
+            evaluate: true,
+        }
     }
 
-    /// Peek at the current token without consuming it.
-    fn peek(&self) -> Option<&str> {
-        if self.pos < self.args.len() {
-            Some(self.args[self.pos].as_str())
-        } else {
-            None
+    fn emit(&mut self, event: TraceEvent<'_>) {
+        if !self.evaluate {
+            return;
+        }
+        if let Some(trace) = self.trace.as_mut() {
+            trace(event);
         }
     }
 
-    /// Consume the current token and advance.
-    fn consume(&mut self) -> Option<&str> {
-        if self.pos < self.args.len() {
-            let tok = self.args[self.pos].as_str();
-            self.pos += 1;
-            Some(tok)
-        } else {
-            None
+    /// Parse with `parse`, evaluating only if `evaluate` (and the enclosing
+    /// operand is evaluated).
+    fn parse_maybe(
+        &mut self,
+        evaluate: bool,
+        parse: fn(&mut Self) -> Result<ExprValue, ExprError>,
+    ) -> Result<ExprValue, ExprError> {
+        let saved = self.evaluate;
+        self.evaluate = saved && evaluate;
+        let result = parse(self);
+        self.evaluate = saved;
+        result
+    }
+
+    fn emit_binary(&mut self, op: &str, left: &ExprValue, right: &ExprValue, result: &ExprValue) {
+        let depth = self.depth;
+        self.emit(TraceEvent::Binary {
+            depth,
+            op,
+            left,
+            right,
+            result,
+        });
+    }
+
+    fn emit_function(&mut self, name: &str, args: &[&ExprValue], result: &ExprValue) {
+        let depth = self.depth;
+        self.emit(TraceEvent::Function {
+            depth,
+            name,
+            args,
+            result,
+        });
+    }
+
+    /// Run `do_match`, reporting the converted regex and outcome when tracing.
+    fn match_values(&mut self, string: &str, pattern: &str) -> Result<ExprValue, ExprError> {
+        if !self.evaluate {
+            return Ok(ExprValue::Integer(0));
+        }
+        let (result, matched) = do_match(string, pattern, self.options)?;
+        if self.trace.is_some() {
+            let regex = bre_to_rust_regex(pattern, self.options);
+            let depth = self.depth;
+            self.emit(TraceEvent::Match {
+                depth,
+                string,
+                pattern,
+                regex: &regex,
+                matched,
+                result: &result,
+            });
         }
+        Ok(result)
+    }
+
+    /// Parse an operand of a parenthesized group or keyword function one
+    /// level deeper, so its trace events nest under the enclosing operation.
+    fn parse_nested(
+        &mut self,
+        parse: fn(&mut Self) -> Result<ExprValue, ExprError>,
+    ) -> Result<ExprValue, ExprError> {
+        self.depth += 1;
+        let result = parse(self);
+        self.depth -= 1;
+        result
+    }
+
+    /// Peek at the current token without consuming it.
+    fn peek(&self) -> Option<&'a str> {
+        let args = self.args;
+        args.get(self.pos).map(AsRef::as_ref)
+    }
+
+    /// Consume the current token and advance.
+    fn consume(&mut self) -> Option<&'a str> {
+        let tok = self.peek()?;
+        self.pos += 1;
+        Some(tok)
     }
 
     /// Expect a specific token, returning an error if not found.
@@ -150,8 +443,17 @@ impl<'a> ExprParser<'a> {
         let mut left = self.parse_and()?;
         while self.peek() == Some("|") {
             self.consume();
-            let right = self.parse_and()?;
-            left = if !left.is_null() { left } else { right };
+            let right = self.parse_maybe(left.is_null(), Self::parse_and)?;
+            // Both null gives 0, whatever spelling of null the right had
+            let result = if !left.is_null() {
+                left.clone()
+            } else if !right.is_null() {
+                right.clone()
+            } else {
+                ExprValue::Integer(0)
+            };
+            self.emit_binary("|", &left, &right, &result);
+            left = result;
         }
         Ok(left)
     }
@@ -162,12 +464,14 @@ impl<'a> ExprParser<'a> {
         let mut left = self.parse_comparison()?;
         while self.peek() == Some("&") {
             self.consume();
-            let right = self.parse_comparison()?;
-            left = if !left.is_null() && !right.is_null() {
-                left
+            let right = self.parse_maybe(!left.is_null(), Self::parse_comparison)?;
+            let result = if !left.is_null() && !right.is_null() {
+                left.clone()
             } else {
                 ExprValue::Integer(0)
             };
+            self.emit_binary("&", &left, &right, &result);
+            left = result;
         }
         Ok(left)
     }
@@ -180,10 +484,11 @@ impl<'a> ExprParser<'a> {
             self.peek(),
             Some("<") | Some("<=") | Some("=") | Some("!=") | Some(">=") | Some(">")
         ) {
-            let op = self.consume().unwrap().to_string();
+            let op = self.consume().unwrap();
             let right = self.parse_addition()?;
-            let result = compare_values(&left, &right, &op);
-            left = ExprValue::Integer(if result { 1 } else { 0 });
+            let result = ExprValue::Integer(i64::from(compare_values(&left, &right, op)));
+            self.emit_binary(op, &left, &right, &result);
+            left = result;
         }
         Ok(left)
     }
@@ -193,21 +498,17 @@ impl<'a> ExprParser<'a> {
     fn parse_addition(&mut self) -> Result<ExprValue, ExprError> {
         let mut left = self.parse_multiplication()?;
         while matches!(self.peek(), Some("+") | Some("-")) {
-            let op = self.consume().unwrap().to_string();
+            let op = self.consume().unwrap();
             let right = self.parse_multiplication()?;
-            let lv = left.as_integer().ok_or(ExprError::NonIntegerArgument)?;
-            let rv = right.as_integer().ok_or(ExprError::NonIntegerArgument)?;
-            left = match op.as_str() {
-                "+" => ExprValue::Integer(
-                    lv.checked_add(rv)
-                        .ok_or_else(|| ExprError::Syntax("integer result too large".into()))?,
-                ),
-                "-" => ExprValue::Integer(
-                    lv.checked_sub(rv)
-                        .ok_or_else(|| ExprError::Syntax("integer result too large".into()))?,
-                ),
-                _ => unreachable!(),
-            };
+            if !self.evaluate {
+                left = ExprValue::Integer(0);
+                continue;
+            }
+            let lv = left.as_number().ok_or(ExprError::NonIntegerArgument)?;
+            let rv = right.as_number().ok_or(ExprError::NonIntegerArgument)?;
+            let result = arithmetic(op, &lv, &rv)?;
+            self.emit_binary(op, &left, &right, &result);
+            left = result;
         }
         Ok(left)
     }
@@ -217,118 +518,95 @@ impl<'a> ExprParser<'a> {
     fn parse_multiplication(&mut self) -> Result<ExprValue, ExprError> {
         let mut left = self.parse_match()?;
         while matches!(self.peek(), Some("*") | Some("/") | Some("%")) {
-            let op = self.consume().unwrap().to_string();
+            let op = self.consume().unwrap();
             let right = self.parse_match()?;
-            let lv = left.as_integer().ok_or(ExprError::NonIntegerArgument)?;
-            let rv = right.as_integer().ok_or(ExprError::NonIntegerArgument)?;
-            left = match op.as_str() {
-                "*" => ExprValue::Integer(
-                    lv.checked_mul(rv)
-                        .ok_or_else(|| ExprError::Syntax("integer result too large".into()))?,
-                ),
-                "/" => {
-                    if rv == 0 {
-                        return Err(ExprError::DivisionByZero);
-                    }
-                    ExprValue::Integer(
-                        lv.checked_div(rv)
-                            .ok_or_else(|| ExprError::Syntax("integer result too large".into()))?,
-                    )
-                }
-                "%" => {
-                    if rv == 0 {
-                        return Err(ExprError::DivisionByZero);
-                    }
-                    ExprValue::Integer(
-                        lv.checked_rem(rv)
-                            .ok_or_else(|| ExprError::Syntax("integer result too large".into()))?,
-                    )
-                }
-                _ => unreachable!(),
-            };
+            if !self.evaluate {
+                left = ExprValue::Integer(0);
+                continue;
+            }
+            let lv = left.as_number().ok_or(ExprError::NonIntegerArgument)?;
+            let rv = right.as_number().ok_or(ExprError::NonIntegerArgument)?;
+            let result = arithmetic(op, &lv, &rv)?;
+            self.emit_binary(op, &left, &right, &result);
+            left = result;
         }
         Ok(left)
     }
 
-    /// Parse match/colon expression.
-    /// MATCH: PRIMARY ( ':' PRIMARY )?
+    /// Parse match/colon expression, left-associative like the others.
+    /// MATCH: PRIMARY ( ':' PRIMARY )*
     fn parse_match(&mut self) -> Result<ExprValue, ExprError> {
-        let left = self.parse_primary()?;
-        if self.peek() == Some(":") {
+        let mut left = self.parse_primary()?;
+        while self.peek() == Some(":") {
             self.consume();
             let right = self.parse_primary()?;
-            let pattern_str = match &right {
-                ExprValue::Str(s) => s.clone(),
-                ExprValue::Integer(n) => n.to_string(),
-            };
-            let string = match &left {
-                ExprValue::Str(s) => s.clone(),
-                ExprValue::Integer(n) => n.to_string(),
-            };
-            return do_match(&string, &pattern_str);
+            let pattern_str = right.to_string();
+            let string = left.to_string();
+            left = self.match_values(&string, &pattern_str)?;
         }
         Ok(left)
     }
 
     /// Parse primary expression: keyword functions, parenthesized expressions, or atoms.
     fn parse_primary(&mut self) -> Result<ExprValue, ExprError> {
+        // In POSIX mode the keywords fall through to plain operands
+        let keywords = !self.options.posix;
         match self.peek() {
-            None => Err(ExprError::MissingOperand),
+            None => match self.pos.checked_sub(1) {
+                // GNU names the operator left dangling at the end
+                Some(prev) => Err(ExprError::Syntax(format!(
+                    "missing argument after '{}'",
+                    self.args[prev].as_ref()
+                ))),
+                None => Err(ExprError::MissingOperand),
+            },
+            Some(")") => Err(ExprError::Syntax("unexpected ')'".to_string())),
             Some("(") => {
                 self.consume();
-                let val = self.parse_or()?;
+                let val = self.parse_nested(Self::parse_or)?;
                 self.expect(")")?;
                 Ok(val)
             }
-            Some("match") => {
+            Some("match") if keywords => {
                 self.consume();
-                let string_val = self.parse_primary()?;
-                let pattern_val = self.parse_primary()?;
-                let string = match &string_val {
-                    ExprValue::Str(s) => s.clone(),
-                    ExprValue::Integer(n) => n.to_string(),
-                };
-                let pattern = match &pattern_val {
-                    ExprValue::Str(s) => s.clone(),
-                    ExprValue::Integer(n) => n.to_string(),
-                };
-                do_match(&string, &pattern)
+                let string_val = self.parse_nested(Self::parse_primary)?;
+                let pattern_val = self.parse_nested(Self::parse_primary)?;
+                let string = string_val.to_string();
+                let pattern = pattern_val.to_string();
+                self.match_values(&string, &pattern)
             }
-            Some("substr") => {
+            Some("substr") if keywords => {
                 self.consume();
-                let string_val = self.parse_primary()?;
-                let pos_val = self.parse_primary()?;
-                let len_val = self.parse_primary()?;
-                let string = match &string_val {
-                    ExprValue::Str(s) => s.clone(),
-                    ExprValue::Integer(n) => n.to_string(),
+                let string_val = self.parse_nested(Self::parse_primary)?;
+                let pos_val = self.parse_nested(Self::parse_primary)?;
+                let len_val = self.parse_nested(Self::parse_primary)?;
+                let string = string_val.to_string();
+                // GNU gives the empty string, not an error, for a
+                // position or length that is not a number
+                let result = match (pos_val.as_size(), len_val.as_size()) {
+                    (Some(pos), Some(len)) => do_substr(&string, pos, len),
+                    _ => ExprValue::Str(String::new()),
                 };
-                let pos = pos_val.as_integer().ok_or(ExprError::NonIntegerArgument)?;
-                let len = len_val.as_integer().ok_or(ExprError::NonIntegerArgument)?;
-                Ok(do_substr(&string, pos, len))
+                self.emit_function("substr", &[&string_val, &pos_val, &len_val], &result);
+                Ok(result)
             }
-            Some("index") => {
+            Some("index") if keywords => {
                 self.consume();
-                let string_val = self.parse_primary()?;
-                let chars_val = self.parse_primary()?;
-                let string = match &string_val {
-                    ExprValue::Str(s) => s.clone(),
-                    ExprValue::Integer(n) => n.to_string(),
-                };
-                let chars = match &chars_val {
-                    ExprValue::Str(s) => s.clone(),
-                    ExprValue::Integer(n) => n.to_string(),
-                };
-                Ok(do_index(&string, &chars))
+                let string_val = self.parse_nested(Self::parse_primary)?;
+                let chars_val = self.parse_nested(Self::parse_primary)?;
+                let string = string_val.to_string();
+                let chars = chars_val.to_string();
+                let result = do_index(&string, &chars);
+                self.emit_function("index", &[&string_val, &chars_val], &result);
+                Ok(result)
             }
-            Some("length") => {
+            Some("length") if keywords => {
                 self.consume();
-                let val = self.parse_primary()?;
-                let s = match &val {
-                    ExprValue::Str(s) => s.clone(),
-                    ExprValue::Integer(n) => n.to_string(),
-                };
-                Ok(ExprValue::Integer(s.len() as i64))
+                let val = self.parse_nested(Self::parse_primary)?;
+                let s = val.to_string();
+                let result = ExprValue::Integer(s.len() as i64);
+                self.emit_function("length", &[&val], &result);
+                Ok(result)
             }
             Some("+") => {
                 // GNU expr extension: '+' is a quoting prefix that treats the
@@ -336,25 +614,13 @@ impl<'a> ExprParser<'a> {
                 // interpreted as a keyword (match, length, substr, index).
                 self.consume();
                 match self.consume() {
-                    Some(tok) => {
-                        let tok = tok.to_string();
-                        if let Some(n) = parse_integer(&tok) {
-                            Ok(ExprValue::Integer(n))
-                        } else {
-                            Ok(ExprValue::Str(tok))
-                        }
-                    }
+                    Some(tok) => Ok(operand(tok)),
                     None => Err(ExprError::Syntax("missing argument after '+'".to_string())),
                 }
             }
             _ => {
                 // Atom: a literal string or number.
-                let tok = self.consume().unwrap().to_string();
-                if let Some(n) = parse_integer(&tok) {
-                    Ok(ExprValue::Integer(n))
-                } else {
-                    Ok(ExprValue::Str(tok))
-                }
+                Ok(operand(self.consume().unwrap()))
             }
         }
     }
@@ -363,17 +629,15 @@ impl<'a> ExprParser<'a> {
 /// Compare two ExprValues. If both are integers, compare numerically;
 /// otherwise compare as strings lexicographically.
 fn compare_values(left: &ExprValue, right: &ExprValue, op: &str) -> bool {
-    let left_int = left.as_integer();
-    let right_int = right.as_integer();
-
-    if let (Some(lv), Some(rv)) = (left_int, right_int) {
+    if let (Some(lv), Some(rv)) = (left.as_number(), right.as_number()) {
+        let ord = lv.compare(&rv);
         match op {
-            "<" => lv < rv,
-            "<=" => lv <= rv,
-            "=" => lv == rv,
-            "!=" => lv != rv,
-            ">=" => lv >= rv,
-            ">" => lv > rv,
+            "<" => ord.is_lt(),
+            "<=" => ord.is_le(),
+            "=" => ord.is_eq(),
+            "!=" => ord.is_ne(),
+            ">=" => ord.is_ge(),
+            ">" => ord.is_gt(),
             _ => false,
         }
     } else {
@@ -397,31 +661,34 @@ fn compare_values(left: &ExprValue, right: &ExprValue, op: &str) -> bool {
 /// - `\{` and `\}` are interval delimiters
 /// - `(` and `)` are literal in BRE
 /// - `{` and `}` are literal in BRE
-/// - `\+`, `\?` are special in BRE (some implementations)
+/// - `\+`, `\?` are special in BRE (GNU), but literal with `options.posix`
 /// - `+`, `?` are literal in BRE
 /// - The match is always anchored at the beginning (as if `^` is prepended).
-///
-/// When inside a `\(` ... `\)` group, `\.` is treated as a literal dot insertion
-/// that does not consume input. It is excluded from the regex and instead tracked
-/// separately so that the match result can be reconstructed with literal dots.
-fn bre_to_rust_regex(pattern: &str) -> String {
+fn bre_to_rust_regex(pattern: &str, options: ExprOptions) -> String {
     let mut result = String::with_capacity(pattern.len() + 2);
     // BRE patterns in expr are implicitly anchored at the start
     result.push('^');
 
     let bytes = pattern.as_bytes();
     let mut i = 0;
-    let mut group_depth = 0u32;
+    // A `*` at the start of the pattern or of a group has nothing to
+    // repeat, so BRE takes it literally
+    let mut at_start = true;
     while i < bytes.len() {
+        if bytes[i] == b'*' && at_start {
+            result.push_str("\\*");
+            at_start = false;
+            i += 1;
+            continue;
+        }
+        let prev = i;
         if bytes[i] == b'\\' && i + 1 < bytes.len() {
             match bytes[i + 1] {
                 b'(' => {
-                    group_depth += 1;
                     result.push('(');
                     i += 2;
                 }
                 b')' => {
-                    group_depth = group_depth.saturating_sub(1);
                     result.push(')');
                     i += 2;
                 }
@@ -433,11 +700,11 @@ fn bre_to_rust_regex(pattern: &str) -> String {
                     result.push('}');
                     i += 2;
                 }
-                b'+' => {
+                b'+' if !options.posix => {
                     result.push('+');
                     i += 2;
                 }
-                b'?' => {
+                b'?' if !options.posix => {
                     result.push('?');
                     i += 2;
                 }
@@ -455,18 +722,7 @@ fn bre_to_rust_regex(pattern: &str) -> String {
                     result.push_str("\\t");
                     i += 2;
                 }
-                b'.' => {
-                    if group_depth > 0 {
-                        // Inside a group, \. is a literal dot insertion that
-                        // does not consume input — skip it in the regex.
-                        i += 2;
-                    } else {
-                        result.push('\\');
-                        result.push('.');
-                        i += 2;
-                    }
-                }
-                b'*' | b'\\' | b'[' | b']' | b'^' | b'$' | b'|' => {
+                b'.' | b'*' | b'\\' | b'[' | b']' | b'^' | b'$' | b'|' => {
                     result.push('\\');
                     result.push(bytes[i + 1] as char);
                     i += 2;
@@ -521,55 +777,15 @@ fn bre_to_rust_regex(pattern: &str) -> String {
                 }
             }
         }
+        at_start = match &bytes[prev..i] {
+            b"\\(" => true,
+            b"^" => prev == 0,
+            _ => false,
+        };
     }
     result
 }
 
-/// Extract a template for the first `\(` ... `\)` group in a BRE pattern.
-/// The template is a list of entries: `true` means a literal dot insertion (from `\.`),
-/// `false` means a character matched from the input.
-/// Returns None if there is no group.
-fn bre_group_template(pattern: &str) -> Option<Vec<bool>> {
-    let bytes = pattern.as_bytes();
-    let mut i = 0;
-    let mut in_group = false;
-    let mut template = Vec::new();
-
-    while i < bytes.len() {
-        if bytes[i] == b'\\' && i + 1 < bytes.len() {
-            match bytes[i + 1] {
-                b'(' if !in_group => {
-                    in_group = true;
-                    i += 2;
-                }
-                b')' if in_group => {
-                    return Some(template);
-                }
-                b'.' if in_group => {
-                    // \. inside group = literal dot insertion (not consuming input)
-                    template.push(true);
-                    i += 2;
-                }
-                _ if in_group => {
-                    // Any other escape inside the group consumes a character from input
-                    template.push(false);
-                    i += 2;
-                }
-                _ => {
-                    i += 2;
-                }
-            }
-        } else if in_group {
-            // Regular character inside group consumes input
-            template.push(false);
-            i += 1;
-        } else {
-            i += 1;
-        }
-    }
-    if in_group { Some(template) } else { None }
-}
-
 /// Check whether a BRE pattern contains `\(` ... `\)` groups.
 fn bre_has_groups(pattern: &str) -> bool {
     let bytes = pattern.as_bytes();
@@ -585,12 +801,16 @@ fn bre_has_groups(pattern: &str) -> bool {
 
 /// Perform regex match operation.
 /// If the pattern has `\(` ... `\)` groups, returns the first captured group (or empty string).
-/// When the group contains `\.`, literal dots are inserted into the result at those positions
-/// without consuming characters from the input.
 /// Otherwise returns the number of matched characters (or 0).
-fn do_match(string: &str, pattern: &str) -> Result<ExprValue, ExprError> {
+/// The flag tells whether the regex matched at all, which the value alone
+/// cannot (an empty match and no match both give 0 or "").
+fn do_match(
+    string: &str,
+    pattern: &str,
+    options: ExprOptions,
+) -> Result<(ExprValue, bool), ExprError> {
     let has_groups = bre_has_groups(pattern);
-    let rust_pattern = bre_to_rust_regex(pattern);
+    let rust_pattern = bre_to_rust_regex(pattern, options);
 
     let re = Regex::new(&rust_pattern)
         .map_err(|e| ExprError::RegexError(format!("Invalid regular expression: {}", e)))?;
@@ -598,56 +818,41 @@ fn do_match(string: &str, pattern: &str) -> Result<ExprValue, ExprError> {
     match re.captures(string) {
         Some(caps) => {
             if has_groups {
-                // Return the first captured group, expanded with literal dot insertions
-                match caps.get(1) {
-                    Some(m) => {
-                        let captured = m.as_str();
-                        if let Some(template) = bre_group_template(pattern) {
-                            let mut result = String::new();
-                            let mut char_iter = captured.chars();
-                            for is_literal_dot in &template {
-                                if *is_literal_dot {
-                                    result.push('.');
-                                } else if let Some(ch) = char_iter.next() {
-                                    result.push(ch);
-                                }
-                            }
-                            Ok(ExprValue::Str(result))
-                        } else {
-                            Ok(ExprValue::Str(captured.to_string()))
-                        }
-                    }
-                    None => Ok(ExprValue::Str(String::new())),
-                }
+                // Return the first captured group
+                let captured = caps.get(1).map_or("", |m| m.as_str());
+                Ok((ExprValue::Str(captured.to_string()), true))
             } else {
                 // Return the number of matched characters
                 let m = caps.get(0).unwrap();
-                Ok(ExprValue::Integer(m.as_str().len() as i64))
+                Ok((ExprValue::Integer(m.as_str().len() as i64), true))
             }
         }
         None => {
             if has_groups {
-                Ok(ExprValue::Str(String::new()))
+                Ok((ExprValue::Str(String::new()), false))
             } else {
-                Ok(ExprValue::Integer(0))
+                Ok((ExprValue::Integer(0), false))
             }
         }
     }
 }
 
 /// Perform the substr operation: extract a substring.
-/// Position is 1-based. If pos or len <= 0 or pos > length, returns empty string.
+/// Position is 1-based. If pos or len <= 0 or pos > length, returns empty
+/// string; a length running past the end stops there. Positions count
+/// bytes, so a cut through a multibyte character leaves U+FFFD in its place.
 fn do_substr(string: &str, pos: i64, len: i64) -> ExprValue {
+    let bytes = string.as_bytes();
     if pos <= 0 || len <= 0 {
         return ExprValue::Str(String::new());
     }
-    let start = (pos - 1) as usize;
-    let slen = string.len();
-    if start >= slen {
-        return ExprValue::Str(String::new());
-    }
-    let end = (start + len as usize).min(slen);
-    ExprValue::Str(string[start..end].to_string())
+    let start = match usize::try_from(pos - 1) {
+        Ok(start) if start < bytes.len() => start,
+        _ => return ExprValue::Str(String::new()),
+    };
+    let len = usize::try_from(len).unwrap_or(usize::MAX);
+    let end = start.saturating_add(len).min(bytes.len());
+    ExprValue::Str(String::from_utf8_lossy(&bytes[start..end]).into_owned())
 }
 
 /// Perform the index operation: find the position of the first character in CHARS
@@ -662,16 +867,34 @@ fn do_index(string: &str, chars: &str) -> ExprValue {
 }
 
 /// Evaluate an expr expression from command-line arguments.
-pub fn evaluate_expr(args: &[String]) -> Result<ExprValue, ExprError> {
+pub fn evaluate_expr<S: AsRef<str>>(args: &[S]) -> Result<ExprValue, ExprError> {
+    evaluate_expr_traced(args, None)
+}
+
+/// Evaluate like `evaluate_expr`, passing every operator application to
+/// `trace` as it happens. The result is identical with or without a tracer.
+pub fn evaluate_expr_traced<S: AsRef<str>>(
+    args: &[S],
+    trace: Option<Tracer<'_>>,
+) -> Result<ExprValue, ExprError> {
+    evaluate_expr_with(args, ExprOptions::default(), trace)
+}
+
+/// Evaluate like `evaluate_expr_traced`, with the given options.
+pub fn evaluate_expr_with<S: AsRef<str>>(
+    args: &[S],
+    options: ExprOptions,
+    trace: Option<Tracer<'_>>,
+) -> Result<ExprValue, ExprError> {
     if args.is_empty() {
         return Err(ExprError::MissingOperand);
     }
-    let mut parser = ExprParser::new(args);
+    let mut parser = ExprParser::new(args, options, trace);
     let result = parser.parse_or()?;
     if parser.pos < parser.args.len() {
         return Err(ExprError::Syntax(format!(
             "unexpected argument '{}'",
-            parser.args[parser.pos]
+            parser.args[parser.pos].as_ref()
         )));
     }
     Ok(result)
@@ -55,9 +55,10 @@ fn test_expr_comparison() {
 
 #[test]
 fn test_expr_string_match() {
-    // expr abc : 'a\(.\)c' -> b
-    assert_eq!(eval(&["abc", ":", "a\\(.\\.\\)c"]).unwrap(), "b.");
-    // Actually test: a\(.\)c matches abc, capturing 'b'
+    // \. is a literal dot inside a group too, so this does not match
+    assert_eq!(eval(&["abc", ":", "a\\(.\\.\\)c"]).unwrap(), "");
+    assert_eq!(eval(&["ab.c", ":", "a\\(.\\.\\)c"]).unwrap(), "b.");
+    // a\(.\)c matches abc, capturing 'b'
     assert_eq!(eval(&["abc", ":", "a\\(.\\)c"]).unwrap(), "b");
     // Without groups: returns length of match
     assert_eq!(eval(&["abc", ":", "abc"]).unwrap(), "3");
@@ -93,6 +94,49 @@ fn test_expr_substr() {
     assert_eq!(eval(&["substr", "hello", "1", "-1"]).unwrap(), "");
 }
 
+#[test]
+fn test_expr_substr_huge_arguments() {
+    let max = i64::MAX.to_string();
+    let min = i64::MIN.to_string();
+    let cases: &[(&str, &str, &str)] = &[
+        // A length past the end, however large, runs to the end
+        ("2", "99999999999999999999", "bc"),
+        ("2", &max, "bc"),
+        ("1", "18446744073709551616", "abc"),
+        // A position past the end, however large, is empty
+        ("99999999999999999999", "1", ""),
+        (&max, &max, ""),
+        ("0", "2", ""),
+        // Negative values on either side, however large, are empty
+        ("-1", "2", ""),
+        (&min, "2", ""),
+        ("-99999999999999999999", "2", ""),
+        ("2", "-1", ""),
+        ("2", &min, ""),
+        ("2", "-99999999999999999999", ""),
+    ];
+    for &(pos, len, want) in cases {
+        assert_eq!(
+            eval(&["substr", "abc", pos, len]).unwrap(),
+            want,
+            "substr abc {pos} {len}"
+        );
+    }
+}
+
+#[test]
+fn test_expr_substr_multibyte_does_not_panic() {
+    assert_eq!(eval(&["substr", "h\u{e9}llo", "2", "2"]).unwrap(), "\u{e9}");
+    assert_eq!(
+        eval(&["substr", "h\u{e9}llo", "2", "1"]).unwrap(),
+        "\u{fffd}"
+    );
+    assert_eq!(
+        eval(&["substr", "h\u{e9}llo", "3", "99"]).unwrap(),
+        "\u{fffd}llo"
+    );
+}
+
 #[test]
 fn test_expr_index() {
     // expr index hello l -> 3
@@ -103,6 +147,9 @@ fn test_expr_index() {
     assert_eq!(eval(&["index", "hello", "oe"]).unwrap(), "2");
     // expr index hello h -> 1
     assert_eq!(eval(&["index", "hello", "h"]).unwrap(), "1");
+    // An empty string or character set finds nothing
+    assert_eq!(eval(&["index", "", "a"]).unwrap(), "0");
+    assert_eq!(eval(&["index", "abc", ""]).unwrap(), "0");
 }
 
 #[test]
@@ -217,6 +264,100 @@ fn test_expr_matches_gnu() {
     );
 }
 
+#[test]
+fn test_expr_arbitrary_precision() {
+    // Outputs match GNU expr built with GMP
+    let cases: &[(&[&str], &str)] = &[
+        (&["9223372036854775807", "+", "1"], "9223372036854775808"),
+        (&["-9223372036854775808", "-", "1"], "-9223372036854775809"),
+        (&["-9223372036854775808", "/", "-1"], "9223372036854775808"),
+        (&["-9223372036854775808", "%", "-1"], "0"),
+        (
+            &["12345678901234567890", "*", "98765432109876543210"],
+            "1219326311370217952237463801111263526900",
+        ),
+        (
+            &["99999999999999999999", "*", "-99999999999999999999"],
+            "-9999999999999999999800000000000000000001",
+        ),
+        (&["99999999999999999999", "/", "7"], "14285714285714285714"),
+        (
+            &["-99999999999999999999", "/", "7"],
+            "-14285714285714285714",
+        ),
+        (&["-99999999999999999999", "%", "7"], "-1"),
+        (&["99999999999999999999", "%", "-7"], "1"),
+        // Back within range
+        (&["9223372036854775808", "-", "9223372036854775807"], "1"),
+        (
+            &["00099999999999999999999", "-", "1"],
+            "99999999999999999998",
+        ),
+        // Comparisons are numeric at any size
+        (&["99999999999999999999", ">", "9223372036854775807"], "1"),
+        (&["-99999999999999999999", "<", "-9223372036854775808"], "1"),
+        (&["99999999999999999999", "=", "099999999999999999999"], "1"),
+        (&["100000000000000000000", "<", "99999999999999999999"], "0"),
+        // An operand keeps its spelling
+        (&["099999999999999999999"], "099999999999999999999"),
+        (&["length", "99999999999999999999"], "20"),
+    ];
+    for &(expr, want) in cases {
+        assert_eq!(eval(expr).unwrap(), want, "expr {:?}", expr);
+    }
+
+    assert!(matches!(
+        evaluate_expr(&args(&["99999999999999999999", "/", "0"])),
+        Err(ExprError::DivisionByZero)
+    ));
+    assert!(matches!(
+        evaluate_expr(&args(&["99999999999999999999", "%", "-0"])),
+        Err(ExprError::DivisionByZero)
+    ));
+    assert!(
+        !evaluate_expr(&args(&["99999999999999999999", "-", "1"]))
+            .unwrap()
+            .is_null()
+    );
+    assert!(
+        evaluate_expr(&args(&[
+            "99999999999999999999",
+            "-",
+            "99999999999999999999"
+        ]))
+        .unwrap()
+        .is_null()
+    );
+}
+
+#[test]
+fn test_expr_i64_boundaries() {
+    // Every operator at the i64 limits gives the exact result, as GNU
+    // expr does; nothing wraps or overflows
+    let max = "9223372036854775807";
+    let min = "-9223372036854775808";
+    let cases: &[(&str, &str, &str, &str)] = &[
+        (max, "+", "1", "9223372036854775808"),
+        (min, "+", "-1", "-9223372036854775809"),
+        (max, "-", "-1", "9223372036854775808"),
+        (min, "-", "1", "-9223372036854775809"),
+        (max, "*", "2", "18446744073709551614"),
+        (min, "*", "-1", "9223372036854775808"),
+        (min, "*", min, "85070591730234615865843651857942052864"),
+        (min, "/", "-1", "9223372036854775808"),
+        (max, "/", "-1", "-9223372036854775807"),
+        (min, "%", "-1", "0"),
+        (min, "%", "2", "0"),
+        (max, "%", min, max),
+        // Results at the limits themselves stay in range
+        (max, "+", "0", max),
+        (min, "-", "0", min),
+    ];
+    for &(l, op, r, want) in cases {
+        assert_eq!(eval(&[l, op, r]).unwrap(), want, "expr {} {} {}", l, op, r);
+    }
+}
+
 #[test]
 fn test_expr_division_by_zero() {
     let result = evaluate_expr(&args(&["1", "/", "0"]));
@@ -265,8 +406,423 @@ fn test_expr_unmatched_paren() {
     let result = evaluate_expr(&args(&["(", "1", "+", "2"]));
     assert!(result.is_err());
 
-    // Missing opening paren (bare closing paren as primary is just the string ")")
-    // This would actually be parsed as the literal string ")" then error on extra args
+    // A bare closing paren where an operand is expected is a syntax error
     let result = evaluate_expr(&args(&[")", "1"]));
     assert!(result.is_err());
+    assert!(matches!(
+        evaluate_expr(&args(&["match", "0", ")"])),
+        Err(ExprError::Syntax(_))
+    ));
+}
+
+// Divergences from GNU expr found by tests/expr_gnu.rs
+#[test]
+fn test_expr_gnu_divergences() {
+    // An operand keeps its spelling; "00" and "-0" are null all the same
+    assert_eq!(eval(&["(", "00", ")"]).unwrap(), "00");
+    assert!(evaluate_expr(&args(&["-0"])).unwrap().is_null());
+    assert_eq!(eval(&["length", "007"]).unwrap(), "3");
+    assert_eq!(eval(&["length", "match", "007", "\\(.*\\)"]).unwrap(), "3");
+    assert_eq!(eval(&["007", "+", "1"]).unwrap(), "8");
+
+    // A '+' sign or blanks make a string, not an integer
+    for bad in ["+1", " 1"] {
+        assert!(matches!(
+            evaluate_expr(&args(&[bad, "+", "1"])),
+            Err(ExprError::NonIntegerArgument)
+        ));
+    }
+
+    // The operand of | or & that cannot matter is parsed but not evaluated
+    assert_eq!(eval(&["1", "|", "1", "/", "0"]).unwrap(), "1");
+    assert_eq!(eval(&["0", "&", "1", "/", "0"]).unwrap(), "0");
+    assert_eq!(eval(&["1", "|", "a", ":", "\\("]).unwrap(), "1");
+    assert!(evaluate_expr(&args(&["1", "|", "1", "/"])).is_err());
+
+    // | of two null operands is 0, whatever the left one was
+    assert_eq!(
+        eval(&["", "=", "0", "|", "substr", "0", "0", "0"]).unwrap(),
+        "0"
+    );
+
+    // : is left-associative
+    assert_eq!(eval(&["0", ":", "", ":", "0"]).unwrap(), "1");
+
+    // substr with a non-integer position or length is empty
+    assert_eq!(eval(&["substr", "abc", "x", "1"]).unwrap(), "");
+    assert_eq!(eval(&["substr", "abc", "1", "+1"]).unwrap(), "");
+
+    // A leading * has nothing to repeat and is literal
+    assert_eq!(eval(&["*", ":", "*"]).unwrap(), "1");
+    assert_eq!(eval(&["**", ":", "**"]).unwrap(), "2");
+    assert_eq!(eval(&["*a", ":", "\\(*\\)a"]).unwrap(), "*");
+
+    // An invalid regex exits 2, like any other invalid expression
+    let err = evaluate_expr(&args(&["a", ":", "\\("])).unwrap_err();
+    assert!(matches!(err, ExprError::RegexError(_)));
+    assert_eq!(err.exit_code(), EXIT_EXPR_ERROR);
+}
+
+/// Evaluate in strict POSIX mode and return the display string.
+fn eval_posix(strs: &[&str]) -> Result<String, ExprError> {
+    let options = ExprOptions { posix: true };
+    evaluate_expr_with(&args(strs), options, None).map(|v| v.to_string())
+}
+
+#[test]
+fn test_expr_posix_keywords_are_operands() {
+    let cases: &[(&[&str], &str, &str)] = &[
+        // (expression, GNU result, POSIX result)
+        (&["length", ":", ".*"], "", "6"),
+        (&["match", ":", "m\\(.*\\)"], "", "atch"),
+        (&["substr", "=", "substr"], "", "1"),
+        (&["index", "<", "length"], "", "1"),
+        (&["length"], "", "length"),
+        (&["length", "abc"], "3", ""),
+        (&["match", "abc", "a"], "1", ""),
+        (&["substr", "abc", "2", "1"], "b", ""),
+        (&["index", "abc", "c"], "3", ""),
+    ];
+    for &(expr, gnu, posix) in cases {
+        match eval(expr) {
+            Ok(v) => assert_eq!(v, gnu, "GNU mode: expr {:?}", expr),
+            Err(_) => assert!(gnu.is_empty(), "GNU mode: expr {:?}", expr),
+        }
+        match eval_posix(expr) {
+            Ok(v) => assert_eq!(v, posix, "POSIX mode: expr {:?}", expr),
+            // A keyword followed by its operands is a syntax error
+            Err(e) => {
+                assert!(posix.is_empty(), "POSIX mode: expr {:?}: {}", expr, e);
+                assert!(matches!(e, ExprError::Syntax(_)), "{:?}", expr);
+            }
+        }
+    }
+}
+
+#[test]
+fn test_expr_posix_regex_operators_are_literal() {
+    // \+ and \? are one-or-more and zero-or-one in GNU mode
+    assert_eq!(eval(&["aa+", ":", "a\\+"]).unwrap(), "2");
+    assert_eq!(eval(&["a+", ":", "a\\+"]).unwrap(), "1");
+    assert_eq!(eval(&["b", ":", "a\\?b"]).unwrap(), "1");
+    assert_eq!(eval(&["a?", ":", "a\\?"]).unwrap(), "1");
+    // and match the literal characters in POSIX mode
+    assert_eq!(eval_posix(&["aa+", ":", "a\\+"]).unwrap(), "0");
+    assert_eq!(eval_posix(&["a+", ":", "a\\+"]).unwrap(), "2");
+    assert_eq!(eval_posix(&["b", ":", "a\\?b"]).unwrap(), "0");
+    assert_eq!(eval_posix(&["a?", ":", "a\\?"]).unwrap(), "2");
+    // The rest of the BRE is the same in both modes
+    for expr in [
+        &["abc", ":", "a\\(b\\)c"][..],
+        &["aaa", ":", "a\\{2\\}"],
+        &["a+?", ":", "a+?"],
+        &["abc", ":", ".*"],
+    ] {
+        assert_eq!(eval_posix(expr).unwrap(), eval(expr).unwrap(), "{:?}", expr);
+    }
+}
+
+/// Evaluate with a tracer, returning the result and the formatted trace lines.
+fn eval_traced(strs: &[&str]) -> (Result<String, ExprError>, Vec<String>) {
+    let mut lines = Vec::new();
+    let mut collect = |event: TraceEvent<'_>| lines.push(event.to_string());
+    let result = evaluate_expr_traced(&args(strs), Some(&mut collect)).map(|v| v.to_string());
+    (result, lines)
+}
+
+#[test]
+fn test_expr_trace_nested() {
+    let expr = [
+        "(",
+        "length",
+        "abc",
+        "+",
+        "2",
+        ")",
+        ">",
+        "4",
+        "&",
+        "abcd",
+        ":",
+        "a\\(b\\)c",
+    ];
+    let (result, lines) = eval_traced(&expr);
+    assert_eq!(result.unwrap(), eval(&expr).unwrap());
+    assert_eq!(
+        lines,
+        [
+            "  length \"abc\" -> 3",
+            "  3 + 2 -> 5",
+            "5 > 4 -> 1",
+            "\"abcd\" : \"a\\\\(b\\\\)c\" regex \"^a(b)c\" matched -> \"b\"",
+            "1 & \"b\" -> 1",
+        ]
+    );
+}
+
+#[test]
+fn test_expr_trace_match_outcome() {
+    // An empty match and no match both yield 0; the trace tells them apart
+    let (result, lines) = eval_traced(&["match", "abc", "x*"]);
+    assert_eq!(result.unwrap(), "0");
+    assert_eq!(lines, ["\"abc\" : \"x*\" regex \"^x*\" matched -> 0"]);
+
+    let (result, lines) = eval_traced(&["abc", ":", "b"]);
+    assert_eq!(result.unwrap(), "0");
+    assert_eq!(lines, ["\"abc\" : \"b\" regex \"^b\" no match -> 0"]);
+}
+
+#[test]
+fn test_expr_trace_error_keeps_partial_trace() {
+    let (result, lines) = eval_traced(&["(", "1", "+", "1", ")", "/", "0"]);
+    assert!(matches!(result, Err(ExprError::DivisionByZero)));
+    assert_eq!(lines, ["  1 + 1 -> 2"]);
+}
+
+#[test]
+fn test_arithmetic_stays_off_the_heap() {
+    // Shell loops run `expr $i + 1` thousands of times; integer-only
+    // expressions evaluate over the borrowed tokens without allocating
+    use crate::common::alloc_count::allocations_in;
+    for expr in [
+        &["1", "+", "1"][..],
+        &["7", "*", "(", "3", "-", "10", ")", "/", "2"],
+        &["1", "<", "2", "&", "3", "!=", "-4"],
+        &["0", "|", "-42"],
+    ] {
+        let (result, allocations) = allocations_in(|| evaluate_expr(expr));
+        assert!(result.is_ok(), "{:?}", expr);
+        assert_eq!(allocations, 0, "{:?}", expr);
+    }
+}
+
+mod integration {
+    use std::process::Command;
+
+    fn bin_path(name: &str) -> std::path::PathBuf {
+        let mut path = std::path::PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        path.push("target");
+        if cfg!(debug_assertions) {
+            path.push("debug");
+        } else {
+            path.push("release");
+        }
+        path.push(format!("{}{}", name, std::env::consts::EXE_SUFFIX));
+        path
+    }
+
+    /// Run fexpr, optionally with EXPR_DEBUG set. Returns (stdout, stderr, exit code).
+    fn run_fexpr(args: &[&str], debug: Option<&str>) -> (String, String, i32) {
+        let mut cmd = Command::new(bin_path("fexpr"));
+        cmd.args(args).env_remove("EXPR_DEBUG");
+        if let Some(value) = debug {
+            cmd.env("EXPR_DEBUG", value);
+        }
+        let output = cmd.output().unwrap();
+        (
+            String::from_utf8_lossy(&output.stdout).into_owned(),
+            String::from_utf8_lossy(&output.stderr).into_owned(),
+            output.status.code().unwrap_or(-1),
+        )
+    }
+
+    #[test]
+    fn test_expr_debug_env_traces_to_stderr() {
+        let expr = [
+            "(",
+            "ab",
+            "=",
+            "ab",
+            ")",
+            "&",
+            "foo.c",
+            ":",
+            "\\(f[a-z]o\\)\\.c",
+        ];
+        let (stdout, stderr, code) = run_fexpr(&expr, Some("1"));
+        assert_eq!(stdout, "1\n");
+        assert_eq!(code, 0);
+        assert_eq!(
+            stderr,
+            "expr: debug:   \"ab\" = \"ab\" -> 1\n\
+             expr: debug: \"foo.c\" : \"\\\\(f[a-z]o\\\\)\\\\.c\" regex \"^(f[a-z]o)\\\\.c\" matched -> \"foo\"\n\
+             expr: debug: 1 & \"foo\" -> 1\n"
+        );
+
+        // Without the hook: same stdout and exit status, silent stderr
+        let (plain_stdout, plain_stderr, plain_code) = run_fexpr(&expr, None);
+        assert_eq!(plain_stdout, stdout);
+        assert_eq!(plain_code, code);
+        assert!(plain_stderr.is_empty());
+    }
+
+    #[test]
+    fn test_expr_debug_keeps_exit_status() {
+        let (stdout, stderr, code) = run_fexpr(&["3", "<", "2"], Some("1"));
+        assert_eq!(stdout, "0\n");
+        assert_eq!(code, 1);
+        assert_eq!(stderr, "expr: debug: 3 < 2 -> 0\n");
+
+        let (_, stderr, code) = run_fexpr(&["1", "/", "0"], Some("1"));
+        assert_eq!(code, 2);
+        assert_eq!(stderr, "expr: division by zero\n");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_expr_non_unicode_argument_is_lossy() {
+        use std::ffi::OsStr;
+        use std::os::unix::ffi::OsStrExt;
+
+        let output = Command::new(bin_path("fexpr"))
+            .args([
+                OsStr::from_bytes(b"a\xffb"),
+                OsStr::new(":"),
+                OsStr::new(".*"),
+            ])
+            .output()
+            .unwrap();
+        // "a\u{FFFD}b": the replacement character is three bytes, and expr
+        // counts bytes as in the C locale
+        assert_eq!(String::from_utf8_lossy(&output.stdout), "5\n");
+        assert_eq!(output.status.code(), Some(0));
+    }
+
+    #[test]
+    fn test_expr_option_terminator() {
+        // Outputs and exit codes match GNU expr 9.x
+        let cases: &[(&[&str], &str, &str, i32)] = &[
+            (&["--", "-5", "+", "3"], "-2\n", "", 0),
+            (&["-5", "+", "3"], "-2\n", "", 0),
+            (&["-e"], "-e\n", "", 0),
+            (&["--", "--help"], "--help\n", "", 0),
+            (&["--", "--"], "--\n", "", 0),
+            (
+                &["--version", "+", "1"],
+                "",
+                "expr: non-integer argument\n",
+                2,
+            ),
+            (
+                &["--help", "1"],
+                "",
+                "expr: syntax error: unexpected argument '1'\n",
+                2,
+            ),
+            (
+                &["--"],
+                "",
+                "expr: missing operand\nTry 'expr --help' for more information.\n",
+                2,
+            ),
+            (
+                &[],
+                "",
+                "expr: missing operand\nTry 'expr --help' for more information.\n",
+                2,
+            ),
+            (
+                &["1", "+"],
+                "",
+                "expr: syntax error: missing argument after '+'\n",
+                2,
+            ),
+            (
+                &["substr", "a", "1"],
+                "",
+                "expr: syntax error: missing argument after '1'\n",
+                2,
+            ),
+        ];
+        for &(args, stdout, stderr, code) in cases {
+            let got = run_fexpr(args, None);
+            assert_eq!(
+                got,
+                (stdout.to_string(), stderr.to_string(), code),
+                "{args:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_expr_posixly_correct_env() {
+        let run = |posix: bool, args: &[&str]| {
+            let mut cmd = Command::new(bin_path("fexpr"));
+            cmd.args(args).env_remove("POSIXLY_CORRECT");
+            if posix {
+                cmd.env("POSIXLY_CORRECT", "1");
+            }
+            let output = cmd.output().unwrap();
+            (
+                String::from_utf8_lossy(&output.stdout).into_owned(),
+                output.status.code(),
+            )
+        };
+        assert_eq!(
+            run(true, &["length", ":", ".*"]),
+            ("6\n".to_string(), Some(0))
+        );
+        assert_eq!(run(false, &["length", ":", ".*"]).1, Some(2));
+        assert_eq!(
+            run(true, &["a+", ":", "a\\+"]),
+            ("2\n".to_string(), Some(0))
+        );
+        assert_eq!(
+            run(false, &["a+", ":", "a\\+"]),
+            ("1\n".to_string(), Some(0))
+        );
+    }
+
+    #[test]
+    fn test_expr_lone_help_and_version() {
+        let (stdout, _, code) = run_fexpr(&["--help"], None);
+        assert!(stdout.starts_with("Usage: expr EXPRESSION\n"));
+        assert_eq!(code, 0);
+
+        let (stdout, _, code) = run_fexpr(&["--version"], None);
+        assert!(stdout.starts_with("expr (fcoreutils)"));
+        assert_eq!(code, 0);
+    }
+}
+
+// The expr core is plain Rust and builds on Windows; these cover it and the
+// fexpr.exe binary there, where the Unix-flavoured suites above are not the
+// point.
+#[cfg(windows)]
+mod windows {
+    use super::*;
+    use std::process::Command;
+
+    #[test]
+    fn test_evaluate_expr_on_windows() {
+        assert_eq!(eval(&["6", "*", "7"]).unwrap(), "42");
+        assert_eq!(eval(&["length", "C:\\Temp"]).unwrap(), "7");
+        assert_eq!(
+            eval(&["C:\\dir\\file.txt", ":", ".*\\.\\(txt\\)"]).unwrap(),
+            "txt"
+        );
+        assert!(matches!(
+            evaluate_expr(&args(&["1", "/", "0"])),
+            Err(ExprError::DivisionByZero)
+        ));
+    }
+
+    #[test]
+    fn test_fexpr_exe_smoke() {
+        let mut path = std::path::PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        path.push("target");
+        path.push(if cfg!(debug_assertions) {
+            "debug"
+        } else {
+            "release"
+        });
+        path.push("fexpr.exe");
+
+        let output = Command::new(&path).args(["2", "+", "3"]).output().unwrap();
+        assert_eq!(String::from_utf8_lossy(&output.stdout).trim_end(), "5");
+        assert_eq!(output.status.code(), Some(0));
+
+        let output = Command::new(&path).args(["0", "&", "1"]).output().unwrap();
+        assert_eq!(output.status.code(), Some(1));
+    }
 }
@@ -16,6 +16,15 @@ pub struct FmtConfig {
     pub uniform_spacing: bool,
     /// Only reformat lines beginning with this prefix.
     pub prefix: Option<String>,
+    /// Columns between tab stops when measuring indentation (default 8).
+    pub tab_width: usize,
+    /// End a line after every sentence, so each sentence starts a new line
+    /// (wrapping within a sentence as usual).
+    pub break_at_sentences: bool,
+    /// If the input's last line has no newline, leave it off the output's
+    /// last line too. Off by default: like GNU fmt, every output line ends
+    /// with a newline.
+    pub preserve_final_newline: bool,
 }
 
 impl Default for FmtConfig {
@@ -23,107 +32,566 @@ impl Default for FmtConfig {
         let width = 75;
         Self {
             width,
-            goal: (width * 93) / 100,
+            goal: width * 187 / 200,
             split_only: false,
             crown_margin: false,
             tagged: false,
             uniform_spacing: false,
             prefix: None,
+            tab_width: 8,
+            break_at_sentences: false,
+            preserve_final_newline: false,
         }
     }
 }
 
 /// Reformat text from `input` and write the result to `output`.
 ///
-/// Text is processed paragraph by paragraph (paragraphs are separated by blank lines).
-/// Each paragraph's words are reflowed to fit within the configured width using
-/// greedy line breaking.
+/// Text is processed paragraph by paragraph. Paragraphs are separated by
+/// blank lines and, as in GNU fmt, by a change of indentation: see
+/// `ParaIndent::continues`. Each paragraph's words are reflowed within the
+/// configured width with GNU fmt's least-cost line breaking.
 pub fn fmt_file<R: BufRead, W: Write>(
-    input: R,
+    mut input: R,
     output: &mut W,
     config: &FmtConfig,
 ) -> io::Result<()> {
-    let mut paragraphs: Vec<Vec<String>> = Vec::new();
+    let mut paragraphs: Vec<Paragraph> = Vec::new();
     let mut current: Vec<String> = Vec::new();
-
-    for line in input.lines() {
-        let line = line?;
+    // Prefix of the paragraph being collected, and its column (prefix mode).
+    let mut current_prefix: Option<(String, usize)> = None;
+    let mut indent = ParaIndent::default();
+    let prefix = config.prefix.as_deref().map(Prefix::new);
+    let no_prefix = Prefix::new("");
+    // GNU fmt's `tabs`: whether a tab has been read among the blanks of the
+    // input so far. Once one has, output blanks are rebuilt with tabs.
+    let mut tabs = false;
+    // Whether the input's last line lacks a newline
+    let mut unterminated = false;
+
+    let mut buf = Vec::new();
+    loop {
+        buf.clear();
+        if input.read_until(b'\n', &mut buf)? == 0 {
+            break;
+        }
+        unterminated = buf.last() != Some(&b'\n');
+        let line = line_text(&buf)?;
+        // GNU reads the start of the next line before writing a paragraph,
+        // so its blanks count towards the paragraph's `tabs`
+        let start = prefix
+            .as_ref()
+            .unwrap_or(&no_prefix)
+            .line_start(&line, config.tab_width);
+        tabs |= start.tab;
 
         // If a prefix is set, only reformat lines that start with it.
-        // Lines without the prefix are emitted verbatim.
-        if let Some(ref pfx) = config.prefix {
-            if !line.starts_with(pfx.as_str()) {
-                // Flush current paragraph before emitting non-prefix line.
-                if !current.is_empty() {
-                    paragraphs.push(current);
-                    current = Vec::new();
+        // Other lines (including prefix-only ones) are copied through and
+        // end the current paragraph.
+        if let Some(ref pfx) = prefix {
+            match pfx.match_line(&line, config.tab_width) {
+                PrefixLine::Text {
+                    prefix,
+                    column,
+                    body,
+                } => {
+                    let text_indent = text_column(&line, body, config.tab_width);
+                    let same = current_prefix.as_ref().is_some_and(|(_, c)| *c == column)
+                        && indent.continues(current.len(), text_indent, config);
+                    if !same && !current.is_empty() {
+                        paragraphs.push(Paragraph::Text {
+                            prefix: current_prefix.take().map(|(p, _)| p),
+                            lines: std::mem::take(&mut current),
+                            tabs,
+                        });
+                    }
+                    if current.is_empty() {
+                        current_prefix = Some((prefix.to_string(), column));
+                    }
+                    indent.push(current.len(), text_indent);
+                    tabs |= line.contains('\t');
+                    // Re-attach the paragraph's own prefix so every line of
+                    // it strips the same string.
+                    let pfx = &current_prefix.as_ref().unwrap().0;
+                    current.push(format!("{}{}", pfx, body));
+                }
+                PrefixLine::Other => {
+                    if !current.is_empty() {
+                        paragraphs.push(Paragraph::Text {
+                            prefix: current_prefix.take().map(|(p, _)| p),
+                            lines: std::mem::take(&mut current),
+                            tabs,
+                        });
+                    }
+                    paragraphs.push(Paragraph::Verbatim(copy_line(
+                        &start,
+                        pfx,
+                        tabs,
+                        config.tab_width,
+                    )));
                 }
-                // Emit as a single-line paragraph marked for verbatim output.
-                // We use a special sentinel: a paragraph whose sole entry is the
-                // raw line prefixed with a NUL byte so we can distinguish it later.
-                paragraphs.push(vec![format!("\x00{}", line)]);
-                continue;
             }
+            continue;
         }
 
         if line.trim().is_empty() {
             if !current.is_empty() {
-                paragraphs.push(current);
-                current = Vec::new();
+                paragraphs.push(Paragraph::Text {
+                    prefix: None,
+                    lines: std::mem::take(&mut current),
+                    tabs,
+                });
             }
-            // Represent a blank line as an empty paragraph.
-            paragraphs.push(Vec::new());
+            paragraphs.push(Paragraph::Blank);
         } else {
+            let text_indent = text_column(&line, &line, config.tab_width);
+            if !current.is_empty() && !indent.continues(current.len(), text_indent, config) {
+                paragraphs.push(Paragraph::Text {
+                    prefix: None,
+                    lines: std::mem::take(&mut current),
+                    tabs,
+                });
+            }
+            indent.push(current.len(), text_indent);
+            tabs |= line.contains('\t');
             current.push(line);
         }
     }
     if !current.is_empty() {
-        paragraphs.push(current);
+        paragraphs.push(Paragraph::Text {
+            prefix: current_prefix.map(|(p, _)| p),
+            lines: current,
+            tabs,
+        });
     }
 
-    for para in &paragraphs {
-        // Blank line separator.
-        if para.is_empty() {
-            output.write_all(b"\n")?;
-            continue;
+    if config.preserve_final_newline && unterminated {
+        // The newline still held back at the end is the final one
+        let mut output = HoldNewline {
+            inner: output,
+            held: false,
+        };
+        write_paragraphs(&paragraphs, config, &mut output)
+    } else {
+        write_paragraphs(&paragraphs, config, output)
+    }
+}
+
+/// The text of a line read with `read_until`, without its terminator: a
+/// newline and a carriage return just before it, as `BufRead::lines`
+/// strips them.
+fn line_text(line: &[u8]) -> io::Result<String> {
+    let text = match line.strip_suffix(b"\n") {
+        Some(text) => text.strip_suffix(b"\r").unwrap_or(text),
+        None => line,
+    };
+    String::from_utf8(text.to_vec()).map_err(|_| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            "stream did not contain valid UTF-8",
+        )
+    })
+}
+
+fn write_paragraphs<W: Write>(
+    paragraphs: &[Paragraph],
+    config: &FmtConfig,
+    output: &mut W,
+) -> io::Result<()> {
+    // Continuation indent of the last paragraph, which a one-line tagged
+    // paragraph inherits
+    let mut other_indent = 0;
+    for para in paragraphs {
+        match para {
+            Paragraph::Blank => output.write_all(b"\n")?,
+            Paragraph::Verbatim(line) => {
+                output.write_all(line.as_bytes())?;
+                output.write_all(b"\n")?;
+            }
+            Paragraph::Text {
+                prefix,
+                lines,
+                tabs,
+            } => format_paragraph(
+                lines,
+                prefix.as_deref(),
+                *tabs,
+                &mut other_indent,
+                config,
+                output,
+            )?,
+        }
+    }
+
+    Ok(())
+}
+
+/// A writer that holds back a newline ending a write until more output
+/// follows, so the caller can drop the output's final newline by never
+/// writing anything after it.
+struct HoldNewline<'a, W: Write> {
+    inner: &'a mut W,
+    held: bool,
+}
+
+impl<W: Write> Write for HoldNewline<'_, W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        if self.held {
+            self.inner.write_all(b"\n")?;
+            self.held = false;
         }
+        let (body, held) = match buf.split_last() {
+            Some((b'\n', body)) => (body, true),
+            _ => (buf, false),
+        };
+        self.inner.write_all(body)?;
+        self.held = held;
+        Ok(buf.len())
+    }
 
-        // Check for verbatim sentinel (non-prefix line).
-        if para.len() == 1 && para[0].starts_with('\x00') {
-            output.write_all(&para[0].as_bytes()[1..])?;
-            output.write_all(b"\n")?;
-            continue;
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// A unit of output: a blank line, a line copied through, or a run of
+/// lines to refill. `prefix` is the -p prefix as it appears in the lines;
+/// `tabs` is GNU fmt's flag as it stood when the paragraph was read.
+enum Paragraph {
+    Blank,
+    Verbatim(String),
+    Text {
+        prefix: Option<String>,
+        lines: Vec<String>,
+        tabs: bool,
+    },
+}
+
+/// Indentation, in display columns, of the first two lines of the
+/// paragraph being collected.
+#[derive(Default)]
+struct ParaIndent {
+    first: usize,
+    second: usize,
+}
+
+impl ParaIndent {
+    /// Record the indent of the line about to become line `n` (0-based) of
+    /// the paragraph.
+    fn push(&mut self, n: usize, indent: usize) {
+        match n {
+            0 => self.first = indent,
+            1 => self.second = indent,
+            _ => {}
         }
+    }
 
-        format_paragraph(para, config, output)?;
+    /// Whether a line indented `indent` columns continues a paragraph of
+    /// `n` lines. By default every line must share the first line's
+    /// indent, so a nested list item or an indented quote starts a new
+    /// paragraph. Crown and tagged modes let the second line differ (tagged
+    /// requires it to) and hold the rest to the second line's indent.
+    fn continues(&self, n: usize, indent: usize, config: &FmtConfig) -> bool {
+        if n >= 2 {
+            indent == self.second
+        } else if config.crown_margin {
+            true
+        } else if config.tagged {
+            indent != self.first
+        } else {
+            indent == self.first
+        }
     }
+}
 
-    Ok(())
+/// Display column where the text of `line` starts, given `body`, the part
+/// of `line` from just after any -p prefix.
+fn text_column(line: &str, body: &str, tab_width: usize) -> usize {
+    let text = body.trim_start_matches([' ', '\t']);
+    display_width(&line[..line.len() - text.len()], tab_width)
 }
 
-/// Determine the leading whitespace (indentation) of a line.
-fn leading_indent(line: &str) -> &str {
-    let trimmed = line.trim_start();
-    &line[..line.len() - trimmed.len()]
+/// The -p prefix, split the way GNU fmt reads it: leading blanks are a
+/// minimum indentation rather than text to match, and trailing blanks are
+/// optional on the line itself but still count towards where the text
+/// must start. So with `-p '> '` the line ">" is a blank quoted line, and
+/// ">>x" is not part of the quote (its text starts in column 1, not 2).
+struct Prefix<'a> {
+    lead_space: usize,
+    text: &'a str,
+    full_len: usize,
 }
 
-/// Check if a word ends a sentence (ends with '.', '!', or '?').
+/// How a line relates to the prefix.
+enum PrefixLine<'a> {
+    /// Prefixed text: `prefix` (with any indentation before it) starts at
+    /// display column `column`; `body` is the rest, including the blanks
+    /// after the prefix.
+    Text {
+        prefix: &'a str,
+        column: usize,
+        body: &'a str,
+    },
+    /// Not prefixed, or prefixed but empty: copied through.
+    Other,
+}
+
+impl<'a> Prefix<'a> {
+    fn new(prefix: &'a str) -> Self {
+        let rest = prefix.trim_start_matches(' ');
+        Prefix {
+            lead_space: prefix.len() - rest.len(),
+            text: rest.trim_end_matches(' '),
+            full_len: rest.len(),
+        }
+    }
+
+    fn match_line<'l>(&self, line: &'l str, tab_width: usize) -> PrefixLine<'l> {
+        let is_blank = |c: char| c == ' ' || c == '\t';
+        let lead_len = line.len() - line.trim_start_matches(is_blank).len();
+        let column = display_width(&line[..lead_len], tab_width);
+        let Some(after) = line[lead_len..].strip_prefix(self.text) else {
+            return PrefixLine::Other;
+        };
+        let prefix_end = lead_len + self.text.len();
+        let gap_len = after.len() - after.trim_start_matches(is_blank).len();
+        if after.len() == gap_len {
+            // Nothing after the prefix: a blank line within the block
+            return PrefixLine::Other;
+        }
+        let text_column = display_width(&line[..prefix_end + gap_len], tab_width);
+        if self.text.is_empty() {
+            // Only blanks were given: any line indented at least that far
+            if column < self.lead_space {
+                return PrefixLine::Other;
+            }
+            return PrefixLine::Text {
+                prefix: "",
+                column: 0,
+                body: line,
+            };
+        }
+        if column < self.lead_space || text_column < column + self.full_len {
+            return PrefixLine::Other;
+        }
+        PrefixLine::Text {
+            prefix: &line[..prefix_end],
+            column,
+            body: after,
+        }
+    }
+}
+
+/// The start of a line as GNU fmt's get_prefix reads it: the blanks,
+/// as much of the prefix as matches and, after a full match, the blanks
+/// after it.
+struct LineStart<'l> {
+    /// Column reached.
+    column: usize,
+    /// Column where the prefix is, or would be.
+    prefix_column: usize,
+    /// The rest of the line.
+    rest: &'l str,
+    /// Whether a tab was among the blanks read.
+    tab: bool,
+}
+
+impl Prefix<'_> {
+    fn line_start<'l>(&self, line: &'l str, tab_width: usize) -> LineStart<'l> {
+        let is_blank = |c: char| c == ' ' || c == '\t';
+        let rest = line.trim_start_matches(is_blank);
+        let lead = &line[..line.len() - rest.len()];
+        let column = display_width(lead, tab_width);
+        let mut start = LineStart {
+            column,
+            prefix_column: column,
+            rest,
+            tab: lead.contains('\t'),
+        };
+        if self.text.is_empty() {
+            start.prefix_column = column.min(self.lead_space);
+            return start;
+        }
+        let matched = rest
+            .char_indices()
+            .zip(self.text.chars())
+            .take_while(|((_, a), b)| a == b)
+            .last()
+            .map_or(0, |((i, a), _)| i + a.len_utf8());
+        start.column += matched;
+        start.rest = &rest[matched..];
+        if matched == self.text.len() {
+            let after = start.rest.trim_start_matches(is_blank);
+            let gap = &start.rest[..start.rest.len() - after.len()];
+            let tab_width = tab_width.max(1);
+            start.column = gap
+                .bytes()
+                .fold(start.column, |col, b| advance_column(col, b, tab_width));
+            start.tab |= gap.contains('\t');
+            start.rest = after;
+        }
+        start
+    }
+}
+
+/// A line that is not refilled, written as GNU fmt's copy_rest does: the
+/// blanks before and after the prefix are rebuilt from their columns, and
+/// a line with nothing past them comes out empty.
+fn copy_line(start: &LineStart, prefix: &Prefix, tabs: bool, tab_width: usize) -> String {
+    let mut out = String::new();
+    let mut column = 0;
+    if start.column > start.prefix_column || !start.rest.is_empty() {
+        put_space(&mut out, &mut column, start.prefix_column, tabs, tab_width);
+        for c in prefix.text.chars() {
+            if column >= start.column {
+                break;
+            }
+            out.push(c);
+            column += c.len_utf8();
+        }
+        if !start.rest.is_empty() {
+            let space = start.column.saturating_sub(column);
+            put_space(&mut out, &mut column, space, tabs, tab_width);
+        }
+    }
+    out.push_str(start.rest);
+    out
+}
+
+/// Append `space` columns of blanks to `out`, which ends at `column`. As in
+/// GNU fmt, once the input has had a tab, whole tab stops are filled with
+/// tabs (unless a tab would stand in for a single space), the rest with
+/// spaces.
+fn put_space(out: &mut String, column: &mut usize, space: usize, tabs: bool, tab_width: usize) {
+    let target = *column + space;
+    if tabs {
+        let tab_width = tab_width.max(1);
+        let tab_target = target / tab_width * tab_width;
+        if *column + 1 < tab_target {
+            while *column < tab_target {
+                out.push('\t');
+                *column = (*column / tab_width + 1) * tab_width;
+            }
+        }
+    }
+    while *column < target {
+        out.push(' ');
+        *column += 1;
+    }
+}
+
+/// Display columns occupied by `s`, expanding tabs to the next multiple
+/// of `tab_width`. Words never contain tabs, so only prefixes and
+/// indentation are affected in practice.
+fn display_width(s: &str, tab_width: usize) -> usize {
+    let tab_width = tab_width.max(1);
+    s.bytes().fold(0, |col, b| {
+        if b == b'\t' {
+            (col / tab_width + 1) * tab_width
+        } else {
+            col + 1
+        }
+    })
+}
+
+/// Check if a word ends a sentence for --break-at-sentences: it ends with
+/// '.', '!' or '?' after a letter or digit, so a standalone "..." or "?!"
+/// does not.
 fn is_sentence_end(word: &str) -> bool {
-    matches!(word.as_bytes().last(), Some(b'.' | b'!' | b'?'))
+    let body = word.trim_end_matches(['.', '!', '?']);
+    body.len() < word.len() && body.chars().any(char::is_alphanumeric)
 }
 
-/// Extract words from a line, optionally stripping a prefix first.
-fn extract_words<'a>(line: &'a str, prefix: Option<&str>) -> Vec<&'a str> {
-    let s = match prefix {
-        Some(pfx) => line.strip_prefix(pfx).unwrap_or(line),
-        None => line,
-    };
-    s.split_whitespace().collect()
+/// A word and what GNU fmt records about it for line breaking.
+struct Word<'a> {
+    text: &'a str,
+    /// Columns of blank output after the word: as in the input between
+    /// words of a line, and 1 (2 after a sentence) at a line end or with -u.
+    space: usize,
+    /// Starts with an opening bracket or quote.
+    paren: bool,
+    /// Ends with ASCII punctuation.
+    punct: bool,
+    /// Ends with '.', '?' or '!', ignoring closing brackets and quotes.
+    period: bool,
+    /// Ends a sentence: a period followed by a line end or 2+ blanks.
+    sentence_end: bool,
+}
+
+/// Append the words of `body` to `words`. `column` is the display column
+/// `body` starts at, so tabs between words expand as in the input.
+fn push_words<'a>(body: &'a str, mut column: usize, config: &FmtConfig, words: &mut Vec<Word<'a>>) {
+    let tab_width = config.tab_width.max(1);
+    let bytes = body.as_bytes();
+    let mut i = 0;
+    while i < bytes.len() && bytes[i].is_ascii_whitespace() {
+        column = advance_column(column, bytes[i], tab_width);
+        i += 1;
+    }
+    while i < bytes.len() {
+        let start = i;
+        while i < bytes.len() && !bytes[i].is_ascii_whitespace() {
+            i += 1;
+        }
+        let text = &body[start..i];
+        column += text.len();
+        let gap_start = column;
+        while i < bytes.len() && bytes[i].is_ascii_whitespace() {
+            column = advance_column(column, bytes[i], tab_width);
+            i += 1;
+        }
+        let first = text.as_bytes()[0];
+        let last = *text.as_bytes().last().unwrap();
+        let unclosed = text.trim_end_matches([')', ']', '\'', '"']);
+        let period = matches!(
+            unclosed.as_bytes().last().unwrap_or(&first),
+            b'.' | b'?' | b'!'
+        );
+        let line_end = i == bytes.len();
+        let space = column - gap_start;
+        let sentence_end = period && (line_end || space > 1);
+        words.push(Word {
+            text,
+            space: if line_end || config.uniform_spacing {
+                1 + sentence_end as usize
+            } else {
+                space
+            },
+            paren: matches!(first, b'(' | b'[' | b'`' | b'\'' | b'"'),
+            punct: last.is_ascii_punctuation(),
+            period,
+            sentence_end,
+        });
+    }
+}
+
+/// Mark the last word of a paragraph as ending a sentence, as GNU fmt
+/// does, so a short last word is costly to leave alone on a line.
+fn end_paragraph(words: &mut [Word]) {
+    if let Some(last) = words.last_mut() {
+        last.period = true;
+        last.sentence_end = true;
+    }
+}
+
+/// The column after `b` is written at `column`.
+fn advance_column(column: usize, b: u8, tab_width: usize) -> usize {
+    if b == b'\t' {
+        (column / tab_width + 1) * tab_width
+    } else {
+        column + 1
+    }
 }
 
 /// Format a single paragraph (a group of non-blank lines) and write it.
 fn format_paragraph<W: Write>(
     lines: &[String],
+    prefix_str: Option<&str>,
+    tabs: bool,
+    other_indent: &mut usize,
     config: &FmtConfig,
     output: &mut W,
 ) -> io::Result<()> {
@@ -131,276 +599,244 @@ fn format_paragraph<W: Write>(
         return Ok(());
     }
 
-    let prefix_str = config.prefix.as_deref();
-
-    // Strip the prefix from lines for indentation analysis.
-    let stripped_first = match prefix_str {
-        Some(pfx) => lines[0].strip_prefix(pfx).unwrap_or(&lines[0]),
-        None => &lines[0],
+    let pfx = prefix_str.unwrap_or("");
+    let body_column = display_width(pfx, config.tab_width);
+    let body = |line: &'_ String| -> String {
+        match prefix_str {
+            Some(p) => line.strip_prefix(p).unwrap_or(line).to_string(),
+            None => line.clone(),
+        }
     };
+    // Indents are the display columns where the text starts, counting the
+    // prefix, as GNU fmt measures them.
+    let indent_of = |line: &String| text_column(line, &body(line), config.tab_width);
 
-    let first_indent = leading_indent(stripped_first).to_string();
-
-    // Determine continuation indent.
-    let rest_indent = if lines.len() > 1 {
-        let stripped = match prefix_str {
-            Some(pfx) => lines[1].strip_prefix(pfx).unwrap_or(&lines[1]),
-            None => &lines[1],
-        };
-        leading_indent(stripped).to_string()
-    } else {
-        first_indent.clone()
-    };
+    let first_indent = indent_of(&lines[0]);
+    let rest_indent = lines.get(1).map_or(first_indent, indent_of);
 
     // Choose indentation based on mode.
-    let (first_line_indent, cont_indent) = if config.tagged {
-        // Tagged paragraph: first line keeps its indent, rest uses second line's indent.
-        (first_indent.clone(), rest_indent.clone())
-    } else if config.crown_margin {
-        // Crown margin: preserve the first two lines' indentation exactly.
-        (first_indent.clone(), rest_indent.clone())
+    let cont_indent = if config.tagged && lines.len() == 1 {
+        // A one-line tagged paragraph has no second line to copy: like GNU,
+        // keep the previous paragraph's continuation indent unless it
+        // equals this first line's, then hang 3 columns in (or at the
+        // margin if the first line is indented).
+        if *other_indent == first_indent {
+            *other_indent = if first_indent == 0 { 3 } else { 0 };
+        }
+        *other_indent
+    } else if config.tagged || config.crown_margin {
+        // Tagged and crown margin: the rest follow the second line
+        rest_indent
     } else {
         // Default: use the first line's indent for all lines.
-        (first_indent.clone(), first_indent.clone())
+        first_indent
     };
 
-    // In split-only mode, we do not rejoin words across lines.
+    *other_indent = cont_indent;
+
+    // In split-only mode each line is filled on its own, never joined
+    // with the next, at its own indentation.
     if config.split_only {
         for line in lines {
-            split_long_line(line, config, prefix_str, output)?;
+            let text = body(line);
+            let mut words = Vec::new();
+            push_words(&text, body_column, config, &mut words);
+            if words.is_empty() {
+                output.write_all(b"\n")?;
+                continue;
+            }
+            end_paragraph(&mut words);
+            let indent = indent_of(line);
+            let result = reflow_paragraph(&words, pfx, indent, indent, tabs, config);
+            output.write_all(result.as_bytes())?;
         }
         return Ok(());
     }
 
     // Collect all words from the paragraph.
-    let mut all_words: Vec<&str> = Vec::new();
-    for line in lines {
-        all_words.extend(extract_words(line, prefix_str));
+    let texts: Vec<String> = lines.iter().map(body).collect();
+    let mut all_words: Vec<Word> = Vec::new();
+    for text in &texts {
+        push_words(text, body_column, config, &mut all_words);
     }
 
     if all_words.is_empty() {
         output.write_all(b"\n")?;
         return Ok(());
     }
-
-    // Build the prefix string to prepend to each output line.
-    let pfx = prefix_str.unwrap_or("");
-
-    // Reflow the words.
-    let result = reflow_paragraph(&all_words, pfx, &first_line_indent, &cont_indent, config);
+    end_paragraph(&mut all_words);
+
+    // Reflow the words. With --break-at-sentences each sentence is filled
+    // on its own, the first at the paragraph's first-line indent and the
+    // rest as continuation lines.
+    if config.break_at_sentences {
+        for (k, sentence) in all_words
+            .split_inclusive(|w| is_sentence_end(w.text))
+            .enumerate()
+        {
+            let indent = if k == 0 { first_indent } else { cont_indent };
+            let result = reflow_paragraph(sentence, pfx, indent, cont_indent, tabs, config);
+            output.write_all(result.as_bytes())?;
+        }
+        return Ok(());
+    }
+    let result = reflow_paragraph(&all_words, pfx, first_indent, cont_indent, tabs, config);
     output.write_all(result.as_bytes())?;
     Ok(())
 }
 
+// Line-breaking costs from GNU fmt. Each line costs LINE_COST, adjusted by
+// where it starts (after a sentence, after punctuation, before an opening
+// bracket) plus the squared shortfall from the goal width and the squared
+// difference from the next line's length.
+const fn equiv(n: i64) -> i64 {
+    n * n
+}
+const LINE_COST: i64 = equiv(70);
+const SENTENCE_BONUS: i64 = equiv(50);
+const NOBREAK_COST: i64 = equiv(600);
+const PAREN_BONUS: i64 = equiv(40);
+const PUNCT_BONUS: i64 = equiv(40);
+
+fn short_cost(n: i64) -> i64 {
+    equiv(n * 10)
+}
+
+fn ragged_cost(n: i64) -> i64 {
+    short_cost(n) / 2
+}
+
+fn widow_cost(len: usize) -> i64 {
+    equiv(200) / (len as i64 + 2)
+}
+
+fn orphan_cost(len: usize) -> i64 {
+    equiv(150) / (len as i64 + 2)
+}
+
+/// Cost of starting a line with `words[i]`.
+fn base_cost(words: &[Word], i: usize) -> i64 {
+    let mut cost = LINE_COST;
+    if i > 0 {
+        let prev = &words[i - 1];
+        if prev.period {
+            if prev.sentence_end {
+                cost -= SENTENCE_BONUS;
+            } else {
+                // "Mr. Smith": keep the abbreviation with its word
+                cost += NOBREAK_COST;
+            }
+        } else if prev.punct {
+            cost -= PUNCT_BONUS;
+        } else if i > 1 && words[i - 2].sentence_end {
+            cost += widow_cost(prev.text.len());
+        }
+    }
+    if words[i].paren {
+        cost -= PAREN_BONUS;
+    } else if words[i].sentence_end {
+        cost += orphan_cost(words[i].text.len());
+    }
+    cost
+}
+
 /// Reflow words into lines that fit within the configured width.
 ///
-/// Uses optimal line breaking with a cost function matching GNU fmt:
-///   SHORT_COST(n) = (n * 10)^2  where n = goal - line_length
-///   RAGGED_COST(n) = (n * 10)^2 / 2  where n = line_length - next_line_length
-///   Last line has zero cost.
+/// Chooses the breaks of least total cost, like GNU fmt: every line must
+/// be shorter than the width unless it holds a single word, and the last
+/// line of the paragraph is free. `first_indent` and `cont_indent` are
+/// the columns the text of the first and the other lines start at.
 fn reflow_paragraph(
-    words: &[&str],
+    words: &[Word],
     prefix: &str,
-    first_indent: &str,
-    cont_indent: &str,
+    first_indent: usize,
+    cont_indent: usize,
+    tabs: bool,
     config: &FmtConfig,
 ) -> String {
-    if words.is_empty() {
-        return String::new();
-    }
-
     let n = words.len();
-    let first_base = prefix.len() + first_indent.len();
-    let cont_base = prefix.len() + cont_indent.len();
+    let first_base = first_indent;
+    let cont_base = cont_indent;
     let goal = config.goal as i64;
     let width = config.width;
 
-    // Compute separator widths between consecutive words on the same line.
-    let sep_widths: Vec<usize> = (0..n)
-        .map(|i| {
-            if i == 0 {
+    // best_cost[i]: least cost of setting words[i..]; next_break[i]: the
+    // word starting the line after the one starting at i, whose length
+    // is line_len[i].
+    let mut best_cost = vec![0i64; n + 1];
+    let mut next_break = vec![n; n + 1];
+    let mut line_len = vec![0usize; n + 1];
+
+    for start in (0..n).rev() {
+        let mut best = i64::MAX;
+        let mut len = if start == 0 { first_base } else { cont_base } + words[start].text.len();
+        let mut next = start + 1;
+        loop {
+            // Break before `next`
+            let line_cost = if next == n {
                 0
-            } else if config.uniform_spacing && is_sentence_end(words[i - 1]) {
-                2
             } else {
-                1
-            }
-        })
-        .collect();
-
-    let word_lens: Vec<usize> = words.iter().map(|w| w.len()).collect();
-
-    // DP state:
-    // cost[i] = minimum cost to format words[i..n]
-    // best[i] = last word index of the optimal line starting at word i
-    // first_line_len[i] = length of the first line in the optimal layout of words[i..n]
-    // has_more_lines[i] = whether the sub-solution at i has more than one line
-    let mut cost = vec![i64::MAX; n + 1];
-    let mut best = vec![0usize; n];
-    let mut first_line_len = vec![0i64; n + 1];
-    let mut has_more_lines = vec![false; n + 1]; // true if sub-solution has >= 2 lines
-    cost[n] = 0;
-
-    for i in (0..n).rev() {
-        let base = if i == 0 { first_base } else { cont_base };
-        let mut len = base + word_lens[i];
-
-        for j in i..n {
-            if j > i {
-                len += sep_widths[j] + word_lens[j];
-            }
-
-            if len > width {
-                if j == i {
-                    let line_cost = if j == n - 1 {
-                        0
-                    } else {
-                        let short_n = goal - len as i64;
-                        let short_cost = short_n * 10 * short_n * 10;
-                        // Ragged cost only if the next sub-solution has more than one line
-                        // (i.e., next line is not the last line of the paragraph)
-                        let ragged_cost = if has_more_lines[j + 1] {
-                            let ragged_n = len as i64 - first_line_len[j + 1];
-                            ragged_n * 10 * ragged_n * 10 / 2
-                        } else {
-                            0
-                        };
-                        short_cost + ragged_cost
-                    };
-                    if cost[j + 1] != i64::MAX {
-                        let total = line_cost + cost[j + 1];
-                        if total < cost[i] {
-                            cost[i] = total;
-                            best[i] = j;
-                            first_line_len[i] = len as i64;
-                            has_more_lines[i] = j + 1 < n;
-                        }
-                    }
+                let mut cost = short_cost(goal - len as i64);
+                if next_break[next] != n {
+                    cost += ragged_cost(len as i64 - line_len[next] as i64);
                 }
+                cost
+            };
+            let cost = line_cost + best_cost[next];
+            if cost < best {
+                best = cost;
+                next_break[start] = next;
+                line_len[start] = len;
+            }
+            if next == n {
                 break;
             }
-
-            let line_cost = if j == n - 1 {
-                0
-            } else {
-                let short_n = goal - len as i64;
-                let short_cost = short_n * 10 * short_n * 10;
-                // Ragged cost only when the next line is not the last line
-                let ragged_cost = if has_more_lines[j + 1] {
-                    let ragged_n = len as i64 - first_line_len[j + 1];
-                    ragged_n * 10 * ragged_n * 10 / 2
-                } else {
-                    0
-                };
-                short_cost + ragged_cost
-            };
-
-            if cost[j + 1] != i64::MAX {
-                let total = line_cost + cost[j + 1];
-                if total < cost[i] {
-                    cost[i] = total;
-                    best[i] = j;
-                    first_line_len[i] = len as i64;
-                    has_more_lines[i] = j + 1 < n;
-                }
+            len += words[next - 1].space + words[next].text.len();
+            if len >= width {
+                break;
             }
+            next += 1;
         }
+        best_cost[start] = best + base_cost(words, start);
     }
 
-    // Reconstruct the lines from the DP solution.
+    // Every blank is rebuilt from columns, as GNU fmt's put_line does
+    let prefix_text = prefix.trim_start_matches([' ', '\t']);
+    let prefix_column = display_width(
+        &prefix[..prefix.len() - prefix_text.len()],
+        config.tab_width,
+    );
     let mut result = String::new();
-    let mut i = 0;
-    let mut is_first_line = true;
-
-    while i < n {
-        let j = best[i];
-        let base = if is_first_line {
-            format!("{}{}", prefix, first_indent)
+    let mut start = 0;
+    while start < n {
+        let end = next_break[start];
+        let indent = if start == 0 {
+            first_indent
         } else {
-            format!("{}{}", prefix, cont_indent)
+            cont_indent
         };
-
-        result.push_str(&base);
-        result.push_str(words[i]);
-        for k in (i + 1)..=j {
-            if config.uniform_spacing && is_sentence_end(words[k - 1]) {
-                result.push_str("  ");
-            } else {
-                result.push(' ');
+        let mut column = 0;
+        put_space(
+            &mut result,
+            &mut column,
+            prefix_column,
+            tabs,
+            config.tab_width,
+        );
+        result.push_str(prefix_text);
+        column += prefix_text.len();
+        let space = indent.saturating_sub(column);
+        put_space(&mut result, &mut column, space, tabs, config.tab_width);
+        for (k, word) in words[start..end].iter().enumerate() {
+            if k > 0 {
+                let space = words[start + k - 1].space;
+                put_space(&mut result, &mut column, space, tabs, config.tab_width);
             }
-            result.push_str(words[k]);
+            result.push_str(word.text);
+            column += word.text.len();
         }
         result.push('\n');
-
-        is_first_line = false;
-        i = j + 1;
+        start = end;
     }
-
     result
 }
-
-/// Split a single long line at the width boundary without reflowing.
-/// Used in split-only mode (-s).
-fn split_long_line<W: Write>(
-    line: &str,
-    config: &FmtConfig,
-    prefix: Option<&str>,
-    output: &mut W,
-) -> io::Result<()> {
-    let stripped = match prefix {
-        Some(pfx) => line.strip_prefix(pfx).unwrap_or(line),
-        None => line,
-    };
-    let indent = leading_indent(stripped).to_string();
-    let pfx = prefix.unwrap_or("");
-
-    if line.len() <= config.width {
-        output.write_all(line.as_bytes())?;
-        output.write_all(b"\n")?;
-        return Ok(());
-    }
-
-    // Split the line's words, preserving the original structure as much as possible.
-    let words: Vec<&str> = extract_words(line, prefix);
-    if words.is_empty() {
-        output.write_all(line.as_bytes())?;
-        output.write_all(b"\n")?;
-        return Ok(());
-    }
-
-    let mut cur_line = format!("{}{}", pfx, indent);
-    for (i, word) in words.iter().enumerate() {
-        let sep = if cur_line.len() == pfx.len() + indent.len() {
-            ""
-        } else if config.uniform_spacing && i > 0 && is_sentence_end(words[i - 1]) {
-            "  "
-        } else {
-            " "
-        };
-
-        if cur_line.len() + sep.len() + word.len() > config.width
-            && cur_line.len() > pfx.len() + indent.len()
-        {
-            output.write_all(cur_line.as_bytes())?;
-            output.write_all(b"\n")?;
-            cur_line = format!("{}{}", pfx, indent);
-        }
-
-        let sep = if cur_line.len() == pfx.len() + indent.len() {
-            ""
-        } else if config.uniform_spacing && i > 0 && is_sentence_end(words[i - 1]) {
-            "  "
-        } else {
-            " "
-        };
-        cur_line.push_str(sep);
-        cur_line.push_str(word);
-    }
-
-    if cur_line.len() > pfx.len() + indent.len() {
-        output.write_all(cur_line.as_bytes())?;
-        output.write_all(b"\n")?;
-    }
-
-    Ok(())
-}
@@ -161,6 +161,72 @@ fn test_fmt_prefix() {
     );
 }
 
+/// Email-style quoting used by the prefix tests below.
+const QUOTED_EMAIL: &str = "> Hello there, this is a long quoted line that will need wrapping\n\
+                            > short\n\
+                            >\n\
+                            >  indented reply text that is long enough to wrap\n\
+                            >> nested quote stays as is even though it is long\n\
+                            >x not quoted\n\
+                            > \n\
+                            Plain reply.\n\
+                            \x20 > indented quote block that wraps too\n\
+                            \x20 >   \n";
+
+#[test]
+fn test_fmt_prefix_email_quoting() {
+    // Expected output from GNU fmt -w 30 -p '> '
+    let config = FmtConfig {
+        width: 30,
+        goal: (30 * 93) / 100,
+        prefix: Some("> ".to_string()),
+        ..FmtConfig::default()
+    };
+    assert_eq!(
+        run_fmt(QUOTED_EMAIL, &config),
+        "> Hello there, this is a long\n\
+         > quoted line that will need\n\
+         > wrapping short\n\
+         >\n\
+         >  indented reply text that\n\
+         >  is long enough to wrap\n\
+         >> nested quote stays as is even though it is long\n\
+         >x not quoted\n\
+         >\n\
+         Plain reply.\n\
+         \x20 > indented quote block that\n\
+         \x20 > wraps too\n\
+         \x20 >\n"
+    );
+}
+
+#[test]
+fn test_fmt_prefix_only_line_separates_paragraphs() {
+    // ">" without the trailing space is a blank line of the quoted block:
+    // copied as is, and the text around it is not joined across it.
+    let config = FmtConfig {
+        prefix: Some("> ".to_string()),
+        ..FmtConfig::default()
+    };
+    assert_eq!(
+        run_fmt("> one\n>\n> two\n> three\n", &config),
+        "> one\n>\n> two three\n"
+    );
+}
+
+#[test]
+fn test_fmt_prefix_leading_blanks() {
+    // Leading blanks in the prefix require at least that much indentation
+    let config = FmtConfig {
+        prefix: Some("  # ".to_string()),
+        ..FmtConfig::default()
+    };
+    assert_eq!(
+        run_fmt("# a\n# b\n  # c\n  # d\n    # e\n", &config),
+        "# a\n# b\n  # c d\n    # e\n"
+    );
+}
+
 // ===== test_fmt_preserves_paragraphs =====
 
 #[test]
@@ -218,6 +284,89 @@ fn test_fmt_tagged() {
     }
 }
 
+// ===== Indentation changes end paragraphs (outputs from GNU fmt 9.1) =====
+
+#[test]
+fn test_fmt_nested_list_matches_gnu() {
+    let config = FmtConfig {
+        width: 40,
+        goal: (40 * 93) / 100,
+        ..FmtConfig::default()
+    };
+    let input = "Shopping list for the weekend trip\n\
+                 to the mountains:\n\
+                 - fruit and vegetables from the\n\
+                 \x20 market near the station\n\
+                 \x20 - apples, pears and a few\n\
+                 \x20   oranges for the kids\n\
+                 \x20 - carrots\n\
+                 - bread\n";
+    let expected = "Shopping list for the weekend trip to\n\
+                    the mountains: - fruit and vegetables\n\
+                    from the\n\
+                    \x20 market near the station - apples,\n\
+                    \x20 pears and a few\n\
+                    \x20   oranges for the kids\n\
+                    \x20 - carrots\n\
+                    - bread\n";
+    assert_eq!(run_fmt(input, &config), expected);
+}
+
+#[test]
+fn test_fmt_quoted_block_after_text_matches_gnu() {
+    let config = FmtConfig {
+        width: 40,
+        goal: (40 * 93) / 100,
+        ..FmtConfig::default()
+    };
+    let input = "As the manual puts it, the tool\n\
+                 reads each file in turn and\n\
+                 \x20   writes the words back out\n\
+                 \x20   filled to the goal width,\n\
+                 \x20   one paragraph at a time.\n\
+                 Which is all we need here.\n";
+    let expected = "As the manual puts it, the tool reads\n\
+                    each file in turn and\n\
+                    \x20   writes the words back out filled\n\
+                    \x20   to the goal width, one paragraph\n\
+                    \x20   at a time.\n\
+                    Which is all we need here.\n";
+    assert_eq!(run_fmt(input, &config), expected);
+}
+
+#[test]
+fn test_fmt_crown_and_tagged_follow_second_line_indent() {
+    let crown = FmtConfig {
+        crown_margin: true,
+        ..FmtConfig::default()
+    };
+    let input = "a b\n  c d\n  e f\n    g h\n  i\n";
+    assert_eq!(run_fmt(input, &crown), "a b c d e f\n    g h i\n");
+
+    let tagged = FmtConfig {
+        tagged: true,
+        ..FmtConfig::default()
+    };
+    // A second line indented like the first is a paragraph of its own
+    let input = "a b\nc d\ne f\n\n  x y\n    z w\n    q\n  r\n";
+    assert_eq!(
+        run_fmt(input, &tagged),
+        "a b\nc d\ne f\n\n  x y z w q\n  r\n"
+    );
+}
+
+#[test]
+fn test_fmt_prefix_indent_change_ends_paragraph() {
+    let config = FmtConfig {
+        prefix: Some("> ".to_string()),
+        ..FmtConfig::default()
+    };
+    assert_eq!(
+        run_fmt("> a b\n>   c d\n> e\n", &config),
+        "> a b\n>   c d\n> e\n"
+    );
+}
+
 // ===== test_fmt_empty_input =====
 
 #[test]
@@ -318,9 +467,267 @@ fn test_fmt_width_of_one() {
     assert_eq!(result, "a\nb\nc\n");
 }
 
+// ===== tab width =====
+
+#[test]
+fn test_fmt_tab_width_affects_wrapping() {
+    // "\t" + 27 columns of words: fits in 30 columns only if a tab is 2 wide.
+    let input = "\tone two three four five six\n";
+    let wide = FmtConfig {
+        width: 30,
+        goal: 30,
+        ..FmtConfig::default()
+    };
+    assert_eq!(run_fmt(input, &wide), "\tone two three four\n\tfive six\n");
+
+    let narrow = FmtConfig {
+        tab_width: 2,
+        ..wide
+    };
+    assert_eq!(run_fmt(input, &narrow), input);
+}
+
+#[test]
+fn test_fmt_tab_width_split_only() {
+    let input = "\tone two three four five six\n";
+    let config = FmtConfig {
+        width: 30,
+        goal: 30,
+        split_only: true,
+        ..FmtConfig::default()
+    };
+    assert_eq!(
+        run_fmt(input, &config),
+        "\tone two three four\n\tfive six\n"
+    );
+    let config = FmtConfig {
+        tab_width: 2,
+        ..config
+    };
+    assert_eq!(run_fmt(input, &config), input);
+}
+
+#[test]
+fn test_fmt_last_word_ends_sentence() {
+    // GNU marks a paragraph's last word as a sentence end, so a short one
+    // is not left alone when the line before can take a word less
+    let input =
+        "aaaaaaaaaaaaaaaaa lto = \"fat\" codegen-units = 1 panic = \"abort\" strip = true\n";
+    assert_eq!(
+        run_default(input),
+        "aaaaaaaaaaaaaaaaa lto = \"fat\" codegen-units = 1 panic = \"abort\" strip\n= true\n"
+    );
+}
+
+#[test]
+fn test_fmt_rebuilds_blanks_with_tabs() {
+    // Once the input has had a tab, indentation and gaps use tabs
+    assert_eq!(run_default("  \tfoo bar\n  \tbaz\n"), "\tfoo bar baz\n");
+    assert_eq!(
+        run_default("x\ty\n\n        foo bar\n"),
+        "x\ty\n\n\tfoo bar\n"
+    );
+    // Without one, spaces stay spaces
+    assert_eq!(
+        run_default("x       y\n\n        foo bar\n"),
+        "x       y\n\n        foo bar\n"
+    );
+}
+
+#[test]
+fn test_fmt_prefix_copies_lines_like_gnu() {
+    let config = FmtConfig {
+        prefix: Some("#".to_string()),
+        ..FmtConfig::default()
+    };
+    // A line of blanks comes out empty; other lines keep their columns
+    assert_eq!(run_fmt("   \n# a\n    zz\n", &config), "\n# a\n    zz\n");
+    assert_eq!(run_fmt("  \t\n# a\n  \tzz\n", &config), "\n# a\n\tzz\n");
+}
+
+// ===== --preserve-final-newline =====
+
+#[test]
+fn test_fmt_preserve_final_newline() {
+    let config = |preserve_final_newline| FmtConfig {
+        width: 20,
+        goal: 18,
+        preserve_final_newline,
+        ..FmtConfig::default()
+    };
+    let (preserve, default) = (config(true), config(false));
+    let cases: &[(&str, &str)] = &[
+        ("", ""),
+        ("one", "one"),
+        (
+            "one two three four five six",
+            "one two three four\nfive six",
+        ),
+        ("one\ntwo\n\nthree", "one two\n\nthree"),
+        // A last line of blanks is a blank output line, still unterminated
+        ("one\n  ", "one\n"),
+        ("one\n\n", "one\n\n"),
+        ("one\r\ntwo", "one two"),
+    ];
+    for (input, unterminated) in cases {
+        assert_eq!(run_fmt(input, &preserve), *unterminated, "{:?}", input);
+        // Default: the output always ends with a newline, as in GNU fmt
+        let expected = match *unterminated {
+            "" => String::new(),
+            out => format!("{}\n", out),
+        };
+        let terminated = match input.strip_suffix('\n') {
+            Some(_) => continue,
+            None => format!("{}\n", input),
+        };
+        assert_eq!(run_fmt(input, &default), expected, "{:?}", input);
+        // A terminated input is unaffected by the flag
+        assert_eq!(
+            run_fmt(&terminated, &preserve),
+            run_fmt(&terminated, &default),
+            "{:?}",
+            input
+        );
+    }
+}
+
+#[test]
+fn test_fmt_preserve_final_newline_modes() {
+    let preserve = || FmtConfig {
+        width: 10,
+        goal: 9,
+        preserve_final_newline: true,
+        ..FmtConfig::default()
+    };
+
+    // -s splits the last line but keeps it unterminated. As in GNU, the
+    // line's last word ends a sentence, so it is not left on its own.
+    let split = FmtConfig {
+        split_only: true,
+        ..preserve()
+    };
+    assert_eq!(
+        run_fmt("aaa bbb\nccc ddd eee", &split),
+        "aaa bbb\nccc\nddd eee"
+    );
+
+    // A last line copied verbatim in prefix mode
+    let prefixed = FmtConfig {
+        prefix: Some("> ".to_string()),
+        ..preserve()
+    };
+    assert_eq!(run_fmt("> aa\n> bb\nplain", &prefixed), "> aa bb\nplain");
+    assert_eq!(run_fmt("plain\n> aa\n> bb", &prefixed), "plain\n> aa bb");
+    assert_eq!(
+        run_fmt("plain\n> aa\n> bb\n", &prefixed),
+        "plain\n> aa bb\n"
+    );
+}
+
 // ===== Integration tests via binary =====
 
 #[cfg(test)]
+#[test]
+fn test_fmt_break_at_sentences() {
+    let config = FmtConfig {
+        width: 50,
+        goal: 46,
+        break_at_sentences: true,
+        ..FmtConfig::default()
+    };
+    let input = "  Short one. This second sentence is quite a bit longer and must \
+                 wrap inside itself at fifty columns! Is the third a question? \
+                 Yes. The fifth and final sentence also runs on for long enough \
+                 to take more than a single line of output.\n";
+    let result = run_fmt(input, &config);
+
+    let sentences = ["Short", "This", "Is", "Yes.", "The"];
+    let mut started = 0;
+    for line in result.lines() {
+        assert!(line.len() <= 50, "{:?}", line);
+        assert!(line.starts_with("  "), "{:?}", line);
+        let words: Vec<&str> = line.split_whitespace().collect();
+        // A sentence can only end at the end of a line
+        for w in &words[..words.len() - 1] {
+            assert!(!w.ends_with(['.', '!', '?']), "{:?}", line);
+        }
+        if started < sentences.len() && words[0] == sentences[started] {
+            started += 1;
+        }
+    }
+    assert_eq!(started, 5, "{}", result);
+    assert!(result.lines().count() > 5);
+
+    // A second pass changes nothing
+    assert_eq!(run_fmt(&result, &config), result);
+
+    // -u only affects spacing within lines
+    let uniform = FmtConfig {
+        uniform_spacing: true,
+        ..config
+    };
+    assert_eq!(run_fmt(input, &uniform), result);
+}
+
+#[test]
+fn test_fmt_break_at_sentences_skips_punctuation_words() {
+    let config = FmtConfig {
+        break_at_sentences: true,
+        ..FmtConfig::default()
+    };
+    // A lone "..." at a line end still gets GNU's two spaces, but the
+    // sentence runs on
+    let input = "We waited ... and waited. Then \u{2014} nothing?! Odd...\n...\nThe end.\n";
+    assert_eq!(
+        run_fmt(input, &config),
+        "We waited ... and waited.\n\
+         Then \u{2014} nothing?!\n\
+         Odd...\n\
+         ...  The end.\n"
+    );
+}
+
+// ===== Punctuation-only words and bullets (outputs from GNU fmt 9.1) =====
+
+#[test]
+fn test_fmt_uniform_spacing_ellipses_match_gnu() {
+    let config = FmtConfig {
+        uniform_spacing: true,
+        ..FmtConfig::default()
+    };
+    // Like any word ending in a period, "..." before a line end or two
+    // blanks gets two spaces after it
+    let input = "So we waited ...\n\
+                 and waited ... and then \u{2014} nothing...\n\
+                 Really?  Yes ...  it was odd.\n\
+                 ...\n\
+                 The end.\n";
+    let expected = "So we waited ...  and waited ... and then \u{2014} nothing...  Really?  Yes ...\n\
+                    it was odd.  ...  The end.\n";
+    assert_eq!(run_fmt(input, &config), expected);
+}
+
+#[test]
+fn test_fmt_bullets_match_gnu() {
+    // A bullet at the paragraph's indent does not start a paragraph, so
+    // the items are filled together
+    let input = "Notes for the release:\n\
+                 * fix the parser\n\
+                 * update docs\n\
+                 - remove the old flag\n\
+                 + plus item\n\
+                 1. first\n\
+                 a) second\n\
+                 \n\
+                 \x20 - indented one\n\
+                 \x20 - indented two\n";
+    let expected = "Notes for the release: * fix the parser * update docs - remove the old\n\
+                    flag + plus item 1. first a) second\n\
+                    \n\
+                    \x20 - indented one - indented two\n";
+    assert_eq!(run_default(input), expected);
+}
+
 mod integration {
     use std::process::Command;
 
@@ -344,7 +751,8 @@ mod integration {
         cmd.stderr(std::process::Stdio::piped());
         let mut child = cmd.spawn().expect("failed to spawn ffmt");
         use std::io::Write;
-        child.stdin.take().unwrap().write_all(input).unwrap();
+        // ffmt may reject its options and exit before reading any input
+        let _ = child.stdin.take().unwrap().write_all(input);
         let output = child.wait_with_output().expect("failed to wait");
         (output.stdout, output.status.code().unwrap_or(1))
     }
@@ -417,6 +825,29 @@ mod integration {
         assert_eq!(code, 1);
     }
 
+    #[test]
+    fn test_ffmt_errors_continue_with_next_operand() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("a"), "one\ntwo\n").unwrap();
+        std::fs::write(dir.path().join("b"), "three\nfour\n").unwrap();
+        std::fs::create_dir(dir.path().join("sub")).unwrap();
+        let output = Command::new(bin_path("ffmt"))
+            .current_dir(dir.path())
+            .args(["a", "missing", "sub", "b"])
+            .output()
+            .unwrap();
+        assert_eq!(output.status.code(), Some(1));
+        assert_eq!(
+            String::from_utf8_lossy(&output.stdout),
+            "one two\nthree four\n"
+        );
+        assert_eq!(
+            String::from_utf8_lossy(&output.stderr),
+            "fmt: cannot open 'missing' for reading: No such file or directory\n\
+             fmt: sub: read error: Is a directory\n"
+        );
+    }
+
     #[cfg(target_os = "linux")]
     #[test]
     fn test_ffmt_matches_gnu() {
@@ -448,4 +879,198 @@ mod integration {
             }
         }
     }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_ffmt_prefix_matches_gnu() {
+        let input = super::QUOTED_EMAIL.as_bytes();
+        for prefix in ["> ", " >", "  > "] {
+            let args = ["-w", "30", "-p", prefix];
+            let (our_out, code) = run_ffmt(input, &args);
+            assert_eq!(code, 0);
+
+            let gnu_out = Command::new("fmt")
+                .args(args)
+                .stdin(std::process::Stdio::piped())
+                .stdout(std::process::Stdio::piped())
+                .spawn()
+                .and_then(|mut child| {
+                    use std::io::Write;
+                    child.stdin.take().unwrap().write_all(input).unwrap();
+                    child.wait_with_output()
+                });
+            if let Ok(gnu) = gnu_out
+                && gnu.status.success()
+            {
+                assert_eq!(
+                    String::from_utf8_lossy(&our_out),
+                    String::from_utf8_lossy(&gnu.stdout),
+                    "output differs from GNU fmt -p {:?}",
+                    prefix
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_ffmt_tab_width() {
+        let input = b"\tone two three four five six\n";
+        let (out, code) = run_ffmt(input, &["-w", "30"]);
+        assert_eq!(code, 0);
+        assert_eq!(out, b"\tone two three four\n\tfive six\n");
+        let (out, code) = run_ffmt(input, &["-w", "30", "--tab-width=2"]);
+        assert_eq!(code, 0);
+        assert_eq!(out, input);
+        let (_, code) = run_ffmt(input, &["--tab-width", "0"]);
+        assert_eq!(code, 1);
+    }
+
+    #[test]
+    fn test_ffmt_width_auto_pipe_fallback() {
+        // stdout is a pipe here, so --width=auto falls back to 75;
+        // COLUMNS must not be consulted.
+        let input = "word ".repeat(60);
+        let (default_out, _) = run_ffmt(input.as_bytes(), &[]);
+        let mut cmd = Command::new(bin_path("ffmt"));
+        cmd.arg("--width=auto").env("COLUMNS", "20");
+        cmd.stdin(std::process::Stdio::piped());
+        cmd.stdout(std::process::Stdio::piped());
+        let mut child = cmd.spawn().expect("failed to spawn ffmt");
+        use std::io::Write;
+        child
+            .stdin
+            .take()
+            .unwrap()
+            .write_all(input.as_bytes())
+            .unwrap();
+        let output = child.wait_with_output().expect("failed to wait");
+        assert!(output.status.success());
+        assert_eq!(output.stdout, default_out);
+
+        // A later numeric width overrides auto
+        let (out, _) = run_ffmt(input.as_bytes(), &["-w", "auto", "-w", "20"]);
+        assert!(
+            String::from_utf8(out)
+                .unwrap()
+                .lines()
+                .all(|l| l.len() <= 20)
+        );
+    }
+
+    #[test]
+    fn test_ffmt_break_at_sentences_rejects_split_only() {
+        let (out, code) = run_ffmt(b"a. b.\n", &["-s", "--break-at-sentences"]);
+        assert_eq!(code, 1);
+        assert!(out.is_empty());
+        let (out, code) = run_ffmt(b"One. Two.\n", &["--break-at-sentences"]);
+        assert_eq!(code, 0);
+        assert_eq!(out, b"One.\nTwo.\n");
+    }
+
+    /// Two files where the first stops mid-paragraph and the second
+    /// carries on with it.
+    fn split_paragraph(dir: &std::path::Path) {
+        std::fs::write(dir.join("a"), "one two three\nfour five\n").unwrap();
+        std::fs::write(dir.join("b"), "six seven\neight\n\nnine\n").unwrap();
+    }
+
+    #[test]
+    fn test_ffmt_files_formatted_separately() {
+        let dir = tempfile::tempdir().unwrap();
+        split_paragraph(dir.path());
+        let output = Command::new(bin_path("ffmt"))
+            .current_dir(dir.path())
+            .args(["a", "b"])
+            .output()
+            .unwrap();
+        assert_eq!(
+            String::from_utf8_lossy(&output.stdout),
+            "one two three four five\nsix seven eight\n\nnine\n"
+        );
+
+        // GNU fmt ends a paragraph at every file boundary too
+        if let Ok(gnu) = Command::new("fmt")
+            .current_dir(dir.path())
+            .args(["a", "b"])
+            .output()
+            && gnu.status.success()
+        {
+            assert_eq!(output.stdout, gnu.stdout);
+        }
+    }
+
+    #[test]
+    fn test_ffmt_join_files() {
+        let dir = tempfile::tempdir().unwrap();
+        split_paragraph(dir.path());
+        // The first file's missing newline still ends its last line
+        std::fs::write(dir.path().join("c"), "zero").unwrap();
+        let output = Command::new(bin_path("ffmt"))
+            .current_dir(dir.path())
+            .args(["--join-files", "c", "a", "missing", "b"])
+            .output()
+            .unwrap();
+        assert_eq!(output.status.code(), Some(1));
+        assert_eq!(
+            String::from_utf8_lossy(&output.stdout),
+            "zero one two three four five six seven eight\n\nnine\n"
+        );
+        assert_eq!(
+            String::from_utf8_lossy(&output.stderr),
+            "fmt: cannot open 'missing' for reading: No such file or directory\n"
+        );
+    }
+
+    #[test]
+    fn test_ffmt_prefix_paragraph_ends_with_file() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("a"), "> one two\n> three\n").unwrap();
+        std::fs::write(dir.path().join("b"), "> four\nplain\n").unwrap();
+        let run = |args: &[&str]| {
+            let output = Command::new(bin_path("ffmt"))
+                .current_dir(dir.path())
+                .args(args)
+                .output()
+                .unwrap();
+            String::from_utf8(output.stdout).unwrap()
+        };
+        assert_eq!(
+            run(&["-p", "> ", "a", "b"]),
+            "> one two three\n> four\nplain\n"
+        );
+        assert_eq!(
+            run(&["-p", "> ", "--join-files", "a", "b"]),
+            "> one two three four\nplain\n"
+        );
+    }
+
+    #[test]
+    fn test_ffmt_width_limits() {
+        let input = "aaaa bbbb cccc dddd eeee ffff gggg hhhh iiii jjjj kkkk llll\n";
+        // -g alone sets the width 10 columns wider
+        let (out, code) = run_ffmt(input.as_bytes(), &["-g", "20"]);
+        assert_eq!(code, 0);
+        assert_eq!(
+            out,
+            b"aaaa bbbb cccc dddd\neeee ffff gggg hhhh\niiii jjjj kkkk llll\n"
+        );
+        // The goal may not pass the width, 75 by default, nor the width 2500
+        assert_eq!(run_ffmt(input.as_bytes(), &["-g", "80"]).1, 1);
+        assert_eq!(run_ffmt(input.as_bytes(), &["-w", "50", "-g", "60"]).1, 1);
+        assert_eq!(run_ffmt(input.as_bytes(), &["-w", "90", "-g", "80"]).1, 0);
+        assert_eq!(run_ffmt(input.as_bytes(), &["-w", "2501"]).1, 1);
+        assert_eq!(run_ffmt(input.as_bytes(), &["-w", "2500"]).1, 0);
+    }
+
+    #[test]
+    fn test_ffmt_preserve_final_newline() {
+        let input = b"one two three\nfour";
+        let (out, code) = run_ffmt(input, &["--preserve-final-newline"]);
+        assert_eq!(code, 0);
+        assert_eq!(out, b"one two three four");
+        let (out, _) = run_ffmt(input, &[]);
+        assert_eq!(out, b"one two three four\n");
+        let (out, _) = run_ffmt(b"one\n", &["--preserve-final-newline"]);
+        assert_eq!(out, b"one\n");
+    }
 }
@@ -0,0 +1,149 @@
+use std::io;
+use std::path::Path;
+
+// ── Extended-attribute digest cache (`--cache=xattr`) ──────────────────
+// Opt-in only: the digest of a file is stored in `user.fcoreutils.<key>`
+// together with the file's size and nanosecond mtime at hashing time, as
+// "SIZE SEC.NSEC DIGEST". A later run trusts the stored digest only while
+// size and mtime still match. Every xattr failure (unsupported filesystem,
+// EPERM, ENODATA, malformed value) is treated as a cache miss.
+
+/// Where a digest returned by `hash_file_cached` came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DigestSource {
+    /// The file was read and hashed.
+    Computed,
+    /// The digest was served from the xattr cache without reading the file.
+    Cached,
+}
+
+/// Attribute name for a cache key such as "md5", "sha256" or "blake2b-256".
+/// The key must identify the digest length as well as the algorithm.
+pub fn cache_attr_name(key: &str) -> String {
+    format!("user.fcoreutils.{}", key)
+}
+
+/// Return `path`'s digest, served from the xattr cache when the stored size
+/// and mtime still match. Otherwise `compute` hashes the file and the cache
+/// is refreshed — but only if the file did not change while being hashed.
+pub fn hash_file_cached(
+    path: &Path,
+    key: &str,
+    compute: impl FnOnce() -> io::Result<String>,
+) -> io::Result<(String, DigestSource)> {
+    let attr = cache_attr_name(key);
+    let before = file_stamp(path);
+    if let Some(stamp) = before
+        && let Some(value) = xattr::get(path, &attr)
+        && let Some(digest) = parse_cache_value(&value, stamp)
+    {
+        return Ok((digest.to_string(), DigestSource::Cached));
+    }
+
+    let digest = compute()?;
+    if let Some(stamp) = before
+        && file_stamp(path) == Some(stamp)
+    {
+        let _ = xattr::set(path, &attr, format_cache_value(stamp, &digest).as_bytes());
+    }
+    Ok((digest, DigestSource::Computed))
+}
+
+/// (size, mtime seconds, mtime nanoseconds) of a regular file.
+type Stamp = (u64, i64, i64);
+
+#[cfg(unix)]
+fn file_stamp(path: &Path) -> Option<Stamp> {
+    use std::os::unix::fs::MetadataExt;
+    let meta = std::fs::metadata(path).ok()?;
+    if !meta.is_file() {
+        return None;
+    }
+    Some((meta.size(), meta.mtime(), meta.mtime_nsec()))
+}
+
+#[cfg(not(unix))]
+fn file_stamp(_path: &Path) -> Option<Stamp> {
+    None
+}
+
+fn format_cache_value(stamp: Stamp, digest: &str) -> String {
+    format!("{} {}.{:09} {}", stamp.0, stamp.1, stamp.2, digest)
+}
+
+/// Return the cached digest if `value` is well-formed and matches `stamp`.
+fn parse_cache_value(value: &[u8], stamp: Stamp) -> Option<&str> {
+    let value = std::str::from_utf8(value).ok()?;
+    let mut parts = value.split(' ');
+    let size: u64 = parts.next()?.parse().ok()?;
+    let (sec, nsec) = parts.next()?.split_once('.')?;
+    let sec: i64 = sec.parse().ok()?;
+    let nsec: i64 = nsec.parse().ok()?;
+    let digest = parts.next()?;
+    if parts.next().is_some()
+        || digest.is_empty()
+        || !digest.bytes().all(|b| b.is_ascii_hexdigit())
+        || (size, sec, nsec) != stamp
+    {
+        return None;
+    }
+    Some(digest)
+}
+
+#[cfg(target_os = "linux")]
+mod xattr {
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
+    use std::path::Path;
+
+    /// Cached values are short ("SIZE SEC.NSEC" plus at most 128 hex digits).
+    const MAX_VALUE: usize = 256;
+
+    pub fn get(path: &Path, name: &str) -> Option<Vec<u8>> {
+        let c_path = CString::new(path.as_os_str().as_bytes()).ok()?;
+        let c_name = CString::new(name).ok()?;
+        let mut buf = vec![0u8; MAX_VALUE];
+        let n = unsafe {
+            libc::getxattr(
+                c_path.as_ptr(),
+                c_name.as_ptr(),
+                buf.as_mut_ptr() as *mut libc::c_void,
+                buf.len(),
+            )
+        };
+        if n < 0 {
+            return None;
+        }
+        buf.truncate(n as usize);
+        Some(buf)
+    }
+
+    pub fn set(path: &Path, name: &str, value: &[u8]) -> Option<()> {
+        let c_path = CString::new(path.as_os_str().as_bytes()).ok()?;
+        let c_name = CString::new(name).ok()?;
+        let ret = unsafe {
+            libc::setxattr(
+                c_path.as_ptr(),
+                c_name.as_ptr(),
+                value.as_ptr() as *const libc::c_void,
+                value.len(),
+                0,
+            )
+        };
+        (ret == 0).then_some(())
+    }
+}
+
+/// No xattr support: every lookup misses and nothing is stored.
+#[cfg(not(target_os = "linux"))]
+mod xattr {
+    use std::path::Path;
+
+    pub fn get(_path: &Path, _name: &str) -> Option<Vec<u8>> {
+        None
+    }
+
+    pub fn set(_path: &Path, _name: &str, _value: &[u8]) -> Option<()> {
+        None
+    }
+}
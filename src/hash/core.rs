@@ -1,8 +1,15 @@
+use std::borrow::Cow;
 use std::cell::RefCell;
 use std::fs::File;
 use std::io::{self, BufRead, Read, Write};
 use std::path::Path;
 
+use crate::common::io::read_retry;
+#[cfg(target_os = "linux")]
+use crate::common::sigbus;
+
+use super::verify::{VerifyEntry, VerifyOptions, VerifyResult, verify_manifest_with};
+
 use std::sync::atomic::AtomicUsize;
 #[cfg(target_os = "linux")]
 use std::sync::atomic::{AtomicBool, Ordering};
@@ -38,6 +45,18 @@ impl HashAlgorithm {
             HashAlgorithm::Blake2b => "BLAKE2b",
         }
     }
+
+    /// Length of the digest in bytes (BLAKE2b at its full 512 bits).
+    pub fn digest_len(self) -> usize {
+        match self {
+            HashAlgorithm::Sha1 => 20,
+            HashAlgorithm::Sha224 => 28,
+            HashAlgorithm::Sha256 => 32,
+            HashAlgorithm::Sha384 => 48,
+            HashAlgorithm::Sha512 | HashAlgorithm::Blake2b => 64,
+            HashAlgorithm::Md5 => 16,
+        }
+    }
 }
 
 // ── Generic hash helpers ────────────────────────────────────────────
@@ -728,10 +747,29 @@ thread_local! {
 /// Optimized hash for large files (>=16MB) on Linux.
 /// Primary path: mmap with HUGEPAGE + POPULATE_READ for zero-copy, single-shot hash.
 /// Falls back to streaming I/O with double-buffered reader thread if mmap fails.
+///
+/// The mapping covers the `file_size` bytes seen at open, whatever the file
+/// does meanwhile. If its size or mtime changed by the end of the hash, the
+/// digest describes no version of the file, so "file changed while reading"
+/// is returned instead; a truncation that faults mid-hash is reported the
+/// same way by the `sigbus` handler, when the tool installed it.
 #[cfg(target_os = "linux")]
-fn hash_file_pipelined(algo: HashAlgorithm, file: File, file_size: u64) -> io::Result<String> {
+fn hash_file_pipelined(
+    algo: HashAlgorithm,
+    file: File,
+    file_size: u64,
+    path: &Path,
+) -> io::Result<String> {
+    let stamp = file_stamp(&file)?;
+    if stamp.0 != file_size {
+        return Err(sigbus::changed_error());
+    }
     // Primary path: mmap with huge pages for zero-copy single-shot hash.
-    match unsafe { memmap2::MmapOptions::new().map(&file) } {
+    match unsafe {
+        memmap2::MmapOptions::new()
+            .len(file_size as usize)
+            .map(&file)
+    } {
         Ok(mmap) => {
             if file_size >= 2 * 1024 * 1024 {
                 let _ = mmap.advise(memmap2::Advice::HugePage);
@@ -744,12 +782,27 @@ fn hash_file_pipelined(algo: HashAlgorithm, file: File, file_size: u64) -> io::R
             } else {
                 let _ = mmap.advise(memmap2::Advice::WillNeed);
             }
-            Ok(hash_bytes(algo, &mmap))
+            let hash = {
+                let _guard = sigbus::Guard::new(path.as_os_str());
+                hash_bytes(algo, &mmap)
+            };
+            if file_stamp(&file)? != stamp {
+                return Err(sigbus::changed_error());
+            }
+            Ok(hash)
         }
         Err(_) => hash_file_pipelined_read(algo, file, file_size),
     }
 }
 
+/// Size and modification time of `file`, to tell whether it changed.
+#[cfg(target_os = "linux")]
+fn file_stamp(file: &File) -> io::Result<(u64, i64, i64)> {
+    use std::os::unix::fs::MetadataExt;
+    let meta = file.metadata()?;
+    Ok((meta.size(), meta.mtime(), meta.mtime_nsec()))
+}
+
 /// Streaming fallback for large files when mmap is unavailable.
 /// Uses double-buffered reader thread with fadvise hints.
 /// Fixed: uses blocking recv() to eliminate triple-buffer allocation bug.
@@ -893,7 +946,7 @@ pub fn hash_file(algo: HashAlgorithm, path: &Path) -> io::Result<String> {
         if file_size >= SMALL_FILE_LIMIT {
             #[cfg(target_os = "linux")]
             {
-                return hash_file_pipelined(algo, file, file_size);
+                return hash_file_pipelined(algo, file, file_size, path);
             }
             // Non-Linux: mmap fallback
             #[cfg(not(target_os = "linux"))]
@@ -1583,7 +1636,7 @@ pub fn blake2b_hash_files_parallel(
 
     // Sort largest first: ensures big files start hashing immediately while
     // small files fill in gaps, minimizing tail latency.
-    indexed.sort_by(|a, b| b.2.cmp(&a.2));
+    indexed.sort_by_key(|&(_, _, size)| std::cmp::Reverse(size));
 
     // Warm page cache for the largest files using async readahead(2).
     // Each hash call handles its own mmap prefaulting, but issuing readahead
@@ -1668,7 +1721,7 @@ pub fn hash_files_parallel(paths: &[&Path], algo: HashAlgorithm) -> Vec<io::Resu
 
     // Sort largest first: ensures big files start hashing immediately while
     // small files fill in gaps, minimizing tail latency.
-    indexed.sort_by(|a, b| b.2.cmp(&a.2));
+    indexed.sort_by_key(|&(_, _, size)| std::cmp::Reverse(size));
 
     // Warm page cache for the largest files using async readahead(2).
     // Each hash call handles its own mmap prefaulting, but issuing readahead
@@ -2440,6 +2493,8 @@ pub struct CheckOptions {
     /// When non-empty, warnings use GNU format: "{prefix}: {line}: message".
     /// When empty, uses generic format: "line {line}: message".
     pub warn_prefix: String,
+    /// Read listed files with O_DIRECT (`--direct-io`).
+    pub direct_io: bool,
 }
 
 /// Result of check mode verification.
@@ -2454,6 +2509,7 @@ pub struct CheckResult {
 
 /// Verify checksums from a check file.
 /// Each line should be "hash  filename" or "hash *filename" or "ALGO (filename) = hash".
+/// The digest field may be hex or base64 (see `decode_digest_field`).
 pub fn check_file<R: BufRead>(
     algo: HashAlgorithm,
     reader: R,
@@ -2461,123 +2517,205 @@ pub fn check_file<R: BufRead>(
     out: &mut impl Write,
     err_out: &mut impl Write,
 ) -> io::Result<CheckResult> {
-    let quiet = opts.quiet;
-    let status_only = opts.status_only;
-    let warn = opts.warn;
-    let ignore_missing = opts.ignore_missing;
-    let mut ok_count = 0;
-    let mut mismatch_count = 0;
-    let mut format_errors = 0;
-    let mut read_errors = 0;
-    let mut ignored_missing_count = 0;
-    let mut line_num = 0;
-
-    for line_result in reader.lines() {
-        line_num += 1;
-        let line = line_result?;
-        let line = line.trim_end();
-
-        if line.is_empty() {
-            continue;
-        }
-
-        // Parse "hash  filename" or "hash *filename" or "ALGO (file) = hash"
-        let (expected_hash, filename) = match parse_check_line(line) {
-            Some(v) => v,
-            None => {
-                format_errors += 1;
-                if warn {
-                    out.flush()?;
-                    if opts.warn_prefix.is_empty() {
-                        writeln!(
-                            err_out,
-                            "line {}: improperly formatted {} checksum line",
-                            line_num,
-                            algo.name()
-                        )?;
-                    } else {
-                        writeln!(
-                            err_out,
-                            "{}: {}: improperly formatted {} checksum line",
-                            opts.warn_prefix,
-                            line_num,
-                            algo.name()
-                        )?;
-                    }
-                }
-                continue;
-            }
-        };
+    check_lines(Some(algo), reader, opts, out, err_out)
+}
 
-        // Compute actual hash
-        let actual = match hash_file(algo, Path::new(filename)) {
-            Ok(h) => h,
-            Err(e) => {
-                if ignore_missing && e.kind() == io::ErrorKind::NotFound {
-                    ignored_missing_count += 1;
-                    continue;
+/// Verify checksums from a check file of tagged lines ("ALGO (filename) = hash"),
+/// taking each line's algorithm from its tag, like `cksum --check` without -a.
+/// Untagged lines are improperly formatted here.
+pub fn check_file_tagged<R: BufRead>(
+    reader: R,
+    opts: &CheckOptions,
+    out: &mut impl Write,
+    err_out: &mut impl Write,
+) -> io::Result<CheckResult> {
+    check_lines(None, reader, opts, out, err_out)
+}
+
+fn check_lines<R: BufRead>(
+    algo: Option<HashAlgorithm>,
+    reader: R,
+    opts: &CheckOptions,
+    out: &mut impl Write,
+    err_out: &mut impl Write,
+) -> io::Result<CheckResult> {
+    let verify_opts = VerifyOptions {
+        algorithm: algo,
+        ignore_missing: opts.ignore_missing,
+        strict: opts.strict,
+        direct_io: opts.direct_io,
+    };
+    let report = verify_manifest_with(reader, Path::new(""), &verify_opts, |entry| {
+        write_check_entry(&entry, algo, opts, out, err_out)
+    })?;
+
+    Ok(CheckResult {
+        ok: report.ok,
+        mismatches: report.mismatches,
+        format_errors: report.malformed,
+        read_errors: report.read_errors
+            + if opts.ignore_missing {
+                0
+            } else {
+                report.missing
+            },
+        ignored_missing: if opts.ignore_missing {
+            report.missing
+        } else {
+            0
+        },
+    })
+}
+
+/// Render one verification entry the way GNU `--check` prints it.
+fn write_check_entry(
+    entry: &VerifyEntry,
+    algo: Option<HashAlgorithm>,
+    opts: &CheckOptions,
+    out: &mut impl Write,
+    err_out: &mut impl Write,
+) -> io::Result<()> {
+    let file = &entry.file;
+    match &entry.result {
+        VerifyResult::Ok => {
+            if !opts.quiet && !opts.status_only {
+                writeln!(out, "{}: OK", file)?;
+            }
+        }
+        VerifyResult::Mismatch { .. } => {
+            if !opts.status_only {
+                writeln!(out, "{}: FAILED", file)?;
+            }
+        }
+        VerifyResult::Missing if opts.ignore_missing => {}
+        VerifyResult::Missing | VerifyResult::ReadError(_) => {
+            if !opts.status_only {
+                out.flush()?;
+                match &entry.result {
+                    VerifyResult::ReadError(e) => {
+                        writeln!(err_out, "{}: {}", file, crate::common::io_error_msg(e))?
+                    }
+                    _ => writeln!(err_out, "{}: No such file or directory", file)?,
                 }
-                read_errors += 1;
-                if !status_only {
-                    out.flush()?;
-                    writeln!(err_out, "{}: {}", filename, e)?;
-                    writeln!(out, "{}: FAILED open or read", filename)?;
+                writeln!(out, "{}: FAILED open or read", file)?;
+            }
+        }
+        VerifyResult::Malformed { line_no } => {
+            if opts.warn {
+                out.flush()?;
+                let what = match algo {
+                    Some(a) => format!("{} checksum line", a.name()),
+                    None => "checksum line".to_string(),
+                };
+                if opts.warn_prefix.is_empty() {
+                    writeln!(err_out, "line {}: improperly formatted {}", line_no, what)?;
+                } else {
+                    writeln!(
+                        err_out,
+                        "{}: {}: improperly formatted {}",
+                        opts.warn_prefix, line_no, what
+                    )?;
                 }
-                continue;
             }
-        };
+        }
+    }
+    Ok(())
+}
 
-        if actual.eq_ignore_ascii_case(expected_hash) {
-            ok_count += 1;
-            if !quiet && !status_only {
-                writeln!(out, "{}: OK", filename)?;
-            }
-        } else {
-            mismatch_count += 1;
-            if !status_only {
-                writeln!(out, "{}: FAILED", filename)?;
+/// Map a check-line tag ("SHA256", "BLAKE2b", "BLAKE2b-256") to its
+/// algorithm and digest length in bytes.
+pub(crate) fn tag_algorithm(tag: &str) -> Option<(HashAlgorithm, usize)> {
+    Some(match tag {
+        "MD5" => (HashAlgorithm::Md5, 16),
+        "SHA1" => (HashAlgorithm::Sha1, 20),
+        "SHA224" => (HashAlgorithm::Sha224, 28),
+        "SHA256" => (HashAlgorithm::Sha256, 32),
+        "SHA384" => (HashAlgorithm::Sha384, 48),
+        "SHA512" => (HashAlgorithm::Sha512, 64),
+        "BLAKE2b" => (HashAlgorithm::Blake2b, 64),
+        _ => {
+            let bits: usize = tag.strip_prefix("BLAKE2b-")?.parse().ok()?;
+            if bits == 0 || bits > 512 || !bits.is_multiple_of(8) {
+                return None;
             }
+            (HashAlgorithm::Blake2b, bits / 8)
+        }
+    })
+}
+
+/// Decode the digest field of a check line into `digest_len` raw bytes.
+/// Hex (either case) and padded standard base64 (`cksum --base64`) are
+/// accepted. Each encoding has one fixed length for a given digest size, so
+/// the field's length picks the interpretation; when both lengths coincide
+/// (2- and 4-byte digests) a field of hex digits is read as hex.
+pub fn decode_digest_field(field: &str, digest_len: usize) -> Option<Vec<u8>> {
+    if field.len() == digest_len * 2
+        && let Some(bytes) = hex_decode(field)
+    {
+        return Some(bytes);
+    }
+    if field.len() == digest_len.div_ceil(3) * 4 {
+        let bytes = base64_simd::STANDARD.decode_to_vec(field).ok()?;
+        if bytes.len() == digest_len {
+            return Some(bytes);
         }
     }
+    None
+}
 
-    Ok(CheckResult {
-        ok: ok_count,
-        mismatches: mismatch_count,
-        format_errors,
-        read_errors,
-        ignored_missing: ignored_missing_count,
-    })
+/// Whether the digest field of a check line matches a computed hex digest.
+pub fn digest_matches(field: &str, actual_hex: &str) -> bool {
+    match decode_digest_field(field, actual_hex.len() / 2) {
+        Some(expected) => hex_encode(&expected).eq_ignore_ascii_case(actual_hex),
+        None => false,
+    }
+}
+
+/// A hex digest as it is printed: lowercase, as GNU always prints it, or
+/// uppercase for `--uppercase`.
+pub fn hex_in_case(hex: &str, uppercase: bool) -> Cow<'_, str> {
+    if uppercase {
+        Cow::Owned(hex.to_ascii_uppercase())
+    } else {
+        Cow::Borrowed(hex)
+    }
+}
+
+/// Re-encode a hex digest as padded standard base64, for `--base64` output.
+pub fn hex_to_base64(hex: &str) -> String {
+    base64_simd::STANDARD.encode_to_string(hex_decode(hex).unwrap_or_default())
+}
+
+/// Decode a hex string (either case). `None` on odd length or a non-hex digit.
+pub fn hex_decode(hex: &str) -> Option<Vec<u8>> {
+    fn nibble(b: u8) -> Option<u8> {
+        match b {
+            b'0'..=b'9' => Some(b - b'0'),
+            b'a'..=b'f' => Some(b - b'a' + 10),
+            b'A'..=b'F' => Some(b - b'A' + 10),
+            _ => None,
+        }
+    }
+    let bytes = hex.as_bytes();
+    if !bytes.len().is_multiple_of(2) {
+        return None;
+    }
+    bytes
+        .chunks_exact(2)
+        .map(|pair| Some(nibble(pair[0])? << 4 | nibble(pair[1])?))
+        .collect()
 }
 
 /// Parse a checksum line in any supported format.
+///
+/// Besides the tagged form (see `parse_check_line_tagged`) this accepts
+/// GNU's "hash  filename" and "hash *filename", and the reversed BSD form
+/// "hash filename" with a single space that `md5 -r` and `sha256 -r`
+/// print. GNU refuses a file that mixes the reversed form with its own;
+/// each line is taken on its own here.
 pub fn parse_check_line(line: &str) -> Option<(&str, &str)> {
-    // Try BSD tag format: "ALGO (filename) = hash"
-    let rest = line
-        .strip_prefix("MD5 (")
-        .or_else(|| line.strip_prefix("SHA1 ("))
-        .or_else(|| line.strip_prefix("SHA224 ("))
-        .or_else(|| line.strip_prefix("SHA256 ("))
-        .or_else(|| line.strip_prefix("SHA384 ("))
-        .or_else(|| line.strip_prefix("SHA512 ("))
-        .or_else(|| line.strip_prefix("BLAKE2b ("))
-        .or_else(|| {
-            // Handle BLAKE2b-NNN (filename) = hash
-            if line.starts_with("BLAKE2b-") {
-                let after = &line["BLAKE2b-".len()..];
-                if let Some(sp) = after.find(" (") {
-                    if after[..sp].bytes().all(|b| b.is_ascii_digit()) {
-                        return Some(&after[sp + 2..]);
-                    }
-                }
-            }
-            None
-        });
-    if let Some(rest) = rest {
-        if let Some(paren_idx) = rest.find(") = ") {
-            let filename = &rest[..paren_idx];
-            let hash = &rest[paren_idx + 4..];
-            return Some((hash, filename));
-        }
+    if let Some((_, hash, filename)) = parse_check_line_tagged(line) {
+        return Some((hash, filename));
     }
 
     // Handle backslash-escaped lines (leading '\')
@@ -2595,19 +2733,61 @@ pub fn parse_check_line(line: &str) -> Option<(&str, &str)> {
         let rest = &line[idx + 2..];
         return Some((hash, rest));
     }
-    None
+    // BSD reversed: "hash filename", the hash all hex digits
+    let (hash, rest) = line.split_once(' ')?;
+    (!hash.is_empty() && !rest.is_empty() && hash.bytes().all(|b| b.is_ascii_hexdigit()))
+        .then_some((hash, rest))
+}
+
+/// Split the part of a tagged line after its tag, "(filename) = hash",
+/// into (hash, filename). As in GNU, the space before "(" and the spaces
+/// around "=" are optional, so BSD spellings like "(f)= hash" parse too,
+/// and the name runs to the last ")", which a digest never contains.
+fn split_tagged_body(body: &str) -> Option<(&str, &str)> {
+    let body = body.strip_prefix(' ').unwrap_or(body).strip_prefix('(')?;
+    let close = body.rfind(')')?;
+    let hash = body[close + 1..]
+        .trim_start_matches(' ')
+        .strip_prefix('=')?
+        .trim_start_matches(' ');
+    (!hash.is_empty()).then_some((hash, &body[..close]))
+}
+
+/// Parse a BSD tag line for one of the supported algorithms:
+/// "ALGO (filename) = hash" with ALGO one of MD5, SHA1, SHA224, SHA256,
+/// SHA384, SHA512, BLAKE2b or BLAKE2b-NNN. Returns (tag, hash, filename).
+/// The spacing may vary as `split_tagged_body` describes.
+pub fn parse_check_line_tagged(line: &str) -> Option<(&str, &str, &str)> {
+    let tag = [
+        "MD5", "SHA1", "SHA224", "SHA256", "SHA384", "SHA512", "BLAKE2b",
+    ]
+    .into_iter()
+    .find(|t| {
+        line.strip_prefix(t)
+            .is_some_and(|rest| rest.starts_with(" (") || rest.starts_with('('))
+    })
+    .map(|t| &line[..t.len()])
+    .or_else(|| {
+        // Handle BLAKE2b-NNN (filename) = hash
+        let after = line.strip_prefix("BLAKE2b-")?;
+        let digits = after.bytes().take_while(u8::is_ascii_digit).count();
+        (digits > 0).then(|| &line[.."BLAKE2b-".len() + digits])
+    })?;
+    let (hash, filename) = split_tagged_body(&line[tag.len()..])?;
+    Some((tag, hash, filename))
 }
 
 /// Parse a BSD-style tag line: "ALGO (filename) = hash"
 /// Returns (expected_hash, filename, optional_bits).
 /// `bits` is the hash length parsed from the algo name (e.g., BLAKE2b-256 -> Some(256)).
 pub fn parse_check_line_tag(line: &str) -> Option<(&str, &str, Option<usize>)> {
-    let paren_start = line.find(" (")?;
+    let paren_start = line.find('(')?;
     let algo_part = &line[..paren_start];
-    let rest = &line[paren_start + 2..];
-    let paren_end = rest.find(") = ")?;
-    let filename = &rest[..paren_end];
-    let hash = &rest[paren_end + 4..];
+    let algo_part = algo_part.strip_suffix(' ').unwrap_or(algo_part);
+    if algo_part.is_empty() || algo_part.contains(' ') {
+        return None;
+    }
+    let (hash, filename) = split_tagged_body(&line[algo_part.len()..])?;
 
     // Parse optional bit length from algo name (e.g., "BLAKE2b-256" -> Some(256))
     let bits = if let Some(dash_pos) = algo_part.rfind('-') {
@@ -2625,18 +2805,16 @@ pub fn parse_check_line_tag(line: &str) -> Option<(&str, &str, Option<usize>)> {
 #[inline]
 fn read_full(reader: &mut impl Read, buf: &mut [u8]) -> io::Result<usize> {
     // Fast path: first read() usually fills the entire buffer for regular files
-    let n = reader.read(buf)?;
+    let n = read_retry(reader, buf)?;
     if n == buf.len() || n == 0 {
         return Ok(n);
     }
     // Slow path: partial read — retry to fill buffer (pipes, slow devices)
     let mut total = n;
     while total < buf.len() {
-        match reader.read(&mut buf[total..]) {
-            Ok(0) => break,
-            Ok(n) => total += n,
-            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
-            Err(e) => return Err(e),
+        match read_retry(reader, &mut buf[total..])? {
+            0 => break,
+            n => total += n,
         }
     }
     Ok(total)
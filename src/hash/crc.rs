@@ -0,0 +1,97 @@
+use std::io::{self, BufRead, Read};
+
+/// POSIX CRC-32 slicing-by-4 lookup tables using polynomial 0x04C11DB7.
+/// Table 0 is the standard byte-at-a-time table; tables 1-3 enable processing
+/// 4 bytes per iteration for ~4x throughput improvement.
+pub(crate) const CRC_TABLES: [[u32; 256]; 4] = {
+    let mut tables = [[0u32; 256]; 4];
+    // Build the base table (table 0)
+    let mut i = 0u32;
+    while i < 256 {
+        let mut crc = i << 24;
+        let mut j = 0;
+        while j < 8 {
+            if crc & 0x8000_0000 != 0 {
+                crc = (crc << 1) ^ 0x04C1_1DB7;
+            } else {
+                crc <<= 1;
+            }
+            j += 1;
+        }
+        tables[0][i as usize] = crc;
+        i += 1;
+    }
+    // Build extended tables for slicing-by-4
+    let mut t = 1;
+    while t < 4 {
+        let mut i = 0;
+        while i < 256 {
+            let prev = tables[t - 1][i];
+            tables[t][i] = (prev << 8) ^ tables[0][(prev >> 24) as usize];
+            i += 1;
+        }
+        t += 1;
+    }
+    tables
+};
+
+/// Feed `data` into a running (non-finalised) CRC, 4 bytes per iteration.
+#[inline]
+fn crc_update(mut crc: u32, data: &[u8]) -> u32 {
+    let chunks = data.chunks_exact(4);
+    let remainder = chunks.remainder();
+
+    for chunk in chunks {
+        crc = CRC_TABLES[3][((crc >> 24) ^ u32::from(chunk[0])) as usize]
+            ^ CRC_TABLES[2][((crc >> 16) as u8 ^ chunk[1]) as usize]
+            ^ CRC_TABLES[1][((crc >> 8) as u8 ^ chunk[2]) as usize]
+            ^ CRC_TABLES[0][(crc as u8 ^ chunk[3]) as usize];
+    }
+    for &byte in remainder {
+        crc = (crc << 8) ^ CRC_TABLES[0][((crc >> 24) ^ u32::from(byte)) as usize];
+    }
+    crc
+}
+
+/// Fold the input length (big-endian, significant bytes only) into the CRC
+/// and complement it, as POSIX cksum specifies.
+#[inline]
+fn crc_finish(mut crc: u32, mut len: u64) -> u32 {
+    while len > 0 {
+        crc = (crc << 8) ^ CRC_TABLES[0][((crc >> 24) ^ (len & 0xFF) as u32) as usize];
+        len >>= 8;
+    }
+    !crc
+}
+
+/// Compute the POSIX CRC-32 checksum (the default `cksum` algorithm) of an
+/// in-memory buffer.
+pub fn posix_cksum(data: &[u8]) -> u32 {
+    crc_finish(crc_update(0, data), data.len() as u64)
+}
+
+/// Streaming POSIX CRC-32: process data from a reader without loading everything into memory.
+/// Uses an 8MB buffer and returns the checksum together with the byte count.
+/// Gives up with `Interrupted` at the next refill once SIGINT has arrived.
+pub fn posix_cksum_streaming<R: Read>(reader: R) -> io::Result<(u32, u64)> {
+    let mut reader = io::BufReader::with_capacity(8 * 1024 * 1024, reader);
+    let mut crc: u32 = 0;
+    let mut total_bytes: u64 = 0;
+
+    loop {
+        crate::common::interrupt::check()?;
+        let buf = match reader.fill_buf() {
+            Err(e) if crate::common::io::should_retry(&e) => continue,
+            result => result?,
+        };
+        if buf.is_empty() {
+            break;
+        }
+        let n = buf.len();
+        total_bytes += n as u64;
+        crc = crc_update(crc, buf);
+        reader.consume(n);
+    }
+
+    Ok((crc_finish(crc, total_bytes), total_bytes))
+}
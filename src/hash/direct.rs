@@ -0,0 +1,217 @@
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::Path;
+
+use super::core::{HashAlgorithm, blake2b_hash_reader, hash_reader};
+
+// ── O_DIRECT reads (--direct-io) ─────────────────────────────────────
+// Verifying freshly written multi-GB files reads them once and never
+// again; going through the page cache evicts pages other processes still
+// need. With O_DIRECT the kernel DMAs straight into our buffer. It demands
+// that the buffer address, the file offset and the length are all block
+// aligned, so the whole blocks are read direct and the unaligned tail is
+// read after dropping the flag on the same descriptor.
+
+/// Alignment for O_DIRECT buffers, offsets and lengths. 4KB covers both
+/// 512-byte and 4K-sector devices.
+pub const DIRECT_IO_ALIGN: usize = 4096;
+
+/// Bytes per O_DIRECT read (a multiple of `DIRECT_IO_ALIGN`).
+const DIRECT_BUF_SIZE: usize = 4 * 1024 * 1024;
+
+/// A file reader that bypasses the page cache where the filesystem allows.
+///
+/// When O_DIRECT cannot be used (not Linux, or the filesystem answers the
+/// open or the first read with EINVAL, as tmpfs does) it silently reads
+/// through the page cache instead; `is_direct` tells which happened.
+pub struct DirectReader {
+    file: File,
+    /// Aligned storage; `buf[start..start + DIRECT_BUF_SIZE]` is the window.
+    buf: Vec<u8>,
+    start: usize,
+    /// Unconsumed bytes of the window: `pos..len`, relative to `start`.
+    pos: usize,
+    len: usize,
+    /// File offset of the next direct read.
+    offset: u64,
+    /// End of the block-aligned prefix read with O_DIRECT.
+    direct_end: u64,
+    /// Still issuing O_DIRECT reads.
+    in_direct: bool,
+    /// At least one O_DIRECT read succeeded, or none was needed.
+    direct: bool,
+}
+
+impl DirectReader {
+    /// Open `path` for O_DIRECT reading, falling back to a normal open.
+    pub fn open(path: &Path) -> io::Result<Self> {
+        let (file, in_direct) = open_direct(path)?;
+        let direct_end = if in_direct {
+            let len = file.metadata()?.len();
+            len - len % DIRECT_IO_ALIGN as u64
+        } else {
+            0
+        };
+        let (buf, start) = if in_direct && direct_end > 0 {
+            let buf = vec![0u8; DIRECT_BUF_SIZE + DIRECT_IO_ALIGN];
+            let start = buf.as_ptr().align_offset(DIRECT_IO_ALIGN);
+            (buf, start)
+        } else {
+            (Vec::new(), 0)
+        };
+        let mut reader = DirectReader {
+            file,
+            buf,
+            start,
+            pos: 0,
+            len: 0,
+            offset: 0,
+            direct_end,
+            in_direct,
+            direct: in_direct,
+        };
+        if in_direct && direct_end == 0 {
+            // Smaller than one block: nothing to read direct
+            reader.end_direct()?;
+        }
+        Ok(reader)
+    }
+
+    /// Whether the file is being read with O_DIRECT rather than through
+    /// the page cache. A filesystem may accept the open and refuse the
+    /// first read, so this is only final once reading has started.
+    pub fn is_direct(&self) -> bool {
+        self.direct
+    }
+
+    /// Shift the read window off its alignment, so the kernel refuses the
+    /// first O_DIRECT read with EINVAL.
+    #[cfg(test)]
+    pub(super) fn misalign(&mut self) {
+        if !self.buf.is_empty() {
+            self.start += 1;
+        }
+    }
+
+    /// Read the next aligned window. Returns 0 once the direct part is done.
+    fn fill(&mut self) -> io::Result<usize> {
+        let want = (self.direct_end - self.offset).min(DIRECT_BUF_SIZE as u64) as usize;
+        let window = &mut self.buf[self.start..self.start + want];
+        loop {
+            match self.file.read(window) {
+                Ok(n) => {
+                    self.offset += n as u64;
+                    if n < want {
+                        // The file shrank; the next offset may be unaligned
+                        self.direct_end = self.offset;
+                    }
+                    return Ok(n);
+                }
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) if e.raw_os_error() == Some(EINVAL) => {
+                    // Opened fine but the filesystem refuses direct reads
+                    if self.offset == 0 {
+                        self.direct = false;
+                    }
+                    self.direct_end = self.offset;
+                    return Ok(0);
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    /// Switch the descriptor to normal reads for the unaligned tail.
+    fn end_direct(&mut self) -> io::Result<()> {
+        self.in_direct = false;
+        self.buf = Vec::new();
+        clear_direct_flag(&self.file)
+    }
+}
+
+impl Read for DirectReader {
+    fn read(&mut self, out: &mut [u8]) -> io::Result<usize> {
+        while self.pos == self.len {
+            if !self.in_direct || self.offset >= self.direct_end {
+                if self.in_direct {
+                    self.end_direct()?;
+                }
+                return self.file.read(out);
+            }
+            self.pos = 0;
+            self.len = self.fill()?;
+        }
+        let window = &self.buf[self.start + self.pos..self.start + self.len];
+        let n = window.len().min(out.len());
+        out[..n].copy_from_slice(&window[..n]);
+        self.pos += n;
+        Ok(n)
+    }
+}
+
+#[cfg(target_os = "linux")]
+const EINVAL: i32 = libc::EINVAL;
+#[cfg(not(target_os = "linux"))]
+const EINVAL: i32 = 22;
+
+/// Open with O_DIRECT; on EINVAL (unsupported filesystem) open normally.
+/// The flag says whether O_DIRECT is in effect.
+#[cfg(target_os = "linux")]
+fn open_direct(path: &Path) -> io::Result<(File, bool)> {
+    use std::os::unix::fs::OpenOptionsExt;
+    match std::fs::OpenOptions::new()
+        .read(true)
+        .custom_flags(libc::O_DIRECT)
+        .open(path)
+    {
+        Ok(f) => Ok((f, true)),
+        Err(e) if e.raw_os_error() == Some(libc::EINVAL) => Ok((File::open(path)?, false)),
+        Err(e) => Err(e),
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn open_direct(path: &Path) -> io::Result<(File, bool)> {
+    Ok((File::open(path)?, false))
+}
+
+/// Drop O_DIRECT from an open descriptor (allowed by F_SETFL on Linux),
+/// keeping the file offset.
+#[cfg(target_os = "linux")]
+fn clear_direct_flag(file: &File) -> io::Result<()> {
+    use std::os::unix::io::AsRawFd;
+    let fd = file.as_raw_fd();
+    let flags = unsafe { libc::fcntl(fd, libc::F_GETFL) };
+    if flags < 0 || unsafe { libc::fcntl(fd, libc::F_SETFL, flags & !libc::O_DIRECT) } < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+#[cfg(not(target_os = "linux"))]
+fn clear_direct_flag(_file: &File) -> io::Result<()> {
+    Ok(())
+}
+
+/// Hash a file with O_DIRECT reads. Returns the hex digest and whether
+/// O_DIRECT was actually used (see `DirectReader`).
+pub fn hash_file_direct(algo: HashAlgorithm, path: &Path) -> io::Result<(String, bool)> {
+    hash_direct(algo, DirectReader::open(path)?)
+}
+
+/// Hash everything `reader` yields. The flag is read after hashing, so it
+/// reflects a read the filesystem refused as well as a refused open.
+pub(super) fn hash_direct(
+    algo: HashAlgorithm,
+    mut reader: DirectReader,
+) -> io::Result<(String, bool)> {
+    let hash = hash_reader(algo, &mut reader)?;
+    Ok((hash, reader.is_direct()))
+}
+
+/// `hash_file_direct` for BLAKE2b with a variable output length in bytes.
+pub fn blake2b_hash_file_direct(path: &Path, output_bytes: usize) -> io::Result<(String, bool)> {
+    let mut reader = DirectReader::open(path)?;
+    let hash = blake2b_hash_reader(&mut reader, output_bytes)?;
+    Ok((hash, reader.is_direct()))
+}
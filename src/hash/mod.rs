@@ -1,6 +1,14 @@
+mod cache;
 mod core;
+mod crc;
+mod direct;
+mod verify;
 
 #[cfg(test)]
 mod tests;
 
+pub use self::cache::*;
 pub use self::core::*;
+pub use self::crc::*;
+pub use self::direct::*;
+pub use self::verify::*;
@@ -1,5 +1,6 @@
 use super::*;
-use std::io::Cursor;
+use std::io::{self, Cursor, Read};
+use std::path::Path;
 
 // ── Hash computation tests (reader path) ────────────────────────────
 
@@ -346,6 +347,7 @@ fn test_check_file_ok() {
         warn: false,
         ignore_missing: false,
         warn_prefix: String::new(),
+        direct_io: false,
     };
 
     let r = check_file(
@@ -384,6 +386,7 @@ fn test_check_file_fail() {
         warn: false,
         ignore_missing: false,
         warn_prefix: String::new(),
+        direct_io: false,
     };
 
     let r = check_file(
@@ -418,6 +421,7 @@ fn test_check_file_quiet() {
         warn: false,
         ignore_missing: false,
         warn_prefix: String::new(),
+        direct_io: false,
     };
 
     let r = check_file(
@@ -454,6 +458,7 @@ fn test_check_file_status_only() {
         warn: false,
         ignore_missing: false,
         warn_prefix: String::new(),
+        direct_io: false,
     };
 
     let r = check_file(
@@ -483,6 +488,7 @@ fn test_check_file_ignore_missing() {
         warn: false,
         ignore_missing: true,
         warn_prefix: String::new(),
+        direct_io: false,
     };
     let r = check_file(HashAlgorithm::Md5, reader, &opts, &mut out, &mut err_out).unwrap();
     assert_eq!(r.ok, 0);
@@ -507,6 +513,7 @@ fn test_check_file_missing_not_ignored() {
         warn: false,
         ignore_missing: false,
         warn_prefix: String::new(),
+        direct_io: false,
     };
     let r = check_file(HashAlgorithm::Md5, reader, &opts, &mut out, &mut err_out).unwrap();
     assert_eq!(r.ok, 0);
@@ -531,6 +538,7 @@ fn test_check_file_format_errors_with_warn() {
         warn: true,
         ignore_missing: false,
         warn_prefix: String::new(),
+        direct_io: false,
     };
 
     let r = check_file(
@@ -559,6 +567,7 @@ fn test_check_file_strict_format_errors() {
         warn: false,
         ignore_missing: false,
         warn_prefix: String::new(),
+        direct_io: false,
     };
 
     let r = check_file(
@@ -593,6 +602,7 @@ fn test_check_file_bsd_tag_format() {
         warn: false,
         ignore_missing: false,
         warn_prefix: String::new(),
+        direct_io: false,
     };
 
     let r = check_file(
@@ -630,6 +640,7 @@ fn test_check_file_case_insensitive() {
         warn: false,
         ignore_missing: false,
         warn_prefix: String::new(),
+        direct_io: false,
     };
 
     let r = check_file(
@@ -672,6 +683,7 @@ fn test_check_file_multiple_lines() {
         warn: false,
         ignore_missing: false,
         warn_prefix: String::new(),
+        direct_io: false,
     };
 
     let r = check_file(
@@ -706,6 +718,7 @@ fn test_check_file_empty_lines_skipped() {
         warn: false,
         ignore_missing: false,
         warn_prefix: String::new(),
+        direct_io: false,
     };
 
     let r = check_file(
@@ -900,3 +913,714 @@ fn test_parse_check_line_bsd_sha512() {
     );
     assert_eq!(file, "file.bin");
 }
+
+// ── BSD check-line compatibility ─────────────────────────────────────
+// One test per line shape --check accepts from BSD and shasum sums files,
+// each parsing to the same digest and name.
+
+const BSD_MD5: &str = "d41d8cd98f00b204e9800998ecf8427e";
+
+fn parses_to(line: &str, file: &str) {
+    assert_eq!(parse_check_line(line), Some((BSD_MD5, file)), "{:?}", line);
+}
+
+#[test]
+fn test_bsd_shape_tagged() {
+    // md5(1), sha256(1), cksum -a and our --bsd
+    parses_to(&format!("MD5 (f) = {}", BSD_MD5), "f");
+}
+
+#[test]
+fn test_bsd_shape_no_space_before_equals() {
+    parses_to(&format!("MD5 (f)= {}", BSD_MD5), "f");
+}
+
+#[test]
+fn test_bsd_shape_no_space_after_equals() {
+    parses_to(&format!("MD5 (f) ={}", BSD_MD5), "f");
+    parses_to(&format!("MD5 (f)={}", BSD_MD5), "f");
+}
+
+#[test]
+fn test_bsd_shape_no_space_before_paren() {
+    parses_to(&format!("MD5(f)= {}", BSD_MD5), "f");
+}
+
+#[test]
+fn test_bsd_shape_reversed() {
+    // md5 -r, sha256 -r: one space, then the name
+    parses_to(&format!("{} f", BSD_MD5), "f");
+    parses_to(&format!("{} a b", BSD_MD5), "a b");
+    // Only a hex digest makes the reversed form
+    assert_eq!(parse_check_line("xyz f"), None);
+}
+
+#[test]
+fn test_bsd_shape_shasum_untagged() {
+    // shasum writes GNU's form: two spaces, or " *" in binary mode
+    parses_to(&format!("{}  f", BSD_MD5), "f");
+    parses_to(&format!("{} *f", BSD_MD5), "f");
+}
+
+#[test]
+fn test_bsd_shape_name_with_parens() {
+    // The name runs to the last ")", as in GNU
+    parses_to(&format!("MD5 (a) = b) = {}", BSD_MD5), "a) = b");
+    assert_eq!(
+        parse_check_line_tag(&format!("BLAKE2b-256(f)={}", BSD_MD5)),
+        Some((BSD_MD5, "f", Some(256)))
+    );
+}
+
+#[test]
+fn test_bsd_shapes_verify() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("e");
+    std::fs::write(&path, b"").unwrap();
+    let name = path.display();
+    let content = format!(
+        "MD5 ({0}) = {1}\nMD5 ({0})= {1}\nMD5 ({0}) ={1}\nMD5({0})= {1}\n{1} {0}\n{1}  {0}\n",
+        name, BSD_MD5
+    );
+    let mut out = Vec::new();
+    let mut err = Vec::new();
+    let result = check_file(
+        HashAlgorithm::Md5,
+        content.as_bytes(),
+        &check_opts(),
+        &mut out,
+        &mut err,
+    )
+    .unwrap();
+    assert_eq!((result.ok, result.format_errors), (6, 0));
+}
+
+// ── Digest field decoding (hex / base64) ─────────────────────────────
+
+fn check_opts() -> CheckOptions {
+    CheckOptions {
+        quiet: false,
+        status_only: false,
+        strict: false,
+        warn: true,
+        ignore_missing: false,
+        warn_prefix: String::new(),
+        direct_io: false,
+    }
+}
+
+#[test]
+fn test_decode_digest_field_hex_and_base64() {
+    let md5 = "b1946ac92492d2347c6235b4d2611184";
+    let bytes = hex_decode(md5).unwrap();
+    assert_eq!(decode_digest_field(md5, 16), Some(bytes.clone()));
+    assert_eq!(
+        decode_digest_field(&md5.to_ascii_uppercase(), 16),
+        Some(bytes.clone())
+    );
+    assert_eq!(hex_to_base64(md5), "sZRqySSS0jR8YjW00mERhA==");
+    assert_eq!(
+        decode_digest_field("sZRqySSS0jR8YjW00mERhA==", 16),
+        Some(bytes)
+    );
+
+    // '+' and '/' belong to the standard alphabet
+    let sha256 = "5891b5b522d5df086d0ff0b110fbd9d21bb4fc7163af34d08286a2e846f6be03";
+    let b64 = hex_to_base64(sha256);
+    assert!(b64.contains('/'));
+    assert!(digest_matches(&b64, sha256));
+
+    // Wrong length for the digest, bad padding, URL-safe alphabet
+    assert_eq!(decode_digest_field("sZRqySSS0jR8YjW00mERhA=", 16), None);
+    assert_eq!(decode_digest_field("sZRqySSS0jR8YjW00mERhA", 16), None);
+    assert_eq!(decode_digest_field(&b64.replace('/', "_"), 32), None);
+    assert_eq!(decode_digest_field(md5, 32), None);
+    assert!(!digest_matches("sZRqySSS0jR8YjW00mERhA==", sha256));
+}
+
+#[test]
+fn test_decode_digest_field_ambiguous_prefers_hex() {
+    // A 4-byte digest is 8 characters in both encodings; hex digits read as hex.
+    assert_eq!(
+        decode_digest_field("deadbeef", 4),
+        Some(vec![0xde, 0xad, 0xbe, 0xef])
+    );
+    // Not hex, so base64 decoding applies.
+    assert_eq!(
+        decode_digest_field("3q2+7w==", 4),
+        Some(vec![0xde, 0xad, 0xbe, 0xef])
+    );
+}
+
+#[test]
+fn test_parse_check_line_tagged() {
+    assert_eq!(
+        parse_check_line_tagged("SHA256 (a b) = WJG1tSLV3whtD/CxEPvZ0hu0/HFjrzTQgoai6Eb2vgM="),
+        Some((
+            "SHA256",
+            "WJG1tSLV3whtD/CxEPvZ0hu0/HFjrzTQgoai6Eb2vgM=",
+            "a b"
+        ))
+    );
+    assert_eq!(
+        parse_check_line_tagged("BLAKE2b-256 (f) = abc"),
+        Some(("BLAKE2b-256", "abc", "f"))
+    );
+    assert_eq!(parse_check_line_tagged("CRC (f) = 1"), None);
+    assert_eq!(parse_check_line_tagged("abc  f"), None);
+}
+
+#[test]
+fn test_check_file_base64_tagged() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("h.txt");
+    std::fs::write(&path, b"hello\n").unwrap();
+    let hex = hash_file(HashAlgorithm::Sha256, &path).unwrap();
+    let content = format!(
+        "SHA256 ({0}) = {1}\nSHA256 ({0}) = {2}\n",
+        path.display(),
+        hex_to_base64(&hex),
+        hex.to_ascii_uppercase()
+    );
+
+    let (mut out, mut err) = (Vec::new(), Vec::new());
+    let r = check_file(
+        HashAlgorithm::Sha256,
+        Cursor::new(content.as_bytes()),
+        &check_opts(),
+        &mut out,
+        &mut err,
+    )
+    .unwrap();
+    assert_eq!((r.ok, r.mismatches, r.format_errors), (2, 0, 0));
+}
+
+#[test]
+fn test_check_file_tagged_per_line_algorithm() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("h.txt");
+    std::fs::write(&path, b"hello\n").unwrap();
+    let md5 = hash_file(HashAlgorithm::Md5, &path).unwrap();
+    let b2 = blake2b_hash_file(&path, 32).unwrap();
+    let content = format!(
+        "MD5 ({0}) = {1}\nBLAKE2b-256 ({0}) = {2}\n{1}  {0}\nSHA1 ({0}) = {1}\n",
+        path.display(),
+        hex_to_base64(&md5),
+        b2
+    );
+
+    let (mut out, mut err) = (Vec::new(), Vec::new());
+    let r = check_file_tagged(
+        Cursor::new(content.as_bytes()),
+        &check_opts(),
+        &mut out,
+        &mut err,
+    )
+    .unwrap();
+    // The untagged line is improperly formatted, and so is the SHA1 line,
+    // whose digest is MD5-sized.
+    assert_eq!((r.ok, r.mismatches, r.format_errors), (2, 0, 2));
+    assert_eq!(
+        String::from_utf8(err).unwrap(),
+        "line 3: improperly formatted checksum line\nline 4: improperly formatted checksum line\n"
+    );
+}
+
+// ── xattr digest cache tests ─────────────────────────────────────────
+
+/// True if `path`'s filesystem accepts user xattrs (cache hits possible).
+fn cache_hits_possible(path: &Path) -> bool {
+    let key = "probe";
+    let _ = hash_file_cached(path, key, || Ok("00".to_string()));
+    matches!(
+        hash_file_cached(path, key, || Ok("00".to_string())),
+        Ok((_, DigestSource::Cached))
+    )
+}
+
+#[test]
+fn test_cache_first_run_computes_then_hits() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("a.txt");
+    std::fs::write(&path, b"hello\n").unwrap();
+    let compute = || hash_file(HashAlgorithm::Md5, &path);
+
+    let (h, source) = hash_file_cached(&path, "md5", compute).unwrap();
+    assert_eq!(h, "b1946ac92492d2347c6235b4d2611184");
+    assert_eq!(source, DigestSource::Computed);
+
+    if !cache_hits_possible(&path) {
+        return;
+    }
+    let (h, source) =
+        hash_file_cached(&path, "md5", || panic!("cache hit must not rehash")).unwrap();
+    assert_eq!(h, "b1946ac92492d2347c6235b4d2611184");
+    assert_eq!(source, DigestSource::Cached);
+}
+
+#[test]
+fn test_cache_invalidated_by_mtime_and_size() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("a.txt");
+    std::fs::write(&path, b"hello\n").unwrap();
+    if !cache_hits_possible(&path) {
+        return;
+    }
+    hash_file_cached(&path, "md5", || hash_file(HashAlgorithm::Md5, &path)).unwrap();
+
+    // Same size, new mtime (a "touch")
+    let f = std::fs::OpenOptions::new().write(true).open(&path).unwrap();
+    let later = std::time::SystemTime::now() + std::time::Duration::from_secs(5);
+    f.set_modified(later).unwrap();
+    drop(f);
+    let (_, source) =
+        hash_file_cached(&path, "md5", || hash_file(HashAlgorithm::Md5, &path)).unwrap();
+    assert_eq!(source, DigestSource::Computed);
+
+    // New content with the mtime pinned: the size still differs
+    std::fs::write(&path, b"hello world\n").unwrap();
+    let f = std::fs::OpenOptions::new().write(true).open(&path).unwrap();
+    f.set_modified(later).unwrap();
+    drop(f);
+    let (h, source) =
+        hash_file_cached(&path, "md5", || hash_file(HashAlgorithm::Md5, &path)).unwrap();
+    assert_eq!(source, DigestSource::Computed);
+    assert_eq!(h, "6f5902ac237024bdd0c176cb93063dc4");
+}
+
+#[test]
+fn test_cache_keys_are_independent() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("a.txt");
+    std::fs::write(&path, b"hello\n").unwrap();
+    hash_file_cached(&path, "md5", || hash_file(HashAlgorithm::Md5, &path)).unwrap();
+    let (h, source) =
+        hash_file_cached(&path, "sha1", || hash_file(HashAlgorithm::Sha1, &path)).unwrap();
+    assert_eq!(source, DigestSource::Computed);
+    assert_eq!(h, "f572d396fae9206628714fb2ce00f72e94f2258f");
+}
+
+#[cfg(unix)]
+#[test]
+fn test_cache_unusable_target_falls_back() {
+    // Non-regular files are never cached; errors from compute propagate.
+    let (h, source) = hash_file_cached(Path::new("/dev/null"), "md5", || {
+        hash_file(HashAlgorithm::Md5, Path::new("/dev/null"))
+    })
+    .unwrap();
+    assert_eq!(h, "d41d8cd98f00b204e9800998ecf8427e");
+    assert_eq!(source, DigestSource::Computed);
+
+    let missing = Path::new("/nonexistent/fcoreutils-cache-test");
+    assert!(hash_file_cached(missing, "md5", || hash_file(HashAlgorithm::Md5, missing)).is_err());
+}
+
+// ── Manifest verification (library API) ────────────────────────────
+
+/// A tempdir with one file per verify outcome and a manifest covering them.
+fn verify_fixture() -> (tempfile::TempDir, String) {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join("good"), "hello\n").unwrap();
+    std::fs::write(dir.path().join("changed"), "hello\n").unwrap();
+    std::fs::create_dir(dir.path().join("subdir")).unwrap();
+    let good = hash_bytes(HashAlgorithm::Md5, b"hello\n");
+    let manifest = format!(
+        "{good}  good\n\
+         {zeros}  changed\n\
+         \n\
+         {good}  gone\n\
+         not a checksum line\n\
+         {good}  subdir\n",
+        good = good,
+        zeros = "0".repeat(32),
+    );
+    (dir, manifest)
+}
+
+#[test]
+fn test_verify_manifest_every_variant() {
+    let (dir, manifest) = verify_fixture();
+    let opts = VerifyOptions {
+        algorithm: Some(HashAlgorithm::Md5),
+        ..VerifyOptions::default()
+    };
+    let report = verify_manifest(Cursor::new(manifest), dir.path(), &opts).unwrap();
+
+    let lines: Vec<(usize, &str)> = report
+        .entries
+        .iter()
+        .map(|e| (e.line_no, e.file.as_str()))
+        .collect();
+    assert_eq!(
+        lines,
+        [
+            (1, "good"),
+            (2, "changed"),
+            (4, "gone"),
+            (5, ""),
+            (6, "subdir")
+        ]
+    );
+    let results: Vec<&VerifyResult> = report.entries.iter().map(|e| &e.result).collect();
+    assert!(matches!(results[0], VerifyResult::Ok));
+    match results[1] {
+        VerifyResult::Mismatch { expected, actual } => {
+            assert_eq!(expected, &"0".repeat(32));
+            assert_eq!(actual, &hash_bytes(HashAlgorithm::Md5, b"hello\n"));
+        }
+        other => panic!("expected a mismatch, got {:?}", other),
+    }
+    assert!(matches!(results[2], VerifyResult::Missing));
+    assert!(matches!(results[3], VerifyResult::Malformed { line_no: 5 }));
+    match results[4] {
+        VerifyResult::ReadError(e) => assert_eq!(e.raw_os_error(), Some(libc::EISDIR)),
+        other => panic!("expected a read error, got {:?}", other),
+    }
+
+    assert_eq!(
+        (
+            report.ok,
+            report.mismatches,
+            report.missing,
+            report.read_errors,
+            report.malformed
+        ),
+        (1, 1, 1, 1, 1)
+    );
+    assert_eq!(report.verified(), 2);
+    assert!(!report.success());
+}
+
+#[test]
+fn test_verify_manifest_success_rules() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join("a"), "a").unwrap();
+    let sum = hash_bytes(HashAlgorithm::Sha256, b"a");
+    let run = |manifest: String, ignore_missing: bool, strict: bool| {
+        let opts = VerifyOptions {
+            algorithm: None,
+            ignore_missing,
+            strict,
+            ..VerifyOptions::default()
+        };
+        verify_manifest(Cursor::new(manifest), dir.path(), &opts).unwrap()
+    };
+
+    // Tagged lines pick their own algorithm; an untagged one is malformed
+    let tagged = format!("SHA256 (a) = {}\n{}  a\n", sum, sum);
+    let report = run(tagged.clone(), false, false);
+    assert_eq!((report.ok, report.malformed), (1, 1));
+    assert!(report.success());
+    assert!(!run(tagged, false, true).success());
+
+    let with_missing = format!("SHA256 (a) = {}\nSHA256 (b) = {}\n", sum, sum);
+    assert!(!run(with_missing.clone(), false, false).success());
+    assert!(run(with_missing, true, false).success());
+
+    // Nothing verified: all missing, or no well-formed line at all
+    assert!(!run(format!("SHA256 (b) = {}\n", sum), true, false).success());
+    assert!(!run("junk\n".to_string(), false, false).success());
+}
+
+#[test]
+fn test_verify_manifest_unescapes_names() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join("back\\slash"), "a").unwrap();
+    std::fs::write(dir.path().join("new\nline"), "a").unwrap();
+    let sum = hash_bytes(HashAlgorithm::Md5, b"a");
+    let manifest =
+        format!("\\{sum}  back\\\\slash\n\\MD5 (new\\nline) = {sum}\n\\{sum}  bad\\tescape\n");
+    let opts = VerifyOptions {
+        algorithm: Some(HashAlgorithm::Md5),
+        ..VerifyOptions::default()
+    };
+    let report = verify_manifest(Cursor::new(manifest), dir.path(), &opts).unwrap();
+    let files: Vec<&str> = report.entries.iter().map(|e| e.file.as_str()).collect();
+    assert_eq!(files, ["back\\slash", "new\nline", ""]);
+    assert_eq!((report.ok, report.malformed), (2, 1));
+}
+
+#[test]
+fn test_verify_manifest_digest_case_and_length() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join("a"), "a").unwrap();
+    let sum = hash_bytes(HashAlgorithm::Sha256, b"a");
+    let mixed: String = sum
+        .chars()
+        .enumerate()
+        .map(|(i, c)| {
+            if i % 2 == 0 {
+                c.to_ascii_uppercase()
+            } else {
+                c
+            }
+        })
+        .collect();
+    let manifest = format!(
+        "{}  a\n{}  a\nSHA256 (a) = {}\n{}  a\n{}0  a\n",
+        sum.to_uppercase(),
+        mixed,
+        mixed,
+        &sum[..62],
+        sum
+    );
+    let opts = VerifyOptions {
+        algorithm: Some(HashAlgorithm::Sha256),
+        ..VerifyOptions::default()
+    };
+    let report = verify_manifest(Cursor::new(manifest), dir.path(), &opts).unwrap();
+    assert_eq!((report.ok, report.mismatches, report.malformed), (3, 0, 2));
+    assert!(matches!(
+        report.entries[3].result,
+        VerifyResult::Malformed { line_no: 4 }
+    ));
+}
+
+#[test]
+fn test_verify_manifest_blake2b_length_from_digest() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join("a"), "a").unwrap();
+    // Untagged BLAKE2b lines of any whole-byte length up to 512 bits check
+    let manifest = format!(
+        "{}  a\n{}  a\n{}  a\n{}  a\n",
+        blake2b_hash_data(b"a", 1),
+        blake2b_hash_data(b"a", 16),
+        blake2b_hash_data(b"a", 64),
+        blake2b_hash_data(b"b", 32),
+    );
+    let opts = VerifyOptions {
+        algorithm: Some(HashAlgorithm::Blake2b),
+        ..VerifyOptions::default()
+    };
+    let report = verify_manifest(Cursor::new(manifest), dir.path(), &opts).unwrap();
+    assert_eq!((report.ok, report.mismatches, report.malformed), (3, 1, 0));
+    // An odd number of hex digits cannot be a digest
+    let report = verify_manifest(Cursor::new("abc  a\n"), dir.path(), &opts).unwrap();
+    assert_eq!(report.malformed, 1);
+}
+
+#[test]
+fn test_hex_in_case() {
+    assert_eq!(hex_in_case("0aF9", false), "0aF9");
+    assert_eq!(hex_in_case("0af9", true), "0AF9");
+}
+
+#[test]
+fn test_verify_manifest_with_streams_entries() {
+    let (dir, manifest) = verify_fixture();
+    let opts = VerifyOptions {
+        algorithm: Some(HashAlgorithm::Md5),
+        ..VerifyOptions::default()
+    };
+    let mut seen = Vec::new();
+    let report = verify_manifest_with(Cursor::new(manifest), dir.path(), &opts, |entry| {
+        seen.push(entry.line_no);
+        Ok(())
+    })
+    .unwrap();
+    assert_eq!(seen, [1, 2, 4, 5, 6]);
+    assert!(report.entries.is_empty());
+    assert_eq!(report.ok + report.mismatches + report.missing, 3);
+
+    // An error from the callback stops the run
+    let mut calls = 0;
+    let err = verify_manifest_with(Cursor::new("x  a\ny  b\n"), dir.path(), &opts, |_| {
+        calls += 1;
+        Err(io::Error::other("stop"))
+    })
+    .unwrap_err();
+    assert_eq!(err.to_string(), "stop");
+    assert_eq!(calls, 1);
+}
+
+#[test]
+fn test_check_cli_output_derives_from_report() {
+    let (dir, manifest) = verify_fixture();
+    let manifest_path = dir.path().join("MD5SUMS");
+    std::fs::write(&manifest_path, &manifest).unwrap();
+
+    let mut bin = std::env::current_exe().unwrap();
+    bin.pop();
+    bin.pop();
+    bin.push("fmd5sum");
+    let output = std::process::Command::new(bin)
+        .current_dir(dir.path())
+        .args(["-c", "MD5SUMS"])
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(1));
+
+    let opts = VerifyOptions {
+        algorithm: Some(HashAlgorithm::Md5),
+        ..VerifyOptions::default()
+    };
+    let report = verify_manifest(Cursor::new(manifest), dir.path(), &opts).unwrap();
+    let mut expected = String::new();
+    for entry in &report.entries {
+        let status = match entry.result {
+            VerifyResult::Ok => "OK",
+            VerifyResult::Mismatch { .. } => "FAILED",
+            VerifyResult::Missing | VerifyResult::ReadError(_) => "FAILED open or read",
+            VerifyResult::Malformed { .. } => continue,
+        };
+        expected.push_str(&format!("{}: {}\n", entry.file, status));
+    }
+    assert_eq!(String::from_utf8_lossy(&output.stdout), expected);
+}
+
+#[test]
+fn test_uppercase_output_verifies() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join("a"), "hello\n").unwrap();
+    let bin_dir = std::env::current_exe().unwrap();
+    let bin_dir = bin_dir.parent().unwrap().parent().unwrap();
+
+    for (tool, algo) in [
+        ("fmd5sum", HashAlgorithm::Md5),
+        ("fsha256sum", HashAlgorithm::Sha256),
+    ] {
+        let bin = bin_dir.join(tool);
+        let output = std::process::Command::new(&bin)
+            .current_dir(dir.path())
+            .args(["--uppercase", "a"])
+            .output()
+            .unwrap();
+        let expected = hash_bytes(algo, b"hello\n").to_uppercase();
+        assert_eq!(
+            String::from_utf8_lossy(&output.stdout),
+            format!("{}  a\n", expected)
+        );
+
+        let sums = dir.path().join("SUMS");
+        std::fs::write(&sums, &output.stdout).unwrap();
+        let check = std::process::Command::new(&bin)
+            .current_dir(dir.path())
+            .args(["--uppercase", "-c", "SUMS"])
+            .output()
+            .unwrap();
+        assert!(check.status.success(), "{tool}");
+        assert_eq!(String::from_utf8_lossy(&check.stdout), "a: OK\n");
+    }
+}
+
+// ── POSIX CRC tests ──────────────────────────────────────────────────
+
+#[test]
+fn test_crc_table_correctness() {
+    // Verify first and last entries of the CRC table
+    assert_eq!(crc::CRC_TABLES[0][0], 0);
+    assert_ne!(crc::CRC_TABLES[0][255], 0);
+}
+
+#[test]
+fn test_posix_cksum_empty() {
+    // Empty input: CRC feeds only length (0), so only complement of 0
+    assert_eq!(posix_cksum(b""), 4294967295);
+}
+
+#[test]
+fn test_posix_cksum_hello() {
+    // GNU cksum gives: 3015617425 6
+    assert_eq!(posix_cksum(b"hello\n"), 3015617425);
+}
+
+#[test]
+fn test_posix_cksum_streaming_matches_slice() {
+    // Odd lengths exercise the slicing-by-4 remainder and multi-byte length folding.
+    for len in [0usize, 1, 3, 4, 5, 255, 256, 70_001] {
+        let data: Vec<u8> = (0..len).map(|i| (i * 7 % 251) as u8).collect();
+        let (crc, n) = posix_cksum_streaming(Cursor::new(&data)).unwrap();
+        assert_eq!(n, len as u64);
+        assert_eq!(crc, posix_cksum(&data), "len {len}");
+    }
+}
+
+// ── O_DIRECT reads ───────────────────────────────────────────────────
+
+/// Deterministic pseudo-random payload (xorshift64).
+fn direct_payload(len: usize) -> Vec<u8> {
+    let mut x: u64 = 0x5eed;
+    (0..len)
+        .map(|_| {
+            x ^= x << 13;
+            x ^= x >> 7;
+            x ^= x << 17;
+            x as u8
+        })
+        .collect()
+}
+
+#[test]
+fn test_direct_reader_matches_file() {
+    let dir = tempfile::tempdir().unwrap();
+    // Several read windows plus an unaligned tail, exactly one block, less
+    // than one block, and empty.
+    for len in [9 * 1024 * 1024 + 123, DIRECT_IO_ALIGN, 777, 0] {
+        let path = dir.path().join(format!("f{len}"));
+        let data = direct_payload(len);
+        std::fs::write(&path, &data).unwrap();
+
+        let mut read = Vec::new();
+        DirectReader::open(&path)
+            .unwrap()
+            .read_to_end(&mut read)
+            .unwrap();
+        assert!(read == data, "len {len}");
+    }
+}
+
+#[test]
+fn test_hash_file_direct_matches_buffered() {
+    let dir = tempfile::tempdir().unwrap();
+    for len in [5 * 1024 * 1024 + 1, 100] {
+        let path = dir.path().join(format!("f{len}"));
+        std::fs::write(&path, direct_payload(len)).unwrap();
+
+        for algo in [
+            HashAlgorithm::Md5,
+            HashAlgorithm::Sha256,
+            HashAlgorithm::Blake2b,
+        ] {
+            let (h, _) = hash_file_direct(algo, &path).unwrap();
+            assert_eq!(h, hash_file(algo, &path).unwrap(), "{algo:?} len {len}");
+        }
+        let (h, _) = blake2b_hash_file_direct(&path, 32).unwrap();
+        assert_eq!(h, blake2b_hash_file(&path, 32).unwrap());
+
+        let (crc, n) = posix_cksum_streaming(DirectReader::open(&path).unwrap()).unwrap();
+        assert_eq!(n, len as u64);
+        assert_eq!(crc, posix_cksum(&direct_payload(len)));
+    }
+}
+
+#[test]
+fn test_direct_read_refused_after_open() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("f");
+    let data = direct_payload(3 * DIRECT_IO_ALIGN + 5);
+    std::fs::write(&path, &data).unwrap();
+
+    // Reported direct until a read is refused, then not direct
+    let mut reader = DirectReader::open(&path).unwrap();
+    let opened_direct = reader.is_direct();
+    reader.misalign();
+    assert_eq!(reader.is_direct(), opened_direct);
+    let (h, direct) = super::direct::hash_direct(HashAlgorithm::Sha256, reader).unwrap();
+    assert!(!direct);
+    assert_eq!(h, hash_file(HashAlgorithm::Sha256, &path).unwrap());
+
+    // The CRC path reads the flag the same way
+    let mut reader = DirectReader::open(&path).unwrap();
+    reader.misalign();
+    let (crc, n) = posix_cksum_streaming(&mut reader).unwrap();
+    assert!(!reader.is_direct());
+    assert_eq!((crc, n), (posix_cksum(&data), data.len() as u64));
+}
+
+#[test]
+fn test_direct_reader_missing_file() {
+    let err = DirectReader::open(Path::new("/nonexistent/direct-io"))
+        .err()
+        .unwrap();
+    assert_eq!(err.kind(), io::ErrorKind::NotFound);
+}
@@ -0,0 +1,241 @@
+use std::borrow::Cow;
+use std::io::{self, BufRead};
+use std::path::Path;
+
+use super::core::{
+    HashAlgorithm, blake2b_hash_file, decode_digest_field, digest_matches, hash_file,
+    parse_check_line, parse_check_line_tagged, tag_algorithm,
+};
+use super::direct::{blake2b_hash_file_direct, hash_file_direct};
+
+// ── Library-level checksum manifest verification ───────────────────────
+// `verify_manifest` checks a "sums" file the way `md5sum -c` / `cksum -c`
+// do, but returns one structured result per line instead of printing
+// "OK"/"FAILED". The --check CLIs render their output from the same
+// entries (through `verify_manifest_with`), so the two cannot disagree.
+
+/// Options for `verify_manifest`.
+#[derive(Debug, Clone, Default)]
+pub struct VerifyOptions {
+    /// Hash every entry with this algorithm. `None` takes the algorithm from
+    /// each line's tag ("SHA256 (file) = ..."), like `cksum -c` without -a;
+    /// untagged lines are then malformed.
+    pub algorithm: Option<HashAlgorithm>,
+    /// A listed file that does not exist is skipped instead of failing.
+    pub ignore_missing: bool,
+    /// Malformed lines make the run fail.
+    pub strict: bool,
+    /// Read listed files with O_DIRECT, bypassing the page cache.
+    pub direct_io: bool,
+}
+
+/// Outcome of one manifest line.
+#[derive(Debug)]
+pub enum VerifyResult {
+    /// The computed digest matches the listed one.
+    Ok,
+    /// The file was read but its digest differs. `expected` is the field as
+    /// written in the manifest (hex or base64), `actual` the computed hex.
+    Mismatch { expected: String, actual: String },
+    /// The listed file does not exist.
+    Missing,
+    /// The listed file exists but could not be read.
+    ReadError(io::Error),
+    /// The line is not a checksum line (blank lines are skipped silently).
+    Malformed { line_no: usize },
+}
+
+/// One non-blank manifest line and what verifying it produced.
+#[derive(Debug)]
+pub struct VerifyEntry {
+    /// 1-based line number in the manifest.
+    pub line_no: usize,
+    /// The file name as listed; empty for a malformed line.
+    pub file: String,
+    pub result: VerifyResult,
+}
+
+/// Every entry of a manifest plus the aggregate counters.
+#[derive(Debug, Default)]
+pub struct VerifyReport {
+    pub entries: Vec<VerifyEntry>,
+    pub ok: usize,
+    pub mismatches: usize,
+    pub missing: usize,
+    pub read_errors: usize,
+    pub malformed: usize,
+    /// Copied from the options the report was produced with.
+    pub ignore_missing: bool,
+    pub strict: bool,
+}
+
+impl VerifyReport {
+    /// Number of files whose digest was actually compared.
+    pub fn verified(&self) -> usize {
+        self.ok + self.mismatches
+    }
+
+    /// Whether the manifest verifies, with GNU's rules: no mismatch, no
+    /// read error, no missing file unless ignored, no malformed line under
+    /// strict, at least one well-formed line, and (with ignore_missing) at
+    /// least one file actually verified.
+    pub fn success(&self) -> bool {
+        let well_formed = self.verified() + self.missing + self.read_errors;
+        self.mismatches == 0
+            && self.read_errors == 0
+            && (self.ignore_missing || self.missing == 0)
+            && !(self.strict && self.malformed > 0)
+            && well_formed > 0
+            && !(self.ignore_missing && self.verified() == 0)
+    }
+
+    fn count(&mut self, result: &VerifyResult) {
+        match result {
+            VerifyResult::Ok => self.ok += 1,
+            VerifyResult::Mismatch { .. } => self.mismatches += 1,
+            VerifyResult::Missing => self.missing += 1,
+            VerifyResult::ReadError(_) => self.read_errors += 1,
+            VerifyResult::Malformed { .. } => self.malformed += 1,
+        }
+    }
+}
+
+/// Verify every line of `manifest`, resolving relative file names against
+/// `base_dir`. Only a failure to read the manifest itself is an `Err`.
+pub fn verify_manifest(
+    manifest: impl BufRead,
+    base_dir: &Path,
+    opts: &VerifyOptions,
+) -> io::Result<VerifyReport> {
+    let mut entries = Vec::new();
+    let mut report = verify_manifest_with(manifest, base_dir, opts, |entry| {
+        entries.push(entry);
+        Ok(())
+    })?;
+    report.entries = entries;
+    Ok(report)
+}
+
+/// Like `verify_manifest`, but hands each entry to `on_entry` as soon as it
+/// is known instead of collecting them, so a caller can stream output. The
+/// returned report has the counters and an empty `entries`. An error from
+/// `on_entry` stops the run and is returned.
+pub fn verify_manifest_with(
+    manifest: impl BufRead,
+    base_dir: &Path,
+    opts: &VerifyOptions,
+    mut on_entry: impl FnMut(VerifyEntry) -> io::Result<()>,
+) -> io::Result<VerifyReport> {
+    let mut report = VerifyReport {
+        ignore_missing: opts.ignore_missing,
+        strict: opts.strict,
+        ..VerifyReport::default()
+    };
+    for (idx, line) in manifest.lines().enumerate() {
+        let line = line?;
+        let line = line.trim_end();
+        if line.is_empty() {
+            continue;
+        }
+        let line_no = idx + 1;
+        let entry = match verify_line(line, base_dir, opts) {
+            Some((file, result)) => VerifyEntry {
+                line_no,
+                file: file.into_owned(),
+                result,
+            },
+            None => VerifyEntry {
+                line_no,
+                file: String::new(),
+                result: VerifyResult::Malformed { line_no },
+            },
+        };
+        report.count(&entry.result);
+        on_entry(entry)?;
+    }
+    Ok(report)
+}
+
+/// Undo the escaping of a name on a line that starts with a backslash:
+/// `\\` is a backslash and `\n` a newline. Any other escape makes the
+/// line malformed, as in GNU.
+fn unescape_filename(name: &str) -> Option<String> {
+    let mut out = String::with_capacity(name.len());
+    let mut chars = name.chars();
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            match chars.next()? {
+                '\\' => out.push('\\'),
+                'n' => out.push('\n'),
+                _ => return None,
+            }
+        } else {
+            out.push(c);
+        }
+    }
+    Some(out)
+}
+
+/// The BLAKE2b digest size a hex field implies, as GNU infers it for lines
+/// without a length tag. Anything else is left to the default 512 bits.
+fn blake2b_hex_len(field: &str) -> Option<usize> {
+    (field.len().is_multiple_of(2)
+        && (2..=128).contains(&field.len())
+        && field.bytes().all(|b| b.is_ascii_hexdigit()))
+    .then_some(field.len() / 2)
+}
+
+/// Parse and check one line; `None` if it is not a checksum line.
+fn verify_line<'a>(
+    line: &'a str,
+    base_dir: &Path,
+    opts: &VerifyOptions,
+) -> Option<(Cow<'a, str>, VerifyResult)> {
+    // A leading backslash marks an escaped file name, in either layout
+    let (line, escaped) = match line.strip_prefix('\\') {
+        Some(rest) => (rest, true),
+        None => (line, false),
+    };
+    // Without a fixed algorithm only tagged lines say what to compute
+    let (algo, blake2b_len, expected, file) = match opts.algorithm {
+        Some(HashAlgorithm::Blake2b) => {
+            let (hash, file) = parse_check_line(line)?;
+            (HashAlgorithm::Blake2b, blake2b_hex_len(hash), hash, file)
+        }
+        Some(a) => parse_check_line(line).map(|(hash, file)| (a, None, hash, file))?,
+        None => {
+            let (tag, hash, file) = parse_check_line_tagged(line)?;
+            let (a, len) = tag_algorithm(tag)?;
+            (a, Some(len), hash, file)
+        }
+    };
+    // A digest of the wrong length for the algorithm makes the line
+    // malformed rather than a mismatch; hex digits may be in either case
+    decode_digest_field(expected, blake2b_len.unwrap_or(algo.digest_len()))?;
+
+    let file = if escaped {
+        Cow::Owned(unescape_filename(file)?)
+    } else {
+        Cow::Borrowed(file)
+    };
+
+    let path = base_dir.join(&*file);
+    let computed = match (algo, blake2b_len, opts.direct_io) {
+        (HashAlgorithm::Blake2b, Some(len), false) => blake2b_hash_file(&path, len),
+        (HashAlgorithm::Blake2b, Some(len), true) => {
+            blake2b_hash_file_direct(&path, len).map(|(h, _)| h)
+        }
+        (_, _, false) => hash_file(algo, &path),
+        (_, _, true) => hash_file_direct(algo, &path).map(|(h, _)| h),
+    };
+    let result = match computed {
+        Ok(actual) if digest_matches(expected, &actual) => VerifyResult::Ok,
+        Ok(actual) => VerifyResult::Mismatch {
+            expected: expected.to_string(),
+            actual,
+        },
+        Err(e) if e.kind() == io::ErrorKind::NotFound => VerifyResult::Missing,
+        Err(e) => VerifyResult::ReadError(e),
+    };
+    Some((file, result))
+}
@@ -25,9 +25,15 @@
 /// 2-3x faster than glibc malloc for small allocations,
 /// better thread-local caching, and reduced fragmentation.
 /// Critical for tools like sort/uniq that do many small allocs.
+#[cfg(not(test))]
 #[global_allocator]
 static GLOBAL: mimalloc::MiMalloc = mimalloc::MiMalloc;
 
+/// Unit tests count allocations on top of it; see `common::alloc_count`.
+#[cfg(test)]
+#[global_allocator]
+static GLOBAL: common::alloc_count::Counting = common::alloc_count::Counting;
+
 pub mod base64;
 pub mod cat;
 #[cfg(unix)]
@@ -1432,11 +1432,9 @@ fn print_columns(
     let col_width_raw = max_name_width + prefix_width;
 
     // Round up to next tab stop
-    let col_width = if tab > 0 {
-        ((col_width_raw + tab) / tab) * tab
-    } else {
-        col_width_raw + 2
-    };
+    let col_width = (col_width_raw + tab)
+        .checked_div(tab)
+        .map_or(col_width_raw + 2, |stops| stops * tab);
 
     if col_width == 0 || col_width >= term_width {
         // Fall back to single column
@@ -2,6 +2,7 @@
 ///
 /// A simplified version of the finger command that displays information
 /// about currently logged-in users using utmpx records and passwd entries.
+use std::collections::HashMap;
 use std::ffi::CStr;
 use std::fmt::Write as FmtWrite;
 use std::path::PathBuf;
@@ -31,6 +32,10 @@ pub struct PinkyConfig {
     pub omit_fullname_host_idle: bool,
     /// Specific users to look up (positional args).
     pub users: Vec<String>,
+    /// Emit JSON instead of text (--format=json): an object holding
+    /// `source_available` and the `entries` array. Every field is present;
+    /// the omit options do not apply.
+    pub json: bool,
 }
 
 impl Default for PinkyConfig {
@@ -46,6 +51,7 @@ impl Default for PinkyConfig {
             omit_fullname_host: false,
             omit_fullname_host_idle: false,
             users: Vec::new(),
+            json: false,
         }
     }
 }
@@ -89,11 +95,72 @@ pub fn get_user_info(username: &str) -> Option<UserInfo> {
     }
 }
 
+/// Per-run cache of passwd lookups keyed by login name, so each user is
+/// looked up once however many sessions they have (NSS lookups can go over
+/// the network). Misses are cached too. The lookup is a closure so callers
+/// such as who/users can share the cache and tests can count the calls.
+pub struct UserInfoCache<F = fn(&str) -> Option<UserInfo>> {
+    lookup: F,
+    entries: HashMap<String, Option<UserInfo>>,
+}
+
+impl UserInfoCache {
+    /// A cache backed by `get_user_info` (getpwnam).
+    pub fn new() -> Self {
+        Self::with_lookup(get_user_info)
+    }
+}
+
+impl Default for UserInfoCache {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<F: FnMut(&str) -> Option<UserInfo>> UserInfoCache<F> {
+    pub fn with_lookup(lookup: F) -> Self {
+        UserInfoCache {
+            lookup,
+            entries: HashMap::new(),
+        }
+    }
+
+    /// The passwd entry for `login`, looking it up on first use.
+    pub fn get(&mut self, login: &str) -> Option<&UserInfo> {
+        if !self.entries.contains_key(login) {
+            let info = (self.lookup)(login);
+            self.entries.insert(login.to_string(), info);
+        }
+        self.entries[login].as_ref()
+    }
+
+    /// Look up every login not cached yet, each once.
+    pub fn prefetch<'a>(&mut self, logins: impl IntoIterator<Item = &'a str>) {
+        for login in logins {
+            self.get(login);
+        }
+    }
+}
+
 /// Compute idle time string for a terminal.
 /// Returns "." if active within the last minute, or "HH:MM" otherwise.
 fn idle_str(line: &str) -> String {
+    match idle_seconds(line) {
+        None => "?".to_string(),
+        Some(idle_secs) if idle_secs < 60 => ".".to_string(),
+        Some(idle_secs) => {
+            let hours = idle_secs / 3600;
+            let mins = (idle_secs % 3600) / 60;
+            format!("{:02}:{:02}", hours, mins)
+        }
+    }
+}
+
+/// Seconds since the terminal was last accessed, or None if it has no
+/// device to stat.
+fn idle_seconds(line: &str) -> Option<i64> {
     if line.is_empty() {
-        return "?".to_string();
+        return None;
     }
     let dev_path = if line.starts_with('/') {
         line.to_string()
@@ -105,20 +172,11 @@ fn idle_str(line: &str) -> String {
     let c_path = std::ffi::CString::new(dev_path).unwrap_or_default();
     let rc = unsafe { libc::stat(c_path.as_ptr(), &mut stat_buf) };
     if rc != 0 {
-        return "?".to_string();
+        return None;
     }
 
     let now = unsafe { libc::time(std::ptr::null_mut()) };
-    let atime = stat_buf.st_atime;
-    let idle_secs = now - atime;
-
-    if idle_secs < 60 {
-        ".".to_string()
-    } else {
-        let hours = idle_secs / 3600;
-        let mins = (idle_secs % 3600) / 60;
-        format!("{:02}:{:02}", hours, mins)
-    }
+    Some((now - stat_buf.st_atime) as i64)
 }
 
 /// Format a Unix timestamp as "Mon DD HH:MM" (short format).
@@ -146,6 +204,31 @@ fn format_time_short(tv_sec: i64) -> String {
     )
 }
 
+/// Format a Unix timestamp as local ISO 8601, e.g. "2024-01-01T09:30:00+01:00".
+fn format_time_iso(tv_sec: i64) -> String {
+    let t = tv_sec as libc::time_t;
+    let tm = unsafe {
+        let mut tm: libc::tm = std::mem::zeroed();
+        libc::localtime_r(&t, &mut tm);
+        tm
+    };
+    let offset = tm.tm_gmtoff as i64;
+    let sign = if offset < 0 { '-' } else { '+' };
+    let offset = offset.abs();
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}{}{:02}:{:02}",
+        tm.tm_year as i64 + 1900,
+        tm.tm_mon + 1,
+        tm.tm_mday,
+        tm.tm_hour,
+        tm.tm_min,
+        tm.tm_sec,
+        sign,
+        offset / 3600,
+        (offset % 3600) / 60
+    )
+}
+
 /// Read a file's first line, returning it or an empty string.
 fn read_first_line(path: &PathBuf) -> String {
     match std::fs::read_to_string(path) {
@@ -177,18 +260,35 @@ pub fn format_short_heading(config: &PinkyConfig) -> String {
     out
 }
 
+/// Whether the short format has a Name column.
+fn shows_fullname(config: &PinkyConfig) -> bool {
+    !config.omit_fullname && !config.omit_fullname_host && !config.omit_fullname_host_idle
+}
+
 /// Format a single entry in short format.
 pub fn format_short_entry(entry: &who::UtmpxEntry, config: &PinkyConfig) -> String {
+    let info = if shows_fullname(config) {
+        get_user_info(&entry.ut_user)
+    } else {
+        None
+    };
+    format_short_row(entry, config, info.as_ref())
+}
+
+/// Format one short-format row from an already looked-up passwd entry.
+fn format_short_row(
+    entry: &who::UtmpxEntry,
+    config: &PinkyConfig,
+    info: Option<&UserInfo>,
+) -> String {
     let mut out = String::new();
 
     // Login name
     let _ = write!(out, "{:<8}", entry.ut_user);
 
     // Full name
-    if !config.omit_fullname && !config.omit_fullname_host && !config.omit_fullname_host_idle {
-        let fullname = get_user_info(&entry.ut_user)
-            .map(|u| u.fullname)
-            .unwrap_or_default();
+    if shows_fullname(config) {
+        let fullname = info.map_or("", |u| u.fullname.as_str());
         // Truncate full name to 20 chars for alignment
         let display_name: String = fullname.chars().take(20).collect();
         let _ = write!(out, " {:<20}", display_name);
@@ -224,18 +324,21 @@ pub fn format_short_entry(entry: &who::UtmpxEntry, config: &PinkyConfig) -> Stri
 
 /// Format output in long format for a specific user.
 pub fn format_long_entry(username: &str, config: &PinkyConfig) -> String {
-    let mut out = String::new();
+    format_long_row(username, config, get_user_info(username).as_ref())
+}
 
-    let info = get_user_info(username);
+/// Long format for `username` from an already looked-up passwd entry.
+fn format_long_row(username: &str, config: &PinkyConfig, info: Option<&UserInfo>) -> String {
+    let mut out = String::new();
 
     let _ = write!(out, "Login name: {:<28}", username);
-    if let Some(ref info) = info {
+    if let Some(info) = info {
         let _ = write!(out, "In real life:  {}", info.fullname);
     }
     let _ = writeln!(out);
 
     if !config.omit_home_shell {
-        if let Some(ref info) = info {
+        if let Some(info) = info {
             let _ = write!(out, "Directory: {:<29}", info.home_dir);
             let _ = writeln!(out, "Shell:  {}", info.shell);
         } else {
@@ -245,7 +348,7 @@ pub fn format_long_entry(username: &str, config: &PinkyConfig) -> String {
 
     // Project file
     if !config.omit_project {
-        if let Some(ref info) = info {
+        if let Some(info) = info {
             let project_path = PathBuf::from(&info.home_dir).join(".project");
             if project_path.exists() {
                 let project = read_first_line(&project_path);
@@ -258,7 +361,7 @@ pub fn format_long_entry(username: &str, config: &PinkyConfig) -> String {
 
     // Plan file
     if !config.omit_plan {
-        if let Some(ref info) = info {
+        if let Some(info) = info {
             let plan_path = PathBuf::from(&info.home_dir).join(".plan");
             if plan_path.exists() {
                 let plan = read_file_contents(&plan_path);
@@ -282,15 +385,146 @@ pub fn format_long_entry(username: &str, config: &PinkyConfig) -> String {
     out
 }
 
-/// Run the pinky command and return the formatted output.
-pub fn run_pinky(config: &PinkyConfig) -> String {
+// ── JSON output (--format=json) ─────────────────────────────────────────
+// For monitoring agents that would otherwise scrape the columns: an array
+// with one object per short-format row or long-format user. Absent values
+// are null rather than empty strings.
+
+/// Append `s` to `out` as a JSON string literal.
+pub(crate) fn json_string(out: &mut String, s: &str) {
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => {
+                let _ = write!(out, "\\u{:04x}", c as u32);
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+/// Append `s` as a JSON string, or null.
+fn json_opt(out: &mut String, s: Option<&str>) {
+    match s {
+        Some(s) => json_string(out, s),
+        None => out.push_str("null"),
+    }
+}
+
+/// One short-format row as a JSON object.
+fn json_short_row(entry: &who::UtmpxEntry, info: Option<&UserInfo>) -> String {
+    let mut out = String::from("{\"user\":");
+    json_string(&mut out, &entry.ut_user);
+    out.push_str(",\"full_name\":");
+    json_opt(&mut out, info.map(|u| u.fullname.as_str()));
+    out.push_str(",\"tty\":");
+    json_string(&mut out, &entry.ut_line);
+    out.push_str(",\"idle_seconds\":");
+    match idle_seconds(&entry.ut_line) {
+        Some(secs) => {
+            let _ = write!(out, "{}", secs.max(0));
+        }
+        None => out.push_str("null"),
+    }
+    out.push_str(",\"login_time\":");
+    json_string(&mut out, &format_time_iso(entry.ut_tv_sec));
+    out.push_str(",\"remote_host\":");
+    json_opt(
+        &mut out,
+        Some(entry.ut_host.as_str()).filter(|h| !h.is_empty()),
+    );
+    if entry.encoding_lossy {
+        out.push_str(",\"encoding_lossy\":true");
+    }
+    out.push('}');
+    out
+}
+
+/// One long-format user as a JSON object. Fields the omit options turn
+/// off, and files that are missing or empty, are null.
+fn json_long_row(username: &str, config: &PinkyConfig, info: Option<&UserInfo>) -> String {
+    let file = |name: &str, first_line: bool| {
+        let path = PathBuf::from(&info?.home_dir).join(name);
+        let text = if first_line {
+            read_first_line(&path)
+        } else {
+            read_file_contents(&path)
+        };
+        (!text.is_empty()).then_some(text)
+    };
+    let home_shell = info.filter(|_| !config.omit_home_shell);
+    let project = if config.omit_project {
+        None
+    } else {
+        file(".project", true)
+    };
+    let plan = if config.omit_plan {
+        None
+    } else {
+        file(".plan", false)
+    };
+
+    let mut out = String::from("{\"user\":");
+    json_string(&mut out, username);
+    out.push_str(",\"full_name\":");
+    json_opt(&mut out, info.map(|u| u.fullname.as_str()));
+    out.push_str(",\"home\":");
+    json_opt(&mut out, home_shell.map(|u| u.home_dir.as_str()));
+    out.push_str(",\"shell\":");
+    json_opt(&mut out, home_shell.map(|u| u.shell.as_str()));
+    out.push_str(",\"project\":");
+    json_opt(&mut out, project.as_deref());
+    out.push_str(",\"plan\":");
+    json_opt(&mut out, plan.as_deref());
+    out.push('}');
+    out
+}
+
+/// Wrap the JSON rows in the top-level object, one row per line.
+fn json_document(rows: &[String], source_available: bool) -> String {
+    let entries = if rows.is_empty() {
+        "[]".to_string()
+    } else {
+        format!("[\n    {}\n  ]", rows.join(",\n    "))
+    };
+    format!(
+        "{{\n  \"source_available\": {},\n  \"entries\": {}\n}}",
+        source_available, entries
+    )
+}
+
+/// Run the pinky command over `records` and return the formatted output.
+pub fn run_pinky(config: &PinkyConfig, records: &who::LoginRecords) -> String {
+    run_pinky_with(
+        config,
+        &records.entries,
+        records.unavailable.is_none(),
+        &mut UserInfoCache::new(),
+    )
+}
+
+/// `run_pinky` over the given utmpx records, resolving passwd entries
+/// through `cache`. `source_available` is false when the login records
+/// could not be read.
+pub(crate) fn run_pinky_with<F: FnMut(&str) -> Option<UserInfo>>(
+    config: &PinkyConfig,
+    entries: &[who::UtmpxEntry],
+    source_available: bool,
+    cache: &mut UserInfoCache<F>,
+) -> String {
     let mut output = String::new();
+    let mut json_rows = Vec::new();
 
     if config.long_format {
         // Long format: show detailed info for each specified user
         let users = if config.users.is_empty() {
             // If no users specified in long mode, show logged-in users
-            let entries = who::read_utmpx();
             let mut names: Vec<String> = entries
                 .iter()
                 .filter(|e| e.ut_type == 7) // USER_PROCESS
@@ -304,16 +538,21 @@ pub fn run_pinky(config: &PinkyConfig) -> String {
         };
 
         for (i, user) in users.iter().enumerate() {
+            let info = cache.get(user);
+            if config.json {
+                json_rows.push(json_long_row(user, config, info));
+                continue;
+            }
             if i > 0 {
                 let _ = writeln!(output);
             }
-            let _ = write!(output, "{}", format_long_entry(user, config));
+            let _ = write!(output, "{}", format_long_row(user, config, info));
         }
     } else {
-        // Short format (default)
-        let entries = who::read_utmpx();
-
-        if !config.omit_heading {
+        // Short format (default): one pass collecting the rows, one batch of
+        // lookups for the distinct logins, then formatting, so a slow lookup
+        // is paid once per user rather than once per session.
+        if !config.omit_heading && !config.json {
             let _ = writeln!(output, "{}", format_short_heading(config));
         }
 
@@ -329,11 +568,28 @@ pub fn run_pinky(config: &PinkyConfig) -> String {
             })
             .collect();
 
+        let full_names = config.json || shows_fullname(config);
+        if full_names {
+            cache.prefetch(user_entries.iter().map(|e| e.ut_user.as_str()));
+        }
         for entry in &user_entries {
-            let _ = writeln!(output, "{}", format_short_entry(entry, config));
+            let info = if full_names {
+                cache.get(&entry.ut_user)
+            } else {
+                None
+            };
+            if config.json {
+                json_rows.push(json_short_row(entry, info));
+            } else {
+                let _ = writeln!(output, "{}", format_short_row(entry, config, info));
+            }
         }
     }
 
+    if config.json {
+        return json_document(&json_rows, source_available);
+    }
+
     // Remove trailing newline for consistency
     if output.ends_with('\n') {
         output.pop();
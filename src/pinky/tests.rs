@@ -105,6 +105,54 @@ fn test_pinky_matches_gnu_format() {
     }
 }
 
+#[test]
+fn test_pinky_long_requires_user() {
+    for args in [&["-l"][..], &["-s", "-l"][..], &["-l", "-b", "-p"][..]] {
+        let output = cmd().args(args).output().unwrap();
+        assert_eq!(output.status.code(), Some(1), "{:?}", args);
+        assert!(output.stdout.is_empty(), "{:?}", args);
+        assert_eq!(
+            String::from_utf8_lossy(&output.stderr),
+            "pinky: no username specified; at least one must be specified when using -l\n\
+             Try 'pinky --help' for more information.\n",
+            "{:?}",
+            args
+        );
+    }
+}
+
+#[test]
+fn test_pinky_last_format_flag_wins() {
+    // A later -s cancels -l, so no user operand is needed
+    let output = cmd().args(["-l", "-s"]).output().unwrap();
+    assert_eq!(output.status.code(), Some(0));
+    let ours = String::from_utf8_lossy(&output.stdout);
+    assert!(!ours.contains("Login name:"));
+
+    let output = cmd().args(["-l", "-l", "root"]).output().unwrap();
+    assert_eq!(output.status.code(), Some(0));
+    assert!(String::from_utf8_lossy(&output.stdout).contains("Login name:"));
+    let output = cmd().args(["-l", "-s", "root"]).output().unwrap();
+    assert!(!String::from_utf8_lossy(&output.stdout).contains("Login name:"));
+}
+
+#[test]
+fn test_pinky_h_is_omit_project_not_help() {
+    let output = cmd().arg("-h").output().unwrap();
+    assert_eq!(output.status.code(), Some(0));
+    assert!(!String::from_utf8_lossy(&output.stdout).contains("Usage"));
+
+    let output = cmd().args(["-h", "-l", "root"]).output().unwrap();
+    assert_eq!(output.status.code(), Some(0));
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("Login name:"));
+    assert!(!stdout.contains("Project:"));
+
+    let output = cmd().arg("--help").output().unwrap();
+    assert_eq!(output.status.code(), Some(0));
+    assert!(String::from_utf8_lossy(&output.stdout).contains("Usage"));
+}
+
 // ---- Unit tests for internal functions ----
 
 #[test]
@@ -151,6 +199,7 @@ fn test_format_short_entry_basic() {
         ut_user: "testuser".to_string(),
         ut_host: "10.0.0.1".to_string(),
         ut_tv_sec: 1_704_067_200,
+        encoding_lossy: false,
     };
     let config = PinkyConfig::default();
     let line = format_short_entry(&entry, &config);
@@ -232,3 +281,334 @@ fn test_get_user_info_nonexistent() {
     let info = get_user_info("this_user_definitely_does_not_exist_12345");
     assert!(info.is_none(), "Should not find nonexistent user");
 }
+
+// ---- Passwd lookup cache ----
+
+fn session(user: &str, n: usize) -> crate::who::UtmpxEntry {
+    crate::who::UtmpxEntry {
+        ut_type: 7, // USER_PROCESS
+        ut_pid: 1000 + n as i32,
+        ut_line: format!("no-such-tty-{}", n),
+        ut_id: n.to_string(),
+        ut_user: user.to_string(),
+        ut_host: String::new(),
+        ut_tv_sec: 1_704_067_200,
+        encoding_lossy: false,
+    }
+}
+
+fn fake_lookup(login: &str) -> Option<UserInfo> {
+    (login != "ghost").then(|| UserInfo {
+        login: login.to_string(),
+        fullname: format!("{} Fullname", login),
+        home_dir: format!("/nonexistent/{}", login),
+        shell: "/bin/sh".to_string(),
+    })
+}
+
+#[test]
+fn test_short_format_looks_up_each_user_once() {
+    let users = ["alice", "bob", "carol"];
+    let entries: Vec<_> = (0..50).map(|n| session(users[n % 3], n)).collect();
+    let mut calls = Vec::new();
+    let mut cache = UserInfoCache::with_lookup(|login: &str| {
+        calls.push(login.to_string());
+        fake_lookup(login)
+    });
+    let output = run_pinky_with(&PinkyConfig::default(), &entries, true, &mut cache);
+    drop(cache);
+
+    calls.sort();
+    assert_eq!(calls, ["alice", "bob", "carol"]);
+    let lines: Vec<&str> = output.lines().collect();
+    assert_eq!(lines.len(), 51);
+    assert!(lines[0].starts_with("Login"));
+    // Rows keep utmpx order and carry the cached names
+    for (n, line) in lines[1..].iter().enumerate() {
+        let user = users[n % 3];
+        assert!(line.starts_with(user), "{}", line);
+        assert!(line.contains(&format!("{} Fullname", user)), "{}", line);
+    }
+}
+
+#[test]
+fn test_cache_skips_lookups_without_name_column() {
+    let entries: Vec<_> = (0..5).map(|n| session("alice", n)).collect();
+    let mut calls = 0;
+    let mut cache = UserInfoCache::with_lookup(|login: &str| {
+        calls += 1;
+        fake_lookup(login)
+    });
+    let config = PinkyConfig {
+        omit_fullname: true,
+        ..PinkyConfig::default()
+    };
+    let output = run_pinky_with(&config, &entries, true, &mut cache);
+    drop(cache);
+    assert_eq!(calls, 0);
+    assert_eq!(output.lines().count(), 6);
+}
+
+#[test]
+fn test_cache_remembers_misses() {
+    let mut calls = 0;
+    let mut cache = UserInfoCache::with_lookup(|login: &str| {
+        calls += 1;
+        fake_lookup(login)
+    });
+    assert!(cache.get("ghost").is_none());
+    assert!(cache.get("ghost").is_none());
+    assert_eq!(cache.get("bob").unwrap().shell, "/bin/sh");
+    cache.prefetch(["bob", "ghost", "bob"]);
+    drop(cache);
+    assert_eq!(calls, 2);
+}
+
+#[test]
+fn test_long_format_uses_cache_for_repeated_users() {
+    let mut calls = 0;
+    let mut cache = UserInfoCache::with_lookup(|login: &str| {
+        calls += 1;
+        fake_lookup(login)
+    });
+    let config = PinkyConfig {
+        long_format: true,
+        users: vec!["alice".to_string(), "alice".to_string()],
+        ..PinkyConfig::default()
+    };
+    let output = run_pinky_with(&config, &[], true, &mut cache);
+    drop(cache);
+    assert_eq!(calls, 1);
+    assert_eq!(output.matches("In real life:  alice Fullname").count(), 2);
+}
+
+// ---- JSON output ----
+
+fn json_config() -> PinkyConfig {
+    PinkyConfig {
+        json: true,
+        ..PinkyConfig::default()
+    }
+}
+
+fn parse_document(output: &str) -> serde_json::Value {
+    serde_json::from_str(output).unwrap_or_else(|e| panic!("invalid JSON ({}): {}", e, output))
+}
+
+fn parse_json(output: &str) -> Vec<serde_json::Value> {
+    let doc = parse_document(output);
+    assert_eq!(doc["source_available"], true, "{}", output);
+    doc["entries"].as_array().unwrap().clone()
+}
+
+#[test]
+fn test_json_short_records() {
+    let mut entries = vec![session("alice", 0), session("ghost", 1), session("bob", 2)];
+    entries[0].ut_host = "10.0.0.1".to_string();
+    entries[2].ut_user = "b\u{fffd}b".to_string();
+    entries[2].encoding_lossy = true;
+    let mut cache = UserInfoCache::with_lookup(fake_lookup);
+    let rows = parse_json(&run_pinky_with(&json_config(), &entries, true, &mut cache));
+
+    assert_eq!(rows.len(), 3);
+    for row in &rows {
+        for key in [
+            "user",
+            "full_name",
+            "tty",
+            "idle_seconds",
+            "login_time",
+            "remote_host",
+        ] {
+            assert!(row.get(key).is_some(), "{} missing from {}", key, row);
+        }
+        // The ttys do not exist, so there is no idle time
+        assert!(row["idle_seconds"].is_null());
+        let login_time = row["login_time"].as_str().unwrap();
+        assert_eq!(login_time.len(), 25, "{}", login_time);
+        assert_eq!(&login_time[10..11], "T");
+    }
+    assert_eq!(rows[0]["user"], "alice");
+    assert_eq!(rows[0]["full_name"], "alice Fullname");
+    assert_eq!(rows[0]["tty"], "no-such-tty-0");
+    assert_eq!(rows[0]["remote_host"], "10.0.0.1");
+    assert!(rows[0].get("encoding_lossy").is_none());
+    assert!(rows[1]["full_name"].is_null());
+    assert!(rows[1]["remote_host"].is_null());
+    assert_eq!(rows[2]["user"], "b\u{fffd}b");
+    assert_eq!(rows[2]["encoding_lossy"], true);
+}
+
+#[test]
+fn test_json_short_respects_user_filter() {
+    let entries: Vec<_> = (0..6)
+        .map(|n| session(["alice", "bob"][n % 2], n))
+        .collect();
+    let config = PinkyConfig {
+        users: vec!["bob".to_string()],
+        ..json_config()
+    };
+    let mut cache = UserInfoCache::with_lookup(fake_lookup);
+    let rows = parse_json(&run_pinky_with(&config, &entries, true, &mut cache));
+    assert_eq!(rows.len(), 3);
+    assert!(rows.iter().all(|r| r["user"] == "bob"));
+
+    let none = PinkyConfig {
+        users: vec!["carol".to_string()],
+        ..json_config()
+    };
+    assert_eq!(
+        run_pinky_with(&none, &entries, true, &mut cache),
+        "{\n  \"source_available\": true,\n  \"entries\": []\n}"
+    );
+}
+
+#[test]
+fn test_json_long_records() {
+    let dir = tempfile::tempdir().unwrap();
+    let home = dir.path().to_str().unwrap().to_string();
+    std::fs::write(dir.path().join(".project"), "Ship \"it\"\nsecond line\n").unwrap();
+    std::fs::write(dir.path().join(".plan"), "line 1\n\tline 2\n").unwrap();
+    let lookup = |login: &str| {
+        fake_lookup(login).map(|mut u| {
+            u.home_dir = home.clone();
+            u
+        })
+    };
+    let config = PinkyConfig {
+        long_format: true,
+        users: vec!["alice".to_string(), "ghost".to_string()],
+        ..json_config()
+    };
+    let mut cache = UserInfoCache::with_lookup(lookup);
+    let rows = parse_json(&run_pinky_with(&config, &[], true, &mut cache));
+
+    assert_eq!(rows.len(), 2);
+    assert_eq!(rows[0]["user"], "alice");
+    assert_eq!(rows[0]["full_name"], "alice Fullname");
+    assert_eq!(rows[0]["home"], home.as_str());
+    assert_eq!(rows[0]["shell"], "/bin/sh");
+    assert_eq!(rows[0]["project"], "Ship \"it\"");
+    assert_eq!(rows[0]["plan"], "line 1\n\tline 2\n");
+    // No passwd entry: everything but the name is null
+    assert_eq!(rows[1]["user"], "ghost");
+    for key in ["full_name", "home", "shell", "project", "plan"] {
+        assert!(rows[1][key].is_null(), "{}", key);
+    }
+
+    // The omit options null their fields
+    let config = PinkyConfig {
+        omit_home_shell: true,
+        omit_project: true,
+        omit_plan: true,
+        users: vec!["alice".to_string()],
+        ..config
+    };
+    let rows = parse_json(&run_pinky_with(&config, &[], true, &mut cache));
+    for key in ["home", "shell", "project", "plan"] {
+        assert!(rows[0][key].is_null(), "{}", key);
+    }
+}
+
+#[test]
+fn test_json_string_escapes() {
+    let mut out = String::new();
+    json_string(&mut out, "a\"b\\c\n\u{1}\u{e9}");
+    let back: String = serde_json::from_str(&out).unwrap();
+    assert_eq!(back, "a\"b\\c\n\u{1}\u{e9}");
+}
+
+#[test]
+fn test_pinky_json_current_session() {
+    let output = cmd().arg("--format=json").output().unwrap();
+    assert!(output.status.success());
+    // Containers often have no login records at all
+    let doc = parse_document(&String::from_utf8_lossy(&output.stdout));
+    assert!(doc["source_available"].is_boolean());
+    for row in doc["entries"].as_array().unwrap() {
+        for key in ["user", "tty", "login_time"] {
+            assert!(row[key].is_string(), "{} in {}", key, row);
+        }
+    }
+
+    let whoami = Command::new("whoami").output().unwrap();
+    let username = String::from_utf8_lossy(&whoami.stdout).trim().to_string();
+    if username.is_empty() {
+        return;
+    }
+    let output = cmd()
+        .args(["-l", "--format=json", &username])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let rows = parse_json(&String::from_utf8_lossy(&output.stdout));
+    assert_eq!(rows.len(), 1);
+    assert_eq!(rows[0]["user"], username.as_str());
+    for key in ["full_name", "home", "shell", "project", "plan"] {
+        assert!(rows[0].get(key).is_some(), "{} missing", key);
+    }
+    assert!(rows[0]["home"].is_string());
+}
+
+#[test]
+fn test_json_reports_unavailable_source() {
+    let mut cache = UserInfoCache::with_lookup(fake_lookup);
+    let doc = parse_document(&run_pinky_with(&json_config(), &[], false, &mut cache));
+    assert_eq!(doc["source_available"], false);
+    assert_eq!(doc["entries"], serde_json::json!([]));
+}
+
+#[test]
+fn test_pinky_missing_records_file() {
+    for args in [&[][..], &["-s"][..], &["-l"][..]] {
+        let output = cmd()
+            .args(args)
+            .args(["--file=/nonexistent/utmp", "alice"])
+            .output()
+            .unwrap();
+        assert_eq!(output.status.code(), Some(0), "{:?}", args);
+        let expected = if args == ["-l"] {
+            // Long format with users never reads the records
+            ""
+        } else {
+            "pinky: cannot read user login records: No such file or directory\n"
+        };
+        assert_eq!(
+            String::from_utf8_lossy(&output.stderr),
+            expected,
+            "{:?}",
+            args
+        );
+    }
+
+    let output = cmd()
+        .args(["--format=json", "--file", "/nonexistent/utmp"])
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(0));
+    let doc = parse_document(&String::from_utf8_lossy(&output.stdout));
+    assert_eq!(doc["source_available"], false);
+    assert_eq!(doc["entries"], serde_json::json!([]));
+}
+
+#[test]
+fn test_pinky_reads_records_file() {
+    // An empty file is a readable source with no sessions
+    let dir = tempfile::tempdir().unwrap();
+    let file = dir.path().join("utmp");
+    std::fs::write(&file, b"").unwrap();
+    let output = cmd()
+        .args(["--format=json", "--file"])
+        .arg(&file)
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(0));
+    assert!(output.stderr.is_empty());
+    assert!(parse_json(&String::from_utf8_lossy(&output.stdout)).is_empty());
+}
+
+#[test]
+fn test_pinky_format_rejects_unknown() {
+    let output = cmd().arg("--format=xml").output().unwrap();
+    assert!(!output.status.success());
+}
@@ -1,6 +1,9 @@
 use std::io::{self, IoSlice, Write};
 
 use rayon::prelude::*;
+use regex_automata::nfa::thompson;
+use regex_automata::util::syntax;
+use regex_automata::{Anchored, Input, MatchKind, hybrid, meta};
 
 /// Threshold for parallel processing (64MB).
 /// Each benchmark invocation is a fresh process, so rayon pool init (~0.5-1ms)
@@ -9,14 +12,74 @@ use rayon::prelude::*;
 /// files where multi-core scanning and copying pays off.
 const PARALLEL_THRESHOLD: usize = 64 * 1024 * 1024;
 
+/// First window of the lazy backward scan used for inputs larger than this.
+/// Later windows double in size up to `LAZY_MAX_WINDOW`, so time to first
+/// output is bounded while big windows still get the parallel reversal.
+const LAZY_WINDOW: usize = 8 * 1024 * 1024;
+
+/// Largest window of the lazy backward scan.
+const LAZY_MAX_WINDOW: usize = 256 * 1024 * 1024;
+
+/// Chunk size of `reverse_bytes`: the input is reversed this much at a time
+/// into one reusable buffer, from the tail forward.
+pub const REVERSE_CHUNK: usize = 8 * 1024 * 1024;
+
+/// Inputs shorter than this take `tac_small`: one backward scan into a
+/// buffer the size of the input and a single write, without a positions
+/// Vec or the parallel and windowed machinery.
+pub const SMALL_INPUT: usize = 64 * 1024;
+
 /// Maximum IoSlice entries per write_vectored batch.
-/// Used by string/regex separator paths.
+/// Used by string/regex separator paths. Nothing here buffers `out`: the
+/// byte paths hand it whole buffers and the others these batches, so a
+/// caller writing to a `Vec` gets no extra copy, and ftac wraps stdout in
+/// its own BufWriter only where batches of small slices make that pay.
 const IOSLICE_BATCH_SIZE: usize = 1024;
 
+/// How much leading data `detect_crlf` inspects for `--auto-separator`.
+pub const CRLF_DETECT_WINDOW: usize = 8 * 1024;
+
+/// The two-byte record separator used by `--crlf`.
+pub const CRLF: &[u8] = b"\r\n";
+
+/// True if CRLF line endings dominate the first `CRLF_DETECT_WINDOW` bytes:
+/// more than half of the `\n` bytes there are preceded by `\r`.
+/// Data with no newline in the window is not CRLF.
+pub fn detect_crlf(data: &[u8]) -> bool {
+    let window = &data[..data.len().min(CRLF_DETECT_WINDOW)];
+    let mut lf = 0usize;
+    let mut crlf = 0usize;
+    for pos in memchr::memchr_iter(b'\n', window) {
+        lf += 1;
+        if pos > 0 && window[pos - 1] == b'\r' {
+            crlf += 1;
+        }
+    }
+    crlf * 2 > lf
+}
+
 /// Reverse records separated by a single byte.
-/// For large data (>= 8MB): parallel chunk-local reversal with contiguous buffers.
-/// For small data: single-threaded forward SIMD scan + contiguous output buffer.
+/// For large data (> 8MB): lazy backward windows, see `tac_bytes_windowed`.
+/// For tiny data (< 64KB): `tac_small`.
+/// Otherwise: single-threaded forward SIMD scan + contiguous output buffer.
 pub fn tac_bytes(data: &[u8], separator: u8, before: bool, out: &mut impl Write) -> io::Result<()> {
+    if data.len() < SMALL_INPUT {
+        return tac_small(data, 1, before, |p| memchr::memrchr(separator, p), out);
+    }
+    if data.len() > LAZY_WINDOW {
+        return tac_bytes_windowed(data, separator, before, LAZY_WINDOW, |_| {}, out);
+    }
+    tac_bytes_eager(data, separator, before, out)
+}
+
+/// Reverse all of `data` in one pass: parallel chunk-local reversal from 64MB,
+/// otherwise a single forward scan into one contiguous buffer.
+pub(crate) fn tac_bytes_eager(
+    data: &[u8],
+    separator: u8,
+    before: bool,
+    out: &mut impl Write,
+) -> io::Result<()> {
     if data.is_empty() {
         return Ok(());
     }
@@ -33,6 +96,86 @@ pub fn tac_bytes(data: &[u8], separator: u8, before: bool, out: &mut impl Write)
     }
 }
 
+/// Reverse `data` window by window from the end, so a reader that stops
+/// early (`tac big.log | head`) leaves all but the tail of the input
+/// unscanned. Each window ends where the previous one started and begins
+/// at the record boundary found by `memrchr` just before its nominal size,
+/// so every window holds whole records and is reversed independently.
+/// `scanned` is told the start of each window before it is processed.
+pub(crate) fn tac_bytes_windowed<W: Write>(
+    data: &[u8],
+    separator: u8,
+    before: bool,
+    first_window: usize,
+    scanned: impl FnMut(usize),
+    out: &mut W,
+) -> io::Result<()> {
+    let boundary = |prefix: &[u8]| match memchr::memrchr(separator, prefix) {
+        Some(pos) if before => pos,
+        Some(pos) => pos + 1,
+        None => 0,
+    };
+    tac_windowed(
+        data,
+        first_window,
+        boundary,
+        |window: &[u8], out: &mut W| tac_bytes_eager(window, separator, before, out),
+        scanned,
+        out,
+    )
+}
+
+/// Drive the lazy backward scan: `boundary(prefix)` gives the start of the
+/// last record beginning inside `prefix` (0 if none), and `reverse` emits
+/// one window of whole records.
+fn tac_windowed<W: Write>(
+    data: &[u8],
+    first_window: usize,
+    boundary: impl Fn(&[u8]) -> usize,
+    mut reverse: impl FnMut(&[u8], &mut W) -> io::Result<()>,
+    mut scanned: impl FnMut(usize),
+    out: &mut W,
+) -> io::Result<()> {
+    let mut end = data.len();
+    let mut window = first_window.max(1);
+    while end > 0 {
+        let start = if end > window {
+            boundary(&data[..end - window])
+        } else {
+            0
+        };
+        scanned(start);
+        reverse(&data[start..end], out)?;
+        end = start;
+        window = (window * 2).min(LAZY_MAX_WINDOW.max(first_window));
+    }
+    Ok(())
+}
+
+/// Reverse a small input with a single write. `rfind(prefix)` gives the
+/// start of the last separator in `prefix`; searching backward from the
+/// previous match finds the same separators as the forward scans, and for
+/// a self-overlapping separator the ones GNU's backward search finds.
+fn tac_small(
+    data: &[u8],
+    sep_len: usize,
+    before: bool,
+    rfind: impl Fn(&[u8]) -> Option<usize>,
+    out: &mut impl Write,
+) -> io::Result<()> {
+    let mut buf = Vec::with_capacity(data.len());
+    let mut end = data.len();
+    let mut search_end = data.len();
+    while let Some(pos) = rfind(&data[..search_end]) {
+        let start = if before { pos } else { pos + sep_len };
+        buf.extend_from_slice(&data[start..end]);
+        end = start;
+        search_end = pos;
+    }
+    buf.extend_from_slice(&data[..end]);
+    out.write_all(&buf)
+}
+
 /// Reverse records of an owned Vec. Delegates to tac_bytes.
 pub fn tac_bytes_owned(
     data: &mut [u8],
@@ -43,9 +186,69 @@ pub fn tac_bytes_owned(
     tac_bytes(data, separator, before, out)
 }
 
+/// `--bytes`: write `data` with its bytes in reverse order, no record
+/// semantics. Works on borrowed (mmapped) input without copying it whole:
+/// see `reverse_bytes_chunked`.
+pub fn reverse_bytes(data: &[u8], out: &mut impl Write) -> io::Result<()> {
+    reverse_bytes_chunked(data, REVERSE_CHUNK, out)
+}
+
+/// Reverse `data` `chunk` bytes at a time, starting from its end, into a
+/// buffer reused for every chunk, writing each chunk as soon as it is done.
+pub(crate) fn reverse_bytes_chunked(
+    data: &[u8],
+    chunk: usize,
+    out: &mut impl Write,
+) -> io::Result<()> {
+    let chunk = chunk.max(1);
+    let mut buf = vec![0u8; chunk.min(data.len())];
+    let mut end = data.len();
+    while end > 0 {
+        let start = end.saturating_sub(chunk);
+        let dst = &mut buf[..end - start];
+        reverse_into(&data[start..end], dst);
+        out.write_all(dst)?;
+        end = start;
+    }
+    Ok(())
+}
+
+/// `--bytes` on an owned Vec: reverse in place, then one write.
+pub fn reverse_bytes_owned(data: &mut [u8], out: &mut impl Write) -> io::Result<()> {
+    data.reverse();
+    out.write_all(data)
+}
+
+/// Copy `src` into `dst` (same length) in reverse byte order. Whole u64
+/// words are taken from the tail of `src` and byte-swapped, which the
+/// compiler turns into vector shuffles; the few leading bytes of `src` that
+/// do not fill a word end up at the tail of `dst`.
+#[inline]
+fn reverse_into(src: &[u8], dst: &mut [u8]) {
+    debug_assert_eq!(src.len(), dst.len());
+    let mut dst_words = dst.chunks_exact_mut(8);
+    let mut src_words = src.rchunks_exact(8);
+    for (d, s) in (&mut dst_words).zip(&mut src_words) {
+        let word = u64::from_ne_bytes(s.try_into().unwrap());
+        d.copy_from_slice(&word.swap_bytes().to_ne_bytes());
+    }
+    let head = src_words.remainder();
+    for (d, s) in dst_words.into_remainder().iter_mut().zip(head.iter().rev()) {
+        *d = *s;
+    }
+}
+
 /// Collect multi-byte separator positions with pre-allocated Vec.
+/// GNU searches backward from the end of the input, which a forward scan
+/// only reproduces when occurrences cannot overlap: "bb" in "bbb" is
+/// matched at 1, not 0.
 #[inline]
 fn collect_positions_str(data: &[u8], separator: &[u8]) -> Vec<usize> {
+    if self_overlapping(separator) {
+        let mut positions: Vec<usize> = memchr::memmem::rfind_iter(data, separator).collect();
+        positions.reverse();
+        return positions;
+    }
     let estimated = data.len() / 40 + 64;
     let mut positions = Vec::with_capacity(estimated);
     for pos in memchr::memmem::find_iter(data, separator) {
@@ -283,7 +486,26 @@ pub fn tac_string_separator(
     if separator.len() == 1 {
         return tac_bytes(data, separator[0], before, out);
     }
+    if data.len() < SMALL_INPUT && !separator.is_empty() {
+        let finder = memchr::memmem::FinderRev::new(separator);
+        return tac_small(data, separator.len(), before, |p| finder.rfind(p), out);
+    }
+
+    // Windows may split the input only where a self-overlapping separator
+    // (like "aa") cannot match differently than in one forward pass
+    if data.len() > LAZY_WINDOW && !self_overlapping(separator) {
+        return tac_string_windowed(data, separator, before, LAZY_WINDOW, |_| {}, out);
+    }
+    tac_string_eager(data, separator, before, out)
+}
 
+/// Reverse all of `data` with one forward memmem pass.
+pub(crate) fn tac_string_eager(
+    data: &[u8],
+    separator: &[u8],
+    before: bool,
+    out: &mut impl Write,
+) -> io::Result<()> {
     let sep_len = separator.len();
 
     if !before {
@@ -293,6 +515,40 @@ pub fn tac_string_separator(
     }
 }
 
+/// True if a proper suffix of `sep` is also its prefix, so that two
+/// occurrences can overlap.
+pub(crate) fn self_overlapping(sep: &[u8]) -> bool {
+    (1..sep.len()).any(|k| sep[k..] == sep[..sep.len() - k])
+}
+
+/// String-separator counterpart of `tac_bytes_windowed`, finding window
+/// boundaries with a backward memmem search. `separator` must not be
+/// self-overlapping.
+pub(crate) fn tac_string_windowed<W: Write>(
+    data: &[u8],
+    separator: &[u8],
+    before: bool,
+    first_window: usize,
+    scanned: impl FnMut(usize),
+    out: &mut W,
+) -> io::Result<()> {
+    debug_assert!(!self_overlapping(separator));
+    let finder = memchr::memmem::FinderRev::new(separator);
+    let boundary = |prefix: &[u8]| match finder.rfind(prefix) {
+        Some(pos) if before => pos,
+        Some(pos) => pos + separator.len(),
+        None => 0,
+    };
+    tac_windowed(
+        data,
+        first_window,
+        boundary,
+        |window: &[u8], out: &mut W| tac_string_eager(window, separator, before, out),
+        scanned,
+        out,
+    )
+}
+
 /// Multi-byte string separator, after mode. Uses writev batching.
 fn tac_string_after(
     data: &[u8],
@@ -364,35 +620,191 @@ fn tac_string_before(
     Ok(())
 }
 
+/// A compiled `-r` separator. The reverse lazy DFA finds the latest match
+/// start in one backward pass; the forward regex then measures that match.
+/// The DFA reports every match (`MatchKind::All`) so that it keeps scanning
+/// past the zero-width match a pattern like `x*` has at the end.
+pub(crate) struct SeparatorRegex {
+    forward: meta::Regex,
+    /// `None` if the pattern is too large for a lazy DFA.
+    reverse: Option<hybrid::dfa::DFA>,
+}
+
+/// Compile a `-r` separator with the same syntax as `regex::bytes::Regex`.
+pub(crate) fn compile_separator(pattern: &str) -> io::Result<SeparatorRegex> {
+    let syntax = syntax::Config::new().utf8(false);
+    let forward = meta::Regex::builder()
+        .configure(meta::Config::new().utf8_empty(false))
+        .syntax(syntax)
+        .build(pattern)
+        .map_err(|e| {
+            let msg = match e.syntax_error() {
+                Some(syntax_err) => syntax_err.to_string(),
+                None => e.to_string(),
+            };
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("invalid regex '{}': {}", pattern, msg),
+            )
+        })?;
+    let reverse = hybrid::dfa::DFA::builder()
+        .configure(
+            hybrid::dfa::Config::new()
+                .match_kind(MatchKind::All)
+                .unicode_word_boundary(true),
+        )
+        .syntax(syntax)
+        .thompson(thompson::Config::new().reverse(true))
+        .build(pattern)
+        .ok();
+    Ok(SeparatorRegex { forward, reverse })
+}
+
+impl SeparatorRegex {
+    /// Force the per-position anchored fallback, for differential tests.
+    #[cfg(test)]
+    pub(crate) fn without_reverse_dfa(mut self) -> Self {
+        self.reverse = None;
+        self
+    }
+}
+
 /// Find regex matches using backward scanning, matching GNU tac's re_search behavior.
-fn find_regex_matches_backward(data: &[u8], re: &regex::bytes::Regex) -> Vec<(usize, usize)> {
+///
+/// Each step takes the latest position at which the pattern matches within
+/// the data not yet consumed, and the next step only looks before that
+/// match's start. A pattern that can match the empty string yields
+/// zero-width matches (start == end); like GNU, these act as zero-width
+/// separators, so `x*` reverses the data byte by byte. Every match starts
+/// strictly before the previous one, so the scan always terminates with at
+/// most `data.len()` matches, and each byte is visited a bounded number of
+/// times by the reverse pass and the forward measurement.
+pub(crate) fn find_regex_matches_backward(data: &[u8], re: &SeparatorRegex) -> Vec<(usize, usize)> {
+    let mut cache = re.reverse.as_ref().map(|dfa| dfa.create_cache());
     let mut matches = Vec::new();
     let mut past_end = data.len();
 
     while past_end > 0 {
-        let buf = &data[..past_end];
-        let mut found = false;
-
-        let mut pos = past_end;
-        while pos > 0 {
-            pos -= 1;
-            if let Some(m) = re.find_at(buf, pos) {
-                if m.start() == pos {
-                    matches.push((m.start(), m.end()));
-                    past_end = m.start();
-                    found = true;
-                    break;
+        let hay = &data[..past_end];
+        let found = match (&re.reverse, &mut cache) {
+            (Some(dfa), Some(cache)) => latest_match(re, dfa, cache, hay),
+            _ => latest_match_anchored(&re.forward, hay, past_end),
+        };
+        let Some((start, end)) = found else { break };
+        debug_assert!(start < past_end && end <= past_end);
+        matches.push((start, end));
+        past_end = start;
+    }
+    debug_assert!(matches.len() <= data.len() + 1);
+
+    matches.reverse();
+    matches
+}
+
+/// The match in `hay` with the latest start before `hay.len()`.
+fn latest_match(
+    re: &SeparatorRegex,
+    dfa: &hybrid::dfa::DFA,
+    cache: &mut hybrid::dfa::Cache,
+    hay: &[u8],
+) -> Option<(usize, usize)> {
+    let mut below = hay.len();
+    loop {
+        let start = match latest_start_reverse(dfa, cache, hay, below) {
+            Ok(Some(start)) => start,
+            Ok(None) => return None,
+            // Quit byte (e.g. non-ASCII near \b) or a thrashing cache
+            Err(()) => return latest_match_anchored(&re.forward, hay, below),
+        };
+        if let Some(m) = anchored_match(&re.forward, hay, start) {
+            return Some(m);
+        }
+        // The engines disagree; keep looking further back rather than loop.
+        below = start;
+        if below == 0 {
+            return None;
+        }
+    }
+}
+
+/// Latest position before `below` at which a match of the pattern starts,
+/// the match ending anywhere within `hay`. One backward pass of the reverse
+/// DFA: its match states are delayed by one byte, so a match state after
+/// consuming `hay[i]` means a match starts at `i + 1`.
+fn latest_start_reverse(
+    dfa: &hybrid::dfa::DFA,
+    cache: &mut hybrid::dfa::Cache,
+    hay: &[u8],
+    below: usize,
+) -> Result<Option<usize>, ()> {
+    let mut sid = dfa
+        .start_state_reverse(cache, &Input::new(hay))
+        .map_err(|_| ())?;
+    for i in (0..hay.len()).rev() {
+        sid = dfa.next_state(cache, sid, hay[i]).map_err(|_| ())?;
+        if sid.is_tagged() {
+            if sid.is_match() {
+                if i + 1 < below {
+                    return Ok(Some(i + 1));
                 }
+            } else if sid.is_dead() {
+                return Ok(None);
+            } else if sid.is_quit() {
+                return Err(());
             }
         }
+    }
+    sid = dfa.next_eoi_state(cache, sid).map_err(|_| ())?;
+    Ok(sid.is_match().then_some(0))
+}
 
-        if !found {
-            break;
+/// Fallback: try an anchored match at each position before `below`, latest
+/// first. Quadratic for patterns that scan far before failing.
+fn latest_match_anchored(re: &meta::Regex, hay: &[u8], below: usize) -> Option<(usize, usize)> {
+    (0..below)
+        .rev()
+        .find_map(|pos| anchored_match(re, hay, pos))
+}
+
+fn anchored_match(re: &meta::Regex, hay: &[u8], pos: usize) -> Option<(usize, usize)> {
+    let input = Input::new(hay).range(pos..).anchored(Anchored::Yes);
+    re.find(input).map(|m| (m.start(), m.end()))
+}
+
+/// Record boundaries for a regex separator, as byte ranges in output order.
+/// Records made empty by zero-width matches are left out; the ranges always
+/// tile `data` exactly, so reversing them gives back the input.
+pub(crate) fn regex_records(
+    data: &[u8],
+    matches: &[(usize, usize)],
+    before: bool,
+) -> Vec<(usize, usize)> {
+    let mut records = Vec::with_capacity(matches.len() + 1);
+    if !before {
+        let last_end = matches.last().map_or(0, |m| m.1);
+        records.push((last_end, data.len()));
+        let mut i = matches.len();
+        while i > 0 {
+            i -= 1;
+            let rec_start = if i == 0 { 0 } else { matches[i - 1].1 };
+            records.push((rec_start, matches[i].1));
         }
+    } else {
+        let mut i = matches.len();
+        while i > 0 {
+            i -= 1;
+            let start = matches[i].0;
+            let end = if i + 1 < matches.len() {
+                matches[i + 1].0
+            } else {
+                data.len()
+            };
+            records.push((start, end));
+        }
+        records.push((0, matches.first().map_or(data.len(), |m| m.0)));
     }
-
-    matches.reverse();
-    matches
+    records.retain(|&(start, end)| start < end);
+    records
 }
 
 /// Reverse records using a regex separator.
@@ -407,16 +819,7 @@ pub fn tac_regex_separator(
         return Ok(());
     }
 
-    let re = match regex::bytes::Regex::new(pattern) {
-        Ok(r) => r,
-        Err(e) => {
-            return Err(io::Error::new(
-                io::ErrorKind::InvalidInput,
-                format!("invalid regex '{}': {}", pattern, e),
-            ));
-        }
-    };
-
+    let re = compile_separator(pattern)?;
     let matches = find_regex_matches_backward(data, &re);
 
     if matches.is_empty() {
@@ -426,40 +829,181 @@ pub fn tac_regex_separator(
 
     // For regex separators, use write_vectored since there are typically
     // few large records. Build all IoSlices at once and flush.
-    let mut slices: Vec<IoSlice<'_>> = Vec::with_capacity(matches.len() + 2);
+    let slices: Vec<IoSlice<'_>> = regex_records(data, &matches, before)
+        .into_iter()
+        .map(|(start, end)| IoSlice::new(&data[start..end]))
+        .collect();
 
-    if !before {
-        let last_end = matches.last().unwrap().1;
+    write_all_vectored(out, &slices)
+}
+
+/// `--unique` with a single-byte separator: reverse the records like
+/// `tac_bytes`, but drop a record equal to the one written just before it,
+/// so the output matches `tac | uniq`. See `tac_unique_spans`.
+pub fn tac_bytes_unique(
+    data: &[u8],
+    separator: u8,
+    before: bool,
+    out: &mut impl Write,
+) -> io::Result<()> {
+    let spans = memchr::memrchr_iter(separator, data).map(|pos| (pos, pos + 1));
+    tac_unique_spans(data, spans, before, out)
+}
+
+/// `--unique` with a multi-byte string separator (see `tac_bytes_unique`).
+pub fn tac_string_unique(
+    data: &[u8],
+    separator: &[u8],
+    before: bool,
+    out: &mut impl Write,
+) -> io::Result<()> {
+    if separator.len() == 1 {
+        return tac_bytes_unique(data, separator[0], before, out);
+    }
+    // Forward positions, so a self-overlapping separator splits as in
+    // `tac_string_separator`
+    let positions = collect_positions_str(data, separator);
+    let spans = positions
+        .iter()
+        .rev()
+        .map(|&pos| (pos, pos + separator.len()));
+    tac_unique_spans(data, spans, before, out)
+}
 
+/// `--unique` with a regex separator (see `tac_bytes_unique`).
+pub fn tac_regex_unique(
+    data: &[u8],
+    pattern: &str,
+    before: bool,
+    out: &mut impl Write,
+) -> io::Result<()> {
+    let re = compile_separator(pattern)?;
+    let matches = find_regex_matches_backward(data, &re);
+    tac_unique_spans(data, matches.into_iter().rev(), before, out)
+}
+
+/// Emit the records delimited by `spans` (separator `(start, end)` ranges,
+/// last first) in reverse order, skipping each record whose key equals the
+/// key of the record written before it.
+///
+/// In after mode the key is the record without its trailing separator,
+/// which is the line `uniq` would compare. An unterminated last record is
+/// written glued to the record after it in the output, so the two form one
+/// key there, as they form one line for `uniq`. In before mode the key is
+/// the whole record, leading separator included.
+fn tac_unique_spans(
+    data: &[u8],
+    spans: impl Iterator<Item = (usize, usize)>,
+    before: bool,
+    out: &mut impl Write,
+) -> io::Result<()> {
+    let mut emit = UniqueEmitter::new(data);
+    let mut spans = spans.peekable();
+    if !before {
+        let last_end = spans.peek().map_or(0, |&(_, end)| end);
         if last_end < data.len() {
-            slices.push(IoSlice::new(&data[last_end..]));
+            let tail = last_end..data.len();
+            match spans.next() {
+                Some((sep_start, sep_end)) => {
+                    let start = spans.peek().map_or(0, |&(_, end)| end);
+                    emit.push_joined(tail, start..sep_end, start..sep_start, out)?;
+                }
+                None => emit.push(tail.clone(), tail, out)?,
+            }
         }
-
-        let mut i = matches.len();
-        while i > 0 {
-            i -= 1;
-            let rec_start = if i == 0 { 0 } else { matches[i - 1].1 };
-            slices.push(IoSlice::new(&data[rec_start..matches[i].1]));
+        while let Some((sep_start, sep_end)) = spans.next() {
+            let start = spans.peek().map_or(0, |&(_, end)| end);
+            if start < sep_end {
+                emit.push(start..sep_end, start..sep_start, out)?;
+            }
         }
     } else {
-        let mut i = matches.len();
-        while i > 0 {
-            i -= 1;
-            let start = matches[i].0;
-            let end = if i + 1 < matches.len() {
-                matches[i + 1].0
-            } else {
-                data.len()
-            };
-            slices.push(IoSlice::new(&data[start..end]));
+        let mut end = data.len();
+        for (sep_start, _) in spans {
+            if sep_start < end {
+                emit.push(sep_start..end, sep_start..end, out)?;
+            }
+            end = sep_start;
+        }
+        if end > 0 {
+            emit.push(0..end, 0..end, out)?;
         }
+    }
+    emit.finish(out)
+}
+
+/// Batches the records `tac_unique_spans` keeps into IoSlices and remembers
+/// the key of the last one written.
+struct UniqueEmitter<'a> {
+    data: &'a [u8],
+    slices: Vec<IoSlice<'a>>,
+    last: Option<std::ops::Range<usize>>,
+    /// Key of a glued unterminated record, which is not one slice of `data`.
+    last_joined: Vec<u8>,
+}
 
-        if matches[0].0 > 0 {
-            slices.push(IoSlice::new(&data[..matches[0].0]));
+impl<'a> UniqueEmitter<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        UniqueEmitter {
+            data,
+            slices: Vec::with_capacity(IOSLICE_BATCH_SIZE),
+            last: None,
+            last_joined: Vec::new(),
         }
     }
 
-    write_all_vectored(out, &slices)
+    fn last_key(&self) -> Option<&[u8]> {
+        match &self.last {
+            Some(range) => Some(&self.data[range.clone()]),
+            None if !self.last_joined.is_empty() => Some(&self.last_joined),
+            None => None,
+        }
+    }
+
+    fn push(
+        &mut self,
+        record: std::ops::Range<usize>,
+        key: std::ops::Range<usize>,
+        out: &mut impl Write,
+    ) -> io::Result<()> {
+        if self.last_key() == Some(&self.data[key.clone()]) {
+            return Ok(());
+        }
+        self.last = Some(key);
+        self.write(record, out)
+    }
+
+    /// The first output line when the input lacks a final separator:
+    /// `tail` immediately followed by `record`, compared as one key.
+    /// Nothing precedes it, so it is always written.
+    fn push_joined(
+        &mut self,
+        tail: std::ops::Range<usize>,
+        record: std::ops::Range<usize>,
+        key: std::ops::Range<usize>,
+        out: &mut impl Write,
+    ) -> io::Result<()> {
+        self.last_joined.extend_from_slice(&self.data[tail.clone()]);
+        self.last_joined.extend_from_slice(&self.data[key]);
+        self.write(tail, out)?;
+        self.write(record, out)
+    }
+
+    fn write(&mut self, range: std::ops::Range<usize>, out: &mut impl Write) -> io::Result<()> {
+        self.slices.push(IoSlice::new(&self.data[range]));
+        if self.slices.len() >= IOSLICE_BATCH_SIZE {
+            write_all_vectored(out, &self.slices)?;
+            self.slices.clear();
+        }
+        Ok(())
+    }
+
+    fn finish(self, out: &mut impl Write) -> io::Result<()> {
+        if self.slices.is_empty() {
+            return Ok(());
+        }
+        write_all_vectored(out, &self.slices)
+    }
 }
 
 /// Write all IoSlice entries, handling partial writes.
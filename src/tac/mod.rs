@@ -1,6 +1,8 @@
 mod core;
+mod output;
 
 #[cfg(test)]
 mod tests;
 
 pub use self::core::*;
+pub use self::output::*;
@@ -0,0 +1,194 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufWriter, IoSlice, Write};
+use std::path::{Path, PathBuf};
+
+use super::core::SMALL_INPUT;
+
+/// Attempts at a free temporary name before giving up.
+const TEMP_ATTEMPTS: u32 = 100;
+
+/// A `BufWriter` that starts at `SMALL_INPUT` bytes and grows to its full
+/// capacity the first time a write would not fit. A small input, which
+/// the tac functions emit in one write, never allocates the big buffer;
+/// a large one still gets few, large writes.
+pub struct GrowingBufWriter<W: Write> {
+    inner: Option<BufWriter<W>>,
+    capacity: usize,
+}
+
+impl<W: Write> GrowingBufWriter<W> {
+    pub fn new(inner: W, capacity: usize) -> Self {
+        GrowingBufWriter {
+            inner: Some(BufWriter::with_capacity(SMALL_INPUT.min(capacity), inner)),
+            capacity,
+        }
+    }
+
+    /// The current buffer size.
+    pub fn capacity(&self) -> usize {
+        self.inner.as_ref().map_or(0, BufWriter::capacity)
+    }
+
+    /// The writer, first grown to full size if `len` more bytes would
+    /// overflow the small buffer.
+    fn reserve(&mut self, len: usize) -> io::Result<&mut BufWriter<W>> {
+        let writer = self
+            .inner
+            .take()
+            .expect("writer is only taken while growing");
+        let writer = if writer.capacity() < self.capacity
+            && writer.buffer().len().saturating_add(len) > writer.capacity()
+        {
+            let (inner, pending) = writer.into_parts();
+            let pending = pending.unwrap_or_else(|e| e.into_inner());
+            let mut grown = BufWriter::with_capacity(self.capacity, inner);
+            // Only buffered: there is room for fewer than the small capacity
+            grown.write_all(&pending)?;
+            grown
+        } else {
+            writer
+        };
+        Ok(self.inner.insert(writer))
+    }
+}
+
+impl<W: Write> Write for GrowingBufWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.reserve(buf.len())?.write(buf)
+    }
+
+    fn write_all(&mut self, buf: &[u8]) -> io::Result<()> {
+        self.reserve(buf.len())?.write_all(buf)
+    }
+
+    fn write_vectored(&mut self, bufs: &[IoSlice<'_>]) -> io::Result<usize> {
+        let len = bufs.iter().map(|b| b.len()).sum();
+        self.reserve(len)?.write_vectored(bufs)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.reserve(0)?.flush()
+    }
+}
+
+/// A file that replaces `dest` once complete, for `tac -o`.
+///
+/// Output goes to a temporary file in the destination's directory, which is
+/// renamed over the destination by `commit`. Until then the destination is
+/// untouched, so `tac -o file file` reads the whole input before anything
+/// replaces it, and a reader never sees a half-written file. A replaced
+/// destination keeps its permission bits. If the result is dropped without
+/// being committed, the temporary file is removed.
+pub struct OutputFile {
+    file: File,
+    temp: PathBuf,
+    dest: PathBuf,
+    committed: bool,
+}
+
+impl OutputFile {
+    /// Create the temporary file for `dest`. A symlink is followed, so the
+    /// file it points to is replaced rather than the link.
+    pub fn create(dest: &Path) -> io::Result<Self> {
+        let dest = match fs::symlink_metadata(dest) {
+            Ok(meta) if meta.file_type().is_symlink() => {
+                fs::canonicalize(dest).unwrap_or_else(|_| dest.to_path_buf())
+            }
+            _ => dest.to_path_buf(),
+        };
+        let (file, temp) = create_temp(&dest)?;
+        let output = OutputFile {
+            file,
+            temp,
+            dest,
+            committed: false,
+        };
+        if let Ok(meta) = fs::metadata(&output.dest)
+            && meta.is_file()
+        {
+            output.file.set_permissions(meta.permissions())?;
+        }
+        Ok(output)
+    }
+
+    /// The temporary file to write the output to.
+    pub fn file(&self) -> &File {
+        &self.file
+    }
+
+    /// Move the finished output into place.
+    pub fn commit(self) -> io::Result<()> {
+        self.commit_with(|from, to| fs::rename(from, to))
+    }
+
+    /// `commit`, renaming with `rename`. When the rename cannot cross
+    /// devices, as when the destination is a bind-mounted file, the output
+    /// is copied into the destination instead.
+    pub(crate) fn commit_with(
+        mut self,
+        rename: impl FnOnce(&Path, &Path) -> io::Result<()>,
+    ) -> io::Result<()> {
+        match rename(&self.temp, &self.dest) {
+            Ok(()) => {
+                self.committed = true;
+                Ok(())
+            }
+            Err(e) if is_cross_device(&e) => copy_into(&self.temp, &self.dest),
+            Err(e) => Err(e),
+        }
+    }
+}
+
+impl Drop for OutputFile {
+    fn drop(&mut self) {
+        if !self.committed {
+            let _ = fs::remove_file(&self.temp);
+        }
+    }
+}
+
+/// Create a new file beside `dest`, named after it so a leftover is easy
+/// to trace.
+fn create_temp(dest: &Path) -> io::Result<(File, PathBuf)> {
+    let dir = match dest.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    let name = dest
+        .file_name()
+        .map_or_else(|| "output".into(), |n| n.to_string_lossy());
+    let pid = std::process::id();
+    let mut last_err = None;
+    for attempt in 0..TEMP_ATTEMPTS {
+        let temp = dir.join(format!(".{}.ftac-{}-{}", name, pid, attempt));
+        match OpenOptions::new().write(true).create_new(true).open(&temp) {
+            Ok(file) => return Ok((file, temp)),
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => last_err = Some(e),
+            Err(e) => return Err(e),
+        }
+    }
+    Err(last_err.unwrap())
+}
+
+#[cfg(unix)]
+fn is_cross_device(e: &io::Error) -> bool {
+    e.raw_os_error() == Some(libc::EXDEV)
+}
+
+#[cfg(not(unix))]
+fn is_cross_device(e: &io::Error) -> bool {
+    e.kind() == io::ErrorKind::CrossesDevices
+}
+
+/// Overwrite `dest` with the contents of `temp`, then remove `temp`. The
+/// destination is rewritten in place, so it keeps its inode and mode.
+fn copy_into(temp: &Path, dest: &Path) -> io::Result<()> {
+    let mut from = File::open(temp)?;
+    let mut to = OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(dest)?;
+    io::copy(&mut from, &mut to)?;
+    fs::remove_file(temp)
+}
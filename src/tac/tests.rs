@@ -1,4 +1,5 @@
 use super::*;
+use std::io::{self, Write};
 
 fn run_tac(input: &[u8], sep: u8, before: bool) -> Vec<u8> {
     let mut out = Vec::new();
@@ -116,6 +117,43 @@ fn test_string_separator_before() {
     assert_eq!(run_tac_str(b"aXYbXYc", b"XY", true), b"XYcXYba");
 }
 
+// ---- CRLF preset tests ----
+
+#[test]
+fn test_crlf_separator() {
+    assert_eq!(
+        run_tac_str(b"a\r\nb\r\nc\r\n", CRLF, false),
+        b"c\r\nb\r\na\r\n"
+    );
+    assert_eq!(run_tac_str(b"a\r\nb\r\nc", CRLF, false), b"cb\r\na\r\n");
+}
+
+#[test]
+fn test_crlf_mixed_endings() {
+    // With the CRLF separator a bare LF is ordinary data and stays inside
+    // its record; a lone CR likewise.
+    assert_eq!(
+        run_tac_str(b"one\r\ntwo\nthree\r\nfo\rur\r\n", CRLF, false),
+        b"fo\rur\r\ntwo\nthree\r\none\r\n"
+    );
+}
+
+#[test]
+fn test_detect_crlf() {
+    assert!(detect_crlf(b"a\r\nb\r\n"));
+    assert!(detect_crlf(b"a\r\nb\r\nc\n"));
+    assert!(!detect_crlf(b"a\nb\n"));
+    // A tie is not dominance
+    assert!(!detect_crlf(b"a\r\nb\n"));
+    assert!(!detect_crlf(b""));
+    assert!(!detect_crlf(b"no newline\r"));
+    assert!(!detect_crlf(b"\n"));
+    // Only the leading window is inspected
+    let mut data = b"x\n".repeat(CRLF_DETECT_WINDOW / 2);
+    data.extend_from_slice(&b"y\r\n".repeat(10_000));
+    assert!(!detect_crlf(&data));
+}
+
 // ---- Regex separator tests ----
 
 #[test]
@@ -129,6 +167,247 @@ fn test_regex_separator_newline() {
     assert_eq!(run_tac_regex(b"aaa\nbbb\n", r"\n", false), b"bbb\naaa\n");
 }
 
+#[test]
+fn test_regex_separator_empty_matches() {
+    // Expected output verified against GNU tac 9.1
+    assert_eq!(run_tac_regex(b"abcabc", "x*", false), b"cbacba");
+    assert_eq!(run_tac_regex(b"abcabc", "b*", false), b"cabcab");
+    assert_eq!(run_tac_regex(b"abcabc", "a*", false), b"cbcaba");
+    assert_eq!(run_tac_regex(b"abcabc", ".*", false), b"cbacba");
+    assert_eq!(run_tac_regex(b"abcabc", "z|", false), b"cbacba");
+    assert_eq!(run_tac_regex(b"abcabc", "b*", true), b"cbacba");
+    assert_eq!(run_tac_regex(b"aXbXXc", "X*", false), b"cXbXaX");
+    assert_eq!(run_tac_regex(b"aXbXXc", "X*", true), b"cXXbXa");
+}
+
+#[test]
+fn test_regex_separator_no_match_is_linear() {
+    // One anchored probe per byte: 1MB without a match must stay fast even
+    // for a pattern whose unanchored search would rescan the tail each time.
+    let data = vec![b'a'; 1 << 20];
+    let start = std::time::Instant::now();
+    assert_eq!(run_tac_regex(&data, "a*b", false), data);
+    assert!(start.elapsed() < std::time::Duration::from_secs(10));
+}
+
+/// Random small inputs and simple patterns: reversing must terminate and the
+/// records must tile the input exactly, whatever the pattern matches.
+mod regex_props {
+    use super::*;
+    use proptest::prelude::*;
+    use std::sync::mpsc;
+    use std::time::Duration;
+
+    fn atom() -> impl Strategy<Value = String> {
+        prop_oneof![
+            Just("a".to_string()),
+            Just("b".to_string()),
+            Just("\\n".to_string()),
+            Just("[ab]".to_string()),
+            Just("[^a]".to_string()),
+            Just(".".to_string()),
+            Just("(ab|b)".to_string()),
+            Just("\\b".to_string()),
+            Just("^".to_string()),
+            Just("$".to_string()),
+        ]
+    }
+
+    fn pattern() -> impl Strategy<Value = String> {
+        prop::collection::vec(
+            (
+                atom(),
+                prop_oneof![Just(""), Just("*"), Just("+"), Just("?")],
+            ),
+            1..4,
+        )
+        .prop_map(|parts| parts.into_iter().map(|(a, q)| a + q).collect())
+    }
+
+    fn data() -> impl Strategy<Value = Vec<u8>> {
+        prop::collection::vec(
+            prop_oneof![Just(b'a'), Just(b'b'), Just(b'\n'), Just(b'c')],
+            0..24,
+        )
+    }
+
+    proptest! {
+        #[test]
+        fn regex_records_tile_input(data in data(), pattern in pattern(), before: bool) {
+            let (tx, rx) = mpsc::channel();
+            let (d, p) = (data.clone(), pattern.clone());
+            std::thread::spawn(move || {
+                let re = compile_separator(&p).unwrap();
+                let matches = find_regex_matches_backward(&d, &re);
+                let anchored =
+                    find_regex_matches_backward(&d, &re.without_reverse_dfa());
+                let records = regex_records(&d, &matches, before);
+                let mut out = Vec::new();
+                tac_regex_separator(&d, &p, before, &mut out).unwrap();
+                let _ = tx.send((matches, anchored, records, out));
+            });
+            let (matches, anchored, records, out) = rx
+                .recv_timeout(Duration::from_secs(10))
+                .expect("tac_regex_separator did not terminate");
+
+            // The reverse DFA finds the same matches as anchored probing
+            prop_assert_eq!(&matches, &anchored);
+            prop_assert!(matches.len() <= data.len() + 1);
+            prop_assert!(matches.windows(2).all(|w| w[0].0 < w[1].0));
+
+            // (b) the output is a permutation of the input bytes
+            let (mut sorted_in, mut sorted_out) = (data.clone(), out.clone());
+            sorted_in.sort_unstable();
+            sorted_out.sort_unstable();
+            prop_assert_eq!(sorted_in, sorted_out);
+
+            // (c) un-reversing the records gives back the input
+            prop_assert!(records.iter().all(|&(s, e)| s < e));
+            let mut rebuilt = Vec::new();
+            for &(s, e) in records.iter().rev() {
+                rebuilt.extend_from_slice(&data[s..e]);
+            }
+            prop_assert_eq!(&rebuilt, &data);
+            if !matches.is_empty() {
+                let written: Vec<u8> = records
+                    .iter()
+                    .flat_map(|&(s, e)| data[s..e].iter().copied())
+                    .collect();
+                prop_assert_eq!(written, out);
+            }
+        }
+    }
+}
+
+/// Invariants every separator mode must keep, checked across the byte,
+/// string, regex and owned entry points and the eager and windowed scans.
+mod separator_props {
+    use super::*;
+    use proptest::prelude::*;
+
+    fn sep_byte() -> impl Strategy<Value = u8> {
+        prop_oneof![Just(b'\n'), Just(b','), Just(b'a'), Just(0u8)]
+    }
+
+    /// Short literal separators, self-overlapping ones ("aa", "aba")
+    /// included.
+    fn sep_string() -> impl Strategy<Value = Vec<u8>> {
+        prop::collection::vec(prop_oneof![Just(b'a'), Just(b'b'), Just(b'\n')], 1..4)
+    }
+
+    /// Random bytes around `sep`, shaped as the empty input, only
+    /// separators, no separators, separators at both ends, or anything.
+    fn data_around(sep: Vec<u8>) -> impl Strategy<Value = (Vec<u8>, Vec<u8>)> {
+        let body = prop::collection::vec(
+            prop_oneof![Just(b'a'), Just(b'b'), Just(b'\n'), Just(b','), Just(0u8)],
+            0..48,
+        );
+        (0..5u8, body, 0..6usize).prop_map(move |(shape, body, n)| {
+            let data = match shape {
+                0 => Vec::new(),
+                1 => sep.repeat(n + 1),
+                2 => body.into_iter().filter(|b| !sep.contains(b)).collect(),
+                3 => [&sep[..], &body, &sep].concat(),
+                _ => body,
+            };
+            (sep.clone(), data)
+        })
+    }
+
+    fn tac_owned(data: &[u8], sep: u8, before: bool) -> Vec<u8> {
+        let mut owned = data.to_vec();
+        let mut out = Vec::new();
+        tac_bytes_owned(&mut owned, sep, before, &mut out).unwrap();
+        out
+    }
+
+    fn sorted(data: &[u8]) -> Vec<u8> {
+        let mut v = data.to_vec();
+        v.sort_unstable();
+        v
+    }
+
+    proptest! {
+        #[test]
+        fn byte_paths_agree(
+            (sep, data) in sep_byte().prop_flat_map(|s| data_around(vec![s])),
+            before: bool,
+            window in 1..8usize,
+        ) {
+            let sep = sep[0];
+            let expected = run_tac(&data, sep, before);
+            prop_assert_eq!(sorted(&expected), sorted(&data));
+            prop_assert_eq!(&tac_owned(&data, sep, before), &expected);
+            prop_assert_eq!(&run_tac_str(&data, &[sep], before), &expected);
+
+            let mut eager = Vec::new();
+            tac_bytes_eager(&data, sep, before, &mut eager).unwrap();
+            prop_assert_eq!(&eager, &expected);
+            let mut windowed = Vec::new();
+            tac_bytes_windowed(&data, sep, before, window, |_| {}, &mut windowed).unwrap();
+            prop_assert_eq!(&windowed, &expected);
+        }
+
+        #[test]
+        fn byte_tac_is_an_involution(
+            (sep, data) in sep_byte().prop_flat_map(|s| data_around(vec![s])),
+        ) {
+            let sep = sep[0];
+            // After mode: every record ends in the separator
+            let mut after = data.clone();
+            after.push(sep);
+            prop_assert_eq!(run_tac(&run_tac(&after, sep, false), sep, false), after.clone());
+            prop_assert_eq!(tac_owned(&tac_owned(&after, sep, false), sep, false), after);
+            // Before mode: every record starts with it
+            let before = [&[sep][..], &data].concat();
+            prop_assert_eq!(run_tac(&run_tac(&before, sep, true), sep, true), before);
+        }
+
+        #[test]
+        fn string_paths_agree(
+            (sep, data) in sep_string().prop_flat_map(data_around),
+            before: bool,
+            window in 1..8usize,
+        ) {
+            let expected = run_tac_str(&data, &sep, before);
+            prop_assert_eq!(sorted(&expected), sorted(&data));
+
+            let mut eager = Vec::new();
+            tac_string_eager(&data, &sep, before, &mut eager).unwrap();
+            prop_assert_eq!(&eager, &expected);
+            if !self_overlapping(&sep) {
+                let mut windowed = Vec::new();
+                tac_string_windowed(&data, &sep, before, window, |_| {}, &mut windowed)
+                    .unwrap();
+                prop_assert_eq!(&windowed, &expected);
+            }
+
+            // A pattern without metacharacters matches just the literal
+            let pattern = std::str::from_utf8(&sep).unwrap();
+            prop_assert_eq!(&run_tac_regex(&data, pattern, before), &expected);
+        }
+
+        #[test]
+        fn string_tac_is_an_involution(
+            (sep, data) in sep_string().prop_flat_map(data_around),
+        ) {
+            // With "aa" the records of "aaa" depend on the search direction,
+            // so reversing twice need not give the input back (nor does GNU)
+            prop_assume!(!self_overlapping(&sep));
+            let after = [&data[..], &sep].concat();
+            prop_assert_eq!(
+                run_tac_str(&run_tac_str(&after, &sep, false), &sep, false),
+                after
+            );
+            let before = [&sep[..], &data].concat();
+            prop_assert_eq!(
+                run_tac_str(&run_tac_str(&before, &sep, true), &sep, true),
+                before
+            );
+        }
+    }
+}
+
 // ---- Edge cases ----
 
 #[test]
@@ -160,6 +439,612 @@ fn test_large_input() {
     assert!(result.ends_with(b"line 0\n"));
 }
 
+// ---- Lazy backward windows ----
+
+/// Deterministic buffer over a small alphabet so separators are dense and
+/// records of every length (including empty ones) occur.
+fn random_buffer(len: usize, mut seed: u64) -> Vec<u8> {
+    (0..len)
+        .map(|_| {
+            seed ^= seed << 13;
+            seed ^= seed >> 7;
+            seed ^= seed << 17;
+            b"ab\nxyz,\n"[(seed % 8) as usize]
+        })
+        .collect()
+}
+
+#[test]
+fn test_windowed_matches_eager() {
+    for seed in 1..=40u64 {
+        let data = random_buffer(1 + (seed as usize * 37) % 700, seed);
+        for window in [1, 2, 7, 64, 1000] {
+            for before in [false, true] {
+                for sep in [b'\n', b','] {
+                    let mut eager = Vec::new();
+                    tac_bytes_eager(&data, sep, before, &mut eager).unwrap();
+                    let mut lazy = Vec::new();
+                    tac_bytes_windowed(&data, sep, before, window, |_| {}, &mut lazy).unwrap();
+                    assert_eq!(
+                        lazy, eager,
+                        "seed {} window {} before {}",
+                        seed, window, before
+                    );
+                }
+                for sep in [&b"xy"[..], b"\nz", b"b\na"] {
+                    let mut eager = Vec::new();
+                    tac_string_eager(&data, sep, before, &mut eager).unwrap();
+                    let mut lazy = Vec::new();
+                    tac_string_windowed(&data, sep, before, window, |_| {}, &mut lazy).unwrap();
+                    assert_eq!(lazy, eager, "seed {} window {} sep {:?}", seed, window, sep);
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn test_windowed_record_longer_than_window() {
+    let data = b"short\nthis record is much longer than the window\nend\n";
+    let mut out = Vec::new();
+    tac_bytes_windowed(data, b'\n', false, 4, |_| {}, &mut out).unwrap();
+    assert_eq!(
+        out,
+        b"end\nthis record is much longer than the window\nshort\n"
+    );
+}
+
+#[test]
+fn test_self_overlapping_separator() {
+    assert!(self_overlapping(b"aa"));
+    assert!(self_overlapping(b"abab"));
+    assert!(!self_overlapping(b"\r\n"));
+    assert!(!self_overlapping(b"ab"));
+    assert!(!self_overlapping(b"x"));
+}
+
+#[test]
+fn test_self_overlapping_separator_matches_backward() {
+    // GNU finds separators searching back from the end, as -r does
+    assert_eq!(run_tac_str(b"bbbaaaaabb", b"bb", true), b"bbbbaaaaab");
+    assert_eq!(run_tac_regex(b"bbbaaaaabb", "bb", true), b"bbbbaaaaab");
+    assert_eq!(run_tac_str(b"bb\nbbbbb", b"bb", false), b"bb\nbbbbb");
+    assert_eq!(run_tac_str(b"aaa\nx", b"aa", false), b"\nxaaa");
+}
+
+#[test]
+fn test_windowed_writes_before_scanning_everything() {
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    /// Records how far back the scan had reached at the first write.
+    struct FirstWrite {
+        lowest_scanned: Rc<Cell<usize>>,
+        at_first_write: Option<usize>,
+        bytes: usize,
+    }
+
+    impl Write for FirstWrite {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.at_first_write.get_or_insert(self.lowest_scanned.get());
+            self.bytes += buf.len();
+            Ok(buf.len())
+        }
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    let mut data = Vec::new();
+    for i in 0..10_000 {
+        data.extend_from_slice(format!("line {}\n", i).as_bytes());
+    }
+    let lowest_scanned = Rc::new(Cell::new(data.len()));
+    let probe = lowest_scanned.clone();
+    let mut out = FirstWrite {
+        lowest_scanned,
+        at_first_write: None,
+        bytes: 0,
+    };
+    tac_bytes_windowed(
+        &data,
+        b'\n',
+        false,
+        1024,
+        |start| probe.set(start),
+        &mut out,
+    )
+    .unwrap();
+
+    // Only about one window had been scanned when output started
+    let scanned = data.len() - out.at_first_write.unwrap();
+    assert!(scanned > 0 && scanned < 2048, "scanned {} bytes", scanned);
+    assert_eq!(out.bytes, data.len());
+    assert_eq!(probe.get(), 0);
+}
+
+#[test]
+fn test_windowed_stops_on_write_error() {
+    /// Accepts `limit` bytes, then fails like a closed pipe.
+    struct ClosedPipe {
+        limit: usize,
+    }
+
+    impl Write for ClosedPipe {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            if self.limit == 0 {
+                return Err(io::ErrorKind::BrokenPipe.into());
+            }
+            let n = buf.len().min(self.limit);
+            self.limit -= n;
+            Ok(n)
+        }
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    let data = random_buffer(1 << 16, 7);
+    let mut lowest = data.len();
+    let err = tac_bytes_windowed(
+        &data,
+        b'\n',
+        false,
+        256,
+        |start| lowest = start,
+        &mut ClosedPipe { limit: 100 },
+    )
+    .unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::BrokenPipe);
+    assert!(
+        data.len() - lowest < 1024,
+        "scanned {} bytes",
+        data.len() - lowest
+    );
+}
+
+// ---- --bytes ----
+
+fn naive_reverse(data: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(data.len());
+    for i in (0..data.len()).rev() {
+        out.push(data[i]);
+    }
+    out
+}
+
+fn run_reverse(data: &[u8], chunk: usize) -> Vec<u8> {
+    let mut out = Vec::new();
+    reverse_bytes_chunked(data, chunk, &mut out).unwrap();
+    out
+}
+
+#[test]
+fn test_reverse_bytes_round_trip() {
+    let data: Vec<u8> = (0..=255u8).cycle().take(1001).collect();
+    for len in [0, 1, 2, 7, 8, 9, 16, 999, 1000, 1001] {
+        let data = &data[..len];
+        for chunk in [1, 3, 8, 64, REVERSE_CHUNK] {
+            let once = run_reverse(data, chunk);
+            assert_eq!(once, naive_reverse(data), "len {} chunk {}", len, chunk);
+            assert_eq!(
+                run_reverse(&once, chunk),
+                data,
+                "len {} chunk {}",
+                len,
+                chunk
+            );
+        }
+        let mut owned = data.to_vec();
+        let mut out = Vec::new();
+        reverse_bytes_owned(&mut owned, &mut out).unwrap();
+        assert_eq!(out, naive_reverse(data));
+    }
+}
+
+#[test]
+fn test_reverse_bytes_matches_naive_on_random_data() {
+    let mut seed = 0x9e37_79b9_7f4a_7c15u64;
+    let data: Vec<u8> = (0..REVERSE_CHUNK + 12_345)
+        .map(|_| {
+            seed ^= seed << 13;
+            seed ^= seed >> 7;
+            seed ^= seed << 17;
+            seed as u8
+        })
+        .collect();
+    let expected = naive_reverse(&data);
+
+    // More than one full-size chunk, plus odd chunk sizes
+    let mut out = Vec::new();
+    reverse_bytes(&data, &mut out).unwrap();
+    assert!(out == expected);
+    for chunk in [4093, 65_536] {
+        assert!(run_reverse(&data, chunk) == expected, "chunk {}", chunk);
+    }
+}
+
+// ---- --unique ----
+
+/// `uniq` over already reversed output: drop a line (including its
+/// terminator) equal to the one before it, comparing without the `sep`.
+fn uniq_reference(data: &[u8], sep: &[u8]) -> Vec<u8> {
+    let mut out = Vec::new();
+    let mut last: Option<&[u8]> = None;
+    let mut rest = data;
+    while !rest.is_empty() {
+        let (line, len) = match memchr::memmem::find(rest, sep) {
+            Some(pos) => (&rest[..pos], pos + sep.len()),
+            None => (rest, rest.len()),
+        };
+        if last != Some(line) {
+            out.extend_from_slice(&rest[..len]);
+            last = Some(line);
+        }
+        rest = &rest[len..];
+    }
+    out
+}
+
+const UNIQUE_INPUTS: &[&[u8]] = &[
+    b"",
+    b"a\n",
+    b"a\na\na\nb\nc\n",
+    b"a\nb\nb\nb\nc\n",
+    b"a\nb\nc\nc\nc\n",
+    b"x\nx\ny\ny\nx\nx\n",
+    b"\n\n\na\n\n\n",
+    b"a\nb\nb",
+    b"bb\nb\nb",
+    b"a\nab\nab\na",
+    b"no separator",
+];
+
+#[test]
+fn test_unique_matches_tac_uniq() {
+    for &input in UNIQUE_INPUTS {
+        let mut out = Vec::new();
+        tac_bytes_unique(input, b'\n', false, &mut out).unwrap();
+        let expected = uniq_reference(&run_tac(input, b'\n', false), b"\n");
+        assert_eq!(out, expected, "{:?}", String::from_utf8_lossy(input));
+
+        // Same records through the string and regex paths
+        let comma: Vec<u8> = input
+            .iter()
+            .map(|&b| if b == b'\n' { b',' } else { b })
+            .collect();
+        let mut out = Vec::new();
+        tac_string_unique(&comma, b",", false, &mut out).unwrap();
+        assert_eq!(out, uniq_reference(&run_tac(&comma, b',', false), b","));
+
+        let crlf = input.iter().fold(Vec::new(), |mut v, &b| {
+            if b == b'\n' {
+                v.push(b'\r');
+            }
+            v.push(b);
+            v
+        });
+        let mut out = Vec::new();
+        tac_string_unique(&crlf, CRLF, false, &mut out).unwrap();
+        assert_eq!(out, uniq_reference(&run_tac_str(&crlf, CRLF, false), CRLF));
+
+        let mut out = Vec::new();
+        tac_regex_unique(input, "\n", false, &mut out).unwrap();
+        assert_eq!(out, expected);
+    }
+}
+
+#[test]
+fn test_unique_ignores_varying_regex_separator() {
+    // Records compare without their separator, whatever it matched
+    let mut out = Vec::new();
+    tac_regex_unique(b"a,b;b,c,", "[,;]", false, &mut out).unwrap();
+    assert_eq!(out, b"c,b,a,");
+}
+
+#[test]
+fn test_unique_before_mode_compares_whole_records() {
+    let mut out = Vec::new();
+    tac_bytes_unique(b"x\na\na\nb", b'\n', true, &mut out).unwrap();
+    assert_eq!(out, b"\nb\nax");
+
+    // The first record has no leading separator, so it differs from "\nx"
+    let mut out = Vec::new();
+    tac_string_unique(b"x--x--x", b"--", true, &mut out).unwrap();
+    assert_eq!(out, b"--xx");
+}
+
+// ---- Output buffering is the caller's ----
+
+/// Counts the calls made on it and keeps what was written.
+#[derive(Default)]
+struct CountingWriter {
+    writes: usize,
+    vectored: usize,
+    data: Vec<u8>,
+}
+
+impl Write for CountingWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.writes += 1;
+        self.data.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+    fn write_vectored(&mut self, bufs: &[io::IoSlice<'_>]) -> io::Result<usize> {
+        self.vectored += 1;
+        let mut n = 0;
+        for b in bufs {
+            self.data.extend_from_slice(b);
+            n += b.len();
+        }
+        Ok(n)
+    }
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+fn numbered_lines(n: usize, sep: &str) -> Vec<u8> {
+    let mut data = Vec::new();
+    for i in 0..n {
+        data.extend_from_slice(format!("line {}{}", i, sep).as_bytes());
+    }
+    data
+}
+
+#[test]
+fn test_byte_separator_writes_once() {
+    // The reversed input is built in one buffer and handed over whole, so
+    // a caller's writer sees a single call with nothing held back
+    let data = numbered_lines(3000, "\n");
+    let mut out = CountingWriter::default();
+    tac_bytes(&data, b'\n', false, &mut out).unwrap();
+    assert_eq!((out.writes, out.vectored), (1, 0));
+    assert_eq!(out.data, run_tac(&data, b'\n', false));
+}
+
+#[test]
+fn test_string_and_regex_separators_write_in_batches() {
+    // One vectored call per batch of 1024 records, none per record, once
+    // the input is past the single-write small-input path
+    let data = numbered_lines(8000, "--");
+    assert!(data.len() > SMALL_INPUT);
+    let batches = 8;
+
+    let mut out = CountingWriter::default();
+    tac_string_separator(&data, b"--", false, &mut out).unwrap();
+    assert_eq!((out.writes, out.vectored), (0, batches));
+    assert_eq!(out.data, run_tac_str(&data, b"--", false));
+
+    let mut out = CountingWriter::default();
+    tac_regex_separator(&data, "-+", false, &mut out).unwrap();
+    assert!(
+        out.writes == 0 && out.vectored <= batches,
+        "{} {}",
+        out.writes,
+        out.vectored
+    );
+    assert_eq!(out.data, run_tac_regex(&data, "-+", false));
+}
+
+#[test]
+fn test_core_functions_allocate_only_positions() {
+    // Writing into a Vec costs nothing beyond the Vec itself: no BufWriter
+    // and no per-record allocation, only the positions vector (bounded by
+    // the reallocations of growing one from empty), plus one output or
+    // IoSlice batch buffer. Small inputs allocate only the output buffer.
+    // The regex path also pays a fixed cost for compiling the pattern,
+    // measured on a single record.
+    use crate::common::alloc_count::allocations_in;
+    let growth = |n: usize| {
+        allocations_in(|| {
+            let mut positions = Vec::new();
+            for pos in 0..n {
+                positions.push(pos);
+            }
+            positions
+        })
+        .1
+    };
+    let regex_base = |before: bool| {
+        let mut out = Vec::with_capacity(64);
+        allocations_in(|| tac_regex_separator(b"line 0--", "-+", before, &mut out)).1
+    };
+
+    // Below and above SMALL_INPUT
+    for n in [3000, 20000] {
+        let data = numbered_lines(n, "\n");
+        let str_data = numbered_lines(n, "--");
+        // tac_small keeps no positions at all
+        let positions = if data.len() < SMALL_INPUT {
+            0
+        } else {
+            growth(n)
+        };
+        for before in [false, true] {
+            let mut out = Vec::with_capacity(data.len());
+            let (result, allocations) =
+                allocations_in(|| tac_bytes(&data, b'\n', before, &mut out));
+            result.unwrap();
+            assert!(allocations <= positions + 1, "bytes {n}: {allocations}");
+            assert_eq!(out, run_tac(&data, b'\n', before));
+
+            let mut out = Vec::with_capacity(str_data.len());
+            let (result, allocations) =
+                allocations_in(|| tac_string_separator(&str_data, b"--", before, &mut out));
+            result.unwrap();
+            assert!(allocations <= positions + 1, "string {n}: {allocations}");
+            assert_eq!(out, run_tac_str(&str_data, b"--", before));
+
+            let mut out = Vec::with_capacity(str_data.len());
+            let (result, allocations) =
+                allocations_in(|| tac_regex_separator(&str_data, "-+", before, &mut out));
+            result.unwrap();
+            assert!(
+                allocations <= regex_base(before) + growth(n),
+                "regex {n}: {allocations}"
+            );
+            assert_eq!(out, run_tac_regex(&str_data, "-+", before));
+        }
+    }
+}
+
+#[test]
+fn test_small_inputs_write_once() {
+    // Sizes around the threshold, in records that straddle it, for every
+    // mode: the output matches the forward-scan paths and, below the
+    // threshold, arrives in one call
+    for size in [0, 1, SMALL_INPUT - 1, SMALL_INPUT, SMALL_INPUT + 1] {
+        let mut data = numbered_lines(size / 10 + 1, "--\n");
+        data.truncate(size);
+        for before in [false, true] {
+            let mut expected = Vec::new();
+            if !data.is_empty() {
+                tac_bytes_eager(&data, b'\n', before, &mut expected).unwrap();
+            }
+            let mut out = CountingWriter::default();
+            tac_bytes(&data, b'\n', before, &mut out).unwrap();
+            assert_eq!(out.data, expected, "bytes size {} before {}", size, before);
+            if size < SMALL_INPUT {
+                assert_eq!(out.writes + out.vectored, usize::from(size > 0));
+            }
+
+            let mut expected = Vec::new();
+            tac_string_eager(&data, b"--", before, &mut expected).unwrap();
+            let mut out = CountingWriter::default();
+            tac_string_separator(&data, b"--", before, &mut out).unwrap();
+            assert_eq!(out.data, expected, "string size {} before {}", size, before);
+            if size < SMALL_INPUT {
+                assert_eq!(out.writes + out.vectored, usize::from(size > 0));
+            }
+        }
+    }
+}
+
+#[test]
+fn test_small_input_overlapping_separator() {
+    // The backward search of the small path agrees with the eager one on
+    // separators whose occurrences overlap
+    for data in [&b"aaaaa"[..], b"baaab", b"aaa\nxaa", b"a"] {
+        for before in [false, true] {
+            let mut expected = Vec::new();
+            tac_string_eager(data, b"aa", before, &mut expected).unwrap();
+            assert_eq!(run_tac_str(data, b"aa", before), expected, "{:?}", data);
+        }
+    }
+}
+
+#[test]
+fn test_growing_buf_writer() {
+    // A small output stays in the small buffer and leaves in one write
+    let mut out = GrowingBufWriter::new(CountingWriter::default(), 1 << 20);
+    out.write_all(b"c\nb\na\n").unwrap();
+    assert_eq!(out.capacity(), SMALL_INPUT);
+    out.flush().unwrap();
+    drop(out);
+
+    // Outgrowing it switches to the full buffer, keeping what was pending
+    let mut inner = CountingWriter::default();
+    let mut out = GrowingBufWriter::new(&mut inner, 1 << 20);
+    let chunk = [b'x'; 1000];
+    for _ in 0..100 {
+        out.write_all(&chunk).unwrap();
+    }
+    let n = out
+        .write_vectored(&[io::IoSlice::new(b"y"), io::IoSlice::new(b"z")])
+        .unwrap();
+    assert_eq!(n, 2);
+    assert_eq!(out.capacity(), 1 << 20);
+    out.flush().unwrap();
+    drop(out);
+    assert_eq!((inner.writes, inner.vectored), (1, 0));
+    assert_eq!(inner.data.len(), 100_002);
+    assert!(inner.data.ends_with(b"xyz"));
+}
+
+mod output_file {
+    use super::*;
+    use std::fs;
+    use std::path::Path;
+
+    fn leftovers(dir: &Path) -> Vec<String> {
+        fs::read_dir(dir)
+            .unwrap()
+            .map(|e| e.unwrap().file_name().to_string_lossy().into_owned())
+            .filter(|n| n.contains(".ftac-"))
+            .collect()
+    }
+
+    #[test]
+    fn test_commit_replaces_destination() {
+        let dir = tempfile::tempdir().unwrap();
+        let dest = dir.path().join("out");
+        fs::write(&dest, b"old\n").unwrap();
+
+        let output = OutputFile::create(&dest).unwrap();
+        output.file().write_all(b"new\n").unwrap();
+        assert_eq!(fs::read(&dest).unwrap(), b"old\n");
+        output.commit().unwrap();
+        assert_eq!(fs::read(&dest).unwrap(), b"new\n");
+        assert!(leftovers(dir.path()).is_empty());
+    }
+
+    #[test]
+    fn test_drop_removes_temporary() {
+        let dir = tempfile::tempdir().unwrap();
+        let dest = dir.path().join("out");
+        fs::write(&dest, b"old\n").unwrap();
+
+        let output = OutputFile::create(&dest).unwrap();
+        output.file().write_all(b"partial").unwrap();
+        assert_eq!(leftovers(dir.path()).len(), 1);
+        drop(output);
+        assert!(leftovers(dir.path()).is_empty());
+        assert_eq!(fs::read(&dest).unwrap(), b"old\n");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_cross_device_falls_back_to_copy() {
+        use std::os::unix::fs::{MetadataExt, PermissionsExt};
+
+        let dir = tempfile::tempdir().unwrap();
+        let dest = dir.path().join("out");
+        fs::write(&dest, b"old\n").unwrap();
+        fs::set_permissions(&dest, fs::Permissions::from_mode(0o640)).unwrap();
+        let ino = fs::metadata(&dest).unwrap().ino();
+
+        let output = OutputFile::create(&dest).unwrap();
+        output.file().write_all(b"new\n").unwrap();
+        output
+            .commit_with(|_, _| Err(io::Error::from_raw_os_error(libc::EXDEV)))
+            .unwrap();
+
+        let meta = fs::metadata(&dest).unwrap();
+        assert_eq!(fs::read(&dest).unwrap(), b"new\n");
+        assert_eq!(meta.ino(), ino);
+        assert_eq!(meta.permissions().mode() & 0o777, 0o640);
+        assert!(leftovers(dir.path()).is_empty());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_other_rename_errors_keep_destination() {
+        let dir = tempfile::tempdir().unwrap();
+        let dest = dir.path().join("out");
+        fs::write(&dest, b"old\n").unwrap();
+
+        let output = OutputFile::create(&dest).unwrap();
+        output.file().write_all(b"new\n").unwrap();
+        let err = output
+            .commit_with(|_, _| Err(io::Error::from_raw_os_error(libc::EACCES)))
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::PermissionDenied);
+        assert_eq!(fs::read(&dest).unwrap(), b"old\n");
+        assert!(leftovers(dir.path()).is_empty());
+    }
+}
+
 // ==================== Integration & GNU compatibility tests ====================
 
 #[cfg(test)]
@@ -297,6 +1182,39 @@ mod integration {
         );
     }
 
+    #[test]
+    fn test_error_quotes_file_name() {
+        let (_stdout, stderr, code) = run_ftac(b"", &["/nonexistent dir/bad\nname"]);
+        assert_eq!(code, 1);
+        assert_eq!(
+            String::from_utf8_lossy(&stderr),
+            "tac: '/nonexistent dir/bad'$'\\n''name': No such file or directory\n"
+        );
+    }
+
+    #[test]
+    fn test_error_keeps_utf8_file_name() {
+        let dir = tempfile::tempdir().unwrap();
+        let output = Command::new(bin_path("ftac"))
+            .arg("café-missing")
+            .current_dir(dir.path())
+            .output()
+            .unwrap();
+        assert_eq!(output.status.code(), Some(1));
+        assert_eq!(
+            String::from_utf8_lossy(&output.stderr),
+            "tac: café-missing: No such file or directory\n"
+        );
+    }
+
+    #[test]
+    fn test_invalid_regex_is_not_a_write_error() {
+        let (_stdout, stderr, code) = run_ftac(b"abc", &["-r", "-s", "("]);
+        assert_eq!(code, 1);
+        let stderr = String::from_utf8_lossy(&stderr);
+        assert!(stderr.starts_with("tac: invalid regex '('"), "{}", stderr);
+    }
+
     #[test]
     fn test_empty_file() {
         let dir = tempfile::tempdir().unwrap();
@@ -453,4 +1371,403 @@ mod integration {
             }
         }
     }
+
+    // ---- CRLF preset ----
+
+    #[test]
+    fn test_crlf_round_trip() {
+        let input = b"first\r\nsecond\r\n\r\nfourth\r\n";
+        let (once, _, code) = run_ftac(input, &["--crlf"]);
+        assert_eq!(code, 0);
+        assert_eq!(once, b"fourth\r\n\r\nsecond\r\nfirst\r\n");
+        let (twice, _, code) = run_ftac(&once, &["--crlf"]);
+        assert_eq!(code, 0);
+        assert_eq!(twice, input);
+    }
+
+    #[test]
+    fn test_crlf_mixed_endings() {
+        // --crlf: bare LF stays inside its record.
+        let input = b"one\r\ntwo\nthree\r\n";
+        let (out, _, _) = run_ftac(input, &["--crlf"]);
+        assert_eq!(out, b"two\nthree\r\none\r\n");
+        // Default: LF separates and each CR stays with its own line.
+        let (out, _, _) = run_ftac(input, &[]);
+        assert_eq!(out, b"three\r\ntwo\none\r\n");
+    }
+
+    #[test]
+    fn test_auto_separator() {
+        let (out, _, _) = run_ftac(b"a\r\nb\nc\r\n", &["--auto-separator"]);
+        assert_eq!(out, b"b\nc\r\na\r\n");
+        let (out, _, _) = run_ftac(b"a\nb\r\nc\n", &["--auto-separator"]);
+        assert_eq!(out, b"c\nb\r\na\n");
+        // An explicit separator wins over the preset
+        let (out, _, _) = run_ftac(b"a\r\nb,c\r\n", &["--auto-separator", "-s", ","]);
+        assert_eq!(out, b"c\r\na\r\nb,");
+    }
+
+    #[test]
+    fn test_version_build_line() {
+        // Every binary prints the shared two-line format; the first line is
+        // unchanged for scripts that parse it
+        for (bin, tool) in [
+            ("ftac", "tac"),
+            ("fwc", "wc"),
+            ("funiq", "uniq"),
+            ("fdd", "dd"),
+            ("fdu", "du"),
+            ("fcut", "cut"),
+        ] {
+            let output = Command::new(bin_path(bin))
+                .arg("--version")
+                .output()
+                .unwrap();
+            assert!(output.status.success(), "{}", bin);
+            let stdout = String::from_utf8(output.stdout).unwrap();
+            let lines: Vec<&str> = stdout.lines().collect();
+            assert_eq!(lines.len(), 2, "{}: {:?}", bin, stdout);
+            assert_eq!(
+                lines[0],
+                format!("{} (fcoreutils) {}", tool, env!("CARGO_PKG_VERSION"))
+            );
+            assert!(
+                lines[1].contains(env!("FCOREUTILS_TARGET")),
+                "{}: {}",
+                bin,
+                lines[1]
+            );
+        }
+    }
+
+    #[test]
+    fn test_separator_file_binary_separator() {
+        let dir = tempfile::tempdir().unwrap();
+        let sep_path = dir.path().join("sep");
+        let sep = b"\x00\xffSEP\x00";
+        std::fs::write(&sep_path, sep).unwrap();
+        let arg = format!("--separator-file={}", sep_path.display());
+
+        let input = [&b"one"[..], sep, b"two", sep, b"three", sep].concat();
+        let (out, err, code) = run_ftac(&input, &[&arg]);
+        assert_eq!(code, 0, "{}", String::from_utf8_lossy(&err));
+        assert_eq!(out, [&b"three"[..], sep, b"two", sep, b"one", sep].concat());
+
+        let input = [sep, &b"one"[..], sep, b"two", sep, b"three"].concat();
+        let (out, _, code) = run_ftac(
+            &input,
+            &["-b", "--separator-file", sep_path.to_str().unwrap()],
+        );
+        assert_eq!(code, 0);
+        assert_eq!(out, [sep, &b"three"[..], sep, b"two", sep, b"one"].concat());
+    }
+
+    #[test]
+    fn test_separator_file_errors() {
+        let dir = tempfile::tempdir().unwrap();
+        let empty = dir.path().join("empty");
+        std::fs::write(&empty, b"").unwrap();
+        let big = dir.path().join("big");
+        std::fs::write(&big, vec![b'x'; 64 * 1024 + 1]).unwrap();
+        let ok = dir.path().join("ok");
+        std::fs::write(&ok, b",").unwrap();
+        let missing = dir.path().join("missing");
+
+        let cases = [
+            (
+                vec![format!("--separator-file={}", empty.display())],
+                "separator file is empty",
+            ),
+            (
+                vec![format!("--separator-file={}", big.display())],
+                "separator file is larger than 64 KiB",
+            ),
+            (
+                vec![format!("--separator-file={}", missing.display())],
+                "No such file or directory",
+            ),
+            (
+                vec![
+                    format!("--separator-file={}", ok.display()),
+                    "-s,".to_string(),
+                ],
+                "--separator-file cannot be combined with -s or -r",
+            ),
+            (
+                vec![
+                    "-r".to_string(),
+                    format!("--separator-file={}", ok.display()),
+                ],
+                "--separator-file cannot be combined with -s or -r",
+            ),
+        ];
+        for (args, msg) in cases {
+            let args: Vec<&str> = args.iter().map(String::as_str).collect();
+            let (out, err, code) = run_ftac(b"a,b,", &args);
+            assert_eq!(code, 1, "{:?}", args);
+            assert!(out.is_empty(), "{:?}", args);
+            let err = String::from_utf8_lossy(&err);
+            assert!(
+                err.starts_with("tac: ") && err.contains(msg),
+                "{:?}: {}",
+                args,
+                err
+            );
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_separator_keeps_non_utf8_bytes() {
+        use std::os::unix::ffi::OsStrExt;
+        let input = b"a\xffb\xffc\xff";
+        let output = Command::new(bin_path("ftac"))
+            .arg("-s")
+            .arg(std::ffi::OsStr::from_bytes(b"\xff"))
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .and_then(|mut child| {
+                child.stdin.take().unwrap().write_all(input)?;
+                child.wait_with_output()
+            })
+            .unwrap();
+        assert_eq!(output.stdout, b"c\xffb\xffa\xff");
+    }
+
+    #[test]
+    fn test_bytes_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        for len in [0usize, 1, 7, 8, 12_345] {
+            let input: Vec<u8> = (0..len).map(|i| (i * 31 % 251) as u8).collect();
+            let (once, _, code) = run_ftac(&input, &["--bytes"]);
+            assert_eq!(code, 0);
+            assert_eq!(once, input.iter().rev().copied().collect::<Vec<u8>>());
+            let (twice, _, _) = run_ftac(&once, &["--bytes"]);
+            assert_eq!(twice, input, "len {}", len);
+
+            // A file operand takes the mmap path
+            let file = dir.path().join(format!("in{}", len));
+            std::fs::write(&file, &input).unwrap();
+            let (out, _, code) = run_ftac(b"", &["--bytes", file.to_str().unwrap()]);
+            assert_eq!(code, 0);
+            assert_eq!(out, once, "len {}", len);
+        }
+    }
+
+    #[test]
+    fn test_bytes_excludes_separator_options() {
+        for args in [
+            &["--bytes", "-b"][..],
+            &["-r", "--bytes"],
+            &["--bytes", "-s", ","],
+            &["--bytes", "--crlf"],
+            &["--auto-separator", "--bytes"],
+            &["--unique", "--bytes"],
+        ] {
+            let (out, err, code) = run_ftac(b"abc", args);
+            assert_eq!(code, 1, "{:?}", args);
+            assert!(out.is_empty());
+            assert!(
+                String::from_utf8_lossy(&err).starts_with(
+                    "tac: --bytes cannot be combined with -b, -r, --unique or a separator option\n"
+                ),
+                "{:?}",
+                args
+            );
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_unique_matches_gnu_tac_uniq() {
+        let mut input = Vec::new();
+        for i in 0..2000u32 {
+            // Runs of equal lines at the start, in the middle and at the end
+            let line = match i {
+                0..=9 => "head".to_string(),
+                1990.. => "tail".to_string(),
+                _ => format!("line {}", i / 7),
+            };
+            input.extend_from_slice(line.as_bytes());
+            input.push(b'\n');
+        }
+        for (data, args) in [
+            (&input[..], &["--unique"][..]),
+            (&input[..input.len() - 1], &["--unique"]),
+            (b"a\nb\nb\n\n\nc\nc", &["--unique"]),
+        ] {
+            let (ours, _, code) = run_ftac(data, args);
+            assert_eq!(code, 0);
+            let Ok(gnu) = run_gnu_tac(data, &[]) else {
+                return;
+            };
+            let Ok(mut uniq) = Command::new("uniq")
+                .stdin(Stdio::piped())
+                .stdout(Stdio::piped())
+                .spawn()
+            else {
+                return;
+            };
+            uniq.stdin.take().unwrap().write_all(&gnu.stdout).unwrap();
+            let expected = uniq.wait_with_output().unwrap().stdout;
+            assert!(ours == expected, "{:?}", String::from_utf8_lossy(&ours));
+        }
+    }
+
+    #[test]
+    fn test_perf_summary() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("input.txt");
+        let data: String = (0..100_000).map(|i| format!("line {}\n", i)).collect();
+        std::fs::write(&file, &data).unwrap();
+
+        for args in [&[][..], &["-s", ","]] {
+            let output = Command::new(bin_path("ftac"))
+                .args(args)
+                .arg(&file)
+                .env("COREUTILS_RS_PERF", "1")
+                .output()
+                .unwrap();
+            assert_eq!(output.status.code(), Some(0));
+            let stderr = String::from_utf8(output.stderr).unwrap();
+            let line = stderr.strip_suffix('\n').unwrap();
+            assert!(!line.contains('\n'), "{}", stderr);
+            let fields: Vec<&str> = line.strip_prefix("ftac: ").unwrap().split(' ').collect();
+            let names: Vec<&str> = fields
+                .iter()
+                .map(|f| f.split('=').next().unwrap())
+                .collect();
+            assert_eq!(names, ["input", "scan", "write", "bytes"], "{}", line);
+            for field in &fields[..3] {
+                let ms = field.split('=').nth(1).unwrap().strip_suffix("ms").unwrap();
+                ms.parse::<u64>().unwrap();
+            }
+            assert_eq!(fields[3], "bytes=1.0MiB");
+        }
+
+        // Off unless the variable is exactly 1
+        for value in [None, Some("0")] {
+            let mut cmd = Command::new(bin_path("ftac"));
+            cmd.arg(&file).env_remove("COREUTILS_RS_PERF");
+            if let Some(value) = value {
+                cmd.env("COREUTILS_RS_PERF", value);
+            }
+            let output = cmd.output().unwrap();
+            assert!(output.stderr.is_empty());
+            assert_eq!(output.stdout.len(), data.len());
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_output_in_place() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("input.txt");
+        let data: String = (0..50_000).map(|i| format!("line {}\n", i)).collect();
+        std::fs::write(&file, &data).unwrap();
+        std::fs::set_permissions(&file, std::fs::Permissions::from_mode(0o640)).unwrap();
+
+        for args in [&["-o"][..], &["-s", "\n", "--output"]] {
+            let output = Command::new(bin_path("ftac"))
+                .args(args)
+                .arg(&file)
+                .arg(&file)
+                .output()
+                .unwrap();
+            assert_eq!(output.status.code(), Some(0));
+            assert!(output.stdout.is_empty() && output.stderr.is_empty());
+        }
+        // Reversed twice: back to the original
+        assert_eq!(std::fs::read_to_string(&file).unwrap(), data);
+        let mode = std::fs::metadata(&file).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o640);
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 1);
+
+        let output = Command::new(bin_path("ftac"))
+            .arg(format!("--output={}", file.display()))
+            .arg(&file)
+            .output()
+            .unwrap();
+        assert_eq!(output.status.code(), Some(0));
+        assert_eq!(
+            std::fs::read(&file).unwrap(),
+            data.lines()
+                .rev()
+                .flat_map(|l| [l, "\n"])
+                .collect::<String>()
+                .as_bytes()
+        );
+    }
+
+    #[test]
+    fn test_output_missing_input_still_writes() {
+        let dir = tempfile::tempdir().unwrap();
+        let out = dir.path().join("out.txt");
+        let missing = dir.path().join("missing");
+        let output = Command::new(bin_path("ftac"))
+            .arg("-o")
+            .arg(&out)
+            .arg(&missing)
+            .arg("-")
+            .stdin(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .and_then(|mut child| {
+                child.stdin.take().unwrap().write_all(b"a\nb\n")?;
+                child.wait_with_output()
+            })
+            .unwrap();
+        assert_eq!(output.status.code(), Some(1));
+        assert!(String::from_utf8_lossy(&output.stderr).contains("missing"));
+        assert_eq!(std::fs::read(&out).unwrap(), b"b\na\n");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_stdout_redirected_to_input() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("input.txt");
+        let other = dir.path().join("other.txt");
+        std::fs::write(&other, b"x\ny\n").unwrap();
+
+        // ftac other input.txt > input.txt, and the same with >>
+        for append in [false, true] {
+            std::fs::write(&file, b"one\ntwo\n").unwrap();
+            let stdout = std::fs::OpenOptions::new()
+                .write(true)
+                .truncate(!append)
+                .append(append)
+                .open(&file)
+                .unwrap();
+            let output = Command::new(bin_path("ftac"))
+                .arg(&other)
+                .arg(&file)
+                .stdout(stdout)
+                .output()
+                .unwrap();
+            assert_eq!(output.status.code(), Some(1));
+            assert_eq!(
+                String::from_utf8_lossy(&output.stderr),
+                format!(
+                    "tac: input file '{}' is the same as the output\n",
+                    file.display()
+                )
+            );
+            let expected: &[u8] = if append { b"one\ntwo\n" } else { b"" };
+            assert_eq!(std::fs::read(&file).unwrap(), expected);
+        }
+
+        // Redirecting to a file that is not an input is fine
+        let out = dir.path().join("out.txt");
+        let output = Command::new(bin_path("ftac"))
+            .arg(&other)
+            .stdout(std::fs::File::create(&out).unwrap())
+            .output()
+            .unwrap();
+        assert_eq!(output.status.code(), Some(0));
+        assert_eq!(std::fs::read(&out).unwrap(), b"y\nx\n");
+    }
 }
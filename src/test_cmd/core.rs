@@ -38,13 +38,10 @@ pub fn evaluate(args: &[String]) -> Result<bool, String> {
             }
             return Err(format!("test: {}: binary operator expected", args[1]));
         }
-        4 => {
-            // ! expr expr expr (3-arg expression negated)
-            if args[0] == "!" {
-                return evaluate(&args[1..]).map(|v| !v);
-            }
-            // ( expr ) with binary
-            // Fall through to general parser
+        // ! expr expr expr (3-arg expression negated); other four-argument
+        // forms fall through to the general parser
+        4 if args[0] == "!" => {
+            return evaluate(&args[1..]).map(|v| !v);
         }
         _ => {}
     }
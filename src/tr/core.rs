@@ -4286,11 +4286,9 @@ pub fn delete_mmap(delete_chars: &[u8], data: &[u8], writer: &mut impl Write) ->
         .iter()
         .filter(|&&b| is_member(&member, b))
         .count();
-    let estimated_deletes = if sample_size > 0 {
-        data.len() * sample_deletes / sample_size
-    } else {
-        data.len()
-    };
+    let estimated_deletes = (data.len() * sample_deletes)
+        .checked_div(sample_size)
+        .unwrap_or(data.len());
 
     if estimated_deletes < MAX_IOV / 2 {
         return delete_bitset_zerocopy(data, &member, writer);
@@ -4350,11 +4348,9 @@ fn delete_range_mmap(data: &[u8], writer: &mut impl Write, lo: u8, hi: u8) -> io
     // if estimated_deletes > MAX_IOV/2, the writev overhead from multiple syscalls
     // exceeds the compact approach cost. Only use zero-copy when all gaps fit in
     // a single writev call.
-    let estimated_deletes = if sample_size > 0 {
-        data.len() * sample_deletes / sample_size
-    } else {
-        data.len()
-    };
+    let estimated_deletes = (data.len() * sample_deletes)
+        .checked_div(sample_size)
+        .unwrap_or(data.len());
     if estimated_deletes < MAX_IOV / 2 {
         return delete_range_mmap_zerocopy(data, writer, lo, hi);
     }
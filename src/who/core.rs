@@ -4,6 +4,8 @@
 /// boot time, dead processes, run level, etc.
 use std::ffi::CStr;
 use std::fmt::Write as FmtWrite;
+use std::io;
+use std::path::Path;
 
 // utmpx entry type constants (from utmpx.h)
 const RUN_LVL: i16 = 1;
@@ -25,6 +27,76 @@ pub struct UtmpxEntry {
     pub ut_user: String,
     pub ut_host: String,
     pub ut_tv_sec: i64,
+    /// A text field was not valid UTF-8 and was decoded lossily.
+    pub encoding_lossy: bool,
+}
+
+/// Where glibc keeps the login records (`_PATH_UTMP`).
+pub const UTMP_FILE: &str = "/var/run/utmp";
+
+/// Login records, and why there are none when the source could not be read.
+#[derive(Debug, Default)]
+pub struct LoginRecords {
+    pub entries: Vec<UtmpxEntry>,
+    /// No records were read because the records file is missing or
+    /// unreadable.
+    pub unavailable: Option<io::Error>,
+}
+
+/// Read the login records in `file`, or the system's when `file` is None.
+///
+/// The system records are read straight from `UTMP_FILE` when it exists,
+/// so every libc gives the same answer (musl's getutxent is a stub that
+/// returns nothing); otherwise this falls back to getutxent. Not
+/// thread-safe, as for `read_utmpx`.
+pub fn read_login_records(file: Option<&Path>) -> LoginRecords {
+    match read_utmp_file(file.unwrap_or(Path::new(UTMP_FILE))) {
+        Ok(entries) => LoginRecords {
+            entries,
+            unavailable: None,
+        },
+        Err(e) if file.is_some() => LoginRecords {
+            entries: Vec::new(),
+            unavailable: Some(e),
+        },
+        Err(e) => {
+            let entries = read_utmpx();
+            let unavailable = if entries.is_empty() && e.kind() != io::ErrorKind::Unsupported {
+                Some(e)
+            } else {
+                None
+            };
+            LoginRecords {
+                entries,
+                unavailable,
+            }
+        }
+    }
+}
+
+/// Decode a utmp file: a packed array of `struct utmpx`.
+#[cfg(target_os = "linux")]
+pub fn read_utmp_file(path: &Path) -> io::Result<Vec<UtmpxEntry>> {
+    let data = std::fs::read(path)?;
+    let size = std::mem::size_of::<libc::utmpx>();
+    Ok(data
+        .chunks_exact(size)
+        .map(|rec| {
+            // SAFETY: utmpx is plain data and the chunk is exactly its size.
+            let e: libc::utmpx = unsafe { std::ptr::read_unaligned(rec.as_ptr().cast()) };
+            decode_utmpx(&e)
+        })
+        .collect())
+}
+
+/// The utmpx layout is only pinned down on Linux; elsewhere the system
+/// database is read through getutxent alone.
+#[cfg(not(target_os = "linux"))]
+pub fn read_utmp_file(_path: &Path) -> io::Result<Vec<UtmpxEntry>> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "reading utmp files is not supported on this platform",
+    ))
 }
 
 /// Read all utmpx entries from the system database.
@@ -42,24 +114,7 @@ pub fn read_utmpx() -> Vec<UtmpxEntry> {
             if entry.is_null() {
                 break;
             }
-            let e = &*entry;
-
-            let user = cstr_from_buf(&e.ut_user);
-            let line = cstr_from_buf(&e.ut_line);
-            let host = cstr_from_buf(&e.ut_host);
-            let id = cstr_from_buf(&e.ut_id);
-
-            let tv_sec = e.ut_tv.tv_sec as i64;
-
-            entries.push(UtmpxEntry {
-                ut_type: e.ut_type as i16,
-                ut_pid: e.ut_pid,
-                ut_line: line,
-                ut_id: id,
-                ut_user: user,
-                ut_host: host,
-                ut_tv_sec: tv_sec,
-            });
+            entries.push(decode_utmpx(&*entry));
         }
         libc::endutxent();
     }
@@ -67,12 +122,38 @@ pub fn read_utmpx() -> Vec<UtmpxEntry> {
     entries
 }
 
-/// Extract a Rust String from a fixed-size C char buffer.
-unsafe fn cstr_from_buf(buf: &[libc::c_char]) -> String {
+fn decode_utmpx(e: &libc::utmpx) -> UtmpxEntry {
+    let mut lossy = false;
+    let user = cstr_from_buf(&e.ut_user, &mut lossy);
+    let line = cstr_from_buf(&e.ut_line, &mut lossy);
+    let host = cstr_from_buf(&e.ut_host, &mut lossy);
+    let id = cstr_from_buf(&e.ut_id, &mut lossy);
+
+    UtmpxEntry {
+        ut_type: e.ut_type as i16,
+        ut_pid: e.ut_pid,
+        ut_line: line,
+        ut_id: id,
+        ut_user: user,
+        ut_host: host,
+        ut_tv_sec: e.ut_tv.tv_sec as i64,
+        encoding_lossy: lossy,
+    }
+}
+
+/// Extract a Rust String from a fixed-size C char buffer, setting `lossy`
+/// if it is not valid UTF-8.
+fn cstr_from_buf(buf: &[libc::c_char], lossy: &mut bool) -> String {
     // Find the first NUL byte or use the entire buffer length
     let len = buf.iter().position(|&c| c == 0).unwrap_or(buf.len());
     let bytes: Vec<u8> = buf[..len].iter().map(|&c| c as u8).collect();
-    String::from_utf8_lossy(&bytes).into_owned()
+    match String::from_utf8(bytes) {
+        Ok(s) => s,
+        Err(e) => {
+            *lossy = true;
+            String::from_utf8_lossy(e.as_bytes()).into_owned()
+        }
+    }
 }
 
 /// Configuration for the who command, derived from CLI flags.
@@ -388,20 +469,19 @@ pub fn format_heading(config: &WhoConfig) -> String {
     out
 }
 
-/// Run the who command and return the formatted output.
-pub fn run_who(config: &WhoConfig) -> String {
-    let entries = read_utmpx();
+/// Run the who command over `entries` and return the formatted output.
+pub fn run_who(config: &WhoConfig, entries: &[UtmpxEntry]) -> String {
     let mut output = String::new();
 
     if config.show_count {
-        return format_count(&entries);
+        return format_count(entries);
     }
 
     if config.show_heading {
         let _ = writeln!(output, "{}", format_heading(config));
     }
 
-    for entry in &entries {
+    for entry in entries {
         if should_show(entry, config) {
             let _ = writeln!(output, "{}", format_entry(entry, config));
         }
@@ -188,6 +188,7 @@ fn test_format_entry_user_process() {
         ut_user: "testuser".to_string(),
         ut_host: "10.0.0.1".to_string(),
         ut_tv_sec: 1_704_067_200,
+        encoding_lossy: false,
     };
     let config = WhoConfig::default();
     let line = format_entry(&entry, &config);
@@ -206,6 +207,7 @@ fn test_format_entry_boot_time() {
         ut_user: "reboot".to_string(),
         ut_host: String::new(),
         ut_tv_sec: 1_704_067_200,
+        encoding_lossy: false,
     };
     let config = WhoConfig {
         show_boot: true,
@@ -237,6 +239,7 @@ fn test_format_count_with_users() {
             ut_user: "alice".to_string(),
             ut_host: String::new(),
             ut_tv_sec: 1_704_067_200,
+            encoding_lossy: false,
         },
         UtmpxEntry {
             ut_type: 7,
@@ -246,6 +249,7 @@ fn test_format_count_with_users() {
             ut_user: "bob".to_string(),
             ut_host: String::new(),
             ut_tv_sec: 1_704_067_200,
+            encoding_lossy: false,
         },
     ];
     let result = format_count(&entries);
@@ -261,3 +265,53 @@ fn test_read_utmpx_returns_vec() {
     // We can't assert specific content, but we can check it's a valid vec
     let _ = entries.len();
 }
+
+#[cfg(target_os = "linux")]
+#[test]
+fn test_read_utmp_file_decodes_records() {
+    let mut rec: libc::utmpx = unsafe { std::mem::zeroed() };
+    rec.ut_type = libc::USER_PROCESS;
+    rec.ut_pid = 42;
+    rec.ut_tv.tv_sec = 1_700_000_000;
+    for (dst, src) in rec.ut_user.iter_mut().zip(b"alice") {
+        *dst = *src as libc::c_char;
+    }
+    for (dst, src) in rec.ut_line.iter_mut().zip(b"pts/3") {
+        *dst = *src as libc::c_char;
+    }
+    let size = std::mem::size_of::<libc::utmpx>();
+    let bytes =
+        unsafe { std::slice::from_raw_parts((&rec as *const libc::utmpx).cast::<u8>(), size) };
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("utmp");
+    // A trailing partial record is ignored
+    std::fs::write(&path, [bytes, bytes, &bytes[..10]].concat()).unwrap();
+
+    let records = read_login_records(Some(&path));
+    assert!(records.unavailable.is_none());
+    assert_eq!(records.entries.len(), 2);
+    let e = &records.entries[0];
+    assert_eq!(e.ut_type, libc::USER_PROCESS);
+    assert_eq!(e.ut_pid, 42);
+    assert_eq!(e.ut_user, "alice");
+    assert_eq!(e.ut_line, "pts/3");
+    assert_eq!(e.ut_tv_sec, 1_700_000_000);
+}
+
+#[test]
+fn test_read_login_records_missing_file() {
+    let records = read_login_records(Some(std::path::Path::new("/nonexistent/utmp")));
+    assert!(records.entries.is_empty());
+    assert!(records.unavailable.is_some());
+}
+
+#[test]
+fn test_who_missing_records_file() {
+    let output = cmd().arg("/nonexistent/utmp").output().unwrap();
+    assert_eq!(output.status.code(), Some(0));
+    assert!(output.stdout.is_empty());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stderr),
+        "who: cannot read user login records: No such file or directory\n"
+    );
+}
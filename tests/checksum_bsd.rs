@@ -0,0 +1,118 @@
+//! `--bsd` output of the digest tools, checked by the tools' own --check
+//! and, where it is installed, by shasum -c.
+
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+/// Each tool with its BSD counterpart's tag and shasum's -a value, if
+/// shasum handles the algorithm.
+const TOOLS: &[(&str, &str, Option<&str>)] = &[
+    (env!("CARGO_BIN_EXE_fmd5sum"), "MD5", None),
+    (env!("CARGO_BIN_EXE_fsha1sum"), "SHA1", Some("1")),
+    (env!("CARGO_BIN_EXE_fsha224sum"), "SHA224", Some("224")),
+    (env!("CARGO_BIN_EXE_fsha256sum"), "SHA256", Some("256")),
+    (env!("CARGO_BIN_EXE_fsha384sum"), "SHA384", Some("384")),
+    (env!("CARGO_BIN_EXE_fsha512sum"), "SHA512", Some("512")),
+];
+
+/// Run `bin` in `dir`, feeding `input` on stdin.
+fn run(dir: &Path, bin: &str, args: &[&str], input: &[u8]) -> std::process::Output {
+    let mut child = Command::new(bin)
+        .current_dir(dir)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    use std::io::Write;
+    child.stdin.take().unwrap().write_all(input).unwrap();
+    child.wait_with_output().unwrap()
+}
+
+fn sample_dir() -> tempfile::TempDir {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join("a"), b"hello\n").unwrap();
+    std::fs::write(dir.path().join("b c"), b"").unwrap();
+    dir
+}
+
+#[test]
+fn bsd_lines_have_bsd_tags() {
+    let dir = sample_dir();
+    for &(bin, tag, _) in TOOLS {
+        let out = run(dir.path(), bin, &["--bsd", "a", "b c"], b"");
+        assert!(out.status.success());
+        let text = String::from_utf8(out.stdout).unwrap();
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines.len(), 2, "{}", text);
+        for (line, name) in lines.iter().zip(["a", "b c"]) {
+            let digest = line
+                .strip_prefix(&format!("{} ({}) = ", tag, name))
+                .unwrap_or_else(|| panic!("{:?}", line));
+            assert!(
+                digest
+                    .bytes()
+                    .all(|b| b.is_ascii_hexdigit() && !b.is_ascii_uppercase())
+            );
+        }
+    }
+}
+
+#[test]
+fn bsd_lines_verify_with_check() {
+    let dir = sample_dir();
+    for &(bin, _, _) in TOOLS {
+        let sums = run(dir.path(), bin, &["--bsd", "a", "b c"], b"").stdout;
+        let out = run(dir.path(), bin, &["-c"], &sums);
+        assert!(
+            out.status.success(),
+            "{}",
+            String::from_utf8_lossy(&out.stderr)
+        );
+        assert_eq!(out.stdout, b"a: OK\nb c: OK\n");
+    }
+}
+
+#[test]
+fn bsd_lines_verify_with_shasum() {
+    let dir = sample_dir();
+    for &(bin, _, alg) in TOOLS {
+        let Some(alg) = alg else { continue };
+        let sums = run(dir.path(), bin, &["--bsd", "a", "b c"], b"").stdout;
+        let Ok(out) = Command::new("shasum")
+            .current_dir(dir.path())
+            .args(["-a", alg, "-c"])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .and_then(|mut child| {
+                use std::io::Write;
+                child.stdin.take().unwrap().write_all(&sums)?;
+                child.wait_with_output()
+            })
+        else {
+            return;
+        };
+        assert!(out.status.success(), "shasum -a {} -c", alg);
+        assert_eq!(out.stdout, b"a: OK\nb c: OK\n");
+    }
+}
+
+#[test]
+fn bsd_rejects_check_and_zero() {
+    let dir = sample_dir();
+    let (bin, _, _) = TOOLS[0];
+    let out = run(dir.path(), bin, &["--bsd", "-c", "a"], b"");
+    assert_eq!(out.status.code(), Some(1));
+    assert!(
+        String::from_utf8_lossy(&out.stderr)
+            .starts_with("md5sum: the --bsd option is meaningless when verifying checksums\n")
+    );
+    let out = run(dir.path(), bin, &["--bsd", "-z", "a"], b"");
+    assert_eq!(out.status.code(), Some(1));
+    assert!(
+        String::from_utf8_lossy(&out.stderr)
+            .starts_with("md5sum: the --bsd option cannot be combined with --zero\n")
+    );
+}
@@ -0,0 +1,473 @@
+//! Conformance vectors and round-trip properties for the base64 family:
+//! base64, base64url, base32, base32hex, base16, base2 and PEM armor.
+//!
+//! The vectors come from RFC 4648 section 10 and from GNU coreutils 9.1
+//! (`basenc`) output. Base64 and PEM are library code and are driven
+//! through both the slice and the streaming APIs, the latter fed through
+//! readers that return a few bytes at a time. The other encodings live in
+//! their binaries, which are run on a file operand and on stdin.
+
+use std::io::{self, Read, Write};
+use std::process::{Command, Stdio};
+
+use coreutils_rs::base64::core as b64;
+use proptest::prelude::*;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Encoding {
+    Base64,
+    Base64Url,
+    Base32,
+    Base32Hex,
+    Base16,
+    Base2Msbf,
+    Base2Lsbf,
+}
+
+use Encoding::*;
+
+impl Encoding {
+    fn basenc_flag(self) -> &'static str {
+        match self {
+            Base64 => "--base64",
+            Base64Url => "--base64url",
+            Base32 => "--base32",
+            Base32Hex => "--base32hex",
+            Base16 => "--base16",
+            Base2Msbf => "--base2msbf",
+            Base2Lsbf => "--base2lsbf",
+        }
+    }
+
+    /// The dedicated binary for this encoding, besides fbasenc.
+    fn tool(self) -> Option<&'static str> {
+        match self {
+            Base64 => Some(env!("CARGO_BIN_EXE_fbase64")),
+            Base32 => Some(env!("CARGO_BIN_EXE_fbase32")),
+            _ => None,
+        }
+    }
+}
+
+struct Vector {
+    input: &'static [u8],
+    encoding: Encoding,
+    wrap: usize,
+    expected: &'static str,
+}
+
+const fn v(
+    input: &'static [u8],
+    encoding: Encoding,
+    wrap: usize,
+    expected: &'static str,
+) -> Vector {
+    Vector {
+        input,
+        encoding,
+        wrap,
+        expected,
+    }
+}
+
+const VECTORS: &[Vector] = &[
+    // RFC 4648 section 10
+    v(b"", Base64, 0, ""),
+    v(b"f", Base64, 0, "Zg=="),
+    v(b"fo", Base64, 0, "Zm8="),
+    v(b"foo", Base64, 0, "Zm9v"),
+    v(b"foob", Base64, 0, "Zm9vYg=="),
+    v(b"fooba", Base64, 0, "Zm9vYmE="),
+    v(b"foobar", Base64, 0, "Zm9vYmFy"),
+    v(b"", Base32, 0, ""),
+    v(b"f", Base32, 0, "MY======"),
+    v(b"fo", Base32, 0, "MZXQ===="),
+    v(b"foo", Base32, 0, "MZXW6==="),
+    v(b"foob", Base32, 0, "MZXW6YQ="),
+    v(b"fooba", Base32, 0, "MZXW6YTB"),
+    v(b"foobar", Base32, 0, "MZXW6YTBOI======"),
+    v(b"", Base32Hex, 0, ""),
+    v(b"f", Base32Hex, 0, "CO======"),
+    v(b"fo", Base32Hex, 0, "CPNG===="),
+    v(b"foo", Base32Hex, 0, "CPNMU==="),
+    v(b"foob", Base32Hex, 0, "CPNMUOG="),
+    v(b"fooba", Base32Hex, 0, "CPNMUOJ1"),
+    v(b"foobar", Base32Hex, 0, "CPNMUOJ1E8======"),
+    v(b"", Base16, 0, ""),
+    v(b"f", Base16, 0, "66"),
+    v(b"fo", Base16, 0, "666F"),
+    v(b"foo", Base16, 0, "666F6F"),
+    v(b"foob", Base16, 0, "666F6F62"),
+    v(b"fooba", Base16, 0, "666F6F6261"),
+    v(b"foobar", Base16, 0, "666F6F626172"),
+    // GNU basenc: the default wrap ends the last line with a newline,
+    // empty input produces nothing at all
+    v(b"", Base64, 76, ""),
+    v(b"f", Base64, 76, "Zg==\n"),
+    v(b"foobar", Base32, 76, "MZXW6YTBOI======\n"),
+    v(b"f", Base2Msbf, 76, "01100110\n"),
+    v(b"\xfb\xff\xbf", Base64, 0, "+/+/"),
+    v(b"\xfb\xff\xbf", Base64Url, 0, "-_-_"),
+    // GNU basenc: wrapping inside groups and inside the padding
+    v(b"foobar", Base64, 1, "Z\nm\n9\nv\nY\nm\nF\ny\n"),
+    v(b"f", Base64, 1, "Z\ng\n=\n=\n"),
+    v(b"foobar", Base64, 4, "Zm9v\nYmFy\n"),
+    v(b"foobar", Base64, 5, "Zm9vY\nmFy\n"),
+    v(b"Hello, World!", Base64, 5, "SGVsb\nG8sIF\ndvcmx\nkIQ==\n"),
+    v(
+        b"Hello, World!",
+        Base64Url,
+        4,
+        "SGVs\nbG8s\nIFdv\ncmxk\nIQ==\n",
+    ),
+    v(b"f", Base32, 4, "MY==\n====\n"),
+    v(b"f", Base32, 5, "MY===\n===\n"),
+    v(b"foobar", Base32, 5, "MZXW6\nYTBOI\n=====\n=\n"),
+    v(
+        b"Hello, World!",
+        Base32,
+        4,
+        "JBSW\nY3DP\nFQQF\nO33S\nNRSC\nC===\n",
+    ),
+    v(b"foobar", Base32Hex, 4, "CPNM\nUOJ1\nE8==\n====\n"),
+    v(
+        b"Hello, World!",
+        Base32Hex,
+        5,
+        "91IMO\nR3F5G\nG5ERR\nIDHI2\n2===\n",
+    ),
+    v(b"foobar", Base16, 5, "666F6\nF6261\n72\n"),
+    v(
+        b"Hello, World!",
+        Base16,
+        4,
+        "4865\n6C6C\n6F2C\n2057\n6F72\n6C64\n21\n",
+    ),
+    v(
+        b"foobar",
+        Base2Msbf,
+        0,
+        "011001100110111101101111011000100110000101110010",
+    ),
+    v(
+        b"foobar",
+        Base2Lsbf,
+        0,
+        "011001101111011011110110010001101000011001001110",
+    ),
+    v(b"f", Base2Msbf, 5, "01100\n110\n"),
+    v(
+        b"foobar",
+        Base2Lsbf,
+        5,
+        "01100\n11011\n11011\n01111\n01100\n10001\n10100\n00110\n01001\n110\n",
+    ),
+    v(
+        b"Hello, World!",
+        Base2Msbf,
+        76,
+        "0100100001100101011011000110110001101111001011000010000001010111011011110111\n\
+         0010011011000110010000100001\n",
+    ),
+];
+
+/// Read sizes the streaming paths are fed with: single bytes, splits inside
+/// a 3-byte group and a 4-char quantum, around a 76-column line, and just
+/// past a page.
+const CHUNK_SIZES: [usize; 9] = [1, 2, 3, 4, 5, 7, 76, 77, 4097];
+
+/// Wrap widths the round-trip properties are checked at.
+const WRAPS: [usize; 5] = [0, 1, 4, 64, 76];
+
+/// A reader returning at most `chunk` bytes per `read`.
+struct Chunked<'a> {
+    data: &'a [u8],
+    chunk: usize,
+}
+
+impl Read for Chunked<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.data.len().min(self.chunk).min(buf.len());
+        buf[..n].copy_from_slice(&self.data[..n]);
+        self.data = &self.data[n..];
+        Ok(n)
+    }
+}
+
+/// Run `bin` with `args`, feeding `input` on stdin; returns stdout.
+fn run(bin: &str, args: &[&str], input: &[u8]) -> Vec<u8> {
+    let mut child = Command::new(bin)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    let mut stdin = child.stdin.take().unwrap();
+    let input = input.to_vec();
+    let writer = std::thread::spawn(move || stdin.write_all(&input));
+    let output = child.wait_with_output().unwrap();
+    writer.join().unwrap().unwrap();
+    assert!(
+        output.status.success(),
+        "{} {:?}: {}",
+        bin,
+        args,
+        String::from_utf8_lossy(&output.stderr)
+    );
+    output.stdout
+}
+
+/// Run `bin` on a file holding `input`.
+fn run_file(bin: &str, args: &[&str], input: &[u8]) -> Vec<u8> {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("input");
+    std::fs::write(&path, input).unwrap();
+    let mut args = args.to_vec();
+    args.push(path.to_str().unwrap());
+    run(bin, &args, b"")
+}
+
+/// Binaries implementing `enc`, with the arguments selecting it.
+fn binaries(enc: Encoding) -> Vec<(&'static str, Vec<&'static str>)> {
+    let mut bins = vec![(env!("CARGO_BIN_EXE_fbasenc"), vec![enc.basenc_flag()])];
+    if let Some(tool) = enc.tool() {
+        bins.push((tool, vec![]));
+    }
+    bins
+}
+
+/// Encode `data` every way `enc` can be encoded, labelling each result.
+/// With `stdin_only` the binaries are not also run on a file.
+fn encode_all(enc: Encoding, data: &[u8], wrap: usize, stdin_only: bool) -> Vec<(String, Vec<u8>)> {
+    let mut results = Vec::new();
+    if enc == Base64 {
+        let mut out = Vec::new();
+        b64::encode_to_writer(data, wrap, false, &mut out).unwrap();
+        results.push(("encode_to_writer".to_string(), out));
+        for threads in [1, 4] {
+            let mut out = Vec::new();
+            b64::encode_to_writer_threads(data, wrap, false, threads, &mut out).unwrap();
+            results.push((format!("encode_to_writer_threads({})", threads), out));
+        }
+        for chunk in CHUNK_SIZES {
+            let mut out = Vec::new();
+            b64::encode_stream(&mut Chunked { data, chunk }, wrap, false, &mut out).unwrap();
+            results.push((format!("encode_stream, reads of {}", chunk), out));
+        }
+    }
+    let wrap_arg = format!("--wrap={}", wrap);
+    for (bin, mut args) in binaries(enc) {
+        args.push(&wrap_arg);
+        results.push((format!("{} {:?} <stdin", bin, args), run(bin, &args, data)));
+        if !stdin_only {
+            results.push((
+                format!("{} {:?} FILE", bin, args),
+                run_file(bin, &args, data),
+            ));
+        }
+    }
+    results
+}
+
+/// Decode `text` every way `enc` can be decoded, labelling each result.
+fn decode_all(enc: Encoding, text: &[u8], stdin_only: bool) -> Vec<(String, Vec<u8>)> {
+    let mut results = Vec::new();
+    if enc == Base64 {
+        let mut out = Vec::new();
+        b64::decode_to_writer(text, false, &mut out).unwrap();
+        results.push(("decode_to_writer".to_string(), out));
+        let mut out = Vec::new();
+        b64::decode_owned(&mut text.to_vec(), false, &mut out).unwrap();
+        results.push(("decode_owned".to_string(), out));
+        let mut out = Vec::new();
+        b64::decode_mmap_inplace(&mut text.to_vec(), false, &mut out).unwrap();
+        results.push(("decode_mmap_inplace".to_string(), out));
+        let mut out = Vec::new();
+        b64::decode_multi_to_writer(text, false, &mut out).unwrap();
+        results.push(("decode_multi_to_writer".to_string(), out));
+        for chunk in CHUNK_SIZES {
+            let mut out = Vec::new();
+            b64::decode_stream(&mut Chunked { data: text, chunk }, false, &mut out).unwrap();
+            results.push((format!("decode_stream, reads of {}", chunk), out));
+            let mut out = Vec::new();
+            b64::decode_multi_stream(&mut Chunked { data: text, chunk }, false, &mut out).unwrap();
+            results.push((format!("decode_multi_stream, reads of {}", chunk), out));
+        }
+    }
+    for (bin, mut args) in binaries(enc) {
+        args.push("--decode");
+        results.push((format!("{} {:?} <stdin", bin, args), run(bin, &args, text)));
+        if !stdin_only {
+            results.push((
+                format!("{} {:?} FILE", bin, args),
+                run_file(bin, &args, text),
+            ));
+        }
+    }
+    results
+}
+
+/// GNU's line layout: every line is `wrap` wide except a shorter last one,
+/// and the output ends in a newline exactly when it is wrapped and not
+/// empty. Unwrapped output has no newline at all.
+fn check_layout(encoded: &[u8], wrap: usize) -> Result<(), String> {
+    if wrap == 0 || encoded.is_empty() {
+        if encoded.contains(&b'\n') {
+            return Err(format!("newline in unwrapped output {:?}", encoded));
+        }
+        return Ok(());
+    }
+    let Some(body) = encoded.strip_suffix(b"\n") else {
+        return Err("wrapped output does not end in a newline".to_string());
+    };
+    let lines: Vec<&[u8]> = body.split(|&b| b == b'\n').collect();
+    for (i, line) in lines.iter().enumerate() {
+        let last = i + 1 == lines.len();
+        if line.is_empty() || line.len() > wrap || (!last && line.len() != wrap) {
+            return Err(format!(
+                "line {} is {} wide, wrap {}",
+                i + 1,
+                line.len(),
+                wrap
+            ));
+        }
+    }
+    Ok(())
+}
+
+#[test]
+fn vectors_encode() {
+    for vector in VECTORS {
+        for (how, out) in encode_all(vector.encoding, vector.input, vector.wrap, false) {
+            assert_eq!(
+                String::from_utf8_lossy(&out),
+                vector.expected,
+                "{:?} of {:?} at wrap {} via {}",
+                vector.encoding,
+                vector.input,
+                vector.wrap,
+                how
+            );
+        }
+    }
+}
+
+#[test]
+fn vectors_decode() {
+    for vector in VECTORS {
+        for (how, out) in decode_all(vector.encoding, vector.expected.as_bytes(), false) {
+            assert_eq!(
+                out, vector.input,
+                "{:?} of {:?} via {}",
+                vector.encoding, vector.expected, how
+            );
+        }
+    }
+}
+
+#[test]
+fn vectors_follow_gnu_layout() {
+    for vector in VECTORS {
+        check_layout(vector.expected.as_bytes(), vector.wrap).unwrap();
+    }
+}
+
+#[test]
+fn pem_vector() {
+    let mut out = Vec::new();
+    b64::encode_pem_to_writer(b"foobar", "TEST", &mut out).unwrap();
+    assert_eq!(out, b"-----BEGIN TEST-----\nZm9vYmFy\n-----END TEST-----\n");
+    for chunk in CHUNK_SIZES {
+        let mut streamed = Vec::new();
+        let mut reader = Chunked {
+            data: b"foobar",
+            chunk,
+        };
+        b64::encode_pem_stream(&mut reader, "TEST", &mut streamed).unwrap();
+        assert_eq!(streamed, out, "reads of {}", chunk);
+    }
+}
+
+/// Encode at every wrap width, check the layout and that every path agrees,
+/// then decode each path's output back to `data`.
+fn check_round_trip(enc: Encoding, data: &[u8]) -> Result<(), TestCaseError> {
+    for wrap in WRAPS {
+        let encoded = encode_all(enc, data, wrap, true);
+        let (_, first) = &encoded[0];
+        prop_assert!(
+            check_layout(first, wrap).is_ok(),
+            "{:?}",
+            check_layout(first, wrap)
+        );
+        for (how, out) in &encoded {
+            prop_assert_eq!(out, first, "{} at wrap {}", how, wrap);
+        }
+        for (how, out) in decode_all(enc, first, true) {
+            prop_assert_eq!(&out, &data, "{} at wrap {}", how, wrap);
+        }
+    }
+    Ok(())
+}
+
+fn bytes() -> impl Strategy<Value = Vec<u8>> {
+    prop::collection::vec(any::<u8>(), 0..4096)
+}
+
+proptest! {
+    #[test]
+    fn round_trip_base64(data in bytes()) {
+        check_round_trip(Base64, &data)?;
+    }
+
+    #[test]
+    fn round_trip_pem(data in bytes()) {
+        // PEM always wraps at 64
+        let mut armored = Vec::new();
+        b64::encode_pem_to_writer(&data, "DATA", &mut armored).unwrap();
+        let text = String::from_utf8(armored.clone()).unwrap();
+        let body = text
+            .strip_prefix("-----BEGIN DATA-----\n")
+            .and_then(|t| t.strip_suffix("-----END DATA-----\n"))
+            .unwrap();
+        prop_assert!(check_layout(body.as_bytes(), b64::PEM_WRAP).is_ok());
+        let mut out = Vec::new();
+        b64::decode_pem_to_writer(&armored, Some("DATA"), false, &mut out).unwrap();
+        prop_assert_eq!(out, data);
+    }
+}
+
+proptest! {
+    // These spawn the binaries; fewer cases keep them to a few seconds
+    #![proptest_config(ProptestConfig::with_cases(24))]
+
+    #[test]
+    fn round_trip_base64url(data in bytes()) {
+        check_round_trip(Base64Url, &data)?;
+    }
+
+    #[test]
+    fn round_trip_base32(data in bytes()) {
+        check_round_trip(Base32, &data)?;
+    }
+
+    #[test]
+    fn round_trip_base32hex(data in bytes()) {
+        check_round_trip(Base32Hex, &data)?;
+    }
+
+    #[test]
+    fn round_trip_base16(data in bytes()) {
+        check_round_trip(Base16, &data)?;
+    }
+
+    #[test]
+    fn round_trip_base2msbf(data in bytes()) {
+        check_round_trip(Base2Msbf, &data)?;
+    }
+
+    #[test]
+    fn round_trip_base2lsbf(data in bytes()) {
+        check_round_trip(Base2Lsbf, &data)?;
+    }
+}
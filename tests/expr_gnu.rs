@@ -0,0 +1,127 @@
+//! Differential test of fexpr against GNU expr.
+//!
+//! Random small expressions are generated from the expr grammar (integers
+//! including negative and out-of-range ones, short strings with regex
+//! metacharacters, every binary operator, the keyword functions and
+//! parentheses, plus the odd stray token) and run through both tools with
+//! the tokens passed directly as argv. Stdout, whether anything was written
+//! to stderr, and the exit code must agree; proptest shrinks a divergent
+//! case and records its seed in tests/expr_gnu.proptest-regressions, which
+//! is checked in so fixed cases stay fixed.
+//!
+//! Ignored by default since it needs GNU expr:
+//!
+//!     cargo test --test expr_gnu -- --ignored
+
+use std::path::Path;
+use std::process::Command;
+
+use proptest::prelude::*;
+use proptest::test_runner::Config;
+
+const GNU_EXPR: &str = "/usr/bin/expr";
+
+/// Integer operands: small, signed, padded, and at or past the i64 limits.
+const INTEGERS: &[&str] = &[
+    "0",
+    "1",
+    "2",
+    "3",
+    "7",
+    "10",
+    "-1",
+    "-3",
+    "-10",
+    "00",
+    "-0",
+    "007",
+    "9223372036854775807",
+    "-9223372036854775808",
+    "9223372036854775808",
+    "-9223372036854775809",
+    "99999999999999999999",
+];
+
+/// String operands, several of them regex metacharacters or patterns.
+const STRINGS: &[&str] = &[
+    "", "a", "b", "abc", "ab", "A", "x y", ".", "*", "a*", "^a", "a$", "[ab]", "[", "\\(a\\)",
+    "\\(.*\\)", "\\(", "a\\{2\\}", "+1", " 1", "1a", "\\", "-",
+];
+
+/// Tokens that are only sometimes operands: operators and keywords out of
+/// place exercise the parser's error paths.
+const STRAYS: &[&str] = &[
+    "(", ")", "|", "&", "=", "<", "+", "-", "*", ":", "match", "length", "substr", "index",
+];
+
+const BINARY_OPS: &[&str] = &[
+    "|", "&", "<", "<=", "=", "!=", ">=", ">", "+", "-", "*", "/", "%", ":",
+];
+
+fn atom() -> impl Strategy<Value = Vec<String>> {
+    prop_oneof![
+        3 => prop::sample::select(INTEGERS),
+        3 => prop::sample::select(STRINGS),
+        1 => prop::sample::select(STRAYS),
+    ]
+    .prop_map(|t| vec![t.to_string()])
+}
+
+fn expression() -> impl Strategy<Value = Vec<String>> {
+    atom().prop_recursive(4, 24, 3, |inner| {
+        prop_oneof![
+            4 => (inner.clone(), prop::sample::select(BINARY_OPS), inner.clone())
+                .prop_map(|(l, op, r)| [l, vec![op.to_string()], r].concat()),
+            2 => inner.clone().prop_map(|e| ["(".to_string()]
+                .into_iter()
+                .chain(e)
+                .chain([")".to_string()])
+                .collect()),
+            1 => inner.clone().prop_map(|e| [vec!["length".to_string()], e].concat()),
+            1 => (inner.clone(), inner.clone())
+                .prop_map(|(a, b)| [vec!["match".to_string()], a, b].concat()),
+            1 => (inner.clone(), inner.clone())
+                .prop_map(|(a, b)| [vec!["index".to_string()], a, b].concat()),
+            1 => (inner.clone(), inner.clone(), inner.clone())
+                .prop_map(|(a, b, c)| [vec!["substr".to_string()], a, b, c].concat()),
+            1 => inner.clone().prop_map(|e| [vec!["+".to_string()], e].concat()),
+        ]
+    })
+}
+
+/// What a run is compared on.
+#[derive(Debug, PartialEq)]
+struct Outcome {
+    stdout: String,
+    stderr: bool,
+    code: Option<i32>,
+}
+
+/// Run `bin` and return its outcome.
+fn run(bin: &str, args: &[String]) -> Outcome {
+    let out = Command::new(bin)
+        .args(args)
+        .env("LC_ALL", "C")
+        .output()
+        .unwrap();
+    Outcome {
+        stdout: String::from_utf8_lossy(&out.stdout).into_owned(),
+        stderr: !out.stderr.is_empty(),
+        code: out.status.code(),
+    }
+}
+
+proptest! {
+    #![proptest_config(Config::with_cases(3000))]
+
+    #[test]
+    #[ignore = "needs GNU expr; run with --ignored"]
+    fn expr_matches_gnu(args in expression()) {
+        if !Path::new(GNU_EXPR).exists() {
+            return Ok(());
+        }
+        let gnu = run(GNU_EXPR, &args);
+        let ours = run(env!("CARGO_BIN_EXE_fexpr"), &args);
+        prop_assert_eq!(ours, gnu, "expr {:?}", args);
+    }
+}
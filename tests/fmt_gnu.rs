@@ -0,0 +1,158 @@
+//! Differential test of ffmt against GNU fmt.
+//!
+//! Every document under tests/fmt-corpus/ is formatted with both tools
+//! across a matrix of option sets. Divergences are written as a unified
+//! diff report to target/fmt-gnu-report.diff; the test fails only if a
+//! document listed in tests/fmt-corpus/must-match.txt diverges.
+//!
+//! Ignored by default since it needs GNU fmt:
+//!
+//!     cargo test --test fmt_gnu -- --ignored
+
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Option sets each document is formatted with.
+const OPTION_SETS: &[&[&str]] = &[
+    &[],
+    &["-w", "20"],
+    &["-w", "40"],
+    &["-w", "100"],
+    &["-w", "60", "-g", "50"],
+    &["-g", "60"],
+    &["-s"],
+    &["-s", "-w", "100"],
+    &["-u"],
+    &["-w", "50", "-u"],
+    &["-p", "> "],
+    &["-p", "> ", "-w", "40"],
+    &["-p", "#"],
+    &["-t"],
+    &["-t", "-w", "100"],
+    &["-c"],
+    &["-c", "-w", "40"],
+];
+
+fn corpus_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fmt-corpus")
+}
+
+/// The GNU fmt on PATH, if there is one.
+fn gnu_fmt() -> Option<&'static str> {
+    let out = Command::new("fmt").arg("--version").output().ok()?;
+    String::from_utf8_lossy(&out.stdout)
+        .contains("GNU coreutils")
+        .then_some("fmt")
+}
+
+/// Documents that must format identically: one file name per line,
+/// `#` starts a comment.
+fn must_match() -> Vec<String> {
+    std::fs::read_to_string(corpus_dir().join("must-match.txt"))
+        .unwrap()
+        .lines()
+        .map(|l| l.split('#').next().unwrap().trim())
+        .filter(|l| !l.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+fn documents() -> Vec<PathBuf> {
+    let mut docs: Vec<PathBuf> = std::fs::read_dir(corpus_dir())
+        .unwrap()
+        .map(|e| e.unwrap().path())
+        .filter(|p| p.extension().is_some_and(|e| e == "txt"))
+        .filter(|p| p.file_name().is_some_and(|n| n != "must-match.txt"))
+        .collect();
+    docs.sort();
+    docs
+}
+
+/// Run `bin` on `doc`, returning stdout and stderr together so a
+/// diagnostic difference shows up in the report too.
+fn format(bin: &str, args: &[&str], doc: &Path) -> Vec<u8> {
+    let out = Command::new(bin).args(args).arg(doc).output().unwrap();
+    let mut text = out.stdout;
+    if !out.stderr.is_empty() || !out.status.success() {
+        text.extend_from_slice(b"--- stderr ---\n");
+        text.extend_from_slice(&out.stderr);
+        text.extend_from_slice(format!("--- {} ---\n", out.status).as_bytes());
+    }
+    text
+}
+
+/// A unified diff of `gnu` against `ours`, via diff(1).
+fn unified_diff(label: &str, gnu: &[u8], ours: &[u8]) -> String {
+    let dir = tempfile::tempdir().unwrap();
+    let (a, b) = (dir.path().join("gnu"), dir.path().join("ffmt"));
+    std::fs::write(&a, gnu).unwrap();
+    std::fs::write(&b, ours).unwrap();
+    let out = Command::new("diff")
+        .arg("-u")
+        .arg(format!("--label=gnu fmt {}", label))
+        .arg(format!("--label=ffmt {}", label))
+        .arg(&a)
+        .arg(&b)
+        .output();
+    match out {
+        Ok(out) => String::from_utf8_lossy(&out.stdout).into_owned(),
+        Err(_) => format!(
+            "--- gnu fmt {0}\n+++ ffmt {0}\n(diff(1) unavailable; outputs differ)\n",
+            label
+        ),
+    }
+}
+
+#[test]
+#[ignore = "needs GNU fmt; run with --ignored"]
+fn fmt_matches_gnu() {
+    let Some(gnu) = gnu_fmt() else {
+        eprintln!("GNU fmt not found; skipping");
+        return;
+    };
+    let ours = env!("CARGO_BIN_EXE_ffmt");
+    let must_match = must_match();
+    let docs = documents();
+    for name in &must_match {
+        assert!(
+            docs.iter().any(|d| d.file_name().unwrap() == name.as_str()),
+            "must-match.txt names a missing document {}",
+            name
+        );
+    }
+
+    let mut report = String::new();
+    let mut failures = Vec::new();
+    let mut divergences = 0;
+    for doc in &docs {
+        let name = doc.file_name().unwrap().to_string_lossy().into_owned();
+        for args in OPTION_SETS {
+            let expected = format(gnu, args, doc);
+            let actual = format(ours, args, doc);
+            if expected == actual {
+                continue;
+            }
+            divergences += 1;
+            let label = format!("{} {}", args.join(" "), name);
+            report.push_str(&unified_diff(label.trim(), &expected, &actual));
+            if must_match.contains(&name) {
+                failures.push(label);
+            }
+        }
+    }
+
+    let report_path = Path::new(env!("CARGO_MANIFEST_DIR")).join("target/fmt-gnu-report.diff");
+    std::fs::write(&report_path, &report).unwrap();
+    eprintln!(
+        "{} of {} runs diverge from GNU fmt; report in {}",
+        divergences,
+        docs.len() * OPTION_SETS.len(),
+        report_path.display()
+    );
+    assert!(
+        failures.is_empty(),
+        "must-match documents diverge from GNU fmt:\n{}\n\n{}",
+        failures.join("\n"),
+        report
+    );
+}
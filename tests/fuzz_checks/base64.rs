@@ -0,0 +1,106 @@
+//! Properties checked by the base64 fuzz targets in fuzz/, and replayed on
+//! the inputs kept under tests/fuzz-regressions/ by the fuzz_regressions
+//! test. A violated property panics.
+
+use std::io::{self, Read};
+
+use coreutils_rs::base64::core as b64;
+
+/// A reader that hands out `data` in pieces of the given sizes, in turn.
+struct Chunked<'a> {
+    data: &'a [u8],
+    sizes: &'a [usize],
+    next: usize,
+}
+
+impl<'a> Chunked<'a> {
+    fn new(data: &'a [u8], sizes: &'a [usize]) -> Self {
+        Chunked {
+            data,
+            sizes,
+            next: 0,
+        }
+    }
+}
+
+impl Read for Chunked<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let size = self.sizes[self.next % self.sizes.len()];
+        self.next += 1;
+        let n = size.min(buf.len()).min(self.data.len());
+        buf[..n].copy_from_slice(&self.data[..n]);
+        self.data = &self.data[n..];
+        Ok(n)
+    }
+}
+
+/// `decode_to_writer` on arbitrary bytes, with and without
+/// --ignore-garbage, must not panic, and whatever it decodes must survive
+/// an encode/decode round trip.
+pub fn decode(data: &[u8]) {
+    for ignore_garbage in [false, true] {
+        let mut decoded = Vec::new();
+        if b64::decode_to_writer(data, ignore_garbage, &mut decoded).is_err() {
+            continue;
+        }
+        let mut encoded = Vec::new();
+        b64::encode_to_writer(&decoded, 76, false, &mut encoded).unwrap();
+        let mut again = Vec::new();
+        b64::decode_to_writer(&encoded, false, &mut again).unwrap();
+        assert_eq!(again, decoded, "ignore_garbage {}", ignore_garbage);
+    }
+}
+
+/// The first byte picks the wrap column (0 to 79, no wrapping at 0) and
+/// CRLF line breaks (its top bit), the next three the sizes the input is
+/// read in, and the rest is the payload. Fed in those pieces, the
+/// streaming encoder and decoder must produce what the one-shot functions
+/// do, errors included.
+pub fn stream(data: &[u8]) {
+    let Some((&[mode, a, b, c], payload)) = data.split_first_chunk::<4>() else {
+        return;
+    };
+    let wrap_col = usize::from(mode & 0x7f) % 80;
+    let crlf = mode & 0x80 != 0;
+    let sizes = [usize::from(a) + 1, usize::from(b) + 1, usize::from(c) + 1];
+
+    let mut whole = Vec::new();
+    b64::encode_to_writer(payload, wrap_col, crlf, &mut whole).unwrap();
+    let mut pieces = Vec::new();
+    b64::encode_stream(
+        &mut Chunked::new(payload, &sizes),
+        wrap_col,
+        crlf,
+        &mut pieces,
+    )
+    .unwrap();
+    assert_eq!(pieces, whole, "encode, wrap {} crlf {}", wrap_col, crlf);
+
+    let mut back = Vec::new();
+    b64::decode_stream(&mut Chunked::new(&whole, &sizes), false, &mut back).unwrap();
+    assert_eq!(back, payload, "round trip, wrap {} crlf {}", wrap_col, crlf);
+
+    for ignore_garbage in [false, true] {
+        let mut whole = Vec::new();
+        let one_shot = b64::decode_to_writer(payload, ignore_garbage, &mut whole);
+        let mut pieces = Vec::new();
+        let streamed = b64::decode_stream(
+            &mut Chunked::new(payload, &sizes),
+            ignore_garbage,
+            &mut pieces,
+        );
+        assert_eq!(
+            streamed.is_ok(),
+            one_shot.is_ok(),
+            "decode result, ignore_garbage {}: {:?} vs {:?}",
+            ignore_garbage,
+            streamed,
+            one_shot
+        );
+        assert_eq!(
+            pieces, whole,
+            "decode output, ignore_garbage {}",
+            ignore_garbage
+        );
+    }
+}
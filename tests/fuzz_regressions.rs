@@ -0,0 +1,38 @@
+//! Replays the inputs under tests/fuzz-regressions/ through the checks the
+//! fuzz targets in fuzz/ run, one directory per target. Drop a crashing
+//! input found with `cargo fuzz run <target>` into its target's directory
+//! to keep it fixed.
+
+use std::fs;
+use std::path::Path;
+
+#[path = "fuzz_checks/base64.rs"]
+mod base64_checks;
+
+/// Run `check` on every file in tests/fuzz-regressions/`target`.
+fn replay(target: &str, check: fn(&[u8])) {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fuzz-regressions")
+        .join(target);
+    let mut inputs: Vec<_> = fs::read_dir(&dir)
+        .unwrap_or_else(|e| panic!("{}: {}", dir.display(), e))
+        .map(|entry| entry.unwrap().path())
+        .collect();
+    inputs.sort();
+    assert!(!inputs.is_empty(), "no inputs in {}", dir.display());
+    for path in inputs {
+        let data = fs::read(&path).unwrap();
+        eprintln!("{}", path.display());
+        check(&data);
+    }
+}
+
+#[test]
+fn base64_decode() {
+    replay("base64_decode", base64_checks::decode);
+}
+
+#[test]
+fn base64_stream() {
+    replay("base64_stream", base64_checks::stream);
+}
@@ -0,0 +1,538 @@
+//! Differential test of the binaries against GNU coreutils over one shared
+//! table of cases.
+//!
+//! Each case names a tool, its arguments and its standard input, and runs
+//! in a scratch directory holding the fixtures from `write_fixtures`. Our
+//! `f<tool>` and GNU's `<tool>` must agree on stdout bytes, on whether
+//! anything was written to stderr (the text too, for cases marked
+//! `exact_stderr`) and on the exit status. A case whose divergence is
+//! known and accepted says why in its `allow` note; it is still run, and
+//! reported, but does not fail the test.
+//!
+//! GNU tools are looked up in $GNU_TOOLS_DIR if set, else on PATH, and a
+//! tool that is missing or not GNU's skips its cases. Ignored by default
+//! since it needs GNU coreutils:
+//!
+//!     cargo test --test gnu_compat -- --ignored
+//!
+//! Set GNU_COMPAT_CASE to run only the cases whose name contains it.
+//! New features should add their cases to `CASES`.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output, Stdio};
+
+use coreutils_rs::hash::{HashAlgorithm, hash_bytes};
+
+struct Case {
+    name: &'static str,
+    tool: &'static str,
+    args: &'static [&'static str],
+    stdin: &'static [u8],
+    /// Compare the stderr text, not just whether there is any.
+    exact_stderr: bool,
+    /// Why a divergence from GNU is accepted.
+    allow: Option<&'static str>,
+}
+
+const fn case(
+    name: &'static str,
+    tool: &'static str,
+    args: &'static [&'static str],
+    stdin: &'static [u8],
+) -> Case {
+    Case {
+        name,
+        tool,
+        args,
+        stdin,
+        exact_stderr: false,
+        allow: None,
+    }
+}
+
+impl Case {
+    const fn exact_stderr(self) -> Self {
+        Case {
+            exact_stderr: true,
+            ..self
+        }
+    }
+
+    const fn allow(self, reason: &'static str) -> Self {
+        Case {
+            allow: Some(reason),
+            ..self
+        }
+    }
+}
+
+const PARAGRAPH: &[u8] = b"The quick brown fox jumps over the lazy dog.  It was not\n\
+    amused.   Neither was the dog, which had been asleep\n\
+    until then.\n\nA second paragraph, short.\n";
+
+const CASES: &[Case] = &[
+    // cksum
+    case("cksum-stdin", "cksum", &[], b"hello\n"),
+    case("cksum-file", "cksum", &["lines.txt"], b""),
+    case("cksum-two-files", "cksum", &["lines.txt", "a.txt"], b""),
+    case("cksum-raw", "cksum", &["--raw", "lines.txt"], b"")
+        .allow("--raw arrived in GNU 9.2; older releases reject it"),
+    case("cksum-md5", "cksum", &["-a", "md5", "lines.txt"], b""),
+    case(
+        "cksum-sha256-untagged",
+        "cksum",
+        &["-a", "sha256", "--untagged", "lines.txt"],
+        b"",
+    ),
+    case(
+        "cksum-sha1-base64",
+        "cksum",
+        &["-a", "sha1", "--base64", "lines.txt"],
+        b"",
+    )
+    .allow("--base64 arrived in GNU 9.2; older releases reject it"),
+    case(
+        "cksum-blake2b",
+        "cksum",
+        &["-a", "blake2b", "lines.txt"],
+        b"",
+    ),
+    case(
+        "cksum-blake2b-length",
+        "cksum",
+        &["-a", "blake2b", "-l", "128", "lines.txt"],
+        b"",
+    ),
+    case(
+        "cksum-length-not-multiple",
+        "cksum",
+        &["-a", "blake2b", "-l", "127"],
+        b"",
+    )
+    .exact_stderr(),
+    case(
+        "cksum-length-needs-blake2b",
+        "cksum",
+        &["-a", "md5", "-l", "128"],
+        b"",
+    )
+    .exact_stderr(),
+    case("cksum-sha512-stdin", "cksum", &["-a", "sha512"], b"hello\n"),
+    case("cksum-check", "cksum", &["-c", "SHA256SUMS"], b""),
+    case(
+        "cksum-check-quiet",
+        "cksum",
+        &["-c", "--quiet", "SHA256SUMS"],
+        b"",
+    ),
+    case("cksum-check-mismatch", "cksum", &["-c", "BADSUMS"], b"").exact_stderr(),
+    case("cksum-missing-file", "cksum", &["nope"], b"").exact_stderr(),
+    case("cksum-bad-algorithm", "cksum", &["-a", "nope"], b""),
+    // tac
+    case("tac-stdin", "tac", &[], b"a\nb\nc\n"),
+    case("tac-no-trailing-newline", "tac", &[], b"a\nb\nc"),
+    case("tac-before", "tac", &["-b"], b"a\nb\nc\n"),
+    case("tac-separator", "tac", &["-s", ","], b"a,b,c,"),
+    case("tac-separator-before", "tac", &["-b", "-s", ","], b",a,b,c"),
+    case("tac-string-separator", "tac", &["-s", "::"], b"a::b::c"),
+    case(
+        "tac-overlapping-separator",
+        "tac",
+        &["-b", "-s", "bb"],
+        b"bbbaaaaabb",
+    ),
+    case("tac-regex", "tac", &["-r", "-s", "[0-9]+"], b"a1b22c333d"),
+    case("tac-file", "tac", &["lines.txt"], b""),
+    case("tac-missing-file", "tac", &["nope"], b"")
+        .exact_stderr()
+        .allow("we report open errors as 'tac: FILE: ERROR' like the other tools"),
+    // cut
+    case(
+        "cut-field",
+        "cut",
+        &["-d", ",", "-f", "2"],
+        b"a,b,c\n1,2,3\n",
+    ),
+    case(
+        "cut-field-ranges",
+        "cut",
+        &["-d", ",", "-f", "1,3-"],
+        b"a,b,c,d\n1,2\n",
+    ),
+    case(
+        "cut-complement",
+        "cut",
+        &["-d", ",", "--complement", "-f", "2"],
+        b"a,b,c\n1,2,3\n",
+    ),
+    case(
+        "cut-only-delimited",
+        "cut",
+        &["-s", "-d", ",", "-f", "1"],
+        b"a,b\nplain\nc,d\n",
+    ),
+    case("cut-bytes", "cut", &["-b", "1-3"], b"abcdef\nxy\n"),
+    case("cut-chars", "cut", &["-c", "2,4"], b"abcdef\n"),
+    case(
+        "cut-output-delimiter",
+        "cut",
+        &["-d", ",", "-f", "1,3", "--output-delimiter=|"],
+        b"a,b,c\n",
+    ),
+    case(
+        "cut-zero-terminated",
+        "cut",
+        &["-z", "-d", ",", "-f", "2"],
+        b"a,b\0c,d\0",
+    ),
+    case("cut-tab-default", "cut", &["-f", "2"], b"a\tb\tc\nx\n"),
+    case("cut-no-list", "cut", &[], b""),
+    case("cut-zero-field", "cut", &["-f", "0"], b"").exact_stderr(),
+    // comm
+    case("comm-default", "comm", &["a.txt", "b.txt"], b""),
+    case("comm-12", "comm", &["-12", "a.txt", "b.txt"], b""),
+    case("comm-3", "comm", &["-3", "a.txt", "b.txt"], b""),
+    case(
+        "comm-output-delimiter",
+        "comm",
+        &["--output-delimiter=|", "a.txt", "b.txt"],
+        b"",
+    ),
+    case("comm-total", "comm", &["--total", "a.txt", "b.txt"], b""),
+    case(
+        "comm-stdin",
+        "comm",
+        &["-", "b.txt"],
+        b"apple\ncherry\ndate\n",
+    ),
+    case(
+        "comm-check-order",
+        "comm",
+        &["--check-order", "unsorted.txt", "b.txt"],
+        b"",
+    )
+    .exact_stderr(),
+    case(
+        "comm-zero-terminated",
+        "comm",
+        &["-z", "a0.txt", "b0.txt"],
+        b"",
+    ),
+    // base64
+    case("base64-encode", "base64", &[], b"hello world\n"),
+    case("base64-wrap", "base64", &["-w", "4"], b"hello world\n"),
+    case("base64-no-wrap", "base64", &["-w", "0"], PARAGRAPH),
+    case("base64-file", "base64", &["lines.txt"], b""),
+    case("base64-decode", "base64", &["-d"], b"aGVsbG8gd29ybGQK\n"),
+    case(
+        "base64-decode-ignore-garbage",
+        "base64",
+        &["-d", "-i"],
+        b"aGVs*bG8g\nd29y!bGQK",
+    ),
+    case("base64-decode-invalid", "base64", &["-d"], b"aGVsbG8!").exact_stderr(),
+    // readlink
+    case("readlink-plain", "readlink", &["link"], b""),
+    case("readlink-not-a-link", "readlink", &["lines.txt"], b""),
+    case("readlink-f", "readlink", &["-f", "dir/../link"], b""),
+    case("readlink-e-dangling", "readlink", &["-e", "dangling"], b""),
+    // -f lets only the last component be missing; -e needs all of them
+    case(
+        "readlink-f-missing-last",
+        "readlink",
+        &["-v", "-f", "dir/missing"],
+        b"",
+    ),
+    case(
+        "readlink-e-missing-last",
+        "readlink",
+        &["-v", "-e", "dir/missing"],
+        b"",
+    )
+    .exact_stderr(),
+    case(
+        "readlink-f-missing-middle",
+        "readlink",
+        &["-v", "-f", "nodir/x"],
+        b"",
+    )
+    .exact_stderr(),
+    case(
+        "readlink-e-missing-middle",
+        "readlink",
+        &["-v", "-e", "nodir/x"],
+        b"",
+    )
+    .exact_stderr(),
+    case(
+        "readlink-f-dangling",
+        "readlink",
+        &["-v", "-f", "dangling"],
+        b"",
+    ),
+    case(
+        "readlink-e-dangling-verbose",
+        "readlink",
+        &["-v", "-e", "dangling"],
+        b"",
+    )
+    .exact_stderr(),
+    case("readlink-f-link", "readlink", &["-f", "link"], b""),
+    case("readlink-e-link", "readlink", &["-e", "link"], b""),
+    case("readlink-m", "readlink", &["-m", "missing/x/../y"], b""),
+    case("readlink-n", "readlink", &["-n", "link"], b""),
+    case("readlink-z", "readlink", &["-z", "link", "dangling"], b""),
+    case("readlink-verbose", "readlink", &["-v", "lines.txt"], b"").exact_stderr(),
+    // fmt
+    case("fmt-default", "fmt", &[], PARAGRAPH),
+    case("fmt-width", "fmt", &["-w", "20"], PARAGRAPH),
+    case("fmt-uniform", "fmt", &["-u"], PARAGRAPH),
+    case("fmt-split-only", "fmt", &["-s", "-w", "10"], PARAGRAPH),
+    case(
+        "fmt-prefix",
+        "fmt",
+        &["-p", "> ", "-w", "30"],
+        b"> quoted text that runs on\n> for a while longer\nplain\n",
+    ),
+    case(
+        "fmt-tagged",
+        "fmt",
+        &["-t", "-w", "30"],
+        b"  first line of a tagged\nparagraph goes on and on here\n",
+    ),
+    case(
+        "fmt-crown",
+        "fmt",
+        &["-c", "-w", "30"],
+        b"  crown line\n    body text that continues\n    a bit\n",
+    ),
+    // expr
+    case("expr-add", "expr", &["1", "+", "2"], b""),
+    case("expr-precedence", "expr", &["2", "+", "3", "*", "4"], b""),
+    case(
+        "expr-parens",
+        "expr",
+        &["(", "1", "+", "2", ")", "*", "3"],
+        b"",
+    ),
+    case("expr-divide-by-zero", "expr", &["1", "/", "0"], b"").exact_stderr(),
+    case("expr-compare-strings", "expr", &["abc", "<", "abd"], b""),
+    case("expr-match", "expr", &["abc", ":", "a\\(.\\)"], b""),
+    case("expr-length", "expr", &["length", "hello"], b""),
+    case("expr-substr", "expr", &["substr", "hello", "2", "3"], b""),
+    case(
+        "expr-substr-huge-length",
+        "expr",
+        &["substr", "abc", "2", "99999999999999999999"],
+        b"",
+    ),
+    case(
+        "expr-substr-past-end",
+        "expr",
+        &["substr", "abc", "9", "1"],
+        b"",
+    ),
+    case("expr-index", "expr", &["index", "hello", "l"], b""),
+    case("expr-index-empty-string", "expr", &["index", "", "a"], b""),
+    case("expr-zero-result", "expr", &["0", "+", "0"], b""),
+    case("expr-missing-operand", "expr", &["1", "+"], b"").exact_stderr(),
+    // echo
+    case("echo-plain", "echo", &["hello", "world"], b""),
+    case("echo-n", "echo", &["-n", "x"], b""),
+    case("echo-e", "echo", &["-e", "a\\tb\\n"], b""),
+    case("echo-E", "echo", &["-E", "a\\tb"], b""),
+    case("echo-stop-output", "echo", &["-e", "abc\\cdef"], b""),
+    case("echo-octal", "echo", &["-e", "\\0101"], b""),
+    case("echo-hex", "echo", &["-e", "\\x41"], b""),
+    case("echo-double-dash", "echo", &["--", "x"], b""),
+    case("echo-unknown-option", "echo", &["-x", "y"], b""),
+];
+
+/// Create the files the cases refer to in `dir`.
+fn write_fixtures(dir: &Path) {
+    let lines = b"one\ntwo\nthree\n";
+    let files: &[(&str, &[u8])] = &[
+        ("lines.txt", lines),
+        ("a.txt", b"apple\ncherry\ndate\n"),
+        ("b.txt", b"banana\ncherry\nelderberry\n"),
+        ("unsorted.txt", b"pear\napple\n"),
+        ("a0.txt", b"apple\0cherry\0"),
+        ("b0.txt", b"banana\0cherry\0"),
+    ];
+    for (name, data) in files {
+        std::fs::write(dir.join(name), data).unwrap();
+    }
+    let digest = hash_bytes(HashAlgorithm::Sha256, lines);
+    std::fs::write(
+        dir.join("SHA256SUMS"),
+        format!("SHA256 (lines.txt) = {}\n", digest),
+    )
+    .unwrap();
+    std::fs::write(
+        dir.join("BADSUMS"),
+        format!("{}  lines.txt\n{}  a.txt\n", digest, digest),
+    )
+    .unwrap();
+    std::fs::create_dir(dir.join("dir")).unwrap();
+    #[cfg(unix)]
+    {
+        std::os::unix::fs::symlink("lines.txt", dir.join("link")).unwrap();
+        std::os::unix::fs::symlink("nowhere", dir.join("dangling")).unwrap();
+    }
+}
+
+/// Our build of `tool`, next to this test's own executable.
+fn our_binary(tool: &str) -> PathBuf {
+    let mut path = std::env::current_exe().unwrap();
+    path.pop();
+    if path.ends_with("deps") {
+        path.pop();
+    }
+    path.join(format!("f{}{}", tool, std::env::consts::EXE_SUFFIX))
+}
+
+/// GNU's `tool` from $GNU_TOOLS_DIR or PATH, if it answers --version as
+/// part of GNU coreutils.
+fn gnu_binary(tool: &str) -> Option<PathBuf> {
+    let path = match std::env::var_os("GNU_TOOLS_DIR") {
+        Some(dir) => Path::new(&dir).join(tool),
+        None => PathBuf::from(tool),
+    };
+    let out = Command::new(&path).arg("--version").output().ok()?;
+    String::from_utf8_lossy(&out.stdout)
+        .contains("GNU coreutils")
+        .then_some(path)
+}
+
+fn run(bin: &Path, case: &Case, dir: &Path) -> Output {
+    let mut child = Command::new(bin)
+        .args(case.args)
+        .current_dir(dir)
+        .env("LC_ALL", "C")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap_or_else(|e| panic!("{}: cannot run {}: {}", case.name, bin.display(), e));
+    let mut stdin = child.stdin.take().unwrap();
+    let input = case.stdin;
+    let writer = std::thread::spawn(move || {
+        use std::io::Write;
+        let _ = stdin.write_all(input);
+    });
+    let out = child.wait_with_output().unwrap();
+    writer.join().unwrap();
+    out
+}
+
+/// The differences between GNU's and our run of `case`, as a readable
+/// report, or None if they agree.
+fn compare(case: &Case, gnu: &Output, ours: &Output) -> Option<String> {
+    let mut report = String::new();
+    if gnu.status.code() != ours.status.code() {
+        report.push_str(&format!(
+            "  exit status: gnu {:?}, ours {:?}\n",
+            gnu.status.code(),
+            ours.status.code()
+        ));
+    }
+    if gnu.stdout != ours.stdout {
+        report.push_str(&unified_diff("stdout", &gnu.stdout, &ours.stdout));
+    }
+    if case.exact_stderr {
+        if gnu.stderr != ours.stderr {
+            report.push_str(&unified_diff("stderr", &gnu.stderr, &ours.stderr));
+        }
+    } else if gnu.stderr.is_empty() != ours.stderr.is_empty() {
+        report.push_str(&format!(
+            "  stderr: gnu {:?}, ours {:?}\n",
+            String::from_utf8_lossy(&gnu.stderr),
+            String::from_utf8_lossy(&ours.stderr)
+        ));
+    }
+    (!report.is_empty()).then_some(report)
+}
+
+/// A line diff of two outputs, with bytes outside printable ASCII escaped
+/// so that NULs and missing final newlines show.
+fn unified_diff(label: &str, gnu: &[u8], ours: &[u8]) -> String {
+    let escape = |data: &[u8]| -> Vec<String> {
+        data.split_inclusive(|&b| b == b'\n')
+            .map(|line| line.escape_ascii().to_string())
+            .collect()
+    };
+    let (gnu, ours) = (escape(gnu), escape(ours));
+    let common = gnu.iter().zip(&ours).take_while(|(a, b)| a == b).count();
+    let mut out = format!("  --- gnu {0}\n  +++ ours {0}\n", label);
+    for line in &gnu[common..] {
+        out.push_str(&format!("  -{}\n", line));
+    }
+    for line in &ours[common..] {
+        out.push_str(&format!("  +{}\n", line));
+    }
+    out
+}
+
+#[test]
+fn cases_are_well_formed() {
+    let mut seen = std::collections::HashSet::new();
+    for case in CASES {
+        assert!(seen.insert(case.name), "duplicate case {}", case.name);
+        assert!(
+            case.name.starts_with(&format!("{}-", case.tool)),
+            "case {} should be named after its tool",
+            case.name
+        );
+        assert!(our_binary(case.tool).exists(), "no f{} binary", case.tool);
+    }
+}
+
+#[test]
+#[ignore = "needs GNU coreutils; run with --ignored"]
+fn binaries_match_gnu() {
+    let filter = std::env::var("GNU_COMPAT_CASE").ok();
+    let dir = tempfile::tempdir().unwrap();
+    write_fixtures(dir.path());
+
+    let mut gnu_tools: HashMap<&str, Option<PathBuf>> = HashMap::new();
+    let (mut ran, mut skipped) = (0, 0);
+    let mut failures = Vec::new();
+    for case in CASES {
+        if filter.as_deref().is_some_and(|f| !case.name.contains(f)) {
+            continue;
+        }
+        let Some(gnu) = gnu_tools
+            .entry(case.tool)
+            .or_insert_with(|| gnu_binary(case.tool))
+        else {
+            skipped += 1;
+            continue;
+        };
+        ran += 1;
+        let expected = run(gnu, case, dir.path());
+        let actual = run(&our_binary(case.tool), case, dir.path());
+        let Some(report) = compare(case, &expected, &actual) else {
+            continue;
+        };
+        match case.allow {
+            Some(reason) => eprintln!("{}: allowed divergence ({})\n{}", case.name, reason, report),
+            None => failures.push(format!(
+                "{} ({} {:?}):\n{}",
+                case.name, case.tool, case.args, report
+            )),
+        }
+    }
+
+    eprintln!(
+        "{} cases run, {} skipped without the GNU tool",
+        ran, skipped
+    );
+    assert!(
+        failures.is_empty(),
+        "{} of {} cases diverge from GNU:\n\n{}",
+        failures.len(),
+        ran,
+        failures.join("\n")
+    );
+}